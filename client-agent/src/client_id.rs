@@ -17,3 +17,19 @@ pub fn generate_or_load_client_id(config: &mut crate::config::Config) -> String
 
     id
 }
+
+/// Token required by the local server, generated once per client
+pub fn generate_or_load_bridge_token(config: &mut crate::config::Config) -> String {
+    if !config.client.bridge_token.is_empty() {
+        return config.client.bridge_token.clone();
+    }
+
+    let token = Uuid::new_v4().simple().to_string();
+    config.client.bridge_token = token.clone();
+
+    if let Err(e) = config.save() {
+        eprintln!("Warning: Failed to save bridge token: {}", e);
+    }
+
+    token
+}
@@ -14,13 +14,29 @@ pub struct Config {
 pub struct ClientConfig {
     pub id: String,
     pub name: String,
+    /// Secret the web UI must send to the local server on localhost
+    #[serde(default)]
+    pub bridge_token: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerConfig {
+    /// URL in use (the active profile's URL when one is selected)
     pub url: String,
     pub enabled: bool,
     pub poll_interval_secs: u64,
+    /// Saved servers to switch between in Settings
+    #[serde(default)]
+    pub profiles: Vec<ServerProfile>,
+    /// Name of the selected profile; empty when using an unsaved URL
+    #[serde(default)]
+    pub active_profile: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerProfile {
+    pub name: String,
+    pub url: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -60,11 +76,14 @@ impl Default for Config {
                     .ok()
                     .and_then(|h| h.into_string().ok())
                     .unwrap_or_else(|| "Unknown PC".to_string()),
+                bridge_token: String::new(),
             },
             server: ServerConfig {
                 url: "http://homelab:3030".to_string(),
                 enabled: true,
                 poll_interval_secs: 30,
+                profiles: Vec::new(),
+                active_profile: String::new(),
             },
             realdebrid: RealDebridConfig {
                 api_key: String::new(),
@@ -0,0 +1,89 @@
+//! `fitgirl://download/<game_id>` links from the web UI. Windows launches a
+//! new client process with the link as its argument; that process hands the
+//! link to the instance already running (via the local server) and exits.
+
+use log::{info, warn};
+use std::time::Duration;
+
+/// URL scheme registered for the client
+pub const SCHEME: &str = "fitgirl";
+
+/// Game ID from a `fitgirl://download/<game_id>` link
+pub fn parse(url: &str) -> Option<i64> {
+    let rest = url.strip_prefix(SCHEME)?.strip_prefix("://")?;
+    let id = rest.strip_prefix("download/")?.trim_end_matches('/');
+    id.parse().ok()
+}
+
+/// Deep link passed on the command line, if any
+pub fn from_args() -> Option<String> {
+    let prefix = format!("{}://", SCHEME);
+    std::env::args().skip(1).find(|arg| arg.starts_with(&prefix))
+}
+
+/// Pass a deep link to an already-running client.
+/// Returns false when no client is listening, so this process should start up instead.
+pub async fn forward_to_running_instance(url: &str, bridge_token: &str) -> bool {
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(3))
+        .build()
+    {
+        Ok(client) => client,
+        Err(_) => return false,
+    };
+
+    let result = client
+        .post(format!("http://127.0.0.1:{}/deeplink", crate::local_server::LOCAL_PORT))
+        .header(crate::local_server::TOKEN_HEADER, bridge_token)
+        .json(&serde_json::json!({ "url": url }))
+        .send()
+        .await;
+
+    match result {
+        Ok(response) if response.status().is_success() => {
+            info!("Handed {} to the running client", url);
+            true
+        }
+        Ok(response) => {
+            warn!("Running client rejected {}: {}", url, response.status());
+            false
+        }
+        Err(_) => false,
+    }
+}
+
+/// Register the `fitgirl://` scheme for the current user, pointing at this exe.
+/// Written to HKCU\Software\Classes, which Windows merges into HKCR without
+/// needing admin rights. Re-run on every start so a moved exe stays registered.
+#[cfg(windows)]
+pub fn register_protocol_handler() {
+    use winreg::enums::HKEY_CURRENT_USER;
+    use winreg::RegKey;
+
+    let exe_path = match std::env::current_exe() {
+        Ok(path) => path,
+        Err(e) => {
+            warn!("Failed to register {}:// handler: {}", SCHEME, e);
+            return;
+        }
+    };
+    let command = format!("\"{}\" \"%1\"", exe_path.to_string_lossy());
+
+    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+    let result = (|| -> std::io::Result<()> {
+        let (key, _) = hkcu.create_subkey(format!("Software\\Classes\\{}", SCHEME))?;
+        key.set_value("", &"URL:Repack Auto-Installer")?;
+        key.set_value("URL Protocol", &"")?;
+        let (open, _) = key.create_subkey("shell\\open\\command")?;
+        open.set_value("", &command)?;
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => info!("Registered {}:// protocol handler", SCHEME),
+        Err(e) => warn!("Failed to register {}:// handler: {}", SCHEME, e),
+    }
+}
+
+#[cfg(not(windows))]
+pub fn register_protocol_handler() {}
@@ -0,0 +1,118 @@
+//! Self-test run from the Settings window, so users can tell which part of the
+//! download → extract pipeline is broken and paste the report into a bug report.
+
+use crate::config::Config;
+use std::time::Duration;
+
+/// Free space below which the disk check fails
+const MIN_FREE_GB: f64 = 20.0;
+
+pub struct CheckResult {
+    pub name: &'static str,
+    pub ok: bool,
+    pub detail: String,
+}
+
+impl CheckResult {
+    fn new(name: &'static str, result: Result<String, String>) -> Self {
+        match result {
+            Ok(detail) => Self { name, ok: true, detail },
+            Err(detail) => Self { name, ok: false, detail },
+        }
+    }
+}
+
+/// Run every check against the current configuration
+pub async fn run(config: &Config) -> Vec<CheckResult> {
+    vec![
+        CheckResult::new("Server", check_server(&config.server.url).await),
+        CheckResult::new("Real-Debrid key", check_rd_key(&config.realdebrid.api_key).await),
+        CheckResult::new("Output folder", check_output_dir(&config.extraction.output_dir.to_string_lossy())),
+        CheckResult::new("Disk space", check_disk_space(&config.extraction.output_dir.to_string_lossy())),
+        CheckResult::new("7-Zip (RAR)", check_7zip().await),
+    ]
+}
+
+/// Plain-text report, one line per check
+pub fn format_report(results: &[CheckResult]) -> String {
+    let mut report = format!(
+        "Repack Auto-Installer {} diagnostics ({})\n",
+        env!("CARGO_PKG_VERSION"),
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
+    );
+    for result in results {
+        report.push_str(&format!(
+            "[{}] {}: {}\n",
+            if result.ok { "OK" } else { "FAIL" },
+            result.name,
+            result.detail
+        ));
+    }
+    report
+}
+
+async fn check_server(server_url: &str) -> Result<String, String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let url = format!("{}/api/health", server_url.trim_end_matches('/'));
+    let response = client.get(&url).send().await
+        .map_err(|e| format!("{} unreachable: {}", url, e))?;
+
+    if response.status().is_success() {
+        Ok(format!("{} is reachable", server_url))
+    } else {
+        Err(format!("{} returned {}", url, response.status()))
+    }
+}
+
+async fn check_rd_key(api_key: &str) -> Result<String, String> {
+    if api_key.is_empty() {
+        return Err("No API key set (a linked client uses the server's key)".to_string());
+    }
+
+    crate::realdebrid::RealDebridClient::new(api_key.to_string())
+        .check_api_key()
+        .await
+        .map(|account| format!("Valid key for {}", account))
+        .map_err(|e| e.to_string())
+}
+
+fn check_output_dir(output_dir: &str) -> Result<String, String> {
+    crate::system_info::check_writable_dir(std::path::Path::new(output_dir))?;
+    Ok(format!("{} is writable", output_dir))
+}
+
+fn check_disk_space(output_dir: &str) -> Result<String, String> {
+    let free_gb = crate::system_info::get_disk_space_gb(output_dir);
+    if free_gb <= 0.0 {
+        return Err(format!("Could not read free space for {}", output_dir));
+    }
+    if free_gb < MIN_FREE_GB {
+        return Err(format!("Only {:.1} GB free (repacks often need {:.0} GB or more)", free_gb, MIN_FREE_GB));
+    }
+    Ok(format!("{:.1} GB free", free_gb))
+}
+
+/// ZIP and 7z are extracted in-process; RAR needs an installed 7-Zip
+async fn check_7zip() -> Result<String, String> {
+    let exe = crate::extractor::find_7zip();
+    let output = tokio::process::Command::new(exe)
+        .arg("i")
+        .output()
+        .await
+        .map_err(|_| "7-Zip not found. Install it from https://www.7-zip.org/ to extract RAR archives".to_string())?;
+
+    if !output.status.success() {
+        return Err(format!("{} exited with {}", exe, output.status));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version = stdout.lines()
+        .map(str::trim)
+        .find(|l| l.starts_with("7-Zip"))
+        .unwrap_or("7-Zip");
+    Ok(format!("{} at {}", version, exe))
+}
@@ -5,11 +5,14 @@
 /// 4. Install game
 /// 5. Report progress at each step
 
+use crate::download_store::DownloadStore;
 use crate::downloader::Downloader;
-use crate::server_client::{ProgressUpdate, ServerClient};
+use crate::notifications::show_notification;
+use crate::server_client::{ClientCommand, InstalledReport, ProgressUpdate, ServerClient};
 use log::{error, info, warn};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use tokio::sync::{Mutex, Notify, RwLock};
 use tokio::time::{self, Duration};
 
 /// URL-decode and sanitize filename for Windows
@@ -31,24 +34,73 @@ fn sanitize_filename(filename: &str) -> String {
         .to_string()
 }
 
+/// Local filename for the `idx`th URL of a download
+fn download_filename(url: &str, idx: usize) -> String {
+    let default_name = format!("file_{}.bin", idx);
+    let filename = url.split('/').last()
+        .unwrap_or(&default_name)
+        .split('?').next()
+        .unwrap_or(&default_name);
+
+    // Sanitize filename for Windows (remove invalid characters)
+    sanitize_filename(filename)
+}
+
 pub async fn poll_and_process_downloads(
     server_client: Arc<ServerClient>,
     client_id: &str,
     output_dir: &Path,
     poll_interval_secs: u64,
+    is_paused: Arc<RwLock<bool>>,
+    poll_now: Arc<Notify>,
 ) {
     let mut interval = time::interval(Duration::from_secs(poll_interval_secs));
     let downloader = Arc::new(Downloader::new());
+    let store = Arc::new(Mutex::new(DownloadStore::load()));
+    let current_download: Arc<RwLock<Option<i64>>> = Arc::new(RwLock::new(None));
+
+    // Watch for commands from the web UI while downloads are processed
+    let watcher = tokio::spawn(watch_commands(
+        server_client.clone(),
+        client_id.to_string(),
+        downloader.clone(),
+        store.clone(),
+        current_download.clone(),
+    ));
+    // Stop the watcher if this loop is ever dropped (e.g. settings changed)
+    let _watcher_guard = AbortOnDrop(watcher);
 
     loop {
-        interval.tick().await;
+        // Poll on the interval, or straight away when a deep link asks for it
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = poll_now.notified() => {}
+        }
+
+        // Skip if paused
+        if *is_paused.read().await {
+            continue;
+        }
 
         // Poll server for pending downloads
         match server_client.get_download_queue(client_id).await {
             Ok(queue) => {
                 for download in queue {
-                    if download.status != "pending" {
-                        continue;  // Skip non-pending downloads
+                    // Paused while working through the queue; the rest stay pending
+                    if *is_paused.read().await {
+                        info!("Paused, leaving remaining downloads queued");
+                        break;
+                    }
+
+                    // Pick up pending downloads, plus ones this agent was working on
+                    // when it stopped (the server still shows them in progress)
+                    let resuming = matches!(download.status.as_str(), "downloading" | "extracting")
+                        && store.lock().await.is_tracked(download.id);
+                    if download.status != "pending" && !resuming {
+                        continue;
+                    }
+                    if resuming {
+                        info!("Resuming interrupted download: {} (ID: {})", download.game_title, download.id);
                     }
 
                     if download.direct_urls.is_empty() {
@@ -59,12 +111,19 @@ pub async fn poll_and_process_downloads(
                     info!("Processing download: {} (ID: {})", download.game_title, download.id);
 
                     // Process this download
-                    if let Err(e) = process_single_download(
+                    downloader.reset_cancel();
+                    *current_download.write().await = Some(download.id);
+                    let result = process_single_download(
                         &server_client,
                         &downloader,
+                        &store,
+                        client_id,
                         download,
                         output_dir,
-                    ).await {
+                    ).await;
+                    *current_download.write().await = None;
+
+                    if let Err(e) = result {
                         error!("Failed to process download: {}", e);
                     }
                 }
@@ -76,14 +135,88 @@ pub async fn poll_and_process_downloads(
     }
 }
 
+/// Aborts a spawned task when dropped
+struct AbortOnDrop(tokio::task::JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Poll the server for commands and act on the ones for downloads this agent
+/// has. Commands for downloads it doesn't know are left for other clients.
+async fn watch_commands(
+    server_client: Arc<ServerClient>,
+    client_id: String,
+    downloader: Arc<Downloader>,
+    store: Arc<Mutex<DownloadStore>>,
+    current_download: Arc<RwLock<Option<i64>>>,
+) {
+    let mut interval = time::interval(Duration::from_secs(5));
+
+    loop {
+        interval.tick().await;
+
+        let commands = match server_client.get_commands(&client_id).await {
+            Ok(commands) => commands,
+            Err(e) => {
+                warn!("Failed to poll commands: {}", e);
+                continue;
+            }
+        };
+
+        for ClientCommand { id, download_id, command } in commands {
+            let is_current = *current_download.read().await == Some(download_id);
+            let is_tracked = store.lock().await.is_tracked(download_id);
+            if !is_current && !is_tracked {
+                continue;
+            }
+
+            match command.as_str() {
+                "cancel" => {
+                    info!("Cancel requested for download {}", download_id);
+                    if is_current {
+                        downloader.cancel();
+                    } else {
+                        store.lock().await.finish(download_id);
+                    }
+                }
+                other => warn!("Ignoring unknown command '{}'", other),
+            }
+
+            if let Err(e) = server_client.ack_command(&client_id, id).await {
+                warn!("Failed to acknowledge command {}: {}", id, e);
+            }
+        }
+    }
+}
+
+/// Report a cancelled download and forget its local state. Always returns
+/// the error that ends processing of the download.
+async fn report_cancelled(
+    server_client: &ServerClient,
+    store: &Mutex<DownloadStore>,
+    download_id: i64,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    info!("Download {} cancelled", download_id);
+    store.lock().await.finish(download_id);
+    report_progress(server_client, download_id, "cancelled", 0.0, None, None, Some("Cancelled by user".to_string())).await?;
+    Err("Download cancelled".into())
+}
+
 async fn process_single_download(
     server_client: &Arc<ServerClient>,
     downloader: &Arc<Downloader>,
+    store: &Mutex<DownloadStore>,
+    client_id: &str,
     download: crate::server_client::DownloadQueueItem,
     output_dir: &Path,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let download_id = download.id;
+    let game_id = download.game_id;
     let game_title = download.game_title.clone();
+    let archive_password = download.archive_password.as_deref();
 
     // Ensure output directory exists
     if !output_dir.exists() {
@@ -91,27 +224,38 @@ async fn process_single_download(
         info!("Created output directory: {:?}", output_dir);
     }
 
+    // Check the download will fit before fetching anything
+    let target_files: Vec<PathBuf> = download.direct_urls.iter()
+        .enumerate()
+        .map(|(idx, url)| output_dir.join(download_filename(url, idx)))
+        .collect();
+    if let Err(e) = check_download_space(&download.game_size, &target_files, output_dir) {
+        error!("{}", e);
+        report_progress(server_client, download_id, "failed", 0.0, None, None, Some(e.clone())).await?;
+        return Err(e.into());
+    }
+
     // Step 1: Download files
     info!("Starting download for: {}", game_title);
+    store.lock().await.start(download_id, &game_title);
     report_progress(server_client, download_id, "downloading", 0.0, None, None, None).await?;
 
     let mut downloaded_files = Vec::new();
     let total_files = download.direct_urls.len();
 
-    for (idx, url) in download.direct_urls.iter().enumerate() {
+    for (idx, (url, file_path)) in download.direct_urls.iter().zip(target_files).enumerate() {
         info!("Downloading file {}/{}", idx + 1, total_files);
 
-        // Extract filename from URL and sanitize it
-        let default_name = format!("file_{}.bin", idx);
-        let filename = url.split('/').last()
-            .unwrap_or(&default_name)
-            .split('?').next()
-            .unwrap_or(&default_name);
+        let filename = file_path.file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
 
-        // Sanitize filename for Windows (remove invalid characters)
-        let sanitized_filename = sanitize_filename(filename);
+        if store.lock().await.is_file_complete(download_id, &file_path) {
+            info!("Already downloaded: {:?}", file_path);
+            downloaded_files.push(file_path);
+            continue;
+        }
 
-        let file_path = output_dir.join(&sanitized_filename);
         info!("Downloading to: {:?}", file_path);
 
         // Download file with retry and backoff
@@ -177,6 +321,7 @@ async fn process_single_download(
                     progress_task.abort();
 
                     info!("Downloaded: {}", filename);
+                    store.lock().await.mark_file_complete(download_id, &file_path);
                     downloaded_files.push(file_path.clone());
 
                     // Update progress - file complete
@@ -197,6 +342,10 @@ async fn process_single_download(
                     // Stop progress reporting task
                     progress_task.abort();
 
+                    if downloader.is_cancelled() {
+                        return report_cancelled(server_client, store, download_id).await;
+                    }
+
                     last_error = format!("{}", e);
                     error!("Download attempt {} failed for {}: {}", attempt + 1, filename, e);
                 }
@@ -217,8 +366,13 @@ async fn process_single_download(
         }
     }
 
+    if downloader.is_cancelled() {
+        return report_cancelled(server_client, store, download_id).await;
+    }
+
     // Step 2: Extract archives
     info!("Download complete. Starting extraction for: {}", game_title);
+    store.lock().await.set_stage(download_id, "extracting");
     report_progress(server_client, download_id, "extracting", 0.0, None, None, None).await?;
 
     // Sanitize the game title for use as a directory name
@@ -239,17 +393,40 @@ async fn process_single_download(
             extract_dir, e, e.raw_os_error().unwrap_or(-1)))?;
     let _ = std::fs::remove_file(&test_file);
 
+    // Check the extracted files will fit before starting, so we don't leave a half-extracted folder
+    if let Err(e) = check_extract_space(&downloaded_files, &extract_dir) {
+        error!("{}", e);
+        report_progress(server_client, download_id, "failed", 0.0, None, None, Some(e.clone())).await?;
+        return Err(e.into());
+    }
+
+    // Multi-volume RAR sets are extracted once, from their first volume
+    let rar_first_volumes = match crate::extractor::rar_first_volumes(&downloaded_files) {
+        Ok(firsts) => firsts,
+        Err(e) => {
+            error!("{}", e);
+            report_progress(server_client, download_id, "failed", 0.0, None, None,
+                Some(format!("Extraction failed: {}", e))).await?;
+            return Err(e.into());
+        }
+    };
+
     for file_path in &downloaded_files {
         if let Some(ext) = file_path.extension().and_then(|e| e.to_str()) {
             let ext_lower = ext.to_lowercase();
 
+            if ext_lower == "rar" && !rar_first_volumes.contains(file_path) {
+                info!("Skipping RAR continuation volume: {:?}", file_path);
+                continue;
+            }
+
             if ext_lower == "zip" || ext_lower == "7z" || ext_lower == "rar" {
                 info!("Extracting: {:?}", file_path);
 
                 let extract_result = match ext_lower.as_str() {
-                    "zip" => crate::extractor::extract_zip(file_path, &extract_dir).await,
-                    "7z" => crate::extractor::extract_7z(file_path, &extract_dir).await,
-                    "rar" => crate::extractor::extract_rar(file_path, &extract_dir).await,
+                    "zip" => crate::extractor::extract_zip(file_path, &extract_dir, archive_password).await,
+                    "7z" => crate::extractor::extract_7z(file_path, &extract_dir, archive_password).await,
+                    "rar" => crate::extractor::extract_rar(file_path, &extract_dir, archive_password).await,
                     _ => unreachable!(),
                 };
 
@@ -275,8 +452,14 @@ async fn process_single_download(
         }
     }
 
+    // Extraction can't be interrupted, but don't go on to install a cancelled game
+    if downloader.is_cancelled() {
+        return report_cancelled(server_client, store, download_id).await;
+    }
+
     // Step 3: Find and launch installer
     info!("Extraction complete. Looking for installer: {}", game_title);
+    store.lock().await.finish(download_id);
     report_progress(server_client, download_id, "installing", 0.0, None, None, None).await?;
 
     // Look for setup.exe in extracted folder
@@ -285,22 +468,32 @@ async fn process_single_download(
     info!("Found installer: {:?}", installer_path);
 
     // Launch installer (user will interact with it)
-    match run_silent_install(&installer_path).await {
+    let started_at = chrono::Utc::now().to_rfc3339();
+    let install_dir = download.install_dir.as_deref()
+        .filter(|d| !d.trim().is_empty())
+        .map(PathBuf::from)
+        .filter(|dir| match crate::system_info::check_writable_dir(dir) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Custom install folder unusable, using the installer's default: {}", e);
+                false
+            }
+        });
+    let install_result = run_silent_install(&installer_path, install_dir.as_deref(), server_client, download_id).await;
+    report_installed(server_client, client_id, game_id, started_at, &install_result).await;
+
+    match install_result {
         Ok(_) => {
-            info!("Installer launched successfully: {}", game_title);
+            info!("Installer finished: {}", game_title);
             report_progress(server_client, download_id, "completed", 100.0, None, None, None).await?;
 
-            // Show notification
-            #[cfg(windows)]
-            tokio::task::spawn_blocking(move || {
-                show_notification(
-                    "Installer Launched",
-                    &format!("{} installer is ready. Follow the on-screen instructions to install.", game_title),
-                );
-            });
+            show_notification(
+                "Installation Complete",
+                &format!("{} has been installed.", game_title),
+            );
         }
         Err(e) => {
-            error!("Failed to launch installer: {}", e);
+            error!("Installation failed: {}", e);
             report_progress(
                 server_client,
                 download_id,
@@ -308,7 +501,7 @@ async fn process_single_download(
                 0.0,
                 None,
                 None,
-                Some(format!("Failed to launch installer: {}", e)),
+                Some(format!("Installation failed: {}", e)),
             ).await?;
             return Err(e.into());
         }
@@ -332,11 +525,33 @@ async fn report_progress(
         download_speed,
         eta,
         error_message,
+        install_step: None,
     };
 
     server_client.update_download_progress(download_id, &update).await
 }
 
+/// Tell the server how an installation went so it shows up in installation stats
+async fn report_installed(
+    server_client: &ServerClient,
+    client_id: &str,
+    game_id: i64,
+    started_at: String,
+    result: &Result<(), String>,
+) {
+    let report = InstalledReport {
+        game_id: Some(game_id),
+        success: result.is_ok(),
+        started_at,
+        completed_at: chrono::Utc::now().to_rfc3339(),
+        error_message: result.as_ref().err().cloned(),
+    };
+
+    if let Err(e) = server_client.report_installed(client_id, &report).await {
+        warn!("Failed to report installation of game {}: {}", game_id, e);
+    }
+}
+
 fn find_installer(dir: &Path) -> Result<PathBuf, String> {
     // Look for setup.exe, install.exe, etc.
     let installer_names = vec!["setup.exe", "install.exe", "installer.exe"];
@@ -367,33 +582,120 @@ fn find_installer(dir: &Path) -> Result<PathBuf, String> {
     Err(format!("No installer found in {:?}", dir))
 }
 
-async fn run_silent_install(installer_path: &Path) -> Result<(), String> {
+async fn run_silent_install(
+    installer_path: &Path,
+    install_dir: Option<&Path>,
+    server_client: &ServerClient,
+    download_id: i64,
+) -> Result<(), String> {
     info!("Launching installer with elevation: {:?}", installer_path);
 
     // Just launch the installer with elevation - let user interact with it
-    // No silent install flags - user can see and control the installation
+    // No silent install flags - user can see and control the installation.
+    // The Inno Setup log tells us what it's doing and why it failed.
+    let log_path = crate::inno_log::log_path_for(installer_path);
+    let mut args = crate::inno_log::log_arg(&log_path);
+    if let Some(dir) = install_dir {
+        info!("Installing to {:?}", dir);
+        args.push_str(&format!(" /DIR=\"{}\"", dir.display()));
+    }
 
     // Run installer with UAC elevation on Windows
     #[cfg(windows)]
     {
-        run_elevated_process(installer_path, "").await?;
+        let process = run_elevated_process(installer_path, &args).await?;
+        wait_for_installer(process, log_path, server_client, download_id).await
     }
 
     #[cfg(not(windows))]
     {
-        return Err("Installation is only supported on Windows".to_string());
+        let _ = (log_path, args, server_client, download_id);
+        Err("Installation is only supported on Windows".to_string())
     }
+}
 
-    Ok(())
+/// Wait for the installer to exit, passing each step from its log on to the server.
+/// Only a zero exit code with no failure in the setup log counts as installed.
+#[cfg(windows)]
+async fn wait_for_installer(
+    process: InstallerProcess,
+    log_path: PathBuf,
+    server_client: &ServerClient,
+    download_id: i64,
+) -> Result<(), String> {
+    let mut log = crate::inno_log::InnoLog::new(log_path);
+
+    // Poll every 5 seconds to check if installer is still running
+    for _ in 0..360 { // Max 30 minutes (360 * 5 seconds)
+        tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+
+        if let Some(step) = log.poll() {
+            info!("Installer: {}", step);
+            let update = ProgressUpdate {
+                status: "installing".to_string(),
+                progress: 0.0,
+                download_speed: None,
+                eta: None,
+                error_message: None,
+                install_step: Some(step),
+            };
+            if let Err(e) = server_client.update_download_progress(download_id, &update).await {
+                warn!("Failed to report install step: {}", e);
+            }
+        }
+
+        if let Some(exit_code) = process.exit_code()? {
+            info!("Installer process exited with code {}", exit_code);
+            log.poll();
+            return match (log.failure(), exit_code) {
+                (Some(reason), _) => Err(format!("Installer failed: {}", reason)),
+                (None, 0) => Ok(()),
+                (None, code) => Err(format!("Installer exited with code {}", code)),
+            };
+        }
+    }
+
+    Err("Installation timeout - process did not complete within 30 minutes".to_string())
 }
 
+/// Handle to an installer started through ShellExecuteExW, closed on drop
 #[cfg(windows)]
-async fn run_elevated_process(exe_path: &Path, args: &str) -> Result<(), String> {
+struct InstallerProcess(winapi::um::winnt::HANDLE);
+
+// The handle is only used to query the exit code, which is safe from any thread
+#[cfg(windows)]
+unsafe impl Send for InstallerProcess {}
+
+#[cfg(windows)]
+impl InstallerProcess {
+    /// The exit code once the process has exited, None while it's still running
+    fn exit_code(&self) -> Result<Option<u32>, String> {
+        use winapi::um::minwinbase::STILL_ACTIVE;
+        use winapi::um::processthreadsapi::GetExitCodeProcess;
+
+        let mut code = 0u32;
+        if unsafe { GetExitCodeProcess(self.0, &mut code) } == 0 {
+            return Err(format!("Failed to query installer status: {}", std::io::Error::last_os_error()));
+        }
+        Ok(if code == STILL_ACTIVE { None } else { Some(code) })
+    }
+}
+
+#[cfg(windows)]
+impl Drop for InstallerProcess {
+    fn drop(&mut self) {
+        unsafe {
+            winapi::um::handleapi::CloseHandle(self.0);
+        }
+    }
+}
+
+#[cfg(windows)]
+async fn run_elevated_process(exe_path: &Path, args: &str) -> Result<InstallerProcess, String> {
     use std::ffi::OsStr;
     use std::iter::once;
     use std::os::windows::ffi::OsStrExt;
-    use std::ptr;
-    use winapi::um::shellapi::ShellExecuteW;
+    use winapi::um::shellapi::{ShellExecuteExW, SEE_MASK_NOCLOSEPROCESS, SHELLEXECUTEINFOW};
     use winapi::um::winuser::SW_SHOW;
 
     // Verify the installer file exists and is readable
@@ -423,106 +725,100 @@ async fn run_elevated_process(exe_path: &Path, args: &str) -> Result<(), String>
     let file: Vec<u16> = OsStr::new(&exe_path_str).encode_wide().chain(once(0)).collect();
     let parameters: Vec<u16> = OsStr::new(args).encode_wide().chain(once(0)).collect();
 
-    // Run in a blocking task since ShellExecuteW is synchronous
+    // Run in a blocking task since ShellExecuteExW waits for the UAC prompt
     let result = tokio::task::spawn_blocking(move || {
         unsafe {
-            let result = ShellExecuteW(
-                ptr::null_mut(),           // hwnd
-                operation.as_ptr(),         // lpOperation - "runas" for elevation
-                file.as_ptr(),              // lpFile - executable path
-                parameters.as_ptr(),        // lpParameters - command line args
-                ptr::null(),                // lpDirectory - use current
-                SW_SHOW,                    // nShowCmd - show window
-            );
-
-            // ShellExecuteW returns a value > 32 if successful
-            let result_code = result as i32;
-            if result as usize > 32 {
-                log::info!("ShellExecuteW succeeded, installer launched");
-                Ok(())
-            } else {
+            let mut info: SHELLEXECUTEINFOW = std::mem::zeroed();
+            info.cbSize = std::mem::size_of::<SHELLEXECUTEINFOW>() as u32;
+            info.fMask = SEE_MASK_NOCLOSEPROCESS; // keep a handle so we can read the exit code
+            info.lpVerb = operation.as_ptr();     // "runas" for elevation
+            info.lpFile = file.as_ptr();          // executable path
+            info.lpParameters = parameters.as_ptr();
+            info.nShow = SW_SHOW;
+
+            if ShellExecuteExW(&mut info) == 0 {
+                let error = std::io::Error::last_os_error();
                 // Map common error codes
-                let error_msg = match result_code {
-                    0 => "The operating system is out of memory or resources",
-                    2 => "File not found",
-                    3 => "Path not found",
-                    5 => "Access denied - try running the client as administrator",
-                    8 => "Out of memory",
-                    31 => "No association for file type",
-                    _ => "Unknown error",
+                let error_msg = match error.raw_os_error() {
+                    Some(2) => "File not found".to_string(),
+                    Some(3) => "Path not found".to_string(),
+                    Some(5) => "Access denied - try running the client as administrator".to_string(),
+                    Some(1223) => "The administrator prompt was declined".to_string(),
+                    _ => error.to_string(),
                 };
-                Err(format!("ShellExecuteW failed (code {}): {}", result_code, error_msg))
+                return Err(format!("ShellExecuteExW failed: {}", error_msg));
             }
+            if info.hProcess.is_null() {
+                return Err("Installer started without a process handle".to_string());
+            }
+
+            log::info!("ShellExecuteExW succeeded, installer launched");
+            Ok(InstallerProcess(info.hProcess))
         }
     })
     .await
     .map_err(|e| format!("Failed to spawn elevated process task: {}", e))?;
 
-    result?;
-
-    // Wait for installation to complete
-    // Note: ShellExecuteW doesn't wait for the process to complete, so we need to poll
+    let process = result?;
     info!("Installer launched with elevation. Waiting for completion...");
+    Ok(process)
+}
 
-    // Wait for installer process to finish by checking if setup.exe is still running
-    let exe_name = exe_path
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("setup.exe");
+/// Fail if the game (size as listed on the server) won't fit on the drive holding
+/// `output_dir`, counting files already downloaded by an earlier attempt
+fn check_download_space(game_size: &str, target_files: &[PathBuf], output_dir: &Path) -> Result<(), String> {
+    let game_gb = match crate::system_info::parse_size_to_gb(game_size) {
+        Some(gb) => gb,
+        None => return Ok(()), // Unknown size, don't block the download
+    };
 
-    // Poll every 5 seconds to check if installer is still running
-    for _ in 0..360 { // Max 30 minutes (360 * 5 seconds)
-        tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+    let free_gb = crate::system_info::get_disk_space_gb(&output_dir.to_string_lossy());
+    if free_gb <= 0.0 {
+        return Ok(()); // Unknown free space
+    }
 
-        // Check if installer process is still running
-        if !is_process_running(exe_name) {
-            info!("Installer process completed");
-            return Ok(());
-        }
+    let existing_gb = target_files.iter()
+        .filter_map(|f| std::fs::metadata(f).ok())
+        .map(|m| m.len() as f64 / 1024.0 / 1024.0 / 1024.0)
+        .sum::<f64>();
+
+    let needed_gb = (game_gb - existing_gb).max(0.0) + 0.5;
+    if free_gb < needed_gb {
+        return Err(format!(
+            "Not enough disk space to download: {:.1} GB free, {:.1} GB needed in {}",
+            free_gb, needed_gb, output_dir.display()
+        ));
     }
 
-    Err("Installation timeout - process did not complete within 30 minutes".to_string())
+    Ok(())
 }
 
-#[cfg(windows)]
-fn is_process_running(process_name: &str) -> bool {
-    use std::process::Command;
-
-    // Use tasklist to check if process is running
-    let output = Command::new("tasklist")
-        .arg("/FI")
-        .arg(format!("IMAGENAME eq {}", process_name))
-        .output();
-
-    if let Ok(output) = output {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        stdout.contains(process_name)
-    } else {
-        false
-    }
-}
+/// Fail if the extracted archives (entry sizes where known, else archive sizes)
+/// won't fit on the drive holding `extract_dir`
+fn check_extract_space(files: &[PathBuf], extract_dir: &Path) -> Result<(), String> {
+    let needed_bytes: u64 = files.iter()
+        .filter(|f| {
+            let ext = f.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+            ext == "zip" || ext == "7z" || ext == "rar"
+        })
+        .map(|f| {
+            crate::extractor::estimate_extracted_size(f)
+                .unwrap_or_else(|| std::fs::metadata(f).map(|m| m.len()).unwrap_or(0))
+        })
+        .sum();
 
-#[cfg(windows)]
-fn show_notification(title: &str, message: &str) {
-    use std::ffi::OsStr;
-    use std::iter::once;
-    use std::os::windows::ffi::OsStrExt;
+    let free_gb = crate::system_info::get_disk_space_gb(&extract_dir.to_string_lossy());
+    if free_gb <= 0.0 {
+        return Ok(()); // Unknown free space, don't block extraction
+    }
 
-    let title_wide: Vec<u16> = OsStr::new(title).encode_wide().chain(once(0)).collect();
-    let message_wide: Vec<u16> = OsStr::new(message).encode_wide().chain(once(0)).collect();
-
-    unsafe {
-        use winapi::um::winuser::{MessageBoxW, MB_ICONINFORMATION, MB_OK};
-        MessageBoxW(
-            std::ptr::null_mut(),
-            message_wide.as_ptr(),
-            title_wide.as_ptr(),
-            MB_OK | MB_ICONINFORMATION,
-        );
+    let needed_gb = needed_bytes as f64 / 1024.0 / 1024.0 / 1024.0 + 0.5;
+    if free_gb < needed_gb {
+        return Err(format!(
+            "Not enough disk space to extract: {:.1} GB free, {:.1} GB needed in {}",
+            free_gb, needed_gb, extract_dir.display()
+        ));
     }
-}
 
-#[cfg(not(windows))]
-fn show_notification(_title: &str, _message: &str) {
-    // No-op on non-Windows platforms
+    Ok(())
 }
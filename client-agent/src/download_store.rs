@@ -0,0 +1,89 @@
+//! Local record of in-flight server downloads, kept in downloads.json next to
+//! the config so a restarted agent can pick up where it left off instead of
+//! re-downloading everything.
+
+use crate::config::Config;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StoredDownload {
+    pub game_title: String,
+    /// Files fully downloaded so far
+    pub completed_files: Vec<PathBuf>,
+    /// "downloading" or "extracting"
+    pub stage: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DownloadStore {
+    downloads: HashMap<i64, StoredDownload>,
+}
+
+impl DownloadStore {
+    fn path() -> PathBuf {
+        Config::config_dir().join("downloads.json")
+    }
+
+    /// Load the store, starting empty if it's missing or unreadable
+    pub fn load() -> Self {
+        std::fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        let result = std::fs::create_dir_all(Config::config_dir())
+            .and_then(|_| std::fs::write(Self::path(), serde_json::to_string_pretty(self).unwrap_or_default()));
+        if let Err(e) = result {
+            log::warn!("Failed to save download state: {}", e);
+        }
+    }
+
+    /// Whether this agent was working on the download before it stopped
+    pub fn is_tracked(&self, download_id: i64) -> bool {
+        self.downloads.contains_key(&download_id)
+    }
+
+    /// Start tracking a download, keeping any files recorded by an earlier run
+    pub fn start(&mut self, download_id: i64, game_title: &str) {
+        let entry = self.downloads.entry(download_id).or_default();
+        entry.game_title = game_title.to_string();
+        entry.stage = "downloading".to_string();
+        self.save();
+    }
+
+    /// Whether `path` was fully downloaded by an earlier run and is still on disk
+    pub fn is_file_complete(&self, download_id: i64, path: &Path) -> bool {
+        self.downloads
+            .get(&download_id)
+            .map(|d| d.completed_files.iter().any(|f| f == path))
+            .unwrap_or(false)
+            && path.is_file()
+    }
+
+    pub fn mark_file_complete(&mut self, download_id: i64, path: &Path) {
+        if let Some(entry) = self.downloads.get_mut(&download_id) {
+            if !entry.completed_files.iter().any(|f| f == path) {
+                entry.completed_files.push(path.to_path_buf());
+                self.save();
+            }
+        }
+    }
+
+    pub fn set_stage(&mut self, download_id: i64, stage: &str) {
+        if let Some(entry) = self.downloads.get_mut(&download_id) {
+            entry.stage = stage.to_string();
+            self.save();
+        }
+    }
+
+    /// Stop tracking a download once it has been handed to the installer
+    pub fn finish(&mut self, download_id: i64) {
+        if self.downloads.remove(&download_id).is_some() {
+            self.save();
+        }
+    }
+}
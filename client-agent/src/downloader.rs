@@ -1,4 +1,5 @@
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tokio::io::AsyncWriteExt;
 use tokio::sync::RwLock;
@@ -14,6 +15,7 @@ pub struct DownloadProgress {
 pub struct Downloader {
     client: reqwest::Client,
     progress: Arc<RwLock<Option<DownloadProgress>>>,
+    cancel_requested: AtomicBool,
 }
 
 impl Downloader {
@@ -25,14 +27,30 @@ impl Downloader {
                 .build()
                 .expect("Failed to build HTTP client"),
             progress: Arc::new(RwLock::new(None)),
+            cancel_requested: AtomicBool::new(false),
         }
     }
 
+    /// Abort the download in progress; it fails with "Download cancelled"
+    pub fn cancel(&self) {
+        self.cancel_requested.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel_requested.load(Ordering::SeqCst)
+    }
+
+    /// Clear a previous cancel before starting the next download
+    pub fn reset_cancel(&self) {
+        self.cancel_requested.store(false, Ordering::SeqCst);
+    }
+
     pub async fn get_progress(&self) -> Option<DownloadProgress> {
         self.progress.read().await.clone()
     }
 
-    /// Download a file from a URL with progress tracking
+    /// Download a file from a URL with progress tracking. A partial file left
+    /// by an interrupted run is resumed when the server supports ranges.
     pub async fn download_file(
         &self,
         url: &str,
@@ -45,19 +63,42 @@ impl Downloader {
             tokio::fs::create_dir_all(parent).await?;
         }
 
+        let existing_len = tokio::fs::metadata(output_path).await
+            .map(|m| if m.is_file() { m.len() } else { 0 })
+            .unwrap_or(0);
+
         // Start download
-        let response = self.client.get(url).send().await?;
+        let mut request = self.client.get(url);
+        if existing_len > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", existing_len));
+        }
+        let mut response = request.send().await?;
+
+        // Partial file is stale or already complete per the server - start over
+        if existing_len > 0 && response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+            response = self.client.get(url).send().await?;
+        }
 
         if !response.status().is_success() {
             return Err(format!("HTTP error: {}", response.status()).into());
         }
 
-        let total_bytes = response.content_length().unwrap_or(0);
+        let resume_from = if response.status() == reqwest::StatusCode::PARTIAL_CONTENT {
+            existing_len
+        } else {
+            0
+        };
+        let total_bytes = response.content_length().map(|len| len + resume_from).unwrap_or(0);
         log::info!("Content length: {} bytes", total_bytes);
 
-        // Create output file
-        let mut file = tokio::fs::File::create(output_path).await?;
-        let mut downloaded_bytes: u64 = 0;
+        // Append when resuming, otherwise create or truncate
+        let mut file = if resume_from > 0 {
+            log::info!("Resuming from byte {}", resume_from);
+            tokio::fs::OpenOptions::new().append(true).open(output_path).await?
+        } else {
+            tokio::fs::File::create(output_path).await?
+        };
+        let mut downloaded_bytes: u64 = resume_from;
         let start_time = std::time::Instant::now();
 
         // Download in chunks
@@ -65,6 +106,12 @@ impl Downloader {
         use futures::StreamExt;
 
         while let Some(chunk) = stream.next().await {
+            if self.is_cancelled() {
+                drop(file);
+                let _ = tokio::fs::remove_file(output_path).await;
+                return Err("Download cancelled".into());
+            }
+
             let chunk = chunk?;
             file.write_all(&chunk).await?;
 
@@ -73,7 +120,7 @@ impl Downloader {
             // Update progress
             let elapsed = start_time.elapsed().as_secs_f64();
             let speed = if elapsed > 0.0 {
-                downloaded_bytes as f64 / elapsed
+                (downloaded_bytes - resume_from) as f64 / elapsed
             } else {
                 0.0
             };
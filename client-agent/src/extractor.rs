@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::fs::File;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -24,6 +24,10 @@ pub enum ExtractionStatus {
     Failed,
 }
 
+/// Error text used when an archive needs a password (the server UI keys off "Password required")
+const PASSWORD_REQUIRED: &str = "Password required: the archive is encrypted";
+const WRONG_PASSWORD: &str = "Password required: the archive password is incorrect";
+
 pub struct Extractor {
     progress: Arc<RwLock<ExtractionProgress>>,
 }
@@ -47,6 +51,7 @@ impl Extractor {
         &self,
         archive_path: &Path,
         output_dir: &Path,
+        password: Option<&str>,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let file = File::open(archive_path)?;
         let mut archive = zip::ZipArchive::new(file)?;
@@ -69,7 +74,18 @@ impl Extractor {
 
         for i in 0..archive.len() {
             let file_size = {
-                let mut file = archive.by_index(i)?;
+                let mut file = match password {
+                    Some(pw) => archive.by_index_decrypt(i, pw.as_bytes())?
+                        .map_err(|_| WRONG_PASSWORD)?,
+                    None => archive.by_index(i).map_err(|e| -> Box<dyn std::error::Error> {
+                        match e {
+                            zip::result::ZipError::UnsupportedArchive(msg) if msg == zip::result::ZipError::PASSWORD_REQUIRED => {
+                                PASSWORD_REQUIRED.into()
+                            }
+                            other => Box::new(other),
+                        }
+                    })?,
+                };
                 let outpath = output_dir.join(file.name());
 
                 if file.name().ends_with('/') {
@@ -118,10 +134,20 @@ impl Extractor {
         &self,
         archive_path: &Path,
         output_dir: &Path,
+        password: Option<&str>,
     ) -> Result<(), Box<dyn std::error::Error>> {
         // Use sevenz-rust for 7z extraction
-        sevenz_rust::decompress_file(archive_path, output_dir)
-            .map_err(|e| format!("7z extraction failed: {}", e))?;
+        let result = match password {
+            Some(pw) => sevenz_rust::decompress_file_with_password(archive_path, output_dir, pw.into()),
+            None => sevenz_rust::decompress_file(archive_path, output_dir),
+        };
+        result.map_err(|e| {
+            if e.to_string().to_lowercase().contains("password") {
+                if password.is_some() { WRONG_PASSWORD.to_string() } else { PASSWORD_REQUIRED.to_string() }
+            } else {
+                format!("7z extraction failed: {}", e)
+            }
+        })?;
 
         {
             let mut prog = self.progress.write().await;
@@ -136,25 +162,10 @@ impl Extractor {
         &self,
         archive_path: &Path,
         output_dir: &Path,
+        password: Option<&str>,
     ) -> Result<(), Box<dyn std::error::Error>> {
         // Use 7-Zip command line for RAR extraction (7z.exe can extract RAR files)
-        // Try multiple common 7-Zip installation paths
-        let seven_zip_paths = vec![
-            r"C:\Program Files\7-Zip\7z.exe",
-            r"C:\Program Files (x86)\7-Zip\7z.exe",
-            "7z.exe", // In PATH
-        ];
-
-        let mut seven_zip_exe = None;
-        for path in &seven_zip_paths {
-            if std::path::Path::new(path).exists() || path == &"7z.exe" {
-                seven_zip_exe = Some(path);
-                break;
-            }
-        }
-
-        let seven_zip_exe = seven_zip_exe
-            .ok_or("7-Zip not found. Please install 7-Zip from https://www.7-zip.org/")?;
+        let seven_zip_exe = find_7zip();
 
         // Run 7z.exe x <archive> -o<output_dir> -y
         let output = tokio::process::Command::new(seven_zip_exe)
@@ -162,12 +173,16 @@ impl Extractor {
             .arg(archive_path)
             .arg(format!("-o{}", output_dir.display()))
             .arg("-y")  // Yes to all prompts
+            .arg(format!("-p{}", password.unwrap_or("")))  // Never prompt for a password
             .output()
             .await
             .map_err(|e| format!("Failed to run 7-Zip: {}", e))?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
+            if stderr.contains("Wrong password") {
+                return Err(if password.is_some() { WRONG_PASSWORD } else { PASSWORD_REQUIRED }.into());
+            }
             return Err(format!("7-Zip extraction failed: {}", stderr).into());
         }
 
@@ -182,31 +197,166 @@ impl Extractor {
 
 }
 
+/// 7-Zip executable used for RAR extraction: a standard install location if
+/// present, otherwise whatever `7z.exe` resolves to on PATH
+pub fn find_7zip() -> &'static str {
+    const INSTALL_PATHS: [&str; 2] = [
+        r"C:\Program Files\7-Zip\7z.exe",
+        r"C:\Program Files (x86)\7-Zip\7z.exe",
+    ];
+
+    INSTALL_PATHS
+        .iter()
+        .find(|path| Path::new(path).exists())
+        .copied()
+        .unwrap_or("7z.exe")
+}
+
+/// Uncompressed size of an archive from its entry headers, when it can be read
+/// without extracting (ZIP only; other formats return None)
+pub fn estimate_extracted_size(archive_path: &Path) -> Option<u64> {
+    let ext = archive_path.extension()?.to_str()?.to_lowercase();
+    if ext != "zip" {
+        return None;
+    }
+
+    let mut archive = zip::ZipArchive::new(File::open(archive_path).ok()?).ok()?;
+    let mut total = 0u64;
+    for i in 0..archive.len() {
+        total += archive.by_index_raw(i).ok()?.size();
+    }
+    Some(total)
+}
+
+/// RAR volume set key and 0-based position for `path`:
+/// name.part1.rar → ("name", 0), name.rar → ("name", 0), name.r00 → ("name", 1)
+fn rar_volume_info(path: &Path) -> Option<(String, u32)> {
+    let name = path.file_name()?.to_str()?.to_lowercase();
+    let (stem, ext) = name.rsplit_once('.')?;
+    let all_digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+
+    if ext == "rar" {
+        if let Some((base, num)) = stem.rsplit_once(".part") {
+            if all_digits(num) {
+                let n: u32 = num.parse().ok()?;
+                return Some((base.to_string(), n.saturating_sub(1)));
+            }
+        }
+        return Some((stem.to_string(), 0));
+    }
+
+    let num = ext.strip_prefix('r')?;
+    if num.len() >= 2 && all_digits(num) {
+        return Some((stem.to_string(), num.parse::<u32>().ok()? + 1));
+    }
+    None
+}
+
+/// Group the RAR volumes among `files` into sets and return the first volume
+/// of each, which is the only one 7-Zip should be pointed at. Fails when a
+/// set is missing its first or a middle volume.
+pub fn rar_first_volumes(files: &[PathBuf]) -> Result<Vec<PathBuf>, String> {
+    let mut sets: Vec<(String, Vec<(u32, &PathBuf)>)> = Vec::new();
+
+    for file in files {
+        let Some((base, idx)) = rar_volume_info(file) else { continue };
+        let key = file.with_file_name(&base).to_string_lossy().to_string();
+        match sets.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, volumes)) => volumes.push((idx, file)),
+            None => sets.push((key, vec![(idx, file)])),
+        }
+    }
+
+    let mut firsts = Vec::new();
+    for (key, mut volumes) in sets {
+        volumes.sort_by_key(|(idx, _)| *idx);
+        for (expected, (idx, _)) in volumes.iter().enumerate() {
+            if *idx != expected as u32 {
+                let name = Path::new(&key).file_name().unwrap_or_default().to_string_lossy();
+                return Err(format!(
+                    "RAR set '{}' is missing volume {} (found volumes up to {})",
+                    name, expected + 1, volumes.last().map(|(i, _)| i + 1).unwrap_or(0)
+                ));
+            }
+        }
+
+        firsts.push(volumes[0].1.clone());
+    }
+
+    Ok(firsts)
+}
+
 // Standalone helper functions for simple extraction without progress tracking
 
 pub async fn extract_zip(
     archive_path: &Path,
     output_dir: &Path,
+    password: Option<&str>,
 ) -> Result<(), String> {
     let extractor = Extractor::new(archive_path.to_string_lossy().to_string());
-    extractor.extract_zip(archive_path, output_dir).await
+    extractor.extract_zip(archive_path, output_dir, password).await
         .map_err(|e| e.to_string())
 }
 
 pub async fn extract_7z(
     archive_path: &Path,
     output_dir: &Path,
+    password: Option<&str>,
 ) -> Result<(), String> {
     let extractor = Extractor::new(archive_path.to_string_lossy().to_string());
-    extractor.extract_7z(archive_path, output_dir).await
+    extractor.extract_7z(archive_path, output_dir, password).await
         .map_err(|e| e.to_string())
 }
 
 pub async fn extract_rar(
     archive_path: &Path,
     output_dir: &Path,
+    password: Option<&str>,
 ) -> Result<(), String> {
     let extractor = Extractor::new(archive_path.to_string_lossy().to_string());
-    extractor.extract_rar(archive_path, output_dir).await
+    extractor.extract_rar(archive_path, output_dir, password).await
         .map_err(|e| e.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(names: &[&str]) -> Vec<PathBuf> {
+        names.iter().map(|n| Path::new("/downloads").join(n)).collect()
+    }
+
+    #[test]
+    fn test_rar_volume_info() {
+        assert_eq!(rar_volume_info(Path::new("Game.part1.rar")), Some(("game".to_string(), 0)));
+        assert_eq!(rar_volume_info(Path::new("Game.part02.rar")), Some(("game".to_string(), 1)));
+        assert_eq!(rar_volume_info(Path::new("Game.rar")), Some(("game".to_string(), 0)));
+        assert_eq!(rar_volume_info(Path::new("Game.r00")), Some(("game".to_string(), 1)));
+        assert_eq!(rar_volume_info(Path::new("Game.R15")), Some(("game".to_string(), 16)));
+        assert_eq!(rar_volume_info(Path::new("Game.zip")), None);
+        assert_eq!(rar_volume_info(Path::new("Game.r1")), None);
+    }
+
+    #[test]
+    fn test_rar_first_volumes_part_sets() {
+        let files = paths(&["Game.part2.rar", "Game.part1.rar", "Game.part3.rar", "Other.rar", "readme.txt"]);
+        assert_eq!(rar_first_volumes(&files).unwrap(), paths(&["Game.part1.rar", "Other.rar"]));
+
+        let missing_middle = paths(&["Game.part1.rar", "Game.part3.rar"]);
+        let err = rar_first_volumes(&missing_middle).unwrap_err();
+        assert!(err.contains("missing volume 2"), "{}", err);
+
+        let missing_first = paths(&["Game.part2.rar", "Game.part3.rar"]);
+        assert!(rar_first_volumes(&missing_first).unwrap_err().contains("missing volume 1"));
+    }
+
+    #[test]
+    fn test_rar_first_volumes_old_style_sets() {
+        let files = paths(&["Game.r01", "Game.rar", "Game.r00"]);
+        assert_eq!(rar_first_volumes(&files).unwrap(), paths(&["Game.rar"]));
+
+        // The .rar is the first volume; .r00 alone isn't enough
+        let missing_first = paths(&["Game.r00", "Game.r01"]);
+        assert!(rar_first_volumes(&missing_first).is_err());
+    }
+}
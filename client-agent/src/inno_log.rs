@@ -0,0 +1,158 @@
+//! Tails the log Inno Setup writes when started with `/LOG="path"`, so we can
+//! show what the installer is doing and say why it failed instead of just
+//! reporting a non-zero exit code.
+
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Where to ask the installer to write its log
+pub fn log_path_for(installer: &Path) -> PathBuf {
+    let name: String = installer
+        .parent()
+        .and_then(|p| p.file_name())
+        .and_then(|n| n.to_str())
+        .unwrap_or("setup")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    std::env::temp_dir().join(format!(
+        "repack-install-{}-{}.log",
+        name,
+        chrono::Utc::now().format("%Y%m%d%H%M%S")
+    ))
+}
+
+/// Inno Setup command-line argument for `log_path`
+pub fn log_arg(log_path: &Path) -> String {
+    format!("/LOG=\"{}\"", log_path.display())
+}
+
+pub struct InnoLog {
+    path: PathBuf,
+    offset: u64,
+    /// Trailing text of a line the installer hasn't finished writing
+    partial: String,
+    step: Option<String>,
+    /// Text of the last message box; becomes the failure reason if setup then aborts
+    last_message: Option<String>,
+    awaiting_message: bool,
+    awaiting_run_filename: bool,
+    failure: Option<String>,
+    succeeded: bool,
+}
+
+impl InnoLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            offset: 0,
+            partial: String::new(),
+            step: None,
+            last_message: None,
+            awaiting_message: false,
+            awaiting_run_filename: false,
+            failure: None,
+            succeeded: false,
+        }
+    }
+
+    /// Read whatever the installer appended since the last call.
+    /// Returns the current step when it changed.
+    pub fn poll(&mut self) -> Option<String> {
+        let mut file = std::fs::File::open(&self.path).ok()?;
+        file.seek(SeekFrom::Start(self.offset)).ok()?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes).ok()?;
+        if bytes.is_empty() {
+            return None;
+        }
+        self.offset += bytes.len() as u64;
+
+        // Inno writes the log in the system ANSI code page; lossy is fine for display
+        self.partial.push_str(&String::from_utf8_lossy(&bytes));
+        let text = std::mem::take(&mut self.partial);
+        let mut lines: Vec<&str> = text.split('\n').collect();
+        if let Some(rest) = lines.pop() {
+            self.partial = rest.to_string();
+        }
+
+        let previous = self.step.clone();
+        for line in lines {
+            self.handle_line(strip_timestamp(line.trim_end_matches('\r')));
+        }
+
+        if self.step != previous {
+            self.step.clone()
+        } else {
+            None
+        }
+    }
+
+    /// Why setup failed, if the log says it did
+    pub fn failure(&self) -> Option<&str> {
+        if self.succeeded {
+            return None;
+        }
+        self.failure.as_deref()
+    }
+
+    fn handle_line(&mut self, line: &str) {
+        if line.is_empty() {
+            return;
+        }
+
+        if self.awaiting_message {
+            self.awaiting_message = false;
+            self.last_message = Some(line.to_string());
+            return;
+        }
+
+        if self.awaiting_run_filename {
+            if let Some(filename) = line.strip_prefix("Filename: ") {
+                self.awaiting_run_filename = false;
+                self.step = Some(format!("Running {}", file_name(filename)));
+                return;
+            }
+        }
+
+        if line == "Starting the installation process." {
+            self.step = Some("Starting installation".to_string());
+        } else if let Some(dest) = line.strip_prefix("Dest filename: ") {
+            self.step = Some(format!("Installing {}", file_name(dest)));
+        } else if line == "-- Run entry --" {
+            self.awaiting_run_filename = true;
+        } else if line == "Installation process succeeded." {
+            self.succeeded = true;
+            self.step = Some("Finishing installation".to_string());
+        } else if line.starts_with("Message box (") {
+            self.awaiting_message = true;
+        } else if let Some(message) = line.strip_prefix("Exception message:") {
+            self.failure = Some(message.trim().to_string());
+        } else if line == "Got EAbort exception." || line == "Rolling back changes." || line.starts_with("Fatal exception") {
+            if self.failure.is_none() {
+                self.failure = Some(
+                    self.last_message.clone()
+                        .unwrap_or_else(|| "Setup aborted and rolled back its changes".to_string()),
+                );
+            }
+        }
+    }
+}
+
+/// Drop the "2024-01-31 12:34:56.789   " prefix Inno puts on each line
+fn strip_timestamp(line: &str) -> &str {
+    let bytes = line.as_bytes();
+    let has_timestamp = bytes.len() > 23
+        && bytes[4] == b'-'
+        && bytes[10] == b' '
+        && bytes[..4].iter().all(u8::is_ascii_digit);
+    if has_timestamp {
+        line.get(23..).map(str::trim_start).unwrap_or(line)
+    } else {
+        line.trim()
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit(['\\', '/']).next().unwrap_or(path)
+}
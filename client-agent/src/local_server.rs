@@ -1,8 +1,20 @@
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{Notify, RwLock};
 use warp::Filter;
 
+/// Port the local server listens on (localhost only)
+pub const LOCAL_PORT: u16 = 9999;
+
+/// Header carrying the client's bridge token. Any page open in the browser can
+/// reach localhost, so every route except /health requires it.
+pub const TOKEN_HEADER: &str = "X-Bridge-Token";
+
+#[derive(Debug)]
+struct Unauthorized;
+
+impl warp::reject::Reject for Unauthorized {}
+
 #[derive(Debug, Deserialize)]
 pub struct DownloadRequest {
     pub game_id: i64,
@@ -10,6 +22,11 @@ pub struct DownloadRequest {
     pub user_id: Option<i64>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct DeepLinkRequest {
+    pub url: String,
+}
+
 #[derive(Debug, Serialize)]
 pub struct DownloadResponse {
     pub success: bool,
@@ -19,46 +36,105 @@ pub struct DownloadResponse {
 /// Shared state for download queue
 pub struct LocalServerState {
     pub pending_downloads: Arc<RwLock<Vec<DownloadRequest>>>,
+    /// Wakes the server poller so a download created in the web UI starts right away
+    pub poll_now: Arc<Notify>,
+    /// Token callers must send in `TOKEN_HEADER`
+    bridge_token: String,
 }
 
 impl LocalServerState {
-    pub fn new() -> Self {
+    pub fn new(bridge_token: String) -> Self {
         Self {
             pending_downloads: Arc::new(RwLock::new(Vec::new())),
+            poll_now: Arc::new(Notify::new()),
+            bridge_token,
         }
     }
 }
 
-/// Start local HTTP server on localhost:9999 for browser communication
+/// Reject requests that don't carry this client's bridge token
+fn authorized(state: Arc<LocalServerState>) -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    warp::header::optional::<String>(TOKEN_HEADER)
+        .and_then(move |token: Option<String>| {
+            let state = state.clone();
+            async move {
+                if !state.bridge_token.is_empty() && token.as_deref() == Some(state.bridge_token.as_str()) {
+                    Ok(())
+                } else {
+                    Err(warp::reject::custom(Unauthorized))
+                }
+            }
+        })
+        .untuple_one()
+}
+
+async fn handle_rejection(err: warp::Rejection) -> Result<impl warp::Reply, warp::Rejection> {
+    if err.find::<Unauthorized>().is_some() {
+        log::warn!("Rejected local request without a valid bridge token");
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&DownloadResponse {
+                success: false,
+                message: "Missing or invalid bridge token".to_string(),
+            }),
+            warp::http::StatusCode::UNAUTHORIZED,
+        ));
+    }
+    Err(err)
+}
+
+/// Start local HTTP server on localhost:LOCAL_PORT for browser communication
 pub async fn start_local_server(state: Arc<LocalServerState>) {
+    let auth = authorized(state.clone());
     let state_filter = warp::any().map(move || state.clone());
 
     // CORS configuration - allow requests from the web UI
     let cors = warp::cors()
         .allow_any_origin()
         .allow_methods(vec!["GET", "POST", "OPTIONS"])
-        .allow_headers(vec!["Content-Type"]);
+        .allow_headers(vec!["Content-Type", TOKEN_HEADER]);
 
     // POST /download - Queue a download
     let download_route = warp::path("download")
         .and(warp::post())
+        .and(auth.clone())
         .and(warp::body::json())
         .and(state_filter.clone())
         .and_then(handle_download_request);
 
+    // POST /deeplink - fitgirl:// link forwarded by a second client process
+    let deep_link_route = warp::path("deeplink")
+        .and(warp::post())
+        .and(auth.clone())
+        .and(warp::body::json())
+        .and(state_filter.clone())
+        .and_then(handle_deep_link);
+
+    // GET /download/<game_id> - same as opening fitgirl://download/<game_id>
+    let deep_link_get_route = warp::path!("download" / i64)
+        .and(warp::get())
+        .and(auth.clone())
+        .and(state_filter.clone())
+        .and_then(|game_id: i64, state: Arc<LocalServerState>| async move {
+            Ok::<_, warp::Rejection>(poll_for_game(game_id, &state))
+        });
+
     // GET /health - Health check
     let health_route = warp::path("health")
         .and(warp::get())
         .map(|| warp::reply::json(&serde_json::json!({"status": "ok"})));
 
     let routes = download_route
+        .or(deep_link_route)
+        .or(deep_link_get_route)
         .or(health_route)
+        .recover(handle_rejection)
         .with(cors);
 
-    log::info!("Starting local server on http://localhost:9999");
+    // Loopback only; never reachable from the network
+    log::info!("Starting local server on http://127.0.0.1:{}", LOCAL_PORT);
 
     warp::serve(routes)
-        .run(([127, 0, 0, 1], 9999))
+        .run(([127, 0, 0, 1], LOCAL_PORT))
         .await;
 }
 
@@ -77,3 +153,37 @@ async fn handle_download_request(
         message: "Download queued successfully".to_string(),
     }))
 }
+
+async fn handle_deep_link(
+    req: DeepLinkRequest,
+    state: Arc<LocalServerState>,
+) -> Result<warp::reply::WithStatus<warp::reply::Json>, warp::Rejection> {
+    match crate::deep_link::parse(&req.url) {
+        Some(game_id) => Ok(poll_for_game(game_id, &state)),
+        None => {
+            log::warn!("Ignoring unrecognised deep link: {}", req.url);
+            Ok(warp::reply::with_status(
+                warp::reply::json(&DownloadResponse {
+                    success: false,
+                    message: format!("Unrecognised link: {}", req.url),
+                }),
+                warp::http::StatusCode::BAD_REQUEST,
+            ))
+        }
+    }
+}
+
+/// The web UI has already queued the download on the server; fetch the
+/// queue now instead of waiting for the next poll
+fn poll_for_game(game_id: i64, state: &LocalServerState) -> warp::reply::WithStatus<warp::reply::Json> {
+    log::info!("Deep link for game_id {}, checking the server queue now", game_id);
+    state.poll_now.notify_one();
+
+    warp::reply::with_status(
+        warp::reply::json(&DownloadResponse {
+            success: true,
+            message: "Checking for the download now".to_string(),
+        }),
+        warp::http::StatusCode::OK,
+    )
+}
@@ -1,16 +1,24 @@
 mod client_id;
 mod config;
+mod deep_link;
+mod diagnostics;
 mod downloader;
 mod download_processor;  // New download processor for full workflow
+mod download_store;
 mod extractor;
+mod inno_log;
 mod local_server;
+mod notifications;
 mod realdebrid;
 mod server_client;
 mod system_info;
+mod tray;
+mod updater;
 
-use config::Config;
+use config::{Config, ServerProfile};
 use eframe::egui;
 use log::{error, info, warn};
+use notifications::show_notification;
 use server_client::ServerClient;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -19,11 +27,6 @@ use tokio::runtime::Runtime;
 use tokio::sync::RwLock;
 use tokio::time;
 
-#[cfg(windows)]
-use std::ffi::OsStr;
-#[cfg(windows)]
-use std::os::windows::ffi::OsStrExt;
-
 #[cfg(windows)]
 use winreg::enums::*;
 #[cfg(windows)]
@@ -53,23 +56,42 @@ struct InstallationInfo {
 struct SettingsWindow {
     state: Arc<AppState>,
     server_url: String,
+    profiles: Vec<ServerProfile>,
+    active_profile: String,
+    new_profile_name: String,
     download_folder: String,
     run_on_startup: bool,
-    #[allow(dead_code)]
-    show_window: Arc<RwLock<bool>>,
+    /// None if the tray icon couldn't be created; closing the window then exits
+    tray: Option<tray::Tray>,
+    /// Last diagnostics report; filled in by a background task
+    diagnostics_report: Arc<std::sync::Mutex<Option<String>>>,
+    diagnostics_running: bool,
 }
 
 impl SettingsWindow {
-    fn new(state: Arc<AppState>) -> Self {
+    fn new(state: Arc<AppState>, ctx: &egui::Context) -> Self {
         let config = state.runtime.block_on(async {
             state.config.read().await.clone()
         });
 
+        let tray = match tray::Tray::new(ctx.clone(), state.is_paused.clone(), state.runtime.clone()) {
+            Ok(tray) => Some(tray),
+            Err(e) => {
+                warn!("Failed to create tray icon: {}", e);
+                None
+            }
+        };
+
         Self {
             server_url: config.server.url.clone(),
+            profiles: config.server.profiles.clone(),
+            active_profile: config.server.active_profile.clone(),
+            new_profile_name: String::new(),
             download_folder: config.extraction.output_dir.to_string_lossy().to_string(),
             run_on_startup: is_in_startup(),
-            show_window: Arc::new(RwLock::new(true)),
+            tray,
+            diagnostics_report: Arc::new(std::sync::Mutex::new(None)),
+            diagnostics_running: false,
             state,
         }
     }
@@ -77,6 +99,12 @@ impl SettingsWindow {
 
 impl eframe::App for SettingsWindow {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Closing the window minimizes to the tray instead of exiting
+        if self.tray.is_some() && ctx.input(|i| i.viewport().close_requested()) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            tray::hide_window(ctx);
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.heading("Repack Auto-Installer Settings");
             ui.add_space(10.0);
@@ -126,6 +154,8 @@ impl eframe::App for SettingsWindow {
             ui.heading("Configuration");
             ui.add_space(10.0);
 
+            self.server_profiles_ui(ui);
+
             ui.horizontal(|ui| {
                 ui.label("Server URL:");
                 ui.text_edit_singleline(&mut self.server_url);
@@ -157,6 +187,9 @@ impl eframe::App for SettingsWindow {
             let is_paused = self.state.runtime.block_on(async {
                 *self.state.is_paused.read().await
             });
+            if let Some(tray) = &self.tray {
+                tray.set_paused(is_paused);
+            }
 
             if ui.button(if is_paused { "▶ Resume Installations" } else { "⏸ Pause Installations" }).clicked() {
                 self.state.runtime.spawn({
@@ -168,6 +201,10 @@ impl eframe::App for SettingsWindow {
                 });
             }
 
+            ui.add_space(10.0);
+
+            self.diagnostics_ui(ui);
+
             ui.add_space(20.0);
 
             // Save button
@@ -184,20 +221,138 @@ impl eframe::App for SettingsWindow {
                 self.state.config.read().await.clone()
             });
             ui.label(egui::RichText::new(format!("Client ID: {}", config.client.id)).small().weak());
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new(format!("Bridge token: {}", config.client.bridge_token)).small().weak());
+                if ui.small_button("📋 Copy").clicked() {
+                    ui.output_mut(|o| o.copied_text = config.client.bridge_token.clone());
+                }
+            });
             ui.label(egui::RichText::new(format!("Version: {}", env!("CARGO_PKG_VERSION"))).small().weak());
         });
     }
 }
 
 impl SettingsWindow {
+    /// "Run Diagnostics" button and the copyable report it produces
+    fn diagnostics_ui(&mut self, ui: &mut egui::Ui) {
+        let report = self.diagnostics_report.lock().unwrap().clone();
+        if self.diagnostics_running && report.is_some() {
+            self.diagnostics_running = false;
+        }
+
+        ui.horizontal(|ui| {
+            let button = ui.add_enabled(!self.diagnostics_running, egui::Button::new("🩺 Run Diagnostics"));
+            if button.clicked() {
+                self.diagnostics_running = true;
+                *self.diagnostics_report.lock().unwrap() = None;
+
+                let state = self.state.clone();
+                let report_slot = self.diagnostics_report.clone();
+                let ctx = ui.ctx().clone();
+                self.state.runtime.spawn(async move {
+                    let config = state.config.read().await.clone();
+                    let results = diagnostics::run(&config).await;
+                    let report = diagnostics::format_report(&results);
+                    info!("Diagnostics:\n{}", report);
+                    *report_slot.lock().unwrap() = Some(report);
+                    ctx.request_repaint();
+                });
+            }
+
+            if self.diagnostics_running {
+                ui.spinner();
+            } else if let Some(report) = &report {
+                if ui.button("📋 Copy Report").clicked() {
+                    ui.output_mut(|o| o.copied_text = report.clone());
+                }
+            }
+        });
+
+        // Selectable so it can be copied by hand too; edits are discarded
+        if let Some(mut report) = report {
+            egui::ScrollArea::vertical().max_height(150.0).show(ui, |ui| {
+                ui.add(
+                    egui::TextEdit::multiline(&mut report)
+                        .font(egui::TextStyle::Monospace)
+                        .desired_width(f32::INFINITY),
+                );
+            });
+        }
+    }
+
+    /// Dropdown of saved servers plus controls to save/remove profiles
+    fn server_profiles_ui(&mut self, ui: &mut egui::Ui) {
+        let mut switch_to = None;
+        let mut remove = false;
+
+        ui.horizontal(|ui| {
+            ui.label("Server:");
+            let selected_text = if self.active_profile.is_empty() {
+                "Custom".to_string()
+            } else {
+                self.active_profile.clone()
+            };
+            egui::ComboBox::from_id_salt("server_profile")
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    for profile in &self.profiles {
+                        let selected = profile.name == self.active_profile;
+                        if ui.selectable_label(selected, &profile.name).on_hover_text(&profile.url).clicked() && !selected {
+                            switch_to = Some(profile.clone());
+                        }
+                    }
+                });
+            if !self.active_profile.is_empty() && ui.button("🗑").on_hover_text("Remove this profile").clicked() {
+                remove = true;
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Profile name:");
+            ui.text_edit_singleline(&mut self.new_profile_name);
+            let name = self.new_profile_name.trim().to_string();
+            if ui.add_enabled(!name.is_empty(), egui::Button::new("➕ Save as profile")).clicked() {
+                let profile = ServerProfile { name: name.clone(), url: self.server_url.clone() };
+                match self.profiles.iter_mut().find(|p| p.name == name) {
+                    Some(existing) => *existing = profile,
+                    None => self.profiles.push(profile),
+                }
+                self.active_profile = name;
+                self.new_profile_name.clear();
+                self.save_settings();
+            }
+        });
+
+        if let Some(profile) = switch_to {
+            info!("Switching to server profile '{}' ({})", profile.name, profile.url);
+            self.active_profile = profile.name;
+            self.server_url = profile.url;
+            self.save_settings();
+        } else if remove {
+            self.profiles.retain(|p| p.name != self.active_profile);
+            self.active_profile.clear();
+            self.save_settings();
+        }
+    }
+
     fn save_settings(&mut self) {
+        // Editing the URL of a selected profile updates that profile
+        if let Some(profile) = self.profiles.iter_mut().find(|p| p.name == self.active_profile) {
+            profile.url = self.server_url.clone();
+        }
+
         let state = self.state.clone();
         let url = self.server_url.clone();
         let folder = self.download_folder.clone();
+        let profiles = self.profiles.clone();
+        let active_profile = self.active_profile.clone();
 
         self.state.runtime.spawn(async move {
             let mut config = state.config.write().await;
-            config.server.url = url;
+            let url_changed = config.server.url != url;
+            config.server.url = url.clone();
+            config.server.profiles = profiles;
+            config.server.active_profile = active_profile;
             config.extraction.output_dir = PathBuf::from(folder);
 
             if let Err(e) = config.save() {
@@ -205,6 +360,13 @@ impl SettingsWindow {
             } else {
                 info!("Settings saved");
             }
+            drop(config);
+
+            // Talk to the new server from now on
+            if url_changed {
+                state.server_client.set_base_url(url);
+                register_with_server(state).await;
+            }
         });
     }
 }
@@ -252,36 +414,6 @@ fn remove_from_startup() {}
 fn is_in_startup() -> bool { false }
 
 // Background tasks
-/// Show a Windows notification (using message box for now)
-#[cfg(windows)]
-fn show_notification(title: &str, message: &str) {
-    use winapi::um::winuser::{MessageBoxW, MB_ICONINFORMATION, MB_OK};
-
-    let title_wide: Vec<u16> = OsStr::new(title)
-        .encode_wide()
-        .chain(std::iter::once(0))
-        .collect();
-
-    let message_wide: Vec<u16> = OsStr::new(message)
-        .encode_wide()
-        .chain(std::iter::once(0))
-        .collect();
-
-    unsafe {
-        MessageBoxW(
-            std::ptr::null_mut(),
-            message_wide.as_ptr(),
-            title_wide.as_ptr(),
-            MB_OK | MB_ICONINFORMATION,
-        );
-    }
-}
-
-#[cfg(not(windows))]
-fn show_notification(title: &str, message: &str) {
-    println!("[NOTIFICATION] {}: {}", title, message);
-}
-
 /// Extract a ZIP file
 async fn extract_zip(file_path: &std::path::Path, output_dir: &std::path::Path) -> Result<(), String> {
 
@@ -370,6 +502,8 @@ async fn process_single_download(state: Arc<AppState>, game_id: i64) -> Result<(
 
     let config = state.config.read().await;
     let server_url = config.server.url.clone();
+    let client_id = config.client.id.clone();
+    let bridge_token = config.client.bridge_token.clone();
     let rd_api_key = config.realdebrid.api_key.clone();
     let rd_enabled = config.realdebrid.enabled;
     let output_dir = config.extraction.output_dir.clone();
@@ -419,7 +553,14 @@ async fn process_single_download(state: Arc<AppState>, game_id: i64) -> Result<(
             vec![rd_client.unrestrict_link(&magnet_link).await?]
         }
     } else {
-        return Err("Real-Debrid is not configured. Please set your API key in the config.".into());
+        // No local key: a linked client can have the server resolve the link
+        {
+            let mut status = state.status.write().await;
+            *status = format!("Resolving links via server...");
+        }
+
+        state.server_client.resolve_links(&client_id, &bridge_token, game_id).await
+            .map_err(|e| format!("Real-Debrid is not configured locally and the server couldn't resolve the link ({}). Set your API key in the config or link this client.", e))?
     };
 
     if download_urls.is_empty() {
@@ -581,15 +722,24 @@ async fn start_installation(state: Arc<AppState>, installer_path: PathBuf, game_
         let mut current = state.current_installation.write().await;
         *current = Some(install_info);
     }
+    let started_at = chrono::Utc::now().to_rfc3339();
 
     {
         let mut status = state.status.write().await;
         *status = format!("Installing {}", game_title);
     }
 
-    // Determine installation directory
+    // Determine installation directory; let the installer pick if ours isn't writable
     let config = state.config.read().await;
-    let install_dir = config.extraction.output_dir.join(&game_title);
+    let install_dir = Some(config.extraction.output_dir.join(&game_title))
+        .filter(|dir| match system_info::check_writable_dir(dir) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("{}, using the installer's default folder", e);
+                false
+            }
+        });
+    let client_id = config.client.id.clone();
     drop(config);
 
     // Run the installer with silent flags
@@ -599,51 +749,73 @@ async fn start_installation(state: Arc<AppState>, installer_path: PathBuf, game_
     // /DIR="path" - Install directory
     // /NOCANCEL - Don't allow cancel
     // /NORESTART - Don't restart PC
-    let result = tokio::process::Command::new(&installer_path)
+    // /LOG="path" - Write a setup log we can tail for progress and failure reasons
+    let log_path = inno_log::log_path_for(&installer_path);
+    let mut command = tokio::process::Command::new(&installer_path);
+    command
         .arg("/VERYSILENT")
         .arg("/LANG=english")
-        .arg(format!("/DIR=\"{}\"", install_dir.display()))
         .arg("/NOCANCEL")
         .arg("/NORESTART")
-        .spawn();
+        .arg(inno_log::log_arg(&log_path));
+    if let Some(dir) = &install_dir {
+        command.arg(format!("/DIR=\"{}\"", dir.display()));
+    }
+    let result = command.spawn();
 
-    match result {
+    let outcome: Result<(), String> = match result {
         Ok(mut child) => {
-            info!("Installer process started: {:?}", installer_path);
+            info!("Installer process started: {:?} (log: {:?})", installer_path, log_path);
+            let mut log = inno_log::InnoLog::new(log_path);
+
+            // Wait for installer to complete, showing its current step meanwhile
+            let wait_result = loop {
+                match child.try_wait() {
+                    Ok(Some(exit_status)) => break Ok(exit_status),
+                    Ok(None) => {}
+                    Err(e) => break Err(e),
+                }
+
+                if let Some(step) = log.poll() {
+                    let mut status = state.status.write().await;
+                    *status = format!("Installing {}: {}", game_title, step);
+                }
+                tokio::time::sleep(Duration::from_secs(2)).await;
+            };
+            log.poll();
 
-            // Wait for installer to complete
-            match child.wait().await {
+            match wait_result {
                 Ok(exit_status) => {
-                    if exit_status.success() {
+                    if exit_status.success() && log.failure().is_none() {
                         info!("Installation completed: {}", game_title);
                         let mut status = state.status.write().await;
                         *status = format!("✅ Installed {}", game_title);
 
                         // Show success notification
-                        let title_clone = game_title.clone();
-                        tokio::task::spawn_blocking(move || {
-                            show_notification(
-                                "Installation Complete",
-                                &format!("{} has been installed successfully!", title_clone)
-                            );
-                        });
+                        show_notification(
+                            "Installation Complete",
+                            &format!("{} has been installed successfully!", game_title)
+                        );
+                        Ok(())
                     } else {
-                        error!("Installation failed with code: {:?}", exit_status.code());
+                        let reason = log.failure()
+                            .map(str::to_string)
+                            .unwrap_or_else(|| format!("exit code {:?}", exit_status.code()));
+                        error!("Installation failed: {}", reason);
                         let mut status = state.status.write().await;
                         *status = format!("❌ Installation failed: {}", game_title);
 
                         // Show error notification
-                        let title_clone = game_title.clone();
-                        tokio::task::spawn_blocking(move || {
-                            show_notification(
-                                "Installation Failed",
-                                &format!("{} installation failed. Check logs for details.", title_clone)
-                            );
-                        });
+                        show_notification(
+                            "Installation Failed",
+                            &format!("{} installation failed: {}", game_title, reason)
+                        );
+                        Err(reason)
                     }
                 }
                 Err(e) => {
                     error!("Failed to wait for installer: {}", e);
+                    Err(format!("Failed to wait for installer: {}", e))
                 }
             }
         }
@@ -651,7 +823,20 @@ async fn start_installation(state: Arc<AppState>, installer_path: PathBuf, game_
             error!("Failed to start installer: {}", e);
             let mut status = state.status.write().await;
             *status = format!("❌ Error starting installer: {}", e);
+            Err(format!("Failed to start installer: {}", e))
         }
+    };
+
+    // Only now that the installer has exited do we know how it went
+    let report = server_client::InstalledReport {
+        game_id: None,
+        success: outcome.is_ok(),
+        started_at,
+        completed_at: chrono::Utc::now().to_rfc3339(),
+        error_message: outcome.err(),
+    };
+    if let Err(e) = state.server_client.report_installed(&client_id, &report).await {
+        warn!("Failed to report installation of {}: {}", game_title, e);
     }
 
     // Clear current installation
@@ -677,6 +862,7 @@ async fn register_with_server(state: Arc<AppState>) {
         &config.client.id,
         &config.client.name,
         &sys_info.os_version,
+        &config.client.bridge_token,
     ).await {
         Ok(_) => info!("Registered with server"),
         Err(e) => warn!("Failed to register: {}", e),
@@ -690,6 +876,9 @@ fn main() -> eframe::Result<()> {
 
     info!("Repack Auto-Installer starting...");
 
+    // Switch to a build downloaded by the previous run, if any
+    updater::apply_staged_update();
+
     // Load config
     let mut config = Config::load().unwrap_or_else(|e| {
         error!("Failed to load config: {}", e);
@@ -699,15 +888,25 @@ fn main() -> eframe::Result<()> {
     // Generate client ID
     let client_id = client_id::generate_or_load_client_id(&mut config);
     info!("Client ID: {}", client_id);
+    let bridge_token = client_id::generate_or_load_bridge_token(&mut config);
 
     // Create runtime
     let runtime = Arc::new(Runtime::new().expect("Failed to create runtime"));
 
+    // Launched by a fitgirl:// link: let the running client handle it if there is one
+    if let Some(url) = deep_link::from_args() {
+        if runtime.block_on(deep_link::forward_to_running_instance(&url, &bridge_token)) {
+            return Ok(());
+        }
+        info!("No running client for {}, starting up", url);
+    }
+    deep_link::register_protocol_handler();
+
     // Create server client
     let server_client = Arc::new(ServerClient::new(config.server.url.clone()));
 
     // Create local server state for browser communication
-    let local_server_state = Arc::new(local_server::LocalServerState::new());
+    let local_server_state = Arc::new(local_server::LocalServerState::new(bridge_token));
 
     // Create app state
     let state = Arc::new(AppState {
@@ -728,6 +927,28 @@ fn main() -> eframe::Result<()> {
         }
     });
 
+    // Check the server for a newer client build
+    runtime.spawn({
+        let state = state.clone();
+        async move {
+            let config = state.config.read().await;
+            if !config.server.enabled {
+                return;
+            }
+            let server_url = config.server.url.clone();
+            drop(config);
+
+            match updater::check_for_update(&server_url).await {
+                Ok(Some(version)) => show_notification(
+                    "Update Ready",
+                    &format!("Version {} has been downloaded and will be used next time the app starts.", version),
+                ),
+                Ok(None) => {}
+                Err(e) => warn!("Update check failed: {}", e),
+            }
+        }
+    });
+
     // Start background monitor for local installers
     runtime.spawn({
         let state = state.clone();
@@ -758,6 +979,8 @@ fn main() -> eframe::Result<()> {
                     &client_id,
                     &output_dir,
                     poll_interval,
+                    state.is_paused.clone(),
+                    state.local_server_state.poll_now.clone(),
                 ).await;
             }
         }
@@ -779,7 +1002,8 @@ fn main() -> eframe::Result<()> {
         }
     });
 
-    // Create GUI
+    // Create GUI (hidden in the tray when launched at startup with --minimized)
+    let start_minimized = std::env::args().any(|arg| arg == "--minimized");
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([500.0, 400.0])
@@ -788,13 +1012,14 @@ fn main() -> eframe::Result<()> {
             .with_decorations(true)     // Show window borders and title bar
             .with_resizable(true)        // Allow resizing
             .with_maximized(false)       // Don't start maximized
-            .with_taskbar(true),         // Show in taskbar
+            .with_taskbar(true)          // Show in taskbar
+            .with_visible(!start_minimized),
         ..Default::default()
     };
 
     eframe::run_native(
         "Repack Auto-Installer",
         options,
-        Box::new(move |_cc| Ok(Box::new(SettingsWindow::new(state)))),
+        Box::new(move |cc| Ok(Box::new(SettingsWindow::new(state, &cc.egui_ctx)))),
     )
 }
@@ -0,0 +1,23 @@
+//! Desktop notifications. On Windows these are native toasts, which don't
+//! block or steal focus like a message box does.
+
+#[cfg(windows)]
+pub fn show_notification(title: &str, message: &str) {
+    use tauri_winrt_notification::{Duration, Sound, Toast};
+
+    let result = Toast::new(Toast::POWERSHELL_APP_ID)
+        .title(title)
+        .text1(message)
+        .sound(Some(Sound::Default))
+        .duration(Duration::Short)
+        .show();
+
+    if let Err(e) = result {
+        log::warn!("Failed to show notification '{}': {}", title, e);
+    }
+}
+
+#[cfg(not(windows))]
+pub fn show_notification(title: &str, message: &str) {
+    println!("[NOTIFICATION] {}: {}", title, message);
+}
@@ -15,6 +15,13 @@ struct TorrentInfo {
     links: Vec<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct UserResponse {
+    username: String,
+    #[serde(rename = "type")]
+    account_type: String,
+}
+
 #[derive(Debug, Serialize)]
 struct AddMagnetRequest {
     magnet: String,
@@ -143,4 +150,21 @@ impl RealDebridClient {
 
         Ok(unrestrict.download)
     }
+
+    /// Check the API key against the account endpoint; returns "username (account type)"
+    pub async fn check_api_key(&self) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let response = self
+            .client
+            .get("https://api.real-debrid.com/rest/1.0/user")
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(format!("Real-Debrid rejected the key: {}", response.status()).into());
+        }
+
+        let user: UserResponse = response.json().await?;
+        Ok(format!("{} ({})", user.username, user.account_type))
+    }
 }
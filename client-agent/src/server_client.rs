@@ -1,10 +1,12 @@
 use crate::extractor::ExtractionProgress;
 use crate::system_info::SystemInfo;
 use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
 
 #[derive(Debug, Clone)]
 pub struct ServerClient {
-    base_url: String,
+    /// Shared so switching server profiles takes effect without a restart
+    base_url: Arc<RwLock<String>>,
     client: reqwest::Client,
 }
 
@@ -13,6 +15,7 @@ struct RegisterRequest {
     client_id: String,
     client_name: String,
     os_version: String,
+    bridge_token: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -25,10 +28,8 @@ struct RegisterResponse {
 #[derive(Debug, Deserialize, Clone)]
 pub struct DownloadQueueItem {
     pub id: i64,
-    #[allow(dead_code)]
     pub game_id: i64,
     pub game_title: String,
-    #[allow(dead_code)]
     pub game_size: String,
     #[allow(dead_code)]
     pub magnet_link: String,
@@ -44,6 +45,12 @@ pub struct DownloadQueueItem {
     pub error_message: Option<String>,
     #[allow(dead_code)]
     pub created_at: String,
+    /// Password for encrypted archives, if the user supplied one
+    #[serde(default)]
+    pub archive_password: Option<String>,
+    /// Where the user wants this game installed; installer default when unset
+    #[serde(default)]
+    pub install_dir: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -53,12 +60,34 @@ pub struct ProgressUpdate {
     pub download_speed: Option<String>,
     pub eta: Option<String>,
     pub error_message: Option<String>,
+    /// What the installer is doing, from its Inno Setup log
+    pub install_step: Option<String>,
+}
+
+/// A command queued for this client from the web UI
+#[derive(Debug, Deserialize, Clone)]
+pub struct ClientCommand {
+    pub id: i64,
+    pub download_id: i64,
+    /// Currently only "cancel"
+    pub command: String,
+}
+
+/// Outcome of an installation, reported after the installer exits
+#[derive(Debug, Serialize)]
+pub struct InstalledReport {
+    /// None when the installer was found on disk rather than queued from the server
+    pub game_id: Option<i64>,
+    pub success: bool,
+    pub started_at: String,
+    pub completed_at: String,
+    pub error_message: Option<String>,
 }
 
 impl ServerClient {
     pub fn new(base_url: String) -> Self {
         Self {
-            base_url,
+            base_url: Arc::new(RwLock::new(base_url)),
             client: reqwest::Client::builder()
                 .timeout(std::time::Duration::from_secs(30))
                 .connect_timeout(std::time::Duration::from_secs(10))
@@ -67,18 +96,29 @@ impl ServerClient {
         }
     }
 
+    fn base_url(&self) -> String {
+        self.base_url.read().unwrap().clone()
+    }
+
+    /// Point this client at a different server
+    pub fn set_base_url(&self, base_url: String) {
+        *self.base_url.write().unwrap() = base_url;
+    }
+
     pub async fn register(
         &self,
         client_id: &str,
         client_name: &str,
         os_version: &str,
+        bridge_token: &str,
     ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
-        let url = format!("{}/api/clients/register", self.base_url);
+        let url = format!("{}/api/clients/register", self.base_url());
 
         let request = RegisterRequest {
             client_id: client_id.to_string(),
             client_name: client_name.to_string(),
             os_version: os_version.to_string(),
+            bridge_token: bridge_token.to_string(),
         };
 
         let response = self
@@ -96,11 +136,39 @@ impl ServerClient {
         Ok(result.success)
     }
 
+    /// Have the server convert a game's link to direct URLs with its own debrid
+    /// key; only works once this client is linked to a user
+    pub async fn resolve_links(
+        &self,
+        client_id: &str,
+        bridge_token: &str,
+        game_id: i64,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!("{}/api/client/resolve-links", self.base_url());
+
+        let response = self.client
+            .post(&url)
+            .header("X-Client-Id", client_id)
+            .header("X-Bridge-Token", bridge_token)
+            .json(&serde_json::json!({ "game_id": game_id }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(format!("Server couldn't resolve links: {}", response.status()).into());
+        }
+
+        let body: serde_json::Value = response.json().await?;
+        Ok(body["urls"].as_array()
+            .map(|urls| urls.iter().filter_map(|u| u.as_str().map(str::to_string)).collect())
+            .unwrap_or_default())
+    }
+
     pub async fn get_download_queue(
         &self,
         client_id: &str,
     ) -> Result<Vec<DownloadQueueItem>, Box<dyn std::error::Error + Send + Sync>> {
-        let url = format!("{}/api/downloads/queue?client_id={}", self.base_url, client_id);
+        let url = format!("{}/api/downloads/queue?client_id={}", self.base_url(), client_id);
 
         let response = self.client.get(&url).send().await?;
 
@@ -112,12 +180,43 @@ impl ServerClient {
         Ok(queue)
     }
 
+    pub async fn get_commands(
+        &self,
+        client_id: &str,
+    ) -> Result<Vec<ClientCommand>, Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!("{}/api/clients/{}/commands", self.base_url(), client_id);
+
+        let response = self.client.get(&url).send().await?;
+
+        if !response.status().is_success() {
+            return Ok(Vec::new());
+        }
+
+        Ok(response.json().await?)
+    }
+
+    pub async fn ack_command(
+        &self,
+        client_id: &str,
+        command_id: i64,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!("{}/api/clients/{}/commands/{}/ack", self.base_url(), client_id, command_id);
+
+        let response = self.client.post(&url).send().await?;
+
+        if !response.status().is_success() {
+            return Err(format!("Failed to acknowledge command: {}", response.status()).into());
+        }
+
+        Ok(())
+    }
+
     pub async fn update_download_progress(
         &self,
         download_id: i64,
         update: &ProgressUpdate,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let url = format!("{}/api/downloads/{}/progress", self.base_url, download_id);
+        let url = format!("{}/api/downloads/{}/progress", self.base_url(), download_id);
 
         let response = self.client
             .post(&url)
@@ -132,13 +231,33 @@ impl ServerClient {
         Ok(())
     }
 
+    pub async fn report_installed(
+        &self,
+        client_id: &str,
+        report: &InstalledReport,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!("{}/api/clients/{}/installed", self.base_url(), client_id);
+
+        let response = self.client
+            .post(&url)
+            .json(report)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(format!("Failed to report installation: {}", response.status()).into());
+        }
+
+        Ok(())
+    }
+
     #[allow(dead_code)]
     pub async fn report_progress(
         &self,
         client_id: &str,
         progress: &ExtractionProgress,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let url = format!("{}/api/clients/{}/progress", self.base_url, client_id);
+        let url = format!("{}/api/clients/{}/progress", self.base_url(), client_id);
 
         self.client
             .post(&url)
@@ -156,7 +275,7 @@ impl ServerClient {
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let url = format!(
             "{}/api/clients/{}/system-info",
-            self.base_url, system_info.client_id
+            self.base_url(), system_info.client_id
         );
 
         self.client
@@ -170,7 +289,7 @@ impl ServerClient {
 
     #[allow(dead_code)]
     pub async fn health_check(&self) -> bool {
-        let url = format!("{}/api/health", self.base_url);
+        let url = format!("{}/api/health", self.base_url());
 
         self.client
             .get(&url)
@@ -46,8 +46,37 @@ pub fn gather_system_info(client_id: &str, client_name: &str) -> SystemInfo {
     }
 }
 
+/// Parse a size string like "50 GB" or "from 12.3 GB" to GB
+pub fn parse_size_to_gb(size_str: &str) -> Option<f64> {
+    let cleaned = size_str.to_lowercase().replace(',', "");
+    let number = cleaned
+        .split_whitespace()
+        .find_map(|s| s.parse::<f64>().ok())?;
+
+    if cleaned.contains("gb") {
+        Some(number)
+    } else if cleaned.contains("mb") {
+        Some(number / 1024.0)
+    } else {
+        None
+    }
+}
+
+/// Create `dir` if needed and check a file can be written in it
+pub fn check_writable_dir(dir: &std::path::Path) -> Result<(), String> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+
+    let test_file = dir.join(".write_test");
+    std::fs::write(&test_file, "test")
+        .map_err(|e| format!("Cannot write to {}: {}", dir.display(), e))?;
+    let _ = std::fs::remove_file(&test_file);
+    Ok(())
+}
+
+/// Free space in GB on the volume containing `drive` (any directory works); 0.0 if unknown
 #[cfg(target_os = "windows")]
-fn get_disk_space_gb(drive: &str) -> f64 {
+pub fn get_disk_space_gb(drive: &str) -> f64 {
     use std::ffi::OsStr;
     use std::os::windows::ffi::OsStrExt;
     use winapi::um::fileapi::GetDiskFreeSpaceExW;
@@ -77,7 +106,7 @@ fn get_disk_space_gb(drive: &str) -> f64 {
 }
 
 #[cfg(not(target_os = "windows"))]
-fn get_disk_space_gb(_drive: &str) -> f64 {
+pub fn get_disk_space_gb(_drive: &str) -> f64 {
     0.0
 }
 
@@ -0,0 +1,98 @@
+//! System tray icon. Closing the window hides it to the tray; the agent keeps
+//! running until Quit is chosen from the tray menu.
+
+use eframe::egui;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+use tokio::sync::RwLock;
+use tray_icon::menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tray_icon::{Icon, MouseButton, TrayIcon, TrayIconBuilder, TrayIconEvent};
+
+const ICON_PNG: &[u8] = include_bytes!("../assets/icon.png");
+
+pub struct Tray {
+    _icon: TrayIcon,
+    pause_item: CheckMenuItem,
+}
+
+impl Tray {
+    /// Create the tray icon and route its menu events. Must be called on the
+    /// GUI thread.
+    pub fn new(
+        ctx: egui::Context,
+        is_paused: Arc<RwLock<bool>>,
+        runtime: Arc<Runtime>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let icon_data = eframe::icon_data::from_png_bytes(ICON_PNG)?;
+        let icon = Icon::from_rgba(icon_data.rgba, icon_data.width, icon_data.height)?;
+
+        let show_item = MenuItem::new("Show", true, None);
+        let pause_item = CheckMenuItem::new("Pause Installations", true, false, None);
+        let quit_item = MenuItem::new("Quit", true, None);
+
+        let menu = Menu::new();
+        menu.append_items(&[
+            &show_item,
+            &pause_item,
+            &PredefinedMenuItem::separator(),
+            &quit_item,
+        ])?;
+
+        let tray_icon = TrayIconBuilder::new()
+            .with_menu(Box::new(menu))
+            .with_tooltip("Repack Auto-Installer")
+            .with_icon(icon)
+            .build()?;
+
+        let show_id = show_item.id().clone();
+        let pause_id = pause_item.id().clone();
+        let quit_id = quit_item.id().clone();
+
+        let menu_ctx = ctx.clone();
+        MenuEvent::set_event_handler(Some(move |event: MenuEvent| {
+            if event.id == show_id {
+                show_window(&menu_ctx);
+            } else if event.id == pause_id {
+                let is_paused = is_paused.clone();
+                runtime.spawn(async move {
+                    let mut paused = is_paused.write().await;
+                    *paused = !*paused;
+                });
+            } else if event.id == quit_id {
+                log::info!("Quit requested from tray");
+                std::process::exit(0);
+            }
+        }));
+
+        TrayIconEvent::set_event_handler(Some(move |event: TrayIconEvent| {
+            if let TrayIconEvent::DoubleClick { button: MouseButton::Left, .. } = event {
+                show_window(&ctx);
+            }
+        }));
+
+        Ok(Self {
+            _icon: tray_icon,
+            pause_item,
+        })
+    }
+
+    /// Keep the tray's pause check mark in sync with the window's button
+    pub fn set_paused(&self, paused: bool) {
+        if self.pause_item.is_checked() != paused {
+            self.pause_item.set_checked(paused);
+        }
+    }
+}
+
+/// Bring the window back from the tray
+pub fn show_window(ctx: &egui::Context) {
+    ctx.send_viewport_cmd(egui::ViewportCommand::Visible(true));
+    ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+    ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+    ctx.request_repaint();
+}
+
+/// Send the window to the tray
+pub fn hide_window(ctx: &egui::Context) {
+    ctx.send_viewport_cmd(egui::ViewportCommand::Visible(false));
+}
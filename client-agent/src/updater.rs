@@ -0,0 +1,176 @@
+//! Self-update from the server. A newer build is downloaded next to the
+//! running exe as `<exe>.new` and swapped in on the next launch, since a
+//! running exe can be renamed on Windows but not overwritten.
+
+use log::{info, warn};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Deserialize)]
+struct LatestVersion {
+    version: String,
+    download_url: String,
+    /// Published next to the binary on the server; updates without one are refused
+    #[serde(default)]
+    sha256: Option<String>,
+}
+
+fn staged_path(exe: &Path) -> PathBuf {
+    exe.with_extension("exe.new")
+}
+
+fn old_path(exe: &Path) -> PathBuf {
+    exe.with_extension("exe.old")
+}
+
+fn partial_path(exe: &Path) -> PathBuf {
+    exe.with_extension("exe.download")
+}
+
+/// Hex SHA-256 of a file, read in chunks so the whole exe isn't held in memory
+fn sha256_file(path: &Path) -> std::io::Result<String> {
+    use sha2::{Digest, Sha256};
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Parse "1.2.3" into comparable numeric parts
+fn parse_version(version: &str) -> Vec<u64> {
+    version
+        .trim()
+        .trim_start_matches('v')
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+/// Whether `latest` is newer than `current`
+fn is_newer(latest: &str, current: &str) -> bool {
+    parse_version(latest) > parse_version(current)
+}
+
+/// Swap in an update staged by a previous run and relaunch it.
+/// Call first thing on startup; returns normally when there's nothing to apply.
+pub fn apply_staged_update() {
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(_) => return,
+    };
+
+    // Leftovers from the last swap and from an interrupted download
+    let _ = std::fs::remove_file(old_path(&exe));
+    let _ = std::fs::remove_file(partial_path(&exe));
+
+    let staged = staged_path(&exe);
+    if !staged.exists() {
+        return;
+    }
+
+    info!("Applying staged update from {:?}", staged);
+
+    if let Err(e) = std::fs::rename(&exe, old_path(&exe)) {
+        warn!("Failed to move current exe aside: {}", e);
+        return;
+    }
+
+    if let Err(e) = std::fs::rename(&staged, &exe) {
+        warn!("Failed to install staged update: {}", e);
+        let _ = std::fs::rename(old_path(&exe), &exe);
+        return;
+    }
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match std::process::Command::new(&exe).args(&args).spawn() {
+        Ok(_) => {
+            info!("Relaunched updated client");
+            std::process::exit(0);
+        }
+        Err(e) => warn!("Failed to relaunch updated client, continuing with current version: {}", e),
+    }
+}
+
+/// Ask the server for the latest client version and stage it if it's newer.
+/// Returns the staged version, if any.
+pub async fn check_for_update(server_url: &str) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>> {
+    let client = reqwest::Client::builder()
+        .connect_timeout(std::time::Duration::from_secs(10))
+        .build()?;
+
+    let response = client
+        .get(format!("{}/api/client/latest-version", server_url))
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        // Server has no release published
+        return Ok(None);
+    }
+
+    let latest: LatestVersion = response.json().await?;
+    let current = env!("CARGO_PKG_VERSION");
+
+    if !is_newer(&latest.version, current) {
+        info!("Client is up to date ({})", current);
+        return Ok(None);
+    }
+
+    info!("Update available: {} -> {}", current, latest.version);
+
+    let expected = match latest.sha256.filter(|h| !h.is_empty()) {
+        Some(hash) => hash.to_lowercase(),
+        None => return Err("Server didn't publish a checksum for this update".into()),
+    };
+
+    let url = if latest.download_url.starts_with("http") {
+        latest.download_url
+    } else {
+        format!("{}{}", server_url, latest.download_url)
+    };
+
+    let exe = std::env::current_exe()?;
+    let staged = staged_path(&exe);
+    let partial = partial_path(&exe);
+
+    if let Err(e) = download_verified(&url, &partial, &expected).await {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e);
+    }
+
+    if let Err(e) = std::fs::rename(&partial, &staged) {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e.into());
+    }
+    info!("Staged update {} at {:?}", latest.version, staged);
+
+    Ok(Some(latest.version))
+}
+
+/// Download the update to `partial` and check it against the published hash
+async fn download_verified(url: &str, partial: &Path, expected: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    crate::downloader::Downloader::new()
+        .download_file(url, partial)
+        .await?;
+
+    if std::fs::metadata(partial)?.len() == 0 {
+        return Err("Downloaded update is empty".into());
+    }
+
+    let path = partial.to_path_buf();
+    let actual = tokio::task::spawn_blocking(move || sha256_file(&path)).await??;
+    if actual != expected {
+        return Err(format!("Update checksum mismatch: expected {}, got {}", expected, actual).into());
+    }
+
+    Ok(())
+}
@@ -14,12 +14,18 @@ let selectedGameId = null;
 let searchTimeout = null;
 let statusCheckInterval = null;
 let downloadPollInterval = null;
+let downloadEventSource = null;
+let libraryEnabled = false; // Server has LIBRARY_DIR set
 let currentView = 'games'; // 'games' or 'downloads'
 let favoriteIds = new Set();
+let wishlistIds = new Set();
 let showingFavorites = false;
+let showingNewGames = false;
 let selectedSource = 'all';
 let currentUser = null; // Stores current authenticated user
 let notificationPollInterval = null;
+let eventSocket = null;
+let eventSocketRetry = null;
 let viewMode = localStorage.getItem('viewMode') || 'list'; // 'list' or 'card'
 
 // ─── Authentication ───
@@ -52,17 +58,30 @@ function updateUserUI() {
 
     // Show user info
     document.getElementById('userName').textContent = currentUser.username;
+    const since = currentUser.created_at ? `Member since ${new Date(currentUser.created_at).toLocaleDateString()}` : '';
+    const lastLogin = currentUser.last_login ? `Last login ${formatDate(currentUser.last_login)}` : '';
+    document.getElementById('userInfo').title = [since, lastLogin].filter(Boolean).join(' · ');
     document.getElementById('userInfo').classList.remove('hidden');
     document.getElementById('logoutBtn').classList.remove('hidden');
 
     // Show admin badge if admin
     if (currentUser.is_admin) {
         document.getElementById('userAdmin').classList.remove('hidden');
+        document.getElementById('settingsTabBtnBackup').classList.remove('hidden');
+        document.getElementById('settingsThresholdsGroup').classList.remove('hidden');
+        document.getElementById('settingsCleanupGroup').classList.remove('hidden');
+        document.getElementById('settingsDiscordWebhookGroup').classList.remove('hidden');
+        document.getElementById('settingsDownloadWebhookGroup').classList.remove('hidden');
+        document.getElementById('settingsTelegramBotGroup').classList.remove('hidden');
+        document.getElementById('settingsGenreAliasesGroup').classList.remove('hidden');
+        document.getElementById('settingsDebridProviderGroup').classList.remove('hidden');
     }
 }
 
 async function handleLogout() {
     try {
+        // Stop pushing this user's notifications to a browser they've left
+        await unsubscribeFromPush();
         const response = await fetch(`${API_BASE}/auth/logout`, {
             method: 'POST',
             credentials: 'include'
@@ -87,12 +106,31 @@ document.addEventListener('DOMContentLoaded', async () => {
         return; // Will redirect to login
     }
 
+    // Back from the link in a verification email
+    const emailVerified = new URLSearchParams(window.location.search).get('email_verified');
+    if (emailVerified !== null) {
+        if (emailVerified === 'true') {
+            showToast('Email address verified', 'success');
+        } else {
+            showToast('That verification link is invalid or has expired', 'error');
+        }
+        history.replaceState(null, '', window.location.pathname);
+    }
+
+    // Keep this browser's push subscription tied to whoever is logged in
+    if ('Notification' in window && Notification.permission === 'granted') {
+        subscribeToPush();
+    }
+
     // Load app data
     loadGames();
     loadGenres();
+    loadSavedSearches();
+    loadNewGamesCount();
     loadFavoriteIds();
-    // Start notification polling
-    startNotificationPolling();
+    loadWishlistIds();
+    // Live notification counts and download changes (polls if the socket can't connect)
+    connectEventSocket();
     // Check if a scrape is already running (e.g. page refresh during scrape)
     checkScrapeStatus().then(() => {
         const container = document.getElementById('scrapeProgressContainer');
@@ -218,6 +256,8 @@ async function loadGames(page = 1, append = false) {
     if (sort) params.append('sort', sort);
     if (genre) params.append('genre', genre);
     if (selectedSource) params.append('source', selectedSource);
+    const linkType = document.getElementById('linkTypeSelect').value;
+    if (linkType) params.append('link_type', linkType);
 
     if (!append) {
         showLoading(true);
@@ -305,7 +345,7 @@ function setupScrollObserver() {
     if (!sentinel) return;
 
     scrollObserver = new IntersectionObserver((entries) => {
-        if (entries[0].isIntersecting && !isLoadingMore && !showingFavorites && currentPage < totalPages) {
+        if (entries[0].isIntersecting && !isLoadingMore && !showingFavorites && !showingNewGames && currentPage < totalPages) {
             loadGames(currentPage + 1, true);
         }
     }, { rootMargin: '400px' });
@@ -313,6 +353,11 @@ function setupScrollObserver() {
     scrollObserver.observe(sentinel);
 }
 
+// Thumbnails go through the server's cache instead of hotlinking the source site
+function thumbnailUrl(game) {
+    return `/api/thumbnails/${game.id}`;
+}
+
 // Build a single card's HTML (shared by renderGames and appendGames)
 function buildCardHtml(game) {
     const hasThumb = game.thumbnail_url && game.thumbnail_url.length > 0;
@@ -322,7 +367,7 @@ function buildCardHtml(game) {
     const sourceBadge = `<span class="source-badge ${source}">${sourceLabel}</span>`;
 
     const thumb = hasThumb
-        ? `<div class="game-thumb"><img src="${escapeHtml(game.thumbnail_url)}" alt="" loading="lazy" onerror="this.parentElement.classList.add('game-thumb-fallback');this.remove()">${sourceBadge}<button onclick="event.stopPropagation();toggleFavorite(${game.id})" class="fav-star" title="${isFav ? 'Remove from favorites' : 'Add to favorites'}">${isFav ? '⭐' : '☆'}</button></div>`
+        ? `<div class="game-thumb"><img src="${thumbnailUrl(game)}" alt="" loading="lazy" onerror="this.parentElement.classList.add('game-thumb-fallback');this.remove()">${sourceBadge}<button onclick="event.stopPropagation();toggleFavorite(${game.id})" class="fav-star" title="${isFav ? 'Remove from favorites' : 'Add to favorites'}">${isFav ? '⭐' : '☆'}</button></div>`
         : `<div class="game-thumb game-thumb-fallback"><span>🎮</span>${sourceBadge}<button onclick="event.stopPropagation();toggleFavorite(${game.id})" class="fav-star" title="${isFav ? 'Remove from favorites' : 'Add to favorites'}">${isFav ? '⭐' : '☆'}</button></div>`;
 
     const genres = game.genres
@@ -409,9 +454,14 @@ function clearFilters() {
     document.getElementById('searchInput').value = '';
     document.getElementById('sortSelect').value = '';
     document.getElementById('genreSelect').value = '';
+    document.getElementById('linkTypeSelect').value = '';
+    document.getElementById('savedSearchSelect').value = '';
+    document.getElementById('deleteSavedSearchBtn').classList.add('hidden');
     setSource('all');
     showingFavorites = false;
     document.getElementById('favToggle').classList.remove('bg-yellow-700');
+    showingNewGames = false;
+    document.getElementById('newGamesToggle').classList.remove('active');
     currentPage = 1;
     currentGames = [];
     loadGames();
@@ -455,17 +505,29 @@ window.addEventListener('DOMContentLoaded', () => {
 function switchLibraryTab(tab) {
     // Update tab active states
     document.getElementById('tabFavorites').classList.toggle('active', tab === 'favorites');
+    document.getElementById('tabWishlist').classList.toggle('active', tab === 'wishlist');
+    document.getElementById('tabHidden').classList.toggle('active', tab === 'hidden');
     document.getElementById('tabDownloaded').classList.toggle('active', tab === 'downloaded');
+    document.getElementById('tabHistory').classList.toggle('active', tab === 'history');
 
     // Show/hide tab content
     document.getElementById('libraryFavoritesTab').classList.toggle('hidden', tab !== 'favorites');
+    document.getElementById('libraryWishlistTab').classList.toggle('hidden', tab !== 'wishlist');
+    document.getElementById('libraryHiddenTab').classList.toggle('hidden', tab !== 'hidden');
     document.getElementById('libraryDownloadedTab').classList.toggle('hidden', tab !== 'downloaded');
+    document.getElementById('libraryHistoryTab').classList.toggle('hidden', tab !== 'history');
 
     // Load content
     if (tab === 'favorites') {
         loadLibraryFavorites();
+    } else if (tab === 'wishlist') {
+        loadLibraryWishlist();
+    } else if (tab === 'hidden') {
+        loadLibraryHidden();
     } else if (tab === 'downloaded') {
         loadLibraryDownloaded();
+    } else if (tab === 'history') {
+        loadLibraryHistory();
     }
 }
 
@@ -494,6 +556,123 @@ async function loadLibraryFavorites() {
     }
 }
 
+async function loadLibraryWishlist() {
+    const grid = document.getElementById('wishlistGrid');
+    const empty = document.getElementById('wishlistEmpty');
+
+    try {
+        const response = await fetch(`${API_BASE}/games/wishlist`);
+        if (!response.ok) throw new Error('Failed to load wishlist');
+
+        const data = await response.json();
+        const wishlistGames = data.wishlist || [];
+        wishlistIds = new Set(data.ids || []);
+
+        if (wishlistGames.length === 0) {
+            grid.innerHTML = '';
+            empty.classList.remove('hidden');
+            return;
+        }
+
+        empty.classList.add('hidden');
+        grid.innerHTML = wishlistGames.map(game => buildCardHtml(game)).join('');
+    } catch (error) {
+        console.error('Error loading wishlist:', error);
+        grid.innerHTML = '<p style="text-align:center;color:var(--text-dim);padding:2rem;">Failed to load wishlist.</p>';
+    }
+}
+
+async function loadLibraryHidden() {
+    const list = document.getElementById('hiddenList');
+    const empty = document.getElementById('hiddenEmpty');
+    const isAdmin = currentUser && currentUser.is_admin;
+
+    try {
+        const response = await fetch(`${API_BASE}/games/hidden`);
+        if (!response.ok) throw new Error('Failed to load hidden games');
+        const hiddenGames = (await response.json()).hidden || [];
+
+        let blacklist = [];
+        if (isAdmin) {
+            const blResponse = await fetch('/api/admin/blacklist');
+            if (blResponse.ok) blacklist = await blResponse.json();
+        }
+
+        if (hiddenGames.length === 0 && blacklist.length === 0) {
+            list.innerHTML = '';
+            empty.classList.remove('hidden');
+            return;
+        }
+
+        empty.classList.add('hidden');
+        const row = (label, detail, action) => `
+            <div style="display:flex;align-items:center;gap:1rem;padding:0.75rem 1rem;background:var(--bg-surface);border-radius:10px;margin-bottom:0.5rem;">
+                <div style="flex:1;min-width:0;">
+                    <div style="font-weight:600;overflow:hidden;text-overflow:ellipsis;white-space:nowrap;">${escapeHtml(label)}</div>
+                    <div style="font-size:0.75rem;color:var(--text-dim);">${escapeHtml(detail)}</div>
+                </div>
+                ${action}
+            </div>`;
+
+        list.innerHTML =
+            hiddenGames.map(g => row(g.title, `${g.source} · ${g.file_size}`,
+                `<button onclick="unhideGame(${g.id})" class="btn btn-ghost">Unhide</button>`)).join('') +
+            (blacklist.length > 0
+                ? `<h3 style="font-size:1rem;font-weight:700;margin:1.5rem 0 0.75rem;">Blacklisted for everyone</h3>` +
+                  blacklist.map(b => row(b.title, b.reason ? `${b.source} · ${b.reason}` : b.source,
+                      `<button onclick="unblacklistGame(${b.id})" class="btn btn-ghost">Remove</button>`)).join('')
+                : '');
+    } catch (error) {
+        console.error('Error loading hidden games:', error);
+        list.innerHTML = '<p style="text-align:center;color:var(--text-dim);padding:2rem;">Failed to load hidden games.</p>';
+    }
+}
+
+let historyPage = 1;
+
+async function loadLibraryHistory(page = 1) {
+    const list = document.getElementById('historyList');
+    const empty = document.getElementById('historyEmpty');
+    const more = document.getElementById('historyMore');
+
+    try {
+        const response = await fetch(`${API_BASE}/downloads/history?page=${page}`);
+        if (!response.ok) throw new Error('Failed to load download history');
+        const data = await response.json();
+        historyPage = data.page;
+
+        if (data.total === 0) {
+            list.innerHTML = '';
+            empty.classList.remove('hidden');
+            more.classList.add('hidden');
+            return;
+        }
+
+        empty.classList.add('hidden');
+        const rows = data.downloads.map(d => {
+            const detail = [
+                d.status.charAt(0).toUpperCase() + d.status.slice(1),
+                formatDate(d.completed_at || d.created_at),
+                d.total_bytes ? formatBytes(d.total_bytes) : null,
+                d.status === 'failed' ? d.error_message : null,
+            ].filter(Boolean).join(' · ');
+            return `
+            <div style="display:flex;align-items:center;gap:1rem;padding:0.75rem 1rem;background:var(--bg-surface);border-radius:10px;margin-bottom:0.5rem;">
+                <div style="flex:1;min-width:0;">
+                    <div style="font-weight:600;overflow:hidden;text-overflow:ellipsis;white-space:nowrap;">${escapeHtml(d.game_title)}</div>
+                    <div style="font-size:0.75rem;color:var(--text-dim);">${escapeHtml(detail)}</div>
+                </div>
+            </div>`;
+        }).join('');
+
+        list.innerHTML = page === 1 ? rows : list.innerHTML + rows;
+        more.classList.toggle('hidden', data.page >= data.total_pages);
+    } catch (error) {
+        console.error('Error loading download history:', error);
+        list.innerHTML = '<p style="text-align:center;color:var(--text-dim);padding:2rem;">Failed to load download history.</p>';
+    }
+}
+
 async function loadLibraryDownloaded() {
     const grid = document.getElementById('downloadedGrid');
     const empty = document.getElementById('downloadedEmpty');
@@ -548,6 +727,7 @@ function showGameModal(gameId) {
 
     selectedGameId = gameId;
     const isFav = favoriteIds.has(gameId);
+    const isWished = wishlistIds.has(gameId);
 
     // Parse screenshots (stored as ||| separated URLs)
     modalScreenshots = game.screenshots ? game.screenshots.split('|||').filter(s => s.length > 0) : [];
@@ -555,7 +735,7 @@ function showGameModal(gameId) {
 
     // If no screenshots, use thumbnail as the only image
     if (modalScreenshots.length === 0 && game.thumbnail_url) {
-        modalScreenshots = [game.thumbnail_url];
+        modalScreenshots = [thumbnailUrl(game)];
     }
 
     const galleryHtml = modalScreenshots.length > 0 ? `
@@ -605,8 +785,14 @@ function showGameModal(gameId) {
         <p style="margin-bottom:0.25rem;font-size:0.85rem"><strong>Repack Size:</strong> ${escapeHtml(game.file_size)}</p>
         ${origSize}
         ${sourceLink}
+        <div id="modalCommunity" style="margin-top:0.5rem"></div>
+        <label style="display:flex;align-items:center;gap:0.5rem;margin-top:0.5rem;font-size:0.85rem;cursor:pointer">
+            <input type="checkbox" id="skipJunkCheckbox" style="width:16px;height:16px;cursor:pointer">
+            <span>Skip samples, readmes and other extras</span>
+        </label>
     `;
     document.getElementById('confirmModal').classList.remove('hidden');
+    loadModalCommunity(gameId);
 
     const btnContainer = document.getElementById('confirmBtnContainer');
     btnContainer.innerHTML = `
@@ -615,6 +801,13 @@ function showGameModal(gameId) {
                 class="btn ${isFav ? 'btn-gold' : 'btn-ghost'}">
             ${isFav ? '⭐ Favorited' : '☆ Favorite'}
         </button>
+        <button onclick="toggleWishlist(${gameId})"
+                id="modalWishBtn"
+                class="btn ${isWished ? 'btn-gold' : 'btn-ghost'}">
+            ${isWished ? '🔖 Wishlisted' : '🔖 Wishlist'}
+        </button>
+        <button onclick="hideGame(${gameId})" class="btn btn-ghost" title="Stop showing this game in your list">🚫 Hide</button>
+        ${currentUser && currentUser.is_admin ? `<button onclick="blacklistGame(${gameId})" class="btn btn-ghost" title="Hide this game from everyone, including after rescrapes">⛔ Blacklist</button>` : ''}
         <button id="downloadBtn" onclick="queueDownload(${gameId})"
                 class="btn btn-primary" style="flex:1">
             Download
@@ -630,6 +823,64 @@ function showGameModal(gameId) {
     `;
 }
 
+// Community install stats plus buttons to add your own result
+async function loadModalCommunity(gameId) {
+    try {
+        const response = await fetch(`${API_BASE}/games/${gameId}`);
+        if (!response.ok) return;
+        const detail = await response.json();
+        renderModalCommunity(gameId, detail.community);
+    } catch (error) {
+        console.error('Failed to load community stats:', error);
+    }
+}
+
+function renderModalCommunity(gameId, stats) {
+    const container = document.getElementById('modalCommunity');
+    if (!container || selectedGameId !== gameId) return;
+
+    let summary = 'No install reports yet';
+    if (stats && stats.total_ratings > 0) {
+        const parts = [`${stats.total_ratings} report${stats.total_ratings === 1 ? '' : 's'}`];
+        if (stats.success_rate != null) parts.push(`${Math.round(stats.success_rate * 100)}% installed fine`);
+        if (stats.avg_difficulty != null) parts.push(`difficulty ${stats.avg_difficulty.toFixed(1)}/5`);
+        summary = parts.join(' · ');
+    }
+    const lowSuccess = stats && stats.total_ratings >= 3 && stats.success_rate != null && stats.success_rate < 0.7;
+
+    container.innerHTML = `
+        <div style="display:flex;align-items:center;gap:0.5rem;flex-wrap:wrap;font-size:0.8rem">
+            <span style="color:${lowSuccess ? 'var(--gold)' : 'var(--text-muted)'}">${lowSuccess ? '⚠️ ' : ''}Community: ${summary}</span>
+            <button class="btn btn-ghost" style="padding:0.15rem 0.5rem;font-size:0.75rem" onclick="rateGame(${gameId}, true)">👍 Installed fine</button>
+            <button class="btn btn-ghost" style="padding:0.15rem 0.5rem;font-size:0.75rem" onclick="rateGame(${gameId}, false)">👎 Had issues</button>
+        </div>
+    `;
+}
+
+async function rateGame(gameId, installSuccess) {
+    let issues = null;
+    if (!installSuccess) {
+        issues = prompt('What went wrong? (optional)');
+        if (issues === null) return;
+    }
+    try {
+        const response = await fetch(`${API_BASE}/games/${gameId}/rating`, {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({ install_success: installSuccess, issues_encountered: issues || null }),
+        });
+        const data = await response.json();
+        if (!response.ok) {
+            showToast(apiErrorMessage(data, 'Failed to save rating'), 'error');
+            return;
+        }
+        showToast('Thanks for the report!', 'success');
+        renderModalCommunity(gameId, data);
+    } catch (error) {
+        showToast('Failed to save rating', 'error');
+    }
+}
+
 function prevScreenshot() {
     modalScreenshotIndex = (modalScreenshotIndex - 1 + modalScreenshots.length) % modalScreenshots.length;
     updateScreenshot();
@@ -666,6 +917,14 @@ function updateModalFavBtn(gameId) {
     btn.innerHTML = isFav ? '⭐ Favorited' : '☆ Favorite';
 }
 
+function updateModalWishBtn(gameId) {
+    const btn = document.getElementById('modalWishBtn');
+    if (!btn) return;
+    const isWished = wishlistIds.has(gameId);
+    btn.className = `btn ${isWished ? 'btn-gold' : 'btn-ghost'}`;
+    btn.innerHTML = isWished ? '🔖 Wishlisted' : '🔖 Wishlist';
+}
+
 function hideConfirmModal() {
     document.getElementById('confirmModal').classList.add('hidden');
     selectedGameId = null;
@@ -673,6 +932,29 @@ function hideConfirmModal() {
 
 // ─── Queue Download ───
 
+// Nudge the desktop client so it picks up the download now instead of on its
+// next poll: through its local server when we hold its token, otherwise via the
+// fitgirl:// handler. Does nothing if it isn't installed.
+async function wakeClient(gameId) {
+    const token = localStorage.getItem('clientBridgeToken');
+    if (token) {
+        try {
+            const response = await fetch(`http://127.0.0.1:9999/download/${encodeURIComponent(gameId)}`, {
+                headers: { 'X-Bridge-Token': token }
+            });
+            if (response.ok) return;
+        } catch (e) {
+            // Client not running on this PC
+        }
+    }
+
+    const frame = document.createElement('iframe');
+    frame.style.display = 'none';
+    frame.src = `fitgirl://download/${encodeURIComponent(gameId)}`;
+    document.body.appendChild(frame);
+    setTimeout(() => frame.remove(), 2000);
+}
+
 async function queueDownload(gameId) {
     const downloadBtn = document.getElementById('downloadBtn');
     if (downloadBtn) {
@@ -708,15 +990,17 @@ async function queueDownload(gameId) {
             downloadBtn.innerHTML = '<span class="spinner"></span> Creating download...';
         }
 
+        const skipJunk = document.getElementById('skipJunkCheckbox')?.checked || false;
         const response = await fetch(`${API_BASE}/downloads/create`, {
             method: 'POST',
             headers: { 'Content-Type': 'application/json' },
-            body: JSON.stringify({ game_id: gameId })
+            body: JSON.stringify({ game_id: gameId, skip_junk: skipJunk })
         });
 
         const data = await response.json();
 
         if (data.success) {
+            wakeClient(gameId);
             showToast('✅ Download queued! Your client will start downloading shortly.', 'success');
             hideConfirmModal();
             // Optionally refresh downloads view
@@ -724,7 +1008,7 @@ async function queueDownload(gameId) {
                 setTimeout(() => loadDownloads(), 1000);
             }
         } else {
-            showToast(data.message || 'Failed to queue download', 'error');
+            showToast(apiErrorMessage(data, 'Failed to queue download'), 'error');
             if (downloadBtn) {
                 downloadBtn.disabled = false;
                 downloadBtn.innerHTML = 'Download';
@@ -761,7 +1045,7 @@ async function addToRealDebrid(gameId) {
         if (data.success && data.downloads && data.downloads.length > 0) {
             showDownloadLinks(data.downloads, data.message);
         } else {
-            showToast(data.message || 'No download links available', 'error');
+            showToast(apiErrorMessage(data, 'No download links available'), 'error');
             hideConfirmModal();
         }
     } catch (error) {
@@ -817,10 +1101,36 @@ function copyAllLinks() {
 
 function startDownloadPolling() {
     stopDownloadPolling();
-    downloadPollInterval = setInterval(loadDownloads, 2000);
+
+    // Prefer server-pushed updates; fall back to polling if the stream fails
+    if (window.EventSource) {
+        downloadEventSource = new EventSource(`${API_BASE}/downloads/stream`);
+        downloadEventSource.addEventListener('downloads', (e) => {
+            try {
+                const data = JSON.parse(e.data);
+                applyDownloads(data.downloads, data.library_enabled);
+            } catch (error) {
+                console.error('Error parsing download stream:', error);
+            }
+        });
+        downloadEventSource.onerror = () => {
+            if (downloadEventSource && downloadEventSource.readyState === EventSource.CLOSED) {
+                downloadEventSource = null;
+                // Status changes still arrive over the event socket, just without live progress
+                if (!isEventSocketOpen()) downloadPollInterval = setInterval(loadDownloads, 2000);
+            }
+        };
+        return;
+    }
+
+    if (!isEventSocketOpen()) downloadPollInterval = setInterval(loadDownloads, 2000);
 }
 
 function stopDownloadPolling() {
+    if (downloadEventSource) {
+        downloadEventSource.close();
+        downloadEventSource = null;
+    }
     if (downloadPollInterval) {
         clearInterval(downloadPollInterval);
         downloadPollInterval = null;
@@ -833,25 +1143,30 @@ async function loadDownloads() {
         if (!response.ok) throw new Error('Failed to load downloads');
 
         const data = await response.json();
-        renderDownloads(data.downloads);
-
-        // Update badge count (both topbar and sidebar)
-        const activeCount = data.downloads.filter(d =>
-            d.status === 'queued' || d.status === 'downloading' || d.status === 'extracting'
-        ).length;
-
-        const sidebarBadge = document.getElementById('sidebarDownloadBadge');
-        if (activeCount > 0) {
-            sidebarBadge.textContent = activeCount;
-            sidebarBadge.classList.remove('hidden');
-        } else {
-            sidebarBadge.classList.add('hidden');
-        }
+        applyDownloads(data.downloads, data.library_enabled);
     } catch (error) {
         console.error('Error loading downloads:', error);
     }
 }
 
+function applyDownloads(downloads, hasLibrary) {
+    libraryEnabled = !!hasLibrary;
+    renderDownloads(downloads);
+
+    // Update badge count (both topbar and sidebar)
+    const activeCount = downloads.filter(d =>
+        d.status === 'queued' || d.status === 'downloading' || d.status === 'extracting'
+    ).length;
+
+    const sidebarBadge = document.getElementById('sidebarDownloadBadge');
+    if (activeCount > 0) {
+        sidebarBadge.textContent = activeCount;
+        sidebarBadge.classList.remove('hidden');
+    } else {
+        sidebarBadge.classList.add('hidden');
+    }
+}
+
 function renderDownloads(downloads) {
     const container = document.getElementById('downloadsList');
 
@@ -881,17 +1196,35 @@ function renderDownloads(downloads) {
                 actionsHtml = `<button onclick="cancelDownload(${dl.id})" class="btn btn-ghost" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem;color:var(--red)">Cancel</button>`;
                 break;
 
+            case 'pending':
+                statsHtml = '<span style="color:var(--gold)">Waiting for your PC to start the download...</span>'
+                    + (dl.install_dir ? `<span style="margin:0 0.35rem;color:var(--text-dim)">·</span><span>Installs to ${escapeHtml(dl.install_dir)}</span>` : '');
+                actionsHtml = `<button onclick="setInstallDir(${dl.id}, '${escapeHtml(dl.install_dir || '').replace(/\\/g, '\\\\')}')" class="btn btn-ghost" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem" title="Choose where your PC installs this game">📁 Install Folder</button>
+                    <button onclick="moveDownload(${dl.id})" class="btn btn-ghost" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem" title="Download this on another of your PCs">💻 Move</button>
+                    <button onclick="cancelDownload(${dl.id})" class="btn btn-ghost" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem;color:var(--red)">Cancel</button>`;
+                break;
+
             case 'downloading':
                 const speedStr = dl.download_speed || '—';
                 const etaStr = dl.eta || '—';
                 const progressStr = `${progressPct.toFixed(1)}%`;
-                statsHtml = `
-                    <span>${progressStr}</span>
-                    <span style="margin:0 0.35rem;color:var(--text-dim)">·</span>
-                    <span>${speedStr}</span>
-                    <span style="margin:0 0.35rem;color:var(--text-dim)">·</span>
-                    <span>${etaStr} remaining</span>
-                `;
+                if (dl.debrid_status) {
+                    // Debrid service is still fetching the torrent on its side
+                    statsHtml = `
+                        <span style="color:var(--gold)">${escapeHtml(dl.debrid_status)}</span>
+                        <span style="margin:0 0.35rem;color:var(--text-dim)">·</span>
+                        <span>${progressStr}</span>
+                        ${dl.download_speed ? `<span style="margin:0 0.35rem;color:var(--text-dim)">·</span><span>${speedStr}</span>` : ''}
+                    `;
+                } else {
+                    statsHtml = `
+                        <span>${progressStr}</span>
+                        <span style="margin:0 0.35rem;color:var(--text-dim)">·</span>
+                        <span>${speedStr}</span>
+                        <span style="margin:0 0.35rem;color:var(--text-dim)">·</span>
+                        <span>${etaStr} remaining</span>
+                    `;
+                }
                 actionsHtml = `<button onclick="cancelDownload(${dl.id})" class="btn btn-ghost" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem;color:var(--red)">Cancel</button>`;
                 break;
 
@@ -902,7 +1235,10 @@ function renderDownloads(downloads) {
                     const filesInfo = ep.files_total > 0
                         ? `${ep.files_done}/${ep.files_total} files`
                         : `${ep.files_done} files`;
-                    statsHtml = `<span style="color:var(--purple)">${escapeHtml(ep.message)}</span>`;
+                    const fileInfo = ep.current_file && ep.file_percent != null
+                        ? `<span style="margin:0 0.35rem;color:var(--text-dim)">·</span><span>${escapeHtml(ep.current_file)} ${Math.round(ep.file_percent)}%</span>`
+                        : '';
+                    statsHtml = `<span style="color:var(--purple)">${escapeHtml(ep.message)}</span>${fileInfo}`;
                     extractPct = epPct;
                     extractInfo = filesInfo;
                 } else {
@@ -911,26 +1247,26 @@ function renderDownloads(downloads) {
                 break;
 
             case 'completed':
-                statsHtml = `<span style="color:var(--green)">Ready to install${dl.completed_at ? ' · ' + formatDate(dl.completed_at) : ''}</span>`;
+                statsHtml = `<span style="color:var(--green)">Ready to install${dl.completed_at ? ' · ' + formatDate(dl.completed_at) : ''}</span>${getMd5StatusHtml(dl.md5_status)}`;
                 const hasMultipleFiles = dl.files && dl.files.length > 1;
                 const md5ButtonDisabled = !dl.has_md5;
                 actionsHtml = `
                     ${dl.installer_path ? `<button onclick="launchInstall(${dl.id})" class="btn btn-primary" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem">Install</button>` : ''}
-                    <button
-                        onclick="${md5ButtonDisabled ? 'showToast(\'No MD5 file found in download\', \'error\')' : `validateMD5(${dl.id})`}"
-                        class="btn btn-ghost"
-                        style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem;${md5ButtonDisabled ? 'opacity:0.4;cursor:not-allowed' : ''}"
-                        ${md5ButtonDisabled ? 'disabled' : ''}>
-                        ✓ Validate MD5
-                    </button>
+                    ${md5ButtonDisabled
+                        ? `<button onclick="generateMD5(${dl.id})" class="btn btn-ghost" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem" title="Hash the current files so they can be re-validated later"># Generate MD5</button>`
+                        : `<button onclick="validateMD5(${dl.id})" class="btn btn-ghost" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem">✓ Validate MD5</button>`}
                     ${hasMultipleFiles ? `<button onclick="downloadAllFiles(${dl.id})" class="btn btn-ghost" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem">⬇ Download All</button>` : ''}
                     ${dl.file_path ? `<button onclick="copyPath('${escapeHtml(dl.file_path)}')" class="btn btn-ghost" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem">Copy Path</button>` : ''}
+                    ${libraryEnabled ? `<button onclick="archiveDownload(${dl.id})" class="btn btn-ghost" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem" title="Move the game folder out of the download directory into the library">📚 Move to Library</button>` : ''}
+                    ${canReextract(dl) ? `<button onclick="reextractDownload(${dl.id})" class="btn btn-ghost" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem" title="Extract the archives still in the download folder again">♻ Re-extract</button>` : ''}
                     <button onclick="deleteDownload(${dl.id}, '${escapeHtml(dl.game_title).replace(/'/g, "\\'")}')" class="btn btn-ghost" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem;color:var(--red)">🗑 Delete Files</button>
                 `;
                 break;
 
             case 'installing':
-                statsHtml = '<span style="color:var(--purple)">Installer launched — complete the setup wizard</span>';
+                statsHtml = dl.install_step
+                    ? `<span style="color:var(--purple)">${escapeHtml(dl.install_step)}</span>`
+                    : '<span style="color:var(--purple)">Installer launched — complete the setup wizard</span>';
                 actionsHtml = `
                     <button onclick="markInstalled(${dl.id})" class="btn btn-primary" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem">Done Installing</button>
                     <button onclick="launchInstall(${dl.id})" class="btn btn-ghost" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem">Relaunch</button>
@@ -942,23 +1278,25 @@ function renderDownloads(downloads) {
                 const hasMultipleFilesInstalled = dl.files && dl.files.length > 1;
                 const md5ButtonDisabledInstalled = !dl.has_md5;
                 actionsHtml = `
-                    <button
-                        onclick="${md5ButtonDisabledInstalled ? 'showToast(\'No MD5 file found in download\', \'error\')' : `validateMD5(${dl.id})`}"
-                        class="btn btn-ghost"
-                        style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem;${md5ButtonDisabledInstalled ? 'opacity:0.4;cursor:not-allowed' : ''}"
-                        ${md5ButtonDisabledInstalled ? 'disabled' : ''}>
-                        ✓ Validate MD5
-                    </button>
+                    ${md5ButtonDisabledInstalled
+                        ? `<button onclick="generateMD5(${dl.id})" class="btn btn-ghost" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem" title="Hash the current files so they can be re-validated later"># Generate MD5</button>`
+                        : `<button onclick="validateMD5(${dl.id})" class="btn btn-ghost" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem">✓ Validate MD5</button>`}
                     ${hasMultipleFilesInstalled ? `<button onclick="downloadAllFiles(${dl.id})" class="btn btn-ghost" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem">⬇ Download All</button>` : ''}
                     ${dl.file_path ? `<button onclick="copyPath('${escapeHtml(dl.file_path)}')" class="btn btn-ghost" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem">Copy Path</button>` : ''}
+                    ${libraryEnabled ? `<button onclick="archiveDownload(${dl.id})" class="btn btn-ghost" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem" title="Move the game folder out of the download directory into the library">📚 Move to Library</button>` : ''}
                     <button onclick="deleteDownload(${dl.id}, '${escapeHtml(dl.game_title).replace(/'/g, "\\'")}')" class="btn btn-ghost" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem;color:var(--red)">🗑 Delete Files</button>
                 `;
                 break;
 
             case 'failed':
                 statsHtml = `<span style="color:var(--red)">${escapeHtml(dl.error_message || 'Unknown error')}</span>`;
+                const needsPassword = (dl.error_message || '').includes('Password required');
+                // Extracting a corrupt or incomplete archive again can't help
+                const needsRedownload = /^Archive (corrupt|incomplete)/.test(dl.error_message || '');
                 actionsHtml = `
-                    <button onclick="retryDownload(${dl.id})" class="btn btn-gold" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem">Retry</button>
+                    ${needsPassword ? `<button onclick="enterArchivePassword(${dl.id})" class="btn btn-primary" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem">🔑 Enter Password</button>` : ''}
+                    <button onclick="retryDownload(${dl.id})" class="btn btn-gold" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem">${needsRedownload ? 'Re-download' : 'Retry'}</button>
+                    ${canReextract(dl) && !needsRedownload ? `<button onclick="reextractDownload(${dl.id})" class="btn btn-ghost" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem" title="Extract the archives still in the download folder again">♻ Re-extract</button>` : ''}
                     <button onclick="removeDownload(${dl.id})" class="btn btn-ghost" style="flex:0;padding:0.35rem 0.75rem;font-size:0.75rem">Remove</button>
                 `;
                 break;
@@ -1029,7 +1367,7 @@ async function cancelDownload(id) {
             showToast('Download cancelled', 'info');
             loadDownloads();
         } else {
-            showToast(data.message, 'error');
+            showToast(apiErrorMessage(data, 'Request failed'), 'error');
         }
     } catch (error) {
         showToast('Error cancelling download', 'error');
@@ -1044,13 +1382,35 @@ async function retryDownload(id) {
             showToast('Download requeued', 'success');
             loadDownloads();
         } else {
-            showToast(data.message, 'error');
+            showToast(apiErrorMessage(data, 'Request failed'), 'error');
         }
     } catch (error) {
         showToast('Error retrying download', 'error');
     }
 }
 
+async function enterArchivePassword(id) {
+    const password = prompt('This archive is password protected. Enter the password:');
+    if (!password) return;
+
+    try {
+        const response = await fetch(`${API_BASE}/downloads/${id}/password`, {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({ password })
+        });
+        const data = await response.json();
+        if (data.success) {
+            showToast(data.message, 'success');
+            loadDownloads();
+        } else {
+            showToast(apiErrorMessage(data, 'Request failed'), 'error');
+        }
+    } catch (error) {
+        showToast('Error saving password', 'error');
+    }
+}
+
 async function removeDownload(id) {
     try {
         const response = await fetch(`${API_BASE}/downloads/${id}/remove`, { method: 'DELETE' });
@@ -1059,7 +1419,7 @@ async function removeDownload(id) {
             showToast('Download removed from list', 'info');
             loadDownloads();
         } else {
-            showToast(data.message, 'error');
+            showToast(apiErrorMessage(data, 'Request failed'), 'error');
         }
     } catch (error) {
         showToast('Error removing download', 'error');
@@ -1078,7 +1438,7 @@ async function deleteDownload(id, gameName) {
             showToast('Download and files deleted permanently', 'success');
             loadDownloads();
         } else {
-            showToast(data.message, 'error');
+            showToast(apiErrorMessage(data, 'Request failed'), 'error');
         }
     } catch (error) {
         showToast('Error deleting download', 'error');
@@ -1092,10 +1452,13 @@ async function scanExistingGames() {
         const response = await fetch(`${API_BASE}/downloads/scan`, { method: 'POST' });
         const data = await response.json();
         if (data.success) {
-            showToast(data.message, 'success');
+            showToast(`Imported ${data.imported} existing game(s)`, 'success');
+            if (data.unmatched && data.unmatched.length > 0) {
+                alert(`These folders didn't match any game. Rename them to the game's title and scan again:\n\n${data.unmatched.join('\n')}`);
+            }
             loadDownloads();
         } else {
-            showToast(data.message, 'error');
+            showToast(apiErrorMessage(data, 'Request failed'), 'error');
         }
     } catch (error) {
         showToast('Error scanning games', 'error');
@@ -1180,7 +1543,7 @@ async function launchInstall(id) {
             showToast('Installer launched! Complete the setup wizard.', 'success');
             loadDownloads();
         } else {
-            showToast(data.message, 'error');
+            showToast(apiErrorMessage(data, 'Request failed'), 'error');
         }
     } catch (error) {
         showToast('Error launching installer', 'error');
@@ -1195,21 +1558,133 @@ async function markInstalled(id) {
             showToast('Marked as installed!', 'success');
             loadDownloads();
         } else {
-            showToast(data.message, 'error');
+            showToast(apiErrorMessage(data, 'Request failed'), 'error');
         }
     } catch (error) {
         showToast('Error updating status', 'error');
     }
 }
 
+async function setInstallDir(id, current) {
+    const dir = prompt('Install folder on your PC (leave empty for the default):', current || '');
+    if (dir === null) return;
+    try {
+        const response = await fetch(`${API_BASE}/downloads/${id}/install-dir`, {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({ install_dir: dir.trim() || null })
+        });
+        const data = await response.json();
+        if (data.success) {
+            showToast(data.message, 'success');
+            loadDownloads();
+        } else {
+            showToast(apiErrorMessage(data, 'Request failed'), 'error');
+        }
+    } catch (error) {
+        showToast('Error setting install folder', 'error');
+    }
+}
+
+async function moveDownload(id) {
+    try {
+        const response = await fetch(`${API_BASE}/clients/mine`);
+        if (!response.ok) throw new Error('Failed to load clients');
+        const clients = (await response.json()).linked || [];
+        if (clients.length < 2) {
+            showToast('Link another PC to your account to move downloads to it', 'info');
+            return;
+        }
+
+        const choices = clients.map((c, i) =>
+            `${i + 1}. ${c.client_name || 'Unnamed Client'} (${c.is_online ? 'online' : 'offline'})`).join('\n');
+        const answer = prompt(`Move this download to which PC?\n\n${choices}`);
+        if (answer === null) return;
+        const client = clients[parseInt(answer, 10) - 1];
+        if (!client) {
+            showToast('Pick one of the listed numbers', 'error');
+            return;
+        }
+
+        const assign = await fetch(`${API_BASE}/downloads/${id}/assign`, {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({ client_id: client.client_id })
+        });
+        const data = await assign.json();
+        if (data.success) {
+            showToast(data.message, 'success');
+            loadDownloads();
+        } else {
+            showToast(apiErrorMessage(data, 'Request failed'), 'error');
+        }
+    } catch (error) {
+        showToast('Error moving download', 'error');
+    }
+}
+
+// Server-side downloads with archives that weren't extracted
+function canReextract(dl) {
+    return dl.files && dl.files.some(f => !f.is_extracted && /\.(zip|7z|rar|r\d{2,}|\d{3})$/i.test(f.filename));
+}
+
+async function reextractDownload(id) {
+    try {
+        const response = await fetch(`${API_BASE}/downloads/${id}/reextract`, { method: 'POST' });
+        const data = await response.json();
+        if (data.success) {
+            showToast(data.message, 'success');
+            loadDownloads();
+        } else {
+            showToast(apiErrorMessage(data, 'Request failed'), 'error');
+        }
+    } catch (error) {
+        showToast('Error starting re-extraction', 'error');
+    }
+}
+
+async function archiveDownload(id) {
+    try {
+        showToast('Moving to library...', 'info');
+        const response = await fetch(`${API_BASE}/downloads/${id}/archive`, { method: 'POST' });
+        const data = await response.json();
+        if (data.success) {
+            showToast(data.message, 'success');
+            loadDownloads();
+        } else {
+            showToast(apiErrorMessage(data, 'Request failed'), 'error');
+        }
+    } catch (error) {
+        showToast('Error moving to library', 'error');
+    }
+}
+
+async function generateMD5(id) {
+    try {
+        showToast('Hashing files, this can take a while...', 'info');
+        const response = await fetch(`${API_BASE}/downloads/${id}/generate-md5`, { method: 'POST' });
+
+        if (!response.ok) {
+            showToast(apiErrorMessage(await response.json().catch(() => null), 'Failed to generate MD5'), 'error');
+            return;
+        }
+
+        const result = await response.json();
+        showToast(`MD5 manifest written for ${result.files_hashed} files`, 'success');
+        loadDownloads();
+    } catch (error) {
+        showToast(`Failed to generate MD5: ${error.message}`, 'error');
+    }
+}
+
 async function validateMD5(id) {
     try {
-        showToast('Validating MD5 checksums...', 'info');
+        showToast('Validating checksums...', 'info');
         const response = await fetch(`${API_BASE}/downloads/${id}/validate`, { method: 'POST' });
 
         if (!response.ok) {
-            const errorText = await response.text();
-            showToast(`Validation failed: ${errorText}`, 'error');
+            const data = await response.json().catch(() => null);
+            showToast(`Validation failed: ${apiErrorMessage(data, response.statusText)}`, 'error');
             return;
         }
 
@@ -1222,7 +1697,7 @@ async function validateMD5(id) {
                     ${result.status}
                 </h3>
                 <div style="font-size:0.875rem;color:var(--text-secondary)">
-                    Total: ${result.total_files} | Valid: ${result.validated} | Failed: ${result.failed} | Skipped: ${result.skipped}
+                    ${result.algorithm ? result.algorithm.toUpperCase() + ' | ' : ''}Total: ${result.total_files} | Valid: ${result.validated} | Failed: ${result.failed} | Skipped: ${result.skipped}
                 </div>
             </div>
         `;
@@ -1300,6 +1775,21 @@ function showUploadModal() {
     document.getElementById('uploadError').classList.add('hidden');
 }
 
+// Downloads the games matching the current search, sort, genre and source
+function exportGames(format) {
+    const params = new URLSearchParams({ extended: 'true' });
+    const search = document.getElementById('searchInput').value;
+    const sort = document.getElementById('sortSelect').value;
+    const genre = document.getElementById('genreSelect').value;
+    if (search) params.append('search', search);
+    if (sort) params.append('sort', sort);
+    if (genre) params.append('genre', genre);
+    if (selectedSource) params.append('source', selectedSource);
+    const linkType = document.getElementById('linkTypeSelect').value;
+    if (linkType) params.append('link_type', linkType);
+    window.location.href = `${API_BASE}/games/export.${format}?${params}`;
+}
+
 function hideUploadModal() {
     document.getElementById('uploadModal').classList.add('hidden');
     document.getElementById('csvFile').value = '';
@@ -1315,11 +1805,13 @@ async function uploadCSV() {
     uploadError.classList.add('hidden');
 
     if (!file) {
-        uploadError.textContent = 'Please select a CSV file';
+        uploadError.textContent = 'Please select a CSV or JSON file';
         uploadError.classList.remove('hidden');
         return;
     }
 
+    // JSON exports carry genres, images and sources; CSV only title, size and link
+    const isJson = file.name.toLowerCase().endsWith('.json');
     const formData = new FormData();
     formData.append('file', file);
 
@@ -1327,10 +1819,16 @@ async function uploadCSV() {
     uploadBtn.textContent = 'Uploading...';
 
     try {
-        const response = await fetch(`${API_BASE}/games/upload`, {
-            method: 'POST',
-            body: formData
-        });
+        const response = isJson
+            ? await fetch(`${API_BASE}/games/import.json?mode=replace`, {
+                method: 'POST',
+                headers: { 'Content-Type': 'application/json' },
+                body: file
+            })
+            : await fetch(`${API_BASE}/games/upload`, {
+                method: 'POST',
+                body: formData
+            });
 
         const data = await response.json();
 
@@ -1339,7 +1837,7 @@ async function uploadCSV() {
             hideUploadModal();
             loadGames();
         } else {
-            uploadError.textContent = data.message;
+            uploadError.textContent = apiErrorMessage(data, 'Error uploading CSV');
             uploadError.classList.remove('hidden');
         }
     } catch (error) {
@@ -1366,7 +1864,7 @@ async function rescrape() {
             showToast(data.message, 'info');
             startStatusPolling();
         } else {
-            showToast(data.message, 'error');
+            showToast(apiErrorMessage(data, 'Request failed'), 'error');
         }
     } catch (error) {
         showToast('Error starting scrape', 'error');
@@ -1465,6 +1963,21 @@ function getStatusLabel(status) {
     }
 }
 
+function getMd5StatusHtml(md5Status) {
+    if (!md5Status) return '';
+    let label, color;
+    if (md5Status === 'pending') {
+        label = 'Verifying MD5...'; color = 'var(--gold)';
+    } else if (md5Status === 'valid') {
+        label = 'MD5 OK'; color = 'var(--green)';
+    } else if (md5Status.startsWith('mismatch:')) {
+        label = `MD5: ${md5Status.split(':')[1]} file(s) corrupt`; color = 'var(--red)';
+    } else {
+        label = 'MD5 check failed'; color = 'var(--red)';
+    }
+    return `<span style="margin:0 0.35rem;color:var(--text-dim)">·</span><span style="color:${color}">${label}</span>`;
+}
+
 function getStatusStyle(status) {
     switch (status) {
         case 'queued': return 'background:var(--yellow-bg);color:var(--gold)';
@@ -1494,6 +2007,11 @@ function formatDate(dateStr) {
     }
 }
 
+// Failed API calls answer with { "error": { "code", "message" } }
+function apiErrorMessage(data, fallback) {
+    return (data && data.error && data.error.message) || fallback;
+}
+
 function showToast(message, type = 'info') {
     const toast = document.getElementById('toast');
     const toastMessage = document.getElementById('toastMessage');
@@ -1557,6 +2075,97 @@ function filterByGenre(genre) {
     loadGames();
 }
 
+// ─── Saved searches ───
+
+let savedSearches = [];
+
+async function loadSavedSearches() {
+    try {
+        const response = await fetch(`${API_BASE}/searches`);
+        if (!response.ok) return;
+        const data = await response.json();
+        savedSearches = data.searches || [];
+
+        const select = document.getElementById('savedSearchSelect');
+        const selected = select.value;
+        select.innerHTML = '<option value="">Saved Searches</option>' + savedSearches
+            .map(s => `<option value="${s.id}">${escapeHtml(s.name)}</option>`)
+            .join('');
+        select.value = savedSearches.some(s => String(s.id) === selected) ? selected : '';
+        document.getElementById('deleteSavedSearchBtn').classList.toggle('hidden', !select.value);
+    } catch (error) {
+        console.error('Failed to load saved searches:', error);
+    }
+}
+
+async function saveCurrentSearch() {
+    const select = document.getElementById('savedSearchSelect');
+    const current = savedSearches.find(s => String(s.id) === select.value);
+    const name = prompt('Name this search:', current ? current.name : '');
+    if (!name || !name.trim()) return;
+
+    const params = {
+        search: document.getElementById('searchInput').value || null,
+        sort: document.getElementById('sortSelect').value || null,
+        genre: document.getElementById('genreSelect').value || null,
+        source: selectedSource && selectedSource !== 'all' ? selectedSource : null,
+        link_type: document.getElementById('linkTypeSelect').value || null,
+    };
+
+    try {
+        const response = await fetch(`${API_BASE}/searches`, {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({ name: name.trim(), params })
+        });
+        const data = await response.json().catch(() => ({}));
+        if (!response.ok) throw new Error(apiErrorMessage(data, 'Failed to save search'));
+
+        select.value = String(data.id);
+        await loadSavedSearches();
+        showToast(`Saved "${data.name}"`, 'success');
+    } catch (error) {
+        showToast(error.message, 'error');
+    }
+}
+
+function applySavedSearch(id) {
+    document.getElementById('deleteSavedSearchBtn').classList.toggle('hidden', !id);
+    const saved = savedSearches.find(s => String(s.id) === id);
+    if (!saved) return;
+
+    const params = saved.params || {};
+    document.getElementById('searchInput').value = params.search || '';
+    document.getElementById('sortSelect').value = params.sort || '';
+    document.getElementById('linkTypeSelect').value = params.link_type || '';
+    const genreSelect = document.getElementById('genreSelect');
+    genreSelect.value = params.genre || '';
+    if (params.genre && genreSelect.value !== params.genre) {
+        genreSelect.add(new Option(params.genre, params.genre));
+        genreSelect.value = params.genre;
+    }
+    // setSource reloads the list with everything above applied
+    setSource(params.source || 'all');
+}
+
+async function deleteSavedSearch() {
+    const select = document.getElementById('savedSearchSelect');
+    const saved = savedSearches.find(s => String(s.id) === select.value);
+    if (!saved || !confirm(`Delete saved search "${saved.name}"?`)) return;
+
+    try {
+        const response = await fetch(`${API_BASE}/searches/${saved.id}`, { method: 'DELETE' });
+        if (!response.ok) {
+            const data = await response.json().catch(() => ({}));
+            throw new Error(apiErrorMessage(data, 'Failed to delete search'));
+        }
+        select.value = '';
+        await loadSavedSearches();
+    } catch (error) {
+        showToast(error.message, 'error');
+    }
+}
+
 // ─── Favorites ───
 
 async function loadFavoriteIds() {
@@ -1597,6 +2206,161 @@ async function toggleFavorite(gameId) {
     }
 }
 
+// ─── Wishlist ───
+
+async function loadWishlistIds() {
+    try {
+        const response = await fetch(`${API_BASE}/games/wishlist`);
+        const data = await response.json();
+        wishlistIds = new Set(data.ids || []);
+    } catch (error) {
+        console.error('Failed to load wishlist:', error);
+    }
+}
+
+async function toggleWishlist(gameId) {
+    const isWished = wishlistIds.has(gameId);
+
+    try {
+        const response = await fetch(`${API_BASE}/games/wishlist/${gameId}`, {
+            method: isWished ? 'DELETE' : 'POST'
+        });
+        if (!response.ok) {
+            const data = await response.json().catch(() => ({}));
+            throw new Error(apiErrorMessage(data, 'Failed to update wishlist'));
+        }
+
+        if (isWished) {
+            wishlistIds.delete(gameId);
+        } else {
+            wishlistIds.add(gameId);
+        }
+        updateModalWishBtn(gameId);
+
+        // Drop it from the library tab straight away
+        if (isWished && !document.getElementById('libraryWishlistTab').classList.contains('hidden')) {
+            loadLibraryWishlist();
+        }
+    } catch (error) {
+        showToast(error.message, 'error');
+    }
+}
+
+// ─── Hidden games and blacklist ───
+
+// Drop a game from the visible list without reloading the page
+function removeGameFromView(gameId) {
+    hideConfirmModal();
+    currentGames = currentGames.filter(g => g.id !== gameId);
+    renderGames(currentGames);
+}
+
+async function hideGame(gameId) {
+    try {
+        const response = await fetch(`${API_BASE}/games/hidden/${gameId}`, { method: 'POST' });
+        if (!response.ok) {
+            const data = await response.json().catch(() => ({}));
+            throw new Error(apiErrorMessage(data, 'Failed to hide game'));
+        }
+        removeGameFromView(gameId);
+        showToast('Game hidden. Unhide it from Library → Hidden.', 'success');
+    } catch (error) {
+        showToast(error.message, 'error');
+    }
+}
+
+async function unhideGame(gameId) {
+    try {
+        const response = await fetch(`${API_BASE}/games/hidden/${gameId}`, { method: 'DELETE' });
+        if (!response.ok) {
+            const data = await response.json().catch(() => ({}));
+            throw new Error(apiErrorMessage(data, 'Failed to unhide game'));
+        }
+        loadLibraryHidden();
+    } catch (error) {
+        showToast(error.message, 'error');
+    }
+}
+
+async function blacklistGame(gameId) {
+    const reason = prompt('Blacklist this game for everyone? It stays hidden after rescrapes.\n\nReason (optional):');
+    if (reason === null) return;
+
+    try {
+        const response = await fetch('/api/admin/blacklist', {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({ game_id: gameId, reason })
+        });
+        if (!response.ok) {
+            const data = await response.json().catch(() => ({}));
+            throw new Error(apiErrorMessage(data, 'Failed to blacklist game'));
+        }
+        removeGameFromView(gameId);
+        showToast('Game blacklisted', 'success');
+    } catch (error) {
+        showToast(error.message, 'error');
+    }
+}
+
+async function unblacklistGame(entryId) {
+    try {
+        const response = await fetch(`/api/admin/blacklist/${entryId}`, { method: 'DELETE' });
+        if (!response.ok) {
+            const data = await response.json().catch(() => ({}));
+            throw new Error(apiErrorMessage(data, 'Failed to remove blacklist entry'));
+        }
+        loadLibraryHidden();
+    } catch (error) {
+        showToast(error.message, 'error');
+    }
+}
+
+// ─── New since last login ───
+
+async function loadNewGamesCount() {
+    try {
+        const response = await fetch(`${API_BASE}/games/new?per_page=1`);
+        if (!response.ok) return;
+        const data = await response.json();
+        document.getElementById('newGamesBadge').textContent = data.count > 99 ? '99+' : data.count;
+        document.getElementById('newGamesWrap').classList.toggle('hidden', !data.count);
+    } catch (error) {
+        console.error('Failed to load new games count:', error);
+    }
+}
+
+function toggleNewGamesView() {
+    showingNewGames = !showingNewGames;
+    document.getElementById('newGamesToggle').classList.toggle('active', showingNewGames);
+    if (showingNewGames) {
+        loadNewGamesView();
+    } else {
+        currentPage = 1;
+        currentGames = [];
+        loadGames();
+    }
+}
+
+async function loadNewGamesView() {
+    try {
+        const response = await fetch(`${API_BASE}/games/new?per_page=200`);
+        const data = await response.json();
+        if (!response.ok) throw new Error(apiErrorMessage(data, 'Failed to load new games'));
+
+        const games = data.games || [];
+        currentGames = games;
+        renderGames(games);
+        const since = data.since ? ` since ${formatDate(data.since)}` : '';
+        const shown = data.count > games.length ? ` (showing the newest ${games.length})` : '';
+        document.getElementById('statsText').textContent = `${data.count} new game${data.count !== 1 ? 's' : ''}${since}${shown}`;
+        document.getElementById('pagination').innerHTML = '';
+        document.getElementById('emptyState').classList.toggle('hidden', games.length > 0);
+    } catch (error) {
+        showToast(error.message, 'error');
+    }
+}
+
 function toggleFavoritesView() {
     showingFavorites = !showingFavorites;
     const btn = document.getElementById('favToggle');
@@ -1680,14 +2444,55 @@ function switchSettingsTab(tabName) {
     document.getElementById(`settingsTab${tabName.charAt(0).toUpperCase() + tabName.slice(1)}`).classList.remove('hidden');
 }
 
+async function restoreBackup() {
+    const file = document.getElementById('restoreFile').files[0];
+    const status = document.getElementById('restoreStatus');
+    const btn = document.getElementById('restoreBtn');
+
+    if (!file) {
+        status.textContent = 'Choose a backup file first';
+        return;
+    }
+    if (!confirm('Replace ALL data with this backup? This cannot be undone.')) {
+        return;
+    }
+
+    const formData = new FormData();
+    formData.append('file', file);
+
+    btn.disabled = true;
+    status.textContent = 'Restoring...';
+
+    try {
+        const response = await fetch(`${API_BASE}/admin/restore`, {
+            method: 'POST',
+            body: formData
+        });
+        const data = await response.json();
+
+        if (response.ok) {
+            showToast(data.message, 'success');
+            setTimeout(() => window.location.reload(), 1500);
+        } else {
+            status.textContent = apiErrorMessage(data, 'Restore failed');
+        }
+    } catch (error) {
+        status.textContent = 'Restore failed';
+    } finally {
+        btn.disabled = false;
+    }
+}
+
 async function showSettingsModal() {
     document.getElementById('settingsModal').classList.remove('hidden');
 
     // Clear inputs
     document.getElementById('settingRawgKey').value = '';
     document.getElementById('settingRdKey').value = '';
+    document.getElementById('settingAdKey').value = '';
     document.getElementById('rawgKeyStatus').textContent = 'Loading...';
     document.getElementById('rdKeyStatus').textContent = 'Loading...';
+    document.getElementById('adKeyStatus').textContent = 'Loading...';
 
     // Reset to first tab
     switchSettingsTab('general');
@@ -1710,6 +2515,44 @@ async function showSettingsModal() {
             document.getElementById('rdKeyStatus').innerHTML = '<span style="color:var(--gold)">Not set</span> — downloads won\'t work without this';
         }
 
+        if (s.ad_api_key_set === 'true') {
+            document.getElementById('adKeyStatus').innerHTML = `<span style="color:var(--green)">✓ Set</span> <span style="color:var(--text-dim)">(${s.ad_api_key_masked})</span> — leave blank to keep current`;
+        } else {
+            document.getElementById('adKeyStatus').innerHTML = '<span style="color:var(--gold)">Not set</span>';
+        }
+
+        if (s.discord_webhook_url_set === 'true') {
+            document.getElementById('discordWebhookStatus').innerHTML = `<span style="color:var(--green)">✓ Set</span> <span style="color:var(--text-dim)">(${s.discord_webhook_url_masked})</span> — leave blank to keep current · <a href="#" onclick="clearDiscordWebhook(); return false;">Remove</a>`;
+        } else {
+            document.getElementById('discordWebhookStatus').innerHTML = '<span style="color:var(--text-dim)">Not set</span>';
+        }
+
+        if (s.download_complete_webhook_set === 'true') {
+            document.getElementById('downloadWebhookStatus').innerHTML = `<span style="color:var(--green)">✓ Set</span> <span style="color:var(--text-dim)">(${s.download_complete_webhook_masked})</span> — leave blank to keep current · <a href="#" onclick="clearDownloadWebhook(); return false;">Remove</a>`;
+        } else {
+            document.getElementById('downloadWebhookStatus').innerHTML = '<span style="color:var(--text-dim)">Not set</span>';
+        }
+
+        if (s.telegram_bot_token_set === 'true') {
+            document.getElementById('telegramBotStatus').innerHTML = `<span style="color:var(--green)">✓ Set</span> <span style="color:var(--text-dim)">(${s.telegram_bot_token_masked})</span> — leave blank to keep current · <a href="#" onclick="clearTelegramBot(); return false;">Remove</a>`;
+        } else {
+            document.getElementById('telegramBotStatus').innerHTML = '<span style="color:var(--text-dim)">Not set</span>';
+        }
+        document.getElementById('settingTelegramChatId').value = s.telegram_chat_id || '';
+        document.getElementById('settingsEmailGroup').classList.toggle('hidden', s.email_available !== 'true');
+        document.getElementById('settingNotifyEmail').checked = s.notify_email === 'true';
+        updateEmailStatus();
+
+        document.getElementById('settingDebridProvider').value = s.debrid_provider || 'realdebrid';
+        loadRealDebridAccount();
+        document.getElementById('settingMinRam').value = s.min_available_ram_gb || '';
+        document.getElementById('settingMinTemp').value = s.min_free_temp_gb || '';
+        document.getElementById('settingCleanupMode').value = s.download_cleanup || 'off';
+        document.getElementById('settingCleanupDays').value = s.download_cleanup_days || '';
+        document.getElementById('settingCleanupMaxDisk').value = s.download_cleanup_max_disk_gb || '';
+        document.getElementById('settingCleanupDeleteFiles').checked = s.download_cleanup_delete_files === 'true';
+        document.getElementById('settingGenreAliases').value = s.genre_aliases || '';
+
         // User settings
         document.getElementById('settingDownloadPath').value = s.download_path || '';
         document.getElementById('settingTheme').value = s.theme || 'dark';
@@ -1717,13 +2560,38 @@ async function showSettingsModal() {
         document.getElementById('settingScraperSteamrip').checked = s.scraper_steamrip_enabled !== 'false';
         document.getElementById('settingNotifyDownloadComplete').checked = s.notify_download_complete !== 'false';
         document.getElementById('settingNotifyNewGames').checked = s.notify_new_games === 'true';
-        document.getElementById('settingNotifyErrors').checked = s.notify_errors !== 'false';
+        document.getElementById('settingNotifyDownloadFailed').checked = s.notify_download_failed !== 'false';
+        document.getElementById('settingNotifyInstallFailed').checked = s.notify_install_failed !== 'false';
+        document.getElementById('settingNotifyClientOffline').checked = s.notify_client_offline !== 'false';
+        document.getElementById('settingNotifyClientOnline').checked = s.notify_client_online === 'true';
+        document.getElementById('settingNotifyScrapeError').checked = s.notify_scrape_error !== 'false';
 
         // Check notification permission status
         updateNotificationStatus();
     } catch (error) {
         document.getElementById('rawgKeyStatus').textContent = 'Failed to load settings';
         document.getElementById('rdKeyStatus').textContent = 'Failed to load settings';
+        document.getElementById('adKeyStatus').textContent = 'Failed to load settings';
+    }
+}
+
+async function loadRealDebridAccount() {
+    const el = document.getElementById('rdAccountStatus');
+    el.textContent = '';
+
+    try {
+        const response = await fetch(`${API_BASE}/realdebrid/account`);
+        if (!response.ok) return;
+        const account = await response.json();
+
+        if (account.account_type === 'premium') {
+            const color = account.premium_days_left < 7 ? 'var(--gold)' : 'var(--green)';
+            el.innerHTML = `${escapeHtml(account.username)} · <span style="color:${color}">Premium: ${account.premium_days_left} days left</span> · ${account.fidelity_points} fidelity points`;
+        } else {
+            el.innerHTML = `${escapeHtml(account.username)} · <span style="color:var(--red)">No premium</span>`;
+        }
+    } catch (error) {
+        console.error('Failed to load Real-Debrid account:', error);
     }
 }
 
@@ -1743,7 +2611,26 @@ async function saveSettings() {
     const rawgKey = document.getElementById('settingRawgKey').value.trim();
     const rdKey = document.getElementById('settingRdKey').value.trim();
     if (rawgKey) settings.rawg_api_key = rawgKey;
+    const adKey = document.getElementById('settingAdKey').value.trim();
     if (rdKey) settings.rd_api_key = rdKey;
+    if (adKey) settings.ad_api_key = adKey;
+    if (currentUser && currentUser.is_admin) {
+        settings.debrid_provider = document.getElementById('settingDebridProvider').value;
+        // Blank restores the default
+        settings.min_available_ram_gb = document.getElementById('settingMinRam').value.trim();
+        settings.min_free_temp_gb = document.getElementById('settingMinTemp').value.trim();
+        settings.download_cleanup = document.getElementById('settingCleanupMode').value;
+        settings.download_cleanup_days = document.getElementById('settingCleanupDays').value.trim();
+        settings.download_cleanup_max_disk_gb = document.getElementById('settingCleanupMaxDisk').value.trim();
+        settings.download_cleanup_delete_files = document.getElementById('settingCleanupDeleteFiles').checked.toString();
+        const discordWebhook = document.getElementById('settingDiscordWebhook').value.trim();
+        if (discordWebhook) settings.discord_webhook_url = discordWebhook;
+        const downloadWebhook = document.getElementById('settingDownloadWebhook').value.trim();
+        if (downloadWebhook) settings.download_complete_webhook = downloadWebhook;
+        const telegramBotToken = document.getElementById('settingTelegramBotToken').value.trim();
+        if (telegramBotToken) settings.telegram_bot_token = telegramBotToken;
+        settings.genre_aliases = document.getElementById('settingGenreAliases').value.trim();
+    }
 
     // User settings
     settings.download_path = document.getElementById('settingDownloadPath').value.trim();
@@ -1752,7 +2639,15 @@ async function saveSettings() {
     settings.scraper_steamrip_enabled = document.getElementById('settingScraperSteamrip').checked.toString();
     settings.notify_download_complete = document.getElementById('settingNotifyDownloadComplete').checked.toString();
     settings.notify_new_games = document.getElementById('settingNotifyNewGames').checked.toString();
-    settings.notify_errors = document.getElementById('settingNotifyErrors').checked.toString();
+    settings.notify_download_failed = document.getElementById('settingNotifyDownloadFailed').checked.toString();
+    settings.notify_install_failed = document.getElementById('settingNotifyInstallFailed').checked.toString();
+    settings.notify_client_offline = document.getElementById('settingNotifyClientOffline').checked.toString();
+    settings.notify_client_online = document.getElementById('settingNotifyClientOnline').checked.toString();
+    settings.notify_scrape_error = document.getElementById('settingNotifyScrapeError').checked.toString();
+    settings.telegram_chat_id = document.getElementById('settingTelegramChatId').value.trim();
+    if (!document.getElementById('settingsEmailGroup').classList.contains('hidden')) {
+        settings.notify_email = document.getElementById('settingNotifyEmail').checked.toString();
+    }
 
     try {
         const response = await fetch(`${API_BASE}/settings`, {
@@ -1765,14 +2660,104 @@ async function saveSettings() {
         if (data.success) {
             showToast('Settings saved!', 'success');
             hideSettingsModal();
+            if ('genre_aliases' in settings) loadGenres();
         } else {
-            showToast(`Error: ${data.message}`, 'error');
+            showToast(`Error: ${apiErrorMessage(data, 'unknown error')}`, 'error');
         }
     } catch (error) {
         showToast('Failed to save settings', 'error');
     }
 }
 
+async function clearDiscordWebhook() {
+    await clearWebhook('discord_webhook_url', 'discordWebhookStatus', 'Discord webhook');
+}
+
+async function clearDownloadWebhook() {
+    await clearWebhook('download_complete_webhook', 'downloadWebhookStatus', 'Download webhook');
+}
+
+function updateEmailStatus() {
+    const status = document.getElementById('emailStatus');
+    document.getElementById('settingEmail').value = currentUser.email || '';
+    if (!currentUser.email) {
+        status.innerHTML = '<span style="color:var(--text-dim)">Not set</span>';
+    } else if (currentUser.email_verified) {
+        status.innerHTML = '<span style="color:var(--green)">✓ Verified</span>';
+    } else {
+        status.innerHTML = '<span style="color:var(--gold)">Waiting for verification</span> — check your inbox for the link';
+    }
+}
+
+async function saveEmail() {
+    const email = document.getElementById('settingEmail').value.trim();
+    try {
+        const response = await fetch(`${API_BASE}/auth/email`, {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({ email })
+        });
+        const data = await response.json();
+        if (data.success) {
+            currentUser.email = email || null;
+            currentUser.email_verified = false;
+            updateEmailStatus();
+            showToast(data.message, 'success');
+        } else {
+            showToast(`Error: ${apiErrorMessage(data, 'unknown error')}`, 'error');
+        }
+    } catch (error) {
+        showToast('Failed to save email address', 'error');
+    }
+}
+
+async function clearTelegramBot() {
+    await clearWebhook('telegram_bot_token', 'telegramBotStatus', 'Telegram bot token');
+}
+
+async function testTelegram() {
+    const chatId = document.getElementById('settingTelegramChatId').value.trim();
+    try {
+        // Save the chat ID first so the test goes where the user typed
+        let response = await fetch(`${API_BASE}/settings`, {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({ settings: { telegram_chat_id: chatId } })
+        });
+        let data = await response.json();
+        if (data.success) {
+            response = await fetch(`${API_BASE}/notifications/telegram/test`, { method: 'POST' });
+            data = await response.json();
+        }
+        if (data.success) {
+            showToast('Test message sent to Telegram', 'success');
+        } else {
+            showToast(`Error: ${apiErrorMessage(data, 'unknown error')}`, 'error');
+        }
+    } catch (error) {
+        showToast('Failed to send Telegram test message', 'error');
+    }
+}
+
+async function clearWebhook(key, statusId, label) {
+    try {
+        const response = await fetch(`${API_BASE}/settings`, {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({ settings: { [key]: '' } })
+        });
+        const data = await response.json();
+        if (data.success) {
+            document.getElementById(statusId).innerHTML = '<span style="color:var(--text-dim)">Not set</span>';
+            showToast(`${label} removed`, 'success');
+        } else {
+            showToast(`Error: ${apiErrorMessage(data, 'unknown error')}`, 'error');
+        }
+    } catch (error) {
+        showToast(`Failed to remove ${label}`, 'error');
+    }
+}
+
 async function requestNotificationPermission() {
     if (!('Notification' in window)) {
         showToast('Browser does not support notifications', 'error');
@@ -1784,6 +2769,7 @@ async function requestNotificationPermission() {
         updateNotificationStatus();
 
         if (permission === 'granted') {
+            await subscribeToPush();
             showToast('Browser notifications enabled!', 'success');
             // Show a test notification
             new Notification('Repack Browser', {
@@ -1798,6 +2784,57 @@ async function requestNotificationPermission() {
     }
 }
 
+function urlBase64ToUint8Array(base64) {
+    const padded = (base64 + '='.repeat((4 - base64.length % 4) % 4)).replace(/-/g, '+').replace(/_/g, '/');
+    return Uint8Array.from(atob(padded), c => c.charCodeAt(0));
+}
+
+// Subscribe this browser to Web Push so notifications arrive with the tab
+// closed. Needs HTTPS (or localhost); without it the in-page ones still work.
+async function subscribeToPush() {
+    if (!('serviceWorker' in navigator) || !('PushManager' in window)) return;
+
+    try {
+        const keyResponse = await fetch(`${API_BASE}/push/public-key`);
+        if (!keyResponse.ok) return;
+        const { public_key } = await keyResponse.json();
+
+        const registration = await navigator.serviceWorker.register('/sw.js');
+        await navigator.serviceWorker.ready;
+        const subscription = await registration.pushManager.getSubscription()
+            || await registration.pushManager.subscribe({
+                userVisibleOnly: true,
+                applicationServerKey: urlBase64ToUint8Array(public_key)
+            });
+
+        await fetch(`${API_BASE}/push/subscribe`, {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify(subscription.toJSON())
+        });
+    } catch (error) {
+        console.error('Web Push subscription failed:', error);
+    }
+}
+
+async function unsubscribeFromPush() {
+    if (!('serviceWorker' in navigator)) return;
+
+    try {
+        const registration = await navigator.serviceWorker.getRegistration('/sw.js');
+        const subscription = registration && await registration.pushManager.getSubscription();
+        if (!subscription) return;
+        await fetch(`${API_BASE}/push/unsubscribe`, {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({ endpoint: subscription.endpoint })
+        });
+        await subscription.unsubscribe();
+    } catch (error) {
+        console.error('Web Push unsubscribe failed:', error);
+    }
+}
+
 function updateNotificationStatus() {
     const statusEl = document.getElementById('notificationStatus');
     const btnEl = document.getElementById('requestNotificationBtn');
@@ -1917,10 +2954,14 @@ async function linkClient(clientId) {
         const data = await response.json();
 
         if (data.success) {
+            // Linking is done from the PC running the client, so remember its local server token here
+            if (data.bridge_token) {
+                localStorage.setItem('clientBridgeToken', data.bridge_token);
+            }
             showToast('Client linked successfully!', 'success');
             loadClients(); // Reload the clients list
         } else {
-            showToast(`Failed to link client: ${data.message}`, 'error');
+            showToast(`Failed to link client: ${apiErrorMessage(data, 'unknown error')}`, 'error');
         }
     } catch (error) {
         console.error('Failed to link client:', error);
@@ -1944,7 +2985,7 @@ async function unlinkClient(clientId) {
             showToast('Client unlinked successfully', 'success');
             loadClients(); // Reload the clients list
         } else {
-            showToast(`Failed to unlink client: ${data.message}`, 'error');
+            showToast(`Failed to unlink client: ${apiErrorMessage(data, 'unknown error')}`, 'error');
         }
     } catch (error) {
         console.error('Failed to unlink client:', error);
@@ -1999,6 +3040,11 @@ async function loadSystemInfo() {
                         <div style="font-size:1.5rem;font-weight:700;font-family:'JetBrains Mono',monospace;">${data.cpu_cores}</div>
                         <div style="font-size:0.7rem;color:var(--text-dim);">cores detected</div>
                     </div>
+                    <div style="background:var(--bg-surface);padding:1rem;border-radius:8px;">
+                        <div style="font-size:0.75rem;color:var(--text-muted);margin-bottom:0.25rem;">GPU</div>
+                        <div style="font-size:0.95rem;font-weight:700;">${data.gpus && data.gpus.length > 0 ? escapeHtml(data.gpus[0].name) : 'Not detected'}</div>
+                        <div style="font-size:0.7rem;color:var(--text-dim);">${data.gpus && data.gpus.length > 0 && data.gpus[0].vram_gb != null ? `${data.gpus[0].vram_gb.toFixed(1)} GB VRAM` : ''}${data.gpus && data.gpus.length > 1 ? ` (+${data.gpus.length - 1} more)` : ''}</div>
+                    </div>
                     <div style="background:var(--bg-surface);padding:1rem;border-radius:8px;">
                         <div style="font-size:0.75rem;color:var(--text-muted);margin-bottom:0.25rem;">Antivirus</div>
                         <div style="font-size:1.5rem;font-weight:700;">${data.antivirus_active ? '🛡️' : '✅'}</div>
@@ -2006,6 +3052,28 @@ async function loadSystemInfo() {
                     </div>
                 </div>
 
+                ${data.drives && data.drives.length > 0 ? `
+                    <div style="background:var(--bg-surface);border-radius:8px;padding:1rem;">
+                        <div style="font-weight:600;margin-bottom:0.5rem;">Drives</div>
+                        ${data.drives.map(drive => {
+                            const usedPct = drive.total_gb > 0 ? Math.round((1 - drive.free_gb / drive.total_gb) * 100) : 0;
+                            const roles = [
+                                drive.mount_point === data.download_drive ? 'Downloads' : null,
+                                drive.mount_point === data.library_drive ? 'Library' : null,
+                            ].filter(Boolean);
+                            return `
+                                <div style="margin-bottom:0.75rem;">
+                                    <div style="display:flex;justify-content:space-between;font-size:0.875rem;margin-bottom:0.25rem;">
+                                        <span style="font-family:'JetBrains Mono',monospace;">${escapeHtml(drive.mount_point)}${roles.length ? ` <span style="color:var(--accent);font-family:inherit;">(${roles.join(', ')})</span>` : ''}</span>
+                                        <span style="color:var(--text-muted);">${drive.free_gb.toFixed(1)} GB free of ${drive.total_gb.toFixed(1)} GB</span>
+                                    </div>
+                                    <div class="progress-track"><div class="progress-fill" style="width:${usedPct}%;"></div></div>
+                                </div>
+                            `;
+                        }).join('')}
+                    </div>
+                ` : ''}
+
                 ${data.issues && data.issues.length > 0 ? `
                     <div style="background:var(--red-dim);border:1px solid rgba(239,68,68,0.25);border-radius:8px;padding:1rem;">
                         <div style="font-weight:600;margin-bottom:0.5rem;color:var(--red);">Issues Found:</div>
@@ -2080,24 +3148,34 @@ async function showPreInstallCheck(gameId) {
         const response = await fetch(`${API_BASE}/pre-install-check/${gameId}`);
         const result = await response.json();
 
-        const statusColor = result.overall_status === 'Pass' ? 'var(--green)' :
-                           result.overall_status === 'Warning' ? 'var(--gold)' : 'var(--red)';
-        const statusIcon = result.overall_status === 'Pass' ? '✅' :
-                          result.overall_status === 'Warning' ? '⚠️' : '🚫';
+        // Statuses arrive lowercase: pass / warning / fail (/ unknown)
+        const statusColor = result.overall_status === 'pass' ? 'var(--green)' :
+                           result.overall_status === 'warning' ? 'var(--gold)' : 'var(--red)';
+        const statusIcon = result.overall_status === 'pass' ? '✅' :
+                          result.overall_status === 'warning' ? '⚠️' : '🚫';
+        const statusLabel = result.overall_status.charAt(0).toUpperCase() + result.overall_status.slice(1);
+        const fieldLabels = { ram: 'RAM', disk: 'Disk Space', cpu: 'CPU', gpu: 'GPU' };
+        const requirements = result.requirements || [];
 
         content.innerHTML = `
             <div style="text-align:center;padding:1rem;background:var(--bg-surface);border-radius:8px;margin-bottom:1rem;">
                 <div style="font-size:3rem;margin-bottom:0.5rem;">${statusIcon}</div>
-                <div style="font-size:1.5rem;font-weight:700;color:${statusColor};">${result.overall_status}</div>
+                <div style="font-size:1.5rem;font-weight:700;color:${statusColor};">${statusLabel}</div>
                 <div style="font-size:0.875rem;color:var(--text-muted);margin-top:0.25rem;">${result.can_proceed ? 'You can proceed with installation' : 'Please resolve issues before installing'}</div>
             </div>
 
+            ${result.community && result.community.total_ratings > 0 && result.community.success_rate != null ? `
+                <div style="font-size:0.85rem;color:var(--text-muted);margin-bottom:1rem;text-align:center;">
+                    👥 ${Math.round(result.community.success_rate * 100)}% of ${result.community.total_ratings} users installed this without issues
+                </div>
+            ` : ''}
+
             <div style="display:grid;gap:0.5rem;margin-bottom:1rem;">
                 ${result.checks.map(check => {
-                    const checkColor = check.status === 'Pass' ? 'var(--green)' :
-                                     check.status === 'Warning' ? 'var(--gold)' : 'var(--red)';
-                    const checkIcon = check.status === 'Pass' ? '✓' :
-                                     check.status === 'Warning' ? '⚠' : '✗';
+                    const checkColor = check.status === 'pass' ? 'var(--green)' :
+                                     check.status === 'warning' ? 'var(--gold)' : 'var(--red)';
+                    const checkIcon = check.status === 'pass' ? '✓' :
+                                     check.status === 'warning' ? '⚠' : '✗';
                     return `
                         <div style="display:flex;gap:0.75rem;padding:0.75rem;background:var(--bg-surface);border-radius:8px;">
                             <div style="color:${checkColor};font-weight:700;flex-shrink:0;">${checkIcon}</div>
@@ -2110,6 +3188,35 @@ async function showPreInstallCheck(gameId) {
                 }).join('')}
             </div>
 
+            ${requirements.length > 0 ? `
+                <div style="font-weight:600;margin-bottom:0.5rem;">📋 Game Requirements</div>
+                <div style="display:grid;gap:0.5rem;margin-bottom:1rem;">
+                    ${requirements.map(req => {
+                        const reqColor = req.status === 'pass' ? 'var(--green)' :
+                                        req.status === 'warning' ? 'var(--gold)' :
+                                        req.status === 'fail' ? 'var(--red)' : 'var(--text-muted)';
+                        const reqIcon = req.status === 'pass' ? '✓' :
+                                       req.status === 'warning' ? '⚠' :
+                                       req.status === 'fail' ? '✗' : '?';
+                        const details = [
+                            req.detected ? `You: ${escapeHtml(req.detected)}` : null,
+                            req.minimum ? `Min: ${escapeHtml(req.minimum)}` : null,
+                            req.recommended ? `Rec: ${escapeHtml(req.recommended)}` : null,
+                        ].filter(Boolean).join(' · ');
+                        return `
+                            <div style="display:flex;gap:0.75rem;padding:0.75rem;background:var(--bg-surface);border-radius:8px;">
+                                <div style="color:${reqColor};font-weight:700;flex-shrink:0;">${reqIcon}</div>
+                                <div>
+                                    <div style="font-weight:600;font-size:0.875rem;">${fieldLabels[req.field] || escapeHtml(req.field)}</div>
+                                    <div style="font-size:0.8rem;margin-top:0.25rem;">${escapeHtml(req.message)}</div>
+                                    ${details ? `<div style="font-size:0.75rem;color:var(--text-muted);margin-top:0.25rem;">${details}</div>` : ''}
+                                </div>
+                            </div>
+                        `;
+                    }).join('')}
+                </div>
+            ` : ''}
+
             ${result.blockers && result.blockers.length > 0 ? `
                 <div style="background:var(--red-dim);border:1px solid rgba(239,68,68,0.25);border-radius:8px;padding:1rem;margin-bottom:1rem;">
                     <div style="font-weight:700;color:var(--red);margin-bottom:0.5rem;">🚫 Blocking Issues:</div>
@@ -2217,11 +3324,39 @@ function renderSidebarTags(filter = '') {
             <div class="filter-item ${isActive ? 'active' : ''}" onclick="toggleSidebarTag('${escapeHtml(tag.name)}')">
                 <span>${escapeHtml(tag.name)}</span>
                 <span class="filter-count">${tag.count}</span>
+                ${currentUser && currentUser.is_admin ? `<span class="filter-count" style="cursor:pointer;" title="Rename or merge this tag on every game" onclick="event.stopPropagation();renameTag('${escapeHtml(tag.name)}')">✎</span>` : ''}
             </div>
         `;
     }).join('');
 }
 
+async function renameTag(from) {
+    const to = prompt(`Rename "${from}" on every game to:`, from);
+    if (!to || !to.trim() || to.trim() === from) return;
+
+    const post = (path, body) => fetch(`${API_BASE}/admin/tags/${path}`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify(body)
+    });
+
+    try {
+        let response = await post('rename', { from, to: to.trim() });
+        if (response.status === 409) {
+            if (!confirm(`"${to.trim()}" already exists. Merge "${from}" into it?`)) return;
+            response = await post('merge', { from: [from], to: to.trim() });
+        }
+        const data = await response.json().catch(() => ({}));
+        if (!response.ok) throw new Error(apiErrorMessage(data, 'Failed to rename tag'));
+
+        selectedSidebarTags.delete(from);
+        showToast(data.message, 'success');
+        loadSidebarTags();
+    } catch (error) {
+        showToast(error.message, 'error');
+    }
+}
+
 function filterSidebarGenres() {
     const search = document.getElementById('genreSidebarSearch').value;
     renderSidebarGenres(search);
@@ -2324,7 +3459,7 @@ function renderCarousel() {
     const html = cardsToShow.map((game, index) => {
         const hasThumb = game.thumbnail_url && game.thumbnail_url.length > 0;
         const thumbContent = hasThumb
-            ? `<img src="${escapeHtml(game.thumbnail_url)}" alt="${escapeHtml(game.title)}" loading="lazy">`
+            ? `<img src="${thumbnailUrl(game)}" alt="${escapeHtml(game.title)}" loading="lazy">`
             : `<div style="display:flex;align-items:center;justify-content:center;height:100%;font-size:3rem;color:var(--text-dim);opacity:0.3;">🎮</div>`;
 
         const rankBadge = showRank ? `<div class="carousel-card-rank">#${index + 1}</div>` : '';
@@ -2378,14 +3513,15 @@ document.addEventListener('click', (e) => {
     }
 });
 
-async function loadNotifications() {
+async function loadNotifications(page = 1) {
     try {
-        const response = await fetch(`${API_BASE}/notifications`);
-        const notifications = await response.json();
+        const response = await fetch(`${API_BASE}/notifications?page=${page}&per_page=20`);
+        const data = await response.json();
+        const notifications = data.notifications || [];
 
         const container = document.getElementById('notificationList');
 
-        if (notifications.length === 0) {
+        if (page === 1 && notifications.length === 0) {
             container.innerHTML = `
                 <div style="text-align:center;padding:2rem;color:var(--text-dim);font-size:0.85rem;">
                     <div style="font-size:2rem;margin-bottom:0.5rem;opacity:0.5;">🔔</div>
@@ -2395,7 +3531,7 @@ async function loadNotifications() {
             return;
         }
 
-        container.innerHTML = notifications.map(notif => {
+        const itemsHtml = notifications.map(notif => {
             const isUnread = !notif.read;
             const time = formatRelativeTime(notif.created_at);
 
@@ -2407,6 +3543,23 @@ async function loadNotifications() {
                 </div>
             `;
         }).join('');
+
+        const moreBtn = container.querySelector('.notification-more');
+        if (moreBtn) moreBtn.remove();
+
+        if (page === 1) {
+            container.innerHTML = itemsHtml;
+        } else {
+            container.insertAdjacentHTML('beforeend', itemsHtml);
+        }
+
+        if (data.page < data.total_pages) {
+            container.insertAdjacentHTML('beforeend', `
+                <button class="btn btn-ghost notification-more" style="width:100%;font-size:0.8rem" onclick="event.stopPropagation(); loadNotifications(${data.page + 1})">
+                    Load more (${data.total - data.page * data.per_page} older)
+                </button>
+            `);
+        }
     } catch (error) {
         console.error('Failed to load notifications:', error);
         document.getElementById('notificationList').innerHTML = `
@@ -2443,22 +3596,78 @@ async function updateNotificationBadge() {
     try {
         const response = await fetch(`${API_BASE}/notifications/count`);
         const data = await response.json();
-        const count = data.count || 0;
-
-        const badge = document.getElementById('notificationBadge');
-        if (count > 0) {
-            badge.textContent = count > 99 ? '99+' : count;
-            badge.classList.remove('hidden');
-        } else {
-            badge.classList.add('hidden');
-        }
+        setNotificationBadge(data.count || 0);
     } catch (error) {
         console.error('Failed to update notification badge:', error);
     }
 }
 
+function setNotificationBadge(count) {
+    const badge = document.getElementById('notificationBadge');
+    if (count > 0) {
+        badge.textContent = count > 99 ? '99+' : count;
+        badge.classList.remove('hidden');
+    } else {
+        badge.classList.add('hidden');
+    }
+}
+
+function isEventSocketOpen() {
+    return eventSocket !== null && eventSocket.readyState === WebSocket.OPEN;
+}
+
+// Server pushes notification counts and download status changes over /api/ws;
+// while it's down we fall back to polling and keep trying to reconnect
+function connectEventSocket() {
+    if (!window.WebSocket) {
+        startNotificationPolling();
+        return;
+    }
+
+    const protocol = location.protocol === 'https:' ? 'wss:' : 'ws:';
+    eventSocket = new WebSocket(`${protocol}//${location.host}${API_BASE}/ws`);
+
+    eventSocket.onopen = () => {
+        stopNotificationPolling();
+        if (downloadPollInterval) {
+            clearInterval(downloadPollInterval);
+            downloadPollInterval = null;
+        }
+    };
+
+    eventSocket.onmessage = (e) => {
+        let event;
+        try {
+            event = JSON.parse(e.data);
+        } catch (error) {
+            console.error('Bad event from server:', error);
+            return;
+        }
+
+        if (event.type === 'notification_count') {
+            setNotificationBadge(event.count);
+            if (notificationPanelOpen) loadNotifications();
+        } else if (event.type === 'download_status') {
+            // The downloads stream already pushes full state while that view is open
+            if (!downloadEventSource) loadDownloads();
+        }
+    };
+
+    eventSocket.onclose = () => {
+        eventSocket = null;
+        if (!currentUser) return; // Logged out
+        startNotificationPolling();
+        if (currentView === 'downloads' && !downloadEventSource && !downloadPollInterval) {
+            downloadPollInterval = setInterval(loadDownloads, 2000);
+        }
+        clearTimeout(eventSocketRetry);
+        eventSocketRetry = setTimeout(connectEventSocket, 15000);
+    };
+}
+
 function startNotificationPolling() {
     // Poll every 30 seconds
+    stopNotificationPolling();
     updateNotificationBadge();
     notificationPollInterval = setInterval(updateNotificationBadge, 30000);
 }
@@ -0,0 +1,28 @@
+// Service worker for Web Push: shows notifications the server pushes while
+// the app isn't open, and focuses (or opens) the app when one is clicked.
+
+self.addEventListener('push', (event) => {
+    let data = {};
+    try {
+        data = event.data ? event.data.json() : {};
+    } catch (error) {
+        data = { title: 'Repack Browser', body: event.data ? event.data.text() : '' };
+    }
+
+    event.waitUntil(self.registration.showNotification(data.title || 'Repack Browser', {
+        body: data.body || '',
+        icon: '/favicon.ico',
+        badge: '/favicon.ico',
+        tag: data.type || undefined
+    }));
+});
+
+self.addEventListener('notificationclick', (event) => {
+    event.notification.close();
+    event.waitUntil((async () => {
+        const windows = await self.clients.matchAll({ type: 'window', includeUncontrolled: true });
+        const app = windows.find(w => new URL(w.url).origin === self.location.origin);
+        if (app) return app.focus();
+        return self.clients.openWindow('/');
+    })());
+});
@@ -0,0 +1,194 @@
+use crate::realdebrid::DownloadLink;
+use reqwest::Client;
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::time::sleep;
+
+const API_BASE: &str = "https://api.alldebrid.com/v4";
+const AGENT: &str = "repack-browser";
+
+// ─── AllDebrid API response types ───
+
+/// Every AllDebrid response is wrapped as { status, data } or { status, error }
+#[derive(Debug, Deserialize)]
+struct ApiEnvelope<T> {
+    status: String,
+    data: Option<T>,
+    error: Option<ApiError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiError {
+    code: String,
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MagnetUploadData {
+    magnets: Vec<UploadedMagnet>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UploadedMagnet {
+    id: Option<i64>,
+    error: Option<ApiError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MagnetStatusData {
+    magnets: MagnetStatus,
+}
+
+#[derive(Debug, Deserialize)]
+struct MagnetStatus {
+    status: String,
+    #[serde(rename = "statusCode")]
+    status_code: i64,
+    #[serde(default)]
+    links: Vec<MagnetLink>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MagnetLink {
+    link: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LinkUnlockData {
+    link: String,
+    filename: String,
+    filesize: Option<u64>,
+}
+
+pub struct AllDebridClient {
+    client: Client,
+    api_key: String,
+}
+
+impl AllDebridClient {
+    pub fn new(api_key: String) -> Self {
+        Self {
+            client: Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .unwrap(),
+            api_key,
+        }
+    }
+
+    pub fn has_api_key(&self) -> bool {
+        !self.api_key.is_empty()
+    }
+
+    /// Call an AllDebrid endpoint and unwrap its response envelope
+    async fn call<T: serde::de::DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        params: &[(&str, &str)],
+    ) -> Result<T, Box<dyn std::error::Error + Send + Sync>> {
+        let response = self.client
+            .get(format!("{}/{}", API_BASE, endpoint))
+            .query(&[("agent", AGENT), ("apikey", self.api_key.as_str())])
+            .query(params)
+            .send()
+            .await?;
+
+        let envelope: ApiEnvelope<T> = response.json().await?;
+
+        if envelope.status != "success" {
+            let msg = envelope.error
+                .map(|e| format!("{} ({})", e.message, e.code))
+                .unwrap_or_else(|| "unknown error".to_string());
+            return Err(format!("AllDebrid API error: {}", msg).into());
+        }
+
+        envelope.data.ok_or_else(|| "AllDebrid API returned no data".into())
+    }
+
+    /// Fetch the account behind the API key; fails when the key is rejected
+    pub async fn user(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let _: serde_json::Value = self.call("user", &[]).await?;
+        Ok(())
+    }
+
+    /// Upload a magnet and return its AllDebrid ID
+    pub async fn upload_magnet(&self, magnet_link: &str) -> Result<i64, Box<dyn std::error::Error + Send + Sync>> {
+        let data: MagnetUploadData = self.call("magnet/upload", &[("magnets[]", magnet_link)]).await?;
+        let magnet = data.magnets.into_iter().next()
+            .ok_or("AllDebrid returned no magnet")?;
+
+        if let Some(err) = magnet.error {
+            return Err(format!("AllDebrid magnet error: {}", err.message).into());
+        }
+
+        magnet.id.ok_or_else(|| "AllDebrid returned no magnet ID".into())
+    }
+
+    /// Wait for a magnet to finish on AllDebrid's side and return its hoster links
+    pub async fn wait_for_ready(&self, magnet_id: i64, max_wait_secs: u64) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
+        let start = std::time::Instant::now();
+        let id = magnet_id.to_string();
+
+        loop {
+            let data: MagnetStatusData = self.call("magnet/status", &[("id", id.as_str())]).await?;
+            let magnet = data.magnets;
+
+            // statusCode: 0-3 processing, 4 ready, 5+ error
+            match magnet.status_code {
+                4 => return Ok(magnet.links.into_iter().map(|l| l.link).collect()),
+                0..=3 => {
+                    if start.elapsed().as_secs() > max_wait_secs {
+                        return Err("Timeout waiting for magnet to be ready".into());
+                    }
+                    sleep(Duration::from_secs(2)).await;
+                }
+                _ => return Err(format!("Magnet failed with status: {}", magnet.status).into()),
+            }
+        }
+    }
+
+    /// Unlock a hoster link to get the direct download URL
+    pub async fn unlock_link(&self, link: &str) -> Result<DownloadLink, Box<dyn std::error::Error + Send + Sync>> {
+        let data: LinkUnlockData = self.call("link/unlock", &[("link", link)]).await?;
+        Ok(DownloadLink {
+            filename: data.filename,
+            download_url: data.link,
+            size: data.filesize.map(|s| s.to_string()),
+        })
+    }
+
+    /// Process a magnet link and return download links
+    pub async fn process_magnet(&self, magnet_link: &str) -> Result<Vec<DownloadLink>, Box<dyn std::error::Error + Send + Sync>> {
+        println!("Processing magnet link via AllDebrid...");
+
+        let magnet_id = self.upload_magnet(magnet_link).await?;
+        println!("Added magnet with ID: {}", magnet_id);
+
+        let links = self.wait_for_ready(magnet_id, 300).await?;
+        println!("Magnet ready! Found {} files", links.len());
+
+        let mut downloads = Vec::new();
+        for (idx, link) in links.iter().enumerate() {
+            match self.unlock_link(link).await {
+                Ok(unlocked) => {
+                    println!("Unlocked file {}/{}: {}", idx + 1, links.len(), unlocked.filename);
+                    downloads.push(unlocked);
+                }
+                Err(e) => {
+                    eprintln!("Failed to unlock link {}: {}", link, e);
+                }
+            }
+        }
+
+        Ok(downloads)
+    }
+
+    /// Universal link processor that handles both magnet and DDL
+    pub async fn process_link(&self, link: &str) -> Result<Vec<DownloadLink>, Box<dyn std::error::Error + Send + Sync>> {
+        if link.starts_with("magnet:") {
+            self.process_magnet(link).await
+        } else {
+            Ok(vec![self.unlock_link(link).await?])
+        }
+    }
+}
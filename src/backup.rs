@@ -0,0 +1,265 @@
+//! Whole-database backup and restore for the admin endpoints.
+//!
+//! Backups are written with `VACUUM INTO`, which gives a consistent snapshot
+//! while the server keeps running. Restores copy the uploaded database's rows
+//! into the live one inside a single transaction, so every holder of the pool
+//! (download workers, event hub, handlers) sees the restored data without the
+//! pool having to be swapped out.
+
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::{Connection, SqlitePool};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+const SQLITE_MAGIC: &[u8] = b"SQLite format 3\0";
+
+/// A file in the temp directory that is deleted when dropped
+pub struct TempFile {
+    pub path: PathBuf,
+}
+
+impl TempFile {
+    pub fn new(prefix: &str) -> Self {
+        let name = format!("{}-{}.db", prefix, uuid::Uuid::new_v4());
+        Self { path: std::env::temp_dir().join(name) }
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Write a consistent copy of the live database to `dest`
+pub async fn backup_to(pool: &SqlitePool, dest: &Path) -> Result<(), sqlx::Error> {
+    sqlx::query("VACUUM INTO ?")
+        .bind(dest.to_string_lossy().to_string())
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Why a restore didn't happen
+#[derive(Debug)]
+pub enum RestoreError {
+    /// The upload isn't a usable backup
+    Invalid(String),
+    /// The upload was fine but copying it into the live database failed
+    Database(String),
+}
+
+impl std::fmt::Display for RestoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RestoreError::Invalid(e) => write!(f, "{}", e),
+            RestoreError::Database(e) => write!(f, "Restore failed: {}", e),
+        }
+    }
+}
+
+/// Replace every table's contents with those of the database file at `source`.
+///
+/// The upload is checked and migrated to the current schema first, so
+/// backups taken by older versions restore cleanly. Nothing in the live
+/// database changes unless the whole copy succeeds.
+pub async fn restore_from(pool: &SqlitePool, source: &Path) -> Result<(), RestoreError> {
+    if !has_sqlite_header(source).await? {
+        return Err(RestoreError::Invalid("Uploaded file is not a SQLite database".to_string()));
+    }
+
+    prepare_upload(source).await?;
+
+    let db_error = |e: sqlx::Error| RestoreError::Database(e.to_string());
+    let mut conn = pool.acquire().await.map_err(db_error)?;
+
+    // Row order across tables doesn't respect foreign keys, and the pragma
+    // can't change inside a transaction
+    sqlx::query("PRAGMA foreign_keys = OFF").execute(&mut *conn).await.map_err(db_error)?;
+    sqlx::query("ATTACH DATABASE ? AS restore_src")
+        .bind(source.to_string_lossy().to_string())
+        .execute(&mut *conn)
+        .await
+        .map_err(db_error)?;
+
+    let result = copy_tables(&mut conn).await;
+
+    let _ = sqlx::query("DETACH DATABASE restore_src").execute(&mut *conn).await;
+    let _ = sqlx::query("PRAGMA foreign_keys = ON").execute(&mut *conn).await;
+
+    result.map_err(db_error)
+}
+
+/// Whether the file starts with the SQLite magic, reading only the header
+async fn has_sqlite_header(source: &Path) -> Result<bool, RestoreError> {
+    use tokio::io::AsyncReadExt;
+
+    let mut file = tokio::fs::File::open(source)
+        .await
+        .map_err(|e| RestoreError::Invalid(format!("Failed to read upload: {}", e)))?;
+    let mut header = [0u8; SQLITE_MAGIC.len()];
+    match file.read_exact(&mut header).await {
+        Ok(_) => Ok(header == SQLITE_MAGIC),
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(RestoreError::Invalid(format!("Failed to read upload: {}", e))),
+    }
+}
+
+/// Sanity-check the uploaded database and bring its schema up to date
+async fn prepare_upload(source: &Path) -> Result<(), RestoreError> {
+    let options = SqliteConnectOptions::from_str(&format!("sqlite:{}", source.display()))
+        .map_err(|e| RestoreError::Database(e.to_string()))?;
+    let upload = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await
+        .map_err(|e| RestoreError::Invalid(format!("Failed to open upload: {}", e)))?;
+
+    let result = async {
+        let (check,): (String,) = sqlx::query_as("PRAGMA quick_check")
+            .fetch_one(&upload)
+            .await
+            .map_err(|e| RestoreError::Invalid(format!("Uploaded database is unreadable: {}", e)))?;
+        if check != "ok" {
+            return Err(RestoreError::Invalid(format!("Uploaded database is corrupt: {}", check)));
+        }
+
+        crate::migrations::run(&upload)
+            .await
+            .map_err(|e| RestoreError::Invalid(format!("Uploaded database can't be upgraded: {}", e)))?;
+
+        // Restoring a database without an admin would lock everyone out of these endpoints
+        let (admins,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM users WHERE is_admin = 1")
+            .fetch_one(&upload)
+            .await
+            .map_err(|e| RestoreError::Invalid(e.to_string()))?;
+        if admins == 0 {
+            return Err(RestoreError::Invalid("Uploaded database has no admin user".to_string()));
+        }
+        Ok(())
+    }
+    .await;
+
+    upload.close().await;
+    result
+}
+
+async fn copy_tables(conn: &mut sqlx::SqliteConnection) -> Result<(), sqlx::Error> {
+    let tables: Vec<(String,)> = sqlx::query_as(
+        "SELECT name FROM main.sqlite_master
+         WHERE type = 'table' AND name NOT LIKE 'sqlite_%' AND name != 'schema_version'",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut tx = conn.begin().await?;
+    for (table,) in tables {
+        // Both sides are on the same migration version, but columns added by
+        // ALTER on an old install can sit in a different order
+        let columns: Vec<(String,)> = sqlx::query_as(
+            "SELECT name FROM pragma_table_info(?, 'main')
+             WHERE name IN (SELECT name FROM pragma_table_info(?, 'restore_src'))",
+        )
+        .bind(&table)
+        .bind(&table)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        sqlx::query(&format!("DELETE FROM main.\"{}\"", table))
+            .execute(&mut *tx)
+            .await?;
+        if columns.is_empty() {
+            continue;
+        }
+
+        let column_list = columns
+            .iter()
+            .map(|(c,)| format!("\"{}\"", c))
+            .collect::<Vec<_>>()
+            .join(", ");
+        sqlx::query(&format!(
+            "INSERT INTO main.\"{table}\" ({cols}) SELECT {cols} FROM restore_src.\"{table}\"",
+            table = table,
+            cols = column_list
+        ))
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn open(path: &Path) -> SqlitePool {
+        let options = SqliteConnectOptions::from_str(&format!("sqlite:{}", path.display()))
+            .unwrap()
+            .create_if_missing(true);
+        let pool = SqlitePoolOptions::new().connect_with(options).await.unwrap();
+        crate::migrations::run(&pool).await.unwrap();
+        pool
+    }
+
+    async fn usernames(pool: &SqlitePool) -> Vec<String> {
+        sqlx::query_as::<_, (String,)>("SELECT username FROM users ORDER BY username")
+            .fetch_all(pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(name,)| name)
+            .collect()
+    }
+
+    async fn add_user(pool: &SqlitePool, username: &str, is_admin: bool) {
+        sqlx::query("INSERT INTO users (username, password_hash, is_admin, created_at) VALUES (?, 'x', ?, '')")
+            .bind(username)
+            .bind(is_admin)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_restore_round_trip() {
+        let live_file = TempFile::new("backup-test-live");
+        let live = open(&live_file.path).await;
+        add_user(&live, "admin", true).await;
+        add_user(&live, "alice", false).await;
+
+        let snapshot = TempFile::new("backup-test-snapshot");
+        backup_to(&live, &snapshot.path).await.unwrap();
+
+        sqlx::query("DELETE FROM users WHERE username = 'alice'").execute(&live).await.unwrap();
+        add_user(&live, "mallory", false).await;
+
+        restore_from(&live, &snapshot.path).await.unwrap();
+        assert_eq!(usernames(&live).await, ["admin", "alice"]);
+        live.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_restore_rejects_bad_uploads() {
+        let live_file = TempFile::new("backup-test-target");
+        let live = open(&live_file.path).await;
+        add_user(&live, "admin", true).await;
+
+        let text = TempFile::new("backup-test-text");
+        std::fs::write(&text.path, b"not a database").unwrap();
+        assert!(matches!(restore_from(&live, &text.path).await, Err(RestoreError::Invalid(_))));
+
+        let empty = TempFile::new("backup-test-empty");
+        std::fs::write(&empty.path, b"").unwrap();
+        assert!(matches!(restore_from(&live, &empty.path).await, Err(RestoreError::Invalid(_))));
+
+        // A real database without an admin would lock everyone out
+        let no_admin_file = TempFile::new("backup-test-no-admin");
+        let no_admin = open(&no_admin_file.path).await;
+        add_user(&no_admin, "alice", false).await;
+        no_admin.close().await;
+        assert!(matches!(restore_from(&live, &no_admin_file.path).await, Err(RestoreError::Invalid(_))));
+
+        assert_eq!(usernames(&live).await, ["admin"]);
+        live.close().await;
+    }
+}
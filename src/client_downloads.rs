@@ -1,7 +1,9 @@
 /// Client-side download management
 /// This module handles the new architecture where clients download to their own PCs
 use crate::db;
-use crate::realdebrid::RealDebridClient;
+use crate::debrid::{self, Debrid};
+use crate::events::EventHub;
+use crate::notifications::Notifier;
 use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
 use std::sync::Arc;
@@ -20,41 +22,65 @@ pub struct ClientDownloadInfo {
     pub eta: Option<String>,
     pub error_message: Option<String>,
     pub created_at: String,
+    /// Password for encrypted archives, if the user supplied one for this game
+    pub archive_password: Option<String>,
+    /// Install folder chosen for this download; the client's default when unset
+    pub install_dir: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CreateDownloadRequest {
     pub game_id: i64,
+    /// Leave out samples, readmes and other extras
+    #[serde(default)]
+    pub skip_junk: bool,
+    /// Password for encrypted archives, remembered for the game
+    #[serde(default)]
+    pub archive_password: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ProgressUpdate {
-    pub status: String,  // "downloading", "extracting", "installing", "completed", "failed"
+    pub status: String,  // "downloading", "extracting", "installing", "completed", "failed", "cancelled"
     pub progress: f64,   // 0.0 to 100.0
     pub download_speed: Option<String>,
     pub eta: Option<String>,
     pub error_message: Option<String>,
+    /// Current installer step, from the client's tail of the Inno Setup log
+    #[serde(default)]
+    pub install_step: Option<String>,
 }
 
+/// Commands no client picked up within this window are dropped
+const COMMAND_TTL_SECS: i64 = 60 * 60;
+
+/// After telling an owner their client went offline or came back, wait this
+/// long before the next status notification for it; a flapping connection
+/// then sends one notification for where it settled instead of one per flap
+const CLIENT_NOTIFY_COOLDOWN_SECS: i64 = 5 * 60;
+
 pub struct ClientDownloadManager {
     db: SqlitePool,
-    rd_client: Arc<RealDebridClient>,
+    debrid: Arc<dyn Debrid>,
+    events: Arc<EventHub>,
+    notifier: Arc<Notifier>,
 }
 
 impl ClientDownloadManager {
-    pub fn new(db: SqlitePool, rd_client: Arc<RealDebridClient>) -> Self {
-        Self { db, rd_client }
+    pub fn new(db: SqlitePool, debrid: Arc<dyn Debrid>, events: Arc<EventHub>, notifier: Arc<Notifier>) -> Self {
+        Self { db, debrid, events, notifier }
     }
 
     /// Create a new download (called when user clicks download button)
     /// This:
-    /// 1. Converts magnet to direct URLs via Real-Debrid
+    /// 1. Converts magnet to direct URLs via the configured debrid provider
     /// 2. Creates download record with user_id
     /// 3. Returns download ID
     pub async fn create_download(
         &self,
         user_id: i64,
         game_id: i64,
+        skip_junk: bool,
     ) -> Result<i64, Box<dyn std::error::Error + Send + Sync>> {
         // Get game info
         let game = db::get_game_by_id(&self.db, game_id).await
@@ -78,22 +104,15 @@ impl ClientDownloadManager {
             ).into());
         }
 
-        // Get fresh RD API key from database settings
-        let api_key = db::get_setting(&self.db, "rd_api_key").await
-            .map_err(|e| format!("Failed to load RD API key: {}", e))?
-            .ok_or("Real-Debrid API key not configured. Please add it in Settings.")?;
-
-        if api_key.is_empty() {
-            return Err("Real-Debrid API key is empty. Please configure it in Settings.".into());
-        }
-
-        // Create fresh RD client with database API key
-        let rd_client = RealDebridClient::new(api_key);
+        // Build a fresh debrid client from the provider/API key in database settings
+        let debrid = debrid::from_settings(&self.db, &self.debrid).await?;
 
-        // Convert magnet to direct URLs via Real-Debrid
-        println!("Converting magnet for game '{}'...", game.title);
-        let download_links = rd_client.process_link(&game.magnet_link).await
-            .map_err(|e| format!("Real-Debrid conversion failed: {}", e))?;
+        // Convert magnet to direct URLs via the debrid service
+        println!("Converting magnet for game '{}' via {}...", game.title, debrid.provider_label());
+        // The user is waiting on this request, so don't wait long for uncached torrents
+        let options = debrid::LinkOptions { skip_junk, max_wait_secs: 300, link_type: Some(game.link_type) };
+        let download_links = debrid::process_link_cached(&self.db, debrid.as_ref(), &game.magnet_link, &options, &|_| {}).await
+            .map_err(|e| format!("{} conversion failed: {}", debrid.provider_label(), e))?;
 
         if download_links.is_empty() {
             return Err("No files found in torrent".into());
@@ -124,6 +143,7 @@ impl ClientDownloadManager {
 
         let download_id = result.last_insert_rowid();
         println!("Created download {} for user {} game '{}'", download_id, user_id, game.title);
+        self.events.download_changed(download_id).await;
 
         Ok(download_id)
     }
@@ -131,11 +151,15 @@ impl ClientDownloadManager {
     /// Get pending downloads for a client
     /// Returns downloads where:
     /// - user_id matches the client's user
+    /// - client_id is unset or this client
     /// - status is 'pending', 'downloading', 'extracting', or 'installing'
     pub async fn get_client_queue(
         &self,
         client_id: &str,
     ) -> Result<Vec<ClientDownloadInfo>, Box<dyn std::error::Error + Send + Sync>> {
+        // Polling the queue doubles as the client's heartbeat
+        db::touch_client(&self.db, client_id).await?;
+
         // Get client info to find user_id
         let client = db::get_client(&self.db, client_id).await?;
 
@@ -148,14 +172,16 @@ impl ClientDownloadManager {
             "SELECT
                 d.id, d.game_id, d.status, d.progress, d.download_speed, d.eta,
                 d.file_path, d.installer_path, d.error_message, d.created_at, d.completed_at,
-                d.client_id, d.user_id,
+                d.client_id, d.user_id, d.install_dir,
                 g.title as game_title, g.file_size as game_size
              FROM downloads d
              JOIN games g ON d.game_id = g.id
-             WHERE d.user_id = ? AND d.status IN ('pending', 'downloading', 'extracting', 'installing')
+             WHERE d.user_id = ? AND (d.client_id IS NULL OR d.client_id = ?)
+               AND d.status IN ('pending', 'downloading', 'extracting', 'installing')
              ORDER BY d.created_at ASC"
         )
         .bind(user_id)
+        .bind(client_id)
         .fetch_all(&self.db)
         .await?;
 
@@ -171,6 +197,8 @@ impl ClientDownloadManager {
                 .and_then(|s| serde_json::from_str(s).ok())
                 .unwrap_or_default();
 
+            let archive_password = db::get_archive_password(&self.db, row.game_id).await?;
+
             downloads.push(ClientDownloadInfo {
                 id: row.id,
                 game_id: row.game_id,
@@ -184,21 +212,152 @@ impl ClientDownloadManager {
                 eta: row.eta,
                 error_message: row.error_message,
                 created_at: row.created_at,
+                archive_password,
+                install_dir: row.install_dir,
             });
         }
 
         Ok(downloads)
     }
 
+    /// Cancel a client download: mark it cancelled and queue a `cancel` command
+    /// for the user's client. Returns false if this isn't a client download.
+    pub async fn cancel_download(&self, download_id: i64) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let row: Option<(Option<i64>, String)> = sqlx::query_as(
+            "SELECT user_id, status FROM downloads WHERE id = ?"
+        )
+        .bind(download_id)
+        .fetch_optional(&self.db)
+        .await?;
+
+        let (user_id, status) = match row {
+            Some((Some(user_id), status)) => (user_id, status),
+            _ => return Ok(false),
+        };
+
+        if !["pending", "downloading", "extracting", "installing"].contains(&status.as_str()) {
+            return Err(format!("Download is already {}", status).into());
+        }
+
+        // Only a client that has started the download needs to be told
+        if status != "pending" {
+            db::queue_client_command(&self.db, user_id, download_id, "cancel").await?;
+        }
+
+        sqlx::query(
+            "UPDATE downloads SET status = 'failed', error_message = 'Cancelled by user', download_speed = NULL, eta = NULL, completed_at = ? WHERE id = ?"
+        )
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(download_id)
+        .execute(&self.db)
+        .await?;
+        self.events.download_changed(download_id).await;
+
+        Ok(true)
+    }
+
+    /// Move a client download to another client. One that was already under
+    /// way starts over as 'pending' on the new client; an installer that's
+    /// running can't be moved.
+    pub async fn assign_download(&self, download_id: i64, client_id: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let status: Option<(String,)> = sqlx::query_as("SELECT status FROM downloads WHERE id = ?")
+            .bind(download_id)
+            .fetch_optional(&self.db)
+            .await?;
+        let (status,) = status.ok_or("Download not found")?;
+
+        if !["pending", "downloading", "extracting"].contains(&status.as_str()) {
+            return Err(format!("Can't move a download that is {}", status).into());
+        }
+
+        sqlx::query(
+            "UPDATE downloads SET client_id = ?, status = 'pending', progress = 0.0, download_speed = NULL, eta = NULL WHERE id = ?"
+        )
+        .bind(client_id)
+        .bind(download_id)
+        .execute(&self.db)
+        .await?;
+        self.events.download_changed(download_id).await;
+
+        Ok(())
+    }
+
+    /// Flip clients that missed their heartbeats for `window_secs` to offline,
+    /// then tell owners about clients that went offline or came back online,
+    /// if they want those notifications
+    pub async fn check_client_status(&self, window_secs: i64) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        for client in db::mark_stale_clients_offline(&self.db, window_secs).await? {
+            println!("📴 Client '{}' went offline (last seen {})", client.client_name, client.last_seen);
+        }
+
+        for change in db::get_client_status_changes(&self.db, CLIENT_NOTIFY_COOLDOWN_SECS).await? {
+            let (notification_type, title, message) = if change.status == "offline" {
+                (
+                    "client_offline",
+                    "Client Offline",
+                    format!("{} stopped checking in. Downloads for it will wait until it's back online.", change.client_name),
+                )
+            } else {
+                let pending = db::count_pending_client_downloads(&self.db, change.user_id, &change.client_id).await?;
+                let message = match pending {
+                    0 => format!("{} is back online.", change.client_name),
+                    1 => format!("{} is back online. Your queued download will start shortly.", change.client_name),
+                    n => format!("{} is back online. Your {} queued downloads will start shortly.", change.client_name, n),
+                };
+                ("client_online", "Client Online", message)
+            };
+
+            // Recorded even when the user doesn't want this type, so turning
+            // it on later doesn't send a stale notification
+            db::set_client_notified_status(&self.db, &change.client_id, &change.status).await?;
+
+            let settings = db::get_user_settings(&self.db, change.user_id).await.ok();
+            if settings.is_none_or(|s| s.wants(notification_type)) {
+                self.notifier.notify_user(
+                    change.user_id,
+                    notification_type,
+                    title,
+                    &message,
+                ).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Pending commands for a client, resolved through the user it's linked to
+    pub async fn get_commands(&self, client_id: &str) -> Result<Vec<db::ClientCommand>, Box<dyn std::error::Error + Send + Sync>> {
+        db::touch_client(&self.db, client_id).await?;
+        let user_id = self.client_user_id(client_id).await?;
+        Ok(db::get_pending_client_commands(&self.db, user_id, COMMAND_TTL_SECS).await?)
+    }
+
+    /// Mark a command as handled by a client
+    pub async fn ack_command(&self, client_id: &str, command_id: i64) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let user_id = self.client_user_id(client_id).await?;
+        Ok(db::ack_client_command(&self.db, user_id, command_id).await?)
+    }
+
+    async fn client_user_id(&self, client_id: &str) -> Result<i64, Box<dyn std::error::Error + Send + Sync>> {
+        db::get_client(&self.db, client_id).await?
+            .and_then(|c| c.user_id)
+            .ok_or_else(|| "Client not linked to a user".into())
+    }
+
     /// Update download progress (called by client)
     pub async fn update_progress(
         &self,
         download_id: i64,
-        update: ProgressUpdate,
+        mut update: ProgressUpdate,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        // Clients report "cancelled"; store it the way server-side cancels are
+        if update.status == "cancelled" {
+            update.status = "failed".to_string();
+            update.error_message = Some("Cancelled by user".to_string());
+        }
+
         // Get download info to find user_id and game_title
-        let download_info: Option<(i64, i64, String)> = sqlx::query_as(
-            "SELECT d.user_id, d.game_id, g.title
+        let download_info: Option<(i64, i64, String, String)> = sqlx::query_as(
+            "SELECT d.user_id, d.game_id, g.title, d.status
              FROM downloads d
              JOIN games g ON d.game_id = g.id
              WHERE d.id = ?"
@@ -210,7 +369,7 @@ impl ClientDownloadManager {
         // Update download status in database
         sqlx::query(
             "UPDATE downloads
-             SET status = ?, progress = ?, download_speed = ?, eta = ?, error_message = ?
+             SET status = ?, progress = ?, download_speed = ?, eta = ?, error_message = ?, install_step = ?
              WHERE id = ?"
         )
         .bind(&update.status)
@@ -218,10 +377,15 @@ impl ClientDownloadManager {
         .bind(&update.download_speed)
         .bind(&update.eta)
         .bind(&update.error_message)
+        .bind(&update.install_step)
         .bind(download_id)
         .execute(&self.db)
         .await?;
 
+        if download_info.as_ref().is_some_and(|(_, _, _, previous_status)| *previous_status != update.status) {
+            self.events.download_changed(download_id).await;
+        }
+
         // If completed or failed, set completed_at timestamp
         if update.status == "completed" || update.status == "failed" {
             let now = chrono::Utc::now().to_rfc3339();
@@ -231,15 +395,25 @@ impl ClientDownloadManager {
                 .execute(&self.db)
                 .await?;
 
-            // Create notifications based on user settings
-            if let Some((user_id, _game_id, game_title)) = download_info {
+            // Create notifications based on user settings, only on the transition
+            // so repeated progress reports don't notify twice
+            if let Some((user_id, _game_id, game_title, previous_status)) = download_info
+                .filter(|(_, _, _, previous_status)| *previous_status != update.status)
+            {
                 if update.status == "completed" {
+                    crate::notifications::send_discord(
+                        &self.db,
+                        "Download Complete",
+                        &format!("{} has finished downloading and is ready to play!", game_title),
+                        crate::notifications::COLOR_DOWNLOAD_COMPLETE,
+                    );
+                    crate::notifications::send_download_complete(&self.db, download_id);
+
                     // Check if user has download completion notifications enabled
                     let settings = db::get_user_settings(&self.db, user_id).await.ok();
                     if let Some(settings) = settings {
-                        if settings.notify_download_complete.unwrap_or(true) {
-                            let _ = db::create_notification(
-                                &self.db,
+                        if settings.wants("download_complete") {
+                            self.notifier.notify_user(
                                 user_id,
                                 "download_complete",
                                 "Download Complete",
@@ -248,17 +422,25 @@ impl ClientDownloadManager {
                         }
                     }
                 } else if update.status == "failed" {
-                    // Check if user has error notifications enabled
+                    debrid::forget_cached_links(&self.db, download_id).await;
+
+                    // A failure while the client was running the installer is an install failure
+                    let error_msg = update.error_message.as_deref().unwrap_or("Unknown error");
+                    let (notification_type, title, message) = if previous_status == "installing" {
+                        ("install_failed", "Install Failed", format!("{} failed to install: {}", game_title, error_msg))
+                    } else {
+                        ("download_error", "Download Failed", format!("{} failed to download: {}", game_title, error_msg))
+                    };
+
+                    // Check if user has notifications of this type enabled
                     let settings = db::get_user_settings(&self.db, user_id).await.ok();
                     if let Some(settings) = settings {
-                        if settings.notify_errors.unwrap_or(true) {
-                            let error_msg = update.error_message.as_deref().unwrap_or("Unknown error");
-                            let _ = db::create_notification(
-                                &self.db,
+                        if settings.wants(notification_type) {
+                            self.notifier.notify_user(
                                 user_id,
-                                "download_error",
-                                "Download Failed",
-                                &format!("{} failed to download: {}", game_title, error_msg),
+                                notification_type,
+                                title,
+                                &message,
                             ).await;
                         }
                     }
@@ -278,26 +460,26 @@ impl ClientDownloadManager {
             r#"
             SELECT d.id, d.game_id, d.status, d.progress, d.download_speed, d.eta,
                    d.file_path, d.installer_path, d.error_message, d.created_at, d.completed_at,
-                   g.title as game_title, g.file_size as game_size, d.client_id, d.user_id
+                   g.title as game_title, g.file_size as game_size, d.client_id, d.user_id, d.md5_status, d.debrid_status, d.install_step, d.install_dir
             FROM downloads d
             JOIN games g ON d.game_id = g.id
             WHERE d.user_id = ?
+               OR d.id IN (SELECT download_id FROM user_downloads WHERE user_id = ?)
             ORDER BY d.created_at DESC
             "#
         )
         .bind(user_id)
+        .bind(user_id)
         .fetch_all(&self.db)
         .await?;
 
+        let ids: Vec<i64> = rows.iter().map(|r| r.id).collect();
+        let mut files_by_download = db::get_download_files_map(&self.db, &ids).await
+            .unwrap_or_default();
+
         let mut downloads = Vec::new();
         for row in rows {
-            let files: Vec<db::DownloadFileRow> = sqlx::query_as(
-                "SELECT id, filename, file_size, file_path, is_extracted FROM download_files WHERE download_id = ?"
-            )
-            .bind(row.id)
-            .fetch_all(&self.db)
-            .await
-            .unwrap_or_default();
+            let files = files_by_download.remove(&row.id).unwrap_or_default();
 
             downloads.push(crate::download_manager::DownloadInfo {
                 id: row.id,
@@ -322,24 +504,33 @@ impl ClientDownloadManager {
                     is_extracted: f.is_extracted,
                 }).collect(),
                 has_md5: false,
+                md5_status: row.md5_status,
+                debrid_status: row.debrid_status,
+                install_step: row.install_step,
+                install_dir: row.install_dir,
+                client_id: row.client_id,
             });
         }
 
         Ok(downloads)
     }
 
-    /// Link a client to a user
+    /// Link a client to a user, unless it's already linked to someone else.
+    /// Returns whether the client is now linked to `user_id`.
     pub async fn link_client_to_user(
         &self,
         client_id: &str,
         user_id: i64,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        sqlx::query("UPDATE clients SET user_id = ? WHERE client_id = ?")
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let result = sqlx::query(
+            "UPDATE clients SET user_id = ? WHERE client_id = ? AND (user_id IS NULL OR user_id = ?)"
+        )
             .bind(user_id)
             .bind(client_id)
+            .bind(user_id)
             .execute(&self.db)
             .await?;
 
-        Ok(())
+        Ok(result.rows_affected() > 0)
     }
 }
@@ -0,0 +1,397 @@
+//! Server settings. An optional `config.toml` provides the base values and
+//! any matching environment variable overrides it, so existing `docker run -e`
+//! setups keep working unchanged. See `config.example.toml` for every key.
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Layout of `config.toml`. Every key is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileConfig {
+    server: ServerSection,
+    database: DatabaseSection,
+    api_keys: ApiKeysSection,
+    downloads: DownloadsSection,
+    scraper: ScraperSection,
+    session: SessionSection,
+    rate_limit: RateLimitSection,
+    notifications: NotificationsSection,
+    thumbnails: ThumbnailsSection,
+    clients: ClientsSection,
+    smtp: SmtpSection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ServerSection {
+    port: Option<u16>,
+    trust_proxy: Option<bool>,
+    public_url: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct DatabaseSection {
+    path: Option<String>,
+    max_connections: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ApiKeysSection {
+    real_debrid: Option<String>,
+    rawg: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct DownloadsSection {
+    dir: Option<String>,
+    auto_extract: Option<bool>,
+    delete_archives: Option<bool>,
+    nested_size_factor: Option<u64>,
+    max_concurrent: Option<usize>,
+    rate_limit_mbps: Option<f64>,
+    library_dir: Option<String>,
+    auto_archive_installed: Option<bool>,
+    max_active_per_user: Option<u32>,
+    daily_gb_per_user: Option<f64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ScraperSection {
+    interval_hours: Option<u64>,
+    rawg_cache_days: Option<i64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SessionSection {
+    lifetime_days: Option<i64>,
+    idle_timeout_days: Option<i64>,
+    secure_cookie: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RateLimitSection {
+    per_minute: Option<u32>,
+    auth_per_minute: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct NotificationsSection {
+    retention_days: Option<i64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ThumbnailsSection {
+    cache_dir: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ClientsSection {
+    offline_after_secs: Option<i64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SmtpSection {
+    host: Option<String>,
+    port: Option<u16>,
+    username: Option<String>,
+    password: Option<String>,
+    from: Option<String>,
+    tls: Option<SmtpTls>,
+}
+
+/// Resolved settings with defaults applied
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// The config file that was loaded, if any
+    pub file: Option<PathBuf>,
+    pub port: u16,
+    pub trust_proxy: bool,
+    /// Address users reach the server at, for links in emails
+    pub public_url: Option<String>,
+    pub database_path: String,
+    pub db_max_connections: u32,
+    pub rd_api_key: String,
+    pub rawg_api_key: String,
+    pub download_dir: String,
+    pub auto_extract: bool,
+    pub delete_archives: bool,
+    /// Nested archives may unpack to at most this many times their outer archive's size
+    pub nested_size_factor: u64,
+    pub max_concurrent: usize,
+    pub rate_limit_mbps: f64,
+    pub library_dir: Option<PathBuf>,
+    pub auto_archive: bool,
+    /// Hours between automatic rescrapes of every source; 0 disables them
+    pub scrape_interval_hours: u64,
+    /// Days a RAWG lookup is reused before it's fetched again; 0 keeps them forever
+    pub rawg_cache_days: i64,
+    pub session: SessionConfig,
+    pub download_quota: DownloadQuota,
+    pub rate_limit_per_minute: u32,
+    pub rate_limit_auth_per_minute: u32,
+    pub notification_retention_days: i64,
+    pub thumbnail_dir: PathBuf,
+    /// Seconds without a heartbeat before a client counts as offline
+    pub client_offline_secs: i64,
+    /// Outgoing mail server; email notifications are off when unset
+    pub smtp: Option<SmtpConfig>,
+}
+
+/// How the SMTP connection is secured
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpTls {
+    /// TLS from the start, usually port 465
+    Tls,
+    /// Plain connection upgraded with STARTTLS, usually port 587
+    Starttls,
+    /// Unencrypted, only for a relay on the local network
+    None,
+}
+
+impl FromStr for SmtpTls {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "tls" => Ok(SmtpTls::Tls),
+            "starttls" => Ok(SmtpTls::Starttls),
+            "none" => Ok(SmtpTls::None),
+            _ => Err(format!("unknown SMTP TLS mode {:?}", s)),
+        }
+    }
+}
+
+/// Outgoing mail server for email notifications
+#[derive(Debug, Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Sender address, e.g. "Repack Browser <games@example.com>"
+    pub from: String,
+    pub tls: SmtpTls,
+}
+
+/// Per-user download limits; admins aren't limited
+#[derive(Debug, Clone, Copy)]
+pub struct DownloadQuota {
+    /// Downloads a user can have queued or in progress at once; 0 means unlimited
+    pub max_active: u32,
+    /// GB of games a user can queue per UTC day; 0 means unlimited
+    pub daily_gb: f64,
+}
+
+/// How login sessions and their cookie are issued
+#[derive(Debug, Clone, Copy)]
+pub struct SessionConfig {
+    /// Maximum session age: a login expires this long after it was made,
+    /// however active it is. Also the cookie's lifetime.
+    pub lifetime_days: i64,
+    /// Sessions unused for this long expire sooner; 0 means only `lifetime_days` applies
+    pub idle_timeout_days: i64,
+    /// Adds `Secure` so the cookie is only sent over HTTPS
+    pub secure_cookie: bool,
+}
+
+impl SessionConfig {
+    /// Days a new or just-used session stays valid, before the `lifetime_days` cap
+    pub fn expiry_days(&self) -> i64 {
+        if self.idle_timeout_days > 0 {
+            self.idle_timeout_days.min(self.lifetime_days)
+        } else {
+            self.lifetime_days
+        }
+    }
+
+    /// `Set-Cookie` value for a new session
+    pub fn cookie(&self, token: &str) -> String {
+        format!(
+            "session={}; HttpOnly; Path=/; Max-Age={}; SameSite=Lax{}",
+            token,
+            self.lifetime_days * 86_400,
+            if self.secure_cookie { "; Secure" } else { "" }
+        )
+    }
+
+    /// `Set-Cookie` value that removes the session cookie
+    pub fn clear_cookie(&self) -> String {
+        format!(
+            "session=; HttpOnly; Path=/; Max-Age=0; SameSite=Lax{}",
+            if self.secure_cookie { "; Secure" } else { "" }
+        )
+    }
+}
+
+impl Config {
+    /// Read `config.toml` and apply environment overrides.
+    ///
+    /// The file is taken from `CONFIG_PATH` when set (and must then exist),
+    /// otherwise `config.toml` next to the executable or in the working
+    /// directory is used if present.
+    pub fn load() -> Result<Self, String> {
+        let (file, parsed) = match find_config_file()? {
+            Some(path) => {
+                let text = std::fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                let parsed: FileConfig = toml::from_str(&text)
+                    .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?;
+                (Some(path), parsed)
+            }
+            None => (None, FileConfig::default()),
+        };
+
+        let exe_dir = exe_dir();
+        let FileConfig {
+            server,
+            database,
+            api_keys,
+            downloads,
+            scraper,
+            session,
+            rate_limit,
+            notifications,
+            thumbnails,
+            clients,
+            smtp,
+        } = parsed;
+
+        let database_path = layer("DATABASE_PATH", database.path).unwrap_or_else(|| {
+            let data_dir = exe_dir.join("data");
+            std::fs::create_dir_all(&data_dir).ok();
+            format!("sqlite:{}?mode=rwc", data_dir.join("games.db").display())
+        });
+
+        Ok(Self {
+            file,
+            port: layer("PORT", server.port).unwrap_or(3000),
+            trust_proxy: layer("TRUST_PROXY", server.trust_proxy).unwrap_or(false),
+            public_url: layer("PUBLIC_URL", server.public_url)
+                .map(|url| url.trim_end_matches('/').to_string()),
+            database_path,
+            db_max_connections: layer("DB_MAX_CONNECTIONS", database.max_connections)
+                .unwrap_or(5)
+                .max(1),
+            rd_api_key: layer("RD_API_KEY", api_keys.real_debrid).unwrap_or_default(),
+            rawg_api_key: layer("RAWG_API_KEY", api_keys.rawg).unwrap_or_default(),
+            download_dir: layer("DOWNLOAD_DIR", downloads.dir)
+                .unwrap_or_else(|| exe_dir.join("downloads").to_string_lossy().to_string()),
+            auto_extract: layer("AUTO_EXTRACT", downloads.auto_extract).unwrap_or(true),
+            delete_archives: layer("DELETE_ARCHIVES", downloads.delete_archives).unwrap_or(false),
+            nested_size_factor: layer("NESTED_ARCHIVE_SIZE_FACTOR", downloads.nested_size_factor)
+                .unwrap_or(crate::extractor::DEFAULT_NESTED_SIZE_FACTOR)
+                .max(1),
+            max_concurrent: layer("MAX_CONCURRENT_DOWNLOADS", downloads.max_concurrent)
+                .unwrap_or(1)
+                .max(1),
+            rate_limit_mbps: layer("DOWNLOAD_RATE_LIMIT_MBPS", downloads.rate_limit_mbps)
+                .unwrap_or(0.0)
+                .max(0.0),
+            library_dir: layer("LIBRARY_DIR", downloads.library_dir).map(PathBuf::from),
+            auto_archive: layer("AUTO_ARCHIVE_INSTALLED", downloads.auto_archive_installed)
+                .unwrap_or(false),
+            scrape_interval_hours: layer("SCRAPE_INTERVAL_HOURS", scraper.interval_hours).unwrap_or(0),
+            rawg_cache_days: layer("RAWG_CACHE_DAYS", scraper.rawg_cache_days).unwrap_or(90).max(0),
+            session: SessionConfig {
+                lifetime_days: layer("SESSION_LIFETIME_DAYS", session.lifetime_days)
+                    .unwrap_or(30)
+                    .max(1),
+                idle_timeout_days: layer("SESSION_IDLE_TIMEOUT_DAYS", session.idle_timeout_days)
+                    .unwrap_or(14)
+                    .max(0),
+                secure_cookie: layer("COOKIE_SECURE", session.secure_cookie).unwrap_or(false),
+            },
+            download_quota: DownloadQuota {
+                max_active: layer("MAX_ACTIVE_DOWNLOADS_PER_USER", downloads.max_active_per_user).unwrap_or(0),
+                daily_gb: layer("DAILY_DOWNLOAD_GB_PER_USER", downloads.daily_gb_per_user)
+                    .unwrap_or(0.0)
+                    .max(0.0),
+            },
+            rate_limit_per_minute: layer("RATE_LIMIT_PER_MINUTE", rate_limit.per_minute).unwrap_or(300),
+            rate_limit_auth_per_minute: layer("RATE_LIMIT_AUTH_PER_MINUTE", rate_limit.auth_per_minute)
+                .unwrap_or(1200),
+            notification_retention_days: layer("NOTIFICATION_RETENTION_DAYS", notifications.retention_days)
+                .unwrap_or(30)
+                .max(0),
+            thumbnail_dir: layer("THUMBNAIL_CACHE_DIR", thumbnails.cache_dir)
+                .map(PathBuf::from)
+                .unwrap_or_else(|| exe_dir.join("data").join("thumbnails")),
+            client_offline_secs: layer("CLIENT_OFFLINE_SECS", clients.offline_after_secs)
+                .unwrap_or(120)
+                .max(10),
+            smtp: smtp_config(smtp),
+        })
+    }
+}
+
+/// SMTP settings, when both a host and a sender address are set
+fn smtp_config(smtp: SmtpSection) -> Option<SmtpConfig> {
+    let host = layer("SMTP_HOST", smtp.host)?;
+    let from = layer("SMTP_FROM", smtp.from)?;
+    let tls = layer("SMTP_TLS", smtp.tls).unwrap_or(SmtpTls::Starttls);
+    Some(SmtpConfig {
+        host,
+        port: layer("SMTP_PORT", smtp.port).unwrap_or(match tls {
+            SmtpTls::Tls => 465,
+            SmtpTls::Starttls => 587,
+            SmtpTls::None => 25,
+        }),
+        username: layer("SMTP_USERNAME", smtp.username),
+        password: layer("SMTP_PASSWORD", smtp.password),
+        from,
+        tls,
+    })
+}
+
+fn find_config_file() -> Result<Option<PathBuf>, String> {
+    if let Some(path) = std::env::var("CONFIG_PATH").ok().filter(|p| !p.trim().is_empty()) {
+        let path = PathBuf::from(path);
+        if !path.is_file() {
+            return Err(format!("CONFIG_PATH points to {}, which does not exist", path.display()));
+        }
+        return Ok(Some(path));
+    }
+
+    Ok([exe_dir().join("config.toml"), PathBuf::from("config.toml")]
+        .into_iter()
+        .find(|p| p.is_file()))
+}
+
+fn exe_dir() -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|path| path.parent().map(Path::to_path_buf))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// The environment variable `key` if set, otherwise the file's value.
+/// Empty or unparsable variables are ignored.
+fn layer<T: FromStr>(key: &str, file_value: Option<T>) -> Option<T> {
+    let Ok(raw) = std::env::var(key) else {
+        return file_value;
+    };
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return file_value;
+    }
+    match raw.parse() {
+        Ok(value) => Some(value),
+        Err(_) => {
+            eprintln!("Warning: ignoring invalid {}={:?}", key, raw);
+            file_value
+        }
+    }
+}
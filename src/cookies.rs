@@ -0,0 +1,80 @@
+//! Minimal `Cookie` request header parsing (RFC 6265 `name=value` pairs).
+
+use axum::http::header::COOKIE;
+use axum::http::HeaderMap;
+
+/// The value of cookie `name`, unquoted and percent-decoded. Looks through
+/// every `Cookie` header, since HTTP/2 clients may send one per cookie.
+/// The first non-empty match wins, as browsers send the most specific first.
+pub fn get(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|header| header.split(';'))
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, value)| key.trim() == name && !value.trim().is_empty())
+        .map(|(_, value)| decode_value(value.trim()))
+}
+
+fn decode_value(raw: &str) -> String {
+    let unquoted = raw
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(raw);
+    // A stray '%' that isn't an escape is kept as-is rather than rejected
+    urlencoding::decode(unquoted)
+        .map(|v| v.into_owned())
+        .unwrap_or_else(|_| unquoted.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(values: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append(COOKIE, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_get_cookie() {
+        let h = headers(&["theme=dark; session=abc123; lang=en"]);
+        assert_eq!(get(&h, "session").as_deref(), Some("abc123"));
+        assert_eq!(get(&h, "missing"), None);
+
+        // Other cookies containing '=' don't confuse the split
+        let h = headers(&["prefs=a=1&b=2; session=tok=en"]);
+        assert_eq!(get(&h, "session").as_deref(), Some("tok=en"));
+
+        // Names must match exactly
+        let h = headers(&["my_session=nope; session=yes"]);
+        assert_eq!(get(&h, "session").as_deref(), Some("yes"));
+    }
+
+    #[test]
+    fn test_get_cookie_decoding() {
+        let h = headers(&["session=\"quoted\""]);
+        assert_eq!(get(&h, "session").as_deref(), Some("quoted"));
+
+        let h = headers(&["session=a%2Bb%3D%3D"]);
+        assert_eq!(get(&h, "session").as_deref(), Some("a+b=="));
+
+        let h = headers(&["session=100%"]);
+        assert_eq!(get(&h, "session").as_deref(), Some("100%"));
+    }
+
+    #[test]
+    fn test_get_cookie_across_headers() {
+        let h = headers(&["theme=dark", "session=abc"]);
+        assert_eq!(get(&h, "session").as_deref(), Some("abc"));
+
+        // A cleared cookie doesn't shadow a real one
+        let h = headers(&["session=; session=abc"]);
+        assert_eq!(get(&h, "session").as_deref(), Some("abc"));
+    }
+}
@@ -1,5 +1,11 @@
 use serde::{Deserialize, Serialize};
-use sqlx::{FromRow, SqlitePool, sqlite::SqlitePoolOptions};
+use sqlx::{FromRow, SqlitePool, sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous}};
+use std::str::FromStr;
+
+use crate::scrapers::LinkType;
+
+/// How long a connection waits on a locked database before failing with "database is locked"
+const BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
 
 // ─── Download-related row types ───
 
@@ -20,6 +26,14 @@ pub struct DownloadRow {
     pub game_size: String,
     pub client_id: Option<String>,
     pub user_id: Option<i64>,
+    #[sqlx(default)]
+    pub md5_status: Option<String>,
+    #[sqlx(default)]
+    pub debrid_status: Option<String>,
+    #[sqlx(default)]
+    pub install_step: Option<String>,
+    #[sqlx(default)]
+    pub install_dir: Option<String>,
 }
 
 #[derive(Debug, Clone, FromRow)]
@@ -31,13 +45,14 @@ pub struct DownloadFileRow {
     pub is_extracted: bool,
 }
 
-#[derive(Debug, Clone, Serialize, FromRow)]
+#[derive(Debug, Clone, Serialize, FromRow, utoipa::ToSchema)]
 pub struct Game {
     pub id: i64,
     pub title: String,
     pub source: String,  // "fitgirl" or "steamrip"
     pub file_size: String,
     pub magnet_link: String,  // Can be magnet link or DDL
+    pub link_type: LinkType,
     pub genres: Option<String>,
     pub company: Option<String>,
     pub original_size: Option<String>,
@@ -46,456 +61,82 @@ pub struct Game {
     pub source_url: Option<String>,
     pub post_date: Option<String>,
     pub search_title: Option<String>,
+    /// Detail page views, counted at most once per viewer every half hour
+    pub view_count: i64,
+}
+
+/// Largest page any list endpoint returns
+pub const MAX_PER_PAGE: i64 = 200;
+
+/// Resolve `page`/`per_page` query values: defaults when absent, an error for
+/// zero or negative values, and `per_page` capped at `max_per_page`
+pub fn page_bounds(
+    page: Option<i64>,
+    per_page: Option<i64>,
+    default_per_page: i64,
+    max_per_page: i64,
+) -> Result<(i64, i64), String> {
+    let page = page.unwrap_or(1);
+    let per_page = per_page.unwrap_or(default_per_page);
+    if page < 1 {
+        return Err("page must be 1 or greater".to_string());
+    }
+    if per_page < 1 {
+        return Err("per_page must be 1 or greater".to_string());
+    }
+    Ok((page, per_page.min(max_per_page)))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct GameQuery {
+    /// Words to match in the title
     pub search: Option<String>,
+    /// title_asc, title_desc, size_asc, size_desc, date_asc, date_desc or popular (most viewed); newest added first by default
     pub sort: Option<String>,
     pub genre: Option<String>,
-    pub source: Option<String>,  // Filter by source
+    /// "fitgirl" or "steamrip"
+    pub source: Option<String>,
+    /// "magnet" or "ddl"
+    pub link_type: Option<String>,
+    /// 1-based page number
     pub page: Option<i64>,
+    /// Games per page, default 50, capped at 200
     pub per_page: Option<i64>,
-    pub ids: Option<String>,  // Comma-separated game IDs for batch fetching
+    /// Comma-separated game IDs for batch fetching
+    pub ids: Option<String>,
+    /// Only games on the logged-in user's wishlist
+    pub wishlist: Option<bool>,
+    /// "exclude" (default) leaves out games the logged-in user hid and games
+    /// an admin blacklisted; "only" returns just those
+    pub hidden: Option<String>,
+    /// The logged-in user the wishlist and hidden filters apply to, filled in by the handler
+    #[serde(skip)]
+    pub user_id: Option<i64>,
+    /// Only games posted after this `YYYY-MM-DDTHH:MM:SS` time, filled in by the "new games" handler
+    #[serde(skip)]
+    pub posted_after: Option<String>,
+    /// `genre` expanded through the alias map: every group must match, by any
+    /// spelling in it. Filled in by the handler; `genre` is matched as-is when empty.
+    #[serde(skip)]
+    pub genre_variants: Vec<Vec<String>>,
 }
 
 /// Initialize the database connection pool and run migrations.
-pub async fn init_db(database_url: &str) -> Result<SqlitePool, sqlx::Error> {
-    let pool = SqlitePoolOptions::new()
-        .max_connections(5)
-        .connect(database_url)
-        .await?;
-
-    // Create tables if they don't exist
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS games (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            title TEXT NOT NULL,
-            file_size TEXT NOT NULL DEFAULT '',
-            magnet_link TEXT NOT NULL,
-            genres TEXT,
-            company TEXT,
-            original_size TEXT,
-            thumbnail_url TEXT,
-            screenshots TEXT,
-            source_url TEXT,
-            post_date TEXT
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await?;
-
-    // Migrations for existing DBs - add new columns if they don't exist
-    for col in &["genres", "company", "original_size", "thumbnail_url", "source_url", "post_date", "screenshots", "search_title"] {
-        let _ = sqlx::query(&format!("ALTER TABLE games ADD COLUMN {} TEXT", col))
-            .execute(&pool)
-            .await;
-    }
-
-    // Add source column with default value 'fitgirl' for backward compatibility
-    let _ = sqlx::query("ALTER TABLE games ADD COLUMN source TEXT DEFAULT 'fitgirl'")
-        .execute(&pool)
-        .await;
-
-    // Set source='fitgirl' for existing games that have NULL source
-    let _ = sqlx::query("UPDATE games SET source = 'fitgirl' WHERE source IS NULL")
-        .execute(&pool)
-        .await;
-
-    // Add index for search performance
-    sqlx::query(
-        "CREATE INDEX IF NOT EXISTS idx_games_title ON games(title COLLATE NOCASE)"
-    )
-    .execute(&pool)
-    .await?;
-
-    sqlx::query(
-        "CREATE INDEX IF NOT EXISTS idx_games_search_title ON games(search_title COLLATE NOCASE)"
-    )
-    .execute(&pool)
-    .await?;
-
-    // Add index for source filtering
-    sqlx::query(
-        "CREATE INDEX IF NOT EXISTS idx_games_source ON games(source)"
-    )
-    .execute(&pool)
-    .await?;
-
-    // System checks table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS system_checks (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            check_date TEXT NOT NULL,
-            ram_available_gb REAL,
-            temp_space_gb REAL,
-            cpu_cores INTEGER,
-            antivirus_active BOOLEAN,
-            missing_dlls TEXT,
-            missing_dependencies TEXT,
-            overall_status TEXT
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await?;
-
-    // Installation logs table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS installation_logs (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            game_id INTEGER,
-            started_at TEXT NOT NULL,
-            completed_at TEXT,
-            status TEXT NOT NULL,
-            error_code TEXT,
-            error_message TEXT,
-            ram_usage_peak REAL,
-            install_duration_minutes INTEGER,
-            FOREIGN KEY (game_id) REFERENCES games(id)
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await?;
-
-    // Community ratings table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS community_ratings (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            game_id INTEGER NOT NULL,
-            install_difficulty INTEGER,
-            install_success BOOLEAN,
-            issues_encountered TEXT,
-            created_at TEXT NOT NULL,
-            FOREIGN KEY (game_id) REFERENCES games(id)
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await?;
-
-    // Game requirements table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS game_requirements (
-            game_id INTEGER PRIMARY KEY,
-            min_ram_gb INTEGER,
-            rec_ram_gb INTEGER,
-            min_cpu TEXT,
-            rec_cpu TEXT,
-            min_gpu TEXT,
-            rec_gpu TEXT,
-            disk_space_gb INTEGER,
-            requires_directx TEXT,
-            requires_dotnet TEXT,
-            requires_vcredist TEXT,
-            FOREIGN KEY (game_id) REFERENCES games(id)
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await?;
-
-    // Download management tables
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS downloads (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            game_id INTEGER NOT NULL,
-            status TEXT NOT NULL DEFAULT 'queued',
-            progress REAL DEFAULT 0.0,
-            download_speed TEXT,
-            eta TEXT,
-            file_path TEXT,
-            installer_path TEXT,
-            error_message TEXT,
-            created_at TEXT NOT NULL,
-            completed_at TEXT,
-            FOREIGN KEY (game_id) REFERENCES games(id)
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await?;
-
-    // Migration: add installer_path column if it doesn't exist (for existing DBs)
-    let _ = sqlx::query("ALTER TABLE downloads ADD COLUMN installer_path TEXT")
-        .execute(&pool)
-        .await;
-
-    // Migration: add client_id column for assigning downloads to specific clients
-    let _ = sqlx::query("ALTER TABLE downloads ADD COLUMN client_id TEXT")
-        .execute(&pool)
-        .await;
-
-    // Migration: add user_id column to link downloads to users
-    let _ = sqlx::query("ALTER TABLE downloads ADD COLUMN user_id INTEGER")
-        .execute(&pool)
-        .await;
-
-    // Settings key-value table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS settings (
-            key TEXT PRIMARY KEY,
-            value TEXT NOT NULL
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await?;
-
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS download_files (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            download_id INTEGER NOT NULL,
-            filename TEXT NOT NULL,
-            file_size INTEGER,
-            file_path TEXT,
-            is_extracted BOOLEAN DEFAULT 0,
-            FOREIGN KEY (download_id) REFERENCES downloads(id)
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await?;
-
-    // Users table for authentication
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS users (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            username TEXT UNIQUE NOT NULL,
-            password_hash TEXT NOT NULL,
-            is_admin BOOLEAN DEFAULT 0,
-            created_at TEXT NOT NULL,
-            last_login TEXT
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await?;
-
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_users_username ON users(username)")
-        .execute(&pool)
-        .await?;
-
-    // Sessions table for login sessions
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS sessions (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            session_token TEXT UNIQUE NOT NULL,
-            user_id INTEGER NOT NULL,
-            created_at TEXT NOT NULL,
-            expires_at TEXT NOT NULL,
-            FOREIGN KEY (user_id) REFERENCES users(id)
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await?;
-
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_sessions_token ON sessions(session_token)")
-        .execute(&pool)
-        .await?;
-
-    // User-specific favorites
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS user_favorites (
-            user_id INTEGER NOT NULL,
-            game_id INTEGER NOT NULL,
-            created_at TEXT NOT NULL,
-            PRIMARY KEY (user_id, game_id),
-            FOREIGN KEY (user_id) REFERENCES users(id),
-            FOREIGN KEY (game_id) REFERENCES games(id)
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await?;
-
-    // User-specific downloads
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS user_downloads (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            user_id INTEGER NOT NULL,
-            download_id INTEGER NOT NULL,
-            created_at TEXT NOT NULL,
-            FOREIGN KEY (user_id) REFERENCES users(id),
-            FOREIGN KEY (download_id) REFERENCES downloads(id)
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await?;
-
-    // User settings
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS user_settings (
-            user_id INTEGER PRIMARY KEY,
-            theme TEXT DEFAULT 'dark',
-            notifications_enabled BOOLEAN DEFAULT 1,
-            auto_download BOOLEAN DEFAULT 0,
-            FOREIGN KEY (user_id) REFERENCES users(id)
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await?;
-
-    // Add new columns for enhanced settings (migrations for existing DBs)
-    let _ = sqlx::query("ALTER TABLE user_settings ADD COLUMN download_path TEXT")
-        .execute(&pool)
-        .await;
-    let _ = sqlx::query("ALTER TABLE user_settings ADD COLUMN scraper_fitgirl_enabled BOOLEAN DEFAULT 1")
-        .execute(&pool)
-        .await;
-    let _ = sqlx::query("ALTER TABLE user_settings ADD COLUMN scraper_steamrip_enabled BOOLEAN DEFAULT 1")
-        .execute(&pool)
-        .await;
-    let _ = sqlx::query("ALTER TABLE user_settings ADD COLUMN notify_download_complete BOOLEAN DEFAULT 1")
-        .execute(&pool)
-        .await;
-    let _ = sqlx::query("ALTER TABLE user_settings ADD COLUMN notify_new_games BOOLEAN DEFAULT 0")
-        .execute(&pool)
-        .await;
-    let _ = sqlx::query("ALTER TABLE user_settings ADD COLUMN notify_errors BOOLEAN DEFAULT 1")
-        .execute(&pool)
-        .await;
-
-    // Game tags table for filtering
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS game_tags (
-            game_id INTEGER NOT NULL,
-            tag TEXT NOT NULL,
-            PRIMARY KEY (game_id, tag),
-            FOREIGN KEY (game_id) REFERENCES games(id)
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await?;
-
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_game_tags_tag ON game_tags(tag)")
-        .execute(&pool)
-        .await?;
-
-    // Game categories table for carousel (top 50, top 150, etc.)
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS game_categories (
-            game_id INTEGER NOT NULL,
-            category TEXT NOT NULL,
-            rank INTEGER,
-            scraped_at TEXT NOT NULL,
-            PRIMARY KEY (game_id, category),
-            FOREIGN KEY (game_id) REFERENCES games(id) ON DELETE CASCADE
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await?;
-
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_game_categories_category ON game_categories(category, rank)")
-        .execute(&pool)
-        .await?;
-
-    // Notifications table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS notifications (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            user_id INTEGER NOT NULL,
-            type TEXT NOT NULL,
-            title TEXT NOT NULL,
-            message TEXT NOT NULL,
-            read BOOLEAN DEFAULT 0,
-            created_at TEXT NOT NULL,
-            FOREIGN KEY (user_id) REFERENCES users(id)
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await?;
-
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_notifications_user_id ON notifications(user_id)")
-        .execute(&pool)
-        .await?;
-
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_notifications_read ON notifications(read)")
-        .execute(&pool)
-        .await?;
-
-    // Create clients table for tracking Windows client agents
-    // Add user_id to link clients to users
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS clients (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            client_id TEXT UNIQUE NOT NULL,
-            client_name TEXT NOT NULL,
-            user_id INTEGER,
-            os_version TEXT,
-            ram_total_gb REAL,
-            ram_available_gb REAL,
-            disk_space_gb REAL,
-            cpu_cores INTEGER,
-            missing_dlls TEXT,
-            last_seen TEXT NOT NULL,
-            registered_at TEXT NOT NULL,
-            FOREIGN KEY (user_id) REFERENCES users(id)
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await?;
-
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_clients_client_id ON clients(client_id)")
-        .execute(&pool)
-        .await?;
+pub async fn init_db(database_url: &str, max_connections: u32) -> Result<SqlitePool, sqlx::Error> {
+    // WAL lets readers run alongside the download workers' writes; every
+    // pooled connection gets these pragmas when it's opened
+    let options = SqliteConnectOptions::from_str(database_url)?
+        .journal_mode(SqliteJournalMode::Wal)
+        .synchronous(SqliteSynchronous::Normal)
+        .busy_timeout(BUSY_TIMEOUT);
 
-    // Create client_progress table for tracking extraction progress
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS client_progress (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            client_id TEXT NOT NULL,
-            game_id INTEGER,
-            file_path TEXT NOT NULL,
-            total_bytes INTEGER NOT NULL DEFAULT 0,
-            extracted_bytes INTEGER NOT NULL DEFAULT 0,
-            progress_percent REAL NOT NULL DEFAULT 0,
-            speed_mbps REAL NOT NULL DEFAULT 0,
-            eta_seconds INTEGER NOT NULL DEFAULT 0,
-            status TEXT NOT NULL,
-            updated_at TEXT NOT NULL,
-            FOREIGN KEY (client_id) REFERENCES clients(client_id)
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await?;
-
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_client_progress_client_id ON client_progress(client_id)")
-        .execute(&pool)
+    let pool = SqlitePoolOptions::new()
+        .max_connections(max_connections)
+        .connect_with(options)
         .await?;
 
-    // Migrations for existing databases
-    let _ = sqlx::query("ALTER TABLE clients ADD COLUMN user_id INTEGER")
-        .execute(&pool)
-        .await;
+    crate::migrations::run(&pool).await?;
 
     // Create default admin user if no users exist
     let user_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM users")
@@ -525,6 +166,162 @@ pub async fn init_db(database_url: &str) -> Result<SqlitePool, sqlx::Error> {
     Ok(pool)
 }
 
+/// WHERE and ORDER BY clauses for a [`GameQuery`]'s filters, shared by the
+/// paged listing and the CSV export
+struct GameFilter {
+    where_clause: String,
+    order_clause: &'static str,
+    bind_values: Vec<String>,
+}
+
+impl GameFilter {
+    fn from_query(query: &GameQuery) -> Self {
+        let search_pattern = query
+            .search
+            .as_deref()
+            .filter(|s| !s.is_empty())
+            .map(|s| format!("%{}%", s));
+
+        let genre_pattern = query
+            .genre
+            .as_deref()
+            .filter(|s| !s.is_empty())
+            .map(|s| format!("%{}%", s));
+
+        // Date and title sorts are backed by the indexes created in init_db
+        let order_clause = match query.sort.as_deref() {
+            Some("title_asc") => "title COLLATE NOCASE ASC",
+            Some("title_desc") => "title COLLATE NOCASE DESC",
+            Some("size_asc") => "file_size ASC",
+            Some("size_desc") => "file_size DESC",
+            Some("date_asc") => "COALESCE(post_date, '') ASC, id ASC",
+            Some("date_desc") => "COALESCE(post_date, '') DESC, id DESC",
+            Some("popular") => "view_count DESC, id DESC",
+            _ => "id DESC",
+        };
+
+        // Build WHERE clauses dynamically
+        let mut conditions: Vec<String> = Vec::new();
+        let mut bind_values: Vec<String> = Vec::new();
+
+        if let Some(ref pattern) = search_pattern {
+            conditions.push("(title LIKE ? OR search_title LIKE ?)".to_string());
+            bind_values.push(pattern.clone());
+            bind_values.push(pattern.clone());
+        }
+
+        if !query.genre_variants.is_empty() {
+            for variants in &query.genre_variants {
+                let any = vec!["genres LIKE ?"; variants.len()].join(" OR ");
+                conditions.push(format!("({})", any));
+                bind_values.extend(variants.iter().map(|v| format!("%{}%", v)));
+            }
+        } else if let Some(ref pattern) = genre_pattern {
+            conditions.push("genres LIKE ?".to_string());
+            bind_values.push(pattern.clone());
+        }
+
+        // Filter by source
+        if let Some(ref source) = query.source {
+            if source != "all" && !source.is_empty() {
+                conditions.push("source = ?".to_string());
+                bind_values.push(source.clone());
+            }
+        }
+
+        if let Some(link_type) = query.link_type.as_deref().filter(|t| !t.is_empty()) {
+            conditions.push("link_type = ?".to_string());
+            bind_values.push(link_type.to_string());
+        }
+
+        // Post dates are the sources' `YYYY-MM-DDTHH:MM:SS`, so they compare as text
+        if let Some(ref after) = query.posted_after {
+            conditions.push("post_date > ?".to_string());
+            bind_values.push(after.clone());
+        }
+
+        // Batch of specific IDs
+        if let Some(ref ids_str) = query.ids {
+            let ids: Vec<&str> = ids_str
+                .split(',')
+                .map(str::trim)
+                .filter(|s| s.parse::<i64>().is_ok())
+                .collect();
+            conditions.push(format!("id IN ({})", vec!["?"; ids.len()].join(",")));
+            bind_values.extend(ids.into_iter().map(String::from));
+        }
+
+        if query.wishlist == Some(true) {
+            if let Some(user_id) = query.user_id {
+                conditions.push("id IN (SELECT game_id FROM user_wishlist WHERE user_id = ?)".to_string());
+                bind_values.push(user_id.to_string());
+            }
+        }
+
+        // Blacklisted entries are matched by source and title so they stay
+        // hidden after a rescrape gives them new IDs
+        let blacklisted = "EXISTS (SELECT 1 FROM game_blacklist b WHERE b.source = games.source AND b.title = games.title)";
+        let hidden_by_user = "id IN (SELECT game_id FROM user_hidden_games WHERE user_id = ?)";
+        if query.hidden.as_deref() == Some("only") {
+            match query.user_id {
+                Some(user_id) => {
+                    conditions.push(format!("({} OR {})", blacklisted, hidden_by_user));
+                    bind_values.push(user_id.to_string());
+                }
+                None => conditions.push(blacklisted.to_string()),
+            }
+        } else {
+            conditions.push(format!("NOT {}", blacklisted));
+            if let Some(user_id) = query.user_id {
+                conditions.push(format!("NOT {}", hidden_by_user));
+                bind_values.push(user_id.to_string());
+            }
+        }
+
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        Self { where_clause, order_clause, bind_values }
+    }
+}
+
+/// Every game matching `query`'s filters, ignoring paging, sent one at a time
+/// so large exports never sit in memory. Stops once the receiver is dropped;
+/// a query error is sent as the last item, so the export can't end cleanly.
+pub async fn send_filtered_games(
+    pool: &SqlitePool,
+    query: &GameQuery,
+    tx: tokio::sync::mpsc::Sender<Result<Game, sqlx::Error>>,
+) {
+    use futures::TryStreamExt;
+
+    let GameFilter { where_clause, order_clause, bind_values } = GameFilter::from_query(query);
+    let sql = format!(
+        "SELECT id, title, source, file_size, magnet_link, link_type, genres, company, original_size, thumbnail_url, screenshots, source_url, post_date, search_title, view_count FROM games {} ORDER BY {}",
+        where_clause, order_clause
+    );
+    let mut select_query = sqlx::query_as::<_, Game>(&sql);
+    for val in &bind_values {
+        select_query = select_query.bind(val);
+    }
+
+    let mut rows = select_query.fetch(pool);
+    loop {
+        let row = match rows.try_next().await {
+            Ok(Some(game)) => Ok(game),
+            Ok(None) => break,
+            Err(e) => Err(e),
+        };
+        let failed = row.is_err();
+        if tx.send(row).await.is_err() || failed {
+            break;
+        }
+    }
+}
+
 /// Query games with search, sort, and pagination.
 pub async fn query_games(
     pool: &SqlitePool,
@@ -543,7 +340,7 @@ pub async fn query_games(
 
         let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
         let sql = format!(
-            "SELECT id, title, source, file_size, magnet_link, genres, company, original_size, thumbnail_url, screenshots, source_url, post_date, search_title FROM games WHERE id IN ({})",
+            "SELECT id, title, source, file_size, magnet_link, link_type, genres, company, original_size, thumbnail_url, screenshots, source_url, post_date, search_title, view_count FROM games WHERE id IN ({})",
             placeholders
         );
 
@@ -557,60 +354,13 @@ pub async fn query_games(
         return Ok((games, count));
     }
 
-    let per_page = query.per_page.unwrap_or(50);
-    let page = query.page.unwrap_or(1);
+    // Handlers reject bad values with a 400; clamp here too so no caller can
+    // ask for the whole table or a negative offset
+    let per_page = query.per_page.unwrap_or(50).clamp(1, MAX_PER_PAGE);
+    let page = query.page.unwrap_or(1).max(1);
     let offset = (page - 1) * per_page;
 
-    let search_pattern = query
-        .search
-        .as_deref()
-        .filter(|s| !s.is_empty())
-        .map(|s| format!("%{}%", s));
-
-    let genre_pattern = query
-        .genre
-        .as_deref()
-        .filter(|s| !s.is_empty())
-        .map(|s| format!("%{}%", s));
-
-    let order_clause = match query.sort.as_deref() {
-        Some("title_asc") => "title COLLATE NOCASE ASC",
-        Some("title_desc") => "title COLLATE NOCASE DESC",
-        Some("size_asc") => "file_size ASC",
-        Some("size_desc") => "file_size DESC",
-        Some("date_asc") => "COALESCE(post_date, '') ASC, id ASC",
-        Some("date_desc") => "COALESCE(post_date, '') DESC, id DESC",
-        _ => "id DESC",
-    };
-
-    // Build WHERE clauses dynamically
-    let mut conditions: Vec<String> = Vec::new();
-    let mut bind_values: Vec<String> = Vec::new();
-
-    if let Some(ref pattern) = search_pattern {
-        conditions.push("(title LIKE ? OR search_title LIKE ?)".to_string());
-        bind_values.push(pattern.clone());
-        bind_values.push(pattern.clone());
-    }
-
-    if let Some(ref pattern) = genre_pattern {
-        conditions.push("genres LIKE ?".to_string());
-        bind_values.push(pattern.clone());
-    }
-
-    // Filter by source
-    if let Some(ref source) = query.source {
-        if source != "all" && !source.is_empty() {
-            conditions.push("source = ?".to_string());
-            bind_values.push(source.clone());
-        }
-    }
-
-    let where_clause = if conditions.is_empty() {
-        String::new()
-    } else {
-        format!("WHERE {}", conditions.join(" AND "))
-    };
+    let GameFilter { where_clause, order_clause, bind_values } = GameFilter::from_query(&query);
 
     // Count total matching rows
     let count_sql = format!("SELECT COUNT(*) FROM games {}", where_clause);
@@ -622,7 +372,7 @@ pub async fn query_games(
 
     // Fetch page of results
     let select_sql = format!(
-        "SELECT id, title, source, file_size, magnet_link, genres, company, original_size, thumbnail_url, screenshots, source_url, post_date, search_title FROM games {} ORDER BY {} LIMIT ? OFFSET ?",
+        "SELECT id, title, source, file_size, magnet_link, link_type, genres, company, original_size, thumbnail_url, screenshots, source_url, post_date, search_title, view_count FROM games {} ORDER BY {} LIMIT ? OFFSET ?",
         where_clause, order_clause
     );
     let mut select_query = sqlx::query_as::<_, Game>(&select_sql);
@@ -639,7 +389,10 @@ pub async fn query_games(
 }
 
 /// Get all unique genres from the database, split by comma.
-pub async fn get_all_genres(pool: &SqlitePool) -> Result<Vec<(String, i64)>, sqlx::Error> {
+pub async fn get_all_genres(
+    pool: &SqlitePool,
+    aliases: &crate::genres::GenreAliases,
+) -> Result<Vec<(String, i64)>, sqlx::Error> {
     // Get all genre strings
     let rows: Vec<(String,)> = sqlx::query_as(
         "SELECT genres FROM games WHERE genres IS NOT NULL AND genres != ''"
@@ -647,27 +400,35 @@ pub async fn get_all_genres(pool: &SqlitePool) -> Result<Vec<(String, i64)>, sql
     .fetch_all(pool)
     .await?;
 
-    // Split by comma, count occurrences
-    let mut genre_counts: std::collections::HashMap<String, i64> = std::collections::HashMap::new();
+    // Split by comma and count each game once per canonical genre. Keyed by
+    // lowercase name, shown capitalized if any game spells it that way.
+    let mut genre_counts: std::collections::HashMap<String, (String, i64)> = std::collections::HashMap::new();
     for (genres_str,) in rows {
-        for genre in genres_str.split(',') {
-            let trimmed = genre.trim().to_string();
-            if !trimmed.is_empty() {
-                *genre_counts.entry(trimmed).or_insert(0) += 1;
+        let mut seen = std::collections::HashSet::new();
+        for genre in genres_str.split(',').filter(|g| !g.trim().is_empty()) {
+            for name in aliases.canonical(genre) {
+                if !seen.insert(name.to_lowercase()) {
+                    continue;
+                }
+                let entry = genre_counts.entry(name.to_lowercase()).or_insert_with(|| (name.clone(), 0));
+                if !entry.0.starts_with(char::is_uppercase) && name.starts_with(char::is_uppercase) {
+                    entry.0 = name;
+                }
+                entry.1 += 1;
             }
         }
     }
 
     // Sort by count descending
-    let mut genres: Vec<(String, i64)> = genre_counts.into_iter().collect();
-    genres.sort_by(|a, b| b.1.cmp(&a.1));
+    let mut genres: Vec<(String, i64)> = genre_counts.into_values().collect();
+    genres.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
     Ok(genres)
 }
 
 /// Get a random game
 pub async fn get_random_game(pool: &SqlitePool) -> Result<Game, sqlx::Error> {
     sqlx::query_as::<_, Game>(
-        "SELECT id, title, source, file_size, magnet_link, genres, company, original_size, thumbnail_url, screenshots, source_url, post_date, search_title FROM games ORDER BY RANDOM() LIMIT 1"
+        "SELECT id, title, source, file_size, magnet_link, link_type, genres, company, original_size, thumbnail_url, screenshots, source_url, post_date, search_title, view_count FROM games ORDER BY RANDOM() LIMIT 1"
     )
     .fetch_one(pool)
     .await
@@ -676,40 +437,84 @@ pub async fn get_random_game(pool: &SqlitePool) -> Result<Game, sqlx::Error> {
 /// Get a single game by ID.
 pub async fn get_game_by_id(pool: &SqlitePool, id: i64) -> Result<Game, sqlx::Error> {
     sqlx::query_as::<_, Game>(
-        "SELECT id, title, source, file_size, magnet_link, genres, company, original_size, thumbnail_url, screenshots, source_url, post_date, search_title FROM games WHERE id = ?"
+        "SELECT id, title, source, file_size, magnet_link, link_type, genres, company, original_size, thumbnail_url, screenshots, source_url, post_date, search_title, view_count FROM games WHERE id = ?"
     )
     .bind(id)
     .fetch_one(pool)
     .await
 }
 
-/// Get existing metadata cache — returns map of lowercase title -> (thumbnail_url, genres)
-/// Used to avoid re-querying RAWG for games we already have metadata for.
-pub async fn get_metadata_cache(pool: &SqlitePool) -> Result<std::collections::HashMap<String, (Option<String>, Option<String>)>, sqlx::Error> {
+/// RAWG lookups from earlier scrapes, keyed by [`crate::rawg::cache_key`].
+/// Entries older than `max_age_days` are left out so they get fetched again;
+/// 0 keeps them forever.
+pub async fn get_metadata_cache(
+    pool: &SqlitePool,
+    max_age_days: i64,
+) -> Result<std::collections::HashMap<String, (Option<String>, Option<String>)>, sqlx::Error> {
     let rows: Vec<(String, Option<String>, Option<String>)> = sqlx::query_as(
-        "SELECT title, thumbnail_url, genres FROM games WHERE thumbnail_url IS NOT NULL OR genres IS NOT NULL"
+        "SELECT normalized_title, image_url, genres FROM rawg_cache WHERE ? = 0 OR fetched_at > ?"
     )
+    .bind(max_age_days)
+    .bind(rawg_cache_cutoff(max_age_days))
     .fetch_all(pool)
     .await?;
 
-    let mut cache = std::collections::HashMap::new();
-    for (title, thumb, genres) in rows {
-        let norm = title.to_lowercase()
-            .replace(|c: char| !c.is_alphanumeric() && c != ' ', "")
-            .split_whitespace()
-            .collect::<Vec<_>>()
-            .join(" ");
-        cache.insert(norm, (thumb, genres));
+    Ok(rows
+        .into_iter()
+        .map(|(title, image_url, genres)| (title, (image_url, genres)))
+        .collect())
+}
+
+/// Remember a successful RAWG lookup, replacing any older entry for the title
+pub async fn save_rawg_metadata(
+    pool: &SqlitePool,
+    normalized_title: &str,
+    image_url: Option<&str>,
+    genres: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO rawg_cache (normalized_title, image_url, genres, fetched_at) VALUES (?, ?, ?, ?)
+         ON CONFLICT(normalized_title) DO UPDATE SET
+            image_url = excluded.image_url, genres = excluded.genres, fetched_at = excluded.fetched_at"
+    )
+    .bind(normalized_title)
+    .bind(image_url)
+    .bind(genres)
+    .bind(chrono::Utc::now().to_rfc3339())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Drop RAWG cache entries older than `max_age_days` (none when it's 0)
+pub async fn prune_rawg_cache(pool: &SqlitePool, max_age_days: i64) -> Result<u64, sqlx::Error> {
+    if max_age_days == 0 {
+        return Ok(0);
     }
-    Ok(cache)
+    let result = sqlx::query("DELETE FROM rawg_cache WHERE fetched_at <= ?")
+        .bind(rawg_cache_cutoff(max_age_days))
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+fn rawg_cache_cutoff(max_age_days: i64) -> String {
+    (chrono::Utc::now() - chrono::Duration::days(max_age_days)).to_rfc3339()
 }
 
-/// A game record ready for insertion
+/// A game record ready for insertion. Also the shape accepted by the JSON
+/// import, where anything but `title` and `magnet_link` may be left out.
+#[derive(Deserialize, utoipa::ToSchema)]
 pub struct GameInsert {
     pub title: String,
+    #[serde(default = "default_game_source")]
     pub source: String,  // "fitgirl" or "steamrip"
+    #[serde(default)]
     pub file_size: String,
     pub magnet_link: String,  // Can be magnet link or DDL
+    /// Derived from the link; recomputed on import
+    #[serde(default)]
+    pub link_type: LinkType,
     pub genres: Option<String>,
     pub company: Option<String>,
     pub original_size: Option<String>,
@@ -717,9 +522,14 @@ pub struct GameInsert {
     pub screenshots: Option<String>,
     pub source_url: Option<String>,
     pub post_date: Option<String>,
+    /// Derived from the title; recomputed on import
     pub search_title: Option<String>,
 }
 
+fn default_game_source() -> String {
+    "fitgirl".to_string()
+}
+
 /// Clean a game title for search indexing.
 /// Strips version numbers, DLC lists, language tags, parenthetical info, etc.
 /// so that searching "Cyberpunk 2077" matches "Cyberpunk 2077 (v2.13 + All DLCs + Bonus Content, MULTi18)"
@@ -750,6 +560,18 @@ pub fn clean_search_title(title: &str) -> String {
     clean.trim().to_string()
 }
 
+/// Count one detail page view
+pub async fn increment_view_count(pool: &SqlitePool, game_id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE games SET view_count = view_count + 1 WHERE id = ?")
+        .bind(game_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Rows per multi-row INSERT in `replace_all_games`
+const GAME_INSERT_BATCH_SIZE: usize = 500;
+
 /// Atomically replace all games in a single transaction.
 /// Deletes existing games and inserts new ones; rolls back on failure.
 pub async fn replace_all_games(
@@ -759,34 +581,173 @@ pub async fn replace_all_games(
     let count = games.len();
     let mut tx = pool.begin().await?;
 
+    // View counts belong to the game, not the row, so carry them over to
+    // the re-inserted rows
+    sqlx::query("DROP TABLE IF EXISTS temp.kept_view_counts")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        "CREATE TEMP TABLE kept_view_counts AS
+         SELECT source, title, view_count FROM games WHERE view_count > 0"
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // Wishlists and hidden games are keyed by game ID, so remember them by
+    // source and title and point them at the new rows afterwards
+    sqlx::query("DROP TABLE IF EXISTS temp.kept_wishlist")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        "CREATE TEMP TABLE kept_wishlist AS
+         SELECT w.user_id, g.source, g.title, w.created_at
+         FROM user_wishlist w JOIN games g ON g.id = w.game_id"
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("DROP TABLE IF EXISTS temp.kept_hidden_games")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        "CREATE TEMP TABLE kept_hidden_games AS
+         SELECT h.user_id, g.source, g.title, h.created_at
+         FROM user_hidden_games h JOIN games g ON g.id = h.game_id"
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("DELETE FROM user_wishlist")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM user_hidden_games")
+        .execute(&mut *tx)
+        .await?;
     sqlx::query("DELETE FROM games")
         .execute(&mut *tx)
         .await?;
 
-    for g in &games {
-        sqlx::query(
-            "INSERT INTO games (title, source, file_size, magnet_link, genres, company, original_size, thumbnail_url, screenshots, source_url, post_date, search_title) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
-        )
-            .bind(&g.title)
-            .bind(&g.source)
-            .bind(&g.file_size)
-            .bind(&g.magnet_link)
-            .bind(&g.genres)
-            .bind(&g.company)
-            .bind(&g.original_size)
-            .bind(&g.thumbnail_url)
-            .bind(&g.screenshots)
-            .bind(&g.source_url)
-            .bind(&g.post_date)
-            .bind(&g.search_title)
-            .execute(&mut *tx)
-            .await?;
+    // One multi-row INSERT per chunk instead of one statement per game;
+    // 500 rows × 13 columns stays well under SQLite's bound-parameter limit
+    for chunk in games.chunks(GAME_INSERT_BATCH_SIZE) {
+        let mut builder: sqlx::QueryBuilder<sqlx::Sqlite> = sqlx::QueryBuilder::new(
+            "INSERT INTO games (title, source, file_size, magnet_link, link_type, genres, company, original_size, thumbnail_url, screenshots, source_url, post_date, search_title) "
+        );
+        builder.push_values(chunk, |mut row, g| {
+            row.push_bind(&g.title)
+                .push_bind(&g.source)
+                .push_bind(&g.file_size)
+                .push_bind(&g.magnet_link)
+                .push_bind(g.link_type)
+                .push_bind(&g.genres)
+                .push_bind(&g.company)
+                .push_bind(&g.original_size)
+                .push_bind(&g.thumbnail_url)
+                .push_bind(&g.screenshots)
+                .push_bind(&g.source_url)
+                .push_bind(&g.post_date)
+                .push_bind(&g.search_title);
+        });
+        builder.build().execute(&mut *tx).await?;
     }
 
+    sqlx::query(
+        "UPDATE games SET view_count = k.view_count
+         FROM kept_view_counts k
+         WHERE k.source = games.source AND k.title = games.title"
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("DROP TABLE temp.kept_view_counts")
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        "INSERT OR IGNORE INTO user_wishlist (user_id, game_id, created_at)
+         SELECT k.user_id, g.id, k.created_at
+         FROM kept_wishlist k JOIN games g ON g.source = k.source AND g.title = k.title"
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("DROP TABLE temp.kept_wishlist")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        "INSERT OR IGNORE INTO user_hidden_games (user_id, game_id, created_at)
+         SELECT k.user_id, g.id, k.created_at
+         FROM kept_hidden_games k JOIN games g ON g.source = k.source AND g.title = k.title"
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("DROP TABLE temp.kept_hidden_games")
+        .execute(&mut *tx)
+        .await?;
+
     tx.commit().await?;
     Ok(count)
 }
 
+/// Add games, updating any existing game with the same source and title in
+/// place so its ID (and with it favorites and download history) is kept.
+/// Returns (inserted, updated).
+pub async fn merge_games(
+    pool: &SqlitePool,
+    games: Vec<GameInsert>,
+) -> Result<(usize, usize), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let (mut inserted, mut updated) = (0, 0);
+
+    for g in &games {
+        let result = sqlx::query(
+            "UPDATE games SET file_size = ?, magnet_link = ?, link_type = ?, genres = ?, company = ?, original_size = ?,
+                thumbnail_url = ?, screenshots = ?, source_url = ?, post_date = ?, search_title = ?
+             WHERE source = ? AND title = ?"
+        )
+        .bind(&g.file_size)
+        .bind(&g.magnet_link)
+        .bind(g.link_type)
+        .bind(&g.genres)
+        .bind(&g.company)
+        .bind(&g.original_size)
+        .bind(&g.thumbnail_url)
+        .bind(&g.screenshots)
+        .bind(&g.source_url)
+        .bind(&g.post_date)
+        .bind(&g.search_title)
+        .bind(&g.source)
+        .bind(&g.title)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() > 0 {
+            updated += 1;
+            continue;
+        }
+
+        sqlx::query(
+            "INSERT INTO games (title, source, file_size, magnet_link, link_type, genres, company, original_size, thumbnail_url, screenshots, source_url, post_date, search_title) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&g.title)
+        .bind(&g.source)
+        .bind(&g.file_size)
+        .bind(&g.magnet_link)
+        .bind(g.link_type)
+        .bind(&g.genres)
+        .bind(&g.company)
+        .bind(&g.original_size)
+        .bind(&g.thumbnail_url)
+        .bind(&g.screenshots)
+        .bind(&g.source_url)
+        .bind(&g.post_date)
+        .bind(&g.search_title)
+        .execute(&mut *tx)
+        .await?;
+        inserted += 1;
+    }
+
+    tx.commit().await?;
+    Ok((inserted, updated))
+}
+
 /// Clear all games from the database.
 #[allow(dead_code)]
 pub async fn clear_games(pool: &SqlitePool) -> Result<(), sqlx::Error> {
@@ -804,12 +765,13 @@ pub async fn insert_games(
 
     for g in &games {
         sqlx::query(
-            "INSERT INTO games (title, source, file_size, magnet_link, genres, company, original_size, thumbnail_url, screenshots, source_url, post_date, search_title) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            "INSERT INTO games (title, source, file_size, magnet_link, link_type, genres, company, original_size, thumbnail_url, screenshots, source_url, post_date, search_title) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
         )
             .bind(&g.title)
             .bind(&g.source)
             .bind(&g.file_size)
             .bind(&g.magnet_link)
+            .bind(g.link_type)
             .bind(&g.genres)
             .bind(&g.company)
             .bind(&g.original_size)
@@ -902,6 +864,7 @@ pub struct InstallationLog {
 pub struct CommunityRating {
     pub id: i64,
     pub game_id: i64,
+    pub user_id: Option<i64>,
     pub install_difficulty: Option<i64>,
     pub install_success: Option<bool>,
     pub issues_encountered: Option<String>,
@@ -1054,19 +1017,27 @@ pub async fn get_all_installation_logs(pool: &SqlitePool) -> Result<Vec<Installa
 
 // ─── Community Ratings ───
 
-/// Insert a community rating
-pub async fn insert_community_rating(
+/// Save a user's rating of a game, replacing any rating they gave it before
+pub async fn upsert_community_rating(
     pool: &SqlitePool,
+    user_id: i64,
     game_id: i64,
     install_difficulty: Option<i64>,
     install_success: Option<bool>,
     issues_encountered: Option<String>,
-) -> Result<i64, sqlx::Error> {
+) -> Result<(), sqlx::Error> {
     let created_at = chrono::Utc::now().to_rfc3339();
 
-    let result = sqlx::query(
-        "INSERT INTO community_ratings (game_id, install_difficulty, install_success, issues_encountered, created_at) VALUES (?, ?, ?, ?, ?)"
+    sqlx::query(
+        "INSERT INTO community_ratings (user_id, game_id, install_difficulty, install_success, issues_encountered, created_at)
+         VALUES (?, ?, ?, ?, ?, ?)
+         ON CONFLICT(user_id, game_id) DO UPDATE SET
+            install_difficulty = excluded.install_difficulty,
+            install_success = excluded.install_success,
+            issues_encountered = excluded.issues_encountered,
+            created_at = excluded.created_at"
     )
+    .bind(user_id)
     .bind(game_id)
     .bind(install_difficulty)
     .bind(install_success)
@@ -1075,7 +1046,7 @@ pub async fn insert_community_rating(
     .execute(pool)
     .await?;
 
-    Ok(result.last_insert_rowid())
+    Ok(())
 }
 
 /// Get community ratings for a game
@@ -1089,19 +1060,33 @@ pub async fn get_community_ratings_for_game(pool: &SqlitePool, game_id: i64) ->
 }
 
 /// Get average rating stats for a game
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct GameRatingStats {
     pub total_ratings: i64,
+    /// 1 (easy) to 5 (hard)
     pub avg_difficulty: Option<f64>,
+    /// Fraction of ratings that reported a successful install, 0.0 to 1.0
     pub success_rate: Option<f64>,
 }
 
+impl GameRatingStats {
+    /// Percentage of users reporting install problems, when there are
+    /// enough ratings to trust it and it's high enough to warn about
+    pub fn issue_percent(&self) -> Option<f64> {
+        if self.total_ratings < 3 {
+            return None;
+        }
+        let failure_rate = 1.0 - self.success_rate?;
+        (failure_rate >= 0.3).then_some(failure_rate * 100.0)
+    }
+}
+
 pub async fn get_game_rating_stats(pool: &SqlitePool, game_id: i64) -> Result<GameRatingStats, sqlx::Error> {
     let row: Option<(i64, Option<f64>, Option<f64>)> = sqlx::query_as(
         "SELECT
             COUNT(*) as total,
             AVG(install_difficulty) as avg_diff,
-            AVG(CASE WHEN install_success THEN 1.0 ELSE 0.0 END) as success_rate
+            AVG(CASE WHEN install_success IS NULL THEN NULL WHEN install_success THEN 1.0 ELSE 0.0 END) as success_rate
          FROM community_ratings
          WHERE game_id = ?"
     )
@@ -1114,7 +1099,7 @@ pub async fn get_game_rating_stats(pool: &SqlitePool, game_id: i64) -> Result<Ga
     Ok(GameRatingStats {
         total_ratings: total,
         avg_difficulty: avg_diff,
-        success_rate: success_rate,
+        success_rate,
     })
 }
 
@@ -1193,6 +1178,21 @@ pub struct Client {
     pub missing_dlls: Option<String>,
     pub last_seen: String,
     pub registered_at: String,
+    /// Only handed to the user the client is linked to
+    #[serde(skip_serializing)]
+    pub bridge_token: Option<String>,
+    /// "online", or "offline" once the heartbeat check has seen it miss the window
+    pub status: String,
+}
+
+/// Whether `client` checked in within the last `window_secs` seconds
+pub fn client_is_online(client: &Client, window_secs: i64) -> bool {
+    chrono::DateTime::parse_from_rfc3339(&client.last_seen)
+        .map(|last_seen| {
+            let elapsed = chrono::Utc::now().signed_duration_since(last_seen.with_timezone(&chrono::Utc));
+            elapsed.num_seconds() < window_secs
+        })
+        .unwrap_or(false)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -1210,32 +1210,43 @@ pub struct ClientProgress {
     pub updated_at: String,
 }
 
-/// Register or update a client
+/// Register or update a client. Once a client has a bridge token, only a
+/// caller presenting it (or, to rotate it, presenting it as `previous_token`)
+/// can update the row; returns false when the token didn't match.
 pub async fn register_client(
     pool: &SqlitePool,
     client_id: &str,
     client_name: &str,
     os_version: &str,
-) -> Result<i64, sqlx::Error> {
+    bridge_token: Option<&str>,
+    previous_token: Option<&str>,
+) -> Result<bool, sqlx::Error> {
     let now = chrono::Utc::now().to_rfc3339();
 
     let result = sqlx::query(
-        "INSERT INTO clients (client_id, client_name, os_version, last_seen, registered_at)
-         VALUES (?, ?, ?, ?, ?)
+        "INSERT INTO clients (client_id, client_name, os_version, bridge_token, last_seen, registered_at)
+         VALUES (?, ?, ?, ?, ?, ?)
          ON CONFLICT(client_id) DO UPDATE SET
             client_name = excluded.client_name,
             os_version = excluded.os_version,
-            last_seen = excluded.last_seen"
+            bridge_token = COALESCE(excluded.bridge_token, clients.bridge_token),
+            last_seen = excluded.last_seen,
+            status = 'online'
+         WHERE clients.bridge_token IS NULL
+            OR clients.bridge_token = excluded.bridge_token
+            OR clients.bridge_token = ?"
     )
     .bind(client_id)
     .bind(client_name)
     .bind(os_version)
+    .bind(bridge_token)
     .bind(&now)
     .bind(&now)
+    .bind(previous_token)
     .execute(pool)
     .await?;
 
-    Ok(result.last_insert_rowid())
+    Ok(result.rows_affected() > 0)
 }
 
 /// Update client system info
@@ -1257,7 +1268,8 @@ pub async fn update_client_system_info(
             disk_space_gb = ?,
             cpu_cores = ?,
             missing_dlls = ?,
-            last_seen = ?
+            last_seen = ?,
+            status = 'online'
          WHERE client_id = ?"
     )
     .bind(ram_total_gb)
@@ -1273,6 +1285,78 @@ pub async fn update_client_system_info(
     Ok(())
 }
 
+/// Record a heartbeat from a client; false if no such client is registered
+pub async fn touch_client(pool: &SqlitePool, client_id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE clients SET last_seen = ?, status = 'online' WHERE client_id = ?")
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(client_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Mark online clients that haven't checked in for `window_secs` as offline,
+/// returning the ones that just went offline
+pub async fn mark_stale_clients_offline(pool: &SqlitePool, window_secs: i64) -> Result<Vec<Client>, sqlx::Error> {
+    let cutoff = (chrono::Utc::now() - chrono::Duration::seconds(window_secs)).to_rfc3339();
+    sqlx::query_as::<_, Client>(
+        "UPDATE clients SET status = 'offline' WHERE status = 'online' AND last_seen < ? RETURNING *"
+    )
+    .bind(&cutoff)
+    .fetch_all(pool)
+    .await
+}
+
+/// A linked client whose status isn't the one its owner last heard about
+#[derive(Debug, Clone, FromRow)]
+pub struct ClientStatusChange {
+    pub client_id: String,
+    pub client_name: String,
+    pub user_id: i64,
+    pub status: String,
+}
+
+/// Linked clients whose status changed since their owner was last notified.
+/// Ones notified less than `cooldown_secs` ago are left until later, so a
+/// flapping connection settles before anything more is sent.
+pub async fn get_client_status_changes(pool: &SqlitePool, cooldown_secs: i64) -> Result<Vec<ClientStatusChange>, sqlx::Error> {
+    let cutoff = (chrono::Utc::now() - chrono::Duration::seconds(cooldown_secs)).to_rfc3339();
+    // Clients start out online, so one nobody was told about yet counts as online
+    sqlx::query_as::<_, ClientStatusChange>(
+        "SELECT client_id, client_name, user_id, status FROM clients
+         WHERE user_id IS NOT NULL
+           AND status != COALESCE(notified_status, 'online')
+           AND (notified_at IS NULL OR notified_at < ?)"
+    )
+    .bind(&cutoff)
+    .fetch_all(pool)
+    .await
+}
+
+/// Record that the client's owner has been told it's `status`
+pub async fn set_client_notified_status(pool: &SqlitePool, client_id: &str, status: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE clients SET notified_status = ?, notified_at = ? WHERE client_id = ?")
+        .bind(status)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(client_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Downloads waiting for a client to pick them up
+pub async fn count_pending_client_downloads(pool: &SqlitePool, user_id: i64, client_id: &str) -> Result<i64, sqlx::Error> {
+    let (count,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM downloads
+         WHERE user_id = ? AND (client_id IS NULL OR client_id = ?) AND status = 'pending'"
+    )
+    .bind(user_id)
+    .bind(client_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(count)
+}
+
 /// Get a client by client_id
 pub async fn get_client(pool: &SqlitePool, client_id: &str) -> Result<Option<Client>, sqlx::Error> {
     sqlx::query_as::<_, Client>(
@@ -1363,6 +1447,11 @@ pub struct User {
     pub is_admin: bool,
     pub created_at: String,
     pub last_login: Option<String>,
+    /// The `last_login` before the current one, for "new since last login"
+    pub previous_login: Option<String>,
+    pub email: Option<String>,
+    /// Whether `email` was confirmed through a verification link
+    pub email_verified: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -1381,6 +1470,8 @@ pub struct UserInfo {
     pub is_admin: bool,
     pub created_at: String,
     pub last_login: Option<String>,
+    pub email: Option<String>,
+    pub email_verified: bool,
 }
 
 impl From<User> for UserInfo {
@@ -1391,6 +1482,8 @@ impl From<User> for UserInfo {
             is_admin: user.is_admin,
             created_at: user.created_at,
             last_login: user.last_login,
+            email: user.email,
+            email_verified: user.email_verified,
         }
     }
 }
@@ -1446,39 +1539,45 @@ pub async fn verify_user(
     if let Some(user) = user {
         use bcrypt::verify;
         if verify(password, &user.password_hash).unwrap_or(false) {
-            // Update last login
+            // Update last login, keeping the one it replaces
             let now = chrono::Utc::now().to_rfc3339();
-            let _ = sqlx::query("UPDATE users SET last_login = ? WHERE id = ?")
+            let _ = sqlx::query("UPDATE users SET previous_login = last_login, last_login = ? WHERE id = ?")
                 .bind(&now)
                 .bind(user.id)
                 .execute(pool)
                 .await;
 
-            return Ok(Some(user));
+            return Ok(Some(User {
+                previous_login: user.last_login.clone(),
+                last_login: Some(now),
+                ..user
+            }));
         }
     }
 
     Ok(None)
 }
 
-/// Create a new session for a user
+/// Create a new session for a user, valid for `expiry_days` unless refreshed
 pub async fn create_session(
     pool: &SqlitePool,
     user_id: i64,
+    expiry_days: i64,
 ) -> Result<String, sqlx::Error> {
     use uuid::Uuid;
 
     let session_token = Uuid::new_v4().to_string();
     let now = chrono::Utc::now();
-    let expires_at = (now + chrono::Duration::days(30)).to_rfc3339();
+    let expires_at = (now + chrono::Duration::days(expiry_days)).to_rfc3339();
 
     sqlx::query(
-        "INSERT INTO sessions (session_token, user_id, created_at, expires_at) VALUES (?, ?, ?, ?)"
+        "INSERT INTO sessions (session_token, user_id, created_at, expires_at, last_seen_at) VALUES (?, ?, ?, ?, ?)"
     )
     .bind(&session_token)
     .bind(user_id)
     .bind(&now.to_rfc3339())
     .bind(&expires_at)
+    .bind(now.to_rfc3339())
     .execute(pool)
     .await?;
 
@@ -1505,6 +1604,125 @@ pub async fn get_user_by_session(
     Ok(user)
 }
 
+/// Change a user's email address (None removes it). The new address is
+/// unverified and earlier verification links stop working.
+pub async fn set_user_email(pool: &SqlitePool, user_id: i64, email: Option<&str>) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("UPDATE users SET email = ?, email_verified = 0 WHERE id = ?")
+        .bind(email)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM email_verifications WHERE user_id = ?")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await
+}
+
+/// Create a token that verifies `email` for the user when its link is opened
+pub async fn create_email_verification(
+    pool: &SqlitePool,
+    user_id: i64,
+    email: &str,
+    ttl_hours: i64,
+) -> Result<String, sqlx::Error> {
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    let expires_at = (chrono::Utc::now() + chrono::Duration::hours(ttl_hours)).to_rfc3339();
+
+    sqlx::query("INSERT INTO email_verifications (token, user_id, email, expires_at) VALUES (?, ?, ?, ?)")
+        .bind(&token)
+        .bind(user_id)
+        .bind(email)
+        .bind(&expires_at)
+        .execute(pool)
+        .await?;
+
+    Ok(token)
+}
+
+/// Mark the address a verification token was issued for as verified, if the
+/// token hasn't expired and the user still has that address. Returns whether
+/// it was.
+pub async fn verify_email(pool: &SqlitePool, token: &str) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let pending: Option<(i64, String)> = sqlx::query_as(
+        "SELECT user_id, email FROM email_verifications WHERE token = ? AND expires_at > ?"
+    )
+    .bind(token)
+    .bind(chrono::Utc::now().to_rfc3339())
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some((user_id, email)) = pending else {
+        return Ok(false);
+    };
+
+    let updated = sqlx::query("UPDATE users SET email_verified = 1 WHERE id = ? AND email = ?")
+        .bind(user_id)
+        .bind(&email)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    sqlx::query("DELETE FROM email_verifications WHERE user_id = ?")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(updated > 0)
+}
+
+/// Drop verification links that expired without being used
+pub async fn delete_expired_email_verifications(pool: &SqlitePool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM email_verifications WHERE expires_at <= ?")
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+/// Where to email a user's notifications: their verified address, if they
+/// opted in
+pub async fn get_notification_email(pool: &SqlitePool, user_id: i64) -> Result<Option<String>, sqlx::Error> {
+    let email: Option<(String,)> = sqlx::query_as(
+        "SELECT u.email FROM users u
+         JOIN user_settings s ON s.user_id = u.id
+         WHERE u.id = ? AND u.email IS NOT NULL AND u.email_verified = 1 AND s.notify_email = 1"
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(email.map(|(email,)| email))
+}
+
+/// Push a live session's expiry to `expiry_days` from now, but never past
+/// `lifetime_days` after it was created
+pub async fn refresh_session(
+    pool: &SqlitePool,
+    session_token: &str,
+    expiry_days: i64,
+    lifetime_days: i64,
+) -> Result<(), sqlx::Error> {
+    let now = chrono::Utc::now();
+
+    sqlx::query(
+        "UPDATE sessions
+         SET last_seen_at = ?,
+             expires_at = MIN(?, strftime('%Y-%m-%dT%H:%M:%S+00:00', created_at, ?))
+         WHERE session_token = ? AND expires_at > ?"
+    )
+    .bind(now.to_rfc3339())
+    .bind((now + chrono::Duration::days(expiry_days)).to_rfc3339())
+    .bind(format!("+{} days", lifetime_days))
+    .bind(session_token)
+    .bind(now.to_rfc3339())
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
 /// Delete a session (logout)
 pub async fn delete_session(
     pool: &SqlitePool,
@@ -1522,104 +1740,522 @@ pub async fn delete_session(
 pub async fn cleanup_expired_sessions(pool: &SqlitePool) -> Result<(), sqlx::Error> {
     let now = chrono::Utc::now().to_rfc3339();
 
-    sqlx::query("DELETE FROM sessions WHERE expires_at < ?")
-        .bind(&now)
+    sqlx::query("DELETE FROM sessions WHERE expires_at < ?")
+        .bind(&now)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Get all users (admin only)
+pub async fn get_all_users(pool: &SqlitePool) -> Result<Vec<UserInfo>, sqlx::Error> {
+    let users: Vec<User> = sqlx::query_as(
+        "SELECT * FROM users ORDER BY created_at DESC"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(users.into_iter().map(UserInfo::from).collect())
+}
+
+/// Check if user is admin
+pub async fn is_admin(pool: &SqlitePool, user_id: i64) -> Result<bool, sqlx::Error> {
+    let (is_admin,): (bool,) = sqlx::query_as(
+        "SELECT is_admin FROM users WHERE id = ?"
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(is_admin)
+}
+
+// ─── User-Specific Favorites ───
+
+/// Add favorite for a user
+pub async fn add_user_favorite(
+    pool: &SqlitePool,
+    user_id: i64,
+    game_id: i64,
+) -> Result<(), sqlx::Error> {
+    let now = chrono::Utc::now().to_rfc3339();
+
+    sqlx::query(
+        "INSERT OR IGNORE INTO user_favorites (user_id, game_id, created_at) VALUES (?, ?, ?)"
+    )
+    .bind(user_id)
+    .bind(game_id)
+    .bind(&now)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Remove favorite for a user
+pub async fn remove_user_favorite(
+    pool: &SqlitePool,
+    user_id: i64,
+    game_id: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM user_favorites WHERE user_id = ? AND game_id = ?")
+        .bind(user_id)
+        .bind(game_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// A user's favorite games, most recently favorited first, in one query
+pub async fn get_user_favorite_games(
+    pool: &SqlitePool,
+    user_id: i64,
+) -> Result<Vec<Game>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT g.id, g.title, g.source, g.file_size, g.magnet_link, g.link_type, g.genres, g.company, g.original_size, g.thumbnail_url, g.screenshots, g.source_url, g.post_date, g.search_title, g.view_count
+         FROM user_favorites f
+         JOIN games g ON g.id = f.game_id
+         WHERE f.user_id = ?
+         ORDER BY f.created_at DESC"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Check if a game is favorited by user
+pub async fn is_favorite(
+    pool: &SqlitePool,
+    user_id: i64,
+    game_id: i64,
+) -> Result<bool, sqlx::Error> {
+    let count: (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM user_favorites WHERE user_id = ? AND game_id = ?"
+    )
+    .bind(user_id)
+    .bind(game_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count.0 > 0)
+}
+
+// ─── User-Specific Wishlist ───
+
+/// Put a game on a user's wishlist
+pub async fn add_user_wishlist(
+    pool: &SqlitePool,
+    user_id: i64,
+    game_id: i64,
+) -> Result<(), sqlx::Error> {
+    let now = chrono::Utc::now().to_rfc3339();
+
+    sqlx::query(
+        "INSERT OR IGNORE INTO user_wishlist (user_id, game_id, created_at) VALUES (?, ?, ?)"
+    )
+    .bind(user_id)
+    .bind(game_id)
+    .bind(&now)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Take a game off a user's wishlist
+pub async fn remove_user_wishlist(
+    pool: &SqlitePool,
+    user_id: i64,
+    game_id: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM user_wishlist WHERE user_id = ? AND game_id = ?")
+        .bind(user_id)
+        .bind(game_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// A user's wishlisted games, most recently added first
+pub async fn get_user_wishlist_games(
+    pool: &SqlitePool,
+    user_id: i64,
+) -> Result<Vec<Game>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT g.id, g.title, g.source, g.file_size, g.magnet_link, g.link_type, g.genres, g.company, g.original_size, g.thumbnail_url, g.screenshots, g.source_url, g.post_date, g.search_title, g.view_count
+         FROM user_wishlist w
+         JOIN games g ON g.id = w.game_id
+         WHERE w.user_id = ?
+         ORDER BY w.created_at DESC"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+// ─── Saved Searches ───
+
+/// A named set of `GameQuery` filters, stored as JSON
+#[derive(Debug, FromRow)]
+pub struct SavedSearch {
+    pub id: i64,
+    pub name: String,
+    pub params: String,
+    pub created_at: String,
+}
+
+/// Save a search under `name`, replacing the user's search of the same name
+pub async fn save_search(
+    pool: &SqlitePool,
+    user_id: i64,
+    name: &str,
+    params: &str,
+) -> Result<SavedSearch, sqlx::Error> {
+    let now = chrono::Utc::now().to_rfc3339();
+
+    sqlx::query_as(
+        "INSERT INTO saved_searches (user_id, name, params, created_at) VALUES (?, ?, ?, ?)
+         ON CONFLICT(user_id, name) DO UPDATE SET params = excluded.params, created_at = excluded.created_at
+         RETURNING id, name, params, created_at"
+    )
+    .bind(user_id)
+    .bind(name)
+    .bind(params)
+    .bind(&now)
+    .fetch_one(pool)
+    .await
+}
+
+/// A user's saved searches, alphabetically
+pub async fn get_saved_searches(
+    pool: &SqlitePool,
+    user_id: i64,
+) -> Result<Vec<SavedSearch>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT id, name, params, created_at FROM saved_searches WHERE user_id = ? ORDER BY name COLLATE NOCASE"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// One of a user's saved searches
+pub async fn get_saved_search(
+    pool: &SqlitePool,
+    user_id: i64,
+    id: i64,
+) -> Result<Option<SavedSearch>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT id, name, params, created_at FROM saved_searches WHERE id = ? AND user_id = ?"
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Delete one of a user's saved searches. Returns false if it didn't exist.
+pub async fn delete_saved_search(
+    pool: &SqlitePool,
+    user_id: i64,
+    id: i64,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM saved_searches WHERE id = ? AND user_id = ?")
+        .bind(id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// ─── Hidden Games ───
+
+/// Hide a game from a user's game list
+pub async fn hide_game_for_user(
+    pool: &SqlitePool,
+    user_id: i64,
+    game_id: i64,
+) -> Result<(), sqlx::Error> {
+    let now = chrono::Utc::now().to_rfc3339();
+
+    sqlx::query(
+        "INSERT OR IGNORE INTO user_hidden_games (user_id, game_id, created_at) VALUES (?, ?, ?)"
+    )
+    .bind(user_id)
+    .bind(game_id)
+    .bind(&now)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Show a previously hidden game again
+pub async fn unhide_game_for_user(
+    pool: &SqlitePool,
+    user_id: i64,
+    game_id: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM user_hidden_games WHERE user_id = ? AND game_id = ?")
+        .bind(user_id)
+        .bind(game_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// A user's hidden games, most recently hidden first
+pub async fn get_user_hidden_games(
+    pool: &SqlitePool,
+    user_id: i64,
+) -> Result<Vec<Game>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT g.id, g.title, g.source, g.file_size, g.magnet_link, g.link_type, g.genres, g.company, g.original_size, g.thumbnail_url, g.screenshots, g.source_url, g.post_date, g.search_title, g.view_count
+         FROM user_hidden_games h
+         JOIN games g ON g.id = h.game_id
+         WHERE h.user_id = ?
+         ORDER BY h.created_at DESC"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// A globally blacklisted source/title pair
+#[derive(Debug, Serialize, FromRow, utoipa::ToSchema)]
+pub struct BlacklistEntry {
+    pub id: i64,
+    pub source: String,
+    pub title: String,
+    pub reason: Option<String>,
+    pub created_at: String,
+}
+
+/// Blacklist a game for everyone. Re-blacklisting updates the reason.
+pub async fn blacklist_game(
+    pool: &SqlitePool,
+    source: &str,
+    title: &str,
+    reason: Option<&str>,
+) -> Result<BlacklistEntry, sqlx::Error> {
+    let now = chrono::Utc::now().to_rfc3339();
+
+    sqlx::query_as(
+        "INSERT INTO game_blacklist (source, title, reason, created_at) VALUES (?, ?, ?, ?)
+         ON CONFLICT(source, title) DO UPDATE SET reason = excluded.reason
+         RETURNING id, source, title, reason, created_at"
+    )
+    .bind(source)
+    .bind(title)
+    .bind(reason)
+    .bind(&now)
+    .fetch_one(pool)
+    .await
+}
+
+/// Lift a blacklist entry. Returns false if it didn't exist.
+pub async fn remove_blacklist_entry(pool: &SqlitePool, id: i64) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM game_blacklist WHERE id = ?")
+        .bind(id)
         .execute(pool)
         .await?;
 
-    Ok(())
+    Ok(result.rows_affected() > 0)
 }
 
-/// Get all users (admin only)
-pub async fn get_all_users(pool: &SqlitePool) -> Result<Vec<UserInfo>, sqlx::Error> {
-    let users: Vec<User> = sqlx::query_as(
-        "SELECT * FROM users ORDER BY created_at DESC"
+/// Every blacklist entry, newest first
+pub async fn get_blacklist(pool: &SqlitePool) -> Result<Vec<BlacklistEntry>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT id, source, title, reason, created_at FROM game_blacklist ORDER BY created_at DESC"
     )
     .fetch_all(pool)
-    .await?;
+    .await
+}
 
-    Ok(users.into_iter().map(UserInfo::from).collect())
+// ─── Download Files ───
+
+/// Fetch the files of several downloads in one query, grouped by download ID
+pub async fn get_download_files_map(
+    pool: &SqlitePool,
+    download_ids: &[i64],
+) -> Result<std::collections::HashMap<i64, Vec<DownloadFileRow>>, sqlx::Error> {
+    let mut map: std::collections::HashMap<i64, Vec<DownloadFileRow>> = std::collections::HashMap::new();
+    if download_ids.is_empty() {
+        return Ok(map);
+    }
+
+    let placeholders = download_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+    let sql = format!(
+        "SELECT download_id, id, filename, file_size, file_path, is_extracted FROM download_files WHERE download_id IN ({}) ORDER BY id",
+        placeholders
+    );
+
+    let mut query = sqlx::query_as::<_, (i64, i64, String, Option<i64>, Option<String>, bool)>(&sql);
+    for id in download_ids {
+        query = query.bind(id);
+    }
+
+    for (download_id, id, filename, file_size, file_path, is_extracted) in query.fetch_all(pool).await? {
+        map.entry(download_id).or_default().push(DownloadFileRow {
+            id,
+            filename,
+            file_size,
+            file_path,
+            is_extracted,
+        });
+    }
+
+    Ok(map)
 }
 
-/// Check if user is admin
-pub async fn is_admin(pool: &SqlitePool, user_id: i64) -> Result<bool, sqlx::Error> {
-    let (is_admin,): (bool,) = sqlx::query_as(
-        "SELECT is_admin FROM users WHERE id = ?"
+// ─── Archive Passwords ───
+
+/// Password stored for a game's encrypted archives
+pub async fn get_archive_password(pool: &SqlitePool, game_id: i64) -> Result<Option<String>, sqlx::Error> {
+    let row: Option<(String,)> = sqlx::query_as(
+        "SELECT password FROM archive_passwords WHERE game_id = ?"
     )
-    .bind(user_id)
-    .fetch_one(pool)
+    .bind(game_id)
+    .fetch_optional(pool)
     .await?;
 
-    Ok(is_admin)
+    Ok(row.map(|(p,)| p))
 }
 
-// ─── User-Specific Favorites ───
-
-/// Add favorite for a user
-pub async fn add_user_favorite(
-    pool: &SqlitePool,
-    user_id: i64,
-    game_id: i64,
-) -> Result<(), sqlx::Error> {
-    let now = chrono::Utc::now().to_rfc3339();
-
+/// Store (or replace) the archive password for a game
+pub async fn set_archive_password(pool: &SqlitePool, game_id: i64, password: &str) -> Result<(), sqlx::Error> {
     sqlx::query(
-        "INSERT OR IGNORE INTO user_favorites (user_id, game_id, created_at) VALUES (?, ?, ?)"
+        "INSERT INTO archive_passwords (game_id, password, updated_at) VALUES (?, ?, ?)
+         ON CONFLICT(game_id) DO UPDATE SET password = excluded.password, updated_at = excluded.updated_at"
     )
-    .bind(user_id)
     .bind(game_id)
-    .bind(&now)
+    .bind(password)
+    .bind(chrono::Utc::now().to_rfc3339())
     .execute(pool)
     .await?;
-
     Ok(())
 }
 
-/// Remove favorite for a user
-pub async fn remove_user_favorite(
+// ─── Debrid Link Cache ───
+
+/// Cached links for a magnet, if they were stored less than `max_age_secs` ago
+pub async fn get_cached_links(
     pool: &SqlitePool,
-    user_id: i64,
-    game_id: i64,
+    magnet_hash: &str,
+    provider: &str,
+    skip_junk: bool,
+    max_age_secs: i64,
+) -> Result<Option<String>, sqlx::Error> {
+    let row: Option<(String, String)> = sqlx::query_as(
+        "SELECT links_json, cached_at FROM rd_link_cache WHERE magnet_hash = ? AND provider = ? AND skip_junk = ?"
+    )
+    .bind(magnet_hash)
+    .bind(provider)
+    .bind(skip_junk)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.and_then(|(links_json, cached_at)| {
+        let cached_at = chrono::DateTime::parse_from_rfc3339(&cached_at).ok()?;
+        let age = chrono::Utc::now().signed_duration_since(cached_at);
+        (age.num_seconds() < max_age_secs).then_some(links_json)
+    }))
+}
+
+/// Store (or refresh) the links for a magnet
+pub async fn cache_links(
+    pool: &SqlitePool,
+    magnet_hash: &str,
+    provider: &str,
+    skip_junk: bool,
+    links_json: &str,
 ) -> Result<(), sqlx::Error> {
-    sqlx::query("DELETE FROM user_favorites WHERE user_id = ? AND game_id = ?")
-        .bind(user_id)
-        .bind(game_id)
+    sqlx::query(
+        "INSERT INTO rd_link_cache (magnet_hash, provider, skip_junk, links_json, cached_at) VALUES (?, ?, ?, ?, ?)
+         ON CONFLICT(magnet_hash, provider, skip_junk) DO UPDATE SET links_json = excluded.links_json, cached_at = excluded.cached_at"
+    )
+    .bind(magnet_hash)
+    .bind(provider)
+    .bind(skip_junk)
+    .bind(links_json)
+    .bind(chrono::Utc::now().to_rfc3339())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Drop every cached link set for a magnet, whichever provider resolved it
+pub async fn delete_cached_links(pool: &SqlitePool, magnet_hash: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM rd_link_cache WHERE magnet_hash = ?")
+        .bind(magnet_hash)
         .execute(pool)
         .await?;
-
     Ok(())
 }
 
-/// Get all favorites for a user
-pub async fn get_user_favorites(
+// ─── Client Commands ───
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ClientCommand {
+    pub id: i64,
+    pub download_id: i64,
+    pub command: String,
+    pub created_at: String,
+}
+
+/// Queue a command for the clients linked to `user_id`
+pub async fn queue_client_command(
     pool: &SqlitePool,
     user_id: i64,
-) -> Result<Vec<i64>, sqlx::Error> {
-    let favorites: Vec<(i64,)> = sqlx::query_as(
-        "SELECT game_id FROM user_favorites WHERE user_id = ? ORDER BY created_at DESC"
+    download_id: i64,
+    command: &str,
+) -> Result<i64, sqlx::Error> {
+    let result = sqlx::query(
+        "INSERT INTO client_commands (user_id, download_id, command, created_at) VALUES (?, ?, ?, ?)"
     )
     .bind(user_id)
-    .fetch_all(pool)
+    .bind(download_id)
+    .bind(command)
+    .bind(chrono::Utc::now().to_rfc3339())
+    .execute(pool)
     .await?;
 
-    Ok(favorites.into_iter().map(|(id,)| id).collect())
+    Ok(result.last_insert_rowid())
 }
 
-/// Check if a game is favorited by user
-pub async fn is_favorite(
+/// Unacknowledged commands for a user's clients, newer than `max_age_secs`
+pub async fn get_pending_client_commands(
     pool: &SqlitePool,
     user_id: i64,
-    game_id: i64,
-) -> Result<bool, sqlx::Error> {
-    let count: (i64,) = sqlx::query_as(
-        "SELECT COUNT(*) FROM user_favorites WHERE user_id = ? AND game_id = ?"
+    max_age_secs: i64,
+) -> Result<Vec<ClientCommand>, sqlx::Error> {
+    let cutoff = (chrono::Utc::now() - chrono::Duration::seconds(max_age_secs)).to_rfc3339();
+
+    sqlx::query_as::<_, ClientCommand>(
+        "SELECT id, download_id, command, created_at FROM client_commands
+         WHERE user_id = ? AND acknowledged_at IS NULL AND created_at > ?
+         ORDER BY id ASC"
     )
     .bind(user_id)
-    .bind(game_id)
-    .fetch_one(pool)
-    .await?;
+    .bind(cutoff)
+    .fetch_all(pool)
+    .await
+}
 
-    Ok(count.0 > 0)
+/// Mark a command as handled by a client
+pub async fn ack_client_command(pool: &SqlitePool, user_id: i64, command_id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE client_commands SET acknowledged_at = ? WHERE id = ? AND user_id = ?")
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(command_id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(())
 }
 
 // ─── User-Specific Downloads ───
@@ -1659,6 +2295,231 @@ pub async fn get_user_download_ids(
     Ok(downloads.into_iter().map(|(id,)| id).collect())
 }
 
+/// Downloads queued or in progress across all users and clients
+pub async fn count_active_downloads(pool: &SqlitePool) -> Result<i64, sqlx::Error> {
+    let (count,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM downloads
+         WHERE status IN ('queued', 'pending', 'downloading', 'extracting', 'installing')"
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(count)
+}
+
+/// Downloads a user has queued or in progress, server-side or on their clients
+pub async fn count_active_user_downloads(pool: &SqlitePool, user_id: i64) -> Result<i64, sqlx::Error> {
+    let (count,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM downloads
+         WHERE status IN ('queued', 'pending', 'downloading', 'extracting', 'installing')
+           AND (user_id = ? OR id IN (SELECT download_id FROM user_downloads WHERE user_id = ?))"
+    )
+    .bind(user_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(count)
+}
+
+/// Bytes a user has queued on `day` (YYYY-MM-DD)
+pub async fn get_download_usage(pool: &SqlitePool, user_id: i64, day: &str) -> Result<i64, sqlx::Error> {
+    let row: Option<(i64,)> = sqlx::query_as("SELECT bytes FROM download_usage WHERE user_id = ? AND day = ?")
+        .bind(user_id)
+        .bind(day)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.map(|(bytes,)| bytes).unwrap_or(0))
+}
+
+/// Add to a user's queued bytes for `day`
+pub async fn add_download_usage(pool: &SqlitePool, user_id: i64, day: &str, bytes: i64) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO download_usage (user_id, day, bytes) VALUES (?, ?, ?)
+         ON CONFLICT(user_id, day) DO UPDATE SET bytes = bytes + excluded.bytes"
+    )
+    .bind(user_id)
+    .bind(day)
+    .bind(bytes)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Drop usage counters from before `day`, which resets the daily caps
+pub async fn prune_download_usage(pool: &SqlitePool, day: &str) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM download_usage WHERE day < ?")
+        .bind(day)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+/// Statuses a download can finish in; anything else is still in the live queue
+pub const FINISHED_DOWNLOAD_STATUSES: &[&str] = &["completed", "installed", "failed"];
+
+/// A finished download, for the download history
+#[derive(Debug, Serialize, FromRow, utoipa::ToSchema)]
+pub struct DownloadHistoryEntry {
+    pub id: i64,
+    pub game_id: i64,
+    pub game_title: String,
+    /// "completed", "installed" or "failed"
+    pub status: String,
+    pub created_at: String,
+    /// When it finished downloading; unset for failures
+    pub completed_at: Option<String>,
+    /// Combined size of the downloaded files, when known
+    pub total_bytes: Option<i64>,
+    pub error_message: Option<String>,
+}
+
+/// A download that just finished, as posted to the download webhook
+#[derive(Debug, Serialize, FromRow)]
+pub struct CompletedDownload {
+    pub download_id: i64,
+    pub game_id: i64,
+    pub title: String,
+    /// Listed size of the game, e.g. "35.2 GB"
+    pub size: String,
+    /// Combined size of the downloaded files, for server-side downloads
+    pub total_bytes: Option<i64>,
+    /// Who queued it: the client download's owner, or the user a server
+    /// download was queued for
+    pub user: Option<String>,
+    /// The client agent it was downloaded on, if any
+    pub client_id: Option<String>,
+    pub completed_at: Option<String>,
+}
+
+pub async fn get_completed_download(pool: &SqlitePool, download_id: i64) -> Result<Option<CompletedDownload>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT d.id AS download_id, d.game_id, COALESCE(g.title, 'Unknown game') AS title,
+                COALESCE(g.file_size, '') AS size,
+                (SELECT SUM(f.file_size) FROM download_files f WHERE f.download_id = d.id) AS total_bytes,
+                u.username AS user, d.client_id, d.completed_at
+         FROM downloads d
+         LEFT JOIN games g ON g.id = d.game_id
+         LEFT JOIN users u ON u.id = COALESCE(
+             d.user_id,
+             (SELECT ud.user_id FROM user_downloads ud WHERE ud.download_id = d.id ORDER BY ud.created_at LIMIT 1)
+         )
+         WHERE d.id = ?"
+    )
+    .bind(download_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// One page of finished downloads, most recently finished first, with the
+/// total count. `user_id` limits it to that user's downloads; `status` to one
+/// of [`FINISHED_DOWNLOAD_STATUSES`].
+pub async fn get_download_history(
+    pool: &SqlitePool,
+    user_id: Option<i64>,
+    status: Option<&str>,
+    page: i64,
+    per_page: i64,
+) -> Result<(Vec<DownloadHistoryEntry>, i64), sqlx::Error> {
+    let statuses: Vec<&str> = match status {
+        Some(status) => vec![status],
+        None => FINISHED_DOWNLOAD_STATUSES.to_vec(),
+    };
+    let where_clause = format!(
+        "WHERE d.status IN ({}) AND (? IS NULL OR d.user_id = ? OR d.id IN (SELECT download_id FROM user_downloads WHERE user_id = ?))",
+        vec!["?"; statuses.len()].join(", ")
+    );
+
+    let count_sql = format!("SELECT COUNT(*) FROM downloads d {}", where_clause);
+    let mut count = sqlx::query_as::<_, (i64,)>(&count_sql);
+    for status in &statuses {
+        count = count.bind(*status);
+    }
+    let (total,) = count.bind(user_id).bind(user_id).bind(user_id).fetch_one(pool).await?;
+
+    let sql = format!(
+        "SELECT d.id, d.game_id, COALESCE(g.title, 'Unknown game') AS game_title, d.status,
+                d.created_at, d.completed_at, d.error_message,
+                (SELECT SUM(f.file_size) FROM download_files f WHERE f.download_id = d.id) AS total_bytes
+         FROM downloads d
+         LEFT JOIN games g ON g.id = d.game_id
+         {}
+         ORDER BY COALESCE(d.completed_at, d.created_at) DESC, d.id DESC
+         LIMIT ? OFFSET ?",
+        where_clause
+    );
+    let mut query = sqlx::query_as::<_, DownloadHistoryEntry>(&sql);
+    for status in &statuses {
+        query = query.bind(*status);
+    }
+    let entries = query
+        .bind(user_id)
+        .bind(user_id)
+        .bind(user_id)
+        .bind(per_page)
+        .bind((page - 1) * per_page)
+        .fetch_all(pool)
+        .await?;
+
+    Ok((entries, total))
+}
+
+/// A finished download that download cleanup may remove files for
+#[derive(Debug, FromRow)]
+pub struct CleanupCandidate {
+    pub id: i64,
+    pub game_title: String,
+    /// `completed_at`, or `created_at` when that's missing
+    pub finished_at: String,
+    pub file_path: Option<String>,
+    /// Whether it still has `download_files` rows
+    pub has_files: bool,
+}
+
+/// Completed and installed downloads that still have file records, or a
+/// folder when `with_paths` is set, oldest first
+pub async fn get_cleanup_candidates(
+    pool: &SqlitePool,
+    with_paths: bool,
+) -> Result<Vec<CleanupCandidate>, sqlx::Error> {
+    sqlx::query_as::<_, CleanupCandidate>(
+        "SELECT * FROM (
+             SELECT d.id, COALESCE(g.title, 'Unknown game') AS game_title,
+                    COALESCE(d.completed_at, d.created_at) AS finished_at, d.file_path,
+                    EXISTS (SELECT 1 FROM download_files f WHERE f.download_id = d.id) AS has_files
+             FROM downloads d
+             LEFT JOIN games g ON g.id = d.game_id
+             WHERE d.status IN ('completed', 'installed')
+         )
+         WHERE has_files OR (? AND file_path IS NOT NULL)
+         ORDER BY finished_at, id"
+    )
+    .bind(with_paths)
+    .fetch_all(pool)
+    .await
+}
+
+/// Every download's file path, to check whether a folder is shared before deleting it
+pub async fn get_download_paths(pool: &SqlitePool) -> Result<Vec<(i64, String)>, sqlx::Error> {
+    sqlx::query_as("SELECT id, file_path FROM downloads WHERE file_path IS NOT NULL")
+        .fetch_all(pool)
+        .await
+}
+
+/// Drop a download's file records, and its paths when its folder was deleted
+pub async fn clear_download_files(pool: &SqlitePool, download_id: i64, clear_paths: bool) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM download_files WHERE download_id = ?")
+        .bind(download_id)
+        .execute(&mut *tx)
+        .await?;
+    if clear_paths {
+        sqlx::query("UPDATE downloads SET file_path = NULL, installer_path = NULL WHERE id = ?")
+            .bind(download_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await
+}
+
 /// Get clients for a specific user
 pub async fn get_user_clients(
     pool: &SqlitePool,
@@ -1714,6 +2575,54 @@ pub async fn remove_game_tag(
     Ok(())
 }
 
+/// Whether any game carries `tag` (case-sensitive, like the tags themselves)
+pub async fn tag_exists(pool: &SqlitePool, tag: &str) -> Result<bool, sqlx::Error> {
+    let (exists,): (bool,) = sqlx::query_as("SELECT EXISTS (SELECT 1 FROM game_tags WHERE tag = ?)")
+        .bind(tag)
+        .fetch_one(pool)
+        .await?;
+    Ok(exists)
+}
+
+/// Replace every tag in `from` with `to` on all games, in one transaction.
+/// Games that already had `to` just lose the old tags. Returns how many games
+/// were relabeled.
+pub async fn relabel_tags(
+    pool: &SqlitePool,
+    from: &[String],
+    to: &str,
+) -> Result<u64, sqlx::Error> {
+    let placeholders = vec!["?"; from.len()].join(", ");
+    let mut tx = pool.begin().await?;
+
+    let count_sql = format!("SELECT COUNT(DISTINCT game_id) FROM game_tags WHERE tag IN ({})", placeholders);
+    let mut count = sqlx::query_as::<_, (i64,)>(&count_sql);
+    for tag in from {
+        count = count.bind(tag);
+    }
+    let (games,) = count.fetch_one(&mut *tx).await?;
+
+    let insert_sql = format!(
+        "INSERT OR IGNORE INTO game_tags (game_id, tag) SELECT DISTINCT game_id, ? FROM game_tags WHERE tag IN ({})",
+        placeholders
+    );
+    let mut insert = sqlx::query(&insert_sql).bind(to);
+    for tag in from {
+        insert = insert.bind(tag);
+    }
+    insert.execute(&mut *tx).await?;
+
+    let delete_sql = format!("DELETE FROM game_tags WHERE tag IN ({}) AND tag != ?", placeholders);
+    let mut delete = sqlx::query(&delete_sql);
+    for tag in from {
+        delete = delete.bind(tag);
+    }
+    delete.bind(to).execute(&mut *tx).await?;
+
+    tx.commit().await?;
+    Ok(games as u64)
+}
+
 /// Get tags for a specific game
 pub async fn get_game_tags(
     pool: &SqlitePool,
@@ -1741,7 +2650,33 @@ pub struct UserSettings {
     pub scraper_steamrip_enabled: Option<bool>,
     pub notify_download_complete: Option<bool>,
     pub notify_new_games: Option<bool>,
-    pub notify_errors: Option<bool>,
+    pub notify_download_failed: Option<bool>,
+    pub notify_install_failed: Option<bool>,
+    pub notify_client_offline: Option<bool>,
+    pub notify_client_online: Option<bool>,
+    pub notify_scrape_error: Option<bool>,
+    /// Telegram chat notifications are mirrored to; empty when unlinked
+    pub telegram_chat_id: Option<String>,
+    /// Also email notifications to the user's verified address
+    pub notify_email: Option<bool>,
+}
+
+impl UserSettings {
+    /// Whether the user wants notifications of `notification_type`. Low disk
+    /// space warnings go with failed downloads; unknown types are always sent.
+    pub fn wants(&self, notification_type: &str) -> bool {
+        let pref = match notification_type {
+            "new_games" => return self.notify_new_games.unwrap_or(false),
+            "client_online" => return self.notify_client_online.unwrap_or(false),
+            "download_complete" => self.notify_download_complete,
+            "download_error" | "disk_space_warning" => self.notify_download_failed,
+            "install_failed" => self.notify_install_failed,
+            "client_offline" => self.notify_client_offline,
+            "scrape_error" => self.notify_scrape_error,
+            _ => None,
+        };
+        pref.unwrap_or(true)
+    }
 }
 
 /// Get user settings
@@ -1794,7 +2729,13 @@ pub async fn update_user_settings(
             scraper_steamrip_enabled = COALESCE(?, scraper_steamrip_enabled),
             notify_download_complete = COALESCE(?, notify_download_complete),
             notify_new_games = COALESCE(?, notify_new_games),
-            notify_errors = COALESCE(?, notify_errors)
+            notify_download_failed = COALESCE(?, notify_download_failed),
+            notify_install_failed = COALESCE(?, notify_install_failed),
+            notify_client_offline = COALESCE(?, notify_client_offline),
+            notify_client_online = COALESCE(?, notify_client_online),
+            notify_scrape_error = COALESCE(?, notify_scrape_error),
+            telegram_chat_id = COALESCE(?, telegram_chat_id),
+            notify_email = COALESCE(?, notify_email)
          WHERE user_id = ?"
     )
     .bind(&settings.theme)
@@ -1805,7 +2746,13 @@ pub async fn update_user_settings(
     .bind(settings.scraper_steamrip_enabled)
     .bind(settings.notify_download_complete)
     .bind(settings.notify_new_games)
-    .bind(settings.notify_errors)
+    .bind(settings.notify_download_failed)
+    .bind(settings.notify_install_failed)
+    .bind(settings.notify_client_offline)
+    .bind(settings.notify_client_online)
+    .bind(settings.notify_scrape_error)
+    .bind(&settings.telegram_chat_id)
+    .bind(settings.notify_email)
     .bind(user_id)
     .execute(pool)
     .await?;
@@ -1815,6 +2762,52 @@ pub async fn update_user_settings(
 
 // ─── Notifications ───
 
+/// A browser's Web Push subscription
+#[derive(Debug, Clone, FromRow)]
+pub struct PushSubscription {
+    pub endpoint: String,
+    /// The browser's public key, base64url
+    pub p256dh: String,
+    /// Shared authentication secret, base64url
+    pub auth: String,
+}
+
+/// Store a browser's subscription for a user. A browser that was subscribed
+/// for someone else (e.g. after switching accounts) moves to this user.
+pub async fn save_push_subscription(pool: &SqlitePool, user_id: i64, subscription: &PushSubscription) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO push_subscriptions (user_id, endpoint, p256dh, auth, created_at) VALUES (?, ?, ?, ?, ?)
+         ON CONFLICT(endpoint) DO UPDATE SET user_id = excluded.user_id, p256dh = excluded.p256dh, auth = excluded.auth"
+    )
+    .bind(user_id)
+    .bind(&subscription.endpoint)
+    .bind(&subscription.p256dh)
+    .bind(&subscription.auth)
+    .bind(chrono::Utc::now().to_rfc3339())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn get_push_subscriptions(pool: &SqlitePool, user_id: i64) -> Result<Vec<PushSubscription>, sqlx::Error> {
+    sqlx::query_as("SELECT endpoint, p256dh, auth FROM push_subscriptions WHERE user_id = ?")
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+}
+
+/// Forget a subscription; `user_id` limits it to that user's subscriptions
+pub async fn delete_push_subscription(pool: &SqlitePool, endpoint: &str, user_id: Option<i64>) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM push_subscriptions WHERE endpoint = ? AND (? IS NULL OR user_id = ?)")
+        .bind(endpoint)
+        .bind(user_id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Notification {
     pub id: i64,
@@ -1851,21 +2844,46 @@ pub async fn create_notification(
     Ok(result.last_insert_rowid())
 }
 
-/// Get notifications for a user (last 50)
-pub async fn get_user_notifications(
+/// One page of a user's notifications, newest first (optionally unread first),
+/// plus the total number of notifications the user has
+pub async fn get_user_notifications_paged(
     pool: &SqlitePool,
     user_id: i64,
-) -> Result<Vec<Notification>, sqlx::Error> {
-    sqlx::query_as(
+    page: i64,
+    per_page: i64,
+    unread_first: bool,
+) -> Result<(Vec<Notification>, i64), sqlx::Error> {
+    let order = if unread_first { "read ASC, created_at DESC" } else { "created_at DESC" };
+    let notifications = sqlx::query_as(&format!(
         "SELECT id, user_id, type as notification_type, title, message, read, created_at
          FROM notifications
          WHERE user_id = ?
-         ORDER BY created_at DESC
-         LIMIT 50"
-    )
+         ORDER BY {}, id DESC
+         LIMIT ? OFFSET ?",
+        order
+    ))
     .bind(user_id)
+    .bind(per_page)
+    .bind((page - 1) * per_page)
     .fetch_all(pool)
-    .await
+    .await?;
+
+    let (total,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM notifications WHERE user_id = ?")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+
+    Ok((notifications, total))
+}
+
+/// Delete read notifications older than `days`; returns how many were removed
+pub async fn prune_read_notifications(pool: &SqlitePool, days: i64) -> Result<u64, sqlx::Error> {
+    let cutoff = (chrono::Utc::now() - chrono::Duration::days(days)).to_rfc3339();
+    let result = sqlx::query("DELETE FROM notifications WHERE read = 1 AND created_at < ?")
+        .bind(&cutoff)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
 }
 
 /// Get unread notification count for a user
@@ -1947,7 +2965,7 @@ pub async fn get_games_by_category(
     limit: i64,
 ) -> Result<Vec<Game>, sqlx::Error> {
     sqlx::query_as::<_, Game>(
-        "SELECT g.id, g.title, g.source, g.file_size, g.magnet_link, g.genres, g.company, g.original_size, g.thumbnail_url, g.screenshots, g.source_url, g.post_date, g.search_title
+        "SELECT g.id, g.title, g.source, g.file_size, g.magnet_link, g.link_type, g.genres, g.company, g.original_size, g.thumbnail_url, g.screenshots, g.source_url, g.post_date, g.search_title, g.view_count
          FROM games g
          JOIN game_categories gc ON gc.game_id = g.id
          WHERE gc.category = ?
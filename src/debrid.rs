@@ -0,0 +1,276 @@
+use crate::alldebrid::AllDebridClient;
+use crate::db;
+use crate::realdebrid::{self, DownloadLink, FileSelection, RealDebridClient, TorrentInfo};
+use crate::scrapers::LinkType;
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+
+/// Supported debrid providers as stored in the `debrid_provider` setting
+pub const PROVIDERS: &[&str] = &["realdebrid", "alldebrid"];
+
+/// How long to wait for the provider to fetch an uncached torrent
+pub const TORRENT_TIMEOUT_SECS: u64 = 6 * 60 * 60;
+
+/// Provider-side progress while a torrent is being fetched by the debrid service
+#[derive(Debug, Clone)]
+pub struct DebridProgress {
+    /// Provider status, e.g. "queued", "downloading"
+    pub status: String,
+    /// 0-100
+    pub progress: f64,
+    /// Bytes per second, when the provider reports it
+    pub speed: Option<u64>,
+}
+
+/// Per-download options for resolving a link
+#[derive(Debug, Clone)]
+pub struct LinkOptions {
+    /// Skip samples, readmes, NFOs and similar extras
+    pub skip_junk: bool,
+    /// How long to wait for the provider to fetch an uncached torrent
+    pub max_wait_secs: u64,
+    /// The game's stored link type; detected from the link when unset
+    pub link_type: Option<LinkType>,
+}
+
+impl Default for LinkOptions {
+    fn default() -> Self {
+        Self {
+            skip_junk: false,
+            max_wait_secs: TORRENT_TIMEOUT_SECS,
+            link_type: None,
+        }
+    }
+}
+
+impl LinkOptions {
+    fn link_type_of(&self, link: &str) -> LinkType {
+        self.link_type.unwrap_or_else(|| LinkType::detect(link))
+    }
+}
+
+/// A debrid service that turns magnets/hoster links into direct download URLs
+#[async_trait]
+pub trait Debrid: Send + Sync {
+    /// Provider ID, e.g. "realdebrid"
+    fn provider_name(&self) -> &'static str;
+
+    /// Human-readable name for messages
+    fn provider_label(&self) -> &'static str;
+
+    /// Whether this client was built with a non-empty API key
+    fn is_configured(&self) -> bool;
+
+    /// Make an authenticated call to the provider to check it's reachable and
+    /// accepts the API key
+    async fn check_account(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Resolve a magnet or hoster link into direct download links
+    async fn process_link(&self, link: &str) -> Result<Vec<DownloadLink>, Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Like `process_link`, but applies `options`, waits for uncached torrents
+    /// and reports provider progress. Providers without file selection filter
+    /// the resolved links by filename instead.
+    async fn process_link_with_progress(
+        &self,
+        link: &str,
+        options: &LinkOptions,
+        _on_progress: &(dyn Fn(DebridProgress) + Send + Sync),
+    ) -> Result<Vec<DownloadLink>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut links = self.process_link(link).await?;
+        if options.skip_junk {
+            links.retain(|l| !realdebrid::is_junk_file(&l.filename));
+        }
+        Ok(links)
+    }
+}
+
+#[async_trait]
+impl Debrid for RealDebridClient {
+    fn provider_name(&self) -> &'static str {
+        "realdebrid"
+    }
+
+    fn provider_label(&self) -> &'static str {
+        "Real-Debrid"
+    }
+
+    fn is_configured(&self) -> bool {
+        self.has_api_key()
+    }
+
+    async fn check_account(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.account_info().await
+            .map(|_| ())
+            .map_err(|e| e.to_string().into())
+    }
+
+    async fn process_link(&self, link: &str) -> Result<Vec<DownloadLink>, Box<dyn std::error::Error + Send + Sync>> {
+        RealDebridClient::process_link(self, link).await
+            .map_err(|e| e.to_string().into())
+    }
+
+    async fn process_link_with_progress(
+        &self,
+        link: &str,
+        options: &LinkOptions,
+        on_progress: &(dyn Fn(DebridProgress) + Send + Sync),
+    ) -> Result<Vec<DownloadLink>, Box<dyn std::error::Error + Send + Sync>> {
+        if options.link_type_of(link) == LinkType::DirectDL {
+            return self.process_ddl(link).await.map_err(|e| e.to_string().into());
+        }
+
+        let selection = FileSelection {
+            skip_junk: options.skip_junk,
+            ..FileSelection::default()
+        };
+
+        let report = |info: &TorrentInfo| on_progress(DebridProgress {
+            status: info.status.clone(),
+            progress: info.progress,
+            speed: info.speed,
+        });
+        self.process_magnet_with_progress(link, &selection, options.max_wait_secs, &report).await
+            .map_err(|e| e.to_string().into())
+    }
+}
+
+#[async_trait]
+impl Debrid for AllDebridClient {
+    fn provider_name(&self) -> &'static str {
+        "alldebrid"
+    }
+
+    fn provider_label(&self) -> &'static str {
+        "AllDebrid"
+    }
+
+    fn is_configured(&self) -> bool {
+        self.has_api_key()
+    }
+
+    async fn check_account(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.user().await
+    }
+
+    async fn process_link(&self, link: &str) -> Result<Vec<DownloadLink>, Box<dyn std::error::Error + Send + Sync>> {
+        AllDebridClient::process_link(self, link).await
+    }
+
+    async fn process_link_with_progress(
+        &self,
+        link: &str,
+        options: &LinkOptions,
+        _on_progress: &(dyn Fn(DebridProgress) + Send + Sync),
+    ) -> Result<Vec<DownloadLink>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut links = match options.link_type_of(link) {
+            LinkType::Magnet => self.process_magnet(link).await?,
+            LinkType::DirectDL => vec![self.unlock_link(link).await?],
+        };
+        if options.skip_junk {
+            links.retain(|l| !realdebrid::is_junk_file(&l.filename));
+        }
+        Ok(links)
+    }
+}
+
+/// How long unrestricted links are reused before asking the provider again
+const LINK_CACHE_TTL_SECS: i64 = 4 * 60 * 60;
+
+/// Cache key for a link: the magnet's info-hash, or the link itself for DDLs
+pub fn magnet_hash(link: &str) -> String {
+    link.split(['?', '&'])
+        .find_map(|part| part.strip_prefix("xt=urn:btih:"))
+        .map(|hash| hash.to_lowercase())
+        .unwrap_or_else(|| link.to_string())
+}
+
+/// `process_link_with_progress`, reusing links resolved within the cache TTL
+/// so retries don't hit the provider's API (and its rate limits) again
+pub async fn process_link_cached(
+    db: &SqlitePool,
+    debrid: &dyn Debrid,
+    link: &str,
+    options: &LinkOptions,
+    on_progress: &(dyn Fn(DebridProgress) + Send + Sync),
+) -> Result<Vec<DownloadLink>, Box<dyn std::error::Error + Send + Sync>> {
+    let hash = magnet_hash(link);
+    let provider = debrid.provider_name();
+
+    if let Ok(Some(json)) = db::get_cached_links(db, &hash, provider, options.skip_junk, LINK_CACHE_TTL_SECS).await {
+        if let Ok(links) = serde_json::from_str::<Vec<DownloadLink>>(&json) {
+            if !links.is_empty() {
+                println!("Using {} cached links for {}", links.len(), hash);
+                return Ok(links);
+            }
+        }
+    }
+
+    let links = debrid.process_link_with_progress(link, options, on_progress).await?;
+
+    if !links.is_empty() {
+        if let Ok(json) = serde_json::to_string(&links) {
+            if let Err(e) = db::cache_links(db, &hash, provider, options.skip_junk, &json).await {
+                eprintln!("Failed to cache links for {}: {}", hash, e);
+            }
+        }
+    }
+
+    Ok(links)
+}
+
+/// Forget the cached links for a download's game after it fails or is
+/// retried, so the next attempt doesn't reuse links that may be what broke
+pub async fn forget_cached_links(db: &SqlitePool, download_id: i64) {
+    let link: Option<(String,)> = sqlx::query_as(
+        "SELECT g.magnet_link FROM downloads d JOIN games g ON d.game_id = g.id WHERE d.id = ?"
+    )
+    .bind(download_id)
+    .fetch_optional(db)
+    .await
+    .unwrap_or(None);
+
+    if let Some((link,)) = link {
+        if let Err(e) = db::delete_cached_links(db, &magnet_hash(&link)).await {
+            eprintln!("Failed to clear cached links for download {}: {}", download_id, e);
+        }
+    }
+}
+
+/// Settings key holding the API key for a provider
+pub fn api_key_setting(provider: &str) -> &'static str {
+    match provider {
+        "alldebrid" => "ad_api_key",
+        _ => "rd_api_key",
+    }
+}
+
+/// Build a client for the provider selected in Settings, using the API key
+/// stored in the database. Falls back to `fallback` (built from env vars at
+/// startup) when it matches the selected provider and no key is stored.
+pub async fn from_settings(
+    db: &SqlitePool,
+    fallback: &Arc<dyn Debrid>,
+) -> Result<Arc<dyn Debrid>, String> {
+    let provider = db::get_setting(db, "debrid_provider").await
+        .ok()
+        .flatten()
+        .filter(|p| PROVIDERS.contains(&p.as_str()))
+        .unwrap_or_else(|| "realdebrid".to_string());
+
+    let api_key = db::get_setting(db, api_key_setting(&provider)).await
+        .ok()
+        .flatten()
+        .filter(|k| !k.is_empty());
+
+    let client: Arc<dyn Debrid> = match (api_key, provider.as_str()) {
+        (Some(key), "alldebrid") => Arc::new(AllDebridClient::new(key)),
+        (Some(key), _) => Arc::new(RealDebridClient::new(key)),
+        (None, _) if fallback.provider_name() == provider && fallback.is_configured() => fallback.clone(),
+        (None, "alldebrid") => return Err("AllDebrid API key not set. Please configure it in Settings.".to_string()),
+        (None, _) => return Err("Real-Debrid API key not set. Please configure it in Settings.".to_string()),
+    };
+
+    Ok(client)
+}
@@ -0,0 +1,331 @@
+//! Retention for finished downloads. On a long-running instance the
+//! `download_files` table and the download directory only ever grow, so an
+//! hourly task forgets the file records of `completed`/`installed` downloads
+//! once they're older than the retention period, or oldest-first while the
+//! download directory is over its size limit, and can delete their folders too.
+//!
+//! It's off until an admin sets `download_cleanup` to `dry_run`, which only
+//! logs what would go, or `on`. The download rows themselves are kept for the
+//! download history. Files are only deleted inside the download directory, so
+//! archived library copies and client-side paths are never touched.
+
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+
+use crate::db;
+
+pub const MODE_SETTING: &str = "download_cleanup";
+pub const RETENTION_DAYS_SETTING: &str = "download_cleanup_days";
+pub const MAX_DISK_GB_SETTING: &str = "download_cleanup_max_disk_gb";
+pub const DELETE_FILES_SETTING: &str = "download_cleanup_delete_files";
+
+const DEFAULT_RETENTION_DAYS: i64 = 30;
+const GB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Off,
+    /// Log what would be removed without changing anything
+    DryRun,
+    On,
+}
+
+impl Mode {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "off" => Some(Mode::Off),
+            "dry_run" => Some(Mode::DryRun),
+            "on" => Some(Mode::On),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Mode::Off => "off",
+            Mode::DryRun => "dry_run",
+            Mode::On => "on",
+        }
+    }
+}
+
+pub struct Policy {
+    pub mode: Mode,
+    /// Finished downloads older than this are cleaned up; 0 turns the age limit off
+    pub retention_days: i64,
+    /// Clean up oldest-first while the download directory is bigger than this
+    pub max_disk_gb: Option<f64>,
+    /// Also delete the download's folder, not just its file records
+    pub delete_files: bool,
+}
+
+/// Check a value for one of the cleanup settings before it's saved
+pub fn validate(key: &str, value: &str) -> Result<(), String> {
+    let ok = match key {
+        MODE_SETTING => Mode::parse(value).is_some(),
+        RETENTION_DAYS_SETTING => value.parse::<i64>().is_ok_and(|days| days >= 0),
+        MAX_DISK_GB_SETTING => value.parse::<f64>().is_ok_and(|gb| gb.is_finite() && gb >= 0.0),
+        DELETE_FILES_SETTING => value == "true" || value == "false",
+        _ => false,
+    };
+    if ok {
+        return Ok(());
+    }
+    Err(match key {
+        MODE_SETTING => format!("{} must be off, dry_run or on", key),
+        RETENTION_DAYS_SETTING => format!("{} must be a non-negative number of days", key),
+        MAX_DISK_GB_SETTING => format!("{} must be a non-negative number of GB", key),
+        _ => format!("{} must be true or false", key),
+    })
+}
+
+/// Cleanup policy from settings; unset values fall back to off, 30 days, no
+/// size limit and keeping files
+pub async fn load_policy(pool: &SqlitePool) -> Policy {
+    let read = |key: &'static str| async move { db::get_setting(pool, key).await.ok().flatten() };
+
+    Policy {
+        mode: read(MODE_SETTING).await.and_then(|v| Mode::parse(&v)).unwrap_or(Mode::Off),
+        retention_days: read(RETENTION_DAYS_SETTING)
+            .await
+            .and_then(|v| v.parse().ok())
+            .filter(|days| *days >= 0)
+            .unwrap_or(DEFAULT_RETENTION_DAYS),
+        max_disk_gb: read(MAX_DISK_GB_SETTING)
+            .await
+            .and_then(|v| v.parse().ok())
+            .filter(|gb: &f64| gb.is_finite() && *gb > 0.0),
+        delete_files: read(DELETE_FILES_SETTING).await.is_some_and(|v| v == "true"),
+    }
+}
+
+/// A finished download that still has something to clean up
+struct Candidate {
+    download: db::CleanupCandidate,
+    /// Its folder, when that's inside the download directory and will be deleted
+    folder: Option<PathBuf>,
+    /// Bytes deleting `folder` frees
+    size: u64,
+}
+
+/// Downloads to clean up, oldest first: everything that finished before
+/// `cutoff`, then more until `excess` bytes would be freed
+fn select(candidates: Vec<Candidate>, cutoff: Option<&str>, mut excess: u64) -> Vec<Candidate> {
+    candidates
+        .into_iter()
+        .filter(|c| {
+            let expired = cutoff.is_some_and(|cutoff| c.download.finished_at.as_str() < cutoff);
+            if !expired && excess == 0 {
+                return false;
+            }
+            excess = excess.saturating_sub(c.size);
+            true
+        })
+        .collect()
+}
+
+/// Drop candidates whose folder another download still uses. Every server
+/// download of a game lands in the same `<download dir>/<title>` folder, so a
+/// folder only goes when all the downloads in it are being cleaned up.
+fn drop_shared(selected: Vec<Candidate>, paths: &[(i64, String)]) -> Vec<Candidate> {
+    let cleaned: Vec<i64> = selected.iter().map(|c| c.download.id).collect();
+    selected
+        .into_iter()
+        .filter(|c| {
+            let Some(own) = c.folder.as_ref().and(c.download.file_path.as_deref()) else { return true };
+            let own = Path::new(own);
+            let shared = paths.iter().any(|(id, path)| {
+                let path = Path::new(path);
+                !cleaned.contains(id) && (path.starts_with(own) || own.starts_with(path))
+            });
+            if shared {
+                println!("  keeping #{} {}: {} is used by another download", c.download.id, c.download.game_title, own.display());
+            }
+            !shared
+        })
+        .collect()
+}
+
+/// Run one cleanup pass with the current settings
+pub async fn run(pool: &SqlitePool, download_dir: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let policy = load_policy(pool).await;
+    if policy.mode == Mode::Off || (policy.retention_days == 0 && policy.max_disk_gb.is_none()) {
+        return Ok(());
+    }
+
+    let download_dir = tokio::fs::canonicalize(download_dir).await.unwrap_or_else(|_| download_dir.to_path_buf());
+    let mut candidates = Vec::new();
+    for download in db::get_cleanup_candidates(pool, policy.delete_files).await? {
+        let folder = match &download.file_path {
+            Some(path) if policy.delete_files => inside(&download_dir, Path::new(path)).await,
+            _ => None,
+        };
+        let size = match &folder {
+            Some(folder) => disk_usage(folder.clone()).await,
+            None => 0,
+        };
+        // Nothing left to do once the records are gone and the folder can't be deleted
+        if folder.is_none() && !download.has_files {
+            continue;
+        }
+        candidates.push(Candidate { download, folder, size });
+    }
+
+    let cutoff = (policy.retention_days > 0)
+        .then(|| (chrono::Utc::now() - chrono::Duration::days(policy.retention_days)).to_rfc3339());
+    let excess = match policy.max_disk_gb {
+        Some(max_gb) if policy.delete_files => {
+            let used = disk_usage(download_dir.clone()).await;
+            used.saturating_sub((max_gb * GB) as u64)
+        }
+        Some(_) => {
+            println!("🧹 Download cleanup: {} only frees space when {} is true", MAX_DISK_GB_SETTING, DELETE_FILES_SETTING);
+            0
+        }
+        None => 0,
+    };
+
+    let mut selected = select(candidates, cutoff.as_deref(), excess);
+    if policy.delete_files {
+        selected = drop_shared(selected, &db::get_download_paths(pool).await?);
+    }
+    if selected.is_empty() {
+        return Ok(());
+    }
+
+    let dry_run = policy.mode == Mode::DryRun;
+    let freed: u64 = selected.iter().map(|c| c.size).sum();
+    println!(
+        "🧹 Download cleanup{}: {} finished download(s), {:.1} GB of files",
+        if dry_run { " (dry run, nothing removed)" } else { "" },
+        selected.len(),
+        freed as f64 / GB
+    );
+
+    for candidate in selected {
+        let download = &candidate.download;
+        let what = match &candidate.folder {
+            Some(folder) => format!("file records and {}", folder.display()),
+            None => "file records".to_string(),
+        };
+        println!("  {} #{} {} (finished {}): {}", if dry_run { "would remove" } else { "removing" },
+            download.id, download.game_title, download.finished_at, what);
+        if dry_run {
+            continue;
+        }
+
+        if let Some(folder) = &candidate.folder {
+            let removed = if folder.is_dir() {
+                tokio::fs::remove_dir_all(folder).await
+            } else {
+                tokio::fs::remove_file(folder).await
+            };
+            if let Err(e) = removed {
+                eprintln!("  Failed to delete {}: {}", folder.display(), e);
+                continue;
+            }
+        }
+        db::clear_download_files(pool, download.id, candidate.folder.is_some()).await?;
+    }
+
+    Ok(())
+}
+
+/// `path` resolved, when it exists and lies strictly inside `dir`
+async fn inside(dir: &Path, path: &Path) -> Option<PathBuf> {
+    let path = tokio::fs::canonicalize(path).await.ok()?;
+    (path != dir && path.starts_with(dir)).then_some(path)
+}
+
+/// Total size of the files under `path`, without following symlinks
+async fn disk_usage(path: PathBuf) -> u64 {
+    fn walk(path: &Path) -> u64 {
+        let Ok(meta) = std::fs::symlink_metadata(path) else { return 0 };
+        if !meta.is_dir() {
+            return meta.len();
+        }
+        std::fs::read_dir(path)
+            .map(|entries| entries.flatten().map(|entry| walk(&entry.path())).sum())
+            .unwrap_or(0)
+    }
+    tokio::task::spawn_blocking(move || walk(&path)).await.unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(id: i64, finished_at: &str, size: u64) -> Candidate {
+        Candidate {
+            download: db::CleanupCandidate {
+                id,
+                game_title: format!("Game {}", id),
+                finished_at: finished_at.to_string(),
+                file_path: None,
+                has_files: true,
+            },
+            folder: None,
+            size,
+        }
+    }
+
+    fn ids(selected: Vec<Candidate>) -> Vec<i64> {
+        selected.into_iter().map(|c| c.download.id).collect()
+    }
+
+    #[test]
+    fn test_select() {
+        let all = || vec![
+            candidate(1, "2026-01-01T00:00:00+00:00", 10),
+            candidate(2, "2026-02-01T00:00:00+00:00", 20),
+            candidate(3, "2026-03-01T00:00:00+00:00", 30),
+        ];
+
+        // Age only
+        assert_eq!(ids(select(all(), Some("2026-02-15T00:00:00+00:00"), 0)), vec![1, 2]);
+        // Over the size limit, oldest first until enough is freed
+        assert_eq!(ids(select(all(), None, 25)), vec![1, 2]);
+        assert_eq!(ids(select(all(), Some("2026-01-15T00:00:00+00:00"), 5)), vec![1]);
+        assert!(select(all(), None, 0).is_empty());
+    }
+
+    #[test]
+    fn test_drop_shared() {
+        let with_folder = |id: i64, path: &str| {
+            let mut c = candidate(id, "2026-01-01T00:00:00+00:00", 10);
+            c.download.file_path = Some(path.to_string());
+            c.folder = Some(PathBuf::from(path));
+            c
+        };
+        let paths = vec![
+            (1, "/downloads/Game A".to_string()),
+            (2, "/downloads/Game A".to_string()),
+            (3, "/downloads/Game B".to_string()),
+            (4, "/downloads/Game C".to_string()),
+            (5, "/downloads/Game C/Setup".to_string()),
+        ];
+
+        // #2 still uses Game A and isn't being cleaned; #5 is nested in Game C
+        let selected = vec![with_folder(1, "/downloads/Game A"), with_folder(3, "/downloads/Game B"), with_folder(4, "/downloads/Game C")];
+        assert_eq!(ids(drop_shared(selected, &paths)), vec![3]);
+
+        // Both downloads of Game A are going, so its folder can go too
+        let selected = vec![with_folder(1, "/downloads/Game A"), with_folder(2, "/downloads/Game A")];
+        assert_eq!(ids(drop_shared(selected, &paths)), vec![1, 2]);
+
+        // Records-only cleanup never touches the folder
+        let mut records_only = with_folder(1, "/downloads/Game A");
+        records_only.folder = None;
+        assert_eq!(ids(drop_shared(vec![records_only], &paths)), vec![1]);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(MODE_SETTING, "dry_run").is_ok());
+        assert!(validate(MODE_SETTING, "yes").is_err());
+        assert!(validate(RETENTION_DAYS_SETTING, "0").is_ok());
+        assert!(validate(RETENTION_DAYS_SETTING, "-1").is_err());
+        assert!(validate(MAX_DISK_GB_SETTING, "250.5").is_ok());
+        assert!(validate(DELETE_FILES_SETTING, "1").is_err());
+    }
+}
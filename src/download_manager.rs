@@ -1,13 +1,18 @@
 use crate::db;
 use crate::downloader::Downloader;
+use crate::events::EventHub;
+use crate::notifications::Notifier;
 use crate::extractor::Extractor;
-use crate::realdebrid::RealDebridClient;
+use crate::debrid::{self, Debrid};
 use serde::Serialize;
 use sqlx::SqlitePool;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::Semaphore;
 
-#[derive(Debug, Clone, Serialize)]
+/// Retries per file for transient download errors (waits 5s, 10s, 20s)
+const MAX_DOWNLOAD_RETRIES: u32 = 3;
+
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
 pub struct DownloadInfo {
     pub id: i64,
     pub game_id: i64,
@@ -25,9 +30,26 @@ pub struct DownloadInfo {
     pub completed_at: Option<String>,
     pub files: Vec<DownloadFileInfo>,
     pub has_md5: bool,
+    pub md5_status: Option<String>,
+    /// Debrid provider progress while it fetches an uncached torrent, e.g. "Real-Debrid: downloading"
+    pub debrid_status: Option<String>,
+    /// What a client's installer is doing, e.g. "Installing data.bin"
+    pub install_step: Option<String>,
+    /// Folder the client should install this game to (client default when unset)
+    pub install_dir: Option<String>,
+    /// Client the download is assigned to; unset means any of the user's clients
+    pub client_id: Option<String>,
+}
+
+/// Outcome of importing existing game folders from the download directory
+#[derive(Debug, Default, Serialize)]
+pub struct ScanResult {
+    pub imported: usize,
+    /// Folder names that didn't match any game
+    pub unmatched: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
 pub struct DownloadFileInfo {
     pub id: i64,
     pub filename: String,
@@ -40,7 +62,13 @@ pub struct DownloadFileInfo {
 pub struct DownloadManagerConfig {
     pub auto_extract: bool,
     pub delete_archives: bool,
+    /// Cap on how much archives nested in a download may unpack to, as a multiple of its size
+    pub nested_size_factor: u64,
     pub max_concurrent: usize,
+    /// Where finished games are moved to keep the download directory lean
+    pub library_dir: Option<std::path::PathBuf>,
+    /// Move a download into the library as soon as it's marked installed
+    pub auto_archive: bool,
 }
 
 impl Default for DownloadManagerConfig {
@@ -48,7 +76,10 @@ impl Default for DownloadManagerConfig {
         Self {
             auto_extract: true,
             delete_archives: false,
+            nested_size_factor: crate::extractor::DEFAULT_NESTED_SIZE_FACTOR,
             max_concurrent: 1,
+            library_dir: None,
+            auto_archive: false,
         }
     }
 }
@@ -57,30 +88,59 @@ pub struct DownloadManager {
     db: SqlitePool,
     downloader: Arc<Downloader>,
     extractor: Arc<Extractor>,
-    rd_client: Arc<RealDebridClient>,
+    debrid: Arc<dyn Debrid>,
     config: DownloadManagerConfig,
-    is_processing: Arc<RwLock<bool>>,
+    events: Arc<EventHub>,
+    notifier: Arc<Notifier>,
+    workers: Arc<Semaphore>,
+}
+
+/// What a queue worker needs to process downloads
+#[derive(Clone)]
+struct WorkerContext {
+    db: SqlitePool,
+    downloader: Arc<Downloader>,
+    extractor: Arc<Extractor>,
+    debrid: Arc<dyn Debrid>,
+    config: DownloadManagerConfig,
+    events: Arc<EventHub>,
+    notifier: Arc<Notifier>,
+    workers: Arc<Semaphore>,
 }
 
 impl DownloadManager {
     pub fn new(
         db: SqlitePool,
         downloader: Arc<Downloader>,
-        rd_client: Arc<RealDebridClient>,
+        debrid: Arc<dyn Debrid>,
         config: DownloadManagerConfig,
+        events: Arc<EventHub>,
+        notifier: Arc<Notifier>,
     ) -> Self {
+        let workers = Arc::new(Semaphore::new(config.max_concurrent.max(1)));
         Self {
             db,
             downloader,
-            extractor: Arc::new(Extractor::new()),
-            rd_client,
+            extractor: Arc::new(Extractor::new().with_nested_size_factor(config.nested_size_factor)),
+            debrid,
             config,
-            is_processing: Arc::new(RwLock::new(false)),
+            events,
+            notifier,
+            workers,
         }
     }
 
+    /// Where new downloads are written
+    pub fn download_dir(&self) -> &std::path::Path {
+        self.downloader.download_dir()
+    }
+
+    pub fn library_dir(&self) -> Option<&std::path::Path> {
+        self.config.library_dir.as_deref()
+    }
+
     /// Add a game to the download queue. Returns the download ID.
-    pub async fn queue_download(&self, game_id: i64) -> Result<i64, Box<dyn std::error::Error + Send + Sync>> {
+    pub async fn queue_download(&self, game_id: i64, skip_junk: bool) -> Result<i64, Box<dyn std::error::Error + Send + Sync>> {
         // Check if game exists
         let game = db::get_game_by_id(&self.db, game_id).await
             .map_err(|e| format!("Game not found: {}", e))?;
@@ -103,10 +163,11 @@ impl DownloadManager {
         let now = chrono::Utc::now().to_rfc3339();
 
         let result = sqlx::query(
-            "INSERT INTO downloads (game_id, status, progress, created_at) VALUES (?, 'queued', 0.0, ?)"
+            "INSERT INTO downloads (game_id, status, progress, created_at, skip_junk) VALUES (?, 'queued', 0.0, ?, ?)"
         )
         .bind(game_id)
         .bind(&now)
+        .bind(skip_junk)
         .execute(&self.db)
         .await?;
 
@@ -119,56 +180,59 @@ impl DownloadManager {
         Ok(download_id)
     }
 
-    /// Trigger queue processing if not already running
-    pub async fn try_process_queue(&self) {
-        let mut is_processing = self.is_processing.write().await;
-        if *is_processing {
-            return;
+    /// Pick up server-side downloads a previous run left mid-flight (killed or
+    /// shut down). Downloads go back to the queue and resume from their partial
+    /// files; interrupted extractions are re-run from the archives on disk.
+    /// Client downloads (which have an owner) belong to the agent and are left alone.
+    pub async fn recover_interrupted(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        // The worker records each file again when it picks the download back up
+        let mut tx = self.db.begin().await?;
+        sqlx::query(
+            "DELETE FROM download_files WHERE download_id IN
+             (SELECT id FROM downloads WHERE status = 'downloading' AND user_id IS NULL)"
+        )
+        .execute(&mut *tx)
+        .await?;
+        let requeued = sqlx::query(
+            "UPDATE downloads SET status = 'queued', download_speed = NULL, eta = NULL
+             WHERE status = 'downloading' AND user_id IS NULL"
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        tx.commit().await?;
+        if requeued > 0 {
+            println!("🔁 Re-queued {} interrupted download(s)", requeued);
         }
-        *is_processing = true;
-        drop(is_processing);
-
-        let db = self.db.clone();
-        let downloader = self.downloader.clone();
-        let extractor = self.extractor.clone();
-        let rd_client = self.rd_client.clone();
-        let config = self.config.clone();
-        let is_processing = self.is_processing.clone();
-
-        tokio::spawn(async move {
-            loop {
-                // Get next queued download
-                let next: Option<(i64, i64)> = sqlx::query_as(
-                    "SELECT id, game_id FROM downloads WHERE status = 'queued' ORDER BY created_at ASC LIMIT 1"
-                )
-                .fetch_optional(&db)
-                .await
-                .unwrap_or(None);
 
-                let Some((download_id, game_id)) = next else {
-                    break;
-                };
-
-                // Process this download
-                if let Err(e) = process_download(
-                    &db,
-                    &downloader,
-                    &extractor,
-                    &rd_client,
-                    &config,
-                    download_id,
-                    game_id,
-                ).await {
-                    eprintln!("Download {} failed: {}", download_id, e);
-                    let _ = update_download_status(&db, download_id, "failed", Some(&e.to_string())).await;
-                }
-
-                // Clear downloader progress for this download
-                downloader.clear_progress(download_id).await;
+        let extracting: Vec<(i64,)> = sqlx::query_as(
+            "UPDATE downloads SET status = 'failed', error_message = 'Extraction was interrupted'
+             WHERE status = 'extracting' AND user_id IS NULL
+             RETURNING id"
+        )
+        .fetch_all(&self.db)
+        .await?;
+        for (download_id,) in extracting {
+            match self.reextract_download(download_id).await {
+                Ok(_) => println!("🔁 Resuming interrupted extraction for download {}", download_id),
+                Err(e) => eprintln!("Could not resume extraction for download {}: {}", download_id, e),
             }
+        }
+
+        Ok(())
+    }
 
-            let mut flag = is_processing.write().await;
-            *flag = false;
+    /// Spawn queue workers until `max_concurrent` are running
+    pub async fn try_process_queue(&self) {
+        spawn_workers(&WorkerContext {
+            db: self.db.clone(),
+            downloader: self.downloader.clone(),
+            extractor: self.extractor.clone(),
+            debrid: self.debrid.clone(),
+            config: self.config.clone(),
+            events: self.events.clone(),
+            notifier: self.notifier.clone(),
+            workers: self.workers.clone(),
         });
     }
 
@@ -178,7 +242,7 @@ impl DownloadManager {
             r#"
             SELECT d.id, d.game_id, d.status, d.progress, d.download_speed, d.eta,
                    d.file_path, d.installer_path, d.error_message, d.created_at, d.completed_at,
-                   g.title as game_title, g.file_size as game_size, d.client_id, d.user_id
+                   g.title as game_title, g.file_size as game_size, d.client_id, d.user_id, d.md5_status, d.debrid_status, d.install_step, d.install_dir
             FROM downloads d
             JOIN games g ON d.game_id = g.id
             ORDER BY d.created_at DESC
@@ -187,40 +251,19 @@ impl DownloadManager {
         .fetch_all(&self.db)
         .await?;
 
+        let ids: Vec<i64> = rows.iter().map(|r| r.id).collect();
+        let mut files_by_download = db::get_download_files_map(&self.db, &ids).await
+            .unwrap_or_default();
+
         let mut downloads = Vec::new();
 
         for row in rows {
-            // Get download files
-            let files: Vec<db::DownloadFileRow> = sqlx::query_as(
-                "SELECT id, filename, file_size, file_path, is_extracted FROM download_files WHERE download_id = ?"
-            )
-            .bind(row.id)
-            .fetch_all(&self.db)
-            .await
-            .unwrap_or_default();
+            let files = files_by_download.remove(&row.id).unwrap_or_default();
 
             // Merge with live progress from downloader if actively downloading
-            let (progress, speed, eta) = if row.status == "downloading" {
-                if let Some(live) = self.downloader.get_progress(row.id).await {
-                    let pct = if live.total_bytes > 0 {
-                        (live.bytes_downloaded as f64 / live.total_bytes as f64) * 100.0
-                    } else {
-                        row.progress
-                    };
-                    let speed_str = format_speed(live.speed);
-                    let eta_str = if live.speed > 0.0 && live.total_bytes > live.bytes_downloaded {
-                        let remaining_bytes = live.total_bytes - live.bytes_downloaded;
-                        let secs = remaining_bytes as f64 / live.speed;
-                        Some(format_eta(secs))
-                    } else {
-                        None
-                    };
-                    (pct, Some(speed_str), eta_str)
-                } else {
-                    (row.progress, row.download_speed.clone(), row.eta.clone())
-                }
-            } else {
-                (row.progress, row.download_speed.clone(), row.eta.clone())
+            let (progress, speed, eta) = match self.live_progress(&row.status, row.id).await {
+                Some((pct, speed, eta)) => (pct.unwrap_or(row.progress), Some(speed), eta),
+                None => (row.progress, row.download_speed.clone(), row.eta.clone()),
             };
 
             // Merge extraction progress if extracting
@@ -234,7 +277,7 @@ impl DownloadManager {
             let has_md5 = if let Some(ref path) = row.file_path {
                 if row.status == "completed" || row.status == "installed" {
                     let dir = std::path::Path::new(path);
-                    crate::md5_validator::find_md5_file(dir).await.is_some()
+                    crate::md5_validator::find_checksum_file(dir).await.is_some()
                 } else {
                     false
                 }
@@ -265,12 +308,54 @@ impl DownloadManager {
                     is_extracted: f.is_extracted,
                 }).collect(),
                 has_md5,
+                md5_status: row.md5_status,
+                debrid_status: row.debrid_status,
+                install_step: row.install_step,
+                install_dir: row.install_dir,
+                client_id: row.client_id,
             });
         }
 
         Ok(downloads)
     }
 
+    /// Live (percent, speed, ETA) from the downloader for an active download.
+    /// Percent is None when the total size isn't known yet.
+    async fn live_progress(&self, status: &str, download_id: i64) -> Option<(Option<f64>, String, Option<String>)> {
+        if status != "downloading" {
+            return None;
+        }
+        let live = self.downloader.get_progress(download_id).await?;
+        let pct = if live.total_bytes > 0 {
+            Some((live.bytes_downloaded as f64 / live.total_bytes as f64) * 100.0)
+        } else {
+            None
+        };
+        let eta = if live.speed > 0.0 && live.total_bytes > live.bytes_downloaded {
+            let remaining_bytes = live.total_bytes - live.bytes_downloaded;
+            Some(format_eta(remaining_bytes as f64 / live.speed))
+        } else {
+            None
+        };
+        Some((pct, format_speed(live.speed), eta))
+    }
+
+    /// Overlay live download/extraction progress onto downloads loaded elsewhere
+    pub async fn merge_live_progress(&self, downloads: &mut [DownloadInfo]) {
+        for dl in downloads.iter_mut() {
+            if let Some((pct, speed, eta)) = self.live_progress(&dl.status, dl.id).await {
+                if let Some(pct) = pct {
+                    dl.progress = pct;
+                }
+                dl.download_speed = Some(speed);
+                dl.eta = eta;
+            }
+            if dl.status == "extracting" {
+                dl.extract_progress = self.extractor.get_progress(dl.id).await;
+            }
+        }
+    }
+
     /// Get downloads assigned to a specific client that are ready for extraction
     /// Returns downloads with status 'completed' (downloaded but not extracted yet)
     pub async fn get_client_queue(&self, client_id: &str) -> Result<Vec<DownloadInfo>, Box<dyn std::error::Error + Send + Sync>> {
@@ -289,17 +374,14 @@ impl DownloadManager {
         .fetch_all(&self.db)
         .await?;
 
+        let ids: Vec<i64> = rows.iter().map(|r| r.id).collect();
+        let mut files_by_download = db::get_download_files_map(&self.db, &ids).await
+            .unwrap_or_default();
+
         let mut downloads = Vec::new();
 
         for row in rows {
-            // Get download files
-            let files: Vec<db::DownloadFileRow> = sqlx::query_as(
-                "SELECT id, filename, file_size, file_path, is_extracted FROM download_files WHERE download_id = ?"
-            )
-            .bind(row.id)
-            .fetch_all(&self.db)
-            .await
-            .unwrap_or_default();
+            let files = files_by_download.remove(&row.id).unwrap_or_default();
 
             // Get extraction progress if extracting
             let extract_progress = if row.status == "extracting" {
@@ -311,7 +393,7 @@ impl DownloadManager {
             // Check if MD5 file exists
             let has_md5 = if let Some(ref path) = row.file_path {
                 let dir = std::path::Path::new(path);
-                crate::md5_validator::find_md5_file(dir).await.is_some()
+                crate::md5_validator::find_checksum_file(dir).await.is_some()
             } else {
                 false
             };
@@ -339,6 +421,11 @@ impl DownloadManager {
                     is_extracted: f.is_extracted,
                 }).collect(),
                 has_md5,
+                md5_status: row.md5_status,
+                debrid_status: row.debrid_status,
+                install_step: row.install_step,
+                install_dir: row.install_dir,
+                client_id: row.client_id,
             });
         }
 
@@ -360,6 +447,7 @@ impl DownloadManager {
 
         // Update DB status
         update_download_status(&self.db, download_id, "failed", Some("Cancelled by user")).await?;
+        self.events.download_changed(download_id).await;
 
         Ok(())
     }
@@ -403,7 +491,7 @@ impl DownloadManager {
             Some((s,)) if s == "failed" => {
                 // Reset to queued
                 sqlx::query(
-                    "UPDATE downloads SET status = 'queued', progress = 0.0, error_message = NULL, download_speed = NULL, eta = NULL WHERE id = ?"
+                    "UPDATE downloads SET status = 'queued', progress = 0.0, error_message = NULL, download_speed = NULL, eta = NULL, md5_status = NULL, debrid_status = NULL, install_step = NULL, retry_count = 0 WHERE id = ?"
                 )
                 .bind(download_id)
                 .execute(&self.db)
@@ -414,6 +502,8 @@ impl DownloadManager {
                     .bind(download_id)
                     .execute(&self.db)
                     .await?;
+                debrid::forget_cached_links(&self.db, download_id).await;
+                self.events.download_changed(download_id).await;
 
                 // Trigger processing
                 self.try_process_queue().await;
@@ -469,6 +559,7 @@ impl DownloadManager {
                 .bind(download_id)
                 .execute(&self.db)
                 .await;
+                self.events.download_changed(download_id).await;
 
                 Ok(installer)
             }
@@ -497,6 +588,13 @@ impl DownloadManager {
                     .bind(download_id)
                     .execute(&self.db)
                     .await?;
+                self.events.download_changed(download_id).await;
+
+                if self.config.auto_archive && self.config.library_dir.is_some() {
+                    if let Err(e) = self.archive_download(download_id).await {
+                        eprintln!("Failed to move download {} to the library: {}", download_id, e);
+                    }
+                }
                 Ok(())
             }
             Some((s,)) => Err(format!("Cannot mark as installed: status is '{}'", s).into()),
@@ -504,11 +602,169 @@ impl DownloadManager {
         }
     }
 
+    /// Extract the archives still in a download's folder again without
+    /// re-downloading. Runs in the background; returns the number of archive sets.
+    pub async fn reextract_download(&self, download_id: i64) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+        let row: Option<(String, i64, Option<String>)> = sqlx::query_as(
+            "SELECT status, game_id, file_path FROM downloads WHERE id = ?"
+        )
+        .bind(download_id)
+        .fetch_optional(&self.db)
+        .await?;
+
+        let (status, game_id, file_path) = row.ok_or("Download not found")?;
+        if status != "completed" && status != "failed" {
+            return Err(format!("Cannot re-extract download with status '{}'", status).into());
+        }
+
+        // Failed downloads may not have recorded their folder yet
+        let game_dir = match file_path {
+            Some(path) => std::path::PathBuf::from(path),
+            None => {
+                let game = db::get_game_by_id(&self.db, game_id).await?;
+                self.downloader.download_dir().join(sanitize_filename(&game.title))
+            }
+        };
+        if !game_dir.is_dir() {
+            return Err(format!("Download folder not found: {}", game_dir.display()).into());
+        }
+
+        let mut files = Vec::new();
+        let mut entries = tokio::fs::read_dir(&game_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.file_type().await?.is_file() {
+                files.push(entry.path());
+            }
+        }
+        files.sort();
+
+        let archive_sets = crate::extractor::Extractor::group_archive_sets(&files);
+        if archive_sets.is_empty() {
+            return Err("No archives left in the download folder to extract".into());
+        }
+
+        // Claim the download so a second request can't start a parallel extraction
+        let claimed = sqlx::query(
+            "UPDATE downloads SET status = 'extracting', error_message = NULL WHERE id = ? AND status = ?"
+        )
+        .bind(download_id)
+        .bind(&status)
+        .execute(&self.db)
+        .await?;
+        if claimed.rows_affected() == 0 {
+            return Err("Download changed state, try again".into());
+        }
+
+        let count = archive_sets.len();
+        println!("Re-extracting {} archive(s) for download {}", count, download_id);
+        self.events.download_changed(download_id).await;
+
+        let db = self.db.clone();
+        let extractor = self.extractor.clone();
+        let config = self.config.clone();
+        let events = self.events.clone();
+        tokio::spawn(async move {
+            if let Err(e) = run_reextract(&db, &extractor, &config, download_id, game_id, &game_dir, &archive_sets).await {
+                eprintln!("Re-extraction of download {} failed: {}", download_id, e);
+                let _ = update_download_status(&db, download_id, "failed", Some(&e.to_string())).await;
+            }
+            events.download_changed(download_id).await;
+        });
+
+        Ok(count)
+    }
+
+    /// Move a finished download's game folder into the library directory and
+    /// point its paths there. Returns the new folder path.
+    pub async fn archive_download(&self, download_id: i64) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let library_dir = self.config.library_dir.as_ref()
+            .ok_or("No library directory configured (set LIBRARY_DIR)")?;
+
+        let row: Option<(String, Option<String>, Option<String>)> = sqlx::query_as(
+            "SELECT status, file_path, installer_path FROM downloads WHERE id = ?"
+        )
+        .bind(download_id)
+        .fetch_optional(&self.db)
+        .await?;
+
+        let (status, file_path, installer_path) = row.ok_or("Download not found")?;
+        if status != "completed" && status != "installed" {
+            return Err(format!("Cannot move to library: status is '{}'", status).into());
+        }
+
+        let source = std::path::PathBuf::from(file_path.ok_or("Download has no file path")?);
+        if !source.is_dir() {
+            return Err(format!("Download folder not found: {}", source.display()).into());
+        }
+        if source.starts_with(library_dir) {
+            return Err("Download is already in the library".into());
+        }
+
+        let dest = library_dir.join(source.file_name().ok_or("Invalid download folder")?);
+        if tokio::fs::try_exists(&dest).await.unwrap_or(false) {
+            return Err(format!("{} already exists in the library", dest.display()).into());
+        }
+
+        tokio::fs::create_dir_all(library_dir).await?;
+        move_dir(&source, &dest).await?;
+
+        // Re-root paths that pointed inside the old folder
+        let relocate = |path: &str| -> String {
+            match std::path::Path::new(path).strip_prefix(&source) {
+                Ok(rel) => dest.join(rel).to_string_lossy().to_string(),
+                Err(_) => path.to_string(),
+            }
+        };
+
+        let new_path = dest.to_string_lossy().to_string();
+        sqlx::query("UPDATE downloads SET file_path = ?, installer_path = ? WHERE id = ?")
+            .bind(&new_path)
+            .bind(installer_path.as_deref().map(relocate))
+            .bind(download_id)
+            .execute(&self.db)
+            .await?;
+
+        let files: Vec<(i64, Option<String>)> = sqlx::query_as(
+            "SELECT id, file_path FROM download_files WHERE download_id = ?"
+        )
+        .bind(download_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        for (file_id, path) in files {
+            if let Some(path) = path {
+                sqlx::query("UPDATE download_files SET file_path = ? WHERE id = ?")
+                    .bind(relocate(&path))
+                    .bind(file_id)
+                    .execute(&self.db)
+                    .await?;
+            }
+        }
+
+        println!("Moved download {} to library: {}", download_id, dest.display());
+        Ok(new_path)
+    }
+
     /// Scan /mnt/storage/games for existing game directories and import them as downloads
-    pub async fn scan_existing_games(&self) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+    /// Folders that can't be matched to a game are left alone and reported back,
+    /// so they can be renamed and picked up by the next scan.
+    pub async fn scan_existing_games(&self) -> Result<ScanResult, Box<dyn std::error::Error + Send + Sync>> {
         let download_dir = self.downloader.download_dir();
         let mut entries = tokio::fs::read_dir(&download_dir).await?;
-        let mut imported = 0;
+        let mut result = ScanResult::default();
+
+        // Normalized search titles of every game, matched against each folder name
+        let games: Vec<(i64, String, Option<String>)> = sqlx::query_as(
+            "SELECT id, title, search_title FROM games"
+        )
+        .fetch_all(&self.db)
+        .await?;
+        let candidates: Vec<(i64, String)> = games.into_iter()
+            .map(|(id, title, search_title)| {
+                let search = search_title.unwrap_or_else(|| db::clean_search_title(&title));
+                (id, normalize_title(&search))
+            })
+            .collect();
 
         while let Some(entry) = entries.next_entry().await? {
             let path = entry.path();
@@ -523,6 +779,11 @@ impl DownloadManager {
                 None => continue,
             };
 
+            // Hidden folders include extractions still in progress
+            if dir_name.starts_with('.') {
+                continue;
+            }
+
             // Check if this directory is already tracked in downloads
             let existing: Option<(i64,)> = sqlx::query_as(
                 "SELECT id FROM downloads WHERE file_path = ?"
@@ -535,17 +796,10 @@ impl DownloadManager {
                 continue; // Already tracked
             }
 
-            // Try to find matching game by title
-            let game_id: Option<i64> = {
-                // Try exact match first
-                let exact: Option<(i64,)> = sqlx::query_as(
-                    "SELECT id FROM games WHERE title LIKE ?"
-                )
-                .bind(format!("%{}%", dir_name))
-                .fetch_optional(&self.db)
-                .await?;
-
-                exact.map(|(id,)| id)
+            let Some(game_id) = match_game_title(&candidates, &dir_name) else {
+                println!("Could not match existing folder to a game: {}", dir_name);
+                result.unmatched.push(dir_name);
+                continue;
             };
 
             // Look for installer
@@ -553,11 +807,11 @@ impl DownloadManager {
 
             // Create download record
             let now = chrono::Utc::now().to_rfc3339();
-            let result = sqlx::query(
+            sqlx::query(
                 "INSERT INTO downloads (game_id, status, progress, file_path, installer_path, created_at, completed_at)
                  VALUES (?, 'completed', 100.0, ?, ?, ?, ?)"
             )
-            .bind(game_id.unwrap_or(-1)) // Use -1 for unknown games
+            .bind(game_id)
             .bind(path.to_string_lossy().as_ref())
             .bind(installer_path.as_ref().map(|p| p.to_string_lossy().to_string()))
             .bind(&now)
@@ -566,10 +820,11 @@ impl DownloadManager {
             .await?;
 
             println!("Imported existing game: {}", dir_name);
-            imported += 1;
+            result.imported += 1;
         }
 
-        Ok(imported)
+        result.unmatched.sort();
+        Ok(result)
     }
 
     /// Permanently delete a download and its files from disk
@@ -603,6 +858,8 @@ impl DownloadManager {
             }
         }
 
+        self.downloader.remove_partials(download_id).await;
+
         // Delete download files records
         sqlx::query("DELETE FROM download_files WHERE download_id = ?")
             .bind(download_id)
@@ -619,40 +876,167 @@ impl DownloadManager {
     }
 }
 
+/// Spawn queue workers while there are free download slots
+fn spawn_workers(ctx: &WorkerContext) {
+    while let Ok(permit) = ctx.workers.clone().try_acquire_owned() {
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            // Hold the permit for the lifetime of this worker
+            let _permit = permit;
+            let WorkerContext { db, downloader, events, notifier, .. } = &ctx;
+
+            loop {
+                let Some((download_id, game_id)) = claim_next_queued(db).await else {
+                    break;
+                };
+                events.download_changed(download_id).await;
+
+                // Process this download
+                if let Err(e) = process_download(&ctx, download_id, game_id).await {
+                    eprintln!("Download {} failed: {}", download_id, e);
+                    let _ = update_download_status(db, download_id, "failed", Some(&e.to_string())).await;
+                    debrid::forget_cached_links(db, download_id).await;
+                    events.download_changed(download_id).await;
+
+                    let title = db::get_game_by_id(db, game_id).await
+                        .map(|g| g.title)
+                        .unwrap_or_else(|_| format!("Download {}", download_id));
+                    notify_download_users(
+                        db,
+                        notifier,
+                        download_id,
+                        "download_error",
+                        "Download Failed",
+                        &format!("{} failed to download: {}", title, e),
+                    ).await;
+                }
+
+                // Clear downloader progress for this download
+                downloader.clear_progress(download_id).await;
+            }
+        });
+    }
+}
+
+/// Resolve a magnet/DDL through the debrid provider, mirroring the provider's
+/// own torrent progress into the downloads row while an uncached torrent is fetched.
+/// The worker's download slot is given up for as long as the provider is fetching.
+async fn resolve_links(
+    ctx: &WorkerContext,
+    debrid: &dyn Debrid,
+    link: &str,
+    options: &debrid::LinkOptions,
+    download_id: i64,
+) -> Result<Vec<crate::realdebrid::DownloadLink>, Box<dyn std::error::Error + Send + Sync>> {
+    let db = &ctx.db;
+    let (tx, mut rx) = tokio::sync::watch::channel(None::<debrid::DebridProgress>);
+    let mut fetching = tx.subscribe();
+    let label = debrid.provider_label();
+
+    let writer_db = db.clone();
+    let writer = tokio::spawn(async move {
+        while rx.changed().await.is_ok() {
+            let Some(p) = rx.borrow_and_update().clone() else { continue };
+            let _ = sqlx::query(
+                "UPDATE downloads SET debrid_status = ?, progress = ?, download_speed = ? WHERE id = ?"
+            )
+            .bind(format!("{}: {}", label, p.status))
+            .bind(p.progress)
+            .bind(p.speed.map(|s| format_speed(s as f64)))
+            .bind(download_id)
+            .execute(&writer_db)
+            .await;
+        }
+    });
+
+    let result = {
+        let report = |p| { tx.send_replace(Some(p)); };
+        let resolve = debrid::process_link_cached(db, debrid, link, options, &report);
+        tokio::pin!(resolve);
+
+        // Resolves once the provider reports it is still fetching the torrent itself
+        let provider_fetching = async {
+            while fetching.changed().await.is_ok() {
+                let status = fetching.borrow_and_update().as_ref().map(|p| p.status.clone());
+                if matches!(status.as_deref(), Some("queued" | "downloading")) {
+                    return;
+                }
+            }
+            std::future::pending().await
+        };
+
+        tokio::select! {
+            result = &mut resolve => result,
+            _ = provider_fetching => {
+                // An uncached torrent can take hours on the provider's side. Lend this
+                // worker's slot to the next queued download meanwhile and take one back
+                // once the links are ready.
+                ctx.workers.add_permits(1);
+                spawn_workers(ctx);
+                let result = resolve.await;
+
+                let _ = sqlx::query("UPDATE downloads SET debrid_status = 'Waiting for a free download slot' WHERE id = ?")
+                    .bind(download_id)
+                    .execute(db)
+                    .await;
+                ctx.events.download_changed(download_id).await;
+                if let Ok(permit) = ctx.workers.acquire().await {
+                    permit.forget();
+                }
+                result
+            }
+        }
+    };
+
+    // Closing the channel stops the writer; then clear the provider status
+    drop(tx);
+    let _ = writer.await;
+    let _ = sqlx::query(
+        "UPDATE downloads SET debrid_status = NULL, progress = 0.0, download_speed = NULL WHERE id = ?"
+    )
+    .bind(download_id)
+    .execute(db)
+    .await;
+
+    result
+}
+
 /// Process a single download: RD → download files → extract
 async fn process_download(
-    db: &SqlitePool,
-    downloader: &Downloader,
-    extractor: &Extractor,
-    rd_client: &RealDebridClient,
-    config: &DownloadManagerConfig,
+    ctx: &WorkerContext,
     download_id: i64,
     game_id: i64,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let WorkerContext { db, downloader, extractor, debrid, config, events, notifier, .. } = ctx;
     let game = db::get_game_by_id(db, game_id).await?;
     println!("Processing download {} for '{}'", download_id, game.title);
 
-    // Update status to downloading
-    update_download_status(db, download_id, "downloading", None).await?;
+    // Step 0: Make sure the download (and extraction) will fit on disk
+    check_disk_space(db, notifier, downloader.download_dir(), &game, config, download_id).await?;
 
-    // Step 1: Process magnet through Real-Debrid
-    // Get API key from database (takes priority over env var)
-    let api_key = db::get_setting(db, "rd_api_key").await.ok().flatten()
-        .filter(|k| !k.is_empty())
-        .ok_or("Real-Debrid API key not set. Please configure it in Settings.")?;
-    
-    // Create RD client with fresh API key from database
-    let rd_client = crate::realdebrid::RealDebridClient::new(api_key);
+    // Step 1: Process magnet through the configured debrid provider
+    // (API key from database settings takes priority over env var)
+    let debrid = debrid::from_settings(db, debrid).await?;
 
-    println!("  Processing download link with Real-Debrid...");
-    let rd_downloads = rd_client.process_link(&game.magnet_link).await
-        .map_err(|e| format!("Real-Debrid error: {}", e))?;
+    println!("  Processing download link with {}...", debrid.provider_label());
+    let skip_junk: Option<bool> = sqlx::query_scalar("SELECT skip_junk FROM downloads WHERE id = ?")
+        .bind(download_id)
+        .fetch_one(db)
+        .await?;
+    let options = debrid::LinkOptions {
+        skip_junk: skip_junk.unwrap_or(false),
+        link_type: Some(game.link_type),
+        ..debrid::LinkOptions::default()
+    };
+
+    let rd_downloads = resolve_links(ctx, debrid.as_ref(), &game.magnet_link, &options, download_id).await
+        .map_err(|e| format!("{} error: {}", debrid.provider_label(), e))?;
 
     if rd_downloads.is_empty() {
-        return Err("No download links from Real-Debrid".into());
+        return Err(format!("No download links from {}", debrid.provider_label()).into());
     }
 
-    println!("  Got {} download links from Real-Debrid", rd_downloads.len());
+    println!("  Got {} download links from {}", rd_downloads.len(), debrid.provider_label());
 
     // Create a subdirectory for this game
     let safe_title = sanitize_filename(&game.title);
@@ -678,8 +1062,28 @@ async fn process_download(
 
         let _file_id = file_result.last_insert_rowid();
 
-        // Download the file
-        match downloader.download_file(&dl.download_url, &dl.filename, download_id).await {
+        // Download the file, retrying transient failures with backoff
+        let mut attempt = 0;
+        let result = loop {
+            match downloader.download_file(&dl.download_url, &dl.filename, download_id).await {
+                Err(e) if attempt < MAX_DOWNLOAD_RETRIES && crate::downloader::is_transient_error(e.as_ref()) => {
+                    attempt += 1;
+                    let delay_secs = 5u64 * (1 << (attempt - 1));
+                    eprintln!(
+                        "  Retry {}/{} for {} in {}s: {}",
+                        attempt, MAX_DOWNLOAD_RETRIES, dl.filename, delay_secs, e
+                    );
+                    let _ = sqlx::query("UPDATE downloads SET retry_count = COALESCE(retry_count, 0) + 1 WHERE id = ?")
+                        .bind(download_id)
+                        .execute(db)
+                        .await;
+                    tokio::time::sleep(std::time::Duration::from_secs(delay_secs)).await;
+                }
+                other => break other,
+            }
+        };
+
+        match result {
             Ok(path) => {
                 // Update file size
                 if let Ok(metadata) = tokio::fs::metadata(&path).await {
@@ -724,41 +1128,27 @@ async fn process_download(
         }
     }
 
+    // Every file has been moved into the game folder
+    downloader.remove_partials(download_id).await;
+
     // Step 3: Extract archives if enabled
     if config.auto_extract {
-        let archives: Vec<_> = downloaded_files.iter()
-            .filter(|f| crate::extractor::Extractor::is_archive(f))
-            .cloned()
-            .collect();
+        // Multi-volume archives are extracted once, from their first volume
+        let archive_sets = crate::extractor::Extractor::group_archive_sets(&downloaded_files);
 
-        if !archives.is_empty() {
+        if !archive_sets.is_empty() {
             update_download_status(db, download_id, "extracting", None).await?;
-            println!("  Extracting {} archive(s)...", archives.len());
-
-            for archive in &archives {
-                match extractor.extract_archive(archive, &game_dir, download_id).await {
-                    Ok(extracted) => {
-                        println!("  Extracted {} files from {}", extracted.len(), archive.display());
-
-                        // Mark file as extracted
-                        let fname = archive.file_name().unwrap_or_default().to_string_lossy();
-                        let _ = sqlx::query(
-                            "UPDATE download_files SET is_extracted = 1 WHERE download_id = ? AND filename = ?"
-                        )
-                        .bind(download_id)
-                        .bind(fname.as_ref())
-                        .execute(db)
-                        .await;
-                    }
-                    Err(e) => {
-                        eprintln!("  Warning: Failed to extract {}: {}", archive.display(), e);
-                        // Don't fail the whole download for extraction errors
-                    }
-                }
-            }
+            events.download_changed(download_id).await;
+            println!("  Extracting {} archive(s)...", archive_sets.len());
+
+            let password = db::get_archive_password(db, game_id).await.ok().flatten();
 
-            // Clear extraction progress
-            extractor.clear_progress(download_id).await;
+            // Fail now rather than running out of space half-way through an archive
+            check_extract_space(&archive_sets, &game_dir, password.as_deref()).await?;
+
+            // Don't fail the whole download for extraction errors other than a missing
+            // password, a corrupt or incomplete archive, or a disk error
+            extract_archive_sets(db, extractor, download_id, &archive_sets, &game_dir, password.as_deref()).await?;
 
             // Validate extraction: check if any .exe files were extracted
             println!("  Validating extraction...");
@@ -778,8 +1168,8 @@ async fn process_download(
 
             // Delete archives after extraction if configured
             if config.delete_archives {
-                for archive in &archives {
-                    let _ = tokio::fs::remove_file(archive).await;
+                for volume in archive_sets.iter().flat_map(|set| &set.volumes) {
+                    let _ = tokio::fs::remove_file(volume).await;
                 }
             }
         }
@@ -804,9 +1194,307 @@ async fn process_download(
     .await?;
 
     println!("Download {} completed: '{}'", download_id, game.title);
+    events.download_changed(download_id).await;
+
+    notify_download_users(
+        db,
+        notifier,
+        download_id,
+        "download_complete",
+        "Download Complete",
+        &format!("{} has finished downloading and is ready to install!", game.title),
+    ).await;
+    crate::notifications::send_discord(
+        db,
+        "Download Complete",
+        &format!("{} has finished downloading and is ready to install!", game.title),
+        crate::notifications::COLOR_DOWNLOAD_COMPLETE,
+    );
+    crate::notifications::send_download_complete(db, download_id);
+
+    // Step 6: Validate checksums in the background if the repack ships a checksum file
+    if crate::md5_validator::find_checksum_file(&game_dir).await.is_some() {
+        let _ = set_md5_status(db, download_id, "pending").await;
+        let db = db.clone();
+        tokio::spawn(async move {
+            run_md5_validation(&db, download_id, &game_dir).await;
+        });
+    }
+
+    Ok(())
+}
+
+/// Extract each archive set into `game_dir`, along with any archives nested
+/// inside it, marking its volumes as extracted. Returns how many sets failed;
+/// a missing or wrong password stops early with an error so the UI can
+/// prompt for it, and so do corrupt or incomplete archives and disk errors,
+/// so the user learns whether to re-download or re-extract.
+async fn extract_archive_sets(
+    db: &SqlitePool,
+    extractor: &Extractor,
+    download_id: i64,
+    archive_sets: &[crate::extractor::ArchiveSet],
+    game_dir: &std::path::Path,
+    password: Option<&str>,
+) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+    let mut failed = 0;
+    let mut fatal_error = None;
+
+    for set in archive_sets {
+        match extractor.extract_set(set, game_dir, download_id, password).await {
+            Ok(extracted) => {
+                println!(
+                    "  Extracted {} files from {} ({} volume(s))",
+                    extracted.len(), set.primary.display(), set.volumes.len()
+                );
+
+                // Mark every volume of the set as extracted
+                for volume in &set.volumes {
+                    let fname = volume.file_name().unwrap_or_default().to_string_lossy();
+                    let _ = sqlx::query(
+                        "UPDATE download_files SET is_extracted = 1 WHERE download_id = ? AND filename = ?"
+                    )
+                    .bind(download_id)
+                    .bind(fname.as_ref())
+                    .execute(db)
+                    .await;
+                }
+            }
+            Err(e) if e.downcast_ref::<crate::extractor::PasswordRequired>().is_some() => {
+                fatal_error = Some(e.to_string());
+                break;
+            }
+            Err(e) if e.downcast_ref::<crate::extractor::ExtractionFailed>()
+                .is_some_and(|f| f.kind != crate::extractor::FailureKind::Other) =>
+            {
+                eprintln!("  Extraction of {} failed: {}", set.primary.display(), e);
+                fatal_error = Some(e.to_string());
+                break;
+            }
+            Err(e) => {
+                eprintln!("  Warning: Failed to extract {}: {}", set.primary.display(), e);
+                failed += 1;
+            }
+        }
+    }
+
+    // Clear extraction progress
+    extractor.clear_progress(download_id).await;
+
+    match fatal_error {
+        Some(err) => Err(err.into()),
+        None => Ok(failed),
+    }
+}
+
+/// Re-run extraction over the archives left in `game_dir`, then mark the
+/// download completed (or failed) the same way `process_download` does
+async fn run_reextract(
+    db: &SqlitePool,
+    extractor: &Extractor,
+    config: &DownloadManagerConfig,
+    download_id: i64,
+    game_id: i64,
+    game_dir: &std::path::Path,
+    archive_sets: &[crate::extractor::ArchiveSet],
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let password = db::get_archive_password(db, game_id).await.ok().flatten();
+    check_extract_space(archive_sets, game_dir, password.as_deref()).await?;
+
+    let failed = extract_archive_sets(db, extractor, download_id, archive_sets, game_dir, password.as_deref()).await?;
+    if failed == archive_sets.len() {
+        return Err(format!("Failed to extract all {} archive(s)", failed).into());
+    }
+
+    if config.delete_archives && failed == 0 {
+        for volume in archive_sets.iter().flat_map(|set| &set.volumes) {
+            let _ = tokio::fs::remove_file(volume).await;
+        }
+    }
+
+    let installer_path = find_installer(game_dir).await;
+    sqlx::query(
+        "UPDATE downloads SET status = 'completed', error_message = NULL, installer_path = ?, completed_at = ? WHERE id = ?"
+    )
+    .bind(installer_path.as_ref().map(|p| p.to_string_lossy().to_string()))
+    .bind(chrono::Utc::now().to_rfc3339())
+    .bind(download_id)
+    .execute(db)
+    .await?;
+
     Ok(())
 }
 
+/// Validate a finished download against its MD5 file and record the outcome
+async fn run_md5_validation(db: &SqlitePool, download_id: i64, dir: &std::path::Path) {
+    println!("  Validating MD5 checksums for download {}...", download_id);
+    let status = match crate::md5_validator::validate_directory(dir).await {
+        Ok(result) => {
+            println!("  MD5 validation for download {}: {}", download_id, result.status);
+            result.md5_status()
+        }
+        Err(e) => {
+            eprintln!("  MD5 validation for download {} failed: {}", download_id, e);
+            "failed".to_string()
+        }
+    };
+    let _ = set_md5_status(db, download_id, &status).await;
+}
+
+/// Store the MD5 validation status for a download
+pub async fn set_md5_status(db: &SqlitePool, download_id: i64, status: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE downloads SET md5_status = ? WHERE id = ?")
+        .bind(status)
+        .bind(download_id)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+/// Atomically move the oldest queued download to 'downloading' so that
+/// concurrent workers never pick up the same row.
+async fn claim_next_queued(db: &SqlitePool) -> Option<(i64, i64)> {
+    sqlx::query_as(
+        "UPDATE downloads SET status = 'downloading'
+         WHERE id = (SELECT id FROM downloads WHERE status = 'queued' ORDER BY created_at ASC, id ASC LIMIT 1)
+           AND status = 'queued'
+         RETURNING id, game_id"
+    )
+    .fetch_optional(db)
+    .await
+    .unwrap_or(None)
+}
+
+/// Fail fast if the download directory can't hold the game's archives,
+/// plus room for extraction when auto-extract is enabled.
+async fn check_disk_space(
+    db: &SqlitePool,
+    notifier: &Notifier,
+    download_dir: &std::path::Path,
+    game: &db::Game,
+    config: &DownloadManagerConfig,
+    download_id: i64,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let Some(size_gb) = crate::installation_checker::parse_size_to_gb(&game.file_size) else {
+        return Ok(()); // Unknown size, nothing to check against
+    };
+
+    tokio::fs::create_dir_all(download_dir).await?;
+    let dir = download_dir.to_path_buf();
+    let free_gb = tokio::task::spawn_blocking(move || crate::system_info::get_free_space_gb(&dir))
+        .await
+        .ok()
+        .flatten();
+    let Some(free_gb) = free_gb else {
+        return Ok(()); // Can't determine free space, don't block the download
+    };
+
+    // Extracted repacks take roughly as much space again as the archives
+    let needed_gb = if config.auto_extract { size_gb * 2.0 } else { size_gb } + 1.0;
+
+    if free_gb < needed_gb {
+        return Err(format!(
+            "Not enough disk space: {:.1} GB free, {:.1} GB needed for '{}'",
+            free_gb, needed_gb, game.title
+        ).into());
+    }
+
+    if free_gb < needed_gb * 1.5 {
+        notify_download_users(
+            db,
+            notifier,
+            download_id,
+            "disk_space_warning",
+            "Low Disk Space",
+            &format!(
+                "Only {:.1} GB free after downloading {} ({:.1} GB needed)",
+                free_gb - needed_gb, game.title, needed_gb
+            ),
+        ).await;
+    }
+
+    Ok(())
+}
+
+/// Make sure the extracted contents of `archive_sets` fit in `dest_dir`.
+/// Uses the archives' entry sizes where they can be listed, else the archive sizes.
+async fn check_extract_space(
+    archive_sets: &[crate::extractor::ArchiveSet],
+    dest_dir: &std::path::Path,
+    password: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut needed_bytes: u64 = 0;
+    for set in archive_sets {
+        needed_bytes += match crate::extractor::Extractor::estimate_extracted_size(set, password).await {
+            Some(size) => size,
+            None => {
+                let mut archive_bytes = 0;
+                for volume in &set.volumes {
+                    archive_bytes += tokio::fs::metadata(volume).await.map(|m| m.len()).unwrap_or(0);
+                }
+                archive_bytes
+            }
+        };
+    }
+
+    let dir = dest_dir.to_path_buf();
+    let free_gb = tokio::task::spawn_blocking(move || crate::system_info::get_free_space_gb(&dir))
+        .await
+        .ok()
+        .flatten();
+    let Some(free_gb) = free_gb else {
+        return Ok(()); // Can't determine free space, don't block extraction
+    };
+
+    // Leave some headroom for the filesystem and temp files
+    let needed_gb = needed_bytes as f64 / 1024.0 / 1024.0 / 1024.0 + 0.5;
+    if free_gb < needed_gb {
+        return Err(format!(
+            "Not enough disk space to extract: {:.1} GB free, {:.1} GB needed in {}",
+            free_gb, needed_gb, dest_dir.display()
+        ).into());
+    }
+
+    Ok(())
+}
+
+/// Notify the users who own a download (or admins, for unowned downloads)
+/// that want notifications of this type.
+async fn notify_download_users(
+    db: &SqlitePool,
+    notifier: &Notifier,
+    download_id: i64,
+    notification_type: &str,
+    title: &str,
+    message: &str,
+) {
+    let mut user_ids: Vec<(i64,)> = sqlx::query_as(
+        "SELECT user_id FROM downloads WHERE id = ? AND user_id IS NOT NULL
+         UNION
+         SELECT user_id FROM user_downloads WHERE download_id = ?"
+    )
+    .bind(download_id)
+    .bind(download_id)
+    .fetch_all(db)
+    .await
+    .unwrap_or_default();
+
+    if user_ids.is_empty() {
+        user_ids = sqlx::query_as("SELECT id FROM users WHERE is_admin = 1")
+            .fetch_all(db)
+            .await
+            .unwrap_or_default();
+    }
+
+    for (user_id,) in user_ids {
+        let enabled = db::get_user_settings(db, user_id).await
+            .map_or(true, |s| s.wants(notification_type));
+        if enabled {
+            notifier.notify_user(user_id, notification_type, title, message).await;
+        }
+    }
+}
+
 async fn update_download_status(
     db: &SqlitePool,
     download_id: i64,
@@ -830,6 +1518,95 @@ async fn update_download_status(
     Ok(())
 }
 
+/// Move a directory, falling back to copy+delete when rename fails (e.g. across devices)
+async fn move_dir(source: &std::path::Path, dest: &std::path::Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if let Err(e) = tokio::fs::rename(source, dest).await {
+        println!("Rename failed ({}), copying {} instead", e, source.display());
+        let (from, to) = (source.to_path_buf(), dest.to_path_buf());
+        tokio::task::spawn_blocking(move || {
+            copy_dir_recursive(&from, &to).inspect_err(|_| {
+                let _ = std::fs::remove_dir_all(&to);
+            })
+        })
+            .await?
+            .map_err(|e2| format!("Failed to move folder: rename={}, copy={}", e, e2))?;
+        tokio::fs::remove_dir_all(source).await?;
+    }
+    Ok(())
+}
+
+fn copy_dir_recursive(source: &std::path::Path, dest: &std::path::Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dest)?;
+    for entry in std::fs::read_dir(source)? {
+        let entry = entry?;
+        let target = dest.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_recursive(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Minimum similarity for a fuzzy folder → game title match
+const MIN_TITLE_SIMILARITY: f64 = 0.85;
+
+/// Lowercase a title and collapse punctuation to single spaces, so
+/// "Foo_ Bar's Tale" and "Foo: Bar's Tale" compare equal
+fn normalize_title(title: &str) -> String {
+    title.chars()
+        .map(|c| if c.is_alphanumeric() { c.to_ascii_lowercase() } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Similarity of two strings as 1 - (edit distance / longer length)
+fn title_similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut row = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != cb);
+            row[j + 1] = substitution.min(prev[j + 1] + 1).min(row[j] + 1);
+        }
+        prev = row;
+    }
+
+    1.0 - prev[b.len()] as f64 / longest as f64
+}
+
+/// Game whose search title matches a download folder name: exact after
+/// normalization first, otherwise the closest fuzzy match above the threshold
+fn match_game_title(candidates: &[(i64, String)], dir_name: &str) -> Option<i64> {
+    let wanted = normalize_title(&db::clean_search_title(dir_name));
+    if wanted.is_empty() {
+        return None;
+    }
+
+    if let Some((id, _)) = candidates.iter().find(|(_, title)| *title == wanted) {
+        return Some(*id);
+    }
+
+    // Edit distance is quadratic, so only score titles sharing the first word
+    let first_word = wanted.split(' ').next();
+    candidates.iter()
+        .filter(|(_, title)| title.split(' ').next() == first_word)
+        .map(|(id, title)| (*id, title_similarity(&wanted, title)))
+        .filter(|(_, score)| *score >= MIN_TITLE_SIMILARITY)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(id, _)| id)
+}
+
 /// Sanitize a string for use as a directory name
 fn sanitize_filename(name: &str) -> String {
     name.chars()
@@ -984,3 +1761,46 @@ fn format_eta(seconds: f64) -> String {
         format!("{}s", secs)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_title() {
+        assert_eq!(normalize_title("Foo_ Bar's Tale"), "foo bar s tale");
+        assert_eq!(normalize_title("Foo: Bar's Tale"), "foo bar s tale");
+        assert_eq!(normalize_title("  HITMAN   3 "), "hitman 3");
+        assert_eq!(normalize_title("---"), "");
+    }
+
+    #[test]
+    fn test_title_similarity() {
+        assert_eq!(title_similarity("", ""), 1.0);
+        assert_eq!(title_similarity("abc", "abc"), 1.0);
+        assert_eq!(title_similarity("abc", ""), 0.0);
+        // One edit in ten characters
+        assert!((title_similarity("hollow kni", "hollow kny") - 0.9).abs() < 1e-9);
+        assert!(title_similarity("elden ring", "hollow knight") < MIN_TITLE_SIMILARITY);
+    }
+
+    #[test]
+    fn test_match_game_title() {
+        let candidates = vec![
+            (1, "the witcher 3 wild hunt".to_string()),
+            (2, "hollow knight".to_string()),
+            (3, "hollow knight silksong".to_string()),
+            (4, "elden ring".to_string()),
+        ];
+
+        // Exact after cleaning the repack suffix and normalizing
+        assert_eq!(match_game_title(&candidates, "Hollow Knight - v1.5.78 + DLC"), Some(2));
+        assert_eq!(match_game_title(&candidates, "The Witcher 3: Wild Hunt"), Some(1));
+        // Close enough to the nearest title
+        assert_eq!(match_game_title(&candidates, "Hollow Knight Silksog"), Some(3));
+        // Too far from anything, or not sharing a first word
+        assert_eq!(match_game_title(&candidates, "Hades"), None);
+        assert_eq!(match_game_title(&candidates, "Eldn Ring"), None);
+        assert_eq!(match_game_title(&candidates, "..."), None);
+    }
+}
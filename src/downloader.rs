@@ -5,7 +5,10 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
+
+/// Folder under the download directory holding files still being downloaded
+const PARTIAL_DIR: &str = ".partial";
 
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct DownloadProgress {
@@ -24,15 +27,86 @@ pub enum DownloadStatus {
     Cancelled,
 }
 
+/// Non-success HTTP status returned by the download server
+#[derive(Debug)]
+pub struct HttpStatusError(pub reqwest::StatusCode);
+
+impl std::fmt::Display for HttpStatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HTTP error: {}", self.0)
+    }
+}
+
+impl std::error::Error for HttpStatusError {}
+
+/// Whether a download error is worth retrying (network blips, timeouts,
+/// server-side 5xx/429) as opposed to permanent failures like 404 or auth errors.
+pub fn is_transient_error(err: &(dyn std::error::Error + Send + Sync + 'static)) -> bool {
+    if let Some(HttpStatusError(status)) = err.downcast_ref::<HttpStatusError>() {
+        return status.is_server_error()
+            || *status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            || *status == reqwest::StatusCode::REQUEST_TIMEOUT;
+    }
+    if let Some(e) = err.downcast_ref::<reqwest::Error>() {
+        if let Some(status) = e.status() {
+            return status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS;
+        }
+        return e.is_timeout() || e.is_connect() || e.is_body() || e.is_request() || e.is_decode();
+    }
+    false
+}
+
+/// Shared token bucket that caps the aggregate throughput of all downloads.
+/// Tokens are bytes; the bucket may go into debt for large chunks, in which
+/// case the caller sleeps until the debt is repaid.
+struct RateLimiter {
+    bytes_per_sec: f64,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl RateLimiter {
+    fn new(bytes_per_sec: f64) -> Self {
+        Self {
+            bytes_per_sec,
+            state: Mutex::new((bytes_per_sec, Instant::now())),
+        }
+    }
+
+    /// Consume `bytes` tokens, sleeping if the bucket is empty
+    async fn acquire(&self, bytes: usize) {
+        let wait = {
+            let mut state = self.state.lock().await;
+            let (tokens, last_refill) = &mut *state;
+            let now = Instant::now();
+            let refill = now.duration_since(*last_refill).as_secs_f64() * self.bytes_per_sec;
+            *tokens = (*tokens + refill).min(self.bytes_per_sec);
+            *last_refill = now;
+            *tokens -= bytes as f64;
+            if *tokens < 0.0 {
+                Duration::from_secs_f64(-*tokens / self.bytes_per_sec)
+            } else {
+                Duration::ZERO
+            }
+        };
+
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
 pub struct Downloader {
     download_dir: PathBuf,
     active_downloads: Arc<RwLock<HashMap<i64, DownloadProgress>>>,
     cancelled: Arc<RwLock<std::collections::HashSet<i64>>>,
     client: Client,
+    rate_limiter: Option<RateLimiter>,
 }
 
 impl Downloader {
-    pub fn new(download_dir: PathBuf) -> Self {
+    /// Create a downloader. `rate_limit_mbps` caps the combined speed of all
+    /// downloads in megabits per second; 0 means unlimited.
+    pub fn new(download_dir: PathBuf, rate_limit_mbps: f64) -> Self {
         let client = Client::builder()
             .timeout(Duration::from_secs(3600)) // 1 hour timeout for large files
             .connect_timeout(Duration::from_secs(30))
@@ -44,6 +118,11 @@ impl Downloader {
             active_downloads: Arc::new(RwLock::new(HashMap::new())),
             cancelled: Arc::new(RwLock::new(std::collections::HashSet::new())),
             client,
+            rate_limiter: if rate_limit_mbps > 0.0 {
+                Some(RateLimiter::new(rate_limit_mbps * 1_000_000.0 / 8.0))
+            } else {
+                None
+            },
         }
     }
 
@@ -52,33 +131,92 @@ impl Downloader {
         &self.download_dir
     }
 
+    /// Where a download's file is written until it's complete. Partial files
+    /// are kept per download, since repacks of different games ship the same
+    /// file names and must never resume from or write into each other's.
+    fn partial_path(&self, download_id: i64, filename: &str) -> PathBuf {
+        self.download_dir.join(PARTIAL_DIR).join(download_id.to_string()).join(filename)
+    }
+
+    /// Remove whatever a download left in its partial folder
+    pub async fn remove_partials(&self, download_id: i64) {
+        let dir = self.download_dir.join(PARTIAL_DIR).join(download_id.to_string());
+        match fs::remove_dir_all(&dir).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => eprintln!("Failed to remove partial files in {}: {}", dir.display(), e),
+        }
+    }
+
     /// Download a file from URL to disk with progress tracking.
-    /// Returns the path to the downloaded file.
+    /// Returns the path to the downloaded file, in the download's partial folder.
     pub async fn download_file(
         &self,
         url: &str,
         filename: &str,
         download_id: i64,
     ) -> Result<PathBuf, Box<dyn std::error::Error + Send + Sync>> {
-        // Ensure download directory exists
-        fs::create_dir_all(&self.download_dir).await?;
+        let file_path = self.partial_path(download_id, filename);
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
 
-        let file_path = self.download_dir.join(filename);
+        // Resume from a partial file left behind by a previous attempt at this download
+        let existing_len = match fs::metadata(&file_path).await {
+            Ok(meta) if meta.is_file() => meta.len(),
+            _ => 0,
+        };
 
-        // Start the request
-        let response = self.client.get(url).send().await?;
+        let mut request = self.client.get(url);
+        if existing_len > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", existing_len));
+        }
+        let mut response = request.send().await?;
+
+        // Server rejected the range (e.g. stale or oversized partial file) — start over
+        if existing_len > 0 && response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+            println!("Server rejected resume for {}, restarting download", filename);
+            response = self.client.get(url).send().await?;
+        }
 
         if !response.status().is_success() {
-            return Err(format!("HTTP error: {}", response.status()).into());
+            return Err(HttpStatusError(response.status()).into());
         }
 
-        let total_bytes = response.content_length().unwrap_or(0);
+        // Only append when the server honoured the exact range we asked for
+        let mut resume_from = 0;
+        let mut total_bytes = response.content_length().unwrap_or(0);
+        if response.status() == reqwest::StatusCode::PARTIAL_CONTENT {
+            let range = response
+                .headers()
+                .get(reqwest::header::CONTENT_RANGE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(parse_content_range);
+            match range {
+                Some((start, total)) if start == existing_len => {
+                    resume_from = existing_len;
+                    total_bytes = total.unwrap_or(existing_len + total_bytes);
+                }
+                _ => {
+                    println!("Unexpected Content-Range for {}, restarting download", filename);
+                    response = self.client.get(url).send().await?;
+                    if !response.status().is_success() {
+                        return Err(HttpStatusError(response.status()).into());
+                    }
+                    total_bytes = response.content_length().unwrap_or(0);
+                }
+            }
+        }
+
+        if resume_from > 0 {
+            println!("Resuming {} from byte {}", filename, resume_from);
+        }
 
         // Initialize progress
         {
             let mut active = self.active_downloads.write().await;
             active.insert(download_id, DownloadProgress {
-                bytes_downloaded: 0,
+                bytes_downloaded: resume_from,
                 total_bytes,
                 speed: 0.0,
                 status: DownloadStatus::Downloading,
@@ -86,11 +224,15 @@ impl Downloader {
             });
         }
 
-        // Create or truncate the file
-        let mut file = fs::File::create(&file_path).await?;
+        // Append to the partial file when resuming, otherwise create or truncate
+        let mut file = if resume_from > 0 {
+            fs::OpenOptions::new().append(true).open(&file_path).await?
+        } else {
+            fs::File::create(&file_path).await?
+        };
 
         let mut stream = response.bytes_stream();
-        let mut bytes_downloaded: u64 = 0;
+        let mut bytes_downloaded: u64 = resume_from;
         let start_time = Instant::now();
         let mut last_update = Instant::now();
 
@@ -113,6 +255,9 @@ impl Downloader {
             }
 
             let chunk = chunk_result?;
+            if let Some(ref limiter) = self.rate_limiter {
+                limiter.acquire(chunk.len()).await;
+            }
             file.write_all(&chunk).await?;
             bytes_downloaded += chunk.len() as u64;
 
@@ -120,7 +265,7 @@ impl Downloader {
             if last_update.elapsed() >= Duration::from_millis(250) {
                 let elapsed = start_time.elapsed().as_secs_f64();
                 let speed = if elapsed > 0.0 {
-                    bytes_downloaded as f64 / elapsed
+                    (bytes_downloaded - resume_from) as f64 / elapsed
                 } else {
                     0.0
                 };
@@ -176,5 +321,94 @@ impl Downloader {
         let mut cancelled = self.cancelled.write().await;
         cancelled.remove(&download_id);
     }
+}
 
+/// Parse a `Content-Range: bytes start-end/total` header into (start, total)
+fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let range = value.trim().strip_prefix("bytes ")?;
+    let (span, total) = range.split_once('/')?;
+    let (start, _end) = span.split_once('-')?;
+    let start = start.trim().parse::<u64>().ok()?;
+    let total = total.trim().parse::<u64>().ok();
+    Some((start, total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    /// Serve `body` to one request, returning the request's head
+    async fn serve_once(body: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/setup.exe", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = socket.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&request).to_lowercase()
+        });
+        (url, handle)
+    }
+
+    #[tokio::test]
+    async fn test_partial_from_other_download_not_resumed() {
+        let dir = std::env::temp_dir().join(format!("downloader-test-{}", uuid::Uuid::new_v4()));
+        let downloader = Downloader::new(dir.clone(), 0.0);
+
+        // Another game's download left a partial file with the same name
+        let other = downloader.partial_path(1, "setup.exe");
+        fs::create_dir_all(other.parent().unwrap()).await.unwrap();
+        fs::write(&other, b"stale bytes").await.unwrap();
+
+        let (url, server) = serve_once("fresh").await;
+        let path = downloader.download_file(&url, "setup.exe", 2).await.unwrap();
+        let request = server.await.unwrap();
+
+        assert!(!request.contains("range:"));
+        assert_eq!(fs::read(&path).await.unwrap(), b"fresh");
+        assert_eq!(fs::read(&other).await.unwrap(), b"stale bytes");
+
+        downloader.remove_partials(2).await;
+        assert!(!path.exists());
+        assert!(other.exists());
+
+        let _ = fs::remove_dir_all(&dir).await;
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_same_named_files() {
+        let dir = std::env::temp_dir().join(format!("downloader-test-{}", uuid::Uuid::new_v4()));
+        let downloader = Downloader::new(dir.clone(), 0.0);
+
+        // Two games downloading at once, both shipping fg-01.bin
+        let (url_a, server_a) = serve_once("game a").await;
+        let (url_b, server_b) = serve_once("game b").await;
+        let (a, b) = tokio::join!(
+            downloader.download_file(&url_a, "fg-01.bin", 1),
+            downloader.download_file(&url_b, "fg-01.bin", 2),
+        );
+        server_a.await.unwrap();
+        server_b.await.unwrap();
+
+        let (a, b) = (a.unwrap(), b.unwrap());
+        assert_ne!(a, b);
+        assert_eq!(fs::read(&a).await.unwrap(), b"game a");
+        assert_eq!(fs::read(&b).await.unwrap(), b"game b");
+
+        let _ = fs::remove_dir_all(&dir).await;
+    }
 }
@@ -0,0 +1,70 @@
+//! Email over SMTP. When a mail server is configured, users can add an email
+//! address, confirm it through a link, and opt in to receiving their
+//! notifications by email as well. Addresses are only mailed once verified.
+
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::time::Duration;
+
+use crate::config::{SmtpConfig, SmtpTls};
+
+/// How long a verification link stays valid
+pub const VERIFICATION_TTL_HOURS: i64 = 24;
+
+const SMTP_TIMEOUT: Duration = Duration::from_secs(15);
+
+pub struct Mailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl Mailer {
+    pub fn new(config: &SmtpConfig) -> Result<Self, String> {
+        let from: Mailbox = config.from.parse()
+            .map_err(|e| format!("Invalid SMTP sender {:?}: {}", config.from, e))?;
+
+        let builder = match config.tls {
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host),
+            SmtpTls::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host),
+            SmtpTls::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host)),
+        }
+        .map_err(|e| format!("Invalid SMTP host {:?}: {}", config.host, e))?;
+
+        let mut builder = builder.port(config.port).timeout(Some(SMTP_TIMEOUT));
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        Ok(Self { transport: builder.build(), from })
+    }
+
+    /// Send a plain-text email
+    pub async fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to.parse()?)
+            .subject(subject)
+            .body(body.to_string())?;
+        self.transport.send(message).await?;
+        Ok(())
+    }
+}
+
+/// Whether `email` is an address we can send to
+pub fn is_valid_address(email: &str) -> bool {
+    email.len() <= 254 && email.parse::<lettre::Address>().is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_address() {
+        assert!(is_valid_address("player@example.com"));
+        assert!(!is_valid_address("player"));
+        assert!(!is_valid_address("Player <player@example.com>"));
+        assert!(!is_valid_address(""));
+    }
+}
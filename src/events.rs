@@ -0,0 +1,90 @@
+//! Per-user event channels pushed to browsers over `/api/ws`, so the web UI
+//! doesn't have to poll for notification counts and download state changes.
+
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use tokio::sync::broadcast;
+
+/// Events buffered per user before a slow socket starts missing them
+const CHANNEL_CAPACITY: usize = 32;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UserEvent {
+    /// The user's unread notification count changed
+    NotificationCount { count: i64 },
+    /// A download the user can see moved to a new status
+    DownloadStatus { download_id: i64, status: String },
+}
+
+pub struct EventHub {
+    db: SqlitePool,
+    channels: std::sync::Mutex<HashMap<i64, broadcast::Sender<UserEvent>>>,
+}
+
+impl EventHub {
+    pub fn new(db: SqlitePool) -> Self {
+        Self {
+            db,
+            channels: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Receive events for `user_id` from now on
+    pub fn subscribe(&self, user_id: i64) -> broadcast::Receiver<UserEvent> {
+        let mut channels = self.channels.lock().unwrap();
+        channels
+            .entry(user_id)
+            .or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0)
+            .subscribe()
+    }
+
+    fn publish(&self, user_id: i64, event: UserEvent) {
+        let mut channels = self.channels.lock().unwrap();
+        if let Some(sender) = channels.get(&user_id) {
+            // Drop the channel once every socket for this user has gone away
+            if sender.send(event).is_err() {
+                channels.remove(&user_id);
+            }
+        }
+    }
+
+    /// Push the user's current unread count after notifications were created or read
+    pub async fn notification_count_changed(&self, user_id: i64) {
+        if !self.channels.lock().unwrap().contains_key(&user_id) {
+            return;
+        }
+        if let Ok(count) = crate::db::get_unread_notification_count(&self.db, user_id).await {
+            self.publish(user_id, UserEvent::NotificationCount { count });
+        }
+    }
+
+    /// Push a download's current status to everyone who can see it: its owner,
+    /// users linked to it, and admins (who see every download)
+    pub async fn download_changed(&self, download_id: i64) {
+        let status: Option<(String,)> = sqlx::query_as("SELECT status FROM downloads WHERE id = ?")
+            .bind(download_id)
+            .fetch_optional(&self.db)
+            .await
+            .unwrap_or(None);
+        let Some((status,)) = status else { return };
+
+        let user_ids: Vec<(i64,)> = sqlx::query_as(
+            "SELECT user_id FROM downloads WHERE id = ? AND user_id IS NOT NULL
+             UNION
+             SELECT user_id FROM user_downloads WHERE download_id = ?
+             UNION
+             SELECT id FROM users WHERE is_admin = 1"
+        )
+        .bind(download_id)
+        .bind(download_id)
+        .fetch_all(&self.db)
+        .await
+        .unwrap_or_default();
+
+        for (user_id,) in user_ids {
+            self.publish(user_id, UserEvent::DownloadStatus { download_id, status: status.clone() });
+        }
+    }
+}
@@ -1,10 +1,18 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::sync::RwLock;
 use tokio::fs;
 
+/// Archives inside archives are unpacked at most this many levels deep
+pub const MAX_NESTED_DEPTH: usize = 3;
+
+/// Nested archives may unpack to at most this many times the size of the
+/// archive they came out of, so a zip bomb can't fill the disk. Overridden by
+/// NESTED_ARCHIVE_SIZE_FACTOR.
+pub const DEFAULT_NESTED_SIZE_FACTOR: u64 = 4;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ArchiveType {
     Zip,
@@ -12,10 +20,14 @@ pub enum ArchiveType {
     Rar,
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
 pub struct ExtractionProgress {
+    /// Archive currently being extracted
+    pub archive: String,
     /// Current file being extracted
     pub current_file: String,
+    /// Progress through the current file, 0.0 - 100.0 (None if the extractor can't tell)
+    pub file_percent: Option<f64>,
     /// Number of files extracted so far
     pub files_done: u64,
     /// Total number of files in the archive (0 if unknown)
@@ -35,7 +47,9 @@ pub struct ExtractionProgress {
 impl Default for ExtractionProgress {
     fn default() -> Self {
         Self {
+            archive: String::new(),
             current_file: String::new(),
+            file_percent: None,
             files_done: 0,
             files_total: 0,
             percent: 0.0,
@@ -47,18 +61,108 @@ impl Default for ExtractionProgress {
     }
 }
 
+/// The archive is encrypted and no password, or the wrong one, was supplied
+#[derive(Debug)]
+pub struct PasswordRequired {
+    pub wrong_password: bool,
+}
+
+impl std::fmt::Display for PasswordRequired {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.wrong_password {
+            write!(f, "Password required: the archive password is incorrect")
+        } else {
+            write!(f, "Password required: the archive is encrypted")
+        }
+    }
+}
+
+impl std::error::Error for PasswordRequired {}
+
+/// Why an extraction failed, which decides whether retrying the extraction
+/// can help or the archive has to be downloaded again
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FailureKind {
+    /// Failed a CRC check or the data is damaged
+    Corrupt,
+    /// A volume of a multi-part archive is missing or cut short
+    MissingVolume,
+    /// The extracted files couldn't be written: out of space, permissions, ...
+    Disk,
+    Other,
+}
+
+/// Extraction failed for a reason other than the password
+#[derive(Debug)]
+pub struct ExtractionFailed {
+    pub kind: FailureKind,
+    pub archive: String,
+    pub detail: String,
+}
+
+impl std::fmt::Display for ExtractionFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            FailureKind::Corrupt => write!(f, "Archive corrupt — re-download: {} is damaged ({})", self.archive, self.detail),
+            FailureKind::MissingVolume => write!(f, "Archive incomplete — re-download: {} is missing a part ({})", self.archive, self.detail),
+            FailureKind::Disk => write!(f, "Disk error — free up space or check permissions, then re-extract: {}", self.detail),
+            FailureKind::Other => write!(f, "Failed to extract {}: {}", self.archive, self.detail),
+        }
+    }
+}
+
+impl std::error::Error for ExtractionFailed {}
+
+impl ExtractionFailed {
+    /// Classify an extraction error; password errors are passed through as they are
+    fn wrap(e: Box<dyn std::error::Error + Send + Sync>, archive_path: &Path) -> Box<dyn std::error::Error + Send + Sync> {
+        if e.is::<PasswordRequired>() || e.is::<ExtractionFailed>() {
+            return e;
+        }
+        let io_kind = e.downcast_ref::<std::io::Error>().map(std::io::Error::kind);
+        let kind = match io_kind {
+            Some(std::io::ErrorKind::StorageFull | std::io::ErrorKind::PermissionDenied | std::io::ErrorKind::ReadOnlyFilesystem) => FailureKind::Disk,
+            _ if e.is::<zip::result::ZipError>() => FailureKind::Corrupt,
+            _ => classify_failure(&e.to_string()),
+        };
+        Box::new(ExtractionFailed {
+            kind,
+            archive: archive_path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+            detail: e.to_string(),
+        })
+    }
+}
+
+/// A single archive or all volumes of a multi-volume archive
+#[derive(Debug, Clone)]
+pub struct ArchiveSet {
+    /// The volume to hand to the extractor (first volume of the set)
+    pub primary: PathBuf,
+    /// Every file belonging to the set, in volume order (includes `primary`)
+    pub volumes: Vec<PathBuf>,
+    pub archive_type: ArchiveType,
+}
+
 pub struct Extractor {
     /// Shared progress state keyed by download_id
     progress: Arc<RwLock<HashMap<i64, ExtractionProgress>>>,
+    /// Cap on nested archives' output, as a multiple of the outer set's size
+    nested_size_factor: u64,
 }
 
 impl Extractor {
     pub fn new() -> Self {
         Self {
             progress: Arc::new(RwLock::new(HashMap::new())),
+            nested_size_factor: DEFAULT_NESTED_SIZE_FACTOR,
         }
     }
 
+    pub fn with_nested_size_factor(mut self, factor: u64) -> Self {
+        self.nested_size_factor = factor.max(1);
+        self
+    }
+
     /// Detect archive type from file extension
     pub fn get_archive_type(path: &Path) -> Option<ArchiveType> {
         let ext = path.extension()?.to_str()?.to_lowercase();
@@ -66,13 +170,94 @@ impl Extractor {
             "zip" => Some(ArchiveType::Zip),
             "7z" => Some(ArchiveType::SevenZip),
             "rar" => Some(ArchiveType::Rar),
+            // Old-style RAR continuation volumes: .r00, .r01, ...
+            _ if is_rar_continuation_ext(&ext) => Some(ArchiveType::Rar),
+            // Split archives (.7z.001, .zip.001, ...) are joined by 7-Zip
+            _ if is_split_archive(path) => Some(ArchiveType::SevenZip),
             _ => None,
         }
     }
 
-    /// Check if a file is an archive we can extract
-    pub fn is_archive(path: &Path) -> bool {
-        Self::get_archive_type(path).is_some()
+    /// Group archive files into volume sets (`.7z.001/.002`, `.part1/.part2.rar`,
+    /// `.rar/.r00`), so each set is extracted once from its first volume.
+    /// Non-archive files are ignored. Sets keep the order they first appear in.
+    pub fn group_archive_sets(files: &[PathBuf]) -> Vec<ArchiveSet> {
+        let mut sets: Vec<(String, ArchiveSet)> = Vec::new();
+
+        for file in files {
+            let Some(archive_type) = Self::get_archive_type(file) else { continue };
+            let Some((key, idx)) = volume_info(file) else { continue };
+            let key = file.with_file_name(&key).to_string_lossy().to_string();
+
+            match sets.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, set)) => {
+                    if idx == 0 {
+                        set.primary = file.clone();
+                        set.archive_type = archive_type;
+                    }
+                    set.volumes.push(file.clone());
+                }
+                None => sets.push((key, ArchiveSet {
+                    primary: file.clone(),
+                    volumes: vec![file.clone()],
+                    archive_type,
+                })),
+            }
+        }
+
+        sets.into_iter()
+            .map(|(_, mut set)| {
+                set.volumes.sort_by_key(|v| volume_info(v).map(|(_, idx)| idx).unwrap_or(0));
+                set
+            })
+            .collect()
+    }
+
+    /// Estimate the uncompressed size of an archive set from its entry headers.
+    /// Returns None when sizes can't be read (no 7-Zip, encrypted headers, ...).
+    pub async fn estimate_extracted_size(set: &ArchiveSet, password: Option<&str>) -> Option<u64> {
+        if set.archive_type == ArchiveType::Zip {
+            let path = set.primary.clone();
+            return tokio::task::spawn_blocking(move || {
+                let file = std::fs::File::open(&path).ok()?;
+                let mut archive = zip::ZipArchive::new(file).ok()?;
+                let mut total = 0u64;
+                for i in 0..archive.len() {
+                    total += archive.by_index_raw(i).ok()?.size();
+                }
+                Some(total)
+            })
+            .await
+            .ok()
+            .flatten();
+        }
+
+        // 7z and RAR: technical listing has a "Size = N" line per entry
+        let seven_zip = find_7zip()?;
+        let output = tokio::process::Command::new(&seven_zip)
+            .arg("l")
+            .arg("-slt")
+            .arg(format!("-p{}", password.unwrap_or("")))
+            .arg(set.primary.as_os_str())
+            .output()
+            .await
+            .ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let sizes: Vec<u64> = stdout.lines()
+            .filter_map(|l| l.strip_prefix("Size = "))
+            .filter_map(|v| v.trim().parse().ok())
+            .collect();
+
+        if sizes.is_empty() {
+            None
+        } else {
+            Some(sizes.iter().sum())
+        }
     }
 
     /// Get current extraction progress for a download
@@ -88,37 +273,67 @@ impl Extractor {
     }
 
     /// Extract an archive to the destination directory with progress tracking.
-    /// `download_id` is used to key the progress state.
+    /// `download_id` is used to key the progress state. `password` is used for
+    /// encrypted archives; a missing or wrong one yields a `PasswordRequired` error.
+    /// The archive is unpacked into a staging folder next to `dest_dir` and
+    /// only moved in once it extracted completely, so a failed or interrupted
+    /// extraction never leaves a half-filled folder behind.
     /// Returns a list of extracted file paths.
     pub async fn extract_archive(
         &self,
         archive_path: &Path,
         dest_dir: &Path,
         download_id: i64,
+        password: Option<&str>,
     ) -> Result<Vec<PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
         fs::create_dir_all(dest_dir).await?;
 
         let archive_type = Self::get_archive_type(archive_path)
             .ok_or_else(|| format!("Unknown archive type: {}", archive_path.display()))?;
 
+        // A staging folder that's still around is from a crashed run
+        let staging = staging_dir(dest_dir, download_id);
+        if fs::metadata(&staging).await.is_ok() {
+            fs::remove_dir_all(&staging).await?;
+        }
+        fs::create_dir_all(&staging).await?;
+
         // Initialize progress
         {
             let mut progress = self.progress.write().await;
+            let archive_name = archive_path.file_name().unwrap_or_default().to_string_lossy().to_string();
             progress.insert(download_id, ExtractionProgress {
-                message: format!("Preparing to extract {}...", 
-                    archive_path.file_name().unwrap_or_default().to_string_lossy()),
+                message: format!("Preparing to extract {}...", archive_name),
+                archive: archive_name,
                 ..Default::default()
             });
         }
 
         let result = match archive_type {
             ArchiveType::Zip => {
-                self.extract_zip(archive_path, dest_dir, download_id).await
+                self.extract_zip(archive_path, &staging, download_id, password).await
+            }
+            ArchiveType::SevenZip => {
+                self.extract_with_7zip(archive_path, &staging, download_id, password).await
             }
-            ArchiveType::SevenZip | ArchiveType::Rar => {
-                self.extract_with_7zip(archive_path, dest_dir, download_id).await
+            ArchiveType::Rar => {
+                // Prefer 7-Zip (as the client does); fall back to unrar where
+                // the 7-Zip build lacks the RAR codec or isn't installed
+                if find_7zip().is_some() {
+                    self.extract_with_7zip(archive_path, &staging, download_id, password).await
+                } else {
+                    self.extract_with_unrar(archive_path, &staging, download_id, password).await
+                }
             }
         };
+        let result = match result {
+            Ok(files) => move_into_place(&staging, dest_dir, files).await,
+            Err(e) => Err(e),
+        }
+        .map_err(|e| ExtractionFailed::wrap(e, archive_path));
+        if let Err(e) = fs::remove_dir_all(&staging).await {
+            eprintln!("  Warning: Failed to remove {}: {}", staging.display(), e);
+        }
 
         // Mark extraction complete or failed in progress
         {
@@ -139,18 +354,112 @@ impl Extractor {
         result
     }
 
+    /// Extract an archive set into `dest_dir`, then any archives that came out
+    /// of it, each next to where it landed, down to `MAX_NESTED_DEPTH` levels.
+    /// Only archives at the top of what a level unpacked are opened, and only
+    /// when that level produced no executable, so archives a game ships as
+    /// data are left alone. Fails before nested archives would unpack to more
+    /// than the configured multiple of the set's size. Nested archives are
+    /// kept; only the download's own archives are ever deleted. Returns the
+    /// files the set produced, nested archives' contents included.
+    pub async fn extract_set(
+        &self,
+        set: &ArchiveSet,
+        dest_dir: &Path,
+        download_id: i64,
+        password: Option<&str>,
+    ) -> Result<Vec<PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut produced = self.extract_new_files(&set.primary, dest_dir, download_id, password).await?;
+        let max_bytes = volumes_size(set).await.saturating_mul(self.nested_size_factor);
+        let mut unpacked_bytes = 0u64;
+        // What each extraction produced, with the folder it was extracted into
+        let mut level = vec![(dest_dir.to_path_buf(), produced.clone())];
+
+        for depth in 1.. {
+            let nested: Vec<ArchiveSet> = level
+                .iter()
+                .filter(|(_, files)| !files.iter().any(|f| is_executable(f)))
+                .flat_map(|(root, files)| {
+                    let top: Vec<PathBuf> = files.iter().filter(|f| f.parent() == Some(root.as_path())).cloned().collect();
+                    Self::group_archive_sets(&top)
+                })
+                .collect();
+            if nested.is_empty() {
+                break;
+            }
+            if depth > MAX_NESTED_DEPTH {
+                return Err(format!(
+                    "Archives nested more than {} levels deep in {}; {} was left packed",
+                    MAX_NESTED_DEPTH, set.primary.display(), nested[0].primary.display()
+                ).into());
+            }
+
+            let mut next_level = Vec::new();
+            for inner in nested {
+                let expected = match Self::estimate_extracted_size(&inner, password).await {
+                    Some(size) => size,
+                    None => volumes_size(&inner).await,
+                };
+                if unpacked_bytes.saturating_add(expected) > max_bytes {
+                    return Err(format!(
+                        "Nested archive {} would unpack to more than {}x the size of {}",
+                        inner.primary.display(), self.nested_size_factor, set.primary.display()
+                    ).into());
+                }
+
+                let inner_dir = inner.primary.parent().unwrap_or(dest_dir).to_path_buf();
+                let files = self.extract_new_files(&inner.primary, &inner_dir, download_id, password).await?;
+                for file in &files {
+                    unpacked_bytes += fs::metadata(file).await.map(|m| m.len()).unwrap_or(0);
+                }
+                // Header sizes can lie; check what actually came out too
+                if unpacked_bytes > max_bytes {
+                    return Err(format!(
+                        "Nested archive {} unpacked to more than {}x the size of {}",
+                        inner.primary.display(), self.nested_size_factor, set.primary.display()
+                    ).into());
+                }
+                println!("  Extracted nested archive {} (level {}, {} files)", inner.primary.display(), depth, files.len());
+
+                produced.extend(files.iter().cloned());
+                next_level.push((inner_dir, files));
+            }
+            level = next_level;
+        }
+
+        Ok(produced)
+    }
+
+    /// `extract_archive`, returning only files that weren't in `dest_dir` before
+    async fn extract_new_files(
+        &self,
+        archive_path: &Path,
+        dest_dir: &Path,
+        download_id: i64,
+        password: Option<&str>,
+    ) -> Result<Vec<PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
+        let existing: HashSet<PathBuf> = match dest_dir.is_dir() {
+            true => collect_files(dest_dir).await?.into_iter().collect(),
+            false => HashSet::new(),
+        };
+        let extracted = self.extract_archive(archive_path, dest_dir, download_id, password).await?;
+        Ok(extracted.into_iter().filter(|f| !existing.contains(f)).collect())
+    }
+
     /// Extract ZIP files using the zip crate with per-file progress
     async fn extract_zip(
         &self,
         archive_path: &Path,
         dest_dir: &Path,
         download_id: i64,
+        password: Option<&str>,
     ) -> Result<Vec<PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
         let archive_path = archive_path.to_path_buf();
         let dest_dir = dest_dir.to_path_buf();
         let progress = self.progress.clone();
+        let password = password.map(str::to_string);
 
-        tokio::task::spawn_blocking(move || {
+        tokio::task::spawn_blocking(move || -> Result<Vec<PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
             let file = std::fs::File::open(&archive_path)?;
             let mut archive = zip::ZipArchive::new(file)?;
             let total = archive.len() as u64;
@@ -166,7 +475,18 @@ impl Extractor {
             }
 
             for i in 0..archive.len() {
-                let mut file = archive.by_index(i)?;
+                let mut file = match &password {
+                    Some(pw) => archive.by_index_decrypt(i, pw.as_bytes())?
+                        .map_err(|_| PasswordRequired { wrong_password: true })?,
+                    None => archive.by_index(i).map_err(|e| -> Box<dyn std::error::Error + Send + Sync> {
+                        match e {
+                            zip::result::ZipError::UnsupportedArchive(msg) if msg == zip::result::ZipError::PASSWORD_REQUIRED => {
+                                Box::new(PasswordRequired { wrong_password: false })
+                            }
+                            other => Box::new(other),
+                        }
+                    })?,
+                };
                 let outpath = dest_dir.join(file.mangled_name());
                 let name = file.name().to_string();
 
@@ -177,7 +497,8 @@ impl Extractor {
                         std::fs::create_dir_all(parent)?;
                     }
                     let mut outfile = std::fs::File::create(&outpath)?;
-                    std::io::copy(&mut file, &mut outfile)?;
+                    let size = file.size();
+                    copy_with_file_progress(&mut file, &mut outfile, &progress, download_id, &name, size)?;
                     extracted_files.push(outpath);
                 }
 
@@ -217,6 +538,7 @@ impl Extractor {
         archive_path: &Path,
         dest_dir: &Path,
         download_id: i64,
+        password: Option<&str>,
     ) -> Result<Vec<PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
         let seven_zip = find_7zip().ok_or(
             "7-Zip not found. Please install 7-Zip to extract RAR/7z files. \
@@ -224,9 +546,13 @@ impl Extractor {
         )?;
 
         // First: list the archive to get total file count
+        // Always pass -p so 7-Zip never stops to prompt for a password
+        let password_arg = format!("-p{}", password.unwrap_or(""));
+
         let list_output = tokio::process::Command::new(&seven_zip)
             .arg("l")
             .arg("-ba")  // bare format, no headers
+            .arg(&password_arg)
             .arg(archive_path.as_os_str())
             .output()
             .await?;
@@ -259,6 +585,7 @@ impl Extractor {
             .arg("-y")
             .arg("-bsp1")  // Enable progress output to stdout
             .arg("-bb1")   // Show names of extracted files
+            .arg(&password_arg)
             .arg(archive_path.as_os_str())
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped())
@@ -266,6 +593,7 @@ impl Extractor {
 
         let stdout = child.stdout.take()
             .ok_or("Failed to capture 7-Zip stdout")?;
+        let stderr_handle = read_stderr(&mut child);
 
         let progress = self.progress.clone();
         let parse_handle = tokio::spawn(async move {
@@ -306,6 +634,8 @@ impl Extractor {
                         if let Some(p) = prog.get_mut(&download_id) {
                             p.files_done = files_done;
                             p.current_file = short.clone();
+                            // 7-Zip only reports overall progress
+                            p.file_percent = None;
                             // If we don't have a percentage from 7zip, estimate from file count
                             if total_files > 0 && !updated {
                                 p.percent = (files_done as f64 / total_files as f64) * 100.0;
@@ -325,9 +655,100 @@ impl Extractor {
         let status = child.wait().await?;
         // Wait for the stdout parser to finish
         let _ = parse_handle.await;
+        let stderr = stderr_handle.await.unwrap_or_default();
 
         if !status.success() {
-            return Err("7-Zip extraction failed".into());
+            // 7-Zip reports "Wrong password" both for missing and incorrect passwords
+            if stderr.contains("Wrong password") {
+                return Err(PasswordRequired { wrong_password: password.is_some() }.into());
+            }
+            return Err(format!("7-Zip extraction failed: {}", stderr.trim()).into());
+        }
+
+        let extracted_files = collect_files(dest_dir).await?;
+        Ok(extracted_files)
+    }
+
+    /// Extract RAR files with the `unrar` command-line tool
+    async fn extract_with_unrar(
+        &self,
+        archive_path: &Path,
+        dest_dir: &Path,
+        download_id: i64,
+        password: Option<&str>,
+    ) -> Result<Vec<PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
+        let unrar = find_unrar().ok_or(
+            "Neither 7-Zip nor unrar found. Please install one of them to extract RAR files."
+        )?;
+
+        {
+            let mut progress = self.progress.write().await;
+            if let Some(p) = progress.get_mut(&download_id) {
+                p.message = "Extracting...".to_string();
+            }
+        }
+
+        use tokio::io::AsyncBufReadExt;
+
+        // unrar wants the destination to end with a separator
+        let mut dest = dest_dir.as_os_str().to_os_string();
+        dest.push(std::path::MAIN_SEPARATOR_STR);
+
+        let mut child = tokio::process::Command::new(&unrar)
+            .arg("x")
+            .arg("-o+")  // Overwrite existing files
+            .arg("-y")
+            .arg(password.map(|pw| format!("-p{}", pw)).unwrap_or_else(|| "-p-".to_string()))  // -p- never prompts
+            .arg(archive_path.as_os_str())
+            .arg(&dest)
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()?;
+
+        let stdout = child.stdout.take()
+            .ok_or("Failed to capture unrar stdout")?;
+
+        let progress = self.progress.clone();
+        let parse_handle = tokio::spawn(async move {
+            let reader = tokio::io::BufReader::new(stdout);
+            let mut lines = reader.lines();
+            let mut files_done: u64 = 0;
+
+            // unrar prints lines like "Extracting  Game/file.bin   45%" and "...  OK"
+            while let Ok(Some(line)) = lines.next_line().await {
+                let trimmed = line.trim();
+                let Some(rest) = trimmed.strip_prefix("Extracting  ") else { continue };
+
+                let name = rest.trim_end_matches("OK").trim_end();
+                let name = name.trim_end_matches(|c: char| c.is_ascii_digit() || c == '%' || c == '\u{8}').trim_end();
+                let pct = parse_7zip_percent(rest);
+                if trimmed.ends_with("OK") {
+                    files_done += 1;
+                }
+
+                let short = short_filename(name);
+                let mut prog = progress.write().await;
+                if let Some(p) = prog.get_mut(&download_id) {
+                    if let Some(pct) = pct {
+                        p.percent = pct;
+                    }
+                    p.files_done = files_done;
+                    p.current_file = short.clone();
+                    p.message = format!("Extracting {} — {}", files_done, short);
+                }
+            }
+        });
+
+        let output = child.wait_with_output().await?;
+        let _ = parse_handle.await;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let lower = stderr.to_lowercase();
+            if lower.contains("password") || lower.contains("encrypted") {
+                return Err(PasswordRequired { wrong_password: password.is_some() }.into());
+            }
+            return Err(format!("unrar extraction failed: {}", stderr.trim()).into());
         }
 
         let extracted_files = collect_files(dest_dir).await?;
@@ -335,6 +756,130 @@ impl Extractor {
     }
 }
 
+/// Collect a child's stderr in the background so it can't fill the pipe and stall
+fn read_stderr(child: &mut tokio::process::Child) -> tokio::task::JoinHandle<String> {
+    let stderr = child.stderr.take();
+    tokio::spawn(async move {
+        use tokio::io::AsyncReadExt;
+        let mut buf = String::new();
+        if let Some(mut stderr) = stderr {
+            let _ = stderr.read_to_string(&mut buf).await;
+        }
+        buf
+    })
+}
+
+/// Classify an extraction error from its message, including 7-Zip's and
+/// unrar's error output
+fn classify_failure(message: &str) -> FailureKind {
+    let message = message.to_lowercase();
+    let has = |needles: &[&str]| needles.iter().any(|n| message.contains(n));
+
+    if has(&["not enough space", "no space left", "disk is full", "write error", "can not open output file", "cannot create", "access is denied", "permission denied"]) {
+        FailureKind::Disk
+    } else if has(&["missing volume", "cannot find volume", "unexpected end of archive"]) {
+        FailureKind::MissingVolume
+    } else if has(&["crc failed", "checksum error", "invalid checksum", "data error", "headers error", "corrupt", "is not archive", "can not open the file as archive"]) {
+        FailureKind::Corrupt
+    } else {
+        FailureKind::Other
+    }
+}
+
+/// `r00`..`r999`: continuation volumes of an old-style RAR set
+fn is_rar_continuation_ext(ext: &str) -> bool {
+    ext.len() >= 3
+        && ext.starts_with('r')
+        && ext[1..].chars().all(|c| c.is_ascii_digit())
+}
+
+/// `name.7z.001`, `name.zip.002`, ...: numbered volumes of a split archive
+fn is_split_archive(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("").to_lowercase();
+    match name.rsplit_once('.') {
+        Some((stem, num)) if num.len() == 3 && num.chars().all(|c| c.is_ascii_digit()) => {
+            stem.ends_with(".7z") || stem.ends_with(".zip") || stem.ends_with(".rar")
+        }
+        _ => false,
+    }
+}
+
+/// The set a volume belongs to and its 0-based position within the set.
+/// Single-file archives are a set of one at index 0. Handles `name.7z.001`,
+/// `name.part1.rar` and `name.rar` + `name.r00` naming.
+fn volume_info(path: &Path) -> Option<(String, u32)> {
+    let name = path.file_name()?.to_str()?.to_lowercase();
+    let (stem, ext) = name.rsplit_once('.')?;
+    let all_digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+
+    // name.7z.001 → ("name.7z", 0)
+    if is_split_archive(path) {
+        let n: u32 = ext.parse().ok()?;
+        return Some((stem.to_string(), n.saturating_sub(1)));
+    }
+
+    // name.r00 → ("name", 1), following name.rar
+    if is_rar_continuation_ext(ext) {
+        let n: u32 = ext[1..].parse().ok()?;
+        return Some((stem.to_string(), n + 1));
+    }
+
+    if ext == "rar" {
+        // name.part1.rar → ("name", 0)
+        if let Some((base, num)) = stem.rsplit_once(".part") {
+            if all_digits(num) {
+                let n: u32 = num.parse().ok()?;
+                return Some((base.to_string(), n.saturating_sub(1)));
+            }
+        }
+        return Some((stem.to_string(), 0));
+    }
+
+    Some((name, 0))
+}
+
+/// Copy one ZIP entry to disk, publishing per-file progress every few MB
+fn copy_with_file_progress(
+    reader: &mut impl std::io::Read,
+    writer: &mut impl std::io::Write,
+    progress: &RwLock<HashMap<i64, ExtractionProgress>>,
+    download_id: i64,
+    name: &str,
+    total: u64,
+) -> std::io::Result<()> {
+    const REPORT_EVERY: u64 = 16 * 1024 * 1024;
+
+    let mut buf = vec![0u8; 256 * 1024];
+    let mut copied: u64 = 0;
+    let mut last_report: u64 = 0;
+
+    let set_file = |pct: Option<f64>| {
+        let mut prog = progress.blocking_write();
+        if let Some(p) = prog.get_mut(&download_id) {
+            p.current_file = short_filename(name);
+            p.file_percent = pct;
+        }
+    };
+    set_file(Some(0.0));
+
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        writer.write_all(&buf[..n])?;
+        copied += n as u64;
+
+        if copied - last_report >= REPORT_EVERY {
+            last_report = copied;
+            set_file((total > 0).then(|| (copied as f64 / total as f64 * 100.0).min(100.0)));
+        }
+    }
+
+    set_file(Some(100.0));
+    Ok(())
+}
+
 /// Parse a percentage from 7-Zip output lines like " 45%" or "  0% - file.ext"
 fn parse_7zip_percent(line: &str) -> Option<f64> {
     // Look for a pattern like "XX%" at the start of the line
@@ -408,6 +953,30 @@ fn find_7zip() -> Option<String> {
     None
 }
 
+/// Find the unrar executable
+fn find_unrar() -> Option<String> {
+    #[cfg(target_os = "windows")]
+    {
+        let common_paths = [
+            r"C:\Program Files\WinRAR\UnRAR.exe",
+            r"C:\Program Files (x86)\WinRAR\UnRAR.exe",
+        ];
+
+        for path in &common_paths {
+            if std::path::Path::new(path).exists() {
+                return Some(path.to_string());
+            }
+        }
+    }
+
+    std::process::Command::new("unrar")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .ok()
+        .map(|_| "unrar".to_string())
+}
+
 /// Check if 7z is available in PATH
 fn which_7z() -> Option<()> {
     std::process::Command::new("7z")
@@ -420,6 +989,68 @@ fn which_7z() -> Option<()> {
         .map(|_| ())
 }
 
+/// Where an archive bound for `dest_dir` is unpacked first: a hidden sibling
+/// folder, so the final move is a rename on the same filesystem
+fn staging_dir(dest_dir: &Path, download_id: i64) -> PathBuf {
+    let name = dest_dir.file_name().unwrap_or_default().to_string_lossy();
+    dest_dir.with_file_name(format!(".{}.extracting-{}", name, download_id))
+}
+
+/// Move everything in `staging` into `dest_dir`, and return `files` at their
+/// new paths
+async fn move_into_place(
+    staging: &Path,
+    dest_dir: &Path,
+    files: Vec<PathBuf>,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
+    let (from, to) = (staging.to_path_buf(), dest_dir.to_path_buf());
+    tokio::task::spawn_blocking(move || merge_dir(&from, &to)).await??;
+    Ok(files
+        .into_iter()
+        .map(|file| match file.strip_prefix(staging) {
+            Ok(relative) => dest_dir.join(relative),
+            Err(_) => file,
+        })
+        .collect())
+}
+
+/// Rename the entries of `from` into `to`, merging into folders that already
+/// exist there and replacing files, as extracting over them would
+fn merge_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        match std::fs::symlink_metadata(&target).ok() {
+            Some(meta) if meta.is_dir() && entry.file_type()?.is_dir() => {
+                merge_dir(&entry.path(), &target)?;
+                continue;
+            }
+            Some(meta) if meta.is_dir() => std::fs::remove_dir_all(&target)?,
+            // Windows won't rename over an existing file
+            Some(_) => std::fs::remove_file(&target)?,
+            None => {}
+        }
+        std::fs::rename(entry.path(), &target)?;
+    }
+    Ok(())
+}
+
+/// Installers and game executables; a level that produced one isn't unpacked further
+fn is_executable(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("exe") || e.eq_ignore_ascii_case("msi"))
+}
+
+/// Total size of an archive set's volumes on disk
+async fn volumes_size(set: &ArchiveSet) -> u64 {
+    let mut total = 0;
+    for volume in &set.volumes {
+        total += fs::metadata(volume).await.map(|m| m.len()).unwrap_or(0);
+    }
+    total
+}
+
 /// Recursively collect all files in a directory
 async fn collect_files(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
     let mut files = Vec::new();
@@ -437,3 +1068,132 @@ async fn collect_files(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::E
 
     Ok(files)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_failure() {
+        assert_eq!(classify_failure("7-Zip extraction failed: ERROR: CRC Failed : Game/data1.bin"), FailureKind::Corrupt);
+        assert_eq!(classify_failure("7-Zip extraction failed: ERROR: Data Error : setup-fitgirl-01.bin"), FailureKind::Corrupt);
+        assert_eq!(classify_failure("unrar extraction failed: Game.part1.rar: checksum error in the encrypted file"), FailureKind::Corrupt);
+        assert_eq!(classify_failure("7-Zip extraction failed: ERROR: Missing volume : Game.7z.003"), FailureKind::MissingVolume);
+        assert_eq!(classify_failure("unrar extraction failed: Cannot find volume Game.part3.rar"), FailureKind::MissingVolume);
+        assert_eq!(classify_failure("7-Zip extraction failed: ERROR: There is not enough space on the disk"), FailureKind::Disk);
+        assert_eq!(classify_failure("unrar extraction failed: Write error in the file data1.bin"), FailureKind::Disk);
+        assert_eq!(classify_failure("7-Zip not found"), FailureKind::Other);
+    }
+
+    #[test]
+    fn test_group_archive_sets() {
+        let files: Vec<PathBuf> = [
+            "Game.part2.rar",
+            "Game.part1.rar",
+            "Setup.7z.001",
+            "Setup.7z.002",
+            "Old.r00",
+            "Old.rar",
+            "readme.txt",
+            "Bonus.zip",
+        ].iter().map(PathBuf::from).collect();
+
+        let sets = Extractor::group_archive_sets(&files);
+        let primaries: Vec<_> = sets.iter().map(|s| s.primary.to_str().unwrap()).collect();
+        assert_eq!(primaries, ["Game.part1.rar", "Setup.7z.001", "Old.rar", "Bonus.zip"]);
+        assert_eq!(sets[0].volumes, [PathBuf::from("Game.part1.rar"), PathBuf::from("Game.part2.rar")]);
+        assert_eq!(sets[2].volumes, [PathBuf::from("Old.rar"), PathBuf::from("Old.r00")]);
+        assert_eq!(sets[1].archive_type, ArchiveType::SevenZip);
+    }
+
+    /// A zip holding a single file
+    fn zip_bytes(name: &str, contents: &[u8]) -> Vec<u8> {
+        use std::io::Write;
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        zip.start_file(name, zip::write::FileOptions::default()).unwrap();
+        zip.write_all(contents).unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[tokio::test]
+    async fn test_extract_set_nested() {
+        let dir = std::env::temp_dir().join(format!("extractor-nested-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let outer = dir.join("Game.zip");
+        std::fs::write(&outer, zip_bytes("inner.zip", &zip_bytes("setup.exe", b"MZ"))).unwrap();
+        let set = Extractor::group_archive_sets(&[outer]).remove(0);
+
+        let files = Extractor::new().extract_set(&set, &dir.join("out"), 1, None).await.unwrap();
+        assert_eq!(files, [dir.join("out").join("inner.zip"), dir.join("out").join("setup.exe")]);
+        // Nested archives aren't the download, so they're kept
+        assert!(dir.join("out").join("inner.zip").exists());
+        assert!(!staging_dir(&dir.join("out"), 1).exists());
+
+        // Archives next to an installer, or below the top of the output, are game data
+        let shipped = {
+            use std::io::Write;
+            let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+            zip.start_file("setup.exe", zip::write::FileOptions::default()).unwrap();
+            zip.write_all(b"MZ").unwrap();
+            zip.start_file("data.zip", zip::write::FileOptions::default()).unwrap();
+            zip.write_all(&zip_bytes("level.dat", b"x")).unwrap();
+            zip.finish().unwrap().into_inner()
+        };
+        let with_installer = dir.join("Installer.zip");
+        std::fs::write(&with_installer, shipped).unwrap();
+        let set = Extractor::group_archive_sets(&[with_installer]).remove(0);
+        Extractor::new().extract_set(&set, &dir.join("installer"), 1, None).await.unwrap();
+        assert!(!dir.join("installer").join("level.dat").exists());
+
+        let buried = dir.join("Buried.zip");
+        std::fs::write(&buried, zip_bytes("data/pack.zip", &zip_bytes("level.dat", b"x"))).unwrap();
+        let set = Extractor::group_archive_sets(&[buried]).remove(0);
+        Extractor::new().extract_set(&set, &dir.join("buried"), 1, None).await.unwrap();
+        assert!(!dir.join("buried").join("data").join("level.dat").exists());
+
+        // One level past the cap is left packed and reported
+        let mut archive = zip_bytes("setup.exe", b"MZ");
+        for level in 0..=MAX_NESTED_DEPTH {
+            archive = zip_bytes(&format!("level{}.zip", level), &archive);
+        }
+        let deep = dir.join("Deep.zip");
+        std::fs::write(&deep, archive).unwrap();
+        let set = Extractor::group_archive_sets(&[deep]).remove(0);
+        assert!(Extractor::new().extract_set(&set, &dir.join("deep"), 1, None).await.is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_extract_archive_failure_leaves_nothing() {
+        let dir = std::env::temp_dir().join(format!("extractor-failed-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // A good entry, then one whose data no longer matches its CRC
+        let mut archive = {
+            use std::io::Write;
+            let stored = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+            let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+            zip.start_file("readme.txt", stored).unwrap();
+            zip.write_all(b"hello").unwrap();
+            zip.start_file("setup.exe", stored).unwrap();
+            zip.write_all(b"MZ-installer").unwrap();
+            zip.finish().unwrap().into_inner()
+        };
+        let data = archive.windows(12).position(|w| w == b"MZ-installer").unwrap();
+        archive[data + 3] ^= 0xff;
+        let broken = dir.join("Broken.zip");
+        std::fs::write(&broken, archive).unwrap();
+
+        let out = dir.join("out");
+        let err = Extractor::new().extract_archive(&broken, &out, 1, None).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<ExtractionFailed>().map(|e| e.kind), Some(FailureKind::Corrupt));
+        assert_eq!(std::fs::read_dir(&out).unwrap().count(), 0);
+        assert!(!staging_dir(&out, 1).exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,164 @@
+//! Canonical genre names. Repack sites and RAWG spell the same genre in
+//! different ways ("RPG", "Role-Playing", "Action RPG"), so the genre list and
+//! the genre filter both go through an alias map.
+//!
+//! Admins can replace the built-in map with the `genre_aliases` setting: a
+//! JSON object from alias to a canonical name, or to a list of names when one
+//! label covers several genres (`{"Action RPG": ["Action", "RPG"]}`).
+//! Aliases match case-insensitively; genres without one are only merged with
+//! other spellings that differ in case.
+
+use serde::Deserialize;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+use crate::db;
+
+pub const GENRE_ALIASES_SETTING: &str = "genre_aliases";
+
+const DEFAULT_ALIASES: &[(&str, &[&str])] = &[
+    ("role-playing", &["RPG"]),
+    ("role playing", &["RPG"]),
+    ("role-playing (rpg)", &["RPG"]),
+    ("rpg", &["RPG"]),
+    ("action rpg", &["Action", "RPG"]),
+    ("action-rpg", &["Action", "RPG"]),
+    ("arpg", &["Action", "RPG"]),
+    ("jrpg", &["RPG"]),
+    ("action-adventure", &["Action", "Adventure"]),
+    ("action adventure", &["Action", "Adventure"]),
+    ("fps", &["Shooter"]),
+    ("first-person shooter", &["Shooter"]),
+    ("first person shooter", &["Shooter"]),
+    ("third-person shooter", &["Shooter"]),
+    ("tps", &["Shooter"]),
+    ("shoot 'em up", &["Shooter"]),
+    ("rts", &["Strategy"]),
+    ("real-time strategy", &["Strategy"]),
+    ("turn-based strategy", &["Strategy"]),
+    ("tbs", &["Strategy"]),
+    ("sim", &["Simulation"]),
+    ("simulator", &["Simulation"]),
+    ("platform", &["Platformer"]),
+    ("driving", &["Racing"]),
+    ("sport", &["Sports"]),
+    ("puzzles", &["Puzzle"]),
+    ("hack & slash", &["Hack and Slash"]),
+    ("hack'n'slash", &["Hack and Slash"]),
+    ("hack-and-slash", &["Hack and Slash"]),
+    ("beat 'em up", &["Fighting"]),
+    ("massively multiplayer", &["MMO"]),
+    ("mmorpg", &["MMO", "RPG"]),
+];
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Targets {
+    One(String),
+    Many(Vec<String>),
+}
+
+pub struct GenreAliases {
+    /// Lowercased alias -> canonical names
+    map: HashMap<String, Vec<String>>,
+}
+
+impl Default for GenreAliases {
+    fn default() -> Self {
+        let map = DEFAULT_ALIASES
+            .iter()
+            .map(|(alias, targets)| (alias.to_string(), targets.iter().map(|t| t.to_string()).collect()))
+            .collect();
+        Self { map }
+    }
+}
+
+impl GenreAliases {
+    /// Parse the `genre_aliases` setting
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let raw: HashMap<String, Targets> = serde_json::from_str(json).map_err(|e| {
+            format!("genre_aliases must be a JSON object of alias to genre name(s): {}", e)
+        })?;
+
+        let mut map = HashMap::new();
+        for (alias, targets) in raw {
+            let targets: Vec<String> = match targets {
+                Targets::One(name) => vec![name],
+                Targets::Many(names) => names,
+            }
+            .into_iter()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect();
+
+            let alias = alias.trim().to_lowercase();
+            if alias.is_empty() || targets.is_empty() {
+                return Err(format!("genre_aliases entry {:?} needs an alias and at least one genre", alias));
+            }
+            map.insert(alias, targets);
+        }
+        Ok(Self { map })
+    }
+
+    /// The canonical names for one raw genre; a genre without an alias is its own name
+    pub fn canonical(&self, genre: &str) -> Vec<String> {
+        let genre = genre.trim();
+        match self.map.get(&genre.to_lowercase()) {
+            Some(targets) => targets.clone(),
+            None => vec![genre.to_string()],
+        }
+    }
+
+    /// Every spelling that counts as `canonical`, itself included
+    pub fn variants(&self, canonical: &str) -> Vec<String> {
+        let mut variants = vec![canonical.to_string()];
+        for (alias, targets) in &self.map {
+            if targets.iter().any(|t| t.eq_ignore_ascii_case(canonical))
+                && !variants.iter().any(|v| v.eq_ignore_ascii_case(alias))
+            {
+                variants.push(alias.clone());
+            }
+        }
+        variants
+    }
+}
+
+/// The alias map from settings, falling back to the built-in one
+pub async fn load_aliases(pool: &SqlitePool) -> GenreAliases {
+    match db::get_setting(pool, GENRE_ALIASES_SETTING).await.ok().flatten() {
+        Some(json) => GenreAliases::parse(&json).unwrap_or_else(|e| {
+            eprintln!("Ignoring invalid {} setting: {}", GENRE_ALIASES_SETTING, e);
+            GenreAliases::default()
+        }),
+        None => GenreAliases::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical() {
+        let aliases = GenreAliases::default();
+        assert_eq!(aliases.canonical("Role-Playing"), vec!["RPG"]);
+        assert_eq!(aliases.canonical(" action RPG "), vec!["Action", "RPG"]);
+        assert_eq!(aliases.canonical("Indie"), vec!["Indie"]);
+    }
+
+    #[test]
+    fn test_variants() {
+        let aliases = GenreAliases::parse(r#"{"Role-Playing": "RPG", "Action RPG": ["Action", "RPG"]}"#).unwrap();
+        let mut variants = aliases.variants("RPG");
+        variants.sort();
+        assert_eq!(variants, vec!["RPG", "action rpg", "role-playing"]);
+        assert_eq!(aliases.variants("Indie"), vec!["Indie"]);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_maps() {
+        assert!(GenreAliases::parse("[]").is_err());
+        assert!(GenreAliases::parse(r#"{"RPG": 1}"#).is_err());
+        assert!(GenreAliases::parse(r#"{"RPG": []}"#).is_err());
+    }
+}
@@ -0,0 +1,86 @@
+//! Subsystem checks behind `/api/health`. Besides the database, it reports
+//! whether the debrid provider is reachable and accepts the API key, free
+//! space on the download directory, whether a scrape is running and how many
+//! downloads are active. Any failing subsystem turns the status `degraded`.
+//!
+//! The debrid check calls the provider's API, so its result is cached for a
+//! minute; uptime monitors polling the endpoint don't eat into rate limits.
+
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+use crate::debrid::{self, Debrid};
+
+/// How long a debrid check result is reused
+const DEBRID_CHECK_TTL: Duration = Duration::from_secs(60);
+
+/// Free space on the download directory below this counts as unhealthy
+pub const LOW_DISK_GB: f64 = 5.0;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DebridHealth {
+    pub provider: String,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskHealth {
+    pub path: String,
+    /// None when free space can't be determined on this platform
+    pub free_gb: Option<f64>,
+    pub ok: bool,
+}
+
+/// Last debrid check, keyed by provider so switching providers re-checks
+#[derive(Clone, Default)]
+pub struct DebridCheckCache {
+    last: Arc<RwLock<Option<(Instant, DebridHealth)>>>,
+}
+
+impl DebridCheckCache {
+    /// Check the provider selected in Settings, or reuse a recent result
+    pub async fn check(&self, db: &SqlitePool, fallback: &Arc<dyn Debrid>) -> DebridHealth {
+        let client = debrid::from_settings(db, fallback).await;
+        let provider = match &client {
+            Ok(client) => client.provider_name(),
+            Err(_) => "none",
+        };
+
+        if let Some((checked_at, health)) = self.last.read().await.as_ref() {
+            if checked_at.elapsed() < DEBRID_CHECK_TTL && health.provider == provider {
+                return health.clone();
+            }
+        }
+
+        let health = match client {
+            Ok(client) => match client.check_account().await {
+                Ok(()) => DebridHealth { provider: provider.to_string(), ok: true, error: None },
+                Err(e) => DebridHealth { provider: provider.to_string(), ok: false, error: Some(e.to_string()) },
+            },
+            Err(e) => DebridHealth { provider: provider.to_string(), ok: false, error: Some(e) },
+        };
+        *self.last.write().await = Some((Instant::now(), health.clone()));
+        health
+    }
+}
+
+/// Free space on the volume holding the download directory
+pub async fn check_disk(download_dir: &Path) -> DiskHealth {
+    let dir = download_dir.to_path_buf();
+    let free_gb = tokio::task::spawn_blocking(move || crate::system_info::get_free_space_gb(&dir))
+        .await
+        .ok()
+        .flatten();
+    DiskHealth {
+        path: download_dir.display().to_string(),
+        free_gb: free_gb.map(|gb| (gb * 10.0).round() / 10.0),
+        // Unknown free space isn't treated as a failure
+        ok: free_gb.is_none_or(|gb| gb >= LOW_DISK_GB),
+    }
+}
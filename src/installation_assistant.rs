@@ -76,8 +76,8 @@ pub fn get_recommended_actions(
         if dep.contains(".NET") {
             actions.push(AssistantAction {
                 id: "install_dotnet".to_string(),
-                name: "Install .NET Framework 4.8".to_string(),
-                description: "Download and install .NET Framework 4.8 (required for some installers)".to_string(),
+                name: format!("Install {}", dep),
+                description: format!("Download and install {} (required for some installers)", dep),
                 action_type: ActionType::InstallDependency,
                 required: true,
                 auto_applicable: false,
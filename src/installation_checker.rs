@@ -1,7 +1,13 @@
 use crate::db;
-use crate::system_info::{SystemInfo, SystemStatus};
+use crate::system_info::{SystemInfo, SystemStatus, Thresholds};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
+use std::sync::LazyLock;
+
+static FIRST_NUMBER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\d+").unwrap());
+static CORE_COUNT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(\d+)[- ]?cores?\b").unwrap());
+static GB_AMOUNT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)(\d+(?:\.\d+)?)\s*gb").unwrap());
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PreInstallCheckResult {
@@ -9,6 +15,13 @@ pub struct PreInstallCheckResult {
     pub overall_status: CheckStatus,
     pub system_info: SystemInfo,
     pub game_requirements: Option<db::GameRequirement>,
+    /// How installs of this game have gone for other users
+    pub community: Option<db::GameRatingStats>,
+    /// This machine against each of the game's listed requirements
+    pub requirements: Vec<RequirementComparison>,
+    /// Installable runtimes this game needs that are missing; pass these to
+    /// the installation assistant
+    pub missing_dependencies: Vec<String>,
     pub checks: Vec<CheckItem>,
     pub warnings: Vec<String>,
     pub blockers: Vec<String>,
@@ -36,6 +49,45 @@ pub enum CheckItemStatus {
     Pass,
     Warning,
     Fail,
+    /// The requirement is listed but can't be compared automatically
+    Unknown,
+}
+
+/// One requirement field compared against the detected hardware
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequirementComparison {
+    /// "ram", "disk", "cpu" or "gpu"
+    pub field: String,
+    /// Fail: below minimum. Warning: meets minimum but not recommended.
+    pub status: CheckItemStatus,
+    /// What this machine has, e.g. "8 GB"
+    pub detected: Option<String>,
+    pub minimum: Option<String>,
+    pub recommended: Option<String>,
+    /// e.g. "You have 8 GB RAM; this game needs 16 GB"
+    pub message: String,
+}
+
+/// Settings keys for the warning thresholds; unset means the defaults
+pub const MIN_RAM_SETTING: &str = "min_available_ram_gb";
+pub const MIN_TEMP_SETTING: &str = "min_free_temp_gb";
+
+/// Warning thresholds from settings, falling back to the defaults
+pub async fn load_thresholds(pool: &SqlitePool) -> Thresholds {
+    let defaults = Thresholds::default();
+    let read = |key: &'static str| async move {
+        db::get_setting(pool, key)
+            .await
+            .ok()
+            .flatten()
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|v| *v >= 0.0)
+    };
+
+    Thresholds {
+        min_available_ram_gb: read(MIN_RAM_SETTING).await.unwrap_or(defaults.min_available_ram_gb),
+        min_free_temp_gb: read(MIN_TEMP_SETTING).await.unwrap_or(defaults.min_free_temp_gb),
+    }
 }
 
 pub async fn check_pre_installation(
@@ -43,7 +95,7 @@ pub async fn check_pre_installation(
     game_id: i64,
 ) -> Result<PreInstallCheckResult, Box<dyn std::error::Error>> {
     // Gather system information
-    let system_info = SystemInfo::gather().await;
+    let system_info = SystemInfo::gather_with(load_thresholds(pool).await).await;
 
     // Get game details
     let game = db::get_game_by_id(pool, game_id).await?;
@@ -85,7 +137,8 @@ pub async fn check_pre_installation(
     }
 
     // Check 4: Dependencies
-    let dep_check = check_dependencies(&system_info, &game_requirements);
+    let missing_dependencies = missing_runtimes(&system_info, game_requirements.as_ref());
+    let dep_check = check_dependencies(&system_info, &game_requirements, &missing_dependencies);
     checks.push(dep_check.clone());
     match dep_check.status {
         CheckItemStatus::Fail => blockers.push(dep_check.message.clone()),
@@ -109,6 +162,27 @@ pub async fn check_pre_installation(
         _ => {}
     }
 
+    // Warn when other users often had trouble installing this game
+    let community = db::get_game_rating_stats(pool, game_id).await.ok();
+    if let Some(percent) = community.as_ref().and_then(|c| c.issue_percent()) {
+        warnings.push(format!("{:.0}% of users report install issues with this game", percent));
+    }
+
+    // Compare against the game's listed requirements. RAM and disk space are
+    // already covered by the checks above; CPU and GPU shortfalls are warnings
+    // since they affect how the game runs, not whether it installs.
+    let requirements = game_requirements
+        .as_ref()
+        .map(|reqs| compare_requirements(&system_info, reqs))
+        .unwrap_or_default();
+    for comparison in &requirements {
+        if matches!(comparison.field.as_str(), "cpu" | "gpu")
+            && matches!(comparison.status, CheckItemStatus::Fail | CheckItemStatus::Warning)
+        {
+            warnings.push(comparison.message.clone());
+        }
+    }
+
     // Determine overall status
     let overall_status = if !blockers.is_empty() {
         CheckStatus::Blocked
@@ -142,6 +216,9 @@ pub async fn check_pre_installation(
         overall_status,
         system_info,
         game_requirements,
+        community,
+        requirements,
+        missing_dependencies,
         checks,
         warnings,
         blockers,
@@ -181,17 +258,25 @@ fn check_ram(system_info: &SystemInfo, game_reqs: &Option<db::GameRequirement>)
     }
 
     // General RAM check
-    if available < 4.0 {
+    let thresholds = &system_info.thresholds;
+    if available < thresholds.critical_ram_gb() {
         CheckItem {
             name: "RAM".to_string(),
             status: CheckItemStatus::Fail,
-            message: format!("Critical: Only {:.1}GB RAM available, 4GB minimum required", available),
+            message: format!(
+                "Critical: Only {:.1}GB RAM available, {}GB minimum required",
+                available,
+                thresholds.critical_ram_gb()
+            ),
         }
-    } else if available < 8.0 {
+    } else if available < thresholds.min_available_ram_gb {
         CheckItem {
             name: "RAM".to_string(),
             status: CheckItemStatus::Warning,
-            message: format!("Low RAM: {:.1}GB available, 8GB recommended", available),
+            message: format!(
+                "Low RAM: {:.1}GB available, {}GB recommended",
+                available, thresholds.min_available_ram_gb
+            ),
         }
     } else {
         CheckItem {
@@ -229,14 +314,14 @@ fn check_disk_space(
                 available, install_space_needed
             ),
         }
-    } else if available < install_space_needed * 1.5 {
+    } else if available < install_space_needed * 1.5 || available < system_info.thresholds.min_free_temp_gb {
         CheckItem {
             name: "Disk Space".to_string(),
             status: CheckItemStatus::Warning,
             message: format!(
                 "Low disk space: {:.1}GB available, {:.1}GB recommended",
                 available,
-                install_space_needed * 1.5
+                (install_space_needed * 1.5).max(system_info.thresholds.min_free_temp_gb)
             ),
         }
     } else {
@@ -284,28 +369,71 @@ fn check_dlls(system_info: &SystemInfo) -> CheckItem {
     }
 }
 
-fn check_dependencies(
-    system_info: &SystemInfo,
-    game_reqs: &Option<db::GameRequirement>,
-) -> CheckItem {
+/// Runtimes missing for this game: the baseline every repack needs plus
+/// whatever its requirements name. Only runtimes an installer can fix are
+/// listed; a GPU below the required DirectX level is reported separately.
+pub fn missing_runtimes(system_info: &SystemInfo, game_reqs: Option<&db::GameRequirement>) -> Vec<String> {
     let mut missing = system_info.missing_dependencies.clone();
 
-    // Check against game-specific requirements
-    if let Some(reqs) = game_reqs {
-        if let Some(ref dx) = reqs.requires_directx {
-            if !dx.is_empty() && system_info.missing_dependencies.iter().any(|d| d.contains("DirectX")) {
-                missing.push(format!("DirectX {} (required by game)", dx));
-            }
+    // Runtimes are only detected on Windows, where the games run
+    if !cfg!(target_os = "windows") {
+        return missing;
+    }
+
+    let Some(reqs) = game_reqs else {
+        return missing;
+    };
+    let runtimes = &system_info.runtimes;
+    let already_listed = |kind: &str, missing: &[String]| missing.iter().any(|d| d.contains(kind));
+
+    if let Some(vc) = reqs.requires_vcredist.as_deref().filter(|v| !v.trim().is_empty()) {
+        if !runtimes.has_vcredist(vc) {
+            missing.push(format!("Visual C++ {} Redistributable", vc.trim()));
         }
-        if let Some(ref dotnet) = reqs.requires_dotnet {
-            if !dotnet.is_empty() && system_info.missing_dependencies.iter().any(|d| d.contains(".NET")) {
-                missing.push(format!(".NET {} (required by game)", dotnet));
-            }
+    }
+    if let Some(dotnet) = reqs.requires_dotnet.as_deref().filter(|v| !v.trim().is_empty()) {
+        if !runtimes.has_dotnet(dotnet) && !already_listed(".NET", &missing) {
+            missing.push(format!(".NET {}", dotnet.trim().trim_start_matches(".NET").trim()));
+        }
+    }
+    if let Some(dx) = reqs.requires_directx.as_deref().filter(|v| !v.trim().is_empty()) {
+        if !runtimes.directx_legacy && requires_legacy_directx(dx) && !already_listed("DirectX", &missing) {
+            missing.push("DirectX Runtime".to_string());
         }
     }
 
     missing.sort();
     missing.dedup();
+    missing
+}
+
+/// DirectX 9-era games need the D3DX runtime; for later versions it's the GPU that matters
+fn requires_legacy_directx(required: &str) -> bool {
+    FIRST_NUMBER
+        .find(required)
+        .and_then(|m| m.as_str().parse::<u32>().ok())
+        .is_none_or(|major| major <= 9)
+}
+
+fn check_dependencies(
+    system_info: &SystemInfo,
+    game_reqs: &Option<db::GameRequirement>,
+    missing: &[String],
+) -> CheckItem {
+    // A DirectX version the GPU can't run isn't fixed by installing anything
+    if let Some(dx) = game_reqs.as_ref().and_then(|r| r.requires_directx.as_deref()) {
+        if cfg!(target_os = "windows") && !requires_legacy_directx(dx) && !system_info.runtimes.has_directx(dx) {
+            return CheckItem {
+                name: "Dependencies".to_string(),
+                status: CheckItemStatus::Fail,
+                message: format!(
+                    "This game needs DirectX {} but your GPU supports feature level {}",
+                    dx.trim(),
+                    system_info.runtimes.directx_feature_level.as_deref().unwrap_or("unknown")
+                ),
+            };
+        }
+    }
 
     if missing.is_empty() {
         CheckItem {
@@ -377,8 +505,200 @@ fn check_cpu(system_info: &SystemInfo, game_reqs: &Option<db::GameRequirement>)
     }
 }
 
+/// Compare each requirement the game lists against this machine
+pub fn compare_requirements(system_info: &SystemInfo, reqs: &db::GameRequirement) -> Vec<RequirementComparison> {
+    let mut comparisons = Vec::new();
+
+    if reqs.min_ram_gb.is_some() || reqs.rec_ram_gb.is_some() {
+        comparisons.push(compare_amount(
+            "ram",
+            "RAM",
+            system_info.ram_total_gb,
+            reqs.min_ram_gb.map(|gb| gb as f64),
+            reqs.rec_ram_gb.map(|gb| gb as f64),
+        ));
+    }
+
+    if let Some(disk_gb) = reqs.disk_space_gb {
+        comparisons.push(compare_amount(
+            "disk",
+            "free disk space",
+            system_info.temp_space_gb,
+            Some(disk_gb as f64),
+            None,
+        ));
+    }
+
+    if reqs.min_cpu.is_some() || reqs.rec_cpu.is_some() {
+        comparisons.push(compare_cpu(system_info, reqs));
+    }
+
+    if reqs.min_gpu.is_some() || reqs.rec_gpu.is_some() {
+        comparisons.push(compare_gpu(system_info, reqs));
+    }
+
+    comparisons
+}
+
+/// Compare a quantity in GB against optional minimum and recommended values
+fn compare_amount(
+    field: &str,
+    label: &str,
+    have_gb: f64,
+    min_gb: Option<f64>,
+    rec_gb: Option<f64>,
+) -> RequirementComparison {
+    let (status, message) = if min_gb.is_some_and(|min| have_gb < min) {
+        (
+            CheckItemStatus::Fail,
+            format!("You have {} {}; this game needs {}", format_gb(have_gb), label, format_gb(min_gb.unwrap())),
+        )
+    } else if rec_gb.is_some_and(|rec| have_gb < rec) {
+        (
+            CheckItemStatus::Warning,
+            format!("You have {} {}; {} is recommended", format_gb(have_gb), label, format_gb(rec_gb.unwrap())),
+        )
+    } else {
+        (CheckItemStatus::Pass, format!("✓ {} {} meets the requirements", format_gb(have_gb), label))
+    };
+
+    RequirementComparison {
+        field: field.to_string(),
+        status,
+        detected: Some(format_gb(have_gb)),
+        minimum: min_gb.map(format_gb),
+        recommended: rec_gb.map(format_gb),
+        message,
+    }
+}
+
+/// CPUs are compared by core count when the requirement states one
+/// ("quad-core", "6 cores"); model names alone can't be ranked
+fn compare_cpu(system_info: &SystemInfo, reqs: &db::GameRequirement) -> RequirementComparison {
+    let cores = system_info.cpu_cores;
+    let min_cores = reqs.min_cpu.as_deref().and_then(parse_core_count);
+    let rec_cores = reqs.rec_cpu.as_deref().and_then(parse_core_count);
+
+    let (status, message) = match (min_cores, rec_cores) {
+        (Some(min), _) if cores < min => (
+            CheckItemStatus::Fail,
+            format!("You have {} CPU cores; this game needs {}", cores, min),
+        ),
+        (_, Some(rec)) if cores < rec => (
+            CheckItemStatus::Warning,
+            format!("You have {} CPU cores; {} are recommended", cores, rec),
+        ),
+        (None, None) => (
+            CheckItemStatus::Unknown,
+            format!(
+                "Needs {}; check it against your {}-core CPU",
+                reqs.min_cpu.as_deref().or(reqs.rec_cpu.as_deref()).unwrap_or_default(),
+                cores
+            ),
+        ),
+        _ => (CheckItemStatus::Pass, format!("✓ {} CPU cores meets the requirements", cores)),
+    };
+
+    RequirementComparison {
+        field: "cpu".to_string(),
+        status,
+        detected: Some(format!("{} cores", cores)),
+        minimum: reqs.min_cpu.clone(),
+        recommended: reqs.rec_cpu.clone(),
+        message,
+    }
+}
+
+/// GPUs are compared by video memory when the requirement mentions it
+/// ("GTX 1060 6GB"); otherwise the names are shown side by side
+fn compare_gpu(system_info: &SystemInfo, reqs: &db::GameRequirement) -> RequirementComparison {
+    // The adapter with the most VRAM is the one a game would pick
+    let gpu = system_info
+        .gpus
+        .iter()
+        .max_by(|a, b| a.vram_gb.unwrap_or(0.0).total_cmp(&b.vram_gb.unwrap_or(0.0)));
+    let detected = gpu.map(|g| match g.vram_gb {
+        Some(vram) => format!("{} ({})", g.name, format_gb(vram)),
+        None => g.name.clone(),
+    });
+
+    let min_vram = reqs.min_gpu.as_deref().and_then(parse_vram_gb);
+    let rec_vram = reqs.rec_gpu.as_deref().and_then(parse_vram_gb);
+    let needed = reqs.min_gpu.as_deref().or(reqs.rec_gpu.as_deref()).unwrap_or_default();
+
+    let (status, message) = match (gpu, gpu.and_then(|g| g.vram_gb)) {
+        (None, _) => (
+            CheckItemStatus::Unknown,
+            format!("No GPU detected; this game needs {}", needed),
+        ),
+        (Some(g), Some(vram)) if min_vram.is_some_and(|min| vram < min) => (
+            CheckItemStatus::Fail,
+            format!(
+                "Your {} has {} of video memory; this game needs {}",
+                g.name,
+                format_gb(vram),
+                format_gb(min_vram.unwrap())
+            ),
+        ),
+        (Some(g), Some(vram)) if rec_vram.is_some_and(|rec| vram < rec) => (
+            CheckItemStatus::Warning,
+            format!(
+                "Your {} has {} of video memory; {} is recommended",
+                g.name,
+                format_gb(vram),
+                format_gb(rec_vram.unwrap())
+            ),
+        ),
+        (Some(g), Some(_)) if min_vram.is_some() || rec_vram.is_some() => (
+            CheckItemStatus::Pass,
+            format!("✓ {} meets the video memory requirements", g.name),
+        ),
+        (Some(g), _) => (
+            CheckItemStatus::Unknown,
+            format!("Needs {}; you have {}", needed, g.name),
+        ),
+    };
+
+    RequirementComparison {
+        field: "gpu".to_string(),
+        status,
+        detected,
+        minimum: reqs.min_gpu.clone(),
+        recommended: reqs.rec_gpu.clone(),
+        message,
+    }
+}
+
+/// Core count from text like "Quad-core 2.5 GHz" or "6 cores"
+fn parse_core_count(cpu: &str) -> Option<i64> {
+    let lower = cpu.to_lowercase();
+    let words = [("dual", 2), ("quad", 4), ("hexa", 6), ("six", 6), ("octa", 8), ("eight", 8)];
+    if let Some((_, n)) = words.iter().find(|(w, _)| {
+        lower.contains(&format!("{}-core", w)) || lower.contains(&format!("{} core", w))
+    }) {
+        return Some(*n);
+    }
+
+    CORE_COUNT.captures(&lower)?.get(1)?.as_str().parse().ok()
+}
+
+/// Largest "N GB" in a GPU requirement, taken as its video memory
+fn parse_vram_gb(gpu: &str) -> Option<f64> {
+    GB_AMOUNT.captures_iter(gpu)
+        .filter_map(|c| c.get(1)?.as_str().parse::<f64>().ok())
+        .reduce(f64::max)
+}
+
+fn format_gb(gb: f64) -> String {
+    if (gb - gb.round()).abs() < 0.05 {
+        format!("{:.0} GB", gb)
+    } else {
+        format!("{:.1} GB", gb)
+    }
+}
+
 /// Parse size string like "50 GB" to GB as f64
-fn parse_size_to_gb(size_str: &str) -> Option<f64> {
+pub fn parse_size_to_gb(size_str: &str) -> Option<f64> {
     let cleaned = size_str.to_lowercase().replace(",", "");
 
     if cleaned.contains("gb") {
@@ -396,3 +716,41 @@ fn parse_size_to_gb(size_str: &str) -> Option<f64> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_core_count() {
+        assert_eq!(parse_core_count("Intel Core i5-2500K Quad-Core 3.3 GHz"), Some(4));
+        assert_eq!(parse_core_count("Dual core 2.4 GHz"), Some(2));
+        assert_eq!(parse_core_count("AMD Ryzen 5 1600 (6 cores)"), Some(6));
+        assert_eq!(parse_core_count("8-core CPU"), Some(8));
+        assert_eq!(parse_core_count("Intel Core i7-8700"), None);
+    }
+
+    #[test]
+    fn test_parse_vram_gb() {
+        assert_eq!(parse_vram_gb("GeForce GTX 1060 6GB / Radeon RX 580 8 GB"), Some(8.0));
+        assert_eq!(parse_vram_gb("1.5 gb VRAM"), Some(1.5));
+        assert_eq!(parse_vram_gb("GeForce GTX 970"), None);
+    }
+
+    #[test]
+    fn test_format_gb() {
+        assert_eq!(format_gb(8.0), "8 GB");
+        assert_eq!(format_gb(7.98), "8 GB");
+        assert_eq!(format_gb(1.5), "1.5 GB");
+    }
+
+    #[test]
+    fn test_requires_legacy_directx() {
+        assert!(requires_legacy_directx("9.0c"));
+        assert!(requires_legacy_directx("DirectX 9"));
+        assert!(!requires_legacy_directx("DirectX 11"));
+        assert!(!requires_legacy_directx("12"));
+        // No version at all: the legacy runtime is the safe bet
+        assert!(requires_legacy_directx("DirectX"));
+    }
+}
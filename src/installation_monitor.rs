@@ -1,5 +1,5 @@
 use crate::db;
-use crate::system_info::SystemInfo;
+use crate::system_info;
 use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
 use std::sync::Arc;
@@ -70,9 +70,8 @@ impl InstallationMonitor {
 
         tokio::spawn(async move {
             while *is_running.read().await {
-                let sys_info = SystemInfo::gather().await;
-
-                let ram_used = sys_info.ram_total_gb - sys_info.ram_available_gb;
+                // Only RAM is needed here, not a full system check
+                let ram_used = system_info::get_total_ram_gb() - system_info::get_available_ram_gb();
 
                 let mut peak = peak_ram.write().await;
                 if ram_used > *peak {
@@ -90,9 +89,7 @@ impl InstallationMonitor {
         let duration = self.started_at.elapsed().as_secs();
         let peak_ram = *self.peak_ram_gb.read().await;
 
-        // Get current RAM
-        let sys_info = SystemInfo::gather().await;
-        let current_ram = sys_info.ram_total_gb - sys_info.ram_available_gb;
+        let current_ram = system_info::get_total_ram_gb() - system_info::get_available_ram_gb();
 
         InstallMonitorState {
             log_id: self.log_id,
@@ -196,9 +193,20 @@ pub async fn get_all_installation_logs(
 }
 
 /// Analyze failed installations and provide recommendations
-pub fn analyze_installation_failure(log: &db::InstallationLog) -> Vec<String> {
+pub fn analyze_installation_failure(
+    log: &db::InstallationLog,
+    community: Option<&db::GameRatingStats>,
+) -> Vec<String> {
     let mut recommendations = Vec::new();
 
+    // A failure many others share points at the repack rather than this machine
+    if let Some(percent) = community.and_then(|c| c.issue_percent()) {
+        recommendations.push(format!(
+            "{:.0}% of users report install issues with this game - check the repack's comments for known fixes",
+            percent
+        ));
+    }
+
     if let Some(ref error_msg) = log.error_message {
         let error_lower = error_msg.to_lowercase();
 
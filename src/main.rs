@@ -1,47 +1,124 @@
+mod alldebrid;
+mod api_error;
+mod backup;
+mod config;
+mod cookies;
 mod db;
+mod debrid;
+mod download_cleanup;
 mod downloader;
+mod events;
 mod download_manager;
+mod email;
 mod client_downloads;  // New client-side download management
 mod extractor;
+mod genres;
+mod health;
 mod installation_assistant;
 mod installation_checker;
 mod installation_monitor;
 mod md5_validator;
+mod migrations;
+mod notifications;
+mod openapi;
+mod pagination;
+mod push;
+mod quota;
+mod rate_limit;
 mod rawg;
 mod realdebrid;
 mod scrapers;
+mod session_refresh;
 mod system_info;
+mod thumbnails;
+mod view_counter;
 
 use axum::{
     body::Body,
-    extract::{Multipart, Path, Query, State},
-    http::{header, StatusCode, HeaderMap},
-    response::{IntoResponse, Json, Response},
+    extract::{ws::{Message, WebSocket, WebSocketUpgrade}, ConnectInfo, DefaultBodyLimit, Multipart, Path, Query, Request, State},
+    http::{header, StatusCode, HeaderMap, Uri},
+    middleware::Next,
+    response::{sse::{Event, KeepAlive, Sse}, IntoResponse, Json, Response},
     routing::{delete, get, post},
     Router,
 };
-use axum::http::header::{COOKIE, SET_COOKIE};
+use axum::http::header::SET_COOKIE;
 use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use futures::StreamExt;
 use tokio_util::io::ReaderStream;
+use tokio_util::sync::CancellationToken;
+use api_error::ApiError;
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use utoipa_swagger_ui::SwaggerUi;
 use tower_http::{
+    compression::CompressionLayer,
     cors::CorsLayer,
     services::ServeDir,
+    trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer},
+    LatencyUnit,
 };
 
 #[derive(Clone)]
 struct AppState {
     db: SqlitePool,
-    rd_client: Arc<realdebrid::RealDebridClient>,
+    debrid: Arc<dyn debrid::Debrid>,
     scrape_status: Arc<RwLock<ScrapeStatus>>,
     download_manager: Arc<download_manager::DownloadManager>,
     client_download_manager: Arc<client_downloads::ClientDownloadManager>,  // New client-side downloads
+    events: Arc<events::EventHub>,
+    notifier: Arc<notifications::Notifier>,
+    aggregate_cache: AggregateCache,
+    debrid_health: health::DebridCheckCache,
+    thumbnails: Arc<thumbnails::ThumbnailCache>,
+    rate_limiter: Arc<rate_limit::RateLimiter>,
+    session_refresher: Arc<session_refresh::SessionRefresher>,
+    view_counter: Arc<view_counter::ViewCounter>,
+    /// Cancelled on SIGINT/SIGTERM so streams, sockets and background tasks wind down
+    shutdown: CancellationToken,
+    session_config: config::SessionConfig,
+    download_quota: config::DownloadQuota,
+    /// Seconds without a heartbeat before a client counts as offline
+    client_offline_secs: i64,
+    /// Take the client IP from X-Forwarded-For (only safe behind a reverse proxy)
+    trust_proxy: bool,
+    /// Address users reach the server at, for links in emails
+    public_url: Option<String>,
     rawg_api_key: String,
+    rawg_cache_days: i64,
+    rd_api_key: String,
     scraper_registry: Arc<scrapers::registry::ScraperRegistry>,
 }
 
+/// How long genre/tag counts and featured lists are served from memory
+const AGGREGATE_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Responses of the aggregate endpoints the homepage calls on every load,
+/// keyed by endpoint. Cleared when a rescrape finishes or tags change.
+#[derive(Clone, Default)]
+struct AggregateCache {
+    entries: Arc<RwLock<std::collections::HashMap<String, (std::time::Instant, serde_json::Value)>>>,
+}
+
+impl AggregateCache {
+    async fn get(&self, key: &str) -> Option<serde_json::Value> {
+        let entries = self.entries.read().await;
+        entries.get(key)
+            .filter(|(cached_at, _)| cached_at.elapsed() < AGGREGATE_CACHE_TTL)
+            .map(|(_, value)| value.clone())
+    }
+
+    async fn insert(&self, key: &str, value: serde_json::Value) {
+        self.entries.write().await.insert(key.to_string(), (std::time::Instant::now(), value));
+    }
+
+    async fn clear(&self) {
+        self.entries.write().await.clear();
+    }
+}
+
 #[derive(Clone, Serialize)]
 struct ScrapeStatus {
     is_running: bool,
@@ -62,7 +139,7 @@ impl Default for ScrapeStatus {
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct GamesResponse {
     games: Vec<db::Game>,
     total: i64,
@@ -71,17 +148,83 @@ struct GamesResponse {
     total_pages: i64,
 }
 
+#[derive(Serialize, ToSchema)]
+struct GameDetail {
+    #[serde(flatten)]
+    game: db::Game,
+    /// How installs have gone for other users
+    community: db::GameRatingStats,
+}
+
+#[derive(Deserialize)]
+struct NotificationQuery {
+    page: Option<i64>,
+    per_page: Option<i64>,
+    #[serde(default)]
+    unread_first: bool,
+}
+
+#[derive(Serialize)]
+struct NotificationsResponse {
+    notifications: Vec<db::Notification>,
+    total: i64,
+    page: i64,
+    per_page: i64,
+    total_pages: i64,
+}
+
 #[derive(Deserialize)]
 struct AddMagnetRequest {
     game_id: i64,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct QueueDownloadRequest {
     game_id: i64,
+    /// Leave out samples, readmes and other extras
+    #[serde(default)]
+    skip_junk: bool,
+    /// Password for encrypted archives, remembered for the game
+    #[serde(default)]
+    archive_password: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, ToSchema)]
+struct ArchivePasswordRequest {
+    password: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+struct InstallDirRequest {
+    /// Absolute folder on the client machine; empty or null restores the default
+    #[serde(default)]
+    install_dir: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+struct BatchQueueRequest {
+    game_ids: Vec<i64>,
+    #[serde(default)]
+    skip_junk: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+struct BatchQueueResult {
+    game_id: i64,
+    success: bool,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    download_id: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct BatchQueueResponse {
+    queued: usize,
+    failed: usize,
+    results: Vec<BatchQueueResult>,
+}
+
+#[derive(Serialize, ToSchema)]
 struct ApiResponse {
     success: bool,
     message: String,
@@ -91,9 +234,31 @@ struct ApiResponse {
     download_id: Option<i64>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct DownloadsResponse {
     downloads: Vec<download_manager::DownloadInfo>,
+    /// Whether LIBRARY_DIR is set, so "Move to Library" can be offered
+    library_enabled: bool,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct DownloadHistoryQuery {
+    /// Only "completed", "installed" or "failed" downloads
+    status: Option<String>,
+    /// 1-based page number
+    page: Option<i64>,
+    /// Entries per page, default 50, capped at 200
+    per_page: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct DownloadHistoryResponse {
+    downloads: Vec<db::DownloadHistoryEntry>,
+    total: i64,
+    page: i64,
+    per_page: i64,
+    total_pages: i64,
 }
 
 // ─── Authentication structures ───
@@ -115,109 +280,204 @@ struct AuthResponse {
     success: bool,
     message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    user: Option<UserInfo>,
-}
-
-#[derive(Serialize)]
-struct UserInfo {
-    id: i64,
-    username: String,
-    is_admin: bool,
+    user: Option<db::UserInfo>,
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let rd_api_key = std::env::var("RD_API_KEY")
-        .unwrap_or_else(|_| {
-            eprintln!("Warning: RD_API_KEY not set. Real-Debrid integration will not work.");
-            String::new()
-        });
+    let command = parse_args(std::env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(2);
+    });
 
-    let rawg_api_key = std::env::var("RAWG_API_KEY")
-        .unwrap_or_else(|_| {
-            eprintln!("Warning: RAWG_API_KEY not set. Game images/metadata from RAWG will not be available.");
-            eprintln!("  Get a free key at https://rawg.io/apidocs");
-            String::new()
-        });
+    // RUST_LOG picks what's logged, e.g. "debug" or "info,tower_http=warn"
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+        )
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::IsTerminal::is_terminal(&std::io::stderr()))
+        .init();
+
+    let config = config::Config::load()?;
+    if let Some(file) = &config.file {
+        println!("⚙️  Config file: {}", file.display());
+    }
 
-    let db_path = std::env::var("DATABASE_PATH")
-        .unwrap_or_else(|_| {
-            let current_dir = std::env::current_exe()
-                .ok()
-                .and_then(|path| path.parent().map(|p| p.to_path_buf()))
-                .unwrap_or_else(|| std::path::PathBuf::from("."));
-            
-            let data_dir = current_dir.join("data");
-            std::fs::create_dir_all(&data_dir).ok();
-            
-            format!("sqlite:{}?mode=rwc", data_dir.join("games.db").display())
-        });
-    
-    println!("📁 Database location: {}", db_path);
-    let db = db::init_db(&db_path).await?;
-
-    // Download configuration from env vars
-    let download_dir = std::env::var("DOWNLOAD_DIR")
-        .unwrap_or_else(|_| {
-            let current_dir = std::env::current_exe()
-                .ok()
-                .and_then(|path| path.parent().map(|p| p.to_path_buf()))
-                .unwrap_or_else(|| std::path::PathBuf::from("."));
-            current_dir.join("downloads").to_string_lossy().to_string()
-        });
+    if config.rd_api_key.is_empty() {
+        tracing::warn!("RD_API_KEY not set. Real-Debrid integration will not work.");
+    }
+    if config.rawg_api_key.is_empty() {
+        tracing::warn!("RAWG_API_KEY not set. Game images/metadata from RAWG will not be available. Get a free key at https://rawg.io/apidocs");
+    }
+
+    println!("📁 Database location: {} (max {} connections)", config.database_path, config.db_max_connections);
+    let db = db::init_db(&config.database_path, config.db_max_connections).await?;
+
+    let config::Config {
+        port,
+        trust_proxy,
+        rd_api_key,
+        rawg_api_key,
+        download_dir,
+        auto_extract,
+        delete_archives,
+        nested_size_factor,
+        max_concurrent,
+        rate_limit_mbps,
+        library_dir,
+        auto_archive,
+        scrape_interval_hours,
+        rawg_cache_days,
+        session: session_config,
+        download_quota,
+        rate_limit_per_minute,
+        rate_limit_auth_per_minute,
+        notification_retention_days,
+        thumbnail_dir,
+        client_offline_secs,
+        public_url,
+        smtp,
+        ..
+    } = config;
+
+    // Initialize scraper registry
+    let mut scraper_registry = scrapers::registry::ScraperRegistry::new();
+    scraper_registry.register(Arc::new(scrapers::fitgirl::FitGirlScraper::new()));
+    scraper_registry.register(Arc::new(scrapers::steamrip::SteamRipScraper::new()));
+    let scraper_registry = Arc::new(scraper_registry);
 
-    let auto_extract = std::env::var("AUTO_EXTRACT")
-        .unwrap_or_else(|_| "true".to_string())
-        .parse::<bool>()
-        .unwrap_or(true);
+    // Links in emails need an address we trust, not whatever Host a request sent
+    let mailer = match &smtp {
+        Some(_) if public_url.is_none() => {
+            tracing::warn!("SMTP is configured but PUBLIC_URL isn't. Email notifications are disabled.");
+            None
+        }
+        Some(smtp) => match email::Mailer::new(smtp) {
+            Ok(mailer) => {
+                println!("📧 Email notifications via {}:{}", smtp.host, smtp.port);
+                Some(Arc::new(mailer))
+            }
+            Err(e) => {
+                tracing::warn!("{}. Email notifications are disabled.", e);
+                None
+            }
+        },
+        None => None,
+    };
+    // Browsers want an https: or mailto: contact in the VAPID claims
+    let push = match push::WebPush::load(&db, public_url.clone().filter(|url| url.starts_with("https://"))).await {
+        Ok(push) => Some(Arc::new(push)),
+        Err(e) => {
+            tracing::warn!("{}. Web Push notifications are disabled.", e);
+            None
+        }
+    };
+    let events = Arc::new(events::EventHub::new(db.clone()));
+    let notifier = Arc::new(notifications::Notifier::new(db.clone(), events.clone(), mailer, push));
+
+    if let Command::ScrapeOnce(source) = command {
+        let sources = scrape_sources(source);
+        if let Some(unknown) = sources.iter().find(|name| scraper_registry.get(name).is_none()) {
+            return Err(format!("Unknown source: {}", unknown).into());
+        }
+
+        let job = ScrapeJob {
+            rawg_key: effective_rawg_key(&db, &rawg_api_key).await,
+            rawg_cache_days,
+            db: db.clone(),
+            scraper_registry,
+            notifier,
+            aggregate_cache: AggregateCache::default(),
+            scrape_status: Arc::new(RwLock::new(ScrapeStatus { is_running: true, ..Default::default() })),
+        };
+        let result = tokio::select! {
+            result = run_scrape(job, sources) => result,
+            _ = shutdown_signal() => Err("Scrape cancelled".to_string()),
+        };
+        db.close().await;
 
-    let delete_archives = std::env::var("DELETE_ARCHIVES")
-        .unwrap_or_else(|_| "false".to_string())
-        .parse::<bool>()
-        .unwrap_or(false);
+        let message = result?;
+        println!("✅ {}", message);
+        return Ok(());
+    }
 
     println!("📂 Download directory: {}", download_dir);
+    if let Some(dir) = &library_dir {
+        println!("📚 Library directory: {} (auto-move after install: {})", dir.display(), auto_archive);
+    }
     println!("📦 Auto-extract: {}", auto_extract);
     println!("🗑️  Delete archives after extraction: {}", delete_archives);
+    println!("⬇️  Max concurrent downloads: {}", max_concurrent);
+    if rate_limit_mbps > 0.0 {
+        println!("🐢 Download rate limit: {} Mbps", rate_limit_mbps);
+    }
+    println!("🚦 API rate limit: {}/min per IP, {}/min per session (0 = unlimited)", rate_limit_per_minute, rate_limit_auth_per_minute);
 
-    let rd_client = Arc::new(realdebrid::RealDebridClient::new(rd_api_key));
-    let dl_downloader = Arc::new(downloader::Downloader::new(download_dir.into()));
+    let debrid: Arc<dyn debrid::Debrid> = Arc::new(realdebrid::RealDebridClient::new(rd_api_key.clone()));
+    let dl_downloader = Arc::new(downloader::Downloader::new(download_dir.into(), rate_limit_mbps));
 
     let dm_config = download_manager::DownloadManagerConfig {
         auto_extract,
         delete_archives,
-        max_concurrent: 1,
+        nested_size_factor,
+        max_concurrent,
+        library_dir,
+        auto_archive,
     };
 
     let dm = Arc::new(download_manager::DownloadManager::new(
         db.clone(),
         dl_downloader,
-        rd_client.clone(),
+        debrid.clone(),
         dm_config,
+        events.clone(),
+        notifier.clone(),
     ));
 
-    // Resume any queued downloads from previous session
+    // Resume any queued downloads from previous session, including ones the
+    // last run was killed in the middle of
+    if let Err(e) = dm.recover_interrupted().await {
+        tracing::error!("Failed to recover interrupted downloads: {}", e);
+    }
     dm.try_process_queue().await;
 
-    // Initialize scraper registry
-    let mut scraper_registry = scrapers::registry::ScraperRegistry::new();
-    scraper_registry.register(Arc::new(scrapers::fitgirl::FitGirlScraper::new()));
-    scraper_registry.register(Arc::new(scrapers::steamrip::SteamRipScraper::new()));
-    let scraper_registry = Arc::new(scraper_registry);
-
     // Create client download manager (new architecture)
     let client_dm = Arc::new(client_downloads::ClientDownloadManager::new(
         db.clone(),
-        rd_client.clone(),
+        debrid.clone(),
+        events.clone(),
+        notifier.clone(),
     ));
 
+    println!("🖼️  Thumbnail cache: {}", thumbnail_dir.display());
+
+    let shutdown = CancellationToken::new();
+
     let state = AppState {
         db: db.clone(),
-        rd_client,
+        debrid,
         scrape_status: Arc::new(RwLock::new(ScrapeStatus::default())),
         download_manager: dm,
         client_download_manager: client_dm,
+        events,
+        notifier,
+        aggregate_cache: AggregateCache::default(),
+        debrid_health: health::DebridCheckCache::default(),
+        thumbnails: Arc::new(thumbnails::ThumbnailCache::new(thumbnail_dir)),
+        rate_limiter: Arc::new(rate_limit::RateLimiter::new(rate_limit_per_minute, rate_limit_auth_per_minute)),
+        session_refresher: Arc::new(session_refresh::SessionRefresher::default()),
+        view_counter: Arc::new(view_counter::ViewCounter::default()),
+        trust_proxy,
+        public_url,
+        shutdown: shutdown.clone(),
+        session_config,
+        download_quota,
+        client_offline_secs,
         rawg_api_key,
+        rawg_cache_days,
+        rd_api_key,
         scraper_registry,
     };
 
@@ -234,14 +494,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .route("/api/auth/login", post(auth_login))
         .route("/api/auth/logout", post(auth_logout))
         .route("/api/auth/me", get(auth_me))
+        .route("/api/auth/email", post(auth_set_email))
+        .route("/api/auth/verify-email", get(auth_verify_email))
         // Existing routes
         .route("/api/games", get(get_games))
         .route("/api/games/:id", get(get_game_detail))
+        .route("/api/thumbnails/:game_id", get(get_thumbnail))
         .route("/api/games/genres", get(get_genres))
         .route("/api/games/tags", get(get_tags))
         .route("/api/games/:id/tags", post(add_tag))
+        .route("/api/games/:id/rating", post(rate_game))
         .route("/api/games/:id/tags/:tag", delete(remove_tag))
         .route("/api/games/random", get(get_random_game))
+        .route("/api/games/new", get(get_new_games))
         .route("/api/games/featured", get(get_featured_games))
         .route("/api/games/favorites", get(get_favorites))
         // Notifications
@@ -249,29 +514,66 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .route("/api/notifications/count", get(get_notification_count))
         .route("/api/notifications/:id/read", post(mark_notification_read_handler))
         .route("/api/notifications/read-all", post(mark_all_notifications_read_handler))
+        .route("/api/notifications/telegram/test", post(test_telegram_notification))
+        .route("/api/push/public-key", get(get_push_public_key))
+        .route("/api/push/subscribe", post(push_subscribe))
+        .route("/api/push/unsubscribe", post(push_unsubscribe))
+        .route("/api/ws", get(ws_handler))  // Live notification counts and download status changes
         .route("/api/games/favorites/:id", post(add_favorite))
         .route("/api/games/favorites/:id", delete(remove_favorite))
+        .route("/api/games/wishlist", get(get_wishlist))
+        .route("/api/games/wishlist/:id", post(add_to_wishlist))
+        .route("/api/games/wishlist/:id", delete(remove_from_wishlist))
+        .route("/api/games/hidden", get(get_hidden_games))
+        .route("/api/games/hidden/:id", post(hide_game))
+        .route("/api/games/hidden/:id", delete(unhide_game))
+        .route("/api/searches", get(get_saved_searches))
+        .route("/api/searches", post(save_search))
+        .route("/api/searches/:id", delete(delete_saved_search))
+        .route("/api/searches/:id/games", get(run_saved_search))
         .route("/api/games/upload", post(upload_csv))
+        .route("/api/games/export.csv", get(export_games_csv))
+        .route("/api/games/export.json", get(export_games_json))
+        .route("/api/games/import.json", post(import_games_json).layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT)))
         .route("/api/games/rescrape", post(rescrape))
         .route("/api/scrape-status", get(get_scrape_status))
         .route("/api/sources", get(get_sources))
         .route("/api/realdebrid/add", post(add_to_realdebrid))
+        .route("/api/realdebrid/account", get(get_realdebrid_account))
         // Download management routes
         .route("/api/downloads", get(get_downloads))
+        .route("/api/downloads/history", get(get_download_history))
         .route("/api/downloads", post(queue_download))
         .route("/api/downloads/create", post(create_client_download))  // NEW: Create download for client architecture
         .route("/api/downloads/:id", get(get_download_status))
         .route("/api/downloads/:id", delete(cancel_download))
         .route("/api/downloads/:id/retry", post(retry_download))
+        .route("/api/downloads/:id/password", post(set_download_password))
+        .route("/api/downloads/:id/assign", post(assign_download))
         .route("/api/downloads/:id/remove", delete(remove_download))
         .route("/api/downloads/:id/progress", post(update_download_progress))  // NEW: Update progress from client
         .route("/api/downloads/:id/install", post(launch_install))
         .route("/api/downloads/:id/installed", post(mark_installed))
+        .route("/api/downloads/:id/archive", post(archive_download))
+        .route("/api/downloads/:id/reextract", post(reextract_download))
+        .route("/api/downloads/:id/install-dir", post(set_install_dir))
         .route("/api/downloads/:id/validate", post(validate_download))
+        .route("/api/downloads/:id/generate-md5", post(generate_download_md5))
         .route("/api/downloads/:id/delete", delete(delete_download))
         .route("/api/downloads/scan", post(scan_existing_games))
         .route("/api/downloads/files/:file_id", get(download_file))
+        .route("/api/downloads/batch", post(queue_download_batch))
         .route("/api/downloads/queue", get(get_client_download_queue))  // NEW: Get downloads for client
+        .route("/api/downloads/stream", get(stream_downloads))  // Live download updates (SSE)
+        .route("/api/downloads/:id/extract-stream", get(stream_extract_progress))  // Live extraction progress (SSE)
+        // Admin backup/restore
+        .route("/api/admin/backup", get(backup_database))
+        .route("/api/admin/restore", post(restore_database).layer(DefaultBodyLimit::disable()))
+        .route("/api/admin/blacklist", get(get_blacklist))
+        .route("/api/admin/blacklist", post(add_to_blacklist))
+        .route("/api/admin/blacklist/:id", delete(remove_from_blacklist))
+        .route("/api/admin/tags/rename", post(rename_tag))
+        .route("/api/admin/tags/merge", post(merge_tags))
         // Settings routes
         .route("/api/settings", get(get_settings))
         .route("/api/settings", post(save_settings))
@@ -293,81 +595,221 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .route("/api/clients/:client_id/queue", get(get_client_queue))
         .route("/api/clients/:client_id/progress", post(update_client_progress))
         .route("/api/clients/:client_id/system-info", post(update_client_system_info))
+        .route("/api/clients/:client_id/installed", post(report_client_installed))
+        .route("/api/clients/:client_id/commands", get(get_client_commands))
+        .route("/api/clients/:client_id/heartbeat", post(client_heartbeat))
+        .route("/api/clients/:client_id/commands/:command_id/ack", post(ack_client_command))
         .route("/api/clients", get(get_all_clients))
         .route("/api/clients/mine", get(get_my_clients))  // Get current user's linked clients
         .route("/api/clients/:client_id/link", post(link_client_to_user))  // Link client to current user
         .route("/api/clients/:client_id/unlink", post(unlink_client_from_user))  // Unlink client
         .route("/api/clients/status", get(get_user_client_status))  // Check if user has connected client
+        // Client agent updates
+        .route("/api/client/latest-version", get(get_client_latest_version))
+        .route("/api/client/download", get(download_client_binary))
+        .route("/api/client/resolve-links", post(resolve_client_links))
         // Health check
         .route("/api/health", get(health_check))
+        // API reference (Swagger UI and the raw OpenAPI document)
+        .merge(SwaggerUi::new("/api/docs").url("/api/openapi.json", openapi::ApiDoc::openapi()))
         // Static files
         .nest_service("/", ServeDir::new(frontend_dir))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), rate_limit))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), refresh_session))
         .layer(CorsLayer::permissive())
-        .with_state(state);
-
-    // Spawn periodic session cleanup task (every hour)
+        // gzip/brotli for clients that send Accept-Encoding; game lists shrink a lot
+        .layer(CompressionLayer::new())
+        // One log line per request with method, path, status and latency
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(DefaultMakeSpan::new().level(tracing::Level::INFO))
+                .on_response(DefaultOnResponse::new().level(tracing::Level::INFO).latency_unit(LatencyUnit::Millis)),
+        )
+        .with_state(state.clone());
+
+    // Spawn periodic session and notification cleanup task (every hour)
     let cleanup_db = db.clone();
+    let cleanup_shutdown = shutdown.clone();
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
         loop {
-            interval.tick().await;
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = cleanup_shutdown.cancelled() => break,
+            }
             if let Err(e) = db::cleanup_expired_sessions(&cleanup_db).await {
-                eprintln!("Session cleanup error: {}", e);
+                tracing::error!("Session cleanup error: {}", e);
+            }
+            if let Err(e) = db::delete_expired_email_verifications(&cleanup_db).await {
+                tracing::error!("Email verification cleanup error: {}", e);
+            }
+            // Earlier days' counters are done with; this is what resets the daily download cap
+            if let Err(e) = db::prune_download_usage(&cleanup_db, &quota::today()).await {
+                tracing::error!("Download usage cleanup error: {}", e);
+            }
+            if notification_retention_days > 0 {
+                match db::prune_read_notifications(&cleanup_db, notification_retention_days).await {
+                    Ok(0) => {}
+                    Ok(n) => println!("🧹 Pruned {} read notification(s) older than {} days", n, notification_retention_days),
+                    Err(e) => tracing::error!("Notification cleanup error: {}", e),
+                }
+            }
+        }
+    });
+
+    // Mark clients offline once they miss their heartbeats, and tell owners
+    // when their clients go offline or come back
+    let heartbeat_state = state.clone();
+    tokio::spawn(async move {
+        let period = std::time::Duration::from_secs((client_offline_secs as u64 / 2).clamp(5, 60));
+        let mut interval = tokio::time::interval(period);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = heartbeat_state.shutdown.cancelled() => break,
+            }
+            if let Err(e) = heartbeat_state.client_download_manager.check_client_status(client_offline_secs).await {
+                tracing::error!("Client heartbeat check error: {}", e);
+            }
+        }
+    });
+
+    // Hourly cleanup of old finished downloads; does nothing until enabled in settings
+    let download_cleanup_state = state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = download_cleanup_state.shutdown.cancelled() => break,
+            }
+            let download_dir = download_cleanup_state.download_manager.download_dir();
+            if let Err(e) = download_cleanup::run(&download_cleanup_state.db, download_dir).await {
+                tracing::error!("Download cleanup error: {}", e);
             }
         }
     });
 
-    let addr = "0.0.0.0:3000";
+    // Rescrape every source on a fixed schedule when scrape_interval_hours is set
+    if scrape_interval_hours > 0 {
+        println!("🔄 Automatic rescrape every {} hour(s)", scrape_interval_hours);
+        let scrape_state = state.clone();
+        tokio::spawn(async move {
+            let period = std::time::Duration::from_secs(scrape_interval_hours * 3600);
+            let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = scrape_state.shutdown.cancelled() => break,
+                }
+                let params = RescrapeParams { source: None };
+                if let Err(e) = rescrape(State(scrape_state.clone()), Query(params)).await {
+                    println!("Skipping scheduled rescrape: {}", e.message());
+                }
+            }
+        });
+    }
+
+    let addr = format!("0.0.0.0:{}", port);
     println!("🚀 Server running on http://{}", addr);
     println!("📊 Frontend available at http://{}", addr);
 
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move {
+            shutdown_signal().await;
+            shutdown.cancel();
+            println!("🛑 Shutting down, waiting for open requests to finish...");
+        }
+    });
+
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+        .with_graceful_shutdown(shutdown.clone().cancelled_owned())
+        .await?;
+
+    // Workers still downloading stop at their next await; their rows stay
+    // 'downloading' and are re-queued by recover_interrupted on the next start.
+    // Closing the pool checkpoints the WAL so the database file is complete.
+    db.close().await;
+    println!("👋 Stopped");
 
     Ok(())
 }
 
+/// What the binary was asked to do on the command line
+enum Command {
+    /// Run the web server (no arguments)
+    Serve,
+    /// `--scrape-once [source]`: scrape into the database and exit without
+    /// starting the server, e.g. from cron or to seed a database in CI
+    ScrapeOnce(Option<String>),
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    const USAGE: &str = "Usage: repack-browser [--scrape-once [fitgirl|steamrip|all]]";
+
+    let command = match args.next().as_deref() {
+        None => Command::Serve,
+        Some("--scrape-once") => Command::ScrapeOnce(args.next()),
+        Some("-h" | "--help") => {
+            println!("{}", USAGE);
+            std::process::exit(0);
+        }
+        Some(other) => return Err(format!("Unknown argument: {}\n{}", other, USAGE)),
+    };
+
+    match args.next() {
+        None => Ok(command),
+        Some(extra) => Err(format!("Unexpected argument: {}\n{}", extra, USAGE)),
+    }
+}
+
+/// Resolves on Ctrl-C, or SIGTERM from `docker stop` and service managers
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
 // ─── Authentication endpoints ───
 
 async fn auth_register(
     State(state): State<AppState>,
     Json(req): Json<RegisterRequest>,
-) -> Result<(StatusCode, HeaderMap, Json<AuthResponse>), StatusCode> {
+) -> Result<(StatusCode, HeaderMap, Json<AuthResponse>), ApiError> {
     // Validate input
     if req.username.trim().is_empty() || req.password.is_empty() {
-        return Ok((
-            StatusCode::BAD_REQUEST,
-            HeaderMap::new(),
-            Json(AuthResponse {
-                success: false,
-                message: "Username and password are required".to_string(),
-                user: None,
-            }),
-        ));
+        return Err(ApiError::bad_request("Username and password are required"));
     }
 
     if req.username.len() < 3 {
-        return Ok((
-            StatusCode::BAD_REQUEST,
-            HeaderMap::new(),
-            Json(AuthResponse {
-                success: false,
-                message: "Username must be at least 3 characters".to_string(),
-                user: None,
-            }),
-        ));
+        return Err(ApiError::bad_request("Username must be at least 3 characters"));
     }
 
     if req.password.len() < 6 {
-        return Ok((
-            StatusCode::BAD_REQUEST,
-            HeaderMap::new(),
-            Json(AuthResponse {
-                success: false,
-                message: "Password must be at least 6 characters".to_string(),
-                user: None,
-            }),
-        ));
+        return Err(ApiError::bad_request("Password must be at least 6 characters"));
     }
 
     // Create user (is_admin = false for regular registration)
@@ -376,35 +818,28 @@ async fn auth_register(
         Err(e) => {
             let msg = format!("{}", e);
             if msg.contains("UNIQUE constraint failed") {
-                return Ok((
-                    StatusCode::CONFLICT,
-                    HeaderMap::new(),
-                    Json(AuthResponse {
-                        success: false,
-                        message: "Username already exists".to_string(),
-                        user: None,
-                    }),
-                ));
+                return Err(ApiError::conflict("Username already exists"));
             }
-            eprintln!("Error creating user: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            tracing::error!("Error creating user: {}", e);
+            return Err(ApiError::internal("Failed to create account"));
         }
     };
 
     // Create session
-    let session_token = db::create_session(&state.db, user_id)
+    let session_token = db::create_session(&state.db, user_id, state.session_config.expiry_days())
         .await
         .map_err(|e| {
-            eprintln!("Error creating session: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
+            tracing::error!("Error creating session: {}", e);
+            ApiError::internal("Failed to create session")
         })?;
 
+    let user = db::get_user_by_session(&state.db, &session_token)
+        .await?
+        .ok_or_else(|| ApiError::internal("New session not found"))?;
+
     // Set cookie
     let mut headers = HeaderMap::new();
-    let cookie = format!(
-        "session={}; HttpOnly; Path=/; Max-Age=2592000; SameSite=Lax",
-        session_token
-    );
+    let cookie = state.session_config.cookie(&session_token);
     headers.insert(SET_COOKIE, cookie.parse().unwrap());
 
     Ok((
@@ -413,11 +848,7 @@ async fn auth_register(
         Json(AuthResponse {
             success: true,
             message: "Account created successfully".to_string(),
-            user: Some(UserInfo {
-                id: user_id,
-                username: req.username,
-                is_admin: false,
-            }),
+            user: Some(user.into()),
         }),
     ))
 }
@@ -425,41 +856,30 @@ async fn auth_register(
 async fn auth_login(
     State(state): State<AppState>,
     Json(req): Json<LoginRequest>,
-) -> Result<(StatusCode, HeaderMap, Json<AuthResponse>), StatusCode> {
+) -> Result<(StatusCode, HeaderMap, Json<AuthResponse>), ApiError> {
     // Verify credentials
     let user = match db::verify_user(&state.db, &req.username, &req.password).await {
         Ok(Some(user)) => user,
         Ok(None) => {
-            return Ok((
-                StatusCode::UNAUTHORIZED,
-                HeaderMap::new(),
-                Json(AuthResponse {
-                    success: false,
-                    message: "Invalid username or password".to_string(),
-                    user: None,
-                }),
-            ));
+            return Err(ApiError::unauthorized("Invalid username or password"));
         }
         Err(e) => {
-            eprintln!("Error verifying user: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            tracing::error!("Error verifying user: {}", e);
+            return Err(ApiError::internal("Failed to verify credentials"));
         }
     };
 
     // Create session
-    let session_token = db::create_session(&state.db, user.id)
+    let session_token = db::create_session(&state.db, user.id, state.session_config.expiry_days())
         .await
         .map_err(|e| {
-            eprintln!("Error creating session: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
+            tracing::error!("Error creating session: {}", e);
+            ApiError::internal("Failed to create session")
         })?;
 
     // Set cookie
     let mut headers = HeaderMap::new();
-    let cookie = format!(
-        "session={}; HttpOnly; Path=/; Max-Age=2592000; SameSite=Lax",
-        session_token
-    );
+    let cookie = state.session_config.cookie(&session_token);
     headers.insert(SET_COOKIE, cookie.parse().unwrap());
 
     Ok((
@@ -468,11 +888,7 @@ async fn auth_login(
         Json(AuthResponse {
             success: true,
             message: "Login successful".to_string(),
-            user: Some(UserInfo {
-                id: user.id,
-                username: user.username,
-                is_admin: user.is_admin,
-            }),
+            user: Some(user.into()),
         }),
     ))
 }
@@ -480,7 +896,7 @@ async fn auth_login(
 async fn auth_logout(
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> Result<(StatusCode, HeaderMap, Json<AuthResponse>), StatusCode> {
+) -> Result<(StatusCode, HeaderMap, Json<AuthResponse>), ApiError> {
     // Extract session token from cookie
     if let Some(session_token) = extract_session_token(&headers) {
         // Delete session from database
@@ -489,7 +905,7 @@ async fn auth_logout(
 
     // Clear cookie
     let mut response_headers = HeaderMap::new();
-    let cookie = "session=; HttpOnly; Path=/; Max-Age=0; SameSite=Lax";
+    let cookie = state.session_config.clear_cookie();
     response_headers.insert(SET_COOKIE, cookie.parse().unwrap());
 
     Ok((
@@ -506,7 +922,7 @@ async fn auth_logout(
 async fn auth_me(
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> Result<Json<AuthResponse>, StatusCode> {
+) -> Result<Json<AuthResponse>, ApiError> {
     // Extract session token from cookie
     let session_token = match extract_session_token(&headers) {
         Some(token) => token,
@@ -530,37 +946,177 @@ async fn auth_me(
             }));
         }
         Err(e) => {
-            eprintln!("Error getting user by session: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            tracing::error!("Error getting user by session: {}", e);
+            return Err(ApiError::internal("Failed to look up session"));
         }
     };
 
     Ok(Json(AuthResponse {
         success: true,
         message: "Authenticated".to_string(),
-        user: Some(UserInfo {
-            id: user.id,
-            username: user.username,
-            is_admin: user.is_admin,
-        }),
+        user: Some(user.into()),
+    }))
+}
+
+#[derive(Deserialize)]
+struct SetEmailRequest {
+    /// Empty removes the address
+    email: String,
+}
+
+/// Set the user's email address and send it a verification link.
+/// Notifications are only emailed once the address is verified.
+async fn auth_set_email(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<SetEmailRequest>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+
+    let email = payload.email.trim();
+    if email.is_empty() {
+        db::set_user_email(&state.db, user.id, None).await.map_err(ApiError::internal)?;
+        return Ok(Json(ApiResponse {
+            success: true,
+            message: "Email address removed".to_string(),
+            downloads: None,
+            download_id: None,
+        }));
+    }
+
+    let mailer = state.notifier.mailer()
+        .ok_or(ApiError::bad_request("Email isn't set up on this server. Ask an admin to configure SMTP."))?;
+    // The mailer is only set up when PUBLIC_URL is
+    let base_url = state.public_url.as_deref()
+        .ok_or(ApiError::bad_request("Email isn't set up on this server. Ask an admin to configure SMTP."))?;
+    if !email::is_valid_address(email) {
+        return Err(ApiError::bad_request("That doesn't look like an email address"));
+    }
+
+    db::set_user_email(&state.db, user.id, Some(email)).await.map_err(ApiError::internal)?;
+    let token = db::create_email_verification(&state.db, user.id, email, email::VERIFICATION_TTL_HOURS)
+        .await
+        .map_err(ApiError::internal)?;
+    let link = format!("{}/api/auth/verify-email?token={}", base_url, token);
+
+    mailer.send(
+        email,
+        "Verify your email address",
+        &format!(
+            "Hi {},\n\nOpen this link to start receiving your Repack Browser notifications at this address:\n\n{}\n\nThe link expires in {} hours. If you didn't ask for this, ignore this email.",
+            user.username, link, email::VERIFICATION_TTL_HOURS
+        ),
+    )
+    .await
+    .map_err(|e| ApiError::new(StatusCode::BAD_GATEWAY, format!("Couldn't send the verification email: {}", e)))?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: format!("Verification email sent to {}", email),
+        downloads: None,
+        download_id: None,
     }))
 }
 
+#[derive(Deserialize)]
+struct VerifyEmailQuery {
+    token: String,
+}
+
+/// Target of the link in verification emails. Works without a session, since
+/// the link may be opened on another device, and sends the browser back to
+/// the app with the outcome.
+async fn auth_verify_email(
+    State(state): State<AppState>,
+    Query(query): Query<VerifyEmailQuery>,
+) -> Result<axum::response::Redirect, ApiError> {
+    let verified = db::verify_email(&state.db, &query.token).await.map_err(ApiError::internal)?;
+    Ok(axum::response::Redirect::to(if verified { "/?email_verified=true" } else { "/?email_verified=false" }))
+}
+
 // Helper function to extract session token from cookie header
 fn extract_session_token(headers: &HeaderMap) -> Option<String> {
-    headers
-        .get(COOKIE)?
-        .to_str()
-        .ok()?
-        .split(';')
-        .find_map(|cookie| {
-            let parts: Vec<&str> = cookie.trim().splitn(2, '=').collect();
-            if parts.len() == 2 && parts[0] == "session" {
-                Some(parts[1].to_string())
-            } else {
-                None
+    cookies::get(headers, "session")
+}
+
+/// The caller's IP, taken from X-Forwarded-For when behind a trusted proxy
+fn client_ip(state: &AppState, headers: &HeaderMap, addr: std::net::SocketAddr) -> String {
+    state.trust_proxy
+        .then(|| headers.get("x-forwarded-for"))
+        .flatten()
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(',').next())
+        .map(|ip| ip.trim().to_string())
+        .unwrap_or_else(|| addr.ip().to_string())
+}
+
+/// Keep sessions that are in use alive by pushing their expiry back, at most
+/// hourly per session and never past the maximum session age. The cookie
+/// already lasts that long, so it isn't re-issued.
+async fn refresh_session(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let config = state.session_config;
+    let token = (config.idle_timeout_days > 0 && request.uri().path().starts_with("/api/"))
+        .then(|| extract_session_token(request.headers()))
+        .flatten();
+    if let Some(token) = token.filter(|token| state.session_refresher.due(token)) {
+        if let Err(e) = db::refresh_session(&state.db, &token, config.expiry_days(), config.lifetime_days).await {
+            tracing::error!("Failed to refresh session: {}", e);
+        }
+    }
+
+    next.run(request).await
+}
+
+/// Limit API requests per session (when logged in) or per IP. Static files and
+/// the health check are never limited.
+async fn rate_limit(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<std::net::SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path();
+    if !path.starts_with("/api/") || path == "/api/health" {
+        return next.run(request).await;
+    }
+
+    let limiter = &state.rate_limiter;
+    let token = extract_session_token(request.headers());
+
+    // Sessions known to belong to a user get their own window. Any other
+    // cookie is charged to the IP, so made-up tokens can't dodge the limit.
+    let result = match token {
+        Some(token) if limiter.is_known_session(&token) => {
+            limiter.check(&format!("session:{}", token), limiter.authenticated_per_minute)
+        }
+        token => {
+            let ip_key = format!("ip:{}", client_ip(&state, request.headers(), addr));
+            let result = limiter.check(&ip_key, limiter.anonymous_per_minute);
+            // Look the token up only once the request has been let through
+            if let (Ok(()), Some(token)) = (&result, token) {
+                if let Ok(Some(_)) = db::get_user_by_session(&state.db, &token).await {
+                    limiter.remember_session(&token);
+                }
             }
-        })
+            result
+        }
+    };
+
+    match result {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => (
+            [(header::RETRY_AFTER, retry_after.to_string())],
+            ApiError::new(
+                StatusCode::TOO_MANY_REQUESTS,
+                format!("Too many requests, try again in {} seconds", retry_after),
+            ),
+        ).into_response(),
+    }
 }
 
 // Helper function to get current user from session
@@ -574,78 +1130,341 @@ async fn get_current_user(db: &SqlitePool, headers: &HeaderMap) -> Result<db::Us
         .ok_or("Invalid or expired session".to_string())
 }
 
+/// Fails unless `user` is an admin or owns download `id`, either as a client
+/// download or through `user_downloads` for server downloads
+async fn require_download_access(state: &AppState, user: &db::User, id: i64) -> Result<(), ApiError> {
+    let owned: Option<(bool,)> = sqlx::query_as(
+        "SELECT d.user_id IS ? OR EXISTS (SELECT 1 FROM user_downloads ud WHERE ud.download_id = d.id AND ud.user_id = ?)
+         FROM downloads d WHERE d.id = ?"
+    )
+    .bind(user.id)
+    .bind(user.id)
+    .bind(id)
+    .fetch_optional(&state.db)
+    .await?;
+    match owned {
+        None => Err(ApiError::not_found("Download not found")),
+        Some((false,)) if !user.is_admin => Err(ApiError::forbidden("This download is not yours")),
+        Some(_) => Ok(()),
+    }
+}
+
 // ─── Game endpoints ───
 
+#[utoipa::path(
+    get,
+    path = "/api/games",
+    tag = "games",
+    params(db::GameQuery),
+    responses(
+        (status = 200, description = "One page of games", body = GamesResponse, headers(
+            ("X-Total-Count" = i64, description = "Games matching the filters"),
+            ("Link" = String, description = "`first`, `prev`, `next` and `last` page URLs"),
+        )),
+        (status = 400, description = "Invalid paging or hidden values", body = api_error::ErrorBody),
+        (status = 401, description = "`wishlist=true` or `hidden=only` without a login", body = api_error::ErrorBody),
+    )
+)]
 async fn get_games(
     State(state): State<AppState>,
-    Query(query): Query<db::GameQuery>,
-) -> Result<Json<GamesResponse>, StatusCode> {
-    let per_page = query.per_page.unwrap_or(50);
-    let page = query.page.unwrap_or(1);
+    uri: Uri,
+    headers: HeaderMap,
+    Query(mut query): Query<db::GameQuery>,
+) -> Result<(HeaderMap, Json<GamesResponse>), ApiError> {
+    resolve_user_filters(&state, &headers, &mut query).await?;
+
+    let (page, per_page) = db::page_bounds(query.page, query.per_page, 50, db::MAX_PER_PAGE)
+        .map_err(ApiError::bad_request)?;
 
     let (games, total) = db::query_games(&state.db, query)
         .await
         .map_err(|e| {
-            eprintln!("Error querying games: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
+            tracing::error!("Error querying games: {}", e);
+            ApiError::internal("Failed to query games")
         })?;
 
     let total_pages = (total as f64 / per_page as f64).ceil() as i64;
-    Ok(Json(GamesResponse {
-        games,
-        total,
-        page,
-        per_page,
-        total_pages,
-    }))
+    Ok((
+        pagination::headers(&uri, page, per_page, total),
+        Json(GamesResponse {
+            games,
+            total,
+            page,
+            per_page,
+            total_pages,
+        }),
+    ))
 }
 
-// ─── Game Detail ───
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct NewGamesParams {
+    /// "last_login" (the default) for the login before the current one, or an RFC 3339 time
+    since: Option<String>,
+    /// 1-based page number
+    page: Option<i64>,
+    /// Games per page, default 50, capped at 200
+    per_page: Option<i64>,
+}
 
-async fn get_game_detail(
+#[derive(Serialize, ToSchema)]
+struct NewGamesResponse {
+    /// The cutoff used; null on a user's first login, when nothing counts as new
+    since: Option<String>,
+    /// How many games were posted since then, for the badge
+    count: i64,
+    /// Newest first
+    games: Vec<db::Game>,
+    page: i64,
+    per_page: i64,
+    total_pages: i64,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/games/new",
+    tag = "games",
+    params(NewGamesParams),
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "Games posted since the cutoff", body = NewGamesResponse, headers(
+            ("X-Total-Count" = i64, description = "Games posted since the cutoff"),
+            ("Link" = String, description = "`first`, `prev`, `next` and `last` page URLs"),
+        )),
+        (status = 400, description = "Invalid `since` or paging values", body = api_error::ErrorBody),
+        (status = 401, description = "Not logged in", body = api_error::ErrorBody),
+    )
+)]
+async fn get_new_games(
+    State(state): State<AppState>,
+    uri: Uri,
+    headers: HeaderMap,
+    Query(params): Query<NewGamesParams>,
+) -> Result<(HeaderMap, Json<NewGamesResponse>), ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+
+    let (page, per_page) = db::page_bounds(params.page, params.per_page, 50, db::MAX_PER_PAGE)
+        .map_err(ApiError::bad_request)?;
+
+    let since = match params.since.as_deref().filter(|s| !s.is_empty()) {
+        None | Some("last_login") => user.previous_login,
+        Some(other) => Some(other.to_string()),
+    };
+    let Some(since) = since else {
+        return Ok((
+            pagination::headers(&uri, page, per_page, 0),
+            Json(NewGamesResponse {
+                since: None,
+                count: 0,
+                games: Vec::new(),
+                page,
+                per_page,
+                total_pages: 0,
+            }),
+        ));
+    };
+    let cutoff = chrono::DateTime::parse_from_rfc3339(&since)
+        .map_err(|_| ApiError::bad_request("since must be last_login or an RFC 3339 time"))?
+        .with_timezone(&chrono::Utc)
+        .format("%Y-%m-%dT%H:%M:%S")
+        .to_string();
+
+    // Hidden and blacklisted games stay out, as in the main listing
+    let query = db::GameQuery {
+        sort: Some("date_desc".to_string()),
+        page: Some(page),
+        per_page: Some(per_page),
+        user_id: Some(user.id),
+        posted_after: Some(cutoff),
+        ..Default::default()
+    };
+    let (games, count) = db::query_games(&state.db, query)
+        .await
+        .map_err(|e| {
+            tracing::error!("Error querying new games: {}", e);
+            ApiError::internal("Failed to query games")
+        })?;
+
+    Ok((
+        pagination::headers(&uri, page, per_page, count),
+        Json(NewGamesResponse {
+            since: Some(since),
+            count,
+            games,
+            page,
+            per_page,
+            total_pages: (count as f64 / per_page as f64).ceil() as i64,
+        }),
+    ))
+}
+
+// ─── Game Detail ───
+
+#[utoipa::path(
+    get,
+    path = "/api/games/{id}",
+    tag = "games",
+    params(("id" = i64, Path, description = "Game ID")),
+    responses(
+        (status = 200, description = "The game with its community install stats", body = GameDetail),
+        (status = 404, description = "No such game", body = api_error::ErrorBody),
+    )
+)]
+async fn get_game_detail(
     State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<std::net::SocketAddr>,
+    headers: HeaderMap,
     Path(game_id): Path<i64>,
-) -> Result<Json<db::Game>, StatusCode> {
+) -> Result<Json<GameDetail>, ApiError> {
     let game = db::get_game_by_id(&state.db, game_id)
         .await
         .map_err(|e| {
-            eprintln!("Error fetching game {}: {}", game_id, e);
-            StatusCode::NOT_FOUND
+            tracing::error!("Error fetching game {}: {}", game_id, e);
+            ApiError::not_found("Game not found")
         })?;
 
-    Ok(Json(game))
+    let viewer = match extract_session_token(&headers) {
+        Some(token) => format!("session:{}", token),
+        None => format!("ip:{}", client_ip(&state, &headers, addr)),
+    };
+    if state.view_counter.should_count(&viewer, game_id) {
+        // Counted in the background so the response doesn't wait on a write
+        let pool = state.db.clone();
+        tokio::spawn(async move {
+            if let Err(e) = db::increment_view_count(&pool, game_id).await {
+                tracing::error!("Failed to count view of game {}: {}", game_id, e);
+            }
+        });
+    }
+    let community = db::get_game_rating_stats(&state.db, game_id).await?;
+
+    Ok(Json(GameDetail { game, community }))
+}
+
+#[derive(Deserialize, ToSchema)]
+struct RateGameRequest {
+    /// Whether the game installed successfully
+    install_success: bool,
+    /// 1 (easy) to 5 (hard)
+    install_difficulty: Option<i64>,
+    /// Free-text description of any problems
+    issues_encountered: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/games/{id}/rating",
+    tag = "games",
+    params(("id" = i64, Path, description = "Game ID")),
+    request_body = RateGameRequest,
+    responses(
+        (status = 200, description = "Updated community stats for the game; rating again replaces your earlier rating", body = db::GameRatingStats),
+        (status = 400, description = "Difficulty out of range", body = api_error::ErrorBody),
+        (status = 401, description = "Not logged in", body = api_error::ErrorBody),
+        (status = 404, description = "No such game", body = api_error::ErrorBody),
+    ),
+    security(("session" = []))
+)]
+async fn rate_game(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(game_id): Path<i64>,
+    Json(req): Json<RateGameRequest>,
+) -> Result<Json<db::GameRatingStats>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+
+    if req.install_difficulty.is_some_and(|d| !(1..=5).contains(&d)) {
+        return Err(ApiError::bad_request("install_difficulty must be between 1 and 5"));
+    }
+    db::get_game_by_id(&state.db, game_id)
+        .await
+        .map_err(|_| ApiError::not_found("Game not found"))?;
+
+    let issues = req.issues_encountered
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    db::upsert_community_rating(&state.db, user.id, game_id, req.install_difficulty, Some(req.install_success), issues)
+        .await?;
+
+    Ok(Json(db::get_game_rating_stats(&state.db, game_id).await?))
+}
+
+/// Serve a game's thumbnail from the on-disk cache, fetching it upstream on first request
+async fn get_thumbnail(
+    State(state): State<AppState>,
+    Path(game_id): Path<i64>,
+) -> Result<Response, ApiError> {
+    let game = db::get_game_by_id(&state.db, game_id)
+        .await
+        .map_err(|_| ApiError::not_found("Game not found"))?;
+    let url = game.thumbnail_url
+        .filter(|u| !u.is_empty())
+        .ok_or_else(|| ApiError::not_found("Game has no thumbnail"))?;
+
+    let (bytes, content_type) = state.thumbnails.get(&url).await.map_err(|e| {
+        tracing::error!("Thumbnail fetch failed for game {} ({}): {}", game_id, url, e);
+        ApiError::new(StatusCode::BAD_GATEWAY, format!("Failed to fetch thumbnail: {}", e))
+    })?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CACHE_CONTROL, "public, max-age=604800")
+        .body(Body::from(bytes))
+        .unwrap())
 }
 
 // ─── Genres ───
 
+#[utoipa::path(
+    get,
+    path = "/api/games/genres",
+    tag = "games",
+    responses(
+        (status = 200, description = "`{ \"genres\": [{ \"name\", \"count\" }] }`", body = Object),
+    )
+)]
 async fn get_genres(
     State(state): State<AppState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    let genres = db::get_all_genres(&state.db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+) -> Result<Json<serde_json::Value>, ApiError> {
+    if let Some(cached) = state.aggregate_cache.get("genres").await {
+        return Ok(Json(cached));
+    }
 
-    Ok(Json(serde_json::json!({
+    let aliases = genres::load_aliases(&state.db).await;
+    let genres = db::get_all_genres(&state.db, &aliases)
+        .await?;
+
+    let response = serde_json::json!({
         "genres": genres.into_iter().map(|(name, count)| {
             serde_json::json!({ "name": name, "count": count })
         }).collect::<Vec<_>>()
-    })))
+    });
+    state.aggregate_cache.insert("genres", response.clone()).await;
+    Ok(Json(response))
 }
 
 // ─── Tags ───
 
 async fn get_tags(
     State(state): State<AppState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, ApiError> {
+    if let Some(cached) = state.aggregate_cache.get("tags").await {
+        return Ok(Json(cached));
+    }
+
     let tags = db::get_all_tags(&state.db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .await?;
 
-    Ok(Json(serde_json::json!({
+    let response = serde_json::json!({
         "tags": tags.into_iter().map(|(name, count)| {
             serde_json::json!({ "name": name, "count": count })
         }).collect::<Vec<_>>()
-    })))
+    });
+    state.aggregate_cache.insert("tags", response.clone()).await;
+    Ok(Json(response))
 }
 
 async fn add_tag(
@@ -653,30 +1472,21 @@ async fn add_tag(
     headers: HeaderMap,
     Path(id): Path<i64>,
     Json(payload): Json<serde_json::Value>,
-) -> Result<Json<ApiResponse>, (StatusCode, Json<ApiResponse>)> {
+) -> Result<Json<ApiResponse>, ApiError> {
     // Require admin
     let user = get_current_user(&state.db, &headers).await
-        .map_err(|e| (StatusCode::UNAUTHORIZED, Json(ApiResponse {
-            success: false, message: e, downloads: None, download_id: None,
-        })))?;
+        .map_err(ApiError::unauthorized)?;
 
     if !user.is_admin {
-        return Err((StatusCode::FORBIDDEN, Json(ApiResponse {
-            success: false, message: "Admin access required".to_string(), downloads: None, download_id: None,
-        })));
+        return Err(ApiError::forbidden("Admin access required"));
     }
 
     let tag = payload.get("tag")
         .and_then(|v| v.as_str())
-        .ok_or_else(|| (StatusCode::BAD_REQUEST, Json(ApiResponse {
-            success: false, message: "Missing tag".to_string(), downloads: None, download_id: None,
-        })))?;
+        .ok_or_else(|| ApiError::bad_request("Missing tag"))?;
 
-    db::add_game_tag(&state.db, id, tag).await.map_err(|e| {
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiResponse {
-            success: false, message: e.to_string(), downloads: None, download_id: None,
-        }))
-    })?;
+    db::add_game_tag(&state.db, id, tag).await.map_err(ApiError::internal)?;
+    state.aggregate_cache.clear().await;
 
     Ok(Json(ApiResponse {
         success: true,
@@ -690,24 +1500,17 @@ async fn remove_tag(
     State(state): State<AppState>,
     headers: HeaderMap,
     Path((id, tag)): Path<(i64, String)>,
-) -> Result<Json<ApiResponse>, (StatusCode, Json<ApiResponse>)> {
+) -> Result<Json<ApiResponse>, ApiError> {
     // Require admin
     let user = get_current_user(&state.db, &headers).await
-        .map_err(|e| (StatusCode::UNAUTHORIZED, Json(ApiResponse {
-            success: false, message: e, downloads: None, download_id: None,
-        })))?;
+        .map_err(ApiError::unauthorized)?;
 
     if !user.is_admin {
-        return Err((StatusCode::FORBIDDEN, Json(ApiResponse {
-            success: false, message: "Admin access required".to_string(), downloads: None, download_id: None,
-        })));
+        return Err(ApiError::forbidden("Admin access required"));
     }
 
-    db::remove_game_tag(&state.db, id, &tag).await.map_err(|e| {
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiResponse {
-            success: false, message: e.to_string(), downloads: None, download_id: None,
-        }))
-    })?;
+    db::remove_game_tag(&state.db, id, &tag).await.map_err(ApiError::internal)?;
+    state.aggregate_cache.clear().await;
 
     Ok(Json(ApiResponse {
         success: true,
@@ -717,32 +1520,132 @@ async fn remove_tag(
     }))
 }
 
+#[derive(Deserialize)]
+struct RenameTagRequest {
+    from: String,
+    to: String,
+}
+
+#[derive(Deserialize)]
+struct MergeTagsRequest {
+    /// Tags folded into `to` and removed
+    from: Vec<String>,
+    to: String,
+}
+
+/// Rename a tag on every game. Renaming onto a tag that's already in use is a
+/// merge, so that needs `/api/admin/tags/merge` instead.
+async fn rename_tag(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<RenameTagRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+    if !user.is_admin {
+        return Err(ApiError::forbidden("Admin access required"));
+    }
+
+    let (from, to) = (req.from.trim(), req.to.trim());
+    if from.is_empty() || to.is_empty() {
+        return Err(ApiError::bad_request("Both from and to are required"));
+    }
+    if from == to {
+        return Err(ApiError::bad_request("from and to are the same tag"));
+    }
+    if !db::tag_exists(&state.db, from).await? {
+        return Err(ApiError::not_found(format!("No games are tagged \"{}\"", from)));
+    }
+    if db::tag_exists(&state.db, to).await? {
+        return Err(ApiError::conflict(format!("\"{}\" is already a tag; merge instead", to)));
+    }
+
+    let games = db::relabel_tags(&state.db, &[from.to_string()], to).await?;
+    state.aggregate_cache.clear().await;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": format!("Renamed \"{}\" to \"{}\" on {} game(s)", from, to, games),
+        "games": games,
+    })))
+}
+
+/// Fold several tags into one on every game
+async fn merge_tags(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<MergeTagsRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+    if !user.is_admin {
+        return Err(ApiError::forbidden("Admin access required"));
+    }
+
+    let to = req.to.trim();
+    if to.is_empty() {
+        return Err(ApiError::bad_request("to is required"));
+    }
+    let mut from: Vec<String> = req.from
+        .iter()
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty() && tag != to)
+        .collect();
+    from.sort();
+    from.dedup();
+    if from.is_empty() {
+        return Err(ApiError::bad_request("from needs at least one tag other than to"));
+    }
+
+    let games = db::relabel_tags(&state.db, &from, to).await?;
+    state.aggregate_cache.clear().await;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": format!("Merged {} tag(s) into \"{}\" on {} game(s)", from.len(), to, games),
+        "games": games,
+    })))
+}
+
 // ─── Notifications ───
 
 async fn get_notifications(
     State(state): State<AppState>,
+    uri: Uri,
     headers: HeaderMap,
-) -> Result<Json<Vec<db::Notification>>, StatusCode> {
+    Query(query): Query<NotificationQuery>,
+) -> Result<(HeaderMap, Json<NotificationsResponse>), ApiError> {
     let user = get_current_user(&state.db, &headers).await
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+        .map_err(ApiError::unauthorized)?;
 
-    let notifications = db::get_user_notifications(&state.db, user.id)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let (page, per_page) = db::page_bounds(query.page, query.per_page, 20, 100)
+        .map_err(ApiError::bad_request)?;
+
+    let (notifications, total) = db::get_user_notifications_paged(&state.db, user.id, page, per_page, query.unread_first)
+        .await?;
 
-    Ok(Json(notifications))
+    let total_pages = (total as f64 / per_page as f64).ceil() as i64;
+    Ok((
+        pagination::headers(&uri, page, per_page, total),
+        Json(NotificationsResponse {
+            notifications,
+            total,
+            page,
+            per_page,
+            total_pages,
+        }),
+    ))
 }
 
 async fn get_notification_count(
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     let user = get_current_user(&state.db, &headers).await
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+        .map_err(ApiError::unauthorized)?;
 
     let count = db::get_unread_notification_count(&state.db, user.id)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .await?;
 
     Ok(Json(serde_json::json!({ "count": count })))
 }
@@ -751,17 +1654,12 @@ async fn mark_notification_read_handler(
     State(state): State<AppState>,
     headers: HeaderMap,
     Path(id): Path<i64>,
-) -> Result<Json<ApiResponse>, (StatusCode, Json<ApiResponse>)> {
+) -> Result<Json<ApiResponse>, ApiError> {
     let user = get_current_user(&state.db, &headers).await
-        .map_err(|e| (StatusCode::UNAUTHORIZED, Json(ApiResponse {
-            success: false, message: e, downloads: None, download_id: None,
-        })))?;
+        .map_err(ApiError::unauthorized)?;
 
-    db::mark_notification_read(&state.db, id, user.id).await.map_err(|e| {
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiResponse {
-            success: false, message: e.to_string(), downloads: None, download_id: None,
-        }))
-    })?;
+    db::mark_notification_read(&state.db, id, user.id).await.map_err(ApiError::internal)?;
+    state.events.notification_count_changed(user.id).await;
 
     Ok(Json(ApiResponse {
         success: true,
@@ -771,20 +1669,121 @@ async fn mark_notification_read_handler(
     }))
 }
 
+/// The VAPID public key browsers subscribe with
+async fn get_push_public_key(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let push = state.notifier.push()
+        .ok_or(ApiError::not_found("Web Push is not available on this server"))?;
+    Ok(Json(serde_json::json!({ "public_key": push.public_key() })))
+}
+
+#[derive(Deserialize)]
+struct PushSubscriptionKeys {
+    p256dh: String,
+    auth: String,
+}
+
+/// A browser `PushSubscription`, as serialized by its `toJSON()`
+#[derive(Deserialize)]
+struct PushSubscribeRequest {
+    endpoint: String,
+    keys: PushSubscriptionKeys,
+}
+
+/// Store this browser's push subscription so notifications reach it while
+/// the app isn't open
+async fn push_subscribe(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<PushSubscribeRequest>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+
+    if !push::is_push_service_endpoint(&payload.endpoint) {
+        return Err(ApiError::bad_request("Push endpoint must be an https:// URL on a browser push service"));
+    }
+    if payload.keys.p256dh.is_empty() || payload.keys.auth.is_empty() {
+        return Err(ApiError::bad_request("Push subscription is missing its keys"));
+    }
+
+    let subscription = db::PushSubscription {
+        endpoint: payload.endpoint,
+        p256dh: payload.keys.p256dh,
+        auth: payload.keys.auth,
+    };
+    db::save_push_subscription(&state.db, user.id, &subscription).await.map_err(ApiError::internal)?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Push notifications enabled".to_string(),
+        downloads: None,
+        download_id: None,
+    }))
+}
+
+#[derive(Deserialize)]
+struct PushUnsubscribeRequest {
+    endpoint: String,
+}
+
+async fn push_unsubscribe(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<PushUnsubscribeRequest>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+
+    db::delete_push_subscription(&state.db, &payload.endpoint, Some(user.id)).await.map_err(ApiError::internal)?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Push notifications disabled".to_string(),
+        downloads: None,
+        download_id: None,
+    }))
+}
+
+/// Send a test message to the user's linked Telegram chat, so they can check
+/// the chat ID before waiting for a real notification
+async fn test_telegram_notification(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ApiResponse>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+
+    let token = notifications::telegram_bot_token(&state.db).await
+        .ok_or(ApiError::bad_request("No Telegram bot is set up. Ask an admin to add a bot token in Settings."))?;
+    let chat_id = db::get_user_settings(&state.db, user.id).await
+        .map_err(ApiError::internal)?
+        .telegram_chat_id
+        .filter(|chat_id| !chat_id.is_empty())
+        .ok_or(ApiError::bad_request("Save your Telegram chat ID first"))?;
+
+    notifications::post_telegram(&token, &chat_id, &format!("Test notification for {}. Telegram notifications are working!", user.username))
+        .await
+        .map_err(|e| ApiError::new(StatusCode::BAD_GATEWAY, format!("Couldn't send the Telegram message: {}", e)))?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Test message sent".to_string(),
+        downloads: None,
+        download_id: None,
+    }))
+}
+
 async fn mark_all_notifications_read_handler(
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> Result<Json<ApiResponse>, (StatusCode, Json<ApiResponse>)> {
+) -> Result<Json<ApiResponse>, ApiError> {
     let user = get_current_user(&state.db, &headers).await
-        .map_err(|e| (StatusCode::UNAUTHORIZED, Json(ApiResponse {
-            success: false, message: e, downloads: None, download_id: None,
-        })))?;
+        .map_err(ApiError::unauthorized)?;
 
-    db::mark_all_notifications_read(&state.db, user.id).await.map_err(|e| {
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiResponse {
-            success: false, message: e.to_string(), downloads: None, download_id: None,
-        }))
-    })?;
+    db::mark_all_notifications_read(&state.db, user.id).await.map_err(ApiError::internal)?;
+    state.events.notification_count_changed(user.id).await;
 
     Ok(Json(ApiResponse {
         success: true,
@@ -794,19 +1793,96 @@ async fn mark_all_notifications_read_handler(
     }))
 }
 
+/// WebSocket pushing `events::UserEvent`s as JSON text messages. The current
+/// unread count is sent on connect so the client doesn't need a separate fetch.
+async fn ws_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Result<Response, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+
+    let events = state.events.subscribe(user.id);
+    let count = db::get_unread_notification_count(&state.db, user.id)
+        .await
+        .unwrap_or(0);
+
+    let shutdown = state.shutdown.clone();
+    Ok(ws.on_upgrade(move |socket| handle_ws(socket, events, count, shutdown)))
+}
+
+async fn handle_ws(
+    mut socket: WebSocket,
+    mut events: tokio::sync::broadcast::Receiver<events::UserEvent>,
+    unread_count: i64,
+    shutdown: CancellationToken,
+) {
+    let initial = events::UserEvent::NotificationCount { count: unread_count };
+    if let Ok(text) = serde_json::to_string(&initial) {
+        if socket.send(Message::Text(text)).await.is_err() {
+            return;
+        }
+    }
+
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => {
+                    let Ok(text) = serde_json::to_string(&event) else { continue };
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                // Missed some events; later ones still carry the current state
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            _ = shutdown.cancelled() => {
+                let _ = socket.send(Message::Close(None)).await;
+                break;
+            }
+        }
+    }
+}
+
 // ─── Featured Games ───
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct FeaturedQuery {
+    /// hot (default), top_week, to_beat, or a random pick otherwise
     category: Option<String>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/games/featured",
+    tag = "games",
+    params(FeaturedQuery),
+    responses(
+        (status = 200, description = "Games for the carousel", body = Vec<db::Game>),
+    )
+)]
 async fn get_featured_games(
     State(state): State<AppState>,
     Query(params): Query<FeaturedQuery>,
-) -> Result<Json<Vec<db::Game>>, StatusCode> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     let category = params.category.as_deref().unwrap_or("hot");
 
+    // Random picks should change on every request, so only ranked lists are cached
+    let cache_key = matches!(category, "hot" | "top_week" | "to_beat")
+        .then(|| format!("featured:{}", category));
+    if let Some(key) = &cache_key {
+        if let Some(cached) = state.aggregate_cache.get(key).await {
+            return Ok(Json(cached));
+        }
+    }
+
     let games = match category {
         "hot" => {
             // Use top_50 category from game_categories table
@@ -816,8 +1892,8 @@ async fn get_featured_games(
                     // Fallback: Most favorited in last 7 days
                     let seven_days_ago = chrono::Utc::now() - chrono::Duration::days(7);
                     let games: Vec<db::Game> = sqlx::query_as(
-                        "SELECT DISTINCT g.id, g.title, g.source, g.file_size, g.magnet_link, g.genres, g.company,
-                         g.original_size, g.thumbnail_url, g.screenshots, g.source_url, g.post_date, g.search_title
+                        "SELECT DISTINCT g.id, g.title, g.source, g.file_size, g.magnet_link, g.link_type, g.genres, g.company,
+                         g.original_size, g.thumbnail_url, g.screenshots, g.source_url, g.post_date, g.search_title, g.view_count
                          FROM games g
                          JOIN user_favorites uf ON g.id = uf.game_id
                          WHERE uf.created_at > ?
@@ -827,22 +1903,20 @@ async fn get_featured_games(
                     )
                     .bind(seven_days_ago.to_rfc3339())
                     .fetch_all(&state.db)
-                    .await
-                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+                    .await?;
 
                     // If less than 10, fill with random games
                     if games.len() < 10 {
                         let mut result = games;
                         let needed = 10 - result.len();
                         let random_games: Vec<db::Game> = sqlx::query_as(
-                            "SELECT id, title, source, file_size, magnet_link, genres, company, original_size,
-                             thumbnail_url, screenshots, source_url, post_date, search_title
+                            "SELECT id, title, source, file_size, magnet_link, link_type, genres, company, original_size,
+                             thumbnail_url, screenshots, source_url, post_date, search_title, view_count
                              FROM games ORDER BY RANDOM() LIMIT ?"
                         )
                         .bind(needed as i64)
                         .fetch_all(&state.db)
-                        .await
-                        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+                        .await?;
                         result.extend(random_games);
                         result
                     } else {
@@ -859,8 +1933,8 @@ async fn get_featured_games(
                     // Fallback: Most downloaded this week (using downloads table)
                     let seven_days_ago = chrono::Utc::now() - chrono::Duration::days(7);
                     let games: Vec<db::Game> = sqlx::query_as(
-                        "SELECT DISTINCT g.id, g.title, g.source, g.file_size, g.magnet_link, g.genres, g.company,
-                         g.original_size, g.thumbnail_url, g.screenshots, g.source_url, g.post_date, g.search_title
+                        "SELECT DISTINCT g.id, g.title, g.source, g.file_size, g.magnet_link, g.link_type, g.genres, g.company,
+                         g.original_size, g.thumbnail_url, g.screenshots, g.source_url, g.post_date, g.search_title, g.view_count
                          FROM games g
                          JOIN downloads d ON g.id = d.game_id
                          WHERE d.created_at > ?
@@ -870,21 +1944,19 @@ async fn get_featured_games(
                     )
                     .bind(seven_days_ago.to_rfc3339())
                     .fetch_all(&state.db)
-                    .await
-                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+                    .await?;
 
                     if games.len() < 10 {
                         let mut result = games;
                         let needed = 10 - result.len();
                         let random_games: Vec<db::Game> = sqlx::query_as(
-                            "SELECT id, title, source, file_size, magnet_link, genres, company, original_size,
-                             thumbnail_url, screenshots, source_url, post_date, search_title
+                            "SELECT id, title, source, file_size, magnet_link, link_type, genres, company, original_size,
+                             thumbnail_url, screenshots, source_url, post_date, search_title, view_count
                              FROM games ORDER BY RANDOM() LIMIT ?"
                         )
                         .bind(needed as i64)
                         .fetch_all(&state.db)
-                        .await
-                        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+                        .await?;
                         result.extend(random_games);
                         result
                     } else {
@@ -896,8 +1968,8 @@ async fn get_featured_games(
         "to_beat" => {
             // Small games (<10GB) with high favorites
             sqlx::query_as(
-                "SELECT g.id, g.title, g.source, g.file_size, g.magnet_link, g.genres, g.company,
-                 g.original_size, g.thumbnail_url, g.screenshots, g.source_url, g.post_date, g.search_title
+                "SELECT g.id, g.title, g.source, g.file_size, g.magnet_link, g.link_type, g.genres, g.company,
+                 g.original_size, g.thumbnail_url, g.screenshots, g.source_url, g.post_date, g.search_title, g.view_count
                  FROM games g
                  LEFT JOIN user_favorites uf ON g.id = uf.game_id
                  WHERE g.file_size LIKE '%GB'
@@ -907,44 +1979,52 @@ async fn get_featured_games(
                  LIMIT 10"
             )
             .fetch_all(&state.db)
-            .await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .await?
         },
         "surprise" => {
             // Random selection
             sqlx::query_as(
-                "SELECT id, title, source, file_size, magnet_link, genres, company, original_size,
-                 thumbnail_url, screenshots, source_url, post_date, search_title
+                "SELECT id, title, source, file_size, magnet_link, link_type, genres, company, original_size,
+                 thumbnail_url, screenshots, source_url, post_date, search_title, view_count
                  FROM games ORDER BY RANDOM() LIMIT 10"
             )
             .fetch_all(&state.db)
-            .await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .await?
         },
         _ => {
             // Default to random
             sqlx::query_as(
-                "SELECT id, title, source, file_size, magnet_link, genres, company, original_size,
-                 thumbnail_url, screenshots, source_url, post_date, search_title
+                "SELECT id, title, source, file_size, magnet_link, link_type, genres, company, original_size,
+                 thumbnail_url, screenshots, source_url, post_date, search_title, view_count
                  FROM games ORDER BY RANDOM() LIMIT 10"
             )
             .fetch_all(&state.db)
-            .await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .await?
         }
     };
 
-    Ok(Json(games))
+    let response = serde_json::to_value(&games).map_err(ApiError::internal)?;
+    if let Some(key) = &cache_key {
+        state.aggregate_cache.insert(key, response.clone()).await;
+    }
+    Ok(Json(response))
 }
 
 // ─── Random Game ───
 
+#[utoipa::path(
+    get,
+    path = "/api/games/random",
+    tag = "games",
+    responses(
+        (status = 200, description = "`{ \"game\": Game }`", body = Object),
+    )
+)]
 async fn get_random_game(
     State(state): State<AppState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     let game = db::get_random_game(&state.db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .await?;
 
     Ok(Json(serde_json::json!({ "game": game })))
 }
@@ -954,24 +2034,13 @@ async fn get_random_game(
 async fn get_favorites(
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     let user = get_current_user(&state.db, &headers).await
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
-
-    let ids = db::get_user_favorites(&state.db, user.id)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    if ids.is_empty() {
-        return Ok(Json(serde_json::json!({ "favorites": [], "ids": [] })));
-    }
+        .map_err(ApiError::unauthorized)?;
 
-    let mut games = Vec::new();
-    for id in &ids {
-        if let Ok(game) = db::get_game_by_id(&state.db, *id).await {
-            games.push(game);
-        }
-    }
+    let games = db::get_user_favorite_games(&state.db, user.id)
+        .await?;
+    let ids: Vec<i64> = games.iter().map(|g| g.id).collect();
 
     Ok(Json(serde_json::json!({
         "favorites": games,
@@ -983,17 +2052,11 @@ async fn add_favorite(
     State(state): State<AppState>,
     headers: HeaderMap,
     Path(id): Path<i64>,
-) -> Result<Json<ApiResponse>, (StatusCode, Json<ApiResponse>)> {
+) -> Result<Json<ApiResponse>, ApiError> {
     let user = get_current_user(&state.db, &headers).await
-        .map_err(|e| (StatusCode::UNAUTHORIZED, Json(ApiResponse {
-            success: false, message: e, downloads: None, download_id: None,
-        })))?;
+        .map_err(ApiError::unauthorized)?;
 
-    db::add_user_favorite(&state.db, user.id, id).await.map_err(|e| {
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiResponse {
-            success: false, message: e.to_string(), downloads: None, download_id: None,
-        }))
-    })?;
+    db::add_user_favorite(&state.db, user.id, id).await.map_err(ApiError::internal)?;
 
     Ok(Json(ApiResponse {
         success: true,
@@ -1007,17 +2070,11 @@ async fn remove_favorite(
     State(state): State<AppState>,
     headers: HeaderMap,
     Path(id): Path<i64>,
-) -> Result<Json<ApiResponse>, (StatusCode, Json<ApiResponse>)> {
+) -> Result<Json<ApiResponse>, ApiError> {
     let user = get_current_user(&state.db, &headers).await
-        .map_err(|e| (StatusCode::UNAUTHORIZED, Json(ApiResponse {
-            success: false, message: e, downloads: None, download_id: None,
-        })))?;
+        .map_err(ApiError::unauthorized)?;
 
-    db::remove_user_favorite(&state.db, user.id, id).await.map_err(|e| {
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiResponse {
-            success: false, message: e.to_string(), downloads: None, download_id: None,
-        }))
-    })?;
+    db::remove_user_favorite(&state.db, user.id, id).await.map_err(ApiError::internal)?;
 
     Ok(Json(ApiResponse {
         success: true,
@@ -1027,46 +2084,602 @@ async fn remove_favorite(
     }))
 }
 
-async fn upload_csv(
+// ─── Wishlist (per-user) ───
+
+async fn get_wishlist(
     State(state): State<AppState>,
-    mut multipart: Multipart,
-) -> Result<Json<ApiResponse>, (StatusCode, Json<ApiResponse>)> {
-    let field = multipart
-        .next_field()
-        .await
-        .map_err(|e| {
-            (StatusCode::BAD_REQUEST, Json(ApiResponse {
-                success: false,
-                message: format!("Failed to read upload: {}", e),
-                downloads: None,
-                download_id: None,
-            }))
-        })?
-        .ok_or_else(|| {
-            (StatusCode::BAD_REQUEST, Json(ApiResponse {
-                success: false,
-                message: "No file provided".to_string(),
-                downloads: None,
-                download_id: None,
-            }))
-        })?;
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
 
-    if field.name() != Some("file") {
-        return Err((StatusCode::BAD_REQUEST, Json(ApiResponse {
-            success: false,
-            message: "Expected field named 'file'".to_string(),
-            downloads: None,
-            download_id: None,
-        })));
-    }
+    let games = db::get_user_wishlist_games(&state.db, user.id).await?;
+    let ids: Vec<i64> = games.iter().map(|g| g.id).collect();
 
-    let data = field.bytes().await.map_err(|e| {
-        (StatusCode::BAD_REQUEST, Json(ApiResponse {
-            success: false,
-            message: format!("Failed to read file data: {}", e),
-            downloads: None,
-            download_id: None,
-        }))
+    Ok(Json(serde_json::json!({
+        "wishlist": games,
+        "ids": ids
+    })))
+}
+
+async fn add_to_wishlist(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<i64>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+
+    db::get_game_by_id(&state.db, id)
+        .await
+        .map_err(|_| ApiError::not_found("Game not found"))?;
+    db::add_user_wishlist(&state.db, user.id, id).await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Added to wishlist".to_string(),
+        downloads: None,
+        download_id: None,
+    }))
+}
+
+async fn remove_from_wishlist(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<i64>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+
+    db::remove_user_wishlist(&state.db, user.id, id).await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Removed from wishlist".to_string(),
+        downloads: None,
+        download_id: None,
+    }))
+}
+
+// ─── Hidden games (per-user) and blacklist (global) ───
+
+async fn get_hidden_games(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+
+    let games = db::get_user_hidden_games(&state.db, user.id).await?;
+    let ids: Vec<i64> = games.iter().map(|g| g.id).collect();
+
+    Ok(Json(serde_json::json!({
+        "hidden": games,
+        "ids": ids
+    })))
+}
+
+async fn hide_game(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<i64>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+
+    db::get_game_by_id(&state.db, id)
+        .await
+        .map_err(|_| ApiError::not_found("Game not found"))?;
+    db::hide_game_for_user(&state.db, user.id, id).await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Game hidden".to_string(),
+        downloads: None,
+        download_id: None,
+    }))
+}
+
+async fn unhide_game(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<i64>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+
+    db::unhide_game_for_user(&state.db, user.id, id).await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Game unhidden".to_string(),
+        downloads: None,
+        download_id: None,
+    }))
+}
+
+#[derive(Deserialize, ToSchema)]
+struct BlacklistRequest {
+    /// Game to blacklist; every game with the same source and title is hidden,
+    /// including ones a later scrape adds
+    game_id: i64,
+    reason: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/blacklist",
+    tag = "games",
+    responses(
+        (status = 200, description = "Every blacklisted source/title pair", body = Vec<db::BlacklistEntry>),
+        (status = 401, description = "Not logged in", body = api_error::ErrorBody),
+        (status = 403, description = "Not an admin", body = api_error::ErrorBody),
+    ),
+    security(("session" = []))
+)]
+async fn get_blacklist(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<db::BlacklistEntry>>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+    if !user.is_admin {
+        return Err(ApiError::forbidden("Admin access required"));
+    }
+
+    Ok(Json(db::get_blacklist(&state.db).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/blacklist",
+    tag = "games",
+    request_body = BlacklistRequest,
+    responses(
+        (status = 200, description = "The game is hidden from everyone", body = db::BlacklistEntry),
+        (status = 401, description = "Not logged in", body = api_error::ErrorBody),
+        (status = 403, description = "Not an admin", body = api_error::ErrorBody),
+        (status = 404, description = "No such game", body = api_error::ErrorBody),
+    ),
+    security(("session" = []))
+)]
+async fn add_to_blacklist(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<BlacklistRequest>,
+) -> Result<Json<db::BlacklistEntry>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+    if !user.is_admin {
+        return Err(ApiError::forbidden("Admin access required"));
+    }
+
+    let game = db::get_game_by_id(&state.db, payload.game_id)
+        .await
+        .map_err(|_| ApiError::not_found("Game not found"))?;
+    let reason = payload.reason.as_deref().map(str::trim).filter(|r| !r.is_empty());
+    let entry = db::blacklist_game(&state.db, &game.source, &game.title, reason).await?;
+
+    Ok(Json(entry))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/blacklist/{id}",
+    tag = "games",
+    params(("id" = i64, Path, description = "Blacklist entry ID")),
+    responses(
+        (status = 200, description = "The game shows up again", body = ApiResponse),
+        (status = 401, description = "Not logged in", body = api_error::ErrorBody),
+        (status = 403, description = "Not an admin", body = api_error::ErrorBody),
+        (status = 404, description = "No such entry", body = api_error::ErrorBody),
+    ),
+    security(("session" = []))
+)]
+async fn remove_from_blacklist(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<i64>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+    if !user.is_admin {
+        return Err(ApiError::forbidden("Admin access required"));
+    }
+
+    if !db::remove_blacklist_entry(&state.db, id).await? {
+        return Err(ApiError::not_found("Blacklist entry not found"));
+    }
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Removed from blacklist".to_string(),
+        downloads: None,
+        download_id: None,
+    }))
+}
+
+// ─── Saved searches (per-user) ───
+
+#[derive(Deserialize)]
+struct SaveSearchRequest {
+    name: String,
+    /// The same filters `/api/games` takes; paging is not saved
+    params: db::GameQuery,
+}
+
+fn saved_search_json(search: db::SavedSearch) -> serde_json::Value {
+    serde_json::json!({
+        "id": search.id,
+        "name": search.name,
+        "params": serde_json::from_str::<serde_json::Value>(&search.params).unwrap_or_default(),
+        "created_at": search.created_at,
+    })
+}
+
+async fn get_saved_searches(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+
+    let searches = db::get_saved_searches(&state.db, user.id).await?;
+
+    Ok(Json(serde_json::json!({
+        "searches": searches.into_iter().map(saved_search_json).collect::<Vec<_>>()
+    })))
+}
+
+async fn save_search(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<SaveSearchRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+
+    let name = req.name.trim();
+    if name.is_empty() {
+        return Err(ApiError::bad_request("Search name is required"));
+    }
+    if name.chars().count() > 100 {
+        return Err(ApiError::bad_request("Search name must be 100 characters or fewer"));
+    }
+
+    let mut params = db::GameQuery { page: None, per_page: None, ..req.params };
+    // Reject filters `/api/games` would reject, so saved searches always run
+    // (the user id it fills in isn't serialized)
+    resolve_user_filters(&state, &headers, &mut params).await?;
+    let params = serde_json::to_string(&params).map_err(ApiError::internal)?;
+
+    let search = db::save_search(&state.db, user.id, name, &params).await?;
+    Ok(Json(saved_search_json(search)))
+}
+
+async fn delete_saved_search(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<i64>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+
+    if !db::delete_saved_search(&state.db, user.id, id).await? {
+        return Err(ApiError::not_found("Saved search not found"));
+    }
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Saved search deleted".to_string(),
+        downloads: None,
+        download_id: None,
+    }))
+}
+
+#[derive(Deserialize)]
+struct PageParams {
+    page: Option<i64>,
+    per_page: Option<i64>,
+}
+
+/// Run a saved search, with paging from the query string
+async fn run_saved_search(
+    State(state): State<AppState>,
+    uri: Uri,
+    headers: HeaderMap,
+    Path(id): Path<i64>,
+    Query(paging): Query<PageParams>,
+) -> Result<(HeaderMap, Json<GamesResponse>), ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+
+    let search = db::get_saved_search(&state.db, user.id, id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Saved search not found"))?;
+    let saved: db::GameQuery = serde_json::from_str(&search.params)
+        .map_err(|e| ApiError::internal(format!("Saved search is unreadable: {}", e)))?;
+
+    get_games(
+        State(state),
+        uri,
+        headers,
+        Query(db::GameQuery { page: paging.page, per_page: paging.per_page, ..saved }),
+    )
+    .await
+}
+
+/// Attach the logged-in user (if any) to a game query, so their hidden games
+/// are left out, and expand its genre through the alias map.
+/// `wishlist=true` and `hidden=only` need a login.
+async fn resolve_user_filters(
+    state: &AppState,
+    headers: &HeaderMap,
+    query: &mut db::GameQuery,
+) -> Result<(), ApiError> {
+    let hidden = query.hidden.as_deref().filter(|h| !h.is_empty());
+    if !matches!(hidden, None | Some("exclude") | Some("only")) {
+        return Err(ApiError::bad_request("hidden must be exclude or only"));
+    }
+
+    match get_current_user(&state.db, headers).await {
+        Ok(user) => query.user_id = Some(user.id),
+        Err(e) if query.wishlist == Some(true) || hidden == Some("only") => {
+            return Err(ApiError::unauthorized(e));
+        }
+        Err(_) => {}
+    }
+
+    if let Some(genre) = query.genre.as_deref().filter(|g| !g.trim().is_empty()) {
+        let aliases = genres::load_aliases(&state.db).await;
+        query.genre_variants = aliases
+            .canonical(genre)
+            .iter()
+            .map(|name| aliases.variants(name))
+            .collect();
+    }
+    Ok(())
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ExportParams {
+    /// Add source, genres, company, original size, thumbnail, source URL and post date columns
+    #[serde(default)]
+    extended: bool,
+}
+
+/// Rows are encoded in batches of this many to keep chunks reasonably sized
+const EXPORT_BATCH_ROWS: usize = 256;
+
+#[utoipa::path(
+    get,
+    path = "/api/games/export.csv",
+    tag = "games",
+    params(db::GameQuery, ExportParams),
+    responses(
+        (status = 200, description = "Every matching game as `Title,Size,Magnet` CSV, re-importable through `/api/games/upload`. `page` and `per_page` are ignored.", content_type = "text/csv", body = String),
+        (status = 401, description = "`wishlist=true` or `hidden=only` without a login", body = api_error::ErrorBody),
+    )
+)]
+async fn export_games_csv(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(mut query): Query<db::GameQuery>,
+    Query(params): Query<ExportParams>,
+) -> Result<Response, ApiError> {
+    resolve_user_filters(&state, &headers, &mut query).await?;
+
+    let (tx, rx) = tokio::sync::mpsc::channel(EXPORT_BATCH_ROWS);
+    let pool = state.db.clone();
+    tokio::spawn(async move { db::send_filtered_games(&pool, &query, tx).await });
+
+    let mut columns = vec!["Title", "Size", "Magnet"];
+    if params.extended {
+        columns.extend(["Source", "Genres", "Company", "Original Size", "Thumbnail", "Source URL", "Post Date"]);
+    }
+    let header_row = csv_rows(std::iter::once(columns));
+
+    let rows = futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|game| (game, rx))
+    })
+    .ready_chunks(EXPORT_BATCH_ROWS)
+    .map(move |games| {
+        // Headers are already sent, so fail the body rather than end a truncated file cleanly
+        let games = games.into_iter().collect::<Result<Vec<_>, _>>().inspect_err(|e| {
+            tracing::error!("CSV export failed: {}", e);
+        })?;
+        Ok(csv_rows(games.iter().map(|g| {
+            let mut row = vec![g.title.as_str(), g.file_size.as_str(), g.magnet_link.as_str()];
+            if params.extended {
+                row.extend([
+                    g.source.as_str(),
+                    g.genres.as_deref().unwrap_or(""),
+                    g.company.as_deref().unwrap_or(""),
+                    g.original_size.as_deref().unwrap_or(""),
+                    g.thumbnail_url.as_deref().unwrap_or(""),
+                    g.source_url.as_deref().unwrap_or(""),
+                    g.post_date.as_deref().unwrap_or(""),
+                ]);
+            }
+            row
+        })))
+    });
+
+    let body = futures::stream::once(async { Ok::<_, sqlx::Error>(header_row) })
+        .chain(rows);
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "text/csv; charset=utf-8")
+        .header(header::CONTENT_DISPOSITION, "attachment; filename=\"games.csv\"")
+        .body(Body::from_stream(body))
+        .unwrap())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/games/export.json",
+    tag = "games",
+    params(db::GameQuery),
+    responses(
+        (status = 200, description = "Every matching game as a JSON array, re-importable through `/api/games/import.json`. `page` and `per_page` are ignored.", body = Vec<db::Game>),
+        (status = 401, description = "`wishlist=true` or `hidden=only` without a login", body = api_error::ErrorBody),
+    )
+)]
+async fn export_games_json(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(mut query): Query<db::GameQuery>,
+) -> Result<Response, ApiError> {
+    resolve_user_filters(&state, &headers, &mut query).await?;
+
+    let (tx, rx) = tokio::sync::mpsc::channel(EXPORT_BATCH_ROWS);
+    let pool = state.db.clone();
+    tokio::spawn(async move { db::send_filtered_games(&pool, &query, tx).await });
+
+    // Written as "[", then rows separated by commas, then "]"
+    let mut first = true;
+    let rows = futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|game| (game, rx))
+    })
+    .ready_chunks(EXPORT_BATCH_ROWS)
+    .map(move |games| {
+        // Fail the body so a broken export never ends in a valid-looking "]"
+        let games = games.into_iter().collect::<Result<Vec<_>, _>>().inspect_err(|e| {
+            tracing::error!("JSON export failed: {}", e);
+        })?;
+        let mut chunk = Vec::new();
+        for game in &games {
+            chunk.push(if first { b'\n' } else { b',' });
+            first = false;
+            let _ = serde_json::to_writer(&mut chunk, game);
+        }
+        Ok(chunk)
+    });
+
+    let body = futures::stream::once(async { Ok::<_, sqlx::Error>(b"[".to_vec()) })
+        .chain(rows)
+        .chain(futures::stream::once(async { Ok(b"\n]\n".to_vec()) }));
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::CONTENT_DISPOSITION, "attachment; filename=\"games.json\"")
+        .body(Body::from_stream(body))
+        .unwrap())
+}
+
+/// Largest JSON import accepted; a fully enriched catalog of ~10k games is well under this
+const IMPORT_BODY_LIMIT: usize = 256 * 1024 * 1024;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ImportParams {
+    /// "merge" (default) adds new games and updates ones with the same source
+    /// and title; "replace" swaps out the whole catalog
+    mode: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/games/import.json",
+    tag = "games",
+    params(ImportParams),
+    request_body(content = Vec<db::GameInsert>, description = "Games as returned by `/api/games/export.json`; `id` is ignored"),
+    responses(
+        (status = 200, description = "Import summary", body = ApiResponse),
+        (status = 400, description = "No valid games or unknown mode", body = api_error::ErrorBody),
+        (status = 403, description = "Not an admin", body = api_error::ErrorBody),
+    ),
+    security(("session" = []))
+)]
+async fn import_games_json(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<ImportParams>,
+    Json(games): Json<Vec<db::GameInsert>>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+    if !user.is_admin {
+        return Err(ApiError::forbidden("Admin access required"));
+    }
+
+    let merge = match params.mode.as_deref() {
+        None | Some("merge") => true,
+        Some("replace") => false,
+        Some(other) => return Err(ApiError::bad_request(format!("Unknown import mode: {}", other))),
+    };
+
+    let total = games.len();
+    let games: Vec<db::GameInsert> = games
+        .into_iter()
+        .filter(|g| !g.title.trim().is_empty() && !g.magnet_link.trim().is_empty())
+        .map(|mut g| {
+            g.title = g.title.trim().to_string();
+            g.search_title = Some(db::clean_search_title(&g.title));
+            g.link_type = scrapers::LinkType::detect(&g.magnet_link);
+            g
+        })
+        .collect();
+    let skipped = total - games.len();
+
+    if games.is_empty() {
+        return Err(ApiError::bad_request("No valid games found. Each game needs a title and magnet_link"));
+    }
+
+    let message = if merge {
+        let (inserted, updated) = db::merge_games(&state.db, games).await?;
+        format!("Added {} games, updated {}", inserted, updated)
+    } else {
+        let count = db::replace_all_games(&state.db, games).await?;
+        format!("Imported {} games", count)
+    };
+    state.aggregate_cache.clear().await;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: if skipped > 0 {
+            format!("{} ({} skipped without a title or link)", message, skipped)
+        } else {
+            message
+        },
+        downloads: None,
+        download_id: None,
+    }))
+}
+
+/// Encode rows as CSV, quoting fields that contain commas or quotes
+fn csv_rows<'a>(rows: impl IntoIterator<Item = Vec<&'a str>>) -> Vec<u8> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for row in rows {
+        // Writing into a Vec can't fail
+        let _ = writer.write_record(row);
+    }
+    writer.into_inner().unwrap_or_default()
+}
+
+async fn upload_csv(
+    State(state): State<AppState>,
+    mut multipart: Multipart,
+) -> Result<Json<ApiResponse>, ApiError> {
+    let field = multipart
+        .next_field()
+        .await
+        .map_err(|e| {
+            ApiError::bad_request(format!("Failed to read upload: {}", e))
+        })?
+        .ok_or_else(|| {
+            ApiError::bad_request("No file provided")
+        })?;
+
+    if field.name() != Some("file") {
+        return Err(ApiError::bad_request("Expected field named 'file'"));
+    }
+
+    let data = field.bytes().await.map_err(|e| {
+        ApiError::bad_request(format!("Failed to read file data: {}", e))
     })?;
 
     let mut reader = csv::Reader::from_reader(data.as_ref());
@@ -1076,13 +2689,13 @@ async fn upload_csv(
         let record = match result {
             Ok(r) => r,
             Err(e) => {
-                eprintln!("CSV parse error at row {}: {}", i + 1, e);
+                tracing::warn!("CSV parse error at row {}: {}", i + 1, e);
                 continue;
             }
         };
 
         if record.len() < 3 {
-            eprintln!("CSV row {} has fewer than 3 columns, skipping", i + 1);
+            tracing::warn!("CSV row {} has fewer than 3 columns, skipping", i + 1);
             continue;
         }
 
@@ -1091,11 +2704,11 @@ async fn upload_csv(
         let magnet_link = record.get(2).unwrap_or("").trim().to_string();
 
         if title.is_empty() {
-            eprintln!("CSV row {} has empty title, skipping", i + 1);
+            tracing::warn!("CSV row {} has empty title, skipping", i + 1);
             continue;
         }
         if !magnet_link.starts_with("magnet:?") {
-            eprintln!("CSV row {} has invalid magnet link, skipping", i + 1);
+            tracing::warn!("CSV row {} has invalid magnet link, skipping", i + 1);
             continue;
         }
 
@@ -1105,6 +2718,7 @@ async fn upload_csv(
             source: "fitgirl".to_string(),  // CSV uploads default to fitgirl
             file_size,
             magnet_link,
+            link_type: scrapers::LinkType::Magnet,
             genres: None,
             company: None,
             original_size: None,
@@ -1116,24 +2730,14 @@ async fn upload_csv(
     }
 
     if games.is_empty() {
-        return Err((StatusCode::BAD_REQUEST, Json(ApiResponse {
-            success: false,
-            message: "No valid games found in CSV. Expected format: Title,Size,magnet:?...".to_string(),
-            downloads: None,
-            download_id: None,
-        })));
+        return Err(ApiError::bad_request("No valid games found in CSV. Expected format: Title,Size,magnet:?..."));
     }
 
     let count = db::replace_all_games(&state.db, games)
         .await
         .map_err(|e| {
-            eprintln!("Database error during CSV import: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiResponse {
-                success: false,
-                message: "Database error during import".to_string(),
-                downloads: None,
-                download_id: None,
-            }))
+            tracing::error!("Database error during CSV import: {}", e);
+            ApiError::internal("Database error during import")
         })?;
 
     Ok(Json(ApiResponse {
@@ -1153,16 +2757,11 @@ struct RescrapeParams {
 async fn rescrape(
     State(state): State<AppState>,
     Query(params): Query<RescrapeParams>,
-) -> Result<Json<ApiResponse>, (StatusCode, Json<ApiResponse>)> {
+) -> Result<Json<ApiResponse>, ApiError> {
     {
         let status = state.scrape_status.read().await;
         if status.is_running {
-            return Err((StatusCode::CONFLICT, Json(ApiResponse {
-                success: false,
-                message: "A scrape is already in progress".to_string(),
-                downloads: None,
-                download_id: None,
-            })));
+            return Err(ApiError::conflict("A scrape is already in progress"));
         }
     }
 
@@ -1173,380 +2772,434 @@ async fn rescrape(
         status.progress = scrapers::ScrapeProgress::default();
     }
 
-    let scrape_status = state.scrape_status.clone();
-    let db = state.db.clone();
-    let scraper_registry = state.scraper_registry.clone();
-
-    // Determine which sources to scrape
-    let source_filter = params.source.unwrap_or_else(|| "all".to_string());
-    let sources_to_scrape: Vec<String> = if source_filter == "all" {
-        vec!["fitgirl".to_string(), "steamrip".to_string()]
-    } else {
-        vec![source_filter]
+    let sources_to_scrape = scrape_sources(params.source);
+    let job = ScrapeJob {
+        db: state.db.clone(),
+        scraper_registry: state.scraper_registry.clone(),
+        notifier: state.notifier.clone(),
+        aggregate_cache: state.aggregate_cache.clone(),
+        scrape_status: state.scrape_status.clone(),
+        rawg_key: effective_rawg_key(&state.db, &state.rawg_api_key).await,
+        rawg_cache_days: state.rawg_cache_days,
     };
 
-    // Read RAWG key from DB first, fall back to env var
-    let rawg_key = db::get_setting(&state.db, "rawg_api_key")
+    let cancel_status = state.scrape_status.clone();
+    let shutdown = state.shutdown.clone();
+    tokio::task::spawn_blocking(move || {
+        tokio::runtime::Handle::current().block_on(async move {
+            let scrape = run_scrape(job, sources_to_scrape);
+
+            // Dropping the scrape on shutdown rolls back its unfinished transaction
+            tokio::select! {
+                _ = scrape => {}
+                _ = shutdown.cancelled() => {
+                    println!("Scrape cancelled by shutdown");
+                    cancel_status.write().await.is_running = false;
+                }
+            }
+        })
+    });
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Scraping started in background. Poll /api/scrape-status for updates.".to_string(),
+        downloads: None,
+        download_id: None,
+    }))
+}
+
+/// Sources named by a `source` parameter; "all" or nothing means every source
+fn scrape_sources(source: Option<String>) -> Vec<String> {
+    match source.as_deref() {
+        None | Some("all") => vec!["fitgirl".to_string(), "steamrip".to_string()],
+        Some(name) => vec![name.to_string()],
+    }
+}
+
+/// RAWG key saved in Settings, falling back to the configured one
+async fn effective_rawg_key(pool: &SqlitePool, configured: &str) -> String {
+    db::get_setting(pool, "rawg_api_key")
         .await
         .ok()
         .flatten()
-        .unwrap_or_else(|| state.rawg_api_key.clone());
+        .unwrap_or_else(|| configured.to_string())
+}
 
-    tokio::task::spawn_blocking(move || {
-        tokio::runtime::Handle::current().block_on(async move {
-            println!("Starting scrape for sources: {:?}", sources_to_scrape);
-
-            // Create shared progress for the scraper
-            let scrape_progress = Arc::new(RwLock::new(scrapers::ScrapeProgress::default()));
-
-            // Spawn a task to sync scraper progress back to ScrapeStatus every second
-            let sync_progress = scrape_progress.clone();
-            let sync_status = scrape_status.clone();
-            let sync_task = tokio::spawn(async move {
-                loop {
-                    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-                    let p = sync_progress.read().await.clone();
-                    let mut s = sync_status.write().await;
-                    if !s.is_running {
-                        break;
-                    }
-                    s.progress = p;
+/// Everything a scrape run needs, shared by the rescrape endpoint and `--scrape-once`
+struct ScrapeJob {
+    db: SqlitePool,
+    scraper_registry: Arc<scrapers::registry::ScraperRegistry>,
+    notifier: Arc<notifications::Notifier>,
+    aggregate_cache: AggregateCache,
+    scrape_status: Arc<RwLock<ScrapeStatus>>,
+    rawg_key: String,
+    /// See [`config::Config::rawg_cache_days`]
+    rawg_cache_days: i64,
+}
+
+/// Scrape `sources_to_scrape`, enrich from RAWG, and replace the games table.
+/// Returns the result message, which is also stored in the scrape status.
+async fn run_scrape(job: ScrapeJob, sources_to_scrape: Vec<String>) -> Result<String, String> {
+    let ScrapeJob { db, scraper_registry, notifier, aggregate_cache, scrape_status, rawg_key, rawg_cache_days } = job;
+
+    println!("Starting scrape for sources: {:?}", sources_to_scrape);
+
+    // Create shared progress for the scraper
+    let scrape_progress = Arc::new(RwLock::new(scrapers::ScrapeProgress::default()));
+
+    // Spawn a task to sync scraper progress back to ScrapeStatus every second
+    let sync_progress = scrape_progress.clone();
+    let sync_status = scrape_status.clone();
+    let sync_task = tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            let p = sync_progress.read().await.clone();
+            let mut s = sync_status.write().await;
+            if !s.is_running {
+                break;
+            }
+            s.progress = p;
+        }
+    });
+
+    // Scrape from all requested sources
+    let mut all_scraped_games = Vec::new();
+    let should_scrape_fitgirl = sources_to_scrape.contains(&"fitgirl".to_string()) ||
+                                sources_to_scrape.contains(&"all".to_string());
+    for source_name in sources_to_scrape {
+        if let Some(scraper) = scraper_registry.get(&source_name) {
+            println!("Scraping from source: {}", scraper.source_label());
+            match scraper.scrape_all_games(scrape_progress.clone()).await {
+                Ok(games) => {
+                    println!("Got {} games from {}", games.len(), scraper.source_label());
+                    all_scraped_games.extend(games);
                 }
-            });
-
-            // Scrape from all requested sources
-            let mut all_scraped_games = Vec::new();
-            let should_scrape_fitgirl = sources_to_scrape.contains(&"fitgirl".to_string()) ||
-                                        sources_to_scrape.contains(&"all".to_string());
-            for source_name in sources_to_scrape {
-                if let Some(scraper) = scraper_registry.get(&source_name) {
-                    println!("Scraping from source: {}", scraper.source_label());
-                    match scraper.scrape_all_games(scrape_progress.clone()).await {
-                        Ok(games) => {
-                            println!("Got {} games from {}", games.len(), scraper.source_label());
-                            all_scraped_games.extend(games);
-                        }
-                        Err(e) => {
-                            eprintln!("Failed to scrape from {}: {}", scraper.source_label(), e);
-                        }
-                    }
-                } else {
-                    eprintln!("Unknown source: {}", source_name);
+                Err(e) => {
+                    tracing::error!("Failed to scrape from {}: {}", scraper.source_label(), e);
                 }
             }
+        } else {
+            tracing::warn!("Unknown source: {}", source_name);
+        }
+    }
 
-            let result = if !all_scraped_games.is_empty() {
-                {
-                    let total = all_scraped_games.len();
-                    let with_img = all_scraped_games.iter().filter(|g| g.thumbnail_url.is_some()).count();
-                    let with_genres = all_scraped_games.iter().filter(|g| g.genres.is_some()).count();
-                    println!(
-                        "WP scrape got {}/{} images, {}/{} genres — checking RAWG for gaps...",
-                        with_img, total, with_genres, total
-                    );
+    let result = if !all_scraped_games.is_empty() {
+        {
+            let total = all_scraped_games.len();
+            let with_img = all_scraped_games.iter().filter(|g| g.thumbnail_url.is_some()).count();
+            let with_genres = all_scraped_games.iter().filter(|g| g.genres.is_some()).count();
+            println!(
+                "WP scrape got {}/{} images, {}/{} genres — checking RAWG for gaps...",
+                with_img, total, with_genres, total
+            );
+
+            // RAWG enrichment — only for games MISSING images or genres
+            if !rawg_key.is_empty() {
+                // Earlier lookups are kept in their own table so they survive
+                // the games table being replaced
+                match db::prune_rawg_cache(&db, rawg_cache_days).await {
+                    Ok(n) if n > 0 => println!("Dropped {} RAWG cache entries older than {} days", n, rawg_cache_days),
+                    Ok(_) => {}
+                    Err(e) => tracing::error!("Failed to prune RAWG cache: {}", e),
+                }
+                let metadata_cache = db::get_metadata_cache(&db, rawg_cache_days).await.unwrap_or_default();
+                let cache_size = metadata_cache.len();
+                if cache_size > 0 {
+                    println!("Loaded RAWG cache with {} entries", cache_size);
+                }
 
-                    // RAWG enrichment — only for games MISSING images or genres
-                    if !rawg_key.is_empty() {
-                        // Load existing metadata cache from DB to avoid re-querying RAWG
-                        let metadata_cache = db::get_metadata_cache(&db).await.unwrap_or_default();
-                        let cache_size = metadata_cache.len();
-                        if cache_size > 0 {
-                            println!("Loaded RAWG cache with {} entries from existing DB", cache_size);
+                // Apply cache first
+                let mut cache_hits = 0;
+                for game in all_scraped_games.iter_mut() {
+                    if game.thumbnail_url.is_some() && game.genres.is_some() {
+                        continue;
+                    }
+                    if let Some((cached_thumb, cached_genres)) = metadata_cache.get(&rawg::cache_key(&game.title)) {
+                        if game.thumbnail_url.is_none() && cached_thumb.is_some() {
+                            game.thumbnail_url = cached_thumb.clone();
+                            cache_hits += 1;
                         }
+                        if game.genres.is_none() && cached_genres.is_some() {
+                            game.genres = cached_genres.clone();
+                        }
+                    }
+                }
+                if cache_hits > 0 {
+                    println!("RAWG cache filled {} games without API calls", cache_hits);
+                }
 
-                        // Apply cache first
-                        let mut cache_hits = 0;
-                        for game in all_scraped_games.iter_mut() {
-                            if game.thumbnail_url.is_some() && game.genres.is_some() {
-                                continue;
+                // A cached answer is final until it expires, even if RAWG had no image or genres
+                let missing_indices: Vec<usize> = all_scraped_games.iter().enumerate()
+                    .filter(|(_, g)| g.thumbnail_url.is_none() || g.genres.is_none())
+                    .filter(|(_, g)| !metadata_cache.contains_key(&rawg::cache_key(&g.title)))
+                    .map(|(i, _)| i)
+                    .collect();
+
+                if missing_indices.is_empty() {
+                    println!("All games have images and genres from WP — skipping RAWG");
+                } else {
+                    println!("RAWG enriching {} games missing images/genres...", missing_indices.len());
+                    let titles: Vec<String> = missing_indices.iter()
+                        .map(|&i| all_scraped_games[i].title.clone())
+                        .collect();
+                    let metadata = rawg::enrich_games(&db, &titles, &rawg_key, scrape_progress.clone()).await;
+
+                    let mut images_applied = 0;
+                    let mut genres_applied = 0;
+                    for (j, meta) in metadata.into_iter().enumerate() {
+                        let i = missing_indices[j];
+                        if let Some(meta) = meta {
+                            if all_scraped_games[i].thumbnail_url.is_none() && meta.image_url.is_some() {
+                                all_scraped_games[i].thumbnail_url = meta.image_url;
+                                images_applied += 1;
                             }
-                            let norm = game.title.to_lowercase()
-                                .replace(|c: char| !c.is_alphanumeric() && c != ' ', "")
-                                .split_whitespace()
-                                .collect::<Vec<_>>()
-                                .join(" ");
-                            if let Some((cached_thumb, cached_genres)) = metadata_cache.get(&norm) {
-                                if game.thumbnail_url.is_none() && cached_thumb.is_some() {
-                                    game.thumbnail_url = cached_thumb.clone();
-                                    cache_hits += 1;
-                                }
-                                if game.genres.is_none() && cached_genres.is_some() {
-                                    game.genres = cached_genres.clone();
-                                }
+                            if all_scraped_games[i].genres.is_none() && meta.genres.is_some() {
+                                all_scraped_games[i].genres = meta.genres;
+                                genres_applied += 1;
                             }
                         }
-                        if cache_hits > 0 {
-                            println!("RAWG cache filled {} games without API calls", cache_hits);
-                        }
+                    }
+                    println!(
+                        "RAWG filled: {} images, {} genres",
+                        images_applied, genres_applied
+                    );
+                }
+            } else {
+                let missing = total - with_img;
+                if missing > 0 {
+                    println!(
+                        "⚠ {} games missing images — set RAWG_API_KEY in Settings to fill gaps",
+                        missing
+                    );
+                }
+            }
 
-                        let missing_indices: Vec<usize> = all_scraped_games.iter().enumerate()
-                            .filter(|(_, g)| g.thumbnail_url.is_none() || g.genres.is_none())
-                            .map(|(i, _)| i)
-                            .collect();
-
-                        if missing_indices.is_empty() {
-                            println!("All games have images and genres from WP — skipping RAWG");
-                        } else {
-                            println!("RAWG enriching {} games missing images/genres...", missing_indices.len());
-                            let titles: Vec<String> = missing_indices.iter()
-                                .map(|&i| all_scraped_games[i].title.clone())
-                                .collect();
-                            let metadata = rawg::enrich_games(&titles, &rawg_key, scrape_progress.clone()).await;
-
-                            let mut images_applied = 0;
-                            let mut genres_applied = 0;
-                            for (j, meta) in metadata.into_iter().enumerate() {
-                                let i = missing_indices[j];
-                                if let Some(meta) = meta {
-                                    if all_scraped_games[i].thumbnail_url.is_none() && meta.image_url.is_some() {
-                                        all_scraped_games[i].thumbnail_url = meta.image_url;
-                                        images_applied += 1;
-                                    }
-                                    if all_scraped_games[i].genres.is_none() && meta.genres.is_some() {
-                                        all_scraped_games[i].genres = meta.genres;
-                                        genres_applied += 1;
-                                    }
-                                }
-                            }
-                            println!(
-                                "RAWG filled: {} images, {} genres",
-                                images_applied, genres_applied
-                            );
+            // Update progress to saving phase
+            {
+                let mut p = scrape_progress.write().await;
+                p.phase = "saving".to_string();
+                p.message = format!("Saving {} games to database...", all_scraped_games.len());
+                p.progress = 98.0;
+            }
+            // Sync once more
+            {
+                let p = scrape_progress.read().await.clone();
+                let mut s = scrape_status.write().await;
+                s.progress = p;
+            }
+
+            println!("Scraped {} games, deduplicating...", all_scraped_games.len());
+
+            // Deduplicate by normalized title — keep the entry with the most metadata
+            let before_dedup = all_scraped_games.len();
+            {
+                let mut seen: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+                let mut keep = vec![false; all_scraped_games.len()];
+                for (i, g) in all_scraped_games.iter().enumerate() {
+                    let norm = g.title.to_lowercase()
+                        .replace(|c: char| !c.is_alphanumeric() && c != ' ', "")
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    if let Some(&prev) = seen.get(&norm) {
+                        // Keep whichever has more metadata (thumbnail, genres, screenshots)
+                        let score = |idx: usize| -> usize {
+                            let g = &all_scraped_games[idx];
+                            (if g.thumbnail_url.is_some() { 1 } else { 0 })
+                            + (if g.genres.is_some() { 1 } else { 0 })
+                            + (if g.screenshots.is_some() { 1 } else { 0 })
+                            + (if g.company.is_some() { 1 } else { 0 })
+                        };
+                        if score(i) > score(prev) {
+                            keep[prev] = false;
+                            keep[i] = true;
+                            seen.insert(norm, i);
                         }
+                        // else keep the previous one
                     } else {
-                        let missing = total - with_img;
-                        if missing > 0 {
-                            println!(
-                                "⚠ {} games missing images — set RAWG_API_KEY in Settings to fill gaps",
-                                missing
-                            );
-                        }
+                        seen.insert(norm, i);
+                        keep[i] = true;
                     }
+                }
+                let mut idx = 0;
+                all_scraped_games.retain(|_| { let k = keep[idx]; idx += 1; k });
+            }
+            if before_dedup != all_scraped_games.len() {
+                println!("Deduped: {} → {} games ({} duplicates removed)",
+                    before_dedup, all_scraped_games.len(), before_dedup - all_scraped_games.len());
+            }
 
-                    // Update progress to saving phase
-                    {
-                        let mut p = scrape_progress.write().await;
-                        p.phase = "saving".to_string();
-                        p.message = format!("Saving {} games to database...", all_scraped_games.len());
-                        p.progress = 98.0;
-                    }
-                    // Sync once more
-                    {
-                        let p = scrape_progress.read().await.clone();
-                        let mut s = scrape_status.write().await;
-                        s.progress = p;
-                    }
+            println!("Inserting {} games into database...", all_scraped_games.len());
 
-                    println!("Scraped {} games, deduplicating...", all_scraped_games.len());
-
-                    // Deduplicate by normalized title — keep the entry with the most metadata
-                    let before_dedup = all_scraped_games.len();
-                    {
-                        let mut seen: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
-                        let mut keep = vec![false; all_scraped_games.len()];
-                        for (i, g) in all_scraped_games.iter().enumerate() {
-                            let norm = g.title.to_lowercase()
-                                .replace(|c: char| !c.is_alphanumeric() && c != ' ', "")
-                                .split_whitespace()
-                                .collect::<Vec<_>>()
-                                .join(" ");
-                            if let Some(&prev) = seen.get(&norm) {
-                                // Keep whichever has more metadata (thumbnail, genres, screenshots)
-                                let score = |idx: usize| -> usize {
-                                    let g = &all_scraped_games[idx];
-                                    (if g.thumbnail_url.is_some() { 1 } else { 0 })
-                                    + (if g.genres.is_some() { 1 } else { 0 })
-                                    + (if g.screenshots.is_some() { 1 } else { 0 })
-                                    + (if g.company.is_some() { 1 } else { 0 })
-                                };
-                                if score(i) > score(prev) {
-                                    keep[prev] = false;
-                                    keep[i] = true;
-                                    seen.insert(norm, i);
-                                }
-                                // else keep the previous one
-                            } else {
-                                seen.insert(norm, i);
-                                keep[i] = true;
-                            }
-                        }
-                        let mut idx = 0;
-                        all_scraped_games.retain(|_| { let k = keep[idx]; idx += 1; k });
-                    }
-                    if before_dedup != all_scraped_games.len() {
-                        println!("Deduped: {} → {} games ({} duplicates removed)",
-                            before_dedup, all_scraped_games.len(), before_dedup - all_scraped_games.len());
+            // Convert scraped games to database inserts
+            let game_inserts: Vec<db::GameInsert> = all_scraped_games
+                .into_iter()
+                .map(|g| {
+                    let search_title = Some(db::clean_search_title(&g.title));
+                    db::GameInsert {
+                        title: g.title,
+                        source: g.source,  // Use the source field from ScrapedGame
+                        file_size: g.file_size,
+                        magnet_link: g.download_link,
+                        link_type: g.link_type,
+                        genres: g.genres,
+                        company: g.company,
+                        original_size: g.original_size,
+                        thumbnail_url: g.thumbnail_url,
+                        screenshots: g.screenshots,
+                        source_url: g.source_url,
+                        post_date: g.post_date,
+                        search_title,
                     }
+                })
+                .collect();
 
-                    println!("Inserting {} games into database...", all_scraped_games.len());
-
-                    // Convert scraped games to database inserts
-                    let game_inserts: Vec<db::GameInsert> = all_scraped_games
-                        .into_iter()
-                        .map(|g| {
-                            let search_title = Some(db::clean_search_title(&g.title));
-                            db::GameInsert {
-                                title: g.title,
-                                source: g.source,  // Use the source field from ScrapedGame
-                                file_size: g.file_size,
-                                magnet_link: g.download_link,
-                                genres: g.genres,
-                                company: g.company,
-                                original_size: g.original_size,
-                                thumbnail_url: g.thumbnail_url,
-                                screenshots: g.screenshots,
-                                source_url: g.source_url,
-                                post_date: g.post_date,
-                                search_title,
-                            }
-                        })
-                        .collect();
-
-                    match db::replace_all_games(&db, game_inserts).await {
-                        Ok(count) => {
-                            println!("Successfully inserted {} games", count);
-
-                            // Scrape FitGirl top repacks for carousel
-                            if should_scrape_fitgirl {
-                                println!("Scraping FitGirl top repacks for carousel...");
-                                if let Some(fitgirl_scraper) = scraper_registry.get("fitgirl") {
-                                    // Downcast to FitGirlScraper to access scrape_top_repacks method
-                                    if let Some(fitgirl) = fitgirl_scraper.as_any().downcast_ref::<scrapers::fitgirl::FitGirlScraper>() {
-                                        // Scrape top_50
-                                        match fitgirl.scrape_top_repacks("top_50").await {
-                                            Ok(top_50_titles) => {
-                                                println!("  Scraped {} titles from top_50", top_50_titles.len());
-                                                let _ = db::clear_category(&db, "top_50").await;
-                                                for (title, rank) in top_50_titles {
-                                                    // Find game_id by normalized title
-                                                    if let Ok(Some((game_id,))) = sqlx::query_as::<_, (i64,)>(
-                                                        "SELECT id FROM games WHERE search_title LIKE ? LIMIT 1"
-                                                    )
-                                                    .bind(format!("%{}%", db::clean_search_title(&title)))
-                                                    .fetch_optional(&db)
-                                                    .await
-                                                    {
-                                                        let _ = db::upsert_game_category(&db, game_id, "top_50", rank).await;
-                                                    }
-                                                }
+            match db::replace_all_games(&db, game_inserts).await {
+                Ok(count) => {
+                    println!("Successfully inserted {} games", count);
+
+                    // Scrape FitGirl top repacks for carousel
+                    if should_scrape_fitgirl {
+                        println!("Scraping FitGirl top repacks for carousel...");
+                        if let Some(fitgirl_scraper) = scraper_registry.get("fitgirl") {
+                            // Downcast to FitGirlScraper to access scrape_top_repacks method
+                            if let Some(fitgirl) = fitgirl_scraper.as_any().downcast_ref::<scrapers::fitgirl::FitGirlScraper>() {
+                                // Scrape top_50
+                                match fitgirl.scrape_top_repacks("top_50").await {
+                                    Ok(top_50_titles) => {
+                                        println!("  Scraped {} titles from top_50", top_50_titles.len());
+                                        let _ = db::clear_category(&db, "top_50").await;
+                                        for (title, rank) in top_50_titles {
+                                            // Find game_id by normalized title
+                                            if let Ok(Some((game_id,))) = sqlx::query_as::<_, (i64,)>(
+                                                "SELECT id FROM games WHERE search_title LIKE ? LIMIT 1"
+                                            )
+                                            .bind(format!("%{}%", db::clean_search_title(&title)))
+                                            .fetch_optional(&db)
+                                            .await
+                                            {
+                                                let _ = db::upsert_game_category(&db, game_id, "top_50", rank).await;
                                             }
-                                            Err(e) => eprintln!("  Failed to scrape top_50: {}", e),
                                         }
+                                    }
+                                    Err(e) => tracing::error!("  Failed to scrape top_50: {}", e),
+                                }
 
-                                        // Scrape top_150
-                                        match fitgirl.scrape_top_repacks("top_150").await {
-                                            Ok(top_150_titles) => {
-                                                println!("  Scraped {} titles from top_150", top_150_titles.len());
-                                                let _ = db::clear_category(&db, "top_150").await;
-                                                for (title, rank) in top_150_titles {
-                                                    // Find game_id by normalized title
-                                                    if let Ok(Some((game_id,))) = sqlx::query_as::<_, (i64,)>(
-                                                        "SELECT id FROM games WHERE search_title LIKE ? LIMIT 1"
-                                                    )
-                                                    .bind(format!("%{}%", db::clean_search_title(&title)))
-                                                    .fetch_optional(&db)
-                                                    .await
-                                                    {
-                                                        let _ = db::upsert_game_category(&db, game_id, "top_150", rank).await;
-                                                    }
-                                                }
+                                // Scrape top_150
+                                match fitgirl.scrape_top_repacks("top_150").await {
+                                    Ok(top_150_titles) => {
+                                        println!("  Scraped {} titles from top_150", top_150_titles.len());
+                                        let _ = db::clear_category(&db, "top_150").await;
+                                        for (title, rank) in top_150_titles {
+                                            // Find game_id by normalized title
+                                            if let Ok(Some((game_id,))) = sqlx::query_as::<_, (i64,)>(
+                                                "SELECT id FROM games WHERE search_title LIKE ? LIMIT 1"
+                                            )
+                                            .bind(format!("%{}%", db::clean_search_title(&title)))
+                                            .fetch_optional(&db)
+                                            .await
+                                            {
+                                                let _ = db::upsert_game_category(&db, game_id, "top_150", rank).await;
                                             }
-                                            Err(e) => eprintln!("  Failed to scrape top_150: {}", e),
                                         }
                                     }
+                                    Err(e) => tracing::error!("  Failed to scrape top_150: {}", e),
                                 }
                             }
+                        }
+                    }
 
-                            // Notify users who have new games notifications enabled
-                            if count > 0 {
-                                let users_result: Result<Vec<(i64,)>, _> = sqlx::query_as(
-                                    "SELECT user_id FROM user_settings WHERE notify_new_games = 1"
-                                )
-                                .fetch_all(&db)
-                                .await;
-
-                                if let Ok(users) = users_result {
-                                    for (user_id,) in users {
-                                        let _ = db::create_notification(
-                                            &db,
-                                            user_id,
-                                            "new_games",
-                                            "New Games Available",
-                                            &format!("{} new games have been added to the library!", count),
-                                        ).await;
-                                    }
-                                }
+                    // Notify users who have new games notifications enabled
+                    if count > 0 {
+                        let users_result: Result<Vec<(i64,)>, _> = sqlx::query_as(
+                            "SELECT user_id FROM user_settings WHERE notify_new_games = 1"
+                        )
+                        .fetch_all(&db)
+                        .await;
+
+                        if let Ok(users) = users_result {
+                            for (user_id,) in users {
+                                notifier.notify_user(
+                                    user_id,
+                                    "new_games",
+                                    "New Games Available",
+                                    &format!("{} new games have been added to the library!", count),
+                                ).await;
                             }
-
-                            format!("Successfully scraped and inserted {} games", count)
                         }
-                        Err(e) => {
-                            eprintln!("Error inserting games: {}", e);
-                            let error_msg = format!("Scrape succeeded but database insert failed: {}", e);
-
-                            // Notify users with error notifications enabled
-                            let users_result: Result<Vec<(i64,)>, _> = sqlx::query_as(
-                                "SELECT user_id FROM user_settings WHERE notify_errors = 1"
-                            )
-                            .fetch_all(&db)
-                            .await;
-
-                            if let Ok(users) = users_result {
-                                for (user_id,) in users {
-                                    let _ = db::create_notification(
-                                        &db,
-                                        user_id,
-                                        "scrape_error",
-                                        "Scrape Error",
-                                        &format!("Database insert failed: {}", e),
-                                    ).await;
-                                }
-                            }
 
-                            error_msg
-                        }
+                        notifications::send_discord(
+                            &db,
+                            "New Games Available",
+                            &format!("{} new games have been added to the library!", count),
+                            notifications::COLOR_NEW_GAMES,
+                        );
                     }
+
+                    Ok(format!("Successfully scraped and inserted {} games", count))
                 }
-            } else {
-                let error_msg = "No games were scraped from any source".to_string();
-
-                // Notify users with error notifications enabled about scrape failure
-                let users_result: Result<Vec<(i64,)>, _> = sqlx::query_as(
-                    "SELECT user_id FROM user_settings WHERE notify_errors = 1"
-                )
-                .fetch_all(&db)
-                .await;
-
-                if let Ok(users) = users_result {
-                    for (user_id,) in users {
-                        let _ = db::create_notification(
-                            &db,
-                            user_id,
-                            "scrape_error",
-                            "Scrape Failed",
-                            "No games were scraped from any source. Check scraper configuration.",
-                        ).await;
+                Err(e) => {
+                    tracing::error!("Error inserting games: {}", e);
+                    let error_msg = format!("Scrape succeeded but database insert failed: {}", e);
+
+                    // Notify users with scrape error notifications enabled
+                    let users_result: Result<Vec<(i64,)>, _> = sqlx::query_as(
+                        "SELECT user_id FROM user_settings WHERE notify_scrape_error = 1"
+                    )
+                    .fetch_all(&db)
+                    .await;
+
+                    if let Ok(users) = users_result {
+                        for (user_id,) in users {
+                            notifier.notify_user(
+                                user_id,
+                                "scrape_error",
+                                "Scrape Error",
+                                &format!("Database insert failed: {}", e),
+                            ).await;
+                        }
                     }
+
+                    Err(error_msg)
                 }
+            }
+        }
+    } else {
+        let error_msg = "No games were scraped from any source".to_string();
+
+        // Notify users with scrape error notifications enabled
+        let users_result: Result<Vec<(i64,)>, _> = sqlx::query_as(
+            "SELECT user_id FROM user_settings WHERE notify_scrape_error = 1"
+        )
+        .fetch_all(&db)
+        .await;
+
+        if let Ok(users) = users_result {
+            for (user_id,) in users {
+                notifier.notify_user(
+                    user_id,
+                    "scrape_error",
+                    "Scrape Failed",
+                    "No games were scraped from any source. Check scraper configuration.",
+                ).await;
+            }
+        }
 
-                error_msg
-            };
+        Err(error_msg)
+    };
 
-            let mut status = scrape_status.write().await;
-            status.is_running = false;
-            status.last_result = Some(result);
-            status.last_completed = Some(chrono::Utc::now().to_rfc3339());
+    // Genre counts and featured lists are stale now that the games changed
+    aggregate_cache.clear().await;
 
-            sync_task.abort();
-        })
+    let mut status = scrape_status.write().await;
+    status.is_running = false;
+    status.last_result = Some(match &result {
+        Ok(message) | Err(message) => message.clone(),
     });
+    status.last_completed = Some(chrono::Utc::now().to_rfc3339());
 
-    Ok(Json(ApiResponse {
-        success: true,
-        message: "Scraping started in background. Poll /api/scrape-status for updates.".to_string(),
-        downloads: None,
-        download_id: None,
-    }))
+    sync_task.abort();
+    result
 }
 
 async fn get_scrape_status(
@@ -1563,118 +3216,335 @@ struct SourcesResponse {
 
 async fn get_sources(
     State(state): State<AppState>,
-) -> Result<Json<SourcesResponse>, StatusCode> {
+) -> Result<Json<SourcesResponse>, ApiError> {
     let stats = db::get_source_stats(&state.db)
         .await
         .map_err(|e| {
-            eprintln!("Error getting source stats: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
+            tracing::error!("Error getting source stats: {}", e);
+            ApiError::internal("Failed to load sources")
+        })?;
+
+    Ok(Json(SourcesResponse { sources: stats }))
+}
+
+async fn add_to_realdebrid(
+    State(state): State<AppState>,
+    Json(payload): Json<AddMagnetRequest>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    let game = db::get_game_by_id(&state.db, payload.game_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Error fetching game {}: {}", payload.game_id, e);
+            ApiError::not_found("Game not found")
+        })?;
+
+    // Use the provider/API key from Settings, falling back to the startup env var
+    let debrid = debrid::from_settings(&state.db, &state.debrid).await
+        .map_err(ApiError::bad_request)?;
+
+    // Use the universal process_link function that handles both magnets and DDL
+    // Reuse recently unrestricted links so retries don't hit the provider again
+    let options = debrid::LinkOptions { skip_junk: false, max_wait_secs: 300, link_type: Some(game.link_type) };
+    match debrid::process_link_cached(&state.db, debrid.as_ref(), &game.magnet_link, &options, &|_| {}).await {
+        Ok(downloads) => {
+            if downloads.is_empty() {
+                Err(ApiError::not_found("No download links available"))
+            } else {
+                Ok(Json(ApiResponse {
+                    success: true,
+                    message: format!("'{}' is ready to download! Found {} file(s).", game.title, downloads.len()),
+                    downloads: Some(downloads),
+                    download_id: None,
+                }))
+            }
+        }
+        Err(e) => {
+            tracing::error!("{} error for game '{}': {}", debrid.provider_label(), game.title, e);
+            Err(ApiError::new(StatusCode::BAD_GATEWAY, format!("{} error: {}", debrid.provider_label(), e)))
+        }
+    }
+}
+
+/// Real-Debrid premium status and remaining traffic
+async fn get_realdebrid_account(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<realdebrid::AccountInfo>, ApiError> {
+    get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+
+    // Check DB for API key first, fall back to startup env var
+    let api_key = db::get_setting(&state.db, "rd_api_key").await
+        .ok()
+        .flatten()
+        .filter(|k| !k.is_empty())
+        .unwrap_or_else(|| state.rd_api_key.clone());
+
+    if api_key.is_empty() {
+        return Err(ApiError::bad_request("Real-Debrid API key not set. Please configure it in Settings."));
+    }
+
+    let client = realdebrid::RealDebridClient::new(api_key);
+    match client.account_info().await {
+        Ok(info) => Ok(Json(info)),
+        Err(e) => {
+            tracing::error!("Real-Debrid account error: {}", e);
+            Err(ApiError::new(StatusCode::BAD_GATEWAY, format!("Real-Debrid error: {}", e)))
+        }
+    }
+}
+
+// ─── Download management endpoints ───
+
+/// Downloads visible to a user: admins see everything, others only their own
+async fn load_user_downloads(
+    state: &AppState,
+    user: &db::User,
+) -> Result<Vec<download_manager::DownloadInfo>, Box<dyn std::error::Error + Send + Sync>> {
+    if user.is_admin {
+        state.download_manager.get_downloads().await
+    } else {
+        let mut downloads = state.client_download_manager.get_user_downloads(user.id).await?;
+        state.download_manager.merge_live_progress(&mut downloads).await;
+        Ok(downloads)
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/downloads",
+    tag = "downloads",
+    responses(
+        (status = 200, description = "The current user's downloads", body = DownloadsResponse),
+        (status = 401, description = "Not logged in", body = api_error::ErrorBody),
+    ),
+    security(("session" = []))
+)]
+async fn get_downloads(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<DownloadsResponse>, ApiError> {
+    // Require authentication
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+
+    let downloads = load_user_downloads(&state, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Error getting downloads: {}", e);
+            ApiError::internal("Failed to load downloads")
         })?;
 
-    Ok(Json(SourcesResponse { sources: stats }))
+    let library_enabled = state.download_manager.library_dir().is_some();
+    Ok(Json(DownloadsResponse { downloads, library_enabled }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/downloads/history",
+    tag = "downloads",
+    params(DownloadHistoryQuery),
+    responses(
+        (status = 200, description = "Finished downloads, most recent first", body = DownloadHistoryResponse, headers(
+            ("X-Total-Count" = i64, description = "Finished downloads matching the filter"),
+            ("Link" = String, description = "`first`, `prev`, `next` and `last` page URLs"),
+        )),
+        (status = 400, description = "Invalid status or paging values", body = api_error::ErrorBody),
+        (status = 401, description = "Not logged in", body = api_error::ErrorBody),
+    ),
+    security(("session" = []))
+)]
+async fn get_download_history(
+    State(state): State<AppState>,
+    uri: Uri,
+    headers: HeaderMap,
+    Query(query): Query<DownloadHistoryQuery>,
+) -> Result<(HeaderMap, Json<DownloadHistoryResponse>), ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+
+    let status = query.status.as_deref().filter(|s| !s.is_empty());
+    if status.is_some_and(|s| !db::FINISHED_DOWNLOAD_STATUSES.contains(&s)) {
+        return Err(ApiError::bad_request("status must be completed, installed or failed"));
+    }
+    let (page, per_page) = db::page_bounds(query.page, query.per_page, 50, db::MAX_PER_PAGE)
+        .map_err(ApiError::bad_request)?;
+
+    // Admins see every download, as in the live queue
+    let owner = if user.is_admin { None } else { Some(user.id) };
+    let (downloads, total) = db::get_download_history(&state.db, owner, status, page, per_page).await?;
+
+    let total_pages = (total as f64 / per_page as f64).ceil() as i64;
+    Ok((
+        pagination::headers(&uri, page, per_page, total),
+        Json(DownloadHistoryResponse {
+            downloads,
+            total,
+            page,
+            per_page,
+            total_pages,
+        }),
+    ))
+}
+
+/// Push the user's download list over SSE whenever it changes
+async fn stream_downloads(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Sse<impl futures::Stream<Item = Result<Event, std::convert::Infallible>>>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+
+    let events = state.events.subscribe(user.id);
+    let state_shutdown = state.shutdown.clone();
+    let stream = futures::stream::unfold(
+        (state, user, events, String::new(), false),
+        |(state, user, mut events, last_payload, mut in_flight)| async move {
+            let mut first = last_payload.is_empty();
+            loop {
+                if !first {
+                    wait_for_downloads_change(&mut events, in_flight).await;
+                }
+                first = false;
+
+                match load_user_downloads(&state, &user).await {
+                    Ok(downloads) => {
+                        in_flight = downloads.iter()
+                            .any(|d| matches!(d.status.as_str(), "downloading" | "extracting" | "installing"));
+                        let library_enabled = state.download_manager.library_dir().is_some();
+                        let payload = serde_json::to_string(&DownloadsResponse { downloads, library_enabled })
+                            .unwrap_or_default();
+                        if payload != last_payload {
+                            let event = Event::default().event("downloads").data(payload.clone());
+                            return Some((Ok(event), (state, user, events, payload, in_flight)));
+                        }
+                    }
+                    Err(e) => tracing::error!("Error streaming downloads: {}", e),
+                }
+            }
+        },
+    );
+
+    // End the stream on shutdown so graceful shutdown isn't held open by it
+    let stream = stream.take_until(state_shutdown.cancelled_owned());
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
 }
 
-async fn add_to_realdebrid(
-    State(state): State<AppState>,
-    Json(payload): Json<AddMagnetRequest>,
-) -> Result<Json<ApiResponse>, (StatusCode, Json<ApiResponse>)> {
-    let game = db::get_game_by_id(&state.db, payload.game_id)
-        .await
-        .map_err(|e| {
-            eprintln!("Error fetching game {}: {}", payload.game_id, e);
-            (StatusCode::NOT_FOUND, Json(ApiResponse {
-                success: false,
-                message: "Game not found".to_string(),
-                downloads: None,
-                download_id: None,
-            }))
-        })?;
-
-    // Check DB for API key first, fall back to startup env var
-    let rd_client = if let Ok(Some(db_key)) = db::get_setting(&state.db, "rd_api_key").await {
-        if !db_key.is_empty() {
-            Arc::new(realdebrid::RealDebridClient::new(db_key))
+/// Wait until the downloads stream should reload: when one of the user's
+/// downloads changes status, or every second while one is in flight, since
+/// live progress isn't published as events
+async fn wait_for_downloads_change(
+    events: &mut tokio::sync::broadcast::Receiver<events::UserEvent>,
+    in_flight: bool,
+) {
+    let tick = async {
+        if in_flight {
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
         } else {
-            state.rd_client.clone()
+            std::future::pending::<()>().await;
         }
-    } else {
-        state.rd_client.clone()
     };
-
-    // Use the universal process_link function that handles both magnets and DDL
-    match rd_client.process_link(&game.magnet_link).await {
-        Ok(downloads) => {
-            if downloads.is_empty() {
-                Ok(Json(ApiResponse {
-                    success: false,
-                    message: "No download links available".to_string(),
-                    downloads: None,
-                    download_id: None,
-                }))
-            } else {
-                Ok(Json(ApiResponse {
-                    success: true,
-                    message: format!("'{}' is ready to download! Found {} file(s).", game.title, downloads.len()),
-                    downloads: Some(downloads),
-                    download_id: None,
-                }))
+    let status_changed = async {
+        loop {
+            match events.recv().await {
+                Ok(events::UserEvent::DownloadStatus { .. }) => return,
+                Ok(_) => continue,
+                // Missed events may have included status changes
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => return,
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => std::future::pending::<()>().await,
             }
         }
-        Err(e) => {
-            eprintln!("Real-Debrid error for game '{}': {}", game.title, e);
-            Ok(Json(ApiResponse {
-                success: false,
-                message: format!("Real-Debrid error: {}", e),
-                downloads: None,
-                download_id: None,
-            }))
-        }
+    };
+    tokio::select! {
+        _ = tick => {}
+        _ = status_changed => {}
     }
 }
 
-// ─── Download management endpoints ───
-
-async fn get_downloads(
+/// Push extraction progress for one download over SSE. Sends "extract" events
+/// while the download is extracting and a final "done" event with its status.
+async fn stream_extract_progress(
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> Result<Json<DownloadsResponse>, StatusCode> {
-    // Require authentication
-    let user = get_current_user(&state.db, &headers).await
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    Path(id): Path<i64>,
+) -> Result<Sse<impl futures::Stream<Item = Result<Event, std::convert::Infallible>>>, ApiError> {
+    get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+
+    state.download_manager.get_download(id).await
+        .map_err(|_| ApiError::not_found("Download not found"))?;
+
+    let state_shutdown = state.shutdown.clone();
+    let stream = futures::stream::unfold(
+        Some((state, String::new())),
+        move |stream_state| async move {
+            let (state, last_payload) = stream_state?;
+            loop {
+                let download = match state.download_manager.get_download(id).await {
+                    Ok(download) => download,
+                    Err(e) => {
+                        tracing::error!("Error streaming extraction for {}: {}", id, e);
+                        let event = Event::default().event("done").data("{\"status\":\"unknown\"}");
+                        return Some((Ok(event), None));
+                    }
+                };
 
-    // Admin sees all downloads, regular users see only their own
-    let downloads = if user.is_admin {
-        state.download_manager.get_downloads()
-            .await
-            .map_err(|e| {
-                eprintln!("Error getting downloads: {}", e);
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?
-    } else {
-        state.client_download_manager.get_user_downloads(user.id)
-            .await
-            .map_err(|e| {
-                eprintln!("Error getting user downloads: {}", e);
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?
-    };
+                if download.status != "extracting" {
+                    let payload = serde_json::json!({ "status": download.status }).to_string();
+                    let event = Event::default().event("done").data(payload);
+                    return Some((Ok(event), None));
+                }
+
+                if let Some(progress) = download.extract_progress {
+                    let payload = serde_json::to_string(&progress).unwrap_or_default();
+                    if payload != last_payload {
+                        let event = Event::default().event("extract").data(payload.clone());
+                        return Some((Ok(event), Some((state, payload))));
+                    }
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            }
+        },
+    );
 
-    Ok(Json(DownloadsResponse { downloads }))
+    // End the stream on shutdown so graceful shutdown isn't held open by it
+    let stream = stream.take_until(state_shutdown.cancelled_owned());
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/downloads",
+    tag = "downloads",
+    request_body = QueueDownloadRequest,
+    responses(
+        (status = 200, description = "Queued; `download_id` is set", body = ApiResponse),
+        (status = 401, description = "Not logged in", body = api_error::ErrorBody),
+        (status = 400, description = "Game not found or can't be queued", body = api_error::ErrorBody),
+        (status = 429, description = "Over the per-user download quota", body = api_error::ErrorBody),
+    ),
+    security(("session" = []))
+)]
 async fn queue_download(
     State(state): State<AppState>,
     headers: HeaderMap,
     Json(payload): Json<QueueDownloadRequest>,
-) -> Result<Json<ApiResponse>, (StatusCode, Json<ApiResponse>)> {
+) -> Result<Json<ApiResponse>, ApiError> {
     // Require authentication
-    let _user = get_current_user(&state.db, &headers).await
-        .map_err(|e| (StatusCode::UNAUTHORIZED, Json(ApiResponse {
-            success: false, message: e, downloads: None, download_id: None,
-        })))?;
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
 
-    match state.download_manager.queue_download(payload.game_id).await {
+    let bytes = check_download_quota(&state, &user, payload.game_id).await?;
+    save_archive_password(&state, payload.game_id, payload.archive_password.as_deref()).await;
+
+    match state.download_manager.queue_download(payload.game_id, payload.skip_junk).await {
         Ok(download_id) => {
+            // Record ownership so the download shows up in the user's own list
+            if let Err(e) = db::add_user_download(&state.db, user.id, download_id).await {
+                tracing::error!("Error linking download {} to user {}: {}", download_id, user.id, e);
+            }
+            quota::record(&state.db, user.id, bytes).await;
+
             Ok(Json(ApiResponse {
                 success: true,
                 message: "Added to download queue".to_string(),
@@ -1683,39 +3553,147 @@ async fn queue_download(
             }))
         }
         Err(e) => {
-            Err((StatusCode::BAD_REQUEST, Json(ApiResponse {
-                success: false,
-                message: e.to_string(),
-                downloads: None,
-                download_id: None,
-            })))
+            Err(ApiError::bad_request(e.to_string()))
         }
     }
 }
 
+/// Enforce the user's download quota before queueing `game_id`. Returns the
+/// game's size in bytes, to count against the daily cap once it's queued.
+async fn check_download_quota(state: &AppState, user: &db::User, game_id: i64) -> Result<i64, ApiError> {
+    let bytes = db::get_game_by_id(&state.db, game_id)
+        .await
+        .map(|game| quota::game_bytes(&game.file_size))
+        .unwrap_or(0);
+    quota::check(&state.db, state.download_quota, user, bytes)
+        .await
+        .map_err(|e| ApiError::new(StatusCode::TOO_MANY_REQUESTS, e))?;
+    Ok(bytes)
+}
+
+/// Queue several games at once, in the order given
+#[utoipa::path(
+    post,
+    path = "/api/downloads/batch",
+    tag = "downloads",
+    request_body = BatchQueueRequest,
+    responses(
+        (status = 200, description = "Per-game results", body = BatchQueueResponse),
+        (status = 401, description = "Not logged in", body = api_error::ErrorBody),
+    ),
+    security(("session" = []))
+)]
+async fn queue_download_batch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<BatchQueueRequest>,
+) -> Result<Json<BatchQueueResponse>, ApiError> {
+    // Require authentication
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+
+    let mut results = Vec::with_capacity(payload.game_ids.len());
+
+    for game_id in payload.game_ids {
+        let bytes = match check_download_quota(&state, &user, game_id).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                results.push(BatchQueueResult {
+                    game_id,
+                    success: false,
+                    message: e.message().to_string(),
+                    download_id: None,
+                });
+                continue;
+            }
+        };
+
+        match state.download_manager.queue_download(game_id, payload.skip_junk).await {
+            Ok(download_id) => {
+                if let Err(e) = db::add_user_download(&state.db, user.id, download_id).await {
+                    tracing::error!("Error linking download {} to user {}: {}", download_id, user.id, e);
+                }
+                quota::record(&state.db, user.id, bytes).await;
+                results.push(BatchQueueResult {
+                    game_id,
+                    success: true,
+                    message: "Added to download queue".to_string(),
+                    download_id: Some(download_id),
+                });
+            }
+            Err(e) => {
+                results.push(BatchQueueResult {
+                    game_id,
+                    success: false,
+                    message: e.to_string(),
+                    download_id: None,
+                });
+            }
+        }
+    }
+
+    let queued = results.iter().filter(|r| r.success).count();
+    let failed = results.len() - queued;
+
+    Ok(Json(BatchQueueResponse { queued, failed, results }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/downloads/{id}",
+    tag = "downloads",
+    params(("id" = i64, Path, description = "Download ID")),
+    responses(
+        (status = 200, description = "The download", body = download_manager::DownloadInfo),
+        (status = 404, description = "No such download", body = api_error::ErrorBody),
+    )
+)]
 async fn get_download_status(
     State(state): State<AppState>,
     Path(id): Path<i64>,
-) -> Result<Json<download_manager::DownloadInfo>, StatusCode> {
+) -> Result<Json<download_manager::DownloadInfo>, ApiError> {
     state.download_manager.get_download(id)
         .await
         .map(Json)
         .map_err(|e| {
-            eprintln!("Error getting download {}: {}", id, e);
-            StatusCode::NOT_FOUND
+            tracing::error!("Error getting download {}: {}", id, e);
+            ApiError::not_found("Download not found")
         })
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/downloads/{id}",
+    tag = "downloads",
+    params(("id" = i64, Path, description = "Download ID")),
+    responses(
+        (status = 200, description = "Cancelled", body = ApiResponse),
+        (status = 401, description = "Not logged in", body = api_error::ErrorBody),
+    ),
+    security(("session" = []))
+)]
 async fn cancel_download(
     State(state): State<AppState>,
     headers: HeaderMap,
     Path(id): Path<i64>,
-) -> Result<Json<ApiResponse>, (StatusCode, Json<ApiResponse>)> {
-    // Require authentication
-    let _user = get_current_user(&state.db, &headers).await
-        .map_err(|e| (StatusCode::UNAUTHORIZED, Json(ApiResponse {
-            success: false, message: e, downloads: None, download_id: None,
-        })))?;
+) -> Result<Json<ApiResponse>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+    require_download_access(&state, &user, id).await?;
+
+    // Client downloads are cancelled by telling the client; the rest run on the server
+    let cancelled_on_client = state.client_download_manager.cancel_download(id)
+        .await
+        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+
+    if cancelled_on_client {
+        return Ok(Json(ApiResponse {
+            success: true,
+            message: "Download cancelled".to_string(),
+            downloads: None,
+            download_id: None,
+        }));
+    }
 
     state.download_manager.cancel_download(id)
         .await
@@ -1725,24 +3703,28 @@ async fn cancel_download(
             downloads: None,
             download_id: None,
         }))
-        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ApiResponse {
-            success: false,
-            message: e.to_string(),
-            downloads: None,
-            download_id: None,
-        })))
+        .map_err(|e| ApiError::bad_request(e.to_string()))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/downloads/{id}/retry",
+    tag = "downloads",
+    params(("id" = i64, Path, description = "Download ID")),
+    responses(
+        (status = 200, description = "Re-queued", body = ApiResponse),
+        (status = 401, description = "Not logged in", body = api_error::ErrorBody),
+    ),
+    security(("session" = []))
+)]
 async fn retry_download(
     State(state): State<AppState>,
     headers: HeaderMap,
     Path(id): Path<i64>,
-) -> Result<Json<ApiResponse>, (StatusCode, Json<ApiResponse>)> {
+) -> Result<Json<ApiResponse>, ApiError> {
     // Require authentication
     let _user = get_current_user(&state.db, &headers).await
-        .map_err(|e| (StatusCode::UNAUTHORIZED, Json(ApiResponse {
-            success: false, message: e, downloads: None, download_id: None,
-        })))?;
+        .map_err(ApiError::unauthorized)?;
 
     state.download_manager.retry_download(id)
         .await
@@ -1752,24 +3734,177 @@ async fn retry_download(
             downloads: None,
             download_id: None,
         }))
-        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ApiResponse {
-            success: false,
-            message: e.to_string(),
+        .map_err(|e| ApiError::bad_request(e.to_string()))
+}
+
+/// Remember a user-supplied archive password for a game (blank passwords are ignored)
+async fn save_archive_password(state: &AppState, game_id: i64, password: Option<&str>) {
+    if let Some(password) = password.filter(|p| !p.is_empty()) {
+        if let Err(e) = db::set_archive_password(&state.db, game_id, password).await {
+            tracing::error!("Error saving archive password for game {}: {}", game_id, e);
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+struct AssignDownloadRequest {
+    /// One of your linked clients
+    client_id: String,
+}
+
+/// Move a client download to another of the user's clients, e.g. from a PC
+/// that's offline to one that's online
+#[utoipa::path(
+    post,
+    path = "/api/downloads/{id}/assign",
+    tag = "downloads",
+    params(("id" = i64, Path, description = "Download ID")),
+    request_body = AssignDownloadRequest,
+    responses(
+        (status = 200, description = "Download moved to the client", body = ApiResponse),
+        (status = 400, description = "The download can't be moved", body = api_error::ErrorBody),
+        (status = 401, description = "Not logged in", body = api_error::ErrorBody),
+        (status = 403, description = "The download or client isn't yours", body = api_error::ErrorBody),
+        (status = 404, description = "Download or client not found", body = api_error::ErrorBody),
+    ),
+    security(("session" = []))
+)]
+async fn assign_download(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<i64>,
+    Json(payload): Json<AssignDownloadRequest>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+
+    let owner: Option<(Option<i64>,)> = sqlx::query_as("SELECT user_id FROM downloads WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await?;
+    match owner {
+        None => return Err(ApiError::not_found("Download not found")),
+        Some((None,)) => return Err(ApiError::bad_request("Only client downloads can be moved")),
+        Some((Some(owner),)) if owner != user.id => {
+            return Err(ApiError::forbidden("This download is not yours"));
+        }
+        Some(_) => {}
+    }
+
+    // Same check as unlinking: the client has to be linked to this user
+    let client = db::get_client(&state.db, &payload.client_id)
+        .await
+        .map_err(ApiError::internal)?
+        .ok_or(ApiError::not_found("Client not found"))?;
+    if client.user_id != Some(user.id) {
+        return Err(ApiError::forbidden("This client is not linked to your account"));
+    }
+
+    state.client_download_manager.assign_download(id, &client.client_id)
+        .await
+        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: format!("Download moved to {}", client.client_name),
+        downloads: None,
+        download_id: Some(id),
+    }))
+}
+
+/// Store the archive password for a download's game and retry it if it
+/// failed because the archive was encrypted
+#[utoipa::path(
+    post,
+    path = "/api/downloads/{id}/password",
+    tag = "downloads",
+    params(("id" = i64, Path, description = "Download ID")),
+    request_body = ArchivePasswordRequest,
+    responses(
+        (status = 200, description = "Password saved", body = ApiResponse),
+        (status = 401, description = "Not logged in", body = api_error::ErrorBody),
+    ),
+    security(("session" = []))
+)]
+async fn set_download_password(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<i64>,
+    Json(payload): Json<ArchivePasswordRequest>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+    require_download_access(&state, &user, id).await?;
+
+    let row: Option<(i64, String, Option<i64>)> = sqlx::query_as(
+        "SELECT game_id, status, user_id FROM downloads WHERE id = ?"
+    )
+    .bind(id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(ApiError::internal)?;
+
+    let (game_id, status, user_id) = row.ok_or_else(|| ApiError::not_found("Download not found"))?;
+
+    let password = payload.password.trim();
+    if password.is_empty() {
+        return Err(ApiError::bad_request("Password cannot be empty"));
+    }
+    save_archive_password(&state, game_id, Some(password)).await;
+
+    if status != "failed" {
+        return Ok(Json(ApiResponse {
+            success: true,
+            message: "Password saved".to_string(),
             downloads: None,
-            download_id: None,
-        })))
+            download_id: Some(id),
+        }));
+    }
+
+    // Client downloads go back to 'pending' for the agent; server downloads are requeued
+    let result = if user_id.is_some() {
+        debrid::forget_cached_links(&state.db, id).await;
+        sqlx::query("UPDATE downloads SET status = 'pending', progress = 0.0, error_message = NULL WHERE id = ?")
+            .bind(id)
+            .execute(&state.db)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    } else {
+        state.download_manager.retry_download(id).await.map_err(|e| e.to_string())
+    };
+    state.events.download_changed(id).await;
+
+    result
+        .map(|_| Json(ApiResponse {
+            success: true,
+            message: "Password saved, retrying download".to_string(),
+            downloads: None,
+            download_id: Some(id),
+        }))
+        .map_err(ApiError::bad_request)
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/downloads/{id}/remove",
+    tag = "downloads",
+    params(("id" = i64, Path, description = "Download ID")),
+    responses(
+        (status = 200, description = "Removed from the list; files are kept", body = ApiResponse),
+        (status = 400, description = "Only completed or failed downloads can be removed", body = api_error::ErrorBody),
+        (status = 401, description = "Not logged in", body = api_error::ErrorBody),
+    ),
+    security(("session" = []))
+)]
 async fn remove_download(
     State(state): State<AppState>,
     headers: HeaderMap,
     Path(id): Path<i64>,
-) -> Result<Json<ApiResponse>, (StatusCode, Json<ApiResponse>)> {
+) -> Result<Json<ApiResponse>, ApiError> {
     // Require authentication
     let _user = get_current_user(&state.db, &headers).await
-        .map_err(|e| (StatusCode::UNAUTHORIZED, Json(ApiResponse {
-            success: false, message: e, downloads: None, download_id: None,
-        })))?;
+        .map_err(ApiError::unauthorized)?;
 
     state.download_manager.remove_download(id)
         .await
@@ -1779,18 +3914,13 @@ async fn remove_download(
             downloads: None,
             download_id: None,
         }))
-        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ApiResponse {
-            success: false,
-            message: e.to_string(),
-            downloads: None,
-            download_id: None,
-        })))
+        .map_err(|e| ApiError::bad_request(e.to_string()))
 }
 
 async fn launch_install(
     State(state): State<AppState>,
     Path(id): Path<i64>,
-) -> Result<Json<ApiResponse>, (StatusCode, Json<ApiResponse>)> {
+) -> Result<Json<ApiResponse>, ApiError> {
     state.download_manager.launch_installer(id)
         .await
         .map(|path| Json(ApiResponse {
@@ -1799,18 +3929,13 @@ async fn launch_install(
             downloads: None,
             download_id: None,
         }))
-        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ApiResponse {
-            success: false,
-            message: e.to_string(),
-            downloads: None,
-            download_id: None,
-        })))
+        .map_err(|e| ApiError::bad_request(e.to_string()))
 }
 
 async fn mark_installed(
     State(state): State<AppState>,
     Path(id): Path<i64>,
-) -> Result<Json<ApiResponse>, (StatusCode, Json<ApiResponse>)> {
+) -> Result<Json<ApiResponse>, ApiError> {
     state.download_manager.mark_installed(id)
         .await
         .map(|_| Json(ApiResponse {
@@ -1819,48 +3944,197 @@ async fn mark_installed(
             downloads: None,
             download_id: None,
         }))
-        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ApiResponse {
-            success: false,
-            message: e.to_string(),
+        .map_err(|e| ApiError::bad_request(e.to_string()))
+}
+
+/// Move a finished download into the library directory
+async fn archive_download(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<i64>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+    require_download_access(&state, &user, id).await?;
+
+    state.download_manager.archive_download(id)
+        .await
+        .map(|path| Json(ApiResponse {
+            success: true,
+            message: format!("Moved to library: {}", path),
+            downloads: None,
+            download_id: Some(id),
+        }))
+        .map_err(|e| ApiError::bad_request(e.to_string()))
+}
+
+/// Extract a download's remaining archives again without re-downloading them
+async fn reextract_download(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<i64>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    // Require authentication
+    let _user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+
+    state.download_manager.reextract_download(id)
+        .await
+        .map(|count| Json(ApiResponse {
+            success: true,
+            message: format!("Re-extracting {} archive(s)", count),
             downloads: None,
-            download_id: None,
-        })))
+            download_id: Some(id),
+        }))
+        .map_err(|e| ApiError::bad_request(e.to_string()))
+}
+
+/// Whether `dir` is an absolute path on the client (drive letter, UNC share or Unix root)
+fn is_absolute_client_path(dir: &str) -> bool {
+    let bytes = dir.as_bytes();
+    let drive = bytes.len() >= 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes[2] == b'\\' || bytes[2] == b'/');
+    drive || dir.starts_with("\\\\") || dir.starts_with('/')
+}
+
+/// Set (or clear) the folder the client installs this download to
+#[utoipa::path(
+    post,
+    path = "/api/downloads/{id}/install-dir",
+    tag = "downloads",
+    params(("id" = i64, Path, description = "Download ID")),
+    request_body = InstallDirRequest,
+    responses(
+        (status = 200, description = "Install folder saved", body = ApiResponse),
+        (status = 401, description = "Not logged in", body = api_error::ErrorBody),
+    ),
+    security(("session" = []))
+)]
+async fn set_install_dir(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<i64>,
+    Json(payload): Json<InstallDirRequest>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+    require_download_access(&state, &user, id).await?;
+
+    let status: Option<(String,)> = sqlx::query_as("SELECT status FROM downloads WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(ApiError::internal)?;
+
+    let (status,) = status.ok_or_else(|| ApiError::not_found("Download not found"))?;
+
+    if status == "installing" || status == "installed" {
+        return Err(ApiError::bad_request(format!("Cannot change the install folder once the download is {}", status)));
+    }
+
+    let install_dir = payload.install_dir
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty());
+
+    if let Some(dir) = &install_dir {
+        if !is_absolute_client_path(dir) {
+            return Err(ApiError::bad_request(format!("Install folder must be an absolute path, e.g. D:\\Games: {}", dir)));
+        }
+    }
+
+    sqlx::query("UPDATE downloads SET install_dir = ? WHERE id = ?")
+        .bind(&install_dir)
+        .bind(id)
+        .execute(&state.db)
+        .await
+        .map_err(ApiError::internal)?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: match install_dir {
+            Some(dir) => format!("Will install to {}", dir),
+            None => "Will install to the client's default folder".to_string(),
+        },
+        downloads: None,
+        download_id: Some(id),
+    }))
 }
 
 async fn validate_download(
     State(state): State<AppState>,
     Path(id): Path<i64>,
-) -> Result<Json<md5_validator::ValidationResult>, (StatusCode, String)> {
+) -> Result<Json<md5_validator::ValidationResult>, ApiError> {
     // Get download info to find the directory
     let download = state.download_manager.get_download(id)
         .await
-        .map_err(|e| (StatusCode::NOT_FOUND, format!("Download not found: {}", e)))?;
+        .map_err(|e| ApiError::not_found(format!("Download not found: {}", e)))?;
 
     let file_path = download.file_path
-        .ok_or_else(|| (StatusCode::BAD_REQUEST, "Download has no file path".to_string()))?;
+        .ok_or_else(|| ApiError::bad_request("Download has no file path"))?;
 
     let dir = std::path::Path::new(&file_path);
 
     if !dir.exists() {
-        return Err((StatusCode::NOT_FOUND, "Download directory does not exist".to_string()));
+        return Err(ApiError::not_found("Download directory does not exist"));
     }
 
     if !dir.is_dir() {
-        return Err((StatusCode::BAD_REQUEST, "Download path is not a directory".to_string()));
+        return Err(ApiError::bad_request("Download path is not a directory"));
     }
 
     println!("Validating MD5 checksums for download {} in {}", id, dir.display());
 
-    md5_validator::validate_directory(dir)
+    match md5_validator::validate_directory(dir).await {
+        Ok(result) => {
+            let _ = download_manager::set_md5_status(&state.db, id, &result.md5_status()).await;
+            Ok(Json(result))
+        }
+        Err(e) => {
+            let _ = download_manager::set_md5_status(&state.db, id, "failed").await;
+            Err(ApiError::internal(format!("Validation error: {}", e)))
+        }
+    }
+}
+
+/// Write an md5sums.txt for a download so it can be re-validated later
+async fn generate_download_md5(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<i64>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+    require_download_access(&state, &user, id).await?;
+
+    let download = state.download_manager.get_download(id)
         .await
-        .map(Json)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Validation error: {}", e)))
+        .map_err(|e| ApiError::not_found(format!("Download not found: {}", e)))?;
+
+    let file_path = download.file_path
+        .ok_or_else(|| ApiError::bad_request("Download has no file path"))?;
+
+    let dir = std::path::Path::new(&file_path);
+
+    if !dir.is_dir() {
+        return Err(ApiError::bad_request("Download path is not a directory"));
+    }
+
+    match md5_validator::generate_manifest(dir).await {
+        Ok(count) => Ok(Json(serde_json::json!({
+            "success": true,
+            "files_hashed": count,
+            "path": dir.join(md5_validator::GENERATED_MANIFEST).to_string_lossy(),
+        }))),
+        Err(e) => Err(ApiError::internal(format!("Failed to generate MD5 manifest: {}", e))),
+    }
 }
 
 async fn delete_download(
     State(state): State<AppState>,
     Path(id): Path<i64>,
-) -> Result<Json<ApiResponse>, (StatusCode, Json<ApiResponse>)> {
+) -> Result<Json<ApiResponse>, ApiError> {
     state.download_manager.delete_download(id)
         .await
         .map(|_| Json(ApiResponse {
@@ -1869,33 +4143,31 @@ async fn delete_download(
             downloads: None,
             download_id: None,
         }))
-        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ApiResponse {
-            success: false,
-            message: e.to_string(),
-            downloads: None,
-            download_id: None,
-        })))
+        .map_err(|e| ApiError::bad_request(e.to_string()))
 }
 
 async fn scan_existing_games(
     State(state): State<AppState>,
-) -> Result<Json<ApiResponse>, (StatusCode, Json<ApiResponse>)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     match state.download_manager.scan_existing_games().await {
-        Ok(count) => {
-            Ok(Json(ApiResponse {
-                success: true,
-                message: format!("Scanned and imported {} existing game(s)", count),
-                downloads: None,
-                download_id: None,
-            }))
+        Ok(result) => {
+            let mut message = format!("Scanned and imported {} existing game(s)", result.imported);
+            if !result.unmatched.is_empty() {
+                message.push_str(&format!(
+                    "; {} folder(s) didn't match a game: {}",
+                    result.unmatched.len(),
+                    result.unmatched.join(", ")
+                ));
+            }
+            Ok(Json(serde_json::json!({
+                "success": true,
+                "message": message,
+                "imported": result.imported,
+                "unmatched": result.unmatched,
+            })))
         }
         Err(e) => {
-            Err((StatusCode::INTERNAL_SERVER_ERROR, Json(ApiResponse {
-                success: false,
-                message: format!("Scan failed: {}", e),
-                downloads: None,
-                download_id: None,
-            })))
+            Err(ApiError::internal(format!("Scan failed: {}", e)))
         }
     }
 }
@@ -1903,7 +4175,7 @@ async fn scan_existing_games(
 async fn download_file(
     State(state): State<AppState>,
     Path(file_id): Path<i64>,
-) -> Result<Response, (StatusCode, String)> {
+) -> Result<Response, ApiError> {
     // Get file info from database
     let file_info: Option<(String, Option<String>)> = sqlx::query_as(
         "SELECT filename, file_path FROM download_files WHERE id = ?"
@@ -1911,29 +4183,29 @@ async fn download_file(
     .bind(file_id)
     .fetch_optional(&state.db)
     .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+    .map_err(|e| ApiError::internal(format!("Database error: {}", e)))?;
 
     let (filename, file_path) = file_info
-        .ok_or_else(|| (StatusCode::NOT_FOUND, "File not found".to_string()))?;
+        .ok_or_else(|| ApiError::not_found("File not found"))?;
 
     let path = file_path
-        .ok_or_else(|| (StatusCode::NOT_FOUND, "File path not available".to_string()))?;
+        .ok_or_else(|| ApiError::not_found("File path not available"))?;
 
     let file_path = std::path::Path::new(&path);
 
     if !file_path.exists() {
-        return Err((StatusCode::NOT_FOUND, "File does not exist on disk".to_string()));
+        return Err(ApiError::not_found("File does not exist on disk"));
     }
 
     // Open the file
     let file = tokio::fs::File::open(&file_path)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to open file: {}", e)))?;
+        .map_err(|e| ApiError::internal(format!("Failed to open file: {}", e)))?;
 
     // Get file size
     let metadata = file.metadata()
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to read metadata: {}", e)))?;
+        .map_err(|e| ApiError::internal(format!("Failed to read metadata: {}", e)))?;
     let file_size = metadata.len();
 
     // Create a stream
@@ -1966,7 +4238,7 @@ struct SettingsPayload {
 }
 
 /// Allowed setting keys (whitelist for security)
-const ALLOWED_SETTINGS: &[&str] = &["rawg_api_key", "rd_api_key"];
+const ALLOWED_SETTINGS: &[&str] = &["rawg_api_key", "rd_api_key", "ad_api_key", notifications::DISCORD_WEBHOOK_SETTING, notifications::DOWNLOAD_WEBHOOK_SETTING, notifications::TELEGRAM_BOT_TOKEN_SETTING];
 
 /// Mask an API key for display: show first 4 and last 4 chars
 fn mask_key(key: &str) -> String {
@@ -1979,10 +4251,10 @@ fn mask_key(key: &str) -> String {
 async fn get_settings(
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> Result<Json<SettingsResponse>, StatusCode> {
+) -> Result<Json<SettingsResponse>, ApiError> {
     // Get current user
     let user = get_current_user(&state.db, &headers).await
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+        .map_err(ApiError::unauthorized)?;
 
     // Get global settings (API keys)
     let pairs = db::get_all_settings(&state.db).await.unwrap_or_default();
@@ -2002,6 +4274,35 @@ async fn get_settings(
         }
     }
 
+    // Selected debrid provider is not secret, return it as-is
+    let provider = db::get_setting(&state.db, "debrid_provider").await
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "realdebrid".to_string());
+    settings.insert("debrid_provider".to_string(), provider);
+
+    // Pre-install warning thresholds, also not secret
+    let thresholds = installation_checker::load_thresholds(&state.db).await;
+    settings.insert(installation_checker::MIN_RAM_SETTING.to_string(), thresholds.min_available_ram_gb.to_string());
+    settings.insert(installation_checker::MIN_TEMP_SETTING.to_string(), thresholds.min_free_temp_gb.to_string());
+
+    // Download cleanup policy
+    let cleanup = download_cleanup::load_policy(&state.db).await;
+    settings.insert(download_cleanup::MODE_SETTING.to_string(), cleanup.mode.as_str().to_string());
+    settings.insert(download_cleanup::RETENTION_DAYS_SETTING.to_string(), cleanup.retention_days.to_string());
+    settings.insert(
+        download_cleanup::MAX_DISK_GB_SETTING.to_string(),
+        cleanup.max_disk_gb.map(|gb| gb.to_string()).unwrap_or_default(),
+    );
+    settings.insert(download_cleanup::DELETE_FILES_SETTING.to_string(), cleanup.delete_files.to_string());
+
+    // Custom genre aliases, empty while the built-in map is in use
+    let genre_aliases = db::get_setting(&state.db, genres::GENRE_ALIASES_SETTING).await
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    settings.insert(genres::GENRE_ALIASES_SETTING.to_string(), genre_aliases);
+
     // Get user-specific settings
     let user_settings = db::get_user_settings(&state.db, user.id)
         .await
@@ -2015,7 +4316,13 @@ async fn get_settings(
             scraper_steamrip_enabled: Some(true),
             notify_download_complete: Some(true),
             notify_new_games: Some(false),
-            notify_errors: Some(true),
+            notify_download_failed: Some(true),
+            notify_install_failed: Some(true),
+            notify_client_offline: Some(true),
+            notify_client_online: Some(false),
+            notify_scrape_error: Some(true),
+            telegram_chat_id: None,
+            notify_email: Some(false),
         });
 
     settings.insert("theme".to_string(), user_settings.theme.unwrap_or_else(|| "dark".to_string()));
@@ -2026,7 +4333,15 @@ async fn get_settings(
     settings.insert("scraper_steamrip_enabled".to_string(), user_settings.scraper_steamrip_enabled.unwrap_or(true).to_string());
     settings.insert("notify_download_complete".to_string(), user_settings.notify_download_complete.unwrap_or(true).to_string());
     settings.insert("notify_new_games".to_string(), user_settings.notify_new_games.unwrap_or(false).to_string());
-    settings.insert("notify_errors".to_string(), user_settings.notify_errors.unwrap_or(true).to_string());
+    settings.insert("notify_download_failed".to_string(), user_settings.notify_download_failed.unwrap_or(true).to_string());
+    settings.insert("notify_install_failed".to_string(), user_settings.notify_install_failed.unwrap_or(true).to_string());
+    settings.insert("notify_client_offline".to_string(), user_settings.notify_client_offline.unwrap_or(true).to_string());
+    settings.insert("notify_client_online".to_string(), user_settings.notify_client_online.unwrap_or(false).to_string());
+    settings.insert("notify_scrape_error".to_string(), user_settings.notify_scrape_error.unwrap_or(true).to_string());
+    settings.insert("telegram_chat_id".to_string(), user_settings.telegram_chat_id.unwrap_or_default());
+    settings.insert("notify_email".to_string(), user_settings.notify_email.unwrap_or(false).to_string());
+    // Whether SMTP is configured, so the UI only offers email when it can work
+    settings.insert("email_available".to_string(), state.notifier.mailer().is_some().to_string());
 
     Ok(Json(SettingsResponse {
         success: true,
@@ -2034,16 +4349,28 @@ async fn get_settings(
     }))
 }
 
+/// Save a server-wide setting, or delete it when the value is blank
+async fn store_global_setting(state: &AppState, key: &str, value: &str) -> Result<(), ApiError> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        db::delete_setting(&state.db, key).await.map_err(|e| {
+            ApiError::internal(format!("Failed to delete setting: {}", e))
+        })
+    } else {
+        db::set_setting(&state.db, key, trimmed).await.map_err(|e| {
+            ApiError::internal(format!("Failed to save setting: {}", e))
+        })
+    }
+}
+
 async fn save_settings(
     State(state): State<AppState>,
     headers: HeaderMap,
     Json(payload): Json<SettingsPayload>,
-) -> Result<Json<ApiResponse>, (StatusCode, Json<ApiResponse>)> {
+) -> Result<Json<ApiResponse>, ApiError> {
     // Get current user
     let user = get_current_user(&state.db, &headers).await
-        .map_err(|e| (StatusCode::UNAUTHORIZED, Json(ApiResponse {
-            success: false, message: e, downloads: None, download_id: None,
-        })))?;
+        .map_err(ApiError::unauthorized)?;
 
     // Separate global settings (API keys) from user settings
     let mut user_settings = db::UserSettings {
@@ -2056,42 +4383,111 @@ async fn save_settings(
         scraper_steamrip_enabled: None,
         notify_download_complete: None,
         notify_new_games: None,
-        notify_errors: None,
+        notify_download_failed: None,
+        notify_install_failed: None,
+        notify_client_offline: None,
+        notify_client_online: None,
+        notify_scrape_error: None,
+        telegram_chat_id: None,
+        notify_email: None,
     };
 
     for (key, value) in &payload.settings {
         match key.as_str() {
             // Global settings (API keys)
-            "rawg_api_key" | "rd_api_key" => {
+            "rawg_api_key" | "rd_api_key" | "ad_api_key" => {
                 if !ALLOWED_SETTINGS.contains(&key.as_str()) {
-                    return Err((StatusCode::BAD_REQUEST, Json(ApiResponse {
-                        success: false,
-                        message: format!("Unknown setting: {}", key),
-                        downloads: None,
-                        download_id: None,
-                    })));
+                    return Err(ApiError::bad_request(format!("Unknown setting: {}", key)));
+                }
+
+                store_global_setting(&state, key, value).await?;
+            },
+            notifications::DISCORD_WEBHOOK_SETTING => {
+                // Posts for every user's downloads go to this channel
+                if !user.is_admin {
+                    return Err(ApiError::forbidden("Admin access required to change the Discord webhook"));
                 }
 
                 let trimmed = value.trim();
-                if trimmed.is_empty() {
-                    db::delete_setting(&state.db, key).await.map_err(|e| {
-                        (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiResponse {
-                            success: false,
-                            message: format!("Failed to delete setting: {}", e),
-                            downloads: None,
-                            download_id: None,
-                        }))
-                    })?;
-                } else {
-                    db::set_setting(&state.db, key, trimmed).await.map_err(|e| {
-                        (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiResponse {
-                            success: false,
-                            message: format!("Failed to save setting: {}", e),
-                            downloads: None,
-                            download_id: None,
-                        }))
-                    })?;
+                if !trimmed.is_empty() && !notifications::is_discord_webhook_url(trimmed) {
+                    return Err(ApiError::bad_request("Discord webhook URL must start with https://discord.com/api/webhooks/"));
+                }
+                store_global_setting(&state, key, trimmed).await?;
+            },
+            notifications::DOWNLOAD_WEBHOOK_SETTING => {
+                // Receives game titles and usernames for every download
+                if !user.is_admin {
+                    return Err(ApiError::forbidden("Admin access required to change the download webhook"));
+                }
+
+                let trimmed = value.trim();
+                if !trimmed.is_empty() && !notifications::is_http_url(trimmed) {
+                    return Err(ApiError::bad_request("Download webhook must be an http:// or https:// URL"));
+                }
+                store_global_setting(&state, key, trimmed).await?;
+            },
+            notifications::TELEGRAM_BOT_TOKEN_SETTING => {
+                // One bot sends every user's notifications
+                if !user.is_admin {
+                    return Err(ApiError::forbidden("Admin access required to change the Telegram bot"));
+                }
+
+                store_global_setting(&state, key, value).await?;
+            },
+            "debrid_provider" => {
+                // Switches the provider every server-side download goes through
+                if !user.is_admin {
+                    return Err(ApiError::forbidden("Admin access required to change the debrid provider"));
+                }
+
+                if !debrid::PROVIDERS.contains(&value.as_str()) {
+                    return Err(ApiError::bad_request(format!("Unknown debrid provider: {}", value)));
+                }
+
+                store_global_setting(&state, key, value).await?;
+            },
+            genres::GENRE_ALIASES_SETTING => {
+                // Changes the genre list and filter for everyone
+                if !user.is_admin {
+                    return Err(ApiError::forbidden("Admin access required to change genre aliases"));
                 }
+
+                let trimmed = value.trim();
+                if !trimmed.is_empty() {
+                    genres::GenreAliases::parse(trimmed).map_err(ApiError::bad_request)?;
+                }
+                store_global_setting(&state, key, trimmed).await?;
+                state.aggregate_cache.clear().await;
+            },
+            installation_checker::MIN_RAM_SETTING | installation_checker::MIN_TEMP_SETTING => {
+                // These change what every user's checks report
+                if !user.is_admin {
+                    return Err(ApiError::forbidden("Admin access required to change warning thresholds"));
+                }
+
+                let trimmed = value.trim();
+                if !trimmed.is_empty() {
+                    match trimmed.parse::<f64>() {
+                        Ok(gb) if gb.is_finite() && gb >= 0.0 => {}
+                        _ => return Err(ApiError::bad_request(format!("{} must be a non-negative number of GB", key))),
+                    }
+                }
+                store_global_setting(&state, key, trimmed).await?;
+            },
+            download_cleanup::MODE_SETTING
+            | download_cleanup::RETENTION_DAYS_SETTING
+            | download_cleanup::MAX_DISK_GB_SETTING
+            | download_cleanup::DELETE_FILES_SETTING => {
+                // Can delete files on the server
+                if !user.is_admin {
+                    return Err(ApiError::forbidden("Admin access required to change download cleanup"));
+                }
+
+                let trimmed = value.trim();
+                if !trimmed.is_empty() {
+                    download_cleanup::validate(key, trimmed).map_err(ApiError::bad_request)?;
+                }
+                store_global_setting(&state, key, trimmed).await?;
             },
             // User-specific settings
             "theme" => user_settings.theme = Some(value.clone()),
@@ -2102,26 +4498,37 @@ async fn save_settings(
             "scraper_steamrip_enabled" => user_settings.scraper_steamrip_enabled = value.parse().ok(),
             "notify_download_complete" => user_settings.notify_download_complete = value.parse().ok(),
             "notify_new_games" => user_settings.notify_new_games = value.parse().ok(),
-            "notify_errors" => user_settings.notify_errors = value.parse().ok(),
+            "notify_download_failed" => user_settings.notify_download_failed = value.parse().ok(),
+            "notify_install_failed" => user_settings.notify_install_failed = value.parse().ok(),
+            "notify_client_offline" => user_settings.notify_client_offline = value.parse().ok(),
+            "notify_client_online" => user_settings.notify_client_online = value.parse().ok(),
+            "notify_scrape_error" => user_settings.notify_scrape_error = value.parse().ok(),
+            // The old catch-all switch sets every failure type at once
+            "notify_errors" => {
+                let enabled = value.parse().ok();
+                user_settings.notify_download_failed = enabled;
+                user_settings.notify_install_failed = enabled;
+                user_settings.notify_client_offline = enabled;
+                user_settings.notify_scrape_error = enabled;
+            },
+            "notify_email" => user_settings.notify_email = value.parse().ok(),
+            "telegram_chat_id" => {
+                // Empty unlinks the chat
+                let trimmed = value.trim();
+                if !trimmed.is_empty() && !notifications::is_telegram_chat_id(trimmed) {
+                    return Err(ApiError::bad_request("Telegram chat ID must be a number or an @channel name"));
+                }
+                user_settings.telegram_chat_id = Some(trimmed.to_string());
+            },
             _ => {
-                return Err((StatusCode::BAD_REQUEST, Json(ApiResponse {
-                    success: false,
-                    message: format!("Unknown setting: {}", key),
-                    downloads: None,
-                    download_id: None,
-                })));
+                return Err(ApiError::bad_request(format!("Unknown setting: {}", key)));
             }
         }
     }
 
     // Save user settings
     db::update_user_settings(&state.db, user.id, &user_settings).await.map_err(|e| {
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiResponse {
-            success: false,
-            message: format!("Failed to save user settings: {}", e),
-            downloads: None,
-            download_id: None,
-        }))
+        ApiError::internal(format!("Failed to save user settings: {}", e))
     })?;
 
     Ok(Json(ApiResponse {
@@ -2136,7 +4543,8 @@ async fn save_settings(
 async fn get_system_info(
     State(state): State<AppState>,
 ) -> Json<serde_json::Value> {
-    let system_info = system_info::SystemInfo::gather().await;
+    let thresholds = installation_checker::load_thresholds(&state.db).await;
+    let system_info = system_info::SystemInfo::gather_with(thresholds).await;
 
     // Save to database
     let _ = db::insert_system_check(
@@ -2159,11 +4567,24 @@ async fn get_system_info(
     )
     .await;
 
+    // Mount points of the drives new downloads and archived games land on
+    let download_drive = system_info::drive_for_path(&system_info.drives, state.download_manager.download_dir())
+        .map(|d| d.mount_point.clone());
+    let library_drive = state
+        .download_manager
+        .library_dir()
+        .and_then(|dir| system_info::drive_for_path(&system_info.drives, dir))
+        .map(|d| d.mount_point.clone());
+
     Json(serde_json::json!({
         "ram_total_gb": system_info.ram_total_gb,
         "ram_available_gb": system_info.ram_available_gb,
         "temp_space_gb": system_info.temp_space_gb,
         "cpu_cores": system_info.cpu_cores,
+        "gpus": system_info.gpus,
+        "drives": system_info.drives,
+        "download_drive": download_drive,
+        "library_drive": library_drive,
         "antivirus_active": system_info.antivirus_active,
         "missing_dlls": system_info.missing_dlls,
         "missing_dependencies": system_info.missing_dependencies,
@@ -2177,14 +4598,124 @@ async fn get_system_info(
 async fn check_pre_install(
     State(state): State<AppState>,
     Path(game_id): Path<i64>,
-) -> Result<Json<installation_checker::PreInstallCheckResult>, (StatusCode, String)> {
+) -> Result<Json<installation_checker::PreInstallCheckResult>, ApiError> {
     match installation_checker::check_pre_installation(&state.db, game_id).await {
         Ok(result) => Ok(Json(result)),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Pre-installation check failed: {}", e),
-        )),
+        Err(e) => Err(ApiError::internal(format!("Pre-installation check failed: {}", e))),
+    }
+}
+
+// ─── Backup / Restore ───
+
+/// Download a consistent snapshot of the whole database
+async fn backup_database(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+    if !user.is_admin {
+        return Err(ApiError::forbidden("Admin access required"));
+    }
+
+    let snapshot = backup::TempFile::new("repack-backup");
+    backup::backup_to(&state.db, &snapshot.path).await.map_err(|e| {
+        tracing::error!("Backup failed: {}", e);
+        ApiError::internal(format!("Backup failed: {}", e))
+    })?;
+
+    let file = tokio::fs::File::open(&snapshot.path)
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to open backup: {}", e)))?;
+    let file_size = file.metadata()
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to read metadata: {}", e)))?
+        .len();
+
+    // The snapshot is deleted once the stream (and with it the file) is dropped
+    let stream = ReaderStream::new(file).map(move |chunk| {
+        let _ = &snapshot;
+        chunk
+    });
+    let filename = format!("repack-browser-{}.db", chrono::Utc::now().format("%Y%m%d-%H%M%S"));
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/vnd.sqlite3")
+        .header(header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename))
+        .header(header::CONTENT_LENGTH, file_size.to_string())
+        .body(Body::from_stream(stream))
+        .unwrap())
+}
+
+/// Replace the database with an uploaded backup
+async fn restore_database(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    mut multipart: Multipart,
+) -> Result<Json<ApiResponse>, ApiError> {
+    let user = get_current_user(&state.db, &headers).await
+        .map_err(ApiError::unauthorized)?;
+    if !user.is_admin {
+        return Err(ApiError::forbidden("Admin access required"));
+    }
+
+    // Workers would keep writing rows the restore is about to replace
+    let (active,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM downloads WHERE status IN ('downloading', 'extracting')"
+    )
+    .fetch_one(&state.db)
+    .await?;
+    if active > 0 || state.scrape_status.read().await.is_running {
+        return Err(ApiError::conflict("Wait for active downloads and scrapes to finish before restoring"));
+    }
+
+    let mut field = multipart
+        .next_field()
+        .await
+        .map_err(|e| ApiError::bad_request(format!("Failed to read upload: {}", e)))?
+        .ok_or_else(|| ApiError::bad_request("No file provided"))?;
+    if field.name() != Some("file") {
+        return Err(ApiError::bad_request("Expected field named 'file'"));
+    }
+
+    // Stream to disk; backups can be far larger than the default body limit
+    let upload = backup::TempFile::new("repack-restore");
+    {
+        use tokio::io::AsyncWriteExt;
+        let mut out = tokio::fs::File::create(&upload.path)
+            .await
+            .map_err(|e| ApiError::internal(format!("Failed to store upload: {}", e)))?;
+        while let Some(chunk) = field
+            .chunk()
+            .await
+            .map_err(|e| ApiError::bad_request(format!("Failed to read upload: {}", e)))?
+        {
+            out.write_all(&chunk)
+                .await
+                .map_err(|e| ApiError::internal(format!("Failed to store upload: {}", e)))?;
+        }
+        out.flush()
+            .await
+            .map_err(|e| ApiError::internal(format!("Failed to store upload: {}", e)))?;
     }
+
+    backup::restore_from(&state.db, &upload.path).await.map_err(|e| {
+        tracing::error!("{}", e);
+        match e {
+            backup::RestoreError::Invalid(_) => ApiError::bad_request(e.to_string()),
+            backup::RestoreError::Database(_) => ApiError::internal(e),
+        }
+    })?;
+    state.aggregate_cache.clear().await;
+    println!("♻️  Database restored from backup by {}", user.username);
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Database restored. Sessions were restored too, so you may need to log in again.".to_string(),
+        downloads: None,
+        download_id: None,
+    }))
 }
 
 // ─── Installation Assistant Handlers ───
@@ -2192,21 +4723,36 @@ async fn check_pre_install(
 #[derive(Deserialize)]
 struct AssistantActionsRequest {
     missing_dlls: Vec<String>,
+    #[serde(default)]
     missing_dependencies: Vec<String>,
     antivirus_active: bool,
     install_path: Option<String>,
+    /// Check runtimes against this game's requirements instead of using
+    /// `missing_dependencies`
+    #[serde(default)]
+    game_id: Option<i64>,
 }
 
 async fn get_assistant_actions(
+    State(state): State<AppState>,
     Json(req): Json<AssistantActionsRequest>,
-) -> Json<Vec<installation_assistant::AssistantAction>> {
+) -> Result<Json<Vec<installation_assistant::AssistantAction>>, ApiError> {
+    let missing_dependencies = match req.game_id {
+        Some(game_id) => {
+            let system_info = system_info::SystemInfo::gather().await;
+            let reqs = db::get_game_requirements(&state.db, game_id).await?;
+            installation_checker::missing_runtimes(&system_info, reqs.as_ref())
+        }
+        None => req.missing_dependencies,
+    };
+
     let actions = installation_assistant::get_recommended_actions(
         &req.missing_dlls,
-        &req.missing_dependencies,
+        &missing_dependencies,
         req.antivirus_active,
         req.install_path.as_deref(),
     );
-    Json(actions)
+    Ok(Json(actions))
 }
 
 #[derive(Deserialize)]
@@ -2216,7 +4762,7 @@ struct InstallDllRequest {
 
 async fn assistant_install_dll(
     Json(req): Json<InstallDllRequest>,
-) -> Result<Json<ApiResponse>, (StatusCode, String)> {
+) -> Result<Json<ApiResponse>, ApiError> {
     match installation_assistant::install_dll(&req.dll_name).await {
         Ok(message) => Ok(Json(ApiResponse {
             success: true,
@@ -2224,10 +4770,7 @@ async fn assistant_install_dll(
             downloads: None,
             download_id: None,
         })),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("DLL installation failed: {}", e),
-        )),
+        Err(e) => Err(ApiError::internal(format!("DLL installation failed: {}", e))),
     }
 }
 
@@ -2238,7 +4781,7 @@ struct AddExclusionRequest {
 
 async fn assistant_add_exclusion(
     Json(req): Json<AddExclusionRequest>,
-) -> Result<Json<ApiResponse>, (StatusCode, String)> {
+) -> Result<Json<ApiResponse>, ApiError> {
     match installation_assistant::add_av_exclusion(&req.path).await {
         Ok(message) => Ok(Json(ApiResponse {
             success: true,
@@ -2246,10 +4789,7 @@ async fn assistant_add_exclusion(
             downloads: None,
             download_id: None,
         })),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to add exclusion: {}", e),
-        )),
+        Err(e) => Err(ApiError::internal(format!("Failed to add exclusion: {}", e))),
     }
 }
 
@@ -2260,7 +4800,7 @@ struct ToggleAvRequest {
 
 async fn assistant_toggle_av(
     Json(req): Json<ToggleAvRequest>,
-) -> Result<Json<ApiResponse>, (StatusCode, String)> {
+) -> Result<Json<ApiResponse>, ApiError> {
     let result = if req.enable {
         installation_assistant::enable_realtime_protection().await
     } else {
@@ -2274,22 +4814,16 @@ async fn assistant_toggle_av(
             downloads: None,
             download_id: None,
         })),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to toggle antivirus: {}", e),
-        )),
+        Err(e) => Err(ApiError::internal(format!("Failed to toggle antivirus: {}", e))),
     }
 }
 
 async fn get_dependency_info(
     Path(dep): Path<String>,
-) -> Result<Json<installation_assistant::DependencyInfo>, (StatusCode, String)> {
+) -> Result<Json<installation_assistant::DependencyInfo>, ApiError> {
     match installation_assistant::get_dependency_installer_info(&dep) {
         Some(info) => Ok(Json(info)),
-        None => Err((
-            StatusCode::NOT_FOUND,
-            format!("No installer information available for: {}", dep),
-        )),
+        None => Err(ApiError::not_found(format!("No installer information available for: {}", dep))),
     }
 }
 
@@ -2298,43 +4832,41 @@ async fn get_dependency_info(
 async fn get_installation_history(
     State(state): State<AppState>,
     Path(game_id): Path<i64>,
-) -> Result<Json<Vec<db::InstallationLog>>, (StatusCode, String)> {
+) -> Result<Json<Vec<db::InstallationLog>>, ApiError> {
     match installation_monitor::get_installation_history(&state.db, game_id).await {
         Ok(logs) => Ok(Json(logs)),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to get installation history: {}", e),
-        )),
+        Err(e) => Err(ApiError::internal(format!("Failed to get installation history: {}", e))),
     }
 }
 
 async fn get_installation_stats(
     State(state): State<AppState>,
-) -> Result<Json<installation_monitor::InstallationStats>, (StatusCode, String)> {
+) -> Result<Json<installation_monitor::InstallationStats>, ApiError> {
     match installation_monitor::get_installation_stats(&state.db).await {
         Ok(stats) => Ok(Json(stats)),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to get installation stats: {}", e),
-        )),
+        Err(e) => Err(ApiError::internal(format!("Failed to get installation stats: {}", e))),
     }
 }
 
 async fn analyze_failed_installation(
     State(state): State<AppState>,
     Path(log_id): Path<i64>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     // Get the log
     let logs = installation_monitor::get_all_installation_logs(&state.db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(ApiError::internal)?;
 
     let log = logs
         .iter()
         .find(|l| l.id == log_id)
-        .ok_or((StatusCode::NOT_FOUND, "Installation log not found".to_string()))?;
+        .ok_or(ApiError::not_found("Installation log not found"))?;
 
-    let recommendations = installation_monitor::analyze_installation_failure(log);
+    let community = match log.game_id {
+        Some(game_id) => db::get_game_rating_stats(&state.db, game_id).await.ok(),
+        None => None,
+    };
+    let recommendations = installation_monitor::analyze_installation_failure(log, community.as_ref());
 
     Ok(Json(serde_json::json!({
         "log": log,
@@ -2349,6 +4881,12 @@ struct RegisterClientRequest {
     client_id: String,
     client_name: String,
     os_version: String,
+    /// Older clients don't send one
+    #[serde(default)]
+    bridge_token: Option<String>,
+    /// The token being replaced, when a client rotates its bridge token
+    #[serde(default)]
+    previous_bridge_token: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -2360,26 +4898,25 @@ struct RegisterClientResponse {
 async fn register_client(
     State(state): State<AppState>,
     Json(payload): Json<RegisterClientRequest>,
-) -> Result<Json<RegisterClientResponse>, (StatusCode, Json<RegisterClientResponse>)> {
+) -> Result<Json<RegisterClientResponse>, ApiError> {
     match db::register_client(
         &state.db,
         &payload.client_id,
         &payload.client_name,
         &payload.os_version,
+        payload.bridge_token.as_deref().filter(|t| !t.is_empty()),
+        payload.previous_bridge_token.as_deref().filter(|t| !t.is_empty()),
     )
     .await
     {
-        Ok(_) => Ok(Json(RegisterClientResponse {
+        Ok(true) => Ok(Json(RegisterClientResponse {
             success: true,
             message: format!("Client {} registered successfully", payload.client_name),
         })),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(RegisterClientResponse {
-                success: false,
-                message: format!("Failed to register client: {}", e),
-            }),
+        Ok(false) => Err(ApiError::forbidden(
+            "This client ID is registered with a different bridge token",
         )),
+        Err(e) => Err(ApiError::internal(format!("Failed to register client: {}", e))),
     }
 }
 
@@ -2416,7 +4953,7 @@ async fn get_client_queue(
             Json(items)
         }
         Err(e) => {
-            eprintln!("Error getting client queue: {}", e);
+            tracing::error!("Error getting client queue: {}", e);
             Json(Vec::new())
         }
     }
@@ -2437,7 +4974,7 @@ async fn update_client_progress(
     State(state): State<AppState>,
     Path(client_id): Path<String>,
     Json(payload): Json<ProgressUpdate>,
-) -> Result<StatusCode, (StatusCode, String)> {
+) -> Result<StatusCode, ApiError> {
     db::upsert_client_progress(
         &state.db,
         &client_id,
@@ -2451,7 +4988,90 @@ async fn update_client_progress(
         &payload.status,
     )
     .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    .map_err(ApiError::internal)?;
+
+    Ok(StatusCode::OK)
+}
+
+/// Commands waiting for a client (e.g. cancel a download)
+async fn get_client_commands(
+    State(state): State<AppState>,
+    Path(client_id): Path<String>,
+) -> Result<Json<Vec<db::ClientCommand>>, ApiError> {
+    state.client_download_manager.get_commands(&client_id)
+        .await
+        .map(Json)
+        .map_err(|e| ApiError::bad_request(e.to_string()))
+}
+
+/// Keep a client marked online between polls
+async fn client_heartbeat(
+    State(state): State<AppState>,
+    Path(client_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    match db::touch_client(&state.db, &client_id).await.map_err(ApiError::internal)? {
+        true => Ok(StatusCode::OK),
+        false => Err(ApiError::not_found("Client not registered")),
+    }
+}
+
+async fn ack_client_command(
+    State(state): State<AppState>,
+    Path((client_id, command_id)): Path<(String, i64)>,
+) -> Result<StatusCode, ApiError> {
+    state.client_download_manager.ack_command(&client_id, command_id)
+        .await
+        .map(|_| StatusCode::OK)
+        .map_err(|e| ApiError::bad_request(e.to_string()))
+}
+
+#[derive(Deserialize)]
+struct ClientInstalledReport {
+    /// Missing for installers the agent found on disk by itself
+    #[serde(default)]
+    game_id: Option<i64>,
+    success: bool,
+    /// RFC 3339 timestamps from the client
+    started_at: String,
+    completed_at: String,
+    #[serde(default)]
+    error_message: Option<String>,
+}
+
+/// Record an installation run by a client agent in installation_logs
+async fn report_client_installed(
+    State(state): State<AppState>,
+    Path(client_id): Path<String>,
+    Json(payload): Json<ClientInstalledReport>,
+) -> Result<StatusCode, ApiError> {
+    let duration_minutes = match (
+        chrono::DateTime::parse_from_rfc3339(&payload.started_at),
+        chrono::DateTime::parse_from_rfc3339(&payload.completed_at),
+    ) {
+        (Ok(start), Ok(end)) => Some((end - start).num_minutes().max(0)),
+        _ => None,
+    };
+
+    let log_id = db::insert_installation_log(&state.db, payload.game_id, "running")
+        .await
+        .map_err(ApiError::internal)?;
+
+    db::update_installation_log(
+        &state.db,
+        log_id,
+        if payload.success { "completed" } else { "failed" },
+        None,
+        payload.error_message,
+        None,
+        duration_minutes,
+    )
+    .await
+    .map_err(ApiError::internal)?;
+
+    println!("Client {} reported installation of game {} ({})",
+        client_id,
+        payload.game_id.map_or_else(|| "found on disk".to_string(), |id| id.to_string()),
+        if payload.success { "completed" } else { "failed" });
 
     Ok(StatusCode::OK)
 }
@@ -2469,7 +5089,7 @@ async fn update_client_system_info(
     State(state): State<AppState>,
     Path(client_id): Path<String>,
     Json(payload): Json<SystemInfoUpdate>,
-) -> Result<StatusCode, (StatusCode, String)> {
+) -> Result<StatusCode, ApiError> {
     let missing_dlls = if payload.missing_dlls.is_empty() {
         None
     } else {
@@ -2486,17 +5106,17 @@ async fn update_client_system_info(
         missing_dlls,
     )
     .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    .map_err(ApiError::internal)?;
 
     Ok(StatusCode::OK)
 }
 
 async fn get_all_clients(
     State(state): State<AppState>,
-) -> Result<Json<Vec<db::Client>>, (StatusCode, String)> {
+) -> Result<Json<Vec<db::Client>>, ApiError> {
     let clients = db::get_all_clients(&state.db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(ApiError::internal)?;
 
     Ok(Json(clients))
 }
@@ -2505,7 +5125,7 @@ async fn get_all_clients(
 async fn get_user_client_status(
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     // Get current user from session
     let user = match get_current_user(&state.db, &headers).await {
         Ok(user) => user,
@@ -2519,7 +5139,7 @@ async fn get_user_client_status(
     // Get clients for this user
     let clients = db::get_user_clients(&state.db, user.id)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(ApiError::internal)?;
 
     if clients.is_empty() {
         return Ok(Json(serde_json::json!({
@@ -2529,19 +5149,7 @@ async fn get_user_client_status(
         })));
     }
 
-    // Check if any client was seen recently (within last 2 minutes)
-    let now = chrono::Utc::now();
-    let mut has_online_client = false;
-
-    for client in &clients {
-        if let Ok(last_seen) = chrono::DateTime::parse_from_rfc3339(&client.last_seen) {
-            let elapsed = now.signed_duration_since(last_seen.with_timezone(&chrono::Utc));
-            if elapsed.num_seconds() < 120 {
-                has_online_client = true;
-                break;
-            }
-        }
-    }
+    let has_online_client = clients.iter().any(|c| db::client_is_online(c, state.client_offline_secs));
 
     Ok(Json(serde_json::json!({
         "has_client": true,
@@ -2559,32 +5167,24 @@ async fn get_user_client_status(
 async fn get_my_clients(
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     // Get current user from session
     let user = match get_current_user(&state.db, &headers).await {
         Ok(user) => user,
-        Err(e) => return Err((StatusCode::UNAUTHORIZED, e)),
+        Err(e) => return Err(ApiError::unauthorized(e)),
     };
 
     // Get all clients
     let all_clients = db::get_all_clients(&state.db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(ApiError::internal)?;
 
     // Separate into linked and unlinked
     let mut linked_clients = Vec::new();
     let mut unlinked_clients = Vec::new();
 
-    let now = chrono::Utc::now();
-
     for client in all_clients {
-        // Check if online (seen in last 2 minutes)
-        let is_online = if let Ok(last_seen) = chrono::DateTime::parse_from_rfc3339(&client.last_seen) {
-            let elapsed = now.signed_duration_since(last_seen.with_timezone(&chrono::Utc));
-            elapsed.num_seconds() < 120
-        } else {
-            false
-        };
+        let is_online = db::client_is_online(&client, state.client_offline_secs);
 
         let client_info = serde_json::json!({
             "client_id": client.client_id,
@@ -2596,6 +5196,8 @@ async fn get_my_clients(
         });
 
         if client.user_id == Some(user.id) {
+            let mut client_info = client_info;
+            client_info["bridge_token"] = serde_json::json!(client.bridge_token);
             linked_clients.push(client_info);
         } else if client.user_id.is_none() {
             unlinked_clients.push(client_info);
@@ -2613,21 +5215,35 @@ async fn link_client_to_user(
     State(state): State<AppState>,
     headers: HeaderMap,
     Path(client_id): Path<String>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     // Get current user from session
     let user = match get_current_user(&state.db, &headers).await {
         Ok(user) => user,
-        Err(e) => return Err((StatusCode::UNAUTHORIZED, e)),
+        Err(e) => return Err(ApiError::unauthorized(e)),
     };
 
-    // Link client to user
+    let client = db::get_client(&state.db, &client_id).await
+        .map_err(ApiError::internal)?
+        .ok_or(ApiError::not_found("Client not found"))?;
+    if client.user_id.is_some_and(|owner| owner != user.id) {
+        return Err(ApiError::forbidden("Client is linked to another user"));
+    }
+
+    // Link client to user; refused if someone else linked it in the meantime
     match state.client_download_manager.link_client_to_user(&client_id, user.id).await {
-        Ok(_) => Ok(Json(serde_json::json!({
-            "success": true,
-            "message": format!("Client linked to your account"),
-        }))),
-        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+        Ok(true) => {}
+        Ok(false) => return Err(ApiError::forbidden("Client is linked to another user")),
+        Err(e) => return Err(ApiError::internal(e)),
     }
+
+    // Hand the web UI the token for the client's local server
+    let bridge_token = client.bridge_token;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": format!("Client linked to your account"),
+        "bridge_token": bridge_token,
+    })))
 }
 
 /// Unlink a client from the current user
@@ -2635,21 +5251,21 @@ async fn unlink_client_from_user(
     State(state): State<AppState>,
     headers: HeaderMap,
     Path(client_id): Path<String>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     // Get current user from session
     let user = match get_current_user(&state.db, &headers).await {
         Ok(user) => user,
-        Err(e) => return Err((StatusCode::UNAUTHORIZED, e)),
+        Err(e) => return Err(ApiError::unauthorized(e)),
     };
 
     // Verify this client belongs to the current user
     let client = db::get_client(&state.db, &client_id)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .ok_or((StatusCode::NOT_FOUND, "Client not found".to_string()))?;
+        .map_err(ApiError::internal)?
+        .ok_or(ApiError::not_found("Client not found"))?;
 
     if client.user_id != Some(user.id) {
-        return Err((StatusCode::FORBIDDEN, "This client is not linked to your account".to_string()));
+        return Err(ApiError::forbidden("This client is not linked to your account"));
     }
 
     // Unlink by setting user_id to NULL
@@ -2657,7 +5273,7 @@ async fn unlink_client_from_user(
         .bind(&client_id)
         .execute(&state.db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(ApiError::internal)?;
 
     Ok(Json(serde_json::json!({
         "success": true,
@@ -2673,32 +5289,28 @@ async fn create_client_download(
     State(state): State<AppState>,
     headers: HeaderMap,
     Json(payload): Json<client_downloads::CreateDownloadRequest>,
-) -> Result<Json<ApiResponse>, (StatusCode, Json<ApiResponse>)> {
+) -> Result<Json<ApiResponse>, ApiError> {
     // Get current user from session
     let user = match get_current_user(&state.db, &headers).await {
         Ok(user) => user,
-        Err(e) => return Err((StatusCode::UNAUTHORIZED, Json(ApiResponse {
-            success: false,
-            message: e,
-            downloads: None,
-            download_id: None,
-        }))),
+        Err(e) => return Err(ApiError::unauthorized(e)),
     };
 
+    let bytes = check_download_quota(&state, &user, payload.game_id).await?;
+    save_archive_password(&state, payload.game_id, payload.archive_password.as_deref()).await;
+
     // Create download
-    match state.client_download_manager.create_download(user.id, payload.game_id).await {
-        Ok(download_id) => Ok(Json(ApiResponse {
-            success: true,
-            message: "Download created and queued for your client".to_string(),
-            downloads: None,
-            download_id: Some(download_id),
-        })),
-        Err(e) => Err((StatusCode::BAD_REQUEST, Json(ApiResponse {
-            success: false,
-            message: e.to_string(),
-            downloads: None,
-            download_id: None,
-        }))),
+    match state.client_download_manager.create_download(user.id, payload.game_id, payload.skip_junk).await {
+        Ok(download_id) => {
+            quota::record(&state.db, user.id, bytes).await;
+            Ok(Json(ApiResponse {
+                success: true,
+                message: "Download created and queued for your client".to_string(),
+                downloads: None,
+                download_id: Some(download_id),
+            }))
+        }
+        Err(e) => Err(ApiError::bad_request(e.to_string())),
     }
 }
 
@@ -2707,14 +5319,14 @@ async fn create_client_download(
 async fn get_client_download_queue(
     State(state): State<AppState>,
     Query(params): Query<std::collections::HashMap<String, String>>,
-) -> Result<Json<Vec<client_downloads::ClientDownloadInfo>>, (StatusCode, String)> {
+) -> Result<Json<Vec<client_downloads::ClientDownloadInfo>>, ApiError> {
     let client_id = params.get("client_id")
-        .ok_or((StatusCode::BAD_REQUEST, "Missing client_id parameter".to_string()))?;
+        .ok_or(ApiError::bad_request("Missing client_id parameter"))?;
 
     state.client_download_manager.get_client_queue(client_id)
         .await
         .map(Json)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+        .map_err(ApiError::internal)
 }
 
 /// Update download progress from client
@@ -2723,7 +5335,7 @@ async fn update_download_progress(
     State(state): State<AppState>,
     Path(download_id): Path<i64>,
     Json(update): Json<client_downloads::ProgressUpdate>,
-) -> Result<Json<ApiResponse>, (StatusCode, Json<ApiResponse>)> {
+) -> Result<Json<ApiResponse>, ApiError> {
     match state.client_download_manager.update_progress(download_id, update).await {
         Ok(_) => Ok(Json(ApiResponse {
             success: true,
@@ -2731,21 +5343,164 @@ async fn update_download_progress(
             downloads: None,
             download_id: Some(download_id),
         })),
-        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, Json(ApiResponse {
-            success: false,
-            message: e.to_string(),
-            downloads: None,
-            download_id: None,
-        }))),
+        Err(e) => Err(ApiError::internal(e)),
+    }
+}
+
+/// The linked client agent making this request. Client IDs are visible to any
+/// user via the clients list, so the agent also has to present the bridge
+/// token it registered with in the X-Bridge-Token header.
+async fn authenticate_client(state: &AppState, headers: &HeaderMap) -> Result<db::Client, ApiError> {
+    let header = |name: &str| headers.get(name)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty())
+        .map(str::to_string);
+    let client_id = header("x-client-id")
+        .ok_or(ApiError::unauthorized("Missing X-Client-Id header"))?;
+    let token = header("x-bridge-token")
+        .ok_or(ApiError::unauthorized("Missing X-Bridge-Token header"))?;
+
+    let client = db::get_client(&state.db, &client_id).await
+        .map_err(ApiError::internal)?
+        .filter(|c| c.bridge_token.as_deref() == Some(token.as_str()))
+        .ok_or(ApiError::unauthorized("Unknown client or wrong bridge token"))?;
+    if client.user_id.is_none() {
+        return Err(ApiError::forbidden("Client is not linked to a user"));
     }
+    Ok(client)
+}
+
+#[derive(Deserialize)]
+struct ResolveLinksRequest {
+    game_id: i64,
+}
+
+/// Turn a game's magnet or hoster link into direct download URLs for a linked
+/// client agent, using the debrid provider configured on the server. Clients
+/// without their own key use this, so the server's key never leaves it.
+async fn resolve_client_links(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<ResolveLinksRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    authenticate_client(&state, &headers).await?;
+
+    let game = db::get_game_by_id(&state.db, payload.game_id)
+        .await
+        .map_err(|_| ApiError::not_found("Game not found"))?;
+
+    let debrid = debrid::from_settings(&state.db, &state.debrid).await
+        .map_err(ApiError::not_found)?;
+
+    let options = debrid::LinkOptions { skip_junk: false, max_wait_secs: 300, link_type: Some(game.link_type) };
+    let downloads = debrid::process_link_cached(&state.db, debrid.as_ref(), &game.magnet_link, &options, &|_| {})
+        .await
+        .map_err(|e| {
+            tracing::error!("{} error resolving '{}' for a client: {}", debrid.provider_label(), game.title, e);
+            ApiError::new(StatusCode::BAD_GATEWAY, format!("{} error: {}", debrid.provider_label(), e))
+        })?;
+
+    let urls: Vec<String> = downloads.into_iter().map(|d| d.download_url).collect();
+    Ok(Json(serde_json::json!({ "urls": urls })))
+}
+
+// ─── Client agent updates ───
+
+/// Filename of the Windows client build in the releases directory
+const CLIENT_BINARY: &str = "repack-client-windows-x64.exe";
+
+/// Directory holding the client build and its VERSION file (CLIENT_RELEASES_DIR, default ./releases)
+fn client_releases_dir() -> std::path::PathBuf {
+    std::env::var("CLIENT_RELEASES_DIR")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|_| std::path::PathBuf::from("./releases"))
+}
+
+/// Newest client version available from this server, read from releases/VERSION
+async fn get_client_latest_version() -> Result<Json<serde_json::Value>, ApiError> {
+    let dir = client_releases_dir();
+
+    let version = tokio::fs::read_to_string(dir.join("VERSION"))
+        .await
+        .map(|v| v.trim().to_string())
+        .map_err(|_| ApiError::not_found("No client release published"))?;
+
+    let size = tokio::fs::metadata(dir.join(CLIENT_BINARY))
+        .await
+        .map(|m| m.len())
+        .map_err(|_| ApiError::not_found("Client binary not found"))?;
+
+    // `sha256sum` output published next to the binary; clients refuse updates without it
+    let sha256 = tokio::fs::read_to_string(dir.join(format!("{}.sha256", CLIENT_BINARY)))
+        .await
+        .ok()
+        .and_then(|s| s.split_whitespace().next().map(str::to_lowercase))
+        .filter(|h| h.len() == 64 && h.chars().all(|c| c.is_ascii_hexdigit()));
+
+    Ok(Json(serde_json::json!({
+        "version": version,
+        "download_url": "/api/client/download",
+        "size": size,
+        "sha256": sha256,
+    })))
+}
+
+/// Serve the latest client build
+async fn download_client_binary() -> Result<Response, ApiError> {
+    let path = client_releases_dir().join(CLIENT_BINARY);
+
+    let file = tokio::fs::File::open(&path)
+        .await
+        .map_err(|_| ApiError::not_found("Client binary not found"))?;
+
+    let file_size = file.metadata()
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to read metadata: {}", e)))?
+        .len();
+
+    let body = Body::from_stream(ReaderStream::new(file));
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/octet-stream")
+        .header(header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", CLIENT_BINARY))
+        .header(header::CONTENT_LENGTH, file_size.to_string())
+        .body(body)
+        .unwrap())
 }
 
+/// Database, debrid, disk and download status; `degraded` with the failing
+/// subsystems listed in `issues` when anything is unhealthy
 async fn health_check(
     State(state): State<AppState>,
 ) -> Json<serde_json::Value> {
     let db_ok = sqlx::query("SELECT 1").execute(&state.db).await.is_ok();
+    let debrid = state.debrid_health.check(&state.db, &state.debrid).await;
+    let disk = health::check_disk(state.download_manager.download_dir()).await;
+    let scrape_running = state.scrape_status.read().await.is_running;
+    let active_downloads = db::count_active_downloads(&state.db).await.ok();
+
+    let mut issues = Vec::new();
+    if !db_ok {
+        issues.push("Database is not responding".to_string());
+    }
+    if !debrid.ok {
+        issues.push(format!("Debrid check failed: {}", debrid.error.as_deref().unwrap_or("unknown error")));
+    }
+    if !disk.ok {
+        issues.push(format!(
+            "Only {:.1} GB free on the download directory (minimum {} GB)",
+            disk.free_gb.unwrap_or(0.0), health::LOW_DISK_GB
+        ));
+    }
+
     Json(serde_json::json!({
-        "status": if db_ok { "ok" } else { "degraded" },
+        "status": if issues.is_empty() { "ok" } else { "degraded" },
         "db": db_ok,
+        "debrid": debrid,
+        "disk": disk,
+        "scrape_running": scrape_running,
+        "active_downloads": active_downloads,
+        "issues": issues,
     }))
 }
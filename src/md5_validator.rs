@@ -1,11 +1,13 @@
 use md5::{Md5, Digest};
+use sha2::Sha256;
 use std::path::{Path, PathBuf};
 use tokio::fs;
-use tokio::io::AsyncReadExt;
 use serde::Serialize;
 
 #[derive(Debug, Clone, Serialize)]
 pub struct ValidationResult {
+    /// Algorithm of the checksum file that was found
+    pub algorithm: HashAlgorithm,
     pub total_files: usize,
     pub validated: usize,
     pub failed: usize,
@@ -31,43 +33,106 @@ pub enum FileStatus {
     Skipped,
 }
 
-/// Find MD5 checksum files in a directory
-pub async fn find_md5_file(dir: &Path) -> Option<PathBuf> {
+/// Hash algorithm used by a checksum file or entry
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    Md5,
+    Sha256,
+    Crc32,
+}
+
+impl HashAlgorithm {
+    /// Infer the algorithm from a checksum file's name
+    fn from_filename(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".md5") || name.starts_with("md5") {
+            Some(Self::Md5)
+        } else if name.ends_with(".sha256") || name.starts_with("sha256") {
+            Some(Self::Sha256)
+        } else if name.ends_with(".sfv") {
+            Some(Self::Crc32)
+        } else {
+            None
+        }
+    }
+
+    /// Infer the algorithm from a hex digest's length
+    fn from_hash(hash: &str) -> Option<Self> {
+        if !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        match hash.len() {
+            8 => Some(Self::Crc32),
+            32 => Some(Self::Md5),
+            64 => Some(Self::Sha256),
+            _ => None,
+        }
+    }
+}
+
+impl ValidationResult {
+    /// Compact status stored on the download row: "valid" or "mismatch:<count>"
+    pub fn md5_status(&self) -> String {
+        if self.failed == 0 {
+            "valid".to_string()
+        } else {
+            format!("mismatch:{}", self.failed)
+        }
+    }
+}
+
+/// Find a checksum file (MD5, SHA-256 or SFV) in a directory
+pub async fn find_checksum_file(dir: &Path) -> Option<(PathBuf, HashAlgorithm)> {
     let common_names = vec![
         "checksums.md5",
+        "md5sums.txt",
         "md5.txt",
         "MD5.txt",
         "checksum.md5",
         "hashes.md5",
+        "checksums.sha256",
+        "sha256sums",
+        "SHA256SUMS",
+        "sha256sums.txt",
+        "SHA256SUMS.txt",
     ];
 
     // First check for common names
     for name in common_names {
         let path = dir.join(name);
         if path.exists() {
-            return Some(path);
+            let algorithm = HashAlgorithm::from_filename(&path)?;
+            return Some((path, algorithm));
         }
     }
 
-    // Then search for any .md5 file
+    // Then search for any .md5/.sha256/.sfv file, preferring the strongest hash
+    let mut found: Vec<(PathBuf, HashAlgorithm)> = Vec::new();
     if let Ok(mut entries) = fs::read_dir(dir).await {
         while let Ok(Some(entry)) = entries.next_entry().await {
             let path = entry.path();
             if path.is_file() {
-                if let Some(ext) = path.extension() {
-                    if ext.eq_ignore_ascii_case("md5") {
-                        return Some(path);
+                let has_checksum_ext = path.extension()
+                    .map(|ext| ["md5", "sha256", "sfv"].iter().any(|e| ext.eq_ignore_ascii_case(e)))
+                    .unwrap_or(false);
+                if has_checksum_ext {
+                    if let Some(algorithm) = HashAlgorithm::from_filename(&path) {
+                        found.push((path, algorithm));
                     }
                 }
             }
         }
     }
 
-    None
+    [HashAlgorithm::Sha256, HashAlgorithm::Md5, HashAlgorithm::Crc32]
+        .into_iter()
+        .find_map(|wanted| found.iter().find(|(_, algo)| *algo == wanted).cloned())
 }
 
-/// Parse an MD5 file and return a map of filename -> hash
-async fn parse_md5_file(path: &Path) -> Result<Vec<(String, String)>, Box<dyn std::error::Error + Send + Sync>> {
+/// Parse a checksum file into (filename, hash, algorithm) entries. The algorithm
+/// is taken from each hash's length, so mixed files work too.
+async fn parse_checksum_file(path: &Path) -> Result<Vec<(String, String, HashAlgorithm)>, Box<dyn std::error::Error + Send + Sync>> {
     let content = fs::read_to_string(path).await?;
     let mut checksums = Vec::new();
 
@@ -78,127 +143,171 @@ async fn parse_md5_file(path: &Path) -> Result<Vec<(String, String)>, Box<dyn st
         }
 
         // Support multiple formats:
-        // 1. "hash *filename" or "hash  filename" (standard md5sum format)
+        // 1. "hash *filename" or "hash  filename" (standard md5sum/sha256sum format)
         // 2. "hash filename"
-        // 3. "filename hash"
+        // 3. "filename hash" (SFV and some MD5 files)
 
         let parts: Vec<&str> = line.split_whitespace().collect();
         if parts.len() < 2 {
             continue;
         }
 
-        let (hash, filename) = if parts[0].len() == 32 && parts[0].chars().all(|c| c.is_ascii_hexdigit()) {
-            // Format: hash filename
-            let hash = parts[0].to_lowercase();
-            let filename = parts[1..].join(" ").trim_start_matches('*').to_string();
-            (hash, filename)
-        } else if parts.len() >= 2 && parts.last().unwrap().len() == 32 {
-            // Format: filename hash (less common)
-            let hash = parts.last().unwrap().to_lowercase();
-            let filename = parts[..parts.len()-1].join(" ");
-            (hash, filename)
-        } else {
-            continue;
+        let first = parts[0];
+        let last = *parts.last().unwrap();
+
+        let (hash, filename, algorithm) = match (HashAlgorithm::from_hash(first), HashAlgorithm::from_hash(last)) {
+            // Format: hash filename (CRC32 is too short to tell apart from a filename here)
+            (Some(algorithm), _) if algorithm != HashAlgorithm::Crc32 => {
+                let filename = parts[1..].join(" ").trim_start_matches('*').to_string();
+                (first.to_lowercase(), filename, algorithm)
+            }
+            // Format: filename hash
+            (_, Some(algorithm)) => {
+                let filename = parts[..parts.len()-1].join(" ");
+                (last.to_lowercase(), filename, algorithm)
+            }
+            _ => continue,
         };
 
-        checksums.push((filename, hash));
+        checksums.push((filename, hash, algorithm));
     }
 
     Ok(checksums)
 }
 
-/// Calculate MD5 hash of a file
-async fn calculate_md5(path: &Path) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    let mut file = fs::File::open(path).await?;
-    let mut hasher = Md5::new();
-    let mut buffer = vec![0u8; 8192];
+/// Files hashed at once. Kept low so spinning disks aren't thrashed by seeks.
+const MAX_PARALLEL_HASHES: usize = 4;
+
+/// Feed a file through `update` in chunks
+fn stream_file(path: &Path, mut update: impl FnMut(&[u8])) -> std::io::Result<()> {
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path)?;
+    let mut buffer = vec![0u8; 1024 * 1024];
 
     loop {
-        let n = file.read(&mut buffer).await?;
+        let n = file.read(&mut buffer)?;
         if n == 0 {
-            break;
+            return Ok(());
+        }
+        update(&buffer[..n]);
+    }
+}
+
+/// Calculate the hash of a file as lowercase hex, streaming it on a blocking thread
+async fn calculate_hash(path: &Path, algorithm: HashAlgorithm) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let path = path.to_path_buf();
+
+    tokio::task::spawn_blocking(move || -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let hash = match algorithm {
+            HashAlgorithm::Md5 => {
+                let mut hasher = Md5::new();
+                stream_file(&path, |chunk| hasher.update(chunk))?;
+                format!("{:x}", hasher.finalize())
+            }
+            HashAlgorithm::Sha256 => {
+                let mut hasher = Sha256::new();
+                stream_file(&path, |chunk| hasher.update(chunk))?;
+                format!("{:x}", hasher.finalize())
+            }
+            HashAlgorithm::Crc32 => {
+                let mut hasher = crc32fast::Hasher::new();
+                stream_file(&path, |chunk| hasher.update(chunk))?;
+                format!("{:08x}", hasher.finalize())
+            }
+        };
+        Ok(hash)
+    })
+    .await?
+}
+
+/// Check a single checksum entry against the file on disk
+async fn validate_entry(
+    dir: &Path,
+    checksum_file: &Path,
+    filename: String,
+    expected_hash: String,
+    algorithm: HashAlgorithm,
+) -> FileValidation {
+    // Try to find the file (might be in subdirectories)
+    let file_path = match find_file(dir, &filename).await {
+        Some(path) => path,
+        None => {
+            return FileValidation {
+                filename,
+                status: FileStatus::Missing,
+                expected_hash: Some(expected_hash),
+                actual_hash: None,
+            };
         }
-        hasher.update(&buffer[..n]);
+    };
+
+    // Skip if it's the checksum file itself
+    if file_path == checksum_file {
+        return FileValidation {
+            filename,
+            status: FileStatus::Skipped,
+            expected_hash: Some(expected_hash),
+            actual_hash: None,
+        };
     }
 
-    let result = hasher.finalize();
-    Ok(format!("{:x}", result))
+    println!("  Validating: {}", filename);
+
+    match calculate_hash(&file_path, algorithm).await {
+        Ok(actual_hash) => FileValidation {
+            filename,
+            status: if actual_hash == expected_hash { FileStatus::Valid } else { FileStatus::Invalid },
+            expected_hash: Some(expected_hash),
+            actual_hash: Some(actual_hash),
+        },
+        Err(e) => {
+            eprintln!("  Error calculating hash for {}: {}", filename, e);
+            FileValidation {
+                filename,
+                status: FileStatus::Invalid,
+                expected_hash: Some(expected_hash),
+                actual_hash: None,
+            }
+        }
+    }
 }
 
-/// Validate files in a directory against an MD5 checksum file
+/// Validate files in a directory against its MD5, SHA-256 or SFV checksum file.
+/// Up to `MAX_PARALLEL_HASHES` files are hashed concurrently.
 pub async fn validate_directory(dir: &Path) -> Result<ValidationResult, Box<dyn std::error::Error + Send + Sync>> {
-    let md5_file = find_md5_file(dir).await
-        .ok_or("No MD5 checksum file found in directory")?;
+    use futures::StreamExt;
+
+    let (checksum_file, algorithm) = find_checksum_file(dir).await
+        .ok_or("No checksum file found in directory")?;
 
-    println!("Found MD5 file: {}", md5_file.display());
+    println!("Found {:?} checksum file: {}", algorithm, checksum_file.display());
 
-    let checksums = parse_md5_file(&md5_file).await?;
+    let checksums = parse_checksum_file(&checksum_file).await?;
 
     if checksums.is_empty() {
-        return Err("No checksums found in MD5 file".into());
+        return Err("No checksums found in checksum file".into());
     }
 
     println!("Validating {} files...", checksums.len());
 
-    let mut results = Vec::new();
+    // `buffered` keeps results in checksum-file order
+    let results: Vec<FileValidation> = futures::stream::iter(checksums)
+        .map(|(filename, expected_hash, entry_algorithm)| {
+            validate_entry(dir, &checksum_file, filename, expected_hash, entry_algorithm)
+        })
+        .buffered(MAX_PARALLEL_HASHES)
+        .collect()
+        .await;
+
     let mut validated = 0;
     let mut failed = 0;
     let mut skipped = 0;
-
-    for (filename, expected_hash) in checksums {
-        // Try to find the file (might be in subdirectories)
-        let file_path = if let Some(path) = find_file(dir, &filename).await {
-            path
-        } else {
-            results.push(FileValidation {
-                filename: filename.clone(),
-                status: FileStatus::Missing,
-                expected_hash: Some(expected_hash),
-                actual_hash: None,
-            });
-            failed += 1;
-            continue;
-        };
-
-        // Skip if it's the MD5 file itself
-        if file_path == md5_file {
-            results.push(FileValidation {
-                filename,
-                status: FileStatus::Skipped,
-                expected_hash: Some(expected_hash),
-                actual_hash: None,
-            });
-            skipped += 1;
-            continue;
-        }
-
-        println!("  Validating: {}", filename);
-
-        match calculate_md5(&file_path).await {
-            Ok(actual_hash) => {
-                let is_valid = actual_hash == expected_hash;
-                results.push(FileValidation {
-                    filename,
-                    status: if is_valid { FileStatus::Valid } else { FileStatus::Invalid },
-                    expected_hash: Some(expected_hash),
-                    actual_hash: Some(actual_hash),
-                });
-                if is_valid {
-                    validated += 1;
-                } else {
-                    failed += 1;
-                }
-            }
-            Err(e) => {
-                eprintln!("  Error calculating hash for {}: {}", filename, e);
-                results.push(FileValidation {
-                    filename,
-                    status: FileStatus::Invalid,
-                    expected_hash: Some(expected_hash),
-                    actual_hash: None,
-                });
-                failed += 1;
-            }
+    for result in &results {
+        match result.status {
+            FileStatus::Valid => validated += 1,
+            FileStatus::Skipped => skipped += 1,
+            FileStatus::Invalid | FileStatus::Missing => failed += 1,
         }
     }
 
@@ -209,6 +318,7 @@ pub async fn validate_directory(dir: &Path) -> Result<ValidationResult, Box<dyn
     };
 
     Ok(ValidationResult {
+        algorithm,
         total_files: results.len(),
         validated,
         failed,
@@ -218,8 +328,76 @@ pub async fn validate_directory(dir: &Path) -> Result<ValidationResult, Box<dyn
     })
 }
 
-/// Recursively find a file by name in a directory (up to 3 levels deep)
+/// Name of the manifest written by `generate_manifest`
+pub const GENERATED_MANIFEST: &str = "md5sums.txt";
+
+/// Hash every file under `dir` and write an md5sum-style manifest to
+/// `dir/md5sums.txt`. Returns the number of files hashed.
+pub async fn generate_manifest(dir: &Path) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+    use futures::StreamExt;
+
+    let root = dir.to_path_buf();
+    let files = tokio::task::spawn_blocking(move || collect_files(&root, &root)).await??;
+
+    if files.is_empty() {
+        return Err("No files found to hash".into());
+    }
+
+    println!("Generating MD5 manifest for {} files in {}", files.len(), dir.display());
+
+    let lines: Vec<Result<String, String>> = futures::stream::iter(files)
+        .map(|relative| async move {
+            calculate_hash(&dir.join(&relative), HashAlgorithm::Md5).await
+                .map(|hash| format!("{} *{}\n", hash, relative))
+                .map_err(|e| format!("Failed to hash {}: {}", relative, e))
+        })
+        .buffered(MAX_PARALLEL_HASHES)
+        .collect()
+        .await;
+
+    let manifest = lines.into_iter().collect::<Result<String, String>>()?;
+
+    let count = manifest.lines().count();
+    fs::write(dir.join(GENERATED_MANIFEST), manifest).await?;
+    Ok(count)
+}
+
+/// List files under `dir` as '/'-separated paths relative to `root`,
+/// leaving out existing checksum files
+fn collect_files(root: &Path, dir: &Path) -> std::io::Result<Vec<String>> {
+    let mut files = Vec::new();
+
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(collect_files(root, &path)?);
+        } else if path.is_file() {
+            let is_checksum_file = path.extension()
+                .map(|ext| ["md5", "sha256", "sfv"].iter().any(|e| ext.eq_ignore_ascii_case(e)))
+                .unwrap_or(false);
+            if is_checksum_file || path.file_name().is_some_and(|n| n == GENERATED_MANIFEST) {
+                continue;
+            }
+            if let Ok(relative) = path.strip_prefix(root) {
+                let parts: Vec<String> = relative.components()
+                    .map(|c| c.as_os_str().to_string_lossy().to_string())
+                    .collect();
+                files.push(parts.join("/"));
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Find a file listed in a checksum file: first as a relative path,
+/// then by name anywhere in the directory (up to 3 levels deep)
 async fn find_file(dir: &Path, filename: &str) -> Option<PathBuf> {
+    let direct = dir.join(filename.replace('\\', "/"));
+    if direct.is_file() {
+        return Some(direct);
+    }
     find_file_recursive(dir, filename, 0, 3).await
 }
 
@@ -0,0 +1,716 @@
+//! Ordered schema migrations. Each migration runs once, inside a transaction,
+//! and is recorded in `schema_version`; a failing step aborts startup with the
+//! migration that broke instead of being silently ignored.
+//!
+//! To change the schema, append a migration with the next version number.
+//! Never edit one that has shipped — existing databases won't re-run it.
+
+use sqlx::{Sqlite, SqlitePool, Transaction};
+
+enum Step {
+    /// Run a statement as-is
+    Sql(&'static str),
+    /// Add a column unless the table already has it. Databases created before
+    /// `schema_version` existed may have any subset of the later columns.
+    AddColumn {
+        table: &'static str,
+        column: &'static str,
+        definition: &'static str,
+    },
+    /// Fill in rows that need Rust code rather than SQL to compute
+    Backfill(Backfill),
+}
+
+#[derive(Clone, Copy)]
+enum Backfill {
+    /// Seed `rawg_cache` from the images and genres games already have, so an
+    /// upgraded server doesn't look every title up on RAWG again
+    RawgCache,
+}
+
+struct Migration {
+    version: i64,
+    description: &'static str,
+    steps: &'static [Step],
+}
+
+const fn add_column(table: &'static str, column: &'static str, definition: &'static str) -> Step {
+    Step::AddColumn { table, column, definition }
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "baseline schema",
+        steps: &[
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS games (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    title TEXT NOT NULL,
+                    file_size TEXT NOT NULL DEFAULT '',
+                    magnet_link TEXT NOT NULL,
+                    genres TEXT,
+                    company TEXT,
+                    original_size TEXT,
+                    thumbnail_url TEXT,
+                    screenshots TEXT,
+                    source_url TEXT,
+                    post_date TEXT
+                )
+                "#,
+            ),
+            add_column("games", "genres", "TEXT"),
+            add_column("games", "company", "TEXT"),
+            add_column("games", "original_size", "TEXT"),
+            add_column("games", "thumbnail_url", "TEXT"),
+            add_column("games", "source_url", "TEXT"),
+            add_column("games", "post_date", "TEXT"),
+            add_column("games", "screenshots", "TEXT"),
+            add_column("games", "search_title", "TEXT"),
+            // Games scraped before multi-source support are all FitGirl repacks
+            add_column("games", "source", "TEXT DEFAULT 'fitgirl'"),
+            Step::Sql("UPDATE games SET source = 'fitgirl' WHERE source IS NULL"),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_games_title ON games(title COLLATE NOCASE)"),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_games_search_title ON games(search_title COLLATE NOCASE)"),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_games_source ON games(source)"),
+            // Filter + sort combinations used by query_games. The expressions must
+            // match its ORDER BY clauses exactly for SQLite to skip the sort step.
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_games_post_date ON games(COALESCE(post_date, ''), id)"),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_games_source_post_date ON games(source, COALESCE(post_date, ''), id)"),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_games_source_title ON games(source, title COLLATE NOCASE)"),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS system_checks (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    check_date TEXT NOT NULL,
+                    ram_available_gb REAL,
+                    temp_space_gb REAL,
+                    cpu_cores INTEGER,
+                    antivirus_active BOOLEAN,
+                    missing_dlls TEXT,
+                    missing_dependencies TEXT,
+                    overall_status TEXT
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS installation_logs (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    game_id INTEGER,
+                    started_at TEXT NOT NULL,
+                    completed_at TEXT,
+                    status TEXT NOT NULL,
+                    error_code TEXT,
+                    error_message TEXT,
+                    ram_usage_peak REAL,
+                    install_duration_minutes INTEGER,
+                    FOREIGN KEY (game_id) REFERENCES games(id)
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS community_ratings (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    game_id INTEGER NOT NULL,
+                    install_difficulty INTEGER,
+                    install_success BOOLEAN,
+                    issues_encountered TEXT,
+                    created_at TEXT NOT NULL,
+                    FOREIGN KEY (game_id) REFERENCES games(id)
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS game_requirements (
+                    game_id INTEGER PRIMARY KEY,
+                    min_ram_gb INTEGER,
+                    rec_ram_gb INTEGER,
+                    min_cpu TEXT,
+                    rec_cpu TEXT,
+                    min_gpu TEXT,
+                    rec_gpu TEXT,
+                    disk_space_gb INTEGER,
+                    requires_directx TEXT,
+                    requires_dotnet TEXT,
+                    requires_vcredist TEXT,
+                    FOREIGN KEY (game_id) REFERENCES games(id)
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS downloads (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    game_id INTEGER NOT NULL,
+                    status TEXT NOT NULL DEFAULT 'queued',
+                    progress REAL DEFAULT 0.0,
+                    download_speed TEXT,
+                    eta TEXT,
+                    file_path TEXT,
+                    installer_path TEXT,
+                    error_message TEXT,
+                    created_at TEXT NOT NULL,
+                    completed_at TEXT,
+                    FOREIGN KEY (game_id) REFERENCES games(id)
+                )
+                "#,
+            ),
+            add_column("downloads", "installer_path", "TEXT"),
+            // Client the download is assigned to
+            add_column("downloads", "client_id", "TEXT"),
+            // Owner; NULL for server-side downloads
+            add_column("downloads", "user_id", "INTEGER"),
+            add_column("downloads", "retry_count", "INTEGER DEFAULT 0"),
+            // Result of the automatic checksum validation
+            add_column("downloads", "md5_status", "TEXT"),
+            // Provider-side torrent progress
+            add_column("downloads", "debrid_status", "TEXT"),
+            // Current step of a client's installer
+            add_column("downloads", "install_step", "TEXT"),
+            // Per-download install folder on the client
+            add_column("downloads", "install_dir", "TEXT"),
+            // Leave out samples/readmes
+            add_column("downloads", "skip_junk", "INTEGER DEFAULT 0"),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS settings (
+                    key TEXT PRIMARY KEY,
+                    value TEXT NOT NULL
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS download_files (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    download_id INTEGER NOT NULL,
+                    filename TEXT NOT NULL,
+                    file_size INTEGER,
+                    file_path TEXT,
+                    is_extracted BOOLEAN DEFAULT 0,
+                    FOREIGN KEY (download_id) REFERENCES downloads(id)
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS users (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    username TEXT UNIQUE NOT NULL,
+                    password_hash TEXT NOT NULL,
+                    is_admin BOOLEAN DEFAULT 0,
+                    created_at TEXT NOT NULL,
+                    last_login TEXT
+                )
+                "#,
+            ),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_users_username ON users(username)"),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS sessions (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    session_token TEXT UNIQUE NOT NULL,
+                    user_id INTEGER NOT NULL,
+                    created_at TEXT NOT NULL,
+                    expires_at TEXT NOT NULL,
+                    FOREIGN KEY (user_id) REFERENCES users(id)
+                )
+                "#,
+            ),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_sessions_token ON sessions(session_token)"),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS user_favorites (
+                    user_id INTEGER NOT NULL,
+                    game_id INTEGER NOT NULL,
+                    created_at TEXT NOT NULL,
+                    PRIMARY KEY (user_id, game_id),
+                    FOREIGN KEY (user_id) REFERENCES users(id),
+                    FOREIGN KEY (game_id) REFERENCES games(id)
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS user_downloads (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    user_id INTEGER NOT NULL,
+                    download_id INTEGER NOT NULL,
+                    created_at TEXT NOT NULL,
+                    FOREIGN KEY (user_id) REFERENCES users(id),
+                    FOREIGN KEY (download_id) REFERENCES downloads(id)
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS user_settings (
+                    user_id INTEGER PRIMARY KEY,
+                    theme TEXT DEFAULT 'dark',
+                    notifications_enabled BOOLEAN DEFAULT 1,
+                    auto_download BOOLEAN DEFAULT 0,
+                    FOREIGN KEY (user_id) REFERENCES users(id)
+                )
+                "#,
+            ),
+            add_column("user_settings", "download_path", "TEXT"),
+            add_column("user_settings", "scraper_fitgirl_enabled", "BOOLEAN DEFAULT 1"),
+            add_column("user_settings", "scraper_steamrip_enabled", "BOOLEAN DEFAULT 1"),
+            add_column("user_settings", "notify_download_complete", "BOOLEAN DEFAULT 1"),
+            add_column("user_settings", "notify_new_games", "BOOLEAN DEFAULT 0"),
+            add_column("user_settings", "notify_errors", "BOOLEAN DEFAULT 1"),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS game_tags (
+                    game_id INTEGER NOT NULL,
+                    tag TEXT NOT NULL,
+                    PRIMARY KEY (game_id, tag),
+                    FOREIGN KEY (game_id) REFERENCES games(id)
+                )
+                "#,
+            ),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_game_tags_tag ON game_tags(tag)"),
+            // Carousel rankings (top 50, top 150, etc.)
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS game_categories (
+                    game_id INTEGER NOT NULL,
+                    category TEXT NOT NULL,
+                    rank INTEGER,
+                    scraped_at TEXT NOT NULL,
+                    PRIMARY KEY (game_id, category),
+                    FOREIGN KEY (game_id) REFERENCES games(id) ON DELETE CASCADE
+                )
+                "#,
+            ),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_game_categories_category ON game_categories(category, rank)"),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS notifications (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    user_id INTEGER NOT NULL,
+                    type TEXT NOT NULL,
+                    title TEXT NOT NULL,
+                    message TEXT NOT NULL,
+                    read BOOLEAN DEFAULT 0,
+                    created_at TEXT NOT NULL,
+                    FOREIGN KEY (user_id) REFERENCES users(id)
+                )
+                "#,
+            ),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_notifications_user_id ON notifications(user_id)"),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_notifications_read ON notifications(read)"),
+            // Windows client agents, linked to the user who owns them
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS clients (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    client_id TEXT UNIQUE NOT NULL,
+                    client_name TEXT NOT NULL,
+                    user_id INTEGER,
+                    os_version TEXT,
+                    ram_total_gb REAL,
+                    ram_available_gb REAL,
+                    disk_space_gb REAL,
+                    cpu_cores INTEGER,
+                    missing_dlls TEXT,
+                    last_seen TEXT NOT NULL,
+                    registered_at TEXT NOT NULL,
+                    FOREIGN KEY (user_id) REFERENCES users(id)
+                )
+                "#,
+            ),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_clients_client_id ON clients(client_id)"),
+            // Token the web UI needs to call the client's local server
+            add_column("clients", "bridge_token", "TEXT"),
+            add_column("clients", "user_id", "INTEGER"),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS client_progress (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    client_id TEXT NOT NULL,
+                    game_id INTEGER,
+                    file_path TEXT NOT NULL,
+                    total_bytes INTEGER NOT NULL DEFAULT 0,
+                    extracted_bytes INTEGER NOT NULL DEFAULT 0,
+                    progress_percent REAL NOT NULL DEFAULT 0,
+                    speed_mbps REAL NOT NULL DEFAULT 0,
+                    eta_seconds INTEGER NOT NULL DEFAULT 0,
+                    status TEXT NOT NULL,
+                    updated_at TEXT NOT NULL,
+                    FOREIGN KEY (client_id) REFERENCES clients(client_id)
+                )
+                "#,
+            ),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_client_progress_client_id ON client_progress(client_id)"),
+            // Passwords users supplied for encrypted archives, per game
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS archive_passwords (
+                    game_id INTEGER PRIMARY KEY,
+                    password TEXT NOT NULL,
+                    updated_at TEXT NOT NULL,
+                    FOREIGN KEY (game_id) REFERENCES games(id)
+                )
+                "#,
+            ),
+            // Cache of unrestricted debrid links, keyed by magnet info-hash
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS rd_link_cache (
+                    magnet_hash TEXT NOT NULL,
+                    provider TEXT NOT NULL,
+                    skip_junk INTEGER NOT NULL DEFAULT 0,
+                    links_json TEXT NOT NULL,
+                    cached_at TEXT NOT NULL,
+                    PRIMARY KEY (magnet_hash, provider, skip_junk)
+                )
+                "#,
+            ),
+            // Commands for client agents (e.g. cancel a download), polled by the client
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS client_commands (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    user_id INTEGER NOT NULL,
+                    download_id INTEGER NOT NULL,
+                    command TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    acknowledged_at TEXT,
+                    FOREIGN KEY (user_id) REFERENCES users(id)
+                )
+                "#,
+            ),
+        ],
+    },
+    Migration {
+        version: 2,
+        description: "user wishlist",
+        steps: &[Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS user_wishlist (
+                user_id INTEGER NOT NULL,
+                game_id INTEGER NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (user_id, game_id),
+                FOREIGN KEY (user_id) REFERENCES users(id),
+                FOREIGN KEY (game_id) REFERENCES games(id)
+            )
+            "#,
+        )],
+    },
+    Migration {
+        version: 3,
+        description: "hidden games and admin blacklist",
+        steps: &[
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS user_hidden_games (
+                    user_id INTEGER NOT NULL,
+                    game_id INTEGER NOT NULL,
+                    created_at TEXT NOT NULL,
+                    PRIMARY KEY (user_id, game_id),
+                    FOREIGN KEY (user_id) REFERENCES users(id),
+                    FOREIGN KEY (game_id) REFERENCES games(id)
+                )
+                "#,
+            ),
+            // Keyed by source and title rather than game ID, since a full
+            // rescrape recreates every game row with new IDs
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS game_blacklist (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    source TEXT NOT NULL,
+                    title TEXT NOT NULL,
+                    reason TEXT,
+                    created_at TEXT NOT NULL,
+                    UNIQUE (source, title)
+                )
+                "#,
+            ),
+        ],
+    },
+    Migration {
+        version: 4,
+        description: "game view counts",
+        steps: &[
+            add_column("games", "view_count", "INTEGER NOT NULL DEFAULT 0"),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_games_view_count ON games(view_count DESC)"),
+        ],
+    },
+    Migration {
+        version: 5,
+        description: "game link types",
+        steps: &[
+            add_column("games", "link_type", "TEXT NOT NULL DEFAULT 'magnet'"),
+            Step::Sql("UPDATE games SET link_type = 'ddl' WHERE magnet_link NOT LIKE 'magnet:%'"),
+        ],
+    },
+    Migration {
+        version: 6,
+        description: "session activity",
+        steps: &[
+            add_column("sessions", "last_seen_at", "TEXT"),
+            Step::Sql("UPDATE sessions SET last_seen_at = created_at WHERE last_seen_at IS NULL"),
+        ],
+    },
+    Migration {
+        version: 7,
+        description: "saved searches",
+        steps: &[Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS saved_searches (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                params TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                UNIQUE (user_id, name),
+                FOREIGN KEY (user_id) REFERENCES users(id)
+            )
+            "#,
+        )],
+    },
+    Migration {
+        version: 8,
+        description: "previous login time",
+        steps: &[add_column("users", "previous_login", "TEXT")],
+    },
+    Migration {
+        version: 9,
+        description: "RAWG metadata cache",
+        steps: &[
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS rawg_cache (
+                    normalized_title TEXT PRIMARY KEY,
+                    image_url TEXT,
+                    genres TEXT,
+                    fetched_at TEXT NOT NULL
+                )
+                "#,
+            ),
+            Step::Backfill(Backfill::RawgCache),
+        ],
+    },
+    Migration {
+        version: 10,
+        description: "per-user daily download usage",
+        steps: &[Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS download_usage (
+                user_id INTEGER NOT NULL,
+                day TEXT NOT NULL,
+                bytes INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (user_id, day)
+            )
+            "#,
+        )],
+    },
+    Migration {
+        version: 11,
+        description: "explicit client online/offline state",
+        steps: &[
+            add_column("clients", "status", "TEXT NOT NULL DEFAULT 'online'"),
+            // Clients that already missed the (default, 120s) offline window start
+            // out offline, rather than all "going offline" on the first heartbeat check
+            Step::Sql(
+                "UPDATE clients SET status = 'offline'
+                 WHERE last_seen < strftime('%Y-%m-%dT%H:%M:%S', 'now', '-120 seconds')",
+            ),
+        ],
+    },
+    Migration {
+        version: 12,
+        description: "per-user Telegram chat for notifications",
+        steps: &[add_column("user_settings", "telegram_chat_id", "TEXT")],
+    },
+    Migration {
+        version: 13,
+        description: "user email addresses and email notifications",
+        steps: &[
+            add_column("users", "email", "TEXT"),
+            add_column("users", "email_verified", "BOOLEAN NOT NULL DEFAULT 0"),
+            add_column("user_settings", "notify_email", "BOOLEAN DEFAULT 0"),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS email_verifications (
+                    token TEXT PRIMARY KEY,
+                    user_id INTEGER NOT NULL,
+                    email TEXT NOT NULL,
+                    expires_at TEXT NOT NULL,
+                    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+                )
+                "#,
+            ),
+        ],
+    },
+    Migration {
+        version: 14,
+        description: "browser Web Push subscriptions",
+        steps: &[Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS push_subscriptions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id INTEGER NOT NULL,
+                endpoint TEXT NOT NULL UNIQUE,
+                p256dh TEXT NOT NULL,
+                auth TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+            )
+            "#,
+        )],
+    },
+    Migration {
+        version: 15,
+        description: "per-type notification preferences",
+        // notify_errors stays in the table but is no longer read; each type
+        // it covered starts out with its value
+        steps: &[
+            add_column("user_settings", "notify_download_failed", "BOOLEAN DEFAULT 1"),
+            add_column("user_settings", "notify_install_failed", "BOOLEAN DEFAULT 1"),
+            add_column("user_settings", "notify_client_offline", "BOOLEAN DEFAULT 1"),
+            add_column("user_settings", "notify_scrape_error", "BOOLEAN DEFAULT 1"),
+            Step::Sql(
+                r#"
+                UPDATE user_settings SET
+                    notify_download_failed = COALESCE(notify_errors, 1),
+                    notify_install_failed = COALESCE(notify_errors, 1),
+                    notify_client_offline = COALESCE(notify_errors, 1),
+                    notify_scrape_error = COALESCE(notify_errors, 1)
+                "#,
+            ),
+        ],
+    },
+    Migration {
+        version: 16,
+        description: "client online notifications",
+        steps: &[
+            add_column("clients", "notified_status", "TEXT"),
+            add_column("clients", "notified_at", "TEXT"),
+            add_column("user_settings", "notify_client_online", "BOOLEAN DEFAULT 0"),
+            // Owners already know about the current status
+            Step::Sql("UPDATE clients SET notified_status = status"),
+        ],
+    },
+    Migration {
+        version: 17,
+        description: "one community rating per user and game",
+        steps: &[
+            add_column("community_ratings", "user_id", "INTEGER REFERENCES users(id)"),
+            // Ratings from before this have no user and don't collide
+            Step::Sql(
+                "CREATE UNIQUE INDEX IF NOT EXISTS idx_community_ratings_user_game
+                 ON community_ratings(user_id, game_id)",
+            ),
+        ],
+    },
+];
+
+/// Bring the database up to the latest schema version
+pub async fn run(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )"
+    )
+    .execute(pool)
+    .await?;
+
+    let (current,): (i64,) = sqlx::query_as("SELECT COALESCE(MAX(version), 0) FROM schema_version")
+        .fetch_one(pool)
+        .await?;
+
+    let latest = MIGRATIONS.last().map(|m| m.version).unwrap_or(0);
+    if current > latest {
+        return Err(sqlx::Error::Configuration(format!(
+            "Database schema is version {}, but this build only knows up to {}. Upgrade the server.",
+            current, latest
+        ).into()));
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        apply(pool, migration).await.map_err(|e| {
+            eprintln!("❌ Migration {} ({}) failed: {}", migration.version, migration.description, e);
+            e
+        })?;
+        println!("🗄️  Applied migration {}: {}", migration.version, migration.description);
+    }
+
+    Ok(())
+}
+
+async fn apply(pool: &SqlitePool, migration: &Migration) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    for step in migration.steps {
+        match step {
+            Step::Sql(sql) => {
+                sqlx::query(sql).execute(&mut *tx).await?;
+            }
+            Step::AddColumn { table, column, definition } => {
+                if !has_column(&mut tx, table, column).await? {
+                    sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+                        .execute(&mut *tx)
+                        .await?;
+                }
+            }
+            Step::Backfill(Backfill::RawgCache) => seed_rawg_cache(&mut tx).await?,
+        }
+    }
+
+    sqlx::query("INSERT INTO schema_version (version, description, applied_at) VALUES (?, ?, ?)")
+        .bind(migration.version)
+        .bind(migration.description)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await
+}
+
+async fn seed_rawg_cache(tx: &mut Transaction<'_, Sqlite>) -> Result<(), sqlx::Error> {
+    // Most complete rows first, so they win when several repacks share a key
+    let rows: Vec<(String, Option<String>, Option<String>)> = sqlx::query_as(
+        "SELECT title, thumbnail_url, genres FROM games
+         WHERE thumbnail_url IS NOT NULL OR genres IS NOT NULL
+         ORDER BY (thumbnail_url IS NOT NULL) + (genres IS NOT NULL) DESC, id DESC"
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    let fetched_at = chrono::Utc::now().to_rfc3339();
+    for (title, image_url, genres) in rows {
+        let key = crate::rawg::cache_key(&title);
+        if key.is_empty() {
+            continue;
+        }
+        sqlx::query(
+            "INSERT OR IGNORE INTO rawg_cache (normalized_title, image_url, genres, fetched_at) VALUES (?, ?, ?, ?)"
+        )
+        .bind(key)
+        .bind(image_url)
+        .bind(genres)
+        .bind(&fetched_at)
+        .execute(&mut **tx)
+        .await?;
+    }
+    Ok(())
+}
+
+async fn has_column(tx: &mut Transaction<'_, Sqlite>, table: &str, column: &str) -> Result<bool, sqlx::Error> {
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM pragma_table_info(?) WHERE name = ?")
+        .bind(table)
+        .bind(column)
+        .fetch_one(&mut **tx)
+        .await?;
+    Ok(count > 0)
+}
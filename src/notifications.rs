@@ -0,0 +1,328 @@
+//! External notification sinks. In-app notifications are rows in the
+//! `notifications` table; the events below are also mirrored to services
+//! users have configured, such as a Discord channel webhook, and finished
+//! downloads are posted as JSON to a generic webhook for Home Assistant, n8n
+//! or custom scripts. Every in-app notification goes through `Notifier::notify_user`,
+//! which also forwards it to the user's Telegram chat when they've linked one,
+//! to their verified email address when they've opted in, and to every
+//! browser they've enabled Web Push in.
+//!
+//! Sends are best-effort: they run in the background and failures are only
+//! logged, so a broken webhook never affects a scrape or a download.
+
+use crate::db;
+use crate::events::EventHub;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Settings key holding the Discord webhook URL
+pub const DISCORD_WEBHOOK_SETTING: &str = "discord_webhook_url";
+
+/// Settings key holding the URL finished downloads are posted to
+pub const DOWNLOAD_WEBHOOK_SETTING: &str = "download_complete_webhook";
+
+/// Settings key holding the Telegram bot token
+pub const TELEGRAM_BOT_TOKEN_SETTING: &str = "telegram_bot_token";
+
+/// Generic webhooks get less time than Discord; they're usually on the LAN
+const DOWNLOAD_WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+const DISCORD_WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+const TELEGRAM_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Embed colours
+pub const COLOR_NEW_GAMES: u32 = 0x5865F2;
+pub const COLOR_DOWNLOAD_COMPLETE: u32 = 0x57F287;
+
+/// Whether `url` looks like a Discord channel webhook
+pub fn is_discord_webhook_url(url: &str) -> bool {
+    ["https://discord.com/api/webhooks/", "https://discordapp.com/api/webhooks/", "https://ptb.discord.com/api/webhooks/", "https://canary.discord.com/api/webhooks/"]
+        .iter()
+        .any(|prefix| url.starts_with(prefix))
+}
+
+/// Whether `url` is an absolute http(s) URL
+pub fn is_http_url(url: &str) -> bool {
+    reqwest::Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.has_host())
+}
+
+/// Whether `chat_id` looks like a Telegram chat: a numeric ID (negative for
+/// groups) or a public @channel
+pub fn is_telegram_chat_id(chat_id: &str) -> bool {
+    let digits = chat_id.strip_prefix('-').unwrap_or(chat_id);
+    let numeric = !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit());
+    let channel = chat_id.strip_prefix('@')
+        .is_some_and(|name| name.len() >= 5 && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+    numeric || channel
+}
+
+/// Delivers in-app notifications: stores them, pushes the new unread count
+/// over the user's event socket, and mirrors them to Telegram, email and
+/// Web Push. Email and Web Push need server configuration, so their senders
+/// live here rather than being looked up per send.
+pub struct Notifier {
+    db: SqlitePool,
+    events: Arc<EventHub>,
+    mailer: Option<Arc<crate::email::Mailer>>,
+    push: Option<Arc<crate::push::WebPush>>,
+}
+
+impl Notifier {
+    pub fn new(
+        db: SqlitePool,
+        events: Arc<EventHub>,
+        mailer: Option<Arc<crate::email::Mailer>>,
+        push: Option<Arc<crate::push::WebPush>>,
+    ) -> Self {
+        Self { db, events, mailer, push }
+    }
+
+    pub fn mailer(&self) -> Option<&Arc<crate::email::Mailer>> {
+        self.mailer.as_ref()
+    }
+
+    pub fn push(&self) -> Option<&Arc<crate::push::WebPush>> {
+        self.push.as_ref()
+    }
+
+    /// Create an in-app notification for a user and mirror it to their Telegram
+    /// chat, email and browsers, if they've set those up
+    pub async fn notify_user(&self, user_id: i64, notification_type: &str, title: &str, message: &str) {
+        if let Err(e) = db::create_notification(&self.db, user_id, notification_type, title, message).await {
+            eprintln!("Failed to create notification for user {}: {}", user_id, e);
+            return;
+        }
+        self.events.notification_count_changed(user_id).await;
+        send_telegram(&self.db, user_id, title, message);
+        self.send_email(user_id, title, message);
+        self.send_push(user_id, notification_type, title, message);
+    }
+
+    /// Push a notification to every browser the user subscribed, in the
+    /// background. Subscriptions the push service reports as gone are dropped.
+    fn send_push(&self, user_id: i64, notification_type: &str, title: &str, message: &str) {
+        let Some(push) = self.push.clone() else {
+            return;
+        };
+        let db = self.db.clone();
+        let (notification_type, title, message) = (notification_type.to_string(), title.to_string(), message.to_string());
+
+        tokio::spawn(async move {
+            let subscriptions = match db::get_push_subscriptions(&db, user_id).await {
+                Ok(subscriptions) => subscriptions,
+                Err(e) => {
+                    eprintln!("Web Push: failed to load subscriptions for user {}: {}", user_id, e);
+                    return;
+                }
+            };
+            let payload = crate::push::PushPayload {
+                notification_type: &notification_type,
+                title: &title,
+                body: &message,
+            };
+            for subscription in subscriptions {
+                match push.send(&subscription, &payload).await {
+                    Ok(()) => {}
+                    Err(crate::push::PushError::Gone) => {
+                        let _ = db::delete_push_subscription(&db, &subscription.endpoint, None).await;
+                    }
+                    Err(e) => eprintln!("Web Push failed for user {}: {}", user_id, e),
+                }
+            }
+        });
+    }
+
+    /// Email a notification to the user's verified address, if SMTP is set up and
+    /// they opted in, in the background
+    fn send_email(&self, user_id: i64, title: &str, message: &str) {
+        let Some(mailer) = self.mailer.clone() else {
+            return;
+        };
+        let db = self.db.clone();
+        let subject = title.to_string();
+        let body = format!("{}\n\nYou can turn these emails off under Settings → Notifications.", message);
+
+        tokio::spawn(async move {
+            let to = match db::get_notification_email(&db, user_id).await {
+                Ok(Some(to)) => to,
+                Ok(None) => return,
+                Err(e) => {
+                    eprintln!("Email notification: failed to look up user {}: {}", user_id, e);
+                    return;
+                }
+            };
+            if let Err(e) = mailer.send(&to, &subject, &body).await {
+                eprintln!("Email notification failed for user {}: {}", user_id, e);
+            }
+        });
+    }
+}
+
+/// Webhook URL from the database setting, falling back to DISCORD_WEBHOOK_URL
+async fn discord_webhook_url(db: &SqlitePool) -> Option<String> {
+    db::get_setting(db, DISCORD_WEBHOOK_SETTING).await
+        .ok()
+        .flatten()
+        .or_else(|| std::env::var("DISCORD_WEBHOOK_URL").ok())
+        .filter(|url| !url.trim().is_empty())
+}
+
+/// Post an embed to the configured Discord webhook, if any, in the background
+pub fn send_discord(db: &SqlitePool, title: &str, message: &str, color: u32) {
+    let db = db.clone();
+    let embed = serde_json::json!({
+        "embeds": [{
+            "title": title,
+            "description": message,
+            "color": color,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }]
+    });
+
+    tokio::spawn(async move {
+        let Some(url) = discord_webhook_url(&db).await else {
+            return;
+        };
+        if let Err(e) = post_webhook(&url, &embed, DISCORD_WEBHOOK_TIMEOUT).await {
+            eprintln!("Discord webhook failed: {}", e);
+        }
+    });
+}
+
+/// Download webhook URL from the database setting, falling back to
+/// DOWNLOAD_COMPLETE_WEBHOOK_URL
+async fn download_webhook_url(db: &SqlitePool) -> Option<String> {
+    db::get_setting(db, DOWNLOAD_WEBHOOK_SETTING).await
+        .ok()
+        .flatten()
+        .or_else(|| std::env::var("DOWNLOAD_COMPLETE_WEBHOOK_URL").ok())
+        .filter(|url| !url.trim().is_empty())
+}
+
+#[derive(Serialize)]
+struct DownloadWebhookBody {
+    event: &'static str,
+    #[serde(flatten)]
+    download: db::CompletedDownload,
+}
+
+/// Post a finished download to the download webhook, if one is set, in the background
+pub fn send_download_complete(db: &SqlitePool, download_id: i64) {
+    let db = db.clone();
+
+    tokio::spawn(async move {
+        let Some(url) = download_webhook_url(&db).await else {
+            return;
+        };
+        let download = match db::get_completed_download(&db, download_id).await {
+            Ok(Some(download)) => download,
+            Ok(None) => return,
+            Err(e) => {
+                eprintln!("Download webhook: failed to load download {}: {}", download_id, e);
+                return;
+            }
+        };
+
+        let body = DownloadWebhookBody { event: "download_complete", download };
+        if let Err(e) = post_webhook(&url, &body, DOWNLOAD_WEBHOOK_TIMEOUT).await {
+            eprintln!("Download webhook failed for download {}: {}", download_id, e);
+        }
+    });
+}
+
+/// Bot token from the database setting, falling back to TELEGRAM_BOT_TOKEN
+pub async fn telegram_bot_token(db: &SqlitePool) -> Option<String> {
+    db::get_setting(db, TELEGRAM_BOT_TOKEN_SETTING).await
+        .ok()
+        .flatten()
+        .or_else(|| std::env::var("TELEGRAM_BOT_TOKEN").ok())
+        .filter(|token| !token.trim().is_empty())
+}
+
+/// The user's linked Telegram chat, if any
+async fn telegram_chat_id(db: &SqlitePool, user_id: i64) -> Option<String> {
+    db::get_user_settings(db, user_id).await
+        .ok()
+        .and_then(|s| s.telegram_chat_id)
+        .filter(|chat_id| !chat_id.is_empty())
+}
+
+/// Send a notification to the user's Telegram chat, if a bot token is set and
+/// they've linked a chat, in the background
+fn send_telegram(db: &SqlitePool, user_id: i64, title: &str, message: &str) {
+    let db = db.clone();
+    let text = format!("{}\n{}", title, message);
+
+    tokio::spawn(async move {
+        let Some(token) = telegram_bot_token(&db).await else {
+            return;
+        };
+        let Some(chat_id) = telegram_chat_id(&db, user_id).await else {
+            return;
+        };
+        if let Err(e) = post_telegram(&token, &chat_id, &text).await {
+            eprintln!("Telegram notification failed for user {}: {}", user_id, e);
+        }
+    });
+}
+
+/// Send `text` to a Telegram chat through the bot
+pub async fn post_telegram(token: &str, chat_id: &str, text: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    #[derive(serde::Deserialize)]
+    struct TelegramResponse {
+        ok: bool,
+        description: Option<String>,
+    }
+
+    // The token is part of the URL, so keep it out of error messages
+    let response = reqwest::Client::builder()
+        .timeout(TELEGRAM_TIMEOUT)
+        .build()?
+        .post(format!("https://api.telegram.org/bot{}/sendMessage", token))
+        .json(&serde_json::json!({
+            "chat_id": chat_id,
+            "text": text,
+            "disable_web_page_preview": true,
+        }))
+        .send()
+        .await
+        .map_err(|e| e.without_url())?;
+
+    let status = response.status();
+    let body: TelegramResponse = response.json().await.map_err(|e| e.without_url())?;
+    if !body.ok {
+        return Err(body.description.unwrap_or_else(|| format!("HTTP {}", status)).into());
+    }
+    Ok(())
+}
+
+async fn post_webhook(url: &str, body: &impl Serialize, timeout: Duration) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let response = reqwest::Client::builder()
+        .timeout(timeout)
+        .build()?
+        .post(url)
+        .json(body)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status()).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_telegram_chat_id() {
+        assert!(is_telegram_chat_id("123456789"));
+        assert!(is_telegram_chat_id("-1001234567890"));
+        assert!(is_telegram_chat_id("@my_channel"));
+        assert!(!is_telegram_chat_id("-"));
+        assert!(!is_telegram_chat_id("@abc"));
+        assert!(!is_telegram_chat_id("https://t.me/foo"));
+    }
+}
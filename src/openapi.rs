@@ -0,0 +1,89 @@
+//! OpenAPI description of the HTTP API, served at `/api/openapi.json` with a
+//! Swagger UI at `/api/docs`. Covers the games and downloads endpoints so far;
+//! add a handler's `#[utoipa::path]` to `paths` below when documenting more.
+
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Repack Browser API",
+        description = "Errors always come back as `{ \"error\": { \"code\", \"message\" } }`. \
+                       Endpoints marked with a lock need the `session` cookie from `/api/auth/login`."
+    ),
+    paths(
+        crate::get_games,
+        crate::get_new_games,
+        crate::get_game_detail,
+        crate::rate_game,
+        crate::export_games_csv,
+        crate::export_games_json,
+        crate::import_games_json,
+        crate::get_blacklist,
+        crate::add_to_blacklist,
+        crate::remove_from_blacklist,
+        crate::get_genres,
+        crate::get_featured_games,
+        crate::get_random_game,
+        crate::get_downloads,
+        crate::get_download_history,
+        crate::queue_download,
+        crate::queue_download_batch,
+        crate::get_download_status,
+        crate::cancel_download,
+        crate::retry_download,
+        crate::remove_download,
+        crate::set_download_password,
+        crate::assign_download,
+        crate::set_install_dir,
+    ),
+    components(schemas(
+        crate::api_error::ErrorBody,
+        crate::api_error::ErrorDetail,
+        crate::ApiResponse,
+        crate::GamesResponse,
+        crate::NewGamesResponse,
+        crate::db::Game,
+        crate::db::GameInsert,
+        crate::scrapers::LinkType,
+        crate::GameDetail,
+        crate::db::GameRatingStats,
+        crate::RateGameRequest,
+        crate::db::BlacklistEntry,
+        crate::BlacklistRequest,
+        crate::DownloadsResponse,
+        crate::DownloadHistoryResponse,
+        crate::db::DownloadHistoryEntry,
+        crate::download_manager::DownloadInfo,
+        crate::download_manager::DownloadFileInfo,
+        crate::extractor::ExtractionProgress,
+        crate::realdebrid::DownloadLink,
+        crate::QueueDownloadRequest,
+        crate::BatchQueueRequest,
+        crate::BatchQueueResponse,
+        crate::BatchQueueResult,
+        crate::ArchivePasswordRequest,
+        crate::AssignDownloadRequest,
+        crate::InstallDirRequest,
+    )),
+    modifiers(&SessionCookie),
+    tags(
+        (name = "games", description = "Browsing the scraped catalog"),
+        (name = "downloads", description = "Queueing and managing downloads"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Registers the `session` cookie that `security(("session" = []))` refers to
+struct SessionCookie;
+
+impl Modify for SessionCookie {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "session",
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::new("session"))),
+        );
+    }
+}
@@ -0,0 +1,87 @@
+//! Standard paging headers for list endpoints: `X-Total-Count` and an RFC 8288
+//! `Link` header with first/prev/next/last pages, for generic HTTP clients
+//! that don't read the page fields in the body.
+
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Uri};
+use axum::http::header::LINK;
+
+pub static X_TOTAL_COUNT: HeaderName = HeaderName::from_static("x-total-count");
+
+/// Paging headers for `page` of a `total`-item list, linking to the same
+/// path and query as `uri` with only `page` changed
+pub fn headers(uri: &Uri, page: i64, per_page: i64, total: i64) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(X_TOTAL_COUNT.clone(), HeaderValue::from(total));
+
+    let last = ((total + per_page - 1) / per_page).max(1);
+    let mut links = vec![(1, "first")];
+    if page > 1 {
+        links.push((page.min(last + 1) - 1, "prev"));
+    }
+    if page < last {
+        links.push((page + 1, "next"));
+    }
+    links.push((last, "last"));
+
+    let value = links
+        .into_iter()
+        .map(|(target, rel)| format!("<{}>; rel=\"{}\"", page_url(uri, target), rel))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if let Ok(value) = HeaderValue::from_str(&value) {
+        headers.insert(LINK, value);
+    }
+    headers
+}
+
+/// `uri`'s path and query with `page` set to `page`; other parameters are kept as sent
+fn page_url(uri: &Uri, page: i64) -> String {
+    let mut params: Vec<&str> = uri
+        .query()
+        .unwrap_or("")
+        .split('&')
+        .filter(|pair| !pair.is_empty() && pair.split('=').next() != Some("page"))
+        .collect();
+    let page_param = format!("page={}", page);
+    params.push(&page_param);
+    format!("{}?{}", uri.path(), params.join("&"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(uri: &str, page: i64, per_page: i64, total: i64) -> String {
+        let headers = headers(&uri.parse().unwrap(), page, per_page, total);
+        headers[LINK].to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_link_header() {
+        assert_eq!(
+            link("/api/games?search=doom&page=2&per_page=10", 2, 10, 35),
+            "</api/games?search=doom&per_page=10&page=1>; rel=\"first\", \
+             </api/games?search=doom&per_page=10&page=1>; rel=\"prev\", \
+             </api/games?search=doom&per_page=10&page=3>; rel=\"next\", \
+             </api/games?search=doom&per_page=10&page=4>; rel=\"last\""
+        );
+
+        // First page of one has neither prev nor next
+        assert_eq!(
+            link("/api/games", 1, 50, 0),
+            "</api/games?page=1>; rel=\"first\", </api/games?page=1>; rel=\"last\""
+        );
+
+        // Past the end, prev points back at the last real page
+        assert_eq!(
+            link("/api/games?page=9", 9, 10, 25),
+            "</api/games?page=1>; rel=\"first\", </api/games?page=3>; rel=\"prev\", </api/games?page=3>; rel=\"last\""
+        );
+    }
+
+    #[test]
+    fn test_total_count_header() {
+        let headers = headers(&"/api/games".parse().unwrap(), 1, 50, 1234);
+        assert_eq!(headers[&X_TOTAL_COUNT], "1234");
+    }
+}
@@ -0,0 +1,179 @@
+//! Browser Web Push. Browsers subscribe through `/api/push/subscribe` and the
+//! service worker in `frontend/sw.js` shows the pushed notifications, so they
+//! arrive even when no tab is open. Messages are signed with a VAPID key the
+//! server generates on first start and keeps in the settings table.
+
+use jwt_simple::algorithms::ES256KeyPair;
+use jwt_simple::reexports::ct_codecs::{Base64UrlSafeNoPadding, Encoder};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::time::Duration;
+use web_push::{ContentEncoding, PartialVapidSignatureBuilder, SubscriptionInfo, VapidSignatureBuilder, WebPushError, WebPushMessageBuilder};
+
+use crate::db;
+
+/// Settings key holding the VAPID private key; never sent to browsers
+const VAPID_KEY_SETTING: &str = "vapid_private_key";
+
+/// How long a push service keeps a message for an offline browser
+const PUSH_TTL_SECS: u32 = 24 * 60 * 60;
+
+const PUSH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Hosts of the browsers' push services: Chrome (FCM), Firefox (autopush),
+/// Safari (Apple) and Edge (WNS). The server POSTs to whatever endpoint a
+/// browser subscribed with, so anything else is refused rather than letting
+/// a subscription point it at hosts on the local network.
+const PUSH_SERVICE_HOSTS: &[&str] = &[
+    "fcm.googleapis.com",
+    "android.googleapis.com",
+    "updates.push.services.mozilla.com",
+    "push.apple.com",
+    "notify.windows.com",
+];
+
+/// Whether `endpoint` is an https URL on a known push service
+pub fn is_push_service_endpoint(endpoint: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(endpoint) else { return false };
+    let Some(host) = url.host_str() else { return false };
+    url.scheme() == "https"
+        && url.port().is_none()
+        && PUSH_SERVICE_HOSTS.iter().any(|service| {
+            host == *service || host.strip_suffix(service).is_some_and(|sub| sub.ends_with('.'))
+        })
+}
+
+/// What the service worker receives
+#[derive(Debug, Serialize)]
+pub struct PushPayload<'a> {
+    #[serde(rename = "type")]
+    pub notification_type: &'a str,
+    pub title: &'a str,
+    pub body: &'a str,
+}
+
+#[derive(Debug)]
+pub enum PushError {
+    /// The browser unsubscribed or the subscription expired; forget it
+    Gone,
+    Other(String),
+}
+
+impl std::fmt::Display for PushError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PushError::Gone => write!(f, "subscription is no longer valid"),
+            PushError::Other(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<WebPushError> for PushError {
+    fn from(e: WebPushError) -> Self {
+        match e {
+            WebPushError::EndpointNotValid(_) | WebPushError::EndpointNotFound(_) => PushError::Gone,
+            e => PushError::Other(e.to_string()),
+        }
+    }
+}
+
+pub struct WebPush {
+    key: PartialVapidSignatureBuilder,
+    /// Uncompressed public key, base64url, for `applicationServerKey`
+    public_key: String,
+    /// VAPID contact, an https: or mailto: URL; some push services require it
+    subject: Option<String>,
+    client: reqwest::Client,
+}
+
+impl WebPush {
+    /// Load the VAPID key from settings, generating and saving one the first time
+    pub async fn load(db: &SqlitePool, subject: Option<String>) -> Result<Self, String> {
+        let encoded = match db::get_setting(db, VAPID_KEY_SETTING).await.map_err(|e| e.to_string())? {
+            Some(encoded) => encoded,
+            None => {
+                let encoded = Base64UrlSafeNoPadding::encode_to_string(ES256KeyPair::generate().to_bytes())
+                    .map_err(|e| e.to_string())?;
+                db::set_setting(db, VAPID_KEY_SETTING, &encoded).await.map_err(|e| e.to_string())?;
+                encoded
+            }
+        };
+
+        let key = VapidSignatureBuilder::from_base64_no_sub(&encoded)
+            .map_err(|e| format!("Invalid VAPID key in settings: {}", e))?;
+        let public_key = Base64UrlSafeNoPadding::encode_to_string(key.get_public_key())
+            .map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            key,
+            public_key,
+            subject,
+            client: reqwest::Client::builder()
+                .timeout(PUSH_TIMEOUT)
+                // A push service answers directly; never follow it elsewhere
+                .redirect(reqwest::redirect::Policy::none())
+                .build()
+                .map_err(|e| e.to_string())?,
+        })
+    }
+
+    pub fn public_key(&self) -> &str {
+        &self.public_key
+    }
+
+    /// Encrypt `payload` for one subscription and hand it to its push service
+    pub async fn send(&self, subscription: &db::PushSubscription, payload: &PushPayload<'_>) -> Result<(), PushError> {
+        // Subscriptions saved before endpoints were checked
+        if !is_push_service_endpoint(&subscription.endpoint) {
+            return Err(PushError::Gone);
+        }
+
+        let info = SubscriptionInfo::new(
+            subscription.endpoint.as_str(),
+            subscription.p256dh.as_str(),
+            subscription.auth.as_str(),
+        );
+        let content = serde_json::to_vec(payload).map_err(|e| PushError::Other(e.to_string()))?;
+
+        let mut signature = self.key.clone().add_sub_info(&info);
+        if let Some(subject) = &self.subject {
+            signature.add_claim("sub", subject.as_str());
+        }
+
+        let mut message = WebPushMessageBuilder::new(&info);
+        message.set_ttl(PUSH_TTL_SECS);
+        message.set_payload(ContentEncoding::Aes128Gcm, &content);
+        message.set_vapid_signature(signature.build()?);
+
+        let request = web_push::request_builder::build_request::<Vec<u8>>(message.build()?);
+        let request = reqwest::Request::try_from(request).map_err(|e| PushError::Other(e.to_string()))?;
+        let response = self.client.execute(request).await
+            .map_err(|e| PushError::Other(e.without_url().to_string()))?;
+
+        let status = response.status();
+        let body = response.bytes().await.map(|b| b.to_vec()).unwrap_or_default();
+        web_push::request_builder::parse_response(status, body)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_push_service_endpoint() {
+        assert!(is_push_service_endpoint("https://fcm.googleapis.com/fcm/send/abc"));
+        assert!(is_push_service_endpoint("https://updates.push.services.mozilla.com/wpush/v2/abc"));
+        assert!(is_push_service_endpoint("https://web.push.apple.com/QGx"));
+        assert!(is_push_service_endpoint("https://wns2-by3p.notify.windows.com/w/?token=abc"));
+
+        assert!(!is_push_service_endpoint("http://fcm.googleapis.com/fcm/send/abc"));
+        assert!(!is_push_service_endpoint("https://fcm.googleapis.com:8443/fcm/send/abc"));
+        assert!(!is_push_service_endpoint("https://evilpush.apple.com.example.org/x"));
+        assert!(!is_push_service_endpoint("https://notpush.apple.com/x"));
+        assert!(!is_push_service_endpoint("https://192.168.1.1/admin"));
+        assert!(!is_push_service_endpoint("https://localhost/x"));
+        assert!(!is_push_service_endpoint("not a url"));
+    }
+}
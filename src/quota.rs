@@ -0,0 +1,73 @@
+//! Per-user download limits, so one user on a shared instance can't fill the
+//! queue: a cap on downloads queued or in progress at once, and on the GB of
+//! games queued per UTC day. Daily usage is counted in `download_usage` and
+//! old days are pruned by the hourly cleanup task. Admins aren't limited.
+
+use sqlx::SqlitePool;
+
+use crate::config::DownloadQuota;
+use crate::db;
+use crate::installation_checker::parse_size_to_gb;
+
+const GB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// The UTC day usage is counted against, as YYYY-MM-DD
+pub fn today() -> String {
+    chrono::Utc::now().format("%Y-%m-%d").to_string()
+}
+
+/// Bytes a game's listed size ("35.2 GB") counts for; unknown sizes count as 0
+pub fn game_bytes(file_size: &str) -> i64 {
+    parse_size_to_gb(file_size).map(|gb| (gb * GB) as i64).unwrap_or(0)
+}
+
+/// Check whether `user` may queue a game of `bytes`. The error says which
+/// limit was hit.
+pub async fn check(pool: &SqlitePool, quota: DownloadQuota, user: &db::User, bytes: i64) -> Result<(), String> {
+    if user.is_admin {
+        return Ok(());
+    }
+
+    if quota.max_active > 0 {
+        let active = db::count_active_user_downloads(pool, user.id).await.map_err(|e| e.to_string())?;
+        if active >= quota.max_active as i64 {
+            return Err(format!(
+                "You already have {} download(s) queued or in progress (limit {}). Wait for one to finish.",
+                active, quota.max_active
+            ));
+        }
+    }
+
+    if quota.daily_gb > 0.0 {
+        let used = db::get_download_usage(pool, user.id, &today()).await.map_err(|e| e.to_string())?;
+        let limit = (quota.daily_gb * GB) as i64;
+        if used + bytes > limit {
+            return Err(format!(
+                "This would take you over today's {} GB download limit ({:.1} GB used). It resets at midnight UTC.",
+                quota.daily_gb,
+                used as f64 / GB
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Count a queued game against the user's daily usage
+pub async fn record(pool: &SqlitePool, user_id: i64, bytes: i64) {
+    if let Err(e) = db::add_download_usage(pool, user_id, &today(), bytes).await {
+        eprintln!("Error recording download usage for user {}: {}", user_id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_game_bytes() {
+        assert_eq!(game_bytes("2 GB"), 2 * 1024 * 1024 * 1024);
+        assert_eq!(game_bytes("512 MB"), 512 * 1024 * 1024);
+        assert_eq!(game_bytes("unknown"), 0);
+    }
+}
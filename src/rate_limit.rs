@@ -0,0 +1,141 @@
+//! Per-client request limits for the API, so an instance exposed beyond
+//! localhost can't be hammered through the search or scrape endpoints.
+//! Counts requests in fixed one-minute windows keyed by session or IP. Only
+//! session tokens known to belong to a user get their own window; any other
+//! cookie is counted against the caller's IP.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Expired windows are swept once the table grows past this many keys
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// How long a session token that resolved to a user is trusted without
+/// looking it up again
+const SESSION_TTL: Duration = Duration::from_secs(5 * 60);
+
+pub struct RateLimiter {
+    /// Requests per minute for callers without a session, keyed by IP
+    pub anonymous_per_minute: u32,
+    /// Requests per minute for logged-in users, keyed by session
+    pub authenticated_per_minute: u32,
+    windows: Mutex<HashMap<String, (Instant, u32)>>,
+    /// Session tokens recently seen to belong to a user, with when they were checked
+    sessions: Mutex<HashMap<String, Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new(anonymous_per_minute: u32, authenticated_per_minute: u32) -> Self {
+        Self {
+            anonymous_per_minute,
+            authenticated_per_minute,
+            windows: Mutex::new(HashMap::new()),
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether `token` was recently found to belong to a user
+    pub fn is_known_session(&self, token: &str) -> bool {
+        self.is_known_session_at(token, Instant::now())
+    }
+
+    fn is_known_session_at(&self, token: &str, now: Instant) -> bool {
+        self.sessions.lock().unwrap()
+            .get(token)
+            .is_some_and(|checked| now.duration_since(*checked) < SESSION_TTL)
+    }
+
+    /// Record that `token` belongs to a user, so its requests get a session window
+    pub fn remember_session(&self, token: &str) {
+        self.remember_session_at(token, Instant::now())
+    }
+
+    fn remember_session_at(&self, token: &str, now: Instant) {
+        let mut sessions = self.sessions.lock().unwrap();
+        if sessions.len() > PRUNE_THRESHOLD {
+            sessions.retain(|_, checked| now.duration_since(*checked) < SESSION_TTL);
+        }
+        sessions.insert(token.to_string(), now);
+    }
+
+    /// Count a request for `key`. A limit of 0 means unlimited.
+    /// Returns the seconds until the window resets when the limit is exceeded.
+    pub fn check(&self, key: &str, limit: u32) -> Result<(), u64> {
+        self.check_at(key, limit, Instant::now())
+    }
+
+    fn check_at(&self, key: &str, limit: u32, now: Instant) -> Result<(), u64> {
+        if limit == 0 {
+            return Ok(());
+        }
+
+        let mut windows = self.windows.lock().unwrap();
+
+        if windows.len() > PRUNE_THRESHOLD {
+            windows.retain(|_, (started, _)| now.duration_since(*started) < WINDOW);
+        }
+
+        let (started, count) = windows.entry(key.to_string()).or_insert((now, 0));
+        if now.duration_since(*started) >= WINDOW {
+            *started = now;
+            *count = 0;
+        }
+
+        if *count >= limit {
+            let remaining = WINDOW.saturating_sub(now.duration_since(*started));
+            return Err(remaining.as_secs().max(1));
+        }
+        *count += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_limit() {
+        let limiter = RateLimiter::new(2, 0);
+        let now = Instant::now();
+
+        assert_eq!(limiter.check_at("ip:a", 2, now), Ok(()));
+        assert_eq!(limiter.check_at("ip:a", 2, now), Ok(()));
+        assert_eq!(limiter.check_at("ip:a", 2, now), Err(60));
+        assert_eq!(limiter.check_at("ip:a", 2, now + Duration::from_secs(45)), Err(15));
+        // Keys are counted separately, and 0 never limits
+        assert_eq!(limiter.check_at("ip:b", 2, now), Ok(()));
+        for _ in 0..100 {
+            assert_eq!(limiter.check_at("user:1", 0, now), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_check_window_rollover() {
+        let limiter = RateLimiter::new(1, 0);
+        let now = Instant::now();
+
+        assert_eq!(limiter.check_at("ip:a", 1, now), Ok(()));
+        assert!(limiter.check_at("ip:a", 1, now + WINDOW - Duration::from_millis(1)).is_err());
+        // A new window starts a fresh count, measured from its first request
+        let next = now + WINDOW;
+        assert_eq!(limiter.check_at("ip:a", 1, next), Ok(()));
+        assert_eq!(limiter.check_at("ip:a", 1, next + Duration::from_secs(30)), Err(30));
+    }
+
+    #[test]
+    fn test_known_sessions_expire() {
+        let limiter = RateLimiter::new(1, 1);
+        let now = Instant::now();
+
+        assert!(!limiter.is_known_session_at("token", now));
+        limiter.remember_session_at("token", now);
+        assert!(limiter.is_known_session_at("token", now + SESSION_TTL - Duration::from_millis(1)));
+        assert!(!limiter.is_known_session_at("other", now));
+        // Looked up again once the entry is stale
+        assert!(!limiter.is_known_session_at("token", now + SESSION_TTL));
+    }
+}
@@ -1,10 +1,16 @@
+use futures::stream::{self, StreamExt};
 use regex::Regex;
-use reqwest::Client;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Client, StatusCode};
 use serde::Deserialize;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
+use tokio::time::Instant;
 
+use crate::db;
 use crate::scrapers::ScrapeProgress;
 
 // ─── RAWG API response types ───
@@ -45,22 +51,97 @@ pub struct GameMetadata {
     pub metacritic: Option<i32>,
 }
 
-/// Enrich a list of games with metadata from RAWG API.
+/// Requests in flight at once during enrichment
+const CONCURRENCY: usize = 5;
+
+/// RAWG's free tier allows about 5 requests a second; starts are spaced this far apart
+const REQUEST_SPACING: Duration = Duration::from_millis(200);
+
+/// Each request, including reading the body, gives up after this long
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Retries after a 429 before the title is skipped
+const MAX_RETRIES: u32 = 3;
+
+/// Spaces request starts across all in-flight lookups so bursts don't trip
+/// the rate limit
+struct Pacer {
+    next_slot: Mutex<Instant>,
+}
+
+impl Pacer {
+    fn new() -> Self {
+        Self { next_slot: Mutex::new(Instant::now()) }
+    }
+
+    /// Wait for this request's turn
+    async fn wait(&self) {
+        let slot = {
+            let mut next = self.next_slot.lock().await;
+            let slot = (*next).max(Instant::now());
+            *next = slot + REQUEST_SPACING;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+
+    /// Hold back every request until `delay` from now, after a 429
+    async fn back_off(&self, delay: Duration) {
+        let mut next = self.next_slot.lock().await;
+        *next = (*next).max(Instant::now() + delay);
+    }
+}
+
+/// Key for a title in the `rawg_cache` table. Built from the cleaned title,
+/// so repacks of different versions of a game share one entry.
+pub fn cache_key(title: &str) -> String {
+    clean_game_title(title)
+        .to_lowercase()
+        .replace(|c: char| !c.is_alphanumeric() && c != ' ', "")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Enrich a list of games with metadata from RAWG API, a few lookups at a
+/// time. Titles that clean up to the same name are looked up once, and each
+/// match is saved to the `rawg_cache` table as it arrives.
 /// Updates the progress state during enrichment.
-/// Returns a map of game index -> metadata.
+/// Returns one entry per title, in the same order.
 pub async fn enrich_games(
+    pool: &SqlitePool,
     titles: &[String],
     api_key: &str,
     progress: Arc<RwLock<ScrapeProgress>>,
 ) -> Vec<Option<GameMetadata>> {
     let client = Client::builder()
         .user_agent("FitGirl-Browser/1.0")
-        .timeout(Duration::from_secs(15))
+        .timeout(REQUEST_TIMEOUT)
         .build()
         .unwrap();
-
-    let total = titles.len();
-    let mut results: Vec<Option<GameMetadata>> = vec![None; total];
+    let pacer = Pacer::new();
+
+    // One lookup per distinct cache key; `lookup_of[i]` is title i's lookup
+    let mut lookups_by_key: HashMap<String, usize> = HashMap::new();
+    let mut unique: Vec<(String, String)> = Vec::new();
+    let lookup_of: Vec<Option<usize>> = titles
+        .iter()
+        .map(|title| {
+            let clean_title = clean_game_title(title);
+            let key = cache_key(title);
+            if key.is_empty() {
+                return None;
+            }
+            Some(*lookups_by_key.entry(key.clone()).or_insert_with(|| {
+                unique.push((key, clean_title));
+                unique.len() - 1
+            }))
+        })
+        .collect();
+
+    let total = unique.len();
+    let mut found: Vec<Option<GameMetadata>> = vec![None; total];
+    let mut done: usize = 0;
     let mut enriched_count: usize = 0;
     let mut image_count: usize = 0;
     let mut genre_count: usize = 0;
@@ -72,61 +153,63 @@ pub async fn enrich_games(
         p.message = format!("Enriching metadata for {} games via RAWG...", total);
     }
 
-    println!("Starting RAWG metadata enrichment for {} games...", total);
+    println!("Starting RAWG metadata enrichment for {} games ({} at a time)...", total, CONCURRENCY);
 
-    // Process in batches to respect rate limits
-    // RAWG free tier: ~5 requests/second
-    for (i, title) in titles.iter().enumerate() {
-        let clean_title = clean_game_title(title);
-        if clean_title.is_empty() {
-            results[i] = None;
-            continue;
-        }
+    let mut lookups = stream::iter(unique.iter().enumerate())
+        .map(|(i, (_, clean_title))| {
+            let (client, pacer) = (&client, &pacer);
+            async move { (i, search_rawg(client, pacer, api_key, clean_title).await) }
+        })
+        .buffer_unordered(CONCURRENCY);
 
-        match search_rawg(&client, api_key, &clean_title).await {
-            Some(meta) => {
-                if meta.image_url.is_some() {
-                    image_count += 1;
-                }
-                if meta.genres.is_some() {
-                    genre_count += 1;
-                }
-                enriched_count += 1;
-                results[i] = Some(meta);
+    while let Some((i, meta)) = lookups.next().await {
+        done += 1;
+        if let Some(meta) = meta {
+            if meta.image_url.is_some() {
+                image_count += 1;
+            }
+            if meta.genres.is_some() {
+                genre_count += 1;
             }
-            None => {
-                results[i] = None;
+            enriched_count += 1;
+
+            // Saved straight away so a scrape that fails later still keeps it
+            if let Err(e) = db::save_rawg_metadata(
+                pool,
+                &unique[i].0,
+                meta.image_url.as_deref(),
+                meta.genres.as_deref(),
+            )
+            .await
+            {
+                eprintln!("  Failed to cache RAWG metadata for \"{}\": {}", unique[i].1, e);
             }
+            found[i] = Some(meta);
         }
 
         // Update progress every 10 games
-        if (i + 1) % 10 == 0 || i + 1 == total {
-            let pct = ((i + 1) as f64 / total as f64) * 100.0;
+        if done.is_multiple_of(10) || done == total {
+            let pct = (done as f64 / total as f64) * 100.0;
             let mut p = progress.write().await;
             p.phase = "enriching".to_string();
             p.progress = pct;
-            p.games_scraped = (i + 1) as i64;
+            p.games_scraped = done as i64;
             p.games_total = total as i64;
             p.with_thumbnail = image_count as i64;
             p.with_genres = genre_count as i64;
             p.message = format!(
                 "RAWG enrichment {}/{} — 🖼 {} images | 🏷 {} genres",
-                i + 1, total, image_count, genre_count
+                done, total, image_count, genre_count
             );
         }
 
         // Print console progress every 50
-        if (i + 1) % 50 == 0 || i + 1 == total {
+        if done.is_multiple_of(50) || done == total {
             println!(
                 "  RAWG {}/{} — {} matched, {} images, {} genres",
-                i + 1, total, enriched_count, image_count, genre_count
+                done, total, enriched_count, image_count, genre_count
             );
         }
-
-        // Rate limit: ~5 requests per second
-        if (i + 1) % 5 == 0 {
-            tokio::time::sleep(Duration::from_millis(1100)).await;
-        }
     }
 
     println!(
@@ -134,20 +217,46 @@ pub async fn enrich_games(
         enriched_count, total, image_count, genre_count
     );
 
-    results
+    lookup_of
+        .into_iter()
+        .map(|lookup| lookup.and_then(|i| found[i].clone()))
+        .collect()
 }
 
-/// Search RAWG for a game and return metadata
-async fn search_rawg(client: &Client, api_key: &str, title: &str) -> Option<GameMetadata> {
+/// Search RAWG for a game and return metadata, retrying when rate limited
+async fn search_rawg(client: &Client, pacer: &Pacer, api_key: &str, title: &str) -> Option<GameMetadata> {
     let url = format!(
         "https://api.rawg.io/api/games?key={}&search={}&page_size=1&search_precise=true",
         api_key,
         urlencoding::encode(title)
     );
 
-    let resp = match client.get(&url).send().await {
-        Ok(r) => r,
-        Err(_) => return None,
+    let mut attempt = 0;
+    let resp = loop {
+        pacer.wait().await;
+        let resp = match client.get(&url).send().await {
+            Ok(r) => r,
+            Err(_) => return None,
+        };
+
+        if resp.status() != StatusCode::TOO_MANY_REQUESTS {
+            break resp;
+        }
+        if attempt == MAX_RETRIES {
+            eprintln!("  RAWG still rate limiting after {} retries, skipping \"{}\"", MAX_RETRIES, title);
+            return None;
+        }
+        attempt += 1;
+
+        // Honour Retry-After when RAWG sends it, otherwise 2s, 4s, 8s
+        let delay = resp
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or_else(|| Duration::from_secs(1 << attempt));
+        pacer.back_off(delay).await;
     };
 
     if !resp.status().is_success() {
@@ -237,4 +346,19 @@ mod tests {
             "Elden Ring"
         );
     }
+
+    #[tokio::test]
+    async fn test_pacer_spaces_requests() {
+        let pacer = Pacer::new();
+        let start = Instant::now();
+        for _ in 0..3 {
+            pacer.wait().await;
+        }
+        assert!(start.elapsed() >= REQUEST_SPACING * 2);
+
+        pacer.back_off(Duration::from_millis(500)).await;
+        let start = Instant::now();
+        pacer.wait().await;
+        assert!(start.elapsed() >= Duration::from_millis(450));
+    }
 }
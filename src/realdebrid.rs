@@ -16,6 +16,66 @@ pub struct TorrentInfo {
     pub filename: String,
     pub status: String,
     pub links: Vec<String>,
+    /// Real-Debrid's own download progress for the torrent (0-100)
+    #[serde(default)]
+    pub progress: f64,
+    /// Bytes per second, only present while Real-Debrid is downloading
+    #[serde(default)]
+    pub speed: Option<u64>,
+    #[serde(default)]
+    pub seeders: Option<u32>,
+    #[serde(default)]
+    pub files: Vec<TorrentFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TorrentFile {
+    pub id: i64,
+    pub path: String,
+    pub bytes: u64,
+    #[serde(default)]
+    pub selected: i32,
+}
+
+/// Extensions that are never needed to install a repack
+const JUNK_EXTENSIONS: &[&str] = &["txt", "nfo", "url", "jpg", "jpeg", "png", "html", "htm"];
+
+/// Which torrent files to ask Real-Debrid for. The default selects everything.
+#[derive(Debug, Clone, Default)]
+pub struct FileSelection {
+    /// Skip files smaller than this many bytes
+    pub min_size_bytes: Option<u64>,
+    /// Only keep files with these extensions (lowercase, no dot); empty keeps all
+    pub extensions: Vec<String>,
+    /// Skip samples, readmes, NFOs and shortcuts
+    pub skip_junk: bool,
+}
+
+impl FileSelection {
+    /// Whether this selection would keep every file (lets RD use "all")
+    pub fn is_all(&self) -> bool {
+        self.min_size_bytes.is_none() && self.extensions.is_empty() && !self.skip_junk
+    }
+
+    /// Whether a file with this path and size should be downloaded
+    pub fn matches(&self, path: &str, bytes: u64) -> bool {
+        if self.min_size_bytes.is_some_and(|min| bytes < min) {
+            return false;
+        }
+        let ext = path.rsplit_once('.').map(|(_, e)| e.to_lowercase()).unwrap_or_default();
+        if !self.extensions.is_empty() && !self.extensions.contains(&ext) {
+            return false;
+        }
+        !(self.skip_junk && is_junk_file(path))
+    }
+}
+
+/// Samples and text/image extras bundled with releases
+pub fn is_junk_file(path: &str) -> bool {
+    let lower = path.to_lowercase();
+    let name = lower.rsplit(['/', '\\']).next().unwrap_or(&lower);
+    let ext = name.rsplit_once('.').map(|(_, e)| e).unwrap_or("");
+    name.contains("sample") || JUNK_EXTENSIONS.contains(&ext)
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -24,13 +84,59 @@ pub struct UnrestrictLinkResponse {
     pub filename: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct DownloadLink {
     pub filename: String,
     pub download_url: String,
     pub size: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct UserResponse {
+    username: String,
+    #[serde(rename = "type")]
+    account_type: String,
+    #[serde(default)]
+    points: i64,
+    /// Seconds of premium left
+    #[serde(default)]
+    premium: i64,
+    expiration: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TrafficEntry {
+    left: Option<i64>,
+    bytes: Option<i64>,
+    limit: Option<i64>,
+    #[serde(rename = "type")]
+    traffic_type: Option<String>,
+}
+
+/// Premium status and per-hoster traffic for the Real-Debrid account
+#[derive(Debug, Serialize)]
+pub struct AccountInfo {
+    pub username: String,
+    /// "premium" or "free"
+    pub account_type: String,
+    pub premium_seconds_left: i64,
+    pub premium_days_left: i64,
+    pub expiration: Option<String>,
+    pub fidelity_points: i64,
+    pub traffic: Vec<HostTraffic>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HostTraffic {
+    pub host: String,
+    /// Remaining quota, in units of `traffic_type` ("links", "gigabytes" or "bytes")
+    pub left: Option<i64>,
+    /// Bytes downloaded from this host today
+    pub bytes: Option<i64>,
+    pub limit: Option<i64>,
+    pub traffic_type: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 struct HostInfo {
     id: String,
@@ -60,6 +166,10 @@ impl RealDebridClient {
             api_key,
         }
     }
+
+    pub fn has_api_key(&self) -> bool {
+        !self.api_key.is_empty()
+    }
     
     /// Add a magnet link to Real-Debrid
     pub async fn add_magnet(&self, magnet_link: &str) -> Result<AddMagnetResponse, Box<dyn std::error::Error>> {
@@ -81,10 +191,37 @@ impl RealDebridClient {
     
     /// Select files from a torrent (use "all" to select all files)
     pub async fn select_files(&self, torrent_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.select_file_ids(torrent_id, "all").await
+    }
+
+    /// Select the torrent files matching `selection`. Falls back to all files
+    /// when the filter would leave nothing to download.
+    pub async fn select_matching_files(
+        &self,
+        torrent_id: &str,
+        files: &[TorrentFile],
+        selection: &FileSelection,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let ids: Vec<String> = files.iter()
+            .filter(|f| selection.matches(&f.path, f.bytes))
+            .map(|f| f.id.to_string())
+            .collect();
+
+        if selection.is_all() || ids.is_empty() || ids.len() == files.len() {
+            self.select_files(torrent_id).await?;
+            return Ok(files.len());
+        }
+
+        self.select_file_ids(torrent_id, &ids.join(",")).await?;
+        Ok(ids.len())
+    }
+
+    /// Call selectFiles with "all" or a comma-separated list of file IDs
+    async fn select_file_ids(&self, torrent_id: &str, files: &str) -> Result<(), Box<dyn std::error::Error>> {
         let response = self.client
             .post(&format!("https://api.real-debrid.com/rest/1.0/torrents/selectFiles/{}", torrent_id))
             .header("Authorization", format!("Bearer {}", self.api_key))
-            .form(&[("files", "all")])
+            .form(&[("files", files)])
             .send()
             .await?;
         
@@ -113,32 +250,50 @@ impl RealDebridClient {
         Ok(result)
     }
     
-    /// Wait for a torrent to be ready for download
-    pub async fn wait_for_ready(&self, torrent_id: &str, max_wait_secs: u64) -> Result<TorrentInfo, Box<dyn std::error::Error>> {
+    /// Poll a torrent until Real-Debrid has finished downloading it, selecting
+    /// files per `selection` if it asks for one. `on_progress` is called after every poll.
+    pub async fn wait_for_ready_with_progress(
+        &self,
+        torrent_id: &str,
+        selection: &FileSelection,
+        max_wait_secs: u64,
+        on_progress: &(dyn Fn(&TorrentInfo) + Send + Sync),
+    ) -> Result<TorrentInfo, Box<dyn std::error::Error>> {
         let start = std::time::Instant::now();
-        
+        let mut files_selected = false;
+
         loop {
             let info = self.get_torrent_info(torrent_id).await?;
-            
+            on_progress(&info);
+
             // Status can be: magnet_error, magnet_conversion, waiting_files_selection, queued, downloading, downloaded, error, virus, compressing, uploading, dead
             match info.status.as_str() {
                 "downloaded" => return Ok(info),
                 "error" | "magnet_error" | "virus" | "dead" => {
                     return Err(format!("Torrent failed with status: {}", info.status).into());
                 }
-                _ => {
-                    // Still processing
-                    if start.elapsed().as_secs() > max_wait_secs {
-                        return Err("Timeout waiting for torrent to be ready".into());
-                    }
-                    
-                    // Wait 2 seconds before checking again
-                    sleep(Duration::from_secs(2)).await;
+                "waiting_files_selection" if !files_selected => {
+                    let selected = self.select_matching_files(torrent_id, &info.files, selection).await?;
+                    files_selected = true;
+                    println!("Selected {}/{} files", selected, info.files.len());
                 }
+                _ => {}
             }
+
+            // Still processing
+            if start.elapsed().as_secs() > max_wait_secs {
+                return Err(format!(
+                    "Timeout waiting for torrent to be ready (status: {}, {:.0}%)",
+                    info.status, info.progress
+                ).into());
+            }
+
+            // Poll less often once Real-Debrid is actually downloading an uncached torrent
+            let delay = if info.status == "downloading" { 5 } else { 2 };
+            sleep(Duration::from_secs(delay)).await;
         }
     }
-    
+
     /// Unrestrict a link to get the direct download URL
     pub async fn unrestrict_link(&self, link: &str) -> Result<UnrestrictLinkResponse, Box<dyn std::error::Error>> {
         let response = self.client
@@ -160,23 +315,36 @@ impl RealDebridClient {
     /// Process a magnet link and return download links
     /// This is the main function that does everything: add, select, wait, unrestrict
     pub async fn process_magnet(&self, magnet_link: &str) -> Result<Vec<DownloadLink>, Box<dyn std::error::Error>> {
+        self.process_magnet_with_progress(magnet_link, &FileSelection::default(), 300, &|_| {}).await
+    }
+
+    /// Same as `process_magnet`, but only selects files matching `selection`,
+    /// waits up to `max_wait_secs` for uncached torrents and reports
+    /// Real-Debrid's progress through `on_progress`
+    pub async fn process_magnet_with_progress(
+        &self,
+        magnet_link: &str,
+        selection: &FileSelection,
+        max_wait_secs: u64,
+        on_progress: &(dyn Fn(&TorrentInfo) + Send + Sync),
+    ) -> Result<Vec<DownloadLink>, Box<dyn std::error::Error>> {
         println!("Processing magnet link...");
-        
+
         // Step 1: Add magnet to Real-Debrid
         let add_result = self.add_magnet(magnet_link).await?;
         println!("Added magnet with ID: {}", add_result.id);
-        
-        // Step 2: Select all files
-        self.select_files(&add_result.id).await?;
-        println!("Selected all files");
-        
-        // Step 3: Wait for torrent to be ready (5 minute timeout)
+
+        // Step 2: Wait for torrent to be ready, selecting files once RD has the metadata.
         // If cached, this should be instant. If not, Real-Debrid will download it.
         println!("Waiting for torrent to be ready...");
-        let info = self.wait_for_ready(&add_result.id, 300).await?;
+        let info = self.wait_for_ready_with_progress(&add_result.id, selection, max_wait_secs, on_progress).await?;
         println!("Torrent ready! Found {} files", info.links.len());
-        
-        // Step 4: Unrestrict all download links
+
+        if info.links.is_empty() {
+            return Err("Torrent finished but Real-Debrid returned no links".into());
+        }
+
+        // Step 3: Unrestrict all download links
         let mut downloads = Vec::new();
         for (idx, link) in info.links.iter().enumerate() {
             match self.unrestrict_link(link).await {
@@ -193,7 +361,7 @@ impl RealDebridClient {
                 }
             }
         }
-        
+
         Ok(downloads)
     }
 
@@ -223,6 +391,55 @@ impl RealDebridClient {
         }
     }
 
+    /// Get premium status, fidelity points and remaining traffic for the account
+    pub async fn account_info(&self) -> Result<AccountInfo, Box<dyn std::error::Error>> {
+        let response = self.client
+            .get("https://api.real-debrid.com/rest/1.0/user")
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(format!("Real-Debrid user error: {}", error_text).into());
+        }
+
+        let user: UserResponse = response.json().await?;
+
+        let response = self.client
+            .get("https://api.real-debrid.com/rest/1.0/traffic")
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(format!("Real-Debrid traffic error: {}", error_text).into());
+        }
+
+        let traffic: std::collections::HashMap<String, TrafficEntry> = response.json().await?;
+        let mut traffic: Vec<HostTraffic> = traffic.into_iter()
+            .map(|(host, t)| HostTraffic {
+                host,
+                left: t.left,
+                bytes: t.bytes,
+                limit: t.limit,
+                traffic_type: t.traffic_type,
+            })
+            .collect();
+        traffic.sort_by(|a, b| a.host.cmp(&b.host));
+
+        Ok(AccountInfo {
+            username: user.username,
+            account_type: user.account_type,
+            premium_seconds_left: user.premium,
+            premium_days_left: user.premium / 86400,
+            expiration: user.expiration,
+            fidelity_points: user.points,
+            traffic,
+        })
+    }
+
     /// Get list of supported file hosters from Real-Debrid
     pub async fn get_supported_hosts(&self) -> Result<HashSet<String>, Box<dyn std::error::Error>> {
         let response = self.client
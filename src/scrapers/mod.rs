@@ -7,13 +7,29 @@ pub mod steamrip;
 pub mod registry;
 pub mod utils;
 
-/// Type of download link
-#[derive(Debug, Clone, PartialEq)]
+/// Type of download link, stored in `games.link_type` as "magnet" or "ddl"
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, serde::Deserialize, sqlx::Type, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
 pub enum LinkType {
+    #[default]
     Magnet,     // BitTorrent magnet link
+    #[serde(rename = "ddl")]
+    #[sqlx(rename = "ddl")]
     DirectDL,   // Direct download link for Real-Debrid
 }
 
+impl LinkType {
+    /// Guess from the link itself, for games that didn't come from a scraper
+    pub fn detect(link: &str) -> Self {
+        if link.starts_with("magnet:") {
+            LinkType::Magnet
+        } else {
+            LinkType::DirectDL
+        }
+    }
+}
+
 /// A scraped game with all metadata
 #[derive(Debug, Clone)]
 pub struct ScrapedGame {
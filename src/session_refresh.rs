@@ -0,0 +1,64 @@
+//! Remembers when each session's expiry was last pushed back, so sliding
+//! expiry costs one database write per session per interval instead of a
+//! query on every API request.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How often an active session's expiry is pushed back
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Stale entries are swept once the table grows past this many sessions
+const PRUNE_THRESHOLD: usize = 10_000;
+
+#[derive(Default)]
+pub struct SessionRefresher {
+    refreshed: Mutex<HashMap<String, Instant>>,
+}
+
+impl SessionRefresher {
+    /// Whether the session's expiry should be pushed back now. Returns true at
+    /// most once per interval for each token (and on its first request after
+    /// a restart); the caller is expected to do the refresh.
+    pub fn due(&self, token: &str) -> bool {
+        self.due_at(token, Instant::now())
+    }
+
+    fn due_at(&self, token: &str, now: Instant) -> bool {
+        let mut refreshed = self.refreshed.lock().unwrap();
+
+        if refreshed.len() > PRUNE_THRESHOLD {
+            refreshed.retain(|_, last| now.duration_since(*last) < REFRESH_INTERVAL);
+        }
+
+        match refreshed.get(token) {
+            Some(last) if now.duration_since(*last) < REFRESH_INTERVAL => false,
+            _ => {
+                refreshed.insert(token.to_string(), now);
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_due_once_per_interval() {
+        let refresher = SessionRefresher::default();
+        let now = Instant::now();
+
+        assert!(refresher.due_at("a", now));
+        assert!(!refresher.due_at("a", now + Duration::from_secs(1)));
+        assert!(!refresher.due_at("a", now + REFRESH_INTERVAL - Duration::from_millis(1)));
+        // Tokens are tracked separately
+        assert!(refresher.due_at("b", now));
+        // The interval restarts from the last refresh
+        let next = now + REFRESH_INTERVAL;
+        assert!(refresher.due_at("a", next));
+        assert!(!refresher.due_at("a", next + Duration::from_secs(60)));
+    }
+}
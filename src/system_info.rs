@@ -7,10 +7,165 @@ pub struct SystemInfo {
     pub ram_available_gb: f64,
     pub temp_space_gb: f64,
     pub cpu_cores: i64,
+    /// Display adapters, primary first; empty if none could be detected
+    #[serde(default)]
+    pub gpus: Vec<GpuInfo>,
+    /// Every mounted local drive with its free and total space
+    #[serde(default)]
+    pub drives: Vec<DriveInfo>,
     pub antivirus_active: bool,
     pub missing_dlls: Vec<String>,
+    /// Runtimes nearly every repack needs that aren't installed
     pub missing_dependencies: Vec<String>,
+    /// What's installed, for comparing against a specific game's requirements
+    #[serde(default)]
+    pub runtimes: InstalledRuntimes,
     pub overall_status: SystemStatus,
+    /// The limits `overall_status` and the issue list were judged against
+    #[serde(skip)]
+    pub thresholds: Thresholds,
+}
+
+/// When low RAM and temp space start producing warnings. Below half of
+/// either value the system is reported as critical.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Thresholds {
+    pub min_available_ram_gb: f64,
+    pub min_free_temp_gb: f64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            min_available_ram_gb: 8.0,
+            min_free_temp_gb: 20.0,
+        }
+    }
+}
+
+impl Thresholds {
+    pub fn critical_ram_gb(&self) -> f64 {
+        self.min_available_ram_gb / 2.0
+    }
+
+    pub fn critical_temp_gb(&self) -> f64 {
+        self.min_free_temp_gb / 2.0
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InstalledRuntimes {
+    /// Visual C++ redistributable families, e.g. "2010", "2015-2022"
+    pub vcredist: Vec<String>,
+    /// .NET Framework and .NET runtime versions, e.g. "3.5", "4.8", "8.0"
+    pub dotnet: Vec<String>,
+    /// Highest Direct3D feature level of the primary GPU, e.g. "12_1"
+    pub directx_feature_level: Option<String>,
+    /// The legacy D3DX runtime (d3dx9_43.dll) from the DirectX End-User Runtime
+    pub directx_legacy: bool,
+}
+
+impl InstalledRuntimes {
+    /// Whether a requirement like "2015-2019" or "2010, 2013" is met.
+    /// 2015 through 2022 share one binary-compatible runtime.
+    pub fn has_vcredist(&self, required: &str) -> bool {
+        let years = find_years(required);
+        if years.is_empty() {
+            return !self.vcredist.is_empty();
+        }
+        years
+            .iter()
+            .all(|year| self.vcredist.iter().any(|v| v == vcredist_family(*year)))
+    }
+
+    /// Whether a requirement like "4.7.2", "3.5" or ".NET 6" is met.
+    /// .NET Framework 4.x updates in place, so a newer 4.x satisfies an older one.
+    pub fn has_dotnet(&self, required: &str) -> bool {
+        let Some(wanted) = find_version(required) else {
+            return !self.dotnet.is_empty();
+        };
+        self.dotnet.iter().filter_map(|v| find_version(v)).any(|have| match wanted.first() {
+            Some(4) => have.first() == Some(&4) && have >= wanted,
+            // 3.5 includes 2.0 and 3.0
+            Some(2) | Some(3) => have == [3, 5],
+            _ => have.first() == wanted.first(),
+        })
+    }
+
+    /// Whether a requirement like "DirectX 11" or "9.0c" is met. DirectX 9
+    /// games need the legacy D3DX runtime; newer ones need a capable GPU.
+    pub fn has_directx(&self, required: &str) -> bool {
+        let Some(major) = find_version(required).and_then(|v| v.first().copied()) else {
+            return true;
+        };
+        if major <= 9 {
+            return self.directx_legacy;
+        }
+        match self.directx_feature_level.as_deref().and_then(|l| l.split('_').next()?.parse::<u32>().ok()) {
+            Some(level) => level >= major,
+            // Feature level couldn't be queried; don't report a false negative
+            None => true,
+        }
+    }
+}
+
+fn vcredist_family(year: u32) -> &'static str {
+    match year {
+        2005 => "2005",
+        2008 => "2008",
+        2010 => "2010",
+        2012 => "2012",
+        2013 => "2013",
+        _ => "2015-2022",
+    }
+}
+
+/// Four-digit years from 2005 on, e.g. [2015, 2019] from "2015-2019"
+fn find_years(text: &str) -> Vec<u32> {
+    let re = regex::Regex::new(r"\b(20[0-2]\d)\b").unwrap();
+    re.captures_iter(text)
+        .filter_map(|c| c[1].parse().ok())
+        .filter(|year| *year >= 2005)
+        .collect()
+}
+
+/// First dotted version number in `text`, e.g. [4, 7, 2]
+fn find_version(text: &str) -> Option<Vec<u32>> {
+    let re = regex::Regex::new(r"\d+(?:\.\d+)*").unwrap();
+    let m = re.find(text)?;
+    m.as_str().split('.').map(|p| p.parse().ok()).collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpuInfo {
+    pub name: String,
+    /// Dedicated video memory; None when the driver doesn't report it
+    pub vram_gb: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriveInfo {
+    /// Drive root on Windows (`D:\`), mount point elsewhere
+    pub mount_point: String,
+    pub total_gb: f64,
+    pub free_gb: f64,
+}
+
+/// The drive holding `path`: the one with the longest matching mount point
+pub fn drive_for_path<'a>(drives: &'a [DriveInfo], path: &Path) -> Option<&'a DriveInfo> {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    drives
+        .iter()
+        .filter(|d| {
+            if cfg!(target_os = "windows") {
+                // canonicalize() returns verbatim paths like \\?\C:\games
+                let path = path.to_string_lossy().to_lowercase();
+                path.trim_start_matches(r"\\?\").starts_with(&d.mount_point.to_lowercase())
+            } else {
+                path.starts_with(&d.mount_point)
+            }
+        })
+        .max_by_key(|d| d.mount_point.len())
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,16 +178,26 @@ pub enum SystemStatus {
 
 impl SystemInfo {
     pub async fn gather() -> Self {
+        Self::gather_with(Thresholds::default()).await
+    }
+
+    /// Gather with operator-configured warning thresholds
+    pub async fn gather_with(thresholds: Thresholds) -> Self {
         let ram_total_gb = get_total_ram_gb();
         let ram_available_gb = get_available_ram_gb();
         let temp_space_gb = get_temp_space_gb();
         let cpu_cores = get_cpu_cores();
+        // lspci and nvidia-smi can take a while, keep them off the async workers
+        let gpus = tokio::task::spawn_blocking(detect_gpus).await.unwrap_or_default();
+        let drives = list_drives();
         let antivirus_active = is_antivirus_active();
         let missing_dlls = check_missing_dlls();
-        let missing_dependencies = check_missing_dependencies();
+        let runtimes = detect_runtimes();
+        let missing_dependencies = check_missing_dependencies(&runtimes);
 
         // Determine overall status
         let overall_status = Self::calculate_status(
+            &thresholds,
             ram_available_gb,
             temp_space_gb,
             &missing_dlls,
@@ -44,21 +209,26 @@ impl SystemInfo {
             ram_available_gb,
             temp_space_gb,
             cpu_cores,
+            gpus,
+            drives,
             antivirus_active,
             missing_dlls,
             missing_dependencies,
+            runtimes,
             overall_status,
+            thresholds,
         }
     }
 
     fn calculate_status(
+        thresholds: &Thresholds,
         ram_available_gb: f64,
         temp_space_gb: f64,
         missing_dlls: &[String],
         missing_dependencies: &[String],
     ) -> SystemStatus {
-        // Critical: Less than 4GB RAM or less than 10GB temp space
-        if ram_available_gb < 4.0 || temp_space_gb < 10.0 {
+        // Critical: under half the warning thresholds (4GB RAM / 10GB temp by default)
+        if ram_available_gb < thresholds.critical_ram_gb() || temp_space_gb < thresholds.critical_temp_gb() {
             return SystemStatus::Critical;
         }
 
@@ -67,8 +237,8 @@ impl SystemInfo {
             return SystemStatus::Warning;
         }
 
-        // Warning: Low RAM (4-8GB) or low space (10-20GB)
-        if ram_available_gb < 8.0 || temp_space_gb < 20.0 {
+        // Warning: Low RAM (4-8GB) or low space (10-20GB) by default
+        if ram_available_gb < thresholds.min_available_ram_gb || temp_space_gb < thresholds.min_free_temp_gb {
             return SystemStatus::Warning;
         }
 
@@ -78,16 +248,17 @@ impl SystemInfo {
     pub fn get_issues(&self) -> Vec<String> {
         let mut issues = Vec::new();
 
-        if self.ram_available_gb < 4.0 {
-            issues.push(format!("⚠️ Critical: Only {:.1}GB RAM available (need 4GB minimum)", self.ram_available_gb));
-        } else if self.ram_available_gb < 8.0 {
-            issues.push(format!("⚠️ Warning: Only {:.1}GB RAM available (8GB recommended)", self.ram_available_gb));
+        let t = &self.thresholds;
+        if self.ram_available_gb < t.critical_ram_gb() {
+            issues.push(format!("⚠️ Critical: Only {:.1}GB RAM available (need {}GB minimum)", self.ram_available_gb, t.critical_ram_gb()));
+        } else if self.ram_available_gb < t.min_available_ram_gb {
+            issues.push(format!("⚠️ Warning: Only {:.1}GB RAM available ({}GB recommended)", self.ram_available_gb, t.min_available_ram_gb));
         }
 
-        if self.temp_space_gb < 10.0 {
-            issues.push(format!("⚠️ Critical: Only {:.1}GB temp space (need 10GB minimum)", self.temp_space_gb));
-        } else if self.temp_space_gb < 20.0 {
-            issues.push(format!("⚠️ Warning: Only {:.1}GB temp space (20GB recommended)", self.temp_space_gb));
+        if self.temp_space_gb < t.critical_temp_gb() {
+            issues.push(format!("⚠️ Critical: Only {:.1}GB temp space (need {}GB minimum)", self.temp_space_gb, t.critical_temp_gb()));
+        } else if self.temp_space_gb < t.min_free_temp_gb {
+            issues.push(format!("⚠️ Warning: Only {:.1}GB temp space ({}GB recommended)", self.temp_space_gb, t.min_free_temp_gb));
         }
 
         if self.antivirus_active {
@@ -108,11 +279,11 @@ impl SystemInfo {
     pub fn get_recommendations(&self) -> Vec<String> {
         let mut recommendations = Vec::new();
 
-        if self.ram_available_gb < 8.0 {
+        if self.ram_available_gb < self.thresholds.min_available_ram_gb {
             recommendations.push("Close unnecessary programs to free up RAM".to_string());
         }
 
-        if self.temp_space_gb < 20.0 {
+        if self.temp_space_gb < self.thresholds.min_free_temp_gb {
             recommendations.push("Free up disk space on your temp drive".to_string());
         }
 
@@ -135,7 +306,7 @@ impl SystemInfo {
 
 // ─── RAM Detection ───
 
-fn get_total_ram_gb() -> f64 {
+pub fn get_total_ram_gb() -> f64 {
     #[cfg(target_os = "windows")]
     {
         use std::mem;
@@ -175,7 +346,7 @@ fn get_total_ram_gb() -> f64 {
     0.0 // Unknown
 }
 
-fn get_available_ram_gb() -> f64 {
+pub fn get_available_ram_gb() -> f64 {
     #[cfg(target_os = "windows")]
     {
         use std::mem;
@@ -212,23 +383,19 @@ fn get_available_ram_gb() -> f64 {
 // ─── Disk Space Detection ───
 
 fn get_temp_space_gb() -> f64 {
-    let temp_dir = std::env::temp_dir();
+    get_free_space_gb(&std::env::temp_dir()).unwrap_or(0.0)
+}
 
+/// Free space in GB on the volume containing `path`, or None if it can't be determined
+pub fn get_free_space_gb(path: &std::path::Path) -> Option<f64> {
     #[cfg(target_os = "windows")]
     {
         use std::ffi::OsStr;
         use std::os::windows::ffi::OsStrExt;
         use winapi::um::fileapi::GetDiskFreeSpaceExW;
 
-        // Get drive root (e.g., C:\ from C:\Users\...)
-        let temp_str = temp_dir.to_string_lossy();
-        let drive_root = if temp_str.len() >= 3 && temp_str.chars().nth(1) == Some(':') {
-            format!("{}:\\", &temp_str[0..1])
-        } else {
-            return 0.0;
-        };
-
-        let wide: Vec<u16> = OsStr::new(&drive_root)
+        // GetDiskFreeSpaceExW accepts any directory on the volume
+        let wide: Vec<u16> = OsStr::new(path)
             .encode_wide()
             .chain(Some(0))
             .collect();
@@ -245,23 +412,141 @@ fn get_temp_space_gb() -> f64 {
                 &mut total_free_bytes as *mut u64 as *mut _,
             ) != 0
             {
-                return free_bytes as f64 / 1024.0 / 1024.0 / 1024.0; // Bytes to GB
+                return Some(free_bytes as f64 / 1024.0 / 1024.0 / 1024.0); // Bytes to GB
             }
         }
+
+        None
     }
 
     #[cfg(not(target_os = "windows"))]
     {
-        // Linux fallback using statvfs
-        use std::os::unix::fs::MetadataExt;
-        if let Ok(metadata) = std::fs::metadata(&temp_dir) {
-            // Simplified - just return a placeholder
-            // Real implementation would use statvfs
-            return 50.0;
+        // POSIX df: second line, fourth column is available 1K blocks
+        let output = std::process::Command::new("df")
+            .args(["-Pk"])
+            .arg(path)
+            .output()
+            .ok()?;
+
+        if !output.status.success() {
+            return None;
         }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let kb = stdout
+            .lines()
+            .nth(1)?
+            .split_whitespace()
+            .nth(3)?
+            .parse::<u64>()
+            .ok()?;
+
+        Some(kb as f64 / 1024.0 / 1024.0) // KB to GB
     }
+}
 
-    0.0 // Unknown
+/// Local fixed and removable drives with their space
+#[cfg(target_os = "windows")]
+fn list_drives() -> Vec<DriveInfo> {
+    use std::ffi::OsStr;
+    use std::os::windows::ffi::OsStrExt;
+    use winapi::um::fileapi::{GetDiskFreeSpaceExW, GetDriveTypeW, GetLogicalDrives};
+    use winapi::um::winbase::{DRIVE_FIXED, DRIVE_REMOVABLE};
+
+    let mask = unsafe { GetLogicalDrives() };
+    let mut drives = Vec::new();
+
+    for i in 0..26u32 {
+        if mask & (1 << i) == 0 {
+            continue;
+        }
+        let root = format!("{}:\\", (b'A' + i as u8) as char);
+        let wide: Vec<u16> = OsStr::new(&root).encode_wide().chain(Some(0)).collect();
+
+        unsafe {
+            // Skip network shares, optical drives and RAM disks
+            let kind = GetDriveTypeW(wide.as_ptr());
+            if kind != DRIVE_FIXED && kind != DRIVE_REMOVABLE {
+                continue;
+            }
+
+            let mut free_bytes: u64 = 0;
+            let mut total_bytes: u64 = 0;
+            let mut total_free_bytes: u64 = 0;
+            if GetDiskFreeSpaceExW(
+                wide.as_ptr(),
+                &mut free_bytes as *mut u64 as *mut _,
+                &mut total_bytes as *mut u64 as *mut _,
+                &mut total_free_bytes as *mut u64 as *mut _,
+            ) != 0
+            {
+                drives.push(DriveInfo {
+                    mount_point: root,
+                    total_gb: total_bytes as f64 / 1024.0 / 1024.0 / 1024.0,
+                    free_gb: free_bytes as f64 / 1024.0 / 1024.0 / 1024.0,
+                });
+            }
+        }
+    }
+
+    drives
+}
+
+/// Real filesystems from `df`, without tmpfs and kernel pseudo-mounts
+#[cfg(not(target_os = "windows"))]
+fn list_drives() -> Vec<DriveInfo> {
+    let Ok(output) = std::process::Command::new("df").arg("-Pk").output() else {
+        return Vec::new();
+    };
+
+    parse_df_output(&String::from_utf8_lossy(&output.stdout))
+        .into_iter()
+        // Containers bind-mount single files such as /etc/hosts
+        .filter(|drive| Path::new(&drive.mount_point).is_dir())
+        .collect()
+}
+
+/// Drives from `df -Pk` output, skipping the header, tmpfs and kernel pseudo-mounts
+#[cfg(not(target_os = "windows"))]
+fn parse_df_output(output: &str) -> Vec<DriveInfo> {
+    let pseudo = ["tmpfs", "devtmpfs", "udev", "none", "shm", "proc", "sysfs"];
+    output
+        .lines()
+        .skip(1)
+        .filter_map(parse_df_line)
+        .filter(|(device, drive)| {
+            !pseudo.contains(device)
+                && !["/proc", "/sys", "/dev", "/run"]
+                    .iter()
+                    .any(|p| drive.mount_point == *p || drive.mount_point.starts_with(&format!("{}/", p)))
+        })
+        .map(|(_, drive)| drive)
+        .collect()
+}
+
+/// One `df -Pk` row: device, 1K-blocks, used, available, capacity, mount point.
+/// The mount point is the rest of the line, so it may contain spaces.
+#[cfg(not(target_os = "windows"))]
+fn parse_df_line(line: &str) -> Option<(&str, DriveInfo)> {
+    let mut fields = line.split_whitespace();
+    let device = fields.next()?;
+    let total_kb: u64 = fields.next()?.parse().ok()?;
+    let _used = fields.next()?;
+    let free_kb: u64 = fields.next()?.parse().ok()?;
+    let _capacity = fields.next()?;
+    let mount_point = fields.collect::<Vec<_>>().join(" ");
+    if mount_point.is_empty() || total_kb == 0 {
+        return None;
+    }
+
+    Some((
+        device,
+        DriveInfo {
+            mount_point,
+            total_gb: total_kb as f64 / 1024.0 / 1024.0,
+            free_gb: free_kb as f64 / 1024.0 / 1024.0,
+        },
+    ))
 }
 
 // ─── CPU Detection ───
@@ -270,6 +555,179 @@ fn get_cpu_cores() -> i64 {
     num_cpus::get() as i64
 }
 
+// ─── GPU Detection ───
+
+/// Hardware adapters from DXGI, skipping the Microsoft Basic Render Driver
+#[cfg(target_os = "windows")]
+fn detect_gpus() -> Vec<GpuInfo> {
+    use winapi::shared::dxgi::{
+        CreateDXGIFactory1, IDXGIAdapter1, IDXGIFactory1, DXGI_ADAPTER_DESC1, DXGI_ADAPTER_FLAG_SOFTWARE,
+    };
+    use winapi::shared::winerror::SUCCEEDED;
+    use winapi::Interface;
+
+    let mut gpus = Vec::new();
+
+    unsafe {
+        let mut factory: *mut IDXGIFactory1 = std::ptr::null_mut();
+        if !SUCCEEDED(CreateDXGIFactory1(
+            &IDXGIFactory1::uuidof(),
+            &mut factory as *mut *mut IDXGIFactory1 as *mut *mut _,
+        )) {
+            return gpus;
+        }
+
+        // EnumAdapters1 fails with DXGI_ERROR_NOT_FOUND past the last adapter
+        let mut index = 0;
+        loop {
+            let mut adapter: *mut IDXGIAdapter1 = std::ptr::null_mut();
+            if !SUCCEEDED((*factory).EnumAdapters1(index, &mut adapter)) {
+                break;
+            }
+
+            let mut desc: DXGI_ADAPTER_DESC1 = std::mem::zeroed();
+            if SUCCEEDED((*adapter).GetDesc1(&mut desc)) && desc.Flags & DXGI_ADAPTER_FLAG_SOFTWARE == 0 {
+                let len = desc.Description.iter().position(|&c| c == 0).unwrap_or(desc.Description.len());
+                gpus.push(GpuInfo {
+                    name: String::from_utf16_lossy(&desc.Description[..len]).trim().to_string(),
+                    vram_gb: Some(desc.DedicatedVideoMemory as f64 / 1024.0 / 1024.0 / 1024.0), // Bytes to GB
+                });
+            }
+
+            (*adapter).Release();
+            index += 1;
+        }
+
+        (*factory).Release();
+    }
+
+    gpus
+}
+
+/// DRM cards from sysfs, named via lspci, with VRAM from amdgpu's sysfs
+/// counters or nvidia-smi. Falls back to plain lspci when sysfs has no cards
+/// (e.g. inside a container).
+#[cfg(not(target_os = "windows"))]
+fn detect_gpus() -> Vec<GpuInfo> {
+    const NVIDIA_VENDOR: &str = "0x10de";
+
+    let mut cards: Vec<(String, GpuInfo, String)> = Vec::new(); // (pci slot, info, vendor id)
+
+    if let Ok(entries) = std::fs::read_dir("/sys/class/drm") {
+        let mut names: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            // card0, card1, ... but not connectors like card0-HDMI-A-1
+            .filter(|n| n.strip_prefix("card").is_some_and(|rest| rest.chars().all(|c| c.is_ascii_digit())))
+            .collect();
+        names.sort();
+
+        for name in names {
+            let device = Path::new("/sys/class/drm").join(&name).join("device");
+            let Some(slot) = std::fs::canonicalize(&device)
+                .ok()
+                .and_then(|p| p.file_name().map(|f| f.to_string_lossy().to_string()))
+            else {
+                continue;
+            };
+            if cards.iter().any(|(s, _, _)| *s == slot) {
+                continue;
+            }
+
+            let read = |file: &str| std::fs::read_to_string(device.join(file)).ok().map(|v| v.trim().to_string());
+            let vendor = read("vendor").unwrap_or_default();
+            let name = lspci_name(&slot).unwrap_or_else(|| {
+                format!("PCI device {}:{}", vendor, read("device").unwrap_or_default())
+            });
+            let vram_gb = read("mem_info_vram_total")
+                .and_then(|v| v.parse::<u64>().ok())
+                .map(|bytes| bytes as f64 / 1024.0 / 1024.0 / 1024.0); // Bytes to GB
+
+            cards.push((slot, GpuInfo { name, vram_gb }, vendor));
+        }
+    }
+
+    // The proprietary NVIDIA driver doesn't expose VRAM in sysfs
+    if cards.iter().any(|(_, gpu, vendor)| vendor == NVIDIA_VENDOR && gpu.vram_gb.is_none()) {
+        let mut nvidia = nvidia_smi_gpus().into_iter();
+        for (_, gpu, _) in cards.iter_mut().filter(|(_, _, vendor)| vendor == NVIDIA_VENDOR) {
+            if let Some(reported) = nvidia.next() {
+                *gpu = reported;
+            }
+        }
+    }
+
+    if cards.is_empty() {
+        return lspci_display_devices();
+    }
+
+    cards.into_iter().map(|(_, gpu, _)| gpu).collect()
+}
+
+/// "Vendor Device" for a PCI slot from `lspci -mm`
+#[cfg(not(target_os = "windows"))]
+fn lspci_name(slot: &str) -> Option<String> {
+    let output = std::process::Command::new("lspci").args(["-mm", "-s", slot]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let line = String::from_utf8_lossy(&output.stdout).lines().next()?.to_string();
+    parse_lspci_line(&line).map(|(_, name)| name)
+}
+
+/// Every VGA/3D/display controller `lspci -mm` lists, without VRAM
+#[cfg(not(target_os = "windows"))]
+fn lspci_display_devices() -> Vec<GpuInfo> {
+    let Ok(output) = std::process::Command::new("lspci").arg("-mm").output() else {
+        return Vec::new();
+    };
+    if !output.status.success() {
+        return Vec::new();
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(parse_lspci_line)
+        .filter(|(class, _)| {
+            class.contains("VGA") || class.contains("3D controller") || class.contains("Display controller")
+        })
+        .map(|(_, name)| GpuInfo { name, vram_gb: None })
+        .collect()
+}
+
+/// Device class and "Vendor Device" from a line like
+/// `01:00.0 "VGA compatible controller" "NVIDIA Corporation" "GA104 [GeForce RTX 3070]" ...`
+#[cfg(not(target_os = "windows"))]
+fn parse_lspci_line(line: &str) -> Option<(String, String)> {
+    let fields: Vec<&str> = line.split('"').skip(1).step_by(2).collect();
+    let (class, vendor, device) = (fields.first()?, fields.get(1)?, fields.get(2)?);
+    Some((class.to_string(), format!("{} {}", vendor, device)))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn nvidia_smi_gpus() -> Vec<GpuInfo> {
+    let Ok(output) = std::process::Command::new("nvidia-smi")
+        .args(["--query-gpu=name,memory.total", "--format=csv,noheader,nounits"])
+        .output()
+    else {
+        return Vec::new();
+    };
+    if !output.status.success() {
+        return Vec::new();
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (name, mib) = line.rsplit_once(',')?;
+            Some(GpuInfo {
+                name: name.trim().to_string(),
+                vram_gb: mib.trim().parse::<f64>().ok().map(|mib| mib / 1024.0), // MiB to GB
+            })
+        })
+        .collect()
+}
+
 // ─── Antivirus Detection ───
 
 fn is_antivirus_active() -> bool {
@@ -333,88 +791,343 @@ fn check_missing_dlls() -> Vec<String> {
 
 // ─── Dependency Detection ───
 
-fn check_missing_dependencies() -> Vec<String> {
+fn check_missing_dependencies(runtimes: &InstalledRuntimes) -> Vec<String> {
     let mut missing = Vec::new();
 
     #[cfg(target_os = "windows")]
     {
-        // Check DirectX
-        if !is_directx_installed() {
+        if !runtimes.directx_legacy {
             missing.push("DirectX Runtime".to_string());
         }
-
-        // Check .NET Framework
-        if !is_dotnet_installed() {
+        if !runtimes.has_dotnet("4.8") {
             missing.push(".NET Framework 4.8".to_string());
         }
-
-        // Check Visual C++ Redistributables
-        let vc_redist_missing = check_vcredist();
-        missing.extend(vc_redist_missing);
+        // Older redistributables only matter when a game asks for them
+        if !runtimes.has_vcredist("2015-2022") {
+            missing.push("Visual C++ 2015-2022 Redistributable".to_string());
+        }
     }
+    #[cfg(not(target_os = "windows"))]
+    let _ = runtimes;
 
     missing
 }
 
 #[cfg(target_os = "windows")]
-fn is_directx_installed() -> bool {
-    // Check for d3dx9_43.dll (DirectX 9.0c)
-    let dx_paths = [
-        "C:\\Windows\\System32\\d3dx9_43.dll",
-        "C:\\Windows\\SysWOW64\\d3dx9_43.dll",
-    ];
+fn detect_runtimes() -> InstalledRuntimes {
+    let mut dotnet = dotnet_framework_versions();
+    dotnet.extend(dotnet_core_versions());
+    dotnet.sort();
+    dotnet.dedup();
+
+    InstalledRuntimes {
+        vcredist: installed_vcredists(),
+        dotnet,
+        directx_feature_level: d3d_feature_level(),
+        directx_legacy: [
+            "C:\\Windows\\System32\\d3dx9_43.dll",
+            "C:\\Windows\\SysWOW64\\d3dx9_43.dll",
+        ]
+        .iter()
+        .any(|path| Path::new(path).exists()),
+    }
+}
 
-    dx_paths.iter().any(|path| Path::new(path).exists())
+#[cfg(not(target_os = "windows"))]
+fn detect_runtimes() -> InstalledRuntimes {
+    InstalledRuntimes::default()
 }
 
+/// Value of a registry entry from `reg query <key> /v <name>`
 #[cfg(target_os = "windows")]
-fn is_dotnet_installed() -> bool {
-    // Check registry for .NET 4.8
-    if let Ok(output) = std::process::Command::new("reg")
-        .args(["query", r"HKLM\SOFTWARE\Microsoft\NET Framework Setup\NDP\v4\Full", "/v", "Release"])
+fn reg_value(key: &str, name: &str) -> Option<String> {
+    let output = std::process::Command::new("reg")
+        .args(["query", key, "/v", name])
         .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout.lines().find(|l| l.trim_start().starts_with(name))?;
+    Some(line.split_whitespace().last()?.to_string())
+}
+
+/// Registry values are printed as hex ("0x80ff4") for REG_DWORD
+#[cfg(target_os = "windows")]
+fn parse_dword(value: &str) -> Option<u32> {
+    match value.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// .NET Framework 3.5 and the installed 4.x release
+#[cfg(target_os = "windows")]
+fn dotnet_framework_versions() -> Vec<String> {
+    let mut versions = Vec::new();
+
+    if reg_value(r"HKLM\SOFTWARE\Microsoft\NET Framework Setup\NDP\v3.5", "Install")
+        .and_then(|v| parse_dword(&v))
+        == Some(1)
     {
-        if output.status.success() {
-            let result = String::from_utf8_lossy(&output.stdout);
-            // .NET 4.8 = Release >= 528040
-            if let Some(line) = result.lines().find(|l| l.contains("Release")) {
-                if let Some(value_str) = line.split_whitespace().last() {
-                    if let Ok(release) = value_str.trim_start_matches("0x").parse::<i32>() {
-                        return release >= 528040;
-                    }
-                }
-            }
-        }
+        versions.push("3.5".to_string());
+    }
+
+    // Release numbers from Microsoft's version table; each is the lowest for that version
+    if let Some(release) = reg_value(r"HKLM\SOFTWARE\Microsoft\NET Framework Setup\NDP\v4\Full", "Release")
+        .and_then(|v| parse_dword(&v))
+    {
+        let version = [
+            (533320, "4.8.1"),
+            (528040, "4.8"),
+            (461808, "4.7.2"),
+            (461308, "4.7.1"),
+            (460798, "4.7"),
+            (394802, "4.6.2"),
+            (394254, "4.6.1"),
+            (393295, "4.6"),
+            (379893, "4.5.2"),
+            (378675, "4.5.1"),
+            (378389, "4.5"),
+        ]
+        .iter()
+        .find(|(min, _)| release >= *min)
+        .map(|(_, v)| v.to_string());
+        versions.extend(version);
     }
 
-    false
+    versions
 }
 
+/// Major.minor of each .NET (Core) runtime, from `dotnet --list-runtimes`
 #[cfg(target_os = "windows")]
-fn check_vcredist() -> Vec<String> {
-    let mut missing = Vec::new();
+fn dotnet_core_versions() -> Vec<String> {
+    let Ok(output) = std::process::Command::new("dotnet").arg("--list-runtimes").output() else {
+        return Vec::new();
+    };
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            // "Microsoft.NETCore.App 8.0.1 [C:\Program Files\dotnet\shared\...]"
+            let version = line.strip_prefix("Microsoft.NETCore.App ")?.split_whitespace().next()?;
+            let mut parts = version.split('.');
+            Some(format!("{}.{}", parts.next()?, parts.next()?))
+        })
+        .collect()
+}
 
-    // Common VC++ Redistributables needed for games
-    let required_versions = [
-        ("2015-2022", r"SOFTWARE\Microsoft\VisualStudio\14.0\VC\Runtimes\x64"),
-        ("2013", r"SOFTWARE\Microsoft\VisualStudio\12.0\VC\Runtimes\x64"),
-        ("2012", r"SOFTWARE\Microsoft\VisualStudio\11.0\VC\Runtimes\x64"),
-        ("2010", r"SOFTWARE\Microsoft\VisualStudio\10.0\VC\VCRedist\x64"),
+/// Redistributable families listed under Programs and Features, either architecture
+#[cfg(target_os = "windows")]
+fn installed_vcredists() -> Vec<String> {
+    let keys = [
+        r"HKLM\SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall",
+        r"HKLM\SOFTWARE\WOW6432Node\Microsoft\Windows\CurrentVersion\Uninstall",
     ];
 
-    for (version, reg_path) in &required_versions {
-        let result = std::process::Command::new("reg")
-            .args(["query", &format!("HKLM\\{}", reg_path)])
-            .output();
+    let mut families = Vec::new();
+    for key in keys {
+        let Ok(output) = std::process::Command::new("reg")
+            .args(["query", key, "/s", "/v", "DisplayName"])
+            .output()
+        else {
+            continue;
+        };
 
-        if let Ok(output) = result {
-            if !output.status.success() {
-                missing.push(format!("Visual C++ {} Redistributable", version));
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            // "    DisplayName    REG_SZ    Microsoft Visual C++ 2015-2022 Redistributable (x64) - 14.38.33130"
+            let Some((_, name)) = line.split_once("REG_SZ") else {
+                continue;
+            };
+            if !name.contains("Visual C++") || !name.contains("Redistributable") {
+                continue;
             }
-        } else {
-            missing.push(format!("Visual C++ {} Redistributable", version));
+            families.extend(find_years(name).into_iter().map(|year| vcredist_family(year).to_string()));
         }
     }
 
-    missing
+    families.sort();
+    families.dedup();
+    families
+}
+
+/// Highest feature level a hardware D3D11 device can be created at.
+/// Runtimes before Windows 8 reject the newer levels, so the list is
+/// retried without them.
+#[cfg(target_os = "windows")]
+fn d3d_feature_level() -> Option<String> {
+    use std::ptr;
+    use winapi::um::d3d11::{D3D11CreateDevice, D3D11_SDK_VERSION};
+    use winapi::um::d3dcommon::*;
+
+    let levels = [
+        D3D_FEATURE_LEVEL_12_1,
+        D3D_FEATURE_LEVEL_12_0,
+        D3D_FEATURE_LEVEL_11_1,
+        D3D_FEATURE_LEVEL_11_0,
+        D3D_FEATURE_LEVEL_10_1,
+        D3D_FEATURE_LEVEL_10_0,
+        D3D_FEATURE_LEVEL_9_3,
+        D3D_FEATURE_LEVEL_9_2,
+        D3D_FEATURE_LEVEL_9_1,
+    ];
+
+    for start in [0, 2, 3] {
+        let candidates = &levels[start..];
+        let mut level: D3D_FEATURE_LEVEL = 0;
+        // With no device out-pointer this only reports the supported level
+        let hr = unsafe {
+            D3D11CreateDevice(
+                ptr::null_mut(),
+                D3D_DRIVER_TYPE_HARDWARE,
+                ptr::null_mut(),
+                0,
+                candidates.as_ptr(),
+                candidates.len() as u32,
+                D3D11_SDK_VERSION,
+                ptr::null_mut(),
+                &mut level,
+                ptr::null_mut(),
+            )
+        };
+        if hr >= 0 {
+            return Some(format!("{}_{}", level >> 12, (level >> 8) & 0xf));
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runtimes(vcredist: &[&str], dotnet: &[&str], feature_level: Option<&str>, legacy: bool) -> InstalledRuntimes {
+        InstalledRuntimes {
+            vcredist: vcredist.iter().map(|v| v.to_string()).collect(),
+            dotnet: dotnet.iter().map(|v| v.to_string()).collect(),
+            directx_feature_level: feature_level.map(str::to_string),
+            directx_legacy: legacy,
+        }
+    }
+
+    #[test]
+    fn test_find_years() {
+        assert_eq!(find_years("2015-2019"), [2015, 2019]);
+        assert_eq!(find_years("VC++ 2010, 2013 x64"), [2010, 2013]);
+        assert_eq!(find_years("2003 or 2005"), [2005]);
+        assert!(find_years("latest").is_empty());
+    }
+
+    #[test]
+    fn test_find_version() {
+        assert_eq!(find_version("4.7.2"), Some(vec![4, 7, 2]));
+        assert_eq!(find_version(".NET 6"), Some(vec![6]));
+        assert_eq!(find_version("DirectX 9.0c"), Some(vec![9, 0]));
+        assert_eq!(find_version("any"), None);
+    }
+
+    #[test]
+    fn test_has_vcredist() {
+        let installed = runtimes(&["2010", "2015-2022"], &[], None, false);
+        assert!(installed.has_vcredist("2010"));
+        // 2015 through 2022 are one runtime
+        assert!(installed.has_vcredist("2017"));
+        assert!(installed.has_vcredist("2015-2019"));
+        assert!(!installed.has_vcredist("2010, 2013"));
+        assert!(installed.has_vcredist("latest"));
+        assert!(!runtimes(&[], &[], None, false).has_vcredist("latest"));
+    }
+
+    #[test]
+    fn test_has_dotnet() {
+        let installed = runtimes(&[], &["3.5", "4.8", "8.0"], None, false);
+        // 4.x updates in place, and 3.5 covers 2.0 and 3.0
+        assert!(installed.has_dotnet("4.7.2"));
+        assert!(installed.has_dotnet("2.0"));
+        assert!(installed.has_dotnet(".NET 8"));
+        assert!(!installed.has_dotnet(".NET 6"));
+        assert!(!runtimes(&[], &["4.6.1"], None, false).has_dotnet("4.8"));
+        assert!(!runtimes(&[], &["4.8"], None, false).has_dotnet("3.5"));
+        assert!(!runtimes(&[], &[], None, false).has_dotnet(".NET"));
+    }
+
+    #[test]
+    fn test_has_directx() {
+        let installed = runtimes(&[], &[], Some("11_1"), false);
+        assert!(installed.has_directx("DirectX 11"));
+        assert!(!installed.has_directx("DirectX 12"));
+        assert!(!installed.has_directx("9.0c"));
+        assert!(runtimes(&[], &[], Some("11_1"), true).has_directx("9.0c"));
+        // Unknown feature level or version isn't reported as missing
+        assert!(runtimes(&[], &[], None, false).has_directx("DirectX 12"));
+        assert!(installed.has_directx("any"));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    const DF_OUTPUT: &str = "\
+Filesystem     1024-blocks      Used Available Capacity Mounted on
+/dev/nvme0n1p2   488245288 300000000 163439208      65% /
+tmpfs              8123456         0   8123456       0% /dev/shm
+/dev/sda1       1953514584 976757292 976757292      50% /mnt/games
+/dev/sdb1        976762584 104857600 871904984      11% /mnt/games/Big Disk
+proc                     0         0         0       -  /proc
+tmpfs              1624692      2048   1622644       1% /run/user/1000
+";
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_parse_df_line() {
+        let (device, drive) = parse_df_line("/dev/sdb1 976762584 104857600 871904984 11% /mnt/games/Big Disk").unwrap();
+        assert_eq!(device, "/dev/sdb1");
+        assert_eq!(drive.mount_point, "/mnt/games/Big Disk");
+        assert!((drive.total_gb - 931.51).abs() < 0.01);
+        assert!((drive.free_gb - 831.51).abs() < 0.01);
+
+        // Header, zero-size and truncated rows
+        assert!(parse_df_line("Filesystem 1024-blocks Used Available Capacity Mounted on").is_none());
+        assert!(parse_df_line("proc 0 0 0 - /proc").is_none());
+        assert!(parse_df_line("/dev/sda1 100 50").is_none());
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_parse_df_output() {
+        let mounts: Vec<String> = parse_df_output(DF_OUTPUT).into_iter().map(|d| d.mount_point).collect();
+        assert_eq!(mounts, ["/", "/mnt/games", "/mnt/games/Big Disk"]);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_drive_for_path() {
+        let drives = parse_df_output(DF_OUTPUT);
+        let mount = |path: &str| drive_for_path(&drives, Path::new(path)).map(|d| d.mount_point.as_str());
+
+        // The deepest mount containing the path wins
+        assert_eq!(mount("/mnt/games/Big Disk/Game"), Some("/mnt/games/Big Disk"));
+        assert_eq!(mount("/mnt/games/Other"), Some("/mnt/games"));
+        // Whole path components only: /mnt/games2 isn't under /mnt/games
+        assert_eq!(mount("/mnt/games2/Game"), Some("/"));
+        assert_eq!(drive_for_path(&[], Path::new("/mnt/games")).map(|d| d.mount_point.as_str()), None);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_parse_lspci_line() {
+        let line = r#"01:00.0 "VGA compatible controller" "NVIDIA Corporation" "GA104 [GeForce RTX 3070]" -ra1 "Micro-Star International Co., Ltd. [MSI]" "Device 3897""#;
+        assert_eq!(
+            parse_lspci_line(line),
+            Some(("VGA compatible controller".to_string(), "NVIDIA Corporation GA104 [GeForce RTX 3070]".to_string()))
+        );
+
+        let line = r#"00:02.0 "Display controller" "Intel Corporation" "Alder Lake-P GT2""#;
+        assert_eq!(
+            parse_lspci_line(line),
+            Some(("Display controller".to_string(), "Intel Corporation Alder Lake-P GT2".to_string()))
+        );
+
+        assert_eq!(parse_lspci_line(r#"00:1f.3 "Audio device" "Intel Corporation""#), None);
+        assert_eq!(parse_lspci_line(""), None);
+    }
 }
@@ -0,0 +1,234 @@
+//! On-disk cache for game thumbnails. Browsers load covers from
+//! `/api/thumbnails/:game_id` instead of hotlinking the source site, which
+//! is slow, sometimes blocks hotlinking, and sees every visitor's IP.
+//!
+//! Thumbnail URLs come from scraped pages and imports, so fetches are limited
+//! to http(s) on public addresses: the server must not be usable to reach
+//! localhost or the LAN it sits on.
+
+use sha2::{Digest, Sha256};
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::time::Duration;
+use url::{Host, Url};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Larger upstream responses are rejected rather than cached
+const MAX_THUMBNAIL_BYTES: usize = 5 * 1024 * 1024;
+
+/// Redirects are followed by hand so every hop gets the same address checks
+const MAX_REDIRECTS: usize = 5;
+
+pub struct ThumbnailCache {
+    dir: PathBuf,
+}
+
+impl ThumbnailCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Image bytes and content type for `url`, fetching it on first use
+    pub async fn get(&self, url: &str) -> Result<(Vec<u8>, &'static str), Box<dyn std::error::Error + Send + Sync>> {
+        let path = self.dir.join(cache_key(url));
+
+        if let Ok(bytes) = tokio::fs::read(&path).await {
+            if let Some(content_type) = sniff_content_type(&bytes) {
+                return Ok((bytes, content_type));
+            }
+        }
+
+        let bytes = self.fetch(url).await?;
+        let content_type = sniff_content_type(&bytes)
+            .ok_or("Upstream response is not a supported image")?;
+
+        // Write to a temp file first so a concurrent request never reads half an image
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let tmp = path.with_extension(format!("tmp-{}", uuid::Uuid::new_v4()));
+        let written = match tokio::fs::write(&tmp, &bytes).await {
+            Ok(()) => tokio::fs::rename(&tmp, &path).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = written {
+            let _ = tokio::fs::remove_file(&tmp).await;
+            return Err(e.into());
+        }
+
+        Ok((bytes, content_type))
+    }
+
+    async fn fetch(&self, url: &str) -> Result<Vec<u8>, BoxError> {
+        let mut url = Url::parse(url)?;
+        let mut redirects = 0;
+        let response = loop {
+            let response = client_for(&url).await?.get(url.clone()).send().await?;
+            if !response.status().is_redirection() {
+                break response;
+            }
+            redirects += 1;
+            if redirects > MAX_REDIRECTS {
+                return Err("Too many redirects".into());
+            }
+            let location = response
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|l| l.to_str().ok())
+                .ok_or("Redirect without a location")?;
+            url = url.join(location)?;
+        };
+
+        if !response.status().is_success() {
+            return Err(format!("HTTP {}", response.status()).into());
+        }
+        if response.content_length().unwrap_or(0) as usize > MAX_THUMBNAIL_BYTES {
+            return Err("Thumbnail too large".into());
+        }
+
+        let bytes = response.bytes().await?;
+        if bytes.len() > MAX_THUMBNAIL_BYTES {
+            return Err("Thumbnail too large".into());
+        }
+        Ok(bytes.to_vec())
+    }
+}
+
+/// A client for one request to `url`, pinned to the addresses its host
+/// resolved to after checking they're all public. Pinning means a second DNS
+/// lookup can't swap in a private address after the check.
+async fn client_for(url: &Url) -> Result<reqwest::Client, BoxError> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("Unsupported thumbnail URL scheme: {}", url.scheme()).into());
+    }
+    let port = url.port_or_known_default().ok_or("Thumbnail URL has no port")?;
+
+    let mut builder = reqwest::Client::builder()
+        .timeout(Duration::from_secs(15))
+        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
+        .redirect(reqwest::redirect::Policy::none());
+
+    let addrs: Vec<SocketAddr> = match url.host() {
+        Some(Host::Ipv4(ip)) => vec![SocketAddr::new(IpAddr::V4(ip), port)],
+        Some(Host::Ipv6(ip)) => vec![SocketAddr::new(IpAddr::V6(ip), port)],
+        Some(Host::Domain(domain)) => {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((domain, port)).await?.collect();
+            builder = builder.resolve_to_addrs(domain, &addrs);
+            addrs
+        }
+        None => return Err("Thumbnail URL has no host".into()),
+    };
+
+    if addrs.is_empty() {
+        return Err(format!("Thumbnail host {} has no addresses", url.host_str().unwrap_or_default()).into());
+    }
+    if let Some(addr) = addrs.iter().find(|addr| !is_public(addr.ip())) {
+        return Err(format!(
+            "Thumbnail host {} resolves to non-public address {}",
+            url.host_str().unwrap_or_default(),
+            addr.ip()
+        )
+        .into());
+    }
+
+    Ok(builder.build()?)
+}
+
+/// Whether `ip` is a globally routable address, i.e. not loopback, private,
+/// link-local, carrier-grade NAT, multicast or otherwise reserved
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b)) // 100.64.0.0/10
+                || (a == 192 && b == 0 && c == 0) // 192.0.0.0/24
+                || (a == 198 && (18..20).contains(&b)) // 198.18.0.0/15
+                || a >= 240)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(v4));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_multicast()
+                || (first & 0xfe00) == 0xfc00 // unique local
+                || (first & 0xffc0) == 0xfe80 // link-local
+                || (first == 0x2001 && ip.segments()[1] == 0x0db8)) // documentation
+        }
+    }
+}
+
+/// File name for a cached URL
+fn cache_key(url: &str) -> String {
+    Sha256::digest(url.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Content type from the image's magic bytes; None for anything that isn't an image
+fn sniff_content_type(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_public() {
+        for ip in ["93.184.216.34", "151.101.1.140", "2606:4700::6810:84e5"] {
+            assert!(is_public(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in [
+            "127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.10", "169.254.169.254",
+            "100.64.0.1", "0.0.0.0", "255.255.255.255", "224.0.0.1",
+            "::1", "::", "fd00::1", "fe80::1", "::ffff:192.168.1.1", "2001:db8::1",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_client_for_rejects_internal_urls() {
+        for url in [
+            "file:///etc/passwd",
+            "ftp://example.com/a.jpg",
+            "http://127.0.0.1/a.jpg",
+            "http://[::1]:8080/a.jpg",
+            "http://192.168.1.1/a.jpg",
+            "http://169.254.169.254/latest/meta-data",
+            "http://localhost:3000/api/settings",
+        ] {
+            assert!(client_for(&Url::parse(url).unwrap()).await.is_err(), "{}", url);
+        }
+        assert!(client_for(&Url::parse("https://93.184.216.34/a.jpg").unwrap()).await.is_ok());
+    }
+
+    #[test]
+    fn test_sniff_content_type() {
+        assert_eq!(sniff_content_type(&[0xFF, 0xD8, 0xFF, 0xE0]), Some("image/jpeg"));
+        assert_eq!(sniff_content_type(b"\x89PNG\r\n\x1a\n...."), Some("image/png"));
+        assert_eq!(sniff_content_type(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(sniff_content_type(b"<html>"), None);
+    }
+}
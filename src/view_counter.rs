@@ -0,0 +1,37 @@
+//! Debounces game detail views so refreshing a page or flicking back and
+//! forth between games doesn't inflate `view_count`. Each viewer (session or
+//! IP) counts once per game per window.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const WINDOW: Duration = Duration::from_secs(30 * 60);
+
+/// Expired entries are swept once the table grows past this many keys
+const PRUNE_THRESHOLD: usize = 10_000;
+
+#[derive(Default)]
+pub struct ViewCounter {
+    seen: Mutex<HashMap<(String, i64), Instant>>,
+}
+
+impl ViewCounter {
+    /// Whether this view of `game_id` by `viewer` should be counted, recording it if so
+    pub fn should_count(&self, viewer: &str, game_id: i64) -> bool {
+        let now = Instant::now();
+        let mut seen = self.seen.lock().unwrap();
+
+        if seen.len() > PRUNE_THRESHOLD {
+            seen.retain(|_, at| now.duration_since(*at) < WINDOW);
+        }
+
+        match seen.get(&(viewer.to_string(), game_id)) {
+            Some(at) if now.duration_since(*at) < WINDOW => false,
+            _ => {
+                seen.insert((viewer.to_string(), game_id), now);
+                true
+            }
+        }
+    }
+}